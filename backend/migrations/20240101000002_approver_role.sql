@@ -0,0 +1,9 @@
+-- Approver participants: approve or reject an envelope without placing a signature
+
+CREATE TYPE signer_role AS ENUM ('signer', 'approver');
+
+ALTER TYPE signer_status ADD VALUE 'approved';
+ALTER TYPE audit_action ADD VALUE 'signer_approved';
+
+ALTER TABLE signers ADD COLUMN role signer_role NOT NULL DEFAULT 'signer';
+ALTER TABLE signers ADD COLUMN approved_at TIMESTAMPTZ;
@@ -0,0 +1,15 @@
+-- Short-lived, host-initiated signing sessions for in-person signing
+
+ALTER TYPE audit_action ADD VALUE 'in_person_session_started';
+
+CREATE TABLE in_person_sessions (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    signer_id UUID NOT NULL REFERENCES signers(id) ON DELETE CASCADE,
+    host_user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    token VARCHAR(128) NOT NULL UNIQUE,
+    expires_at TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_in_person_sessions_token ON in_person_sessions(token);
+CREATE INDEX idx_in_person_sessions_signer_id ON in_person_sessions(signer_id);
@@ -0,0 +1,12 @@
+-- Named signature images saved by users for reuse across documents
+
+CREATE TABLE user_signatures (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    name VARCHAR(100) NOT NULL,
+    signature_data TEXT NOT NULL,
+    signature_hash VARCHAR(128) NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_user_signatures_user_id ON user_signatures(user_id);
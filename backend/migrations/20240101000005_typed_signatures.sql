@@ -0,0 +1,8 @@
+-- Typed signatures rendered server-side in a whitelisted script font
+
+CREATE TYPE signature_type AS ENUM ('drawn', 'typed');
+
+ALTER TABLE signatures
+    ADD COLUMN signature_type signature_type NOT NULL DEFAULT 'drawn',
+    ADD COLUMN typed_text VARCHAR(100),
+    ADD COLUMN typed_font VARCHAR(50);
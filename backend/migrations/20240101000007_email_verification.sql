@@ -0,0 +1,21 @@
+-- Optional email one-time-code verification before a signer can sign
+
+ALTER TYPE audit_action ADD VALUE 'verification_code_sent';
+ALTER TYPE audit_action ADD VALUE 'verification_failed';
+ALTER TYPE audit_action ADD VALUE 'signer_verified';
+
+ALTER TABLE documents ADD COLUMN require_email_verification BOOLEAN NOT NULL DEFAULT FALSE;
+
+ALTER TABLE signers ADD COLUMN verified_at TIMESTAMPTZ;
+
+CREATE TABLE signer_verification_codes (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    signer_id UUID NOT NULL REFERENCES signers(id) ON DELETE CASCADE,
+    code_hash VARCHAR(128) NOT NULL,
+    expires_at TIMESTAMPTZ NOT NULL,
+    failed_attempts INTEGER NOT NULL DEFAULT 0,
+    consumed_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_signer_verification_codes_signer_id ON signer_verification_codes(signer_id);
@@ -0,0 +1,15 @@
+-- Owner-set access codes a signer must enter before opening the signing session
+
+ALTER TYPE audit_action ADD VALUE 'access_code_failed';
+ALTER TYPE audit_action ADD VALUE 'signer_unlocked';
+ALTER TYPE audit_action ADD VALUE 'signer_updated';
+
+CREATE TABLE signer_access_codes (
+    signer_id UUID PRIMARY KEY REFERENCES signers(id) ON DELETE CASCADE,
+    code_hash VARCHAR(255) NOT NULL,
+    failed_attempts INTEGER NOT NULL DEFAULT 0,
+    locked_until TIMESTAMPTZ,
+    unlocked_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
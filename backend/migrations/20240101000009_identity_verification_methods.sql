@@ -0,0 +1,12 @@
+-- Generalize signer verification to pluggable identity verification providers
+
+ALTER TYPE audit_action ADD VALUE 'verification_started';
+
+ALTER TABLE documents ADD COLUMN verification_method VARCHAR(50);
+UPDATE documents SET verification_method = 'email_otp' WHERE require_email_verification;
+ALTER TABLE documents DROP COLUMN require_email_verification;
+
+ALTER TABLE signers ADD COLUMN verification_method VARCHAR(50);
+ALTER TABLE signers ADD COLUMN verification_outcome VARCHAR(20);
+UPDATE signers SET verification_method = 'email_otp', verification_outcome = 'verified'
+    WHERE verified_at IS NOT NULL;
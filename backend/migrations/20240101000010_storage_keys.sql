@@ -0,0 +1,8 @@
+-- Store backend-relative storage keys instead of local filesystem paths.
+-- Files were written to {STORAGE_PATH}/{owner_id}/{document_id}/{file}, so
+-- the key is the last three path segments. Keys already in that form are
+-- left untouched.
+
+UPDATE documents
+SET file_path = substring(file_path FROM '([0-9a-fA-F-]{36}/[0-9a-fA-F-]{36}/[^/]+)$')
+WHERE file_path ~ '.+/[0-9a-fA-F-]{36}/[0-9a-fA-F-]{36}/[^/]+$';
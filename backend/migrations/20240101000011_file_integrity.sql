@@ -0,0 +1,3 @@
+-- Record stored files that no longer match their upload hash
+
+ALTER TYPE audit_action ADD VALUE 'file_integrity_violation';
@@ -0,0 +1,16 @@
+-- Results of the periodic storage integrity and orphan scan
+
+CREATE TABLE storage_scan_reports (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    started_at TIMESTAMPTZ NOT NULL,
+    completed_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    objects_scanned INTEGER NOT NULL,
+    documents_checked INTEGER NOT NULL,
+    orphans JSONB NOT NULL DEFAULT '[]',
+    orphans_deleted INTEGER NOT NULL DEFAULT 0,
+    missing_files JSONB NOT NULL DEFAULT '[]',
+    hash_mismatches JSONB NOT NULL DEFAULT '[]',
+    errors JSONB NOT NULL DEFAULT '[]'
+);
+
+CREATE INDEX idx_storage_scan_reports_started_at ON storage_scan_reports(started_at DESC);
@@ -0,0 +1,8 @@
+-- Deleted documents go to the trash and are purged after a retention period
+
+ALTER TABLE documents ADD COLUMN deleted_at TIMESTAMPTZ;
+
+CREATE INDEX idx_documents_deleted_at ON documents(deleted_at) WHERE deleted_at IS NOT NULL;
+
+ALTER TYPE audit_action ADD VALUE 'document_deleted';
+ALTER TYPE audit_action ADD VALUE 'document_restored';
@@ -0,0 +1,20 @@
+-- Retention policy: legal holds and a log of purged documents
+
+ALTER TABLE documents ADD COLUMN legal_hold BOOLEAN NOT NULL DEFAULT false;
+
+ALTER TYPE audit_action ADD VALUE 'legal_hold_changed';
+
+-- Outlives the purged rows, so no foreign keys
+CREATE TABLE purge_log (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    document_id UUID NOT NULL,
+    owner_id UUID NOT NULL,
+    title VARCHAR(255) NOT NULL,
+    status document_status NOT NULL,
+    file_hash VARCHAR(128) NOT NULL,
+    reason VARCHAR(50) NOT NULL,
+    document_created_at TIMESTAMPTZ NOT NULL,
+    purged_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_purge_log_purged_at ON purge_log(purged_at DESC);
@@ -0,0 +1,10 @@
+-- Erasure of signer personal data. Anonymized audit entries keep their
+-- original hashes; anonymized_at marks that their contents were rewritten
+-- after they were hashed.
+
+ALTER TYPE audit_action ADD VALUE 'signer_anonymized';
+
+ALTER TABLE signers ADD COLUMN anonymized_at TIMESTAMPTZ;
+ALTER TABLE audit_logs ADD COLUMN anonymized_at TIMESTAMPTZ;
+
+CREATE INDEX idx_signers_email_lower ON signers(LOWER(email));
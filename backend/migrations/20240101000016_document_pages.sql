@@ -0,0 +1,10 @@
+-- Page dimensions recorded at upload, in PDF points as displayed (rotation
+-- applied), so sessions don't have to parse the PDF
+
+CREATE TABLE document_pages (
+    document_id UUID NOT NULL REFERENCES documents(id) ON DELETE CASCADE,
+    page_number INTEGER NOT NULL,
+    width DOUBLE PRECISION NOT NULL,
+    height DOUBLE PRECISION NOT NULL,
+    PRIMARY KEY (document_id, page_number)
+);
@@ -0,0 +1,21 @@
+-- Cache page metadata on the document itself so it is read along with the
+-- document. Rows left NULL are filled in from the stored file on first use.
+
+ALTER TABLE documents ADD COLUMN page_count INTEGER;
+ALTER TABLE documents ADD COLUMN page_sizes JSONB;
+
+UPDATE documents d
+SET page_count = p.page_count, page_sizes = p.page_sizes
+FROM (
+    SELECT document_id,
+           COUNT(*)::INTEGER AS page_count,
+           jsonb_agg(
+               jsonb_build_object('number', page_number, 'width', width, 'height', height)
+               ORDER BY page_number
+           ) AS page_sizes
+    FROM document_pages
+    GROUP BY document_id
+) p
+WHERE d.id = p.document_id;
+
+DROP TABLE document_pages;
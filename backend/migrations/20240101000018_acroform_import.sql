@@ -0,0 +1,11 @@
+-- Fields imported from a PDF's AcroForm remember the form field they came
+-- from so that importing again does not duplicate them.
+
+ALTER TYPE field_type ADD VALUE 'checkbox';
+ALTER TYPE audit_action ADD VALUE 'form_fields_imported';
+
+ALTER TABLE document_fields ADD COLUMN acroform_name TEXT;
+
+CREATE UNIQUE INDEX idx_document_fields_acroform_name
+    ON document_fields(document_id, acroform_name)
+    WHERE acroform_name IS NOT NULL;
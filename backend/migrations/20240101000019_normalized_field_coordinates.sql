@@ -0,0 +1,25 @@
+-- Field positions as fractions (0-1) of the page width and height, measured
+-- from the top-left corner of the page as displayed, so they no longer
+-- depend on the scale a client renders pages at. x, y, width and height keep
+-- the deprecated point values while clients move over.
+--
+-- Fields of documents whose page sizes are not cached yet are converted when
+-- the page sizes are first read from the stored file.
+
+ALTER TABLE document_fields
+    ADD COLUMN norm_x DOUBLE PRECISION,
+    ADD COLUMN norm_y DOUBLE PRECISION,
+    ADD COLUMN norm_width DOUBLE PRECISION,
+    ADD COLUMN norm_height DOUBLE PRECISION;
+
+UPDATE document_fields f
+SET norm_x = f.x / (p.size->>'width')::DOUBLE PRECISION,
+    norm_y = f.y / (p.size->>'height')::DOUBLE PRECISION,
+    norm_width = f.width / (p.size->>'width')::DOUBLE PRECISION,
+    norm_height = f.height / (p.size->>'height')::DOUBLE PRECISION
+FROM documents d
+CROSS JOIN LATERAL jsonb_array_elements(d.page_sizes) AS p(size)
+WHERE d.id = f.document_id
+  AND (p.size->>'number')::INTEGER = f.page
+  AND (p.size->>'width')::DOUBLE PRECISION > 0
+  AND (p.size->>'height')::DOUBLE PRECISION > 0;
@@ -0,0 +1,9 @@
+-- Completed documents can be sealed with the server's signing certificate.
+-- The sealed copy is stored next to the original, which is kept unchanged.
+
+ALTER TYPE audit_action ADD VALUE 'document_sealed';
+
+ALTER TABLE documents ADD COLUMN sealed_file_path TEXT;
+ALTER TABLE documents ADD COLUMN sealed_file_hash VARCHAR(128);
+ALTER TABLE documents ADD COLUMN sealed_at TIMESTAMPTZ;
+ALTER TABLE documents ADD COLUMN seal_signer TEXT;
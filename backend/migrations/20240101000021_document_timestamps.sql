@@ -0,0 +1,26 @@
+-- RFC 3161 timestamps from an external authority attesting when a document's
+-- file hash, or a completion certificate's hash, existed. Rows are created as
+-- soon as a timestamp is wanted and retried until a token is obtained.
+
+CREATE TYPE timestamp_subject AS ENUM ('document', 'certificate');
+
+ALTER TYPE audit_action ADD VALUE 'document_timestamped';
+
+CREATE TABLE document_timestamps (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    document_id UUID NOT NULL REFERENCES documents(id) ON DELETE CASCADE,
+    subject timestamp_subject NOT NULL,
+    hashed_value VARCHAR(128) NOT NULL,
+    tsa_url TEXT NOT NULL,
+    token BYTEA,
+    gen_time TIMESTAMPTZ,
+    serial_number TEXT,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    last_error TEXT,
+    next_attempt_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_document_timestamps_document ON document_timestamps(document_id);
+CREATE INDEX idx_document_timestamps_pending ON document_timestamps(next_attempt_at)
+    WHERE token IS NULL AND next_attempt_at IS NOT NULL;
@@ -0,0 +1,13 @@
+-- Stored hashes are now prefixed with the algorithm that produced them
+-- ("sha512:..."), which no longer fits in 128 characters. Existing hashes are
+-- left unprefixed and read as SHA-256.
+
+ALTER TABLE documents ALTER COLUMN file_hash TYPE VARCHAR(255);
+ALTER TABLE documents ALTER COLUMN sealed_file_hash TYPE VARCHAR(255);
+ALTER TABLE signatures ALTER COLUMN signature_hash TYPE VARCHAR(255);
+ALTER TABLE user_signatures ALTER COLUMN signature_hash TYPE VARCHAR(255);
+ALTER TABLE audit_logs ALTER COLUMN entry_hash TYPE VARCHAR(255);
+ALTER TABLE audit_logs ALTER COLUMN previous_hash TYPE VARCHAR(255);
+ALTER TABLE signer_verification_codes ALTER COLUMN code_hash TYPE VARCHAR(255);
+ALTER TABLE purge_log ALTER COLUMN file_hash TYPE VARCHAR(255);
+ALTER TABLE document_timestamps ALTER COLUMN hashed_value TYPE VARCHAR(255);
@@ -0,0 +1,3 @@
+-- Owners checking whether a file they were sent is the one signed.
+
+ALTER TYPE audit_action ADD VALUE 'file_verified';
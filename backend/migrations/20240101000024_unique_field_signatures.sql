@@ -0,0 +1,11 @@
+-- A signer has at most one signature per field, so a retried submission can
+-- never record a field twice. Keep the earliest row of any existing duplicates.
+
+DELETE FROM signatures a
+USING signatures b
+WHERE a.field_id = b.field_id
+  AND a.signer_id = b.signer_id
+  AND (a.created_at, a.id) > (b.created_at, b.id);
+
+ALTER TABLE signatures
+    ADD CONSTRAINT signatures_field_signer_unique UNIQUE (field_id, signer_id);
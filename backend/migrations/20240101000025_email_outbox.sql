@@ -0,0 +1,21 @@
+-- Emails queued in the same transaction as the change that triggers them, then
+-- delivered and retried until the mail server accepts them. A document sent
+-- twice at once can only queue its signing requests once.
+
+CREATE TYPE outbox_email_kind AS ENUM ('signing_request');
+
+CREATE TABLE email_outbox (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    kind outbox_email_kind NOT NULL,
+    document_id UUID NOT NULL REFERENCES documents(id) ON DELETE CASCADE,
+    signer_id UUID NOT NULL REFERENCES signers(id) ON DELETE CASCADE,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    last_error TEXT,
+    next_attempt_at TIMESTAMPTZ,
+    sent_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_email_outbox_document ON email_outbox(document_id);
+CREATE INDEX idx_email_outbox_pending ON email_outbox(next_attempt_at)
+    WHERE sent_at IS NULL AND next_attempt_at IS NOT NULL;
@@ -0,0 +1,3 @@
+-- Owners renaming a document.
+
+ALTER TYPE audit_action ADD VALUE 'document_updated';
@@ -0,0 +1,5 @@
+-- Owners recalling a sent document back to draft, optionally telling signers
+-- the request was withdrawn.
+
+ALTER TYPE audit_action ADD VALUE 'document_recalled';
+ALTER TYPE outbox_email_kind ADD VALUE 'request_withdrawn';
@@ -0,0 +1,5 @@
+-- Why an owner voided a document, shown to signers and emailed to them.
+
+ALTER TABLE documents ADD COLUMN void_reason TEXT;
+
+ALTER TYPE outbox_email_kind ADD VALUE 'request_cancelled';
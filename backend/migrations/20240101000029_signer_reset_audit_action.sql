@@ -0,0 +1,3 @@
+-- Owners giving a signer who declined by mistake another chance.
+
+ALTER TYPE audit_action ADD VALUE 'signer_reset';
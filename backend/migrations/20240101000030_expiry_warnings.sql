@@ -0,0 +1,7 @@
+-- When a signer was warned that their document expires soon, so the warning
+-- is only sent once.
+
+ALTER TABLE signers ADD COLUMN expiry_warning_sent_at TIMESTAMPTZ;
+
+CREATE INDEX idx_documents_pending_expires_at ON documents(expires_at)
+    WHERE status = 'pending' AND expires_at IS NOT NULL;
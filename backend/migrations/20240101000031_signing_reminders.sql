@@ -0,0 +1,14 @@
+-- Automatic reminders: every `reminder_interval_days` after the last email,
+-- signers who have not finished are reminded, up to `max_reminders` times
+-- (manual reminders count too). Both unset turns them off.
+
+ALTER TABLE documents
+    ADD COLUMN reminder_interval_days INT CHECK (reminder_interval_days > 0),
+    ADD COLUMN max_reminders INT CHECK (max_reminders > 0);
+
+ALTER TABLE signers
+    ADD COLUMN reminders_sent INT NOT NULL DEFAULT 0,
+    ADD COLUMN last_reminded_at TIMESTAMPTZ;
+
+ALTER TYPE audit_action ADD VALUE 'signer_reminded';
+ALTER TYPE outbox_email_kind ADD VALUE 'signing_reminder';
@@ -0,0 +1,17 @@
+-- Owners sharing a document with other users, who may view it or, as
+-- editors, also prepare its fields and signers while it is a draft.
+
+CREATE TYPE document_share_role AS ENUM ('viewer', 'editor');
+
+CREATE TABLE document_shares (
+    document_id UUID NOT NULL REFERENCES documents(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    role document_share_role NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (document_id, user_id)
+);
+
+CREATE INDEX idx_document_shares_user_id ON document_shares(user_id);
+
+ALTER TYPE audit_action ADD VALUE 'document_shared';
+ALTER TYPE audit_action ADD VALUE 'document_unshared';
@@ -0,0 +1,9 @@
+-- Per-user storage quotas. Usage is the stored size of each live document's
+-- original and sealed files; sizes of documents uploaded before this
+-- migration are filled in from storage at startup.
+
+ALTER TABLE documents ADD COLUMN file_size BIGINT;
+ALTER TABLE documents ADD COLUMN sealed_file_size BIGINT;
+
+-- NULL uses DEFAULT_STORAGE_QUOTA_MB; 0 means unlimited.
+ALTER TABLE users ADD COLUMN storage_quota_mb BIGINT CHECK (storage_quota_mb >= 0);
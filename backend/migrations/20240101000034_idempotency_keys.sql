@@ -0,0 +1,18 @@
+-- Responses of requests made with an Idempotency-Key, so a retried request is
+-- answered with the original response instead of being executed again. Rows
+-- only become visible once the response is stored; a request with the same key
+-- in flight blocks on the uncommitted row until then.
+
+CREATE TABLE idempotency_keys (
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    key VARCHAR(255) NOT NULL,
+    request_hash VARCHAR(64) NOT NULL,
+    response_status SMALLINT,
+    response_content_type VARCHAR(255),
+    response_body BYTEA,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    expires_at TIMESTAMPTZ NOT NULL,
+    PRIMARY KEY (user_id, key)
+);
+
+CREATE INDEX idx_idempotency_keys_expires_at ON idempotency_keys(expires_at);
@@ -0,0 +1,3 @@
+-- Completed documents downloaded as a ZIP package.
+
+ALTER TYPE audit_action ADD VALUE 'document_package_downloaded';
@@ -0,0 +1,12 @@
+-- Labels owners put on their documents to organize and filter their list.
+-- Names keep the case they were entered with but are compared
+-- case-insensitively, so a document cannot carry both "Q3" and "q3".
+
+CREATE TABLE document_tags (
+    document_id UUID NOT NULL REFERENCES documents(id) ON DELETE CASCADE,
+    name VARCHAR(50) NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE UNIQUE INDEX idx_document_tags_document_name ON document_tags(document_id, LOWER(name));
+CREATE INDEX idx_document_tags_name ON document_tags(LOWER(name));
@@ -0,0 +1,11 @@
+-- Trigram indexes for searching documents by title, file name and signer
+-- name or email. Matching is by substring, so "acme" finds a document
+-- whose signer is jane@acme.com. The indexes are on the columns themselves,
+-- so adding or editing a signer keeps them up to date.
+
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+CREATE INDEX idx_documents_title_trgm ON documents USING GIN (title gin_trgm_ops);
+CREATE INDEX idx_documents_original_filename_trgm ON documents USING GIN (original_filename gin_trgm_ops);
+CREATE INDEX idx_signers_name_trgm ON signers USING GIN (name gin_trgm_ops);
+CREATE INDEX idx_signers_email_trgm ON signers USING GIN (email gin_trgm_ops);
@@ -0,0 +1,21 @@
+-- Sign-in history, so an owner's account activity can be accounted for.
+-- Failed logins for an unknown email have no user; `email` is the address
+-- that was given.
+
+CREATE TYPE auth_event_type AS ENUM ('login_succeeded', 'login_failed');
+
+CREATE TABLE auth_events (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id UUID REFERENCES users(id) ON DELETE CASCADE,
+    event_type auth_event_type NOT NULL,
+    email VARCHAR(255) NOT NULL,
+    failure_reason VARCHAR(50),
+    -- When the bearer token issued by a successful login expires.
+    token_expires_at TIMESTAMPTZ,
+    ip_address VARCHAR(45),
+    user_agent TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_auth_events_user_id ON auth_events(user_id, created_at DESC);
+CREATE INDEX idx_auth_events_created_at ON auth_events(created_at);
@@ -0,0 +1,19 @@
+-- Orders each document's audit entries by a per-document sequence number
+-- instead of created_at, which two entries can share. Entries are numbered
+-- from 1 in the order they were written; existing ones are numbered by
+-- created_at, then id.
+
+ALTER TABLE audit_logs ADD COLUMN sequence BIGINT;
+
+UPDATE audit_logs a
+SET sequence = numbered.sequence
+FROM (
+    SELECT id, ROW_NUMBER() OVER (PARTITION BY document_id ORDER BY created_at, id) AS sequence
+    FROM audit_logs
+) numbered
+WHERE a.id = numbered.id;
+
+ALTER TABLE audit_logs ALTER COLUMN sequence SET NOT NULL;
+
+CREATE UNIQUE INDEX idx_audit_logs_document_sequence ON audit_logs(document_id, sequence);
+DROP INDEX idx_audit_logs_document_id;
@@ -0,0 +1,27 @@
+-- Tamper alerts raised when a document's stored file or audit chain fails
+-- verification. At most one unacknowledged event is kept per document and
+-- kind; repeated failures only bump `occurrences`, so a recurring check does
+-- not alert again until an admin acknowledges the event.
+
+CREATE TYPE security_event_type AS ENUM ('file_integrity_violation', 'audit_chain_broken');
+CREATE TYPE security_event_severity AS ENUM ('low', 'medium', 'high');
+CREATE TYPE security_event_source AS ENUM ('request', 'storage_scan', 'certificate');
+
+CREATE TABLE security_events (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    document_id UUID NOT NULL REFERENCES documents(id) ON DELETE CASCADE,
+    event_type security_event_type NOT NULL,
+    severity security_event_severity NOT NULL,
+    -- What ran the check that first failed.
+    source security_event_source NOT NULL,
+    details JSONB,
+    occurrences INTEGER NOT NULL DEFAULT 1,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_seen_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    acknowledged_at TIMESTAMPTZ,
+    acknowledged_by UUID REFERENCES users(id) ON DELETE SET NULL
+);
+
+CREATE UNIQUE INDEX idx_security_events_open ON security_events(document_id, event_type)
+    WHERE acknowledged_at IS NULL;
+CREATE INDEX idx_security_events_created_at ON security_events(created_at DESC);
@@ -0,0 +1,25 @@
+-- Read-only links to a completed document's PDF and certificate, for people
+-- without an account. Only a SHA-256 of the token is stored; the passcode,
+-- when set, is a bcrypt hash.
+
+ALTER TYPE audit_action ADD VALUE 'share_link_created';
+ALTER TYPE audit_action ADD VALUE 'share_link_revoked';
+ALTER TYPE audit_action ADD VALUE 'share_link_accessed';
+ALTER TYPE audit_action ADD VALUE 'share_link_passcode_failed';
+
+CREATE TABLE share_links (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    document_id UUID NOT NULL REFERENCES documents(id) ON DELETE CASCADE,
+    token_hash VARCHAR(64) NOT NULL UNIQUE,
+    passcode_hash VARCHAR(255),
+    failed_passcode_attempts INTEGER NOT NULL DEFAULT 0,
+    locked_until TIMESTAMPTZ,
+    expires_at TIMESTAMPTZ NOT NULL,
+    created_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    revoked_at TIMESTAMPTZ,
+    access_count INTEGER NOT NULL DEFAULT 0,
+    last_accessed_at TIMESTAMPTZ
+);
+
+CREATE INDEX idx_share_links_document_id ON share_links(document_id);
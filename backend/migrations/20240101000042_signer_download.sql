@@ -0,0 +1,3 @@
+-- Lets the owner offer signers the completed document on their status page.
+
+ALTER TABLE documents ADD COLUMN signer_download_enabled BOOLEAN NOT NULL DEFAULT false;
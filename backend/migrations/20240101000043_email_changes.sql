@@ -0,0 +1,18 @@
+-- Pending changes of a user's login email. The change is applied once the
+-- new address is confirmed through the emailed link; only a SHA-256 of the
+-- link's token is stored.
+
+ALTER TYPE auth_event_type ADD VALUE 'email_change_requested';
+ALTER TYPE auth_event_type ADD VALUE 'email_changed';
+
+CREATE TABLE email_changes (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    new_email VARCHAR(255) NOT NULL,
+    token_hash VARCHAR(64) NOT NULL UNIQUE,
+    expires_at TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    confirmed_at TIMESTAMPTZ
+);
+
+CREATE INDEX idx_email_changes_user_id ON email_changes(user_id);
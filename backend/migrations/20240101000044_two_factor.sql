@@ -0,0 +1,29 @@
+-- TOTP two-factor authentication. Secrets are encrypted with the server's
+-- TOTP key; a secret being set up stays pending until a code from it is
+-- verified. Recovery codes are stored as SHA-256 hashes and used once.
+
+ALTER TYPE auth_event_type ADD VALUE 'two_factor_required';
+ALTER TYPE auth_event_type ADD VALUE 'two_factor_enabled';
+ALTER TYPE auth_event_type ADD VALUE 'two_factor_disabled';
+ALTER TYPE auth_event_type ADD VALUE 'recovery_code_used';
+
+CREATE TABLE user_two_factor (
+    user_id UUID PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    secret_encrypted TEXT,
+    pending_secret_encrypted TEXT,
+    enabled_at TIMESTAMPTZ,
+    -- Time step of the last accepted code, so no code is accepted twice.
+    last_used_step BIGINT,
+    failed_attempts INT NOT NULL DEFAULT 0,
+    locked_until TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE recovery_codes (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    code_hash VARCHAR(64) NOT NULL,
+    used_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (user_id, code_hash)
+);
@@ -0,0 +1,10 @@
+-- What a user may do, replacing the admin flag. Senders upload and send
+-- documents; viewers can only see documents shared with them.
+
+CREATE TYPE user_role AS ENUM ('admin', 'sender', 'viewer');
+
+ALTER TABLE users ADD COLUMN role user_role NOT NULL DEFAULT 'sender';
+
+UPDATE users SET role = 'admin' WHERE is_admin;
+
+ALTER TABLE users DROP COLUMN is_admin;
@@ -0,0 +1,17 @@
+-- Runs of the background jobs, so admins can see when each last ran and
+-- whether it failed.
+
+CREATE TYPE job_run_status AS ENUM ('running', 'succeeded', 'failed');
+
+CREATE TABLE job_runs (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    job_name VARCHAR(100) NOT NULL,
+    status job_run_status NOT NULL DEFAULT 'running',
+    error TEXT,
+    -- The admin who ran the job by hand; NULL for scheduled runs.
+    triggered_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    started_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    finished_at TIMESTAMPTZ
+);
+
+CREATE INDEX idx_job_runs_job_started ON job_runs(job_name, started_at DESC);
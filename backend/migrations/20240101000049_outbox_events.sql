@@ -0,0 +1,33 @@
+-- Domain events written in the same transaction as the change they record,
+-- then fanned out to their consumers by a dispatcher. The consumers' work and
+-- the event's processed mark commit together, so each event is fanned out
+-- exactly once; the deliveries it queues are retried until they succeed.
+
+CREATE TYPE outbox_event_type AS ENUM (
+    'document_sent',
+    'signer_signed',
+    'signer_declined',
+    'document_completed'
+);
+
+CREATE TABLE outbox_events (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    event_type outbox_event_type NOT NULL,
+    document_id UUID NOT NULL REFERENCES documents(id) ON DELETE CASCADE,
+    signer_id UUID REFERENCES signers(id) ON DELETE CASCADE,
+    payload JSONB NOT NULL DEFAULT '{}',
+    attempts INTEGER NOT NULL DEFAULT 0,
+    last_error TEXT,
+    next_attempt_at TIMESTAMPTZ DEFAULT NOW(),
+    processed_at TIMESTAMPTZ,
+    -- Not NOW(), so events recorded in one transaction keep their order
+    created_at TIMESTAMPTZ NOT NULL DEFAULT clock_timestamp()
+);
+
+CREATE INDEX idx_outbox_events_document ON outbox_events(document_id);
+CREATE INDEX idx_outbox_events_pending ON outbox_events(next_attempt_at)
+    WHERE processed_at IS NULL AND next_attempt_at IS NOT NULL;
+
+-- Completion notices also go to the document owner, who is not a signer.
+ALTER TYPE outbox_email_kind ADD VALUE 'completion_notice';
+ALTER TABLE email_outbox ALTER COLUMN signer_id DROP NOT NULL;
@@ -0,0 +1,16 @@
+-- "Anyone with the link" signer slots: the first visitor claims the slot by
+-- entering their name and email, which replace the slot's placeholders
+
+ALTER TYPE audit_action ADD VALUE 'open_link_created';
+ALTER TYPE audit_action ADD VALUE 'signer_claimed';
+
+CREATE TABLE signer_open_links (
+    signer_id UUID PRIMARY KEY REFERENCES signers(id) ON DELETE CASCADE,
+    token VARCHAR(128) NOT NULL UNIQUE,
+    -- The claimant's signing link is returned once and never emailed
+    single_use BOOLEAN NOT NULL DEFAULT FALSE,
+    claimed_at TIMESTAMPTZ,
+    claimed_ip TEXT,
+    claimed_user_agent TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
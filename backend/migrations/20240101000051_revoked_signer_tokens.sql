@@ -0,0 +1,13 @@
+-- Signer links replaced by the owner, kept so the old link can say it was
+-- revoked instead of not existing
+
+ALTER TYPE audit_action ADD VALUE 'signer_token_rotated';
+
+CREATE TABLE revoked_signer_tokens (
+    token_hash VARCHAR(128) PRIMARY KEY,
+    signer_id UUID NOT NULL REFERENCES signers(id) ON DELETE CASCADE,
+    reason TEXT,
+    revoked_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_revoked_signer_tokens_signer_id ON revoked_signer_tokens(signer_id);
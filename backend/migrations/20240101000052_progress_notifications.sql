@@ -0,0 +1,6 @@
+-- Milestones at which the owner is emailed about signing progress. Stored as
+-- a JSON list of rule names so new milestones need no schema change.
+
+ALTER TABLE documents ADD COLUMN progress_notifications JSONB NOT NULL DEFAULT '[]';
+
+ALTER TYPE outbox_email_kind ADD VALUE 'progress_update';
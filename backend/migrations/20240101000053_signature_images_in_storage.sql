@@ -0,0 +1,11 @@
+-- Signature images move from base64 text in the table to the storage backend,
+-- next to the document under signatures/. Rows written before keep their data
+-- here until it is moved at startup or when the image is first requested.
+
+ALTER TABLE signatures
+    ADD COLUMN storage_key VARCHAR(512),
+    ADD COLUMN content_type VARCHAR(100),
+    ADD COLUMN byte_length INTEGER,
+    ALTER COLUMN signature_data DROP NOT NULL;
+
+CREATE INDEX idx_signatures_inline_data ON signatures(id) WHERE storage_key IS NULL;
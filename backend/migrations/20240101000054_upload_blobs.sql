@@ -0,0 +1,19 @@
+-- Stored original PDFs, counted by how many documents use them, so an owner
+-- uploading the same file again can reuse the stored copy. A file is only
+-- deleted once no document refers to it.
+
+CREATE TABLE blobs (
+    storage_key VARCHAR(512) PRIMARY KEY,
+    owner_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    file_hash VARCHAR(255) NOT NULL,
+    ref_count INTEGER NOT NULL DEFAULT 1,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_blobs_owner_hash ON blobs(owner_id, file_hash) WHERE ref_count > 0;
+
+INSERT INTO blobs (storage_key, owner_id, file_hash, ref_count)
+SELECT file_path, owner_id, file_hash, COUNT(*)
+FROM documents
+GROUP BY file_path, owner_id, file_hash
+ON CONFLICT (storage_key) DO NOTHING;
@@ -0,0 +1,14 @@
+-- Envelope encryption of stored files. A document's files are encrypted with
+-- its own data key, kept wrapped by the master key named in
+-- encryption_key_id. Each encrypted file records the nonce it was encrypted
+-- with; files without one are stored in plaintext.
+
+ALTER TABLE documents
+    ADD COLUMN encryption_key_id VARCHAR(64),
+    ADD COLUMN wrapped_data_key BYTEA,
+    ADD COLUMN file_nonce BYTEA,
+    ADD COLUMN sealed_file_nonce BYTEA;
+
+ALTER TABLE signatures ADD COLUMN image_nonce BYTEA;
+
+CREATE INDEX idx_documents_encryption_key_id ON documents(encryption_key_id);
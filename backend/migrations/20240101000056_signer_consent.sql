@@ -0,0 +1,10 @@
+-- Signers' consent to do business electronically (ESIGN Act, eIDAS), with the
+-- version of the consent text they accepted.
+
+ALTER TABLE signers
+    ADD COLUMN consented_at TIMESTAMPTZ,
+    ADD COLUMN consent_version VARCHAR(64),
+    ADD COLUMN consent_ip_address VARCHAR(45),
+    ADD COLUMN consent_user_agent TEXT;
+
+ALTER TYPE audit_action ADD VALUE 'consent_given';
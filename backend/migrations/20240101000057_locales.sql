@@ -0,0 +1,7 @@
+-- Language emails are written in, as a BCP 47 tag (`fr`, `en-GB`). Signers
+-- without one use their document's, and documents without one the server's
+-- DEFAULT_LOCALE. Unsupported tags are kept, and fall back the same way.
+
+ALTER TABLE signers ADD COLUMN locale VARCHAR(35);
+
+ALTER TABLE documents ADD COLUMN locale VARCHAR(35);
@@ -0,0 +1,5 @@
+-- Timezone a user reads certificate and audit export times in, as an IANA
+-- name. Times are still stored in UTC; NULL uses the server's
+-- DISPLAY_TIMEZONE.
+
+ALTER TABLE users ADD COLUMN display_timezone VARCHAR(64);
@@ -0,0 +1,13 @@
+-- Branding owners apply to the emails their signers get and to the signing
+-- page. The logo is stored in file storage, unencrypted since it is served
+-- publicly for email clients to load.
+
+CREATE TABLE user_branding (
+    user_id UUID PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    company_name VARCHAR(100),
+    -- #rrggbb
+    primary_color VARCHAR(7),
+    logo_storage_key TEXT,
+    logo_content_type VARCHAR(32),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
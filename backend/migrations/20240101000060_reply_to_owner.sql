@@ -0,0 +1,3 @@
+-- Whether signing requests and reminders to a user's signers set Reply-To to
+-- the user, so signers' replies reach them rather than the no-reply sender.
+ALTER TABLE users ADD COLUMN reply_to_enabled BOOLEAN NOT NULL DEFAULT TRUE;
@@ -0,0 +1,4 @@
+-- Signers who have not finished are emailed the updated calendar event when
+-- a pending document's deadline changes.
+
+ALTER TYPE outbox_email_kind ADD VALUE 'deadline_changed';
@@ -0,0 +1,7 @@
+-- What a signer has filled in before submitting, saved so they can resume
+-- later. It is not a signature: cleared once they sign or decline, and never
+-- read into the audit trail or the certificate.
+
+ALTER TABLE signers
+    ADD COLUMN signing_progress JSONB,
+    ADD COLUMN signing_progress_saved_at TIMESTAMPTZ;
@@ -0,0 +1,12 @@
+-- Fields can be required, and shown only while another field on the same
+-- document holds a given value. A field whose condition is unmet is hidden:
+-- it is not required and accepts no value.
+
+ALTER TABLE document_fields
+    ADD COLUMN required BOOLEAN NOT NULL DEFAULT FALSE,
+    ADD COLUMN condition_field_id UUID REFERENCES document_fields(id) ON DELETE SET NULL,
+    ADD COLUMN condition_value TEXT;
+
+CREATE INDEX idx_document_fields_condition_field_id
+    ON document_fields(condition_field_id)
+    WHERE condition_field_id IS NOT NULL;
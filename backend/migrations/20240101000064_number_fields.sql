@@ -0,0 +1,11 @@
+-- Number fields only accept numbers, optionally within a range and with at
+-- most a number of decimal places. The label names the field in the messages
+-- a signer sees.
+
+ALTER TYPE field_type ADD VALUE 'number';
+
+ALTER TABLE document_fields
+    ADD COLUMN label TEXT,
+    ADD COLUMN min_value NUMERIC,
+    ADD COLUMN max_value NUMERIC,
+    ADD COLUMN decimal_places INTEGER CHECK (decimal_places BETWEEN 0 AND 10);
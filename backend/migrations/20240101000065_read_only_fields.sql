@@ -0,0 +1,4 @@
+-- Read-only fields hold a value set by the owner, e.g. a contract number,
+-- which signers see but cannot change.
+
+ALTER TABLE document_fields ADD COLUMN read_only BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,10 @@
+-- Radio fields sharing a group key are options of one choice: a signer
+-- selects at most one of them, and exactly one when any of them is required.
+
+ALTER TYPE field_type ADD VALUE 'radio';
+
+ALTER TABLE document_fields ADD COLUMN group_key TEXT;
+
+CREATE INDEX idx_document_fields_group_key
+    ON document_fields(document_id, group_key)
+    WHERE group_key IS NOT NULL;
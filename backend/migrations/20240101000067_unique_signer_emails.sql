@@ -0,0 +1,38 @@
+-- A document lists each signer email once, ignoring case and surrounding
+-- spaces, so that concurrent requests cannot add the same person twice. Open
+-- signer slots have no email until they are claimed.
+--
+-- Existing duplicates are not resolved here: deleting a signer would leave
+-- their fields open to anyone, and changing the email of one already sent
+-- would break their reminders and completion mail. The migration stops and
+-- lists them instead, to be fixed by hand (remove or rename all but one of
+-- each, reassigning their fields) before starting the server again.
+
+DO $$
+DECLARE
+    conflicts TEXT;
+BEGIN
+    SELECT string_agg(
+               format('document %s (%s): %s, signers %s', document_id, status, email, signer_ids),
+               E'\n'
+           )
+    INTO conflicts
+    FROM (
+        SELECT s.document_id, d.status, LOWER(TRIM(s.email)) AS email,
+               string_agg(s.id::TEXT, ', ' ORDER BY s.created_at, s.id) AS signer_ids
+        FROM signers s
+        JOIN documents d ON d.id = s.document_id
+        WHERE s.email <> ''
+        GROUP BY s.document_id, d.status, LOWER(TRIM(s.email))
+        HAVING COUNT(*) > 1
+    ) AS duplicates;
+
+    IF conflicts IS NOT NULL THEN
+        RAISE EXCEPTION 'Documents list the same signer email more than once; keep one signer of each before migrating:%',
+            E'\n' || conflicts;
+    END IF;
+END $$;
+
+CREATE UNIQUE INDEX idx_signers_document_email
+    ON signers(document_id, LOWER(TRIM(email)))
+    WHERE email <> '';
@@ -0,0 +1,3 @@
+-- Owners changing who signs a document after it was sent.
+
+ALTER TYPE audit_action ADD VALUE 'document_amended';
@@ -0,0 +1,6 @@
+-- Signers taken off a document after it was sent. Their row stays for the
+-- audit trail and the email telling them, but they no longer count.
+
+ALTER TYPE signer_status ADD VALUE 'removed';
+
+ALTER TYPE outbox_email_kind ADD VALUE 'signer_removed';
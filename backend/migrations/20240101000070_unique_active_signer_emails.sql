@@ -0,0 +1,8 @@
+-- A removed signer's email can be given to a signer again, e.g. to invite
+-- the same person back.
+
+DROP INDEX idx_signers_document_email;
+
+CREATE UNIQUE INDEX idx_signers_document_email
+    ON signers(document_id, LOWER(TRIM(email)))
+    WHERE email <> '' AND status <> 'removed';
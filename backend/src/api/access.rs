@@ -0,0 +1,49 @@
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::api::error::{ApiError, ApiResult};
+use crate::db;
+use crate::models::document::Document;
+use crate::models::share::ShareRole;
+
+/// What a user may do with a document, from least to most.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum DocumentAccess {
+    Viewer,
+    Editor,
+    Owner,
+}
+
+impl From<ShareRole> for DocumentAccess {
+    fn from(role: ShareRole) -> Self {
+        match role {
+            ShareRole::Viewer => Self::Viewer,
+            ShareRole::Editor => Self::Editor,
+        }
+    }
+}
+
+/// The user's access to `document`, or `Forbidden` when it is less than
+/// `required`. Owners have every access; other users what the document was
+/// shared with them as.
+pub async fn require(
+    pool: &PgPool,
+    document: &Document,
+    user_id: Uuid,
+    required: DocumentAccess,
+) -> ApiResult<DocumentAccess> {
+    let access = if document.owner_id == user_id {
+        Some(DocumentAccess::Owner)
+    } else if required == DocumentAccess::Owner {
+        None
+    } else {
+        db::share::get_share_role(pool, document.id, user_id)
+            .await?
+            .map(DocumentAccess::from)
+    };
+
+    match access {
+        Some(access) if access >= required => Ok(access),
+        _ => Err(ApiError::Forbidden),
+    }
+}
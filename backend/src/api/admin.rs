@@ -0,0 +1,628 @@
+use axum::{
+    extract::{Path, Query, State},
+    Extension, Json,
+};
+use chrono::Utc;
+use serde::Deserialize;
+use tracing::warn;
+use utoipa::IntoParams;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::api::error::{ApiError, ApiResult, ErrorResponse};
+use crate::api::middleware::{extract_client_info_from_headers, AuthUser};
+use crate::api::state::AppState;
+use crate::db;
+use crate::models::auth_event::AuthEvent;
+use crate::models::invitation::{CreateInvitationRequest, Invitation};
+use crate::models::job::{JobRun, JobSummary};
+use crate::models::purge_log::PurgeLogEntry;
+use crate::models::security_event::{SecurityEvent, SecurityEventSource};
+use crate::models::signer::AnonymizeSignerRequest;
+use crate::models::storage_scan::StorageScanReport;
+use crate::models::timestamp::TimestampVerification;
+use crate::models::user::{
+    Role, SetStorageQuotaRequest, SetUserRoleRequest, StorageUsage, UserPublic,
+};
+use crate::services::anonymization::{self, AnonymizationActor, AnonymizationReport};
+use crate::services::integrity::{IntegrityActor, IntegrityReport};
+use crate::services::{crypto, quota};
+
+const DEFAULT_SCAN_LIMIT: i64 = 20;
+const MAX_SCAN_LIMIT: i64 = 100;
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ScanListQuery {
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct PurgeLogQuery {
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AdminAuthEventQuery {
+    /// Only the events of this user.
+    pub user_id: Option<Uuid>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SecurityEventQuery {
+    /// Also list acknowledged events.
+    pub include_acknowledged: Option<bool>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+/// Re-hashes a document's stored file on demand, bypassing any cached verdict.
+#[utoipa::path(
+    post,
+    path = "/admin/documents/{id}/verify-integrity",
+    tag = "admin",
+    params(("id" = Uuid, Path, description = "Document id")),
+    responses((status = 200, description = "Whether the stored file still matches its hash", body = IntegrityReport))
+)]
+pub async fn verify_document_integrity(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(id): Path<Uuid>,
+    headers: axum::http::HeaderMap,
+) -> ApiResult<Json<IntegrityReport>> {
+    auth_user.require_admin()?;
+
+    let (ip_address, user_agent) = extract_client_info_from_headers(&headers);
+
+    let document = db::document::get_document_by_id(&state.pool, id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Document not found".to_string()))?;
+
+    let report = state
+        .file_integrity
+        .verify(
+            &document,
+            &IntegrityActor {
+                signer_id: None,
+                user_id: Some(auth_user.user_id),
+                ip_address: Some(&ip_address),
+                user_agent: Some(&user_agent),
+                source: SecurityEventSource::Request,
+            },
+        )
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!("Failed to verify file: {}", e)))?;
+
+    Ok(Json(report))
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/storage/scans",
+    tag = "admin",
+    params(ScanListQuery),
+    responses((status = 200, description = "Recent storage scan reports", body = Vec<StorageScanReport>))
+)]
+pub async fn list_storage_scans(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Query(query): Query<ScanListQuery>,
+) -> ApiResult<Json<Vec<StorageScanReport>>> {
+    auth_user.require_admin()?;
+
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_SCAN_LIMIT)
+        .clamp(1, MAX_SCAN_LIMIT);
+    let reports = db::storage_scan::list_reports(&state.pool, limit).await?;
+
+    Ok(Json(reports))
+}
+
+/// Runs a storage scan now rather than waiting for the schedule.
+#[utoipa::path(
+    post,
+    path = "/admin/storage/scans",
+    tag = "admin",
+    responses((status = 200, description = "The scan report", body = StorageScanReport))
+)]
+pub async fn run_storage_scan(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+) -> ApiResult<Json<StorageScanReport>> {
+    auth_user.require_admin()?;
+
+    state
+        .storage_scanner
+        .scan()
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!("Storage scan failed: {}", e)))?
+        .map(Json)
+        .ok_or_else(|| ApiError::Conflict("A storage scan is already running".to_string()))
+}
+
+/// The background jobs and how each last ran.
+#[utoipa::path(
+    get,
+    path = "/admin/jobs",
+    tag = "admin",
+    responses((status = 200, description = "Registered jobs with their latest run", body = Vec<JobSummary>))
+)]
+pub async fn list_jobs(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+) -> ApiResult<Json<Vec<JobSummary>>> {
+    auth_user.require_admin()?;
+
+    let mut latest = db::job_run::get_latest_runs(&state.pool).await?;
+
+    let jobs = state
+        .scheduler
+        .jobs()
+        .iter()
+        .map(|job| JobSummary {
+            name: job.name().to_string(),
+            interval_secs: job.interval().as_secs(),
+            last_run: latest
+                .iter()
+                .position(|run| run.job_name == job.name())
+                .map(|index| latest.swap_remove(index)),
+        })
+        .collect();
+
+    Ok(Json(jobs))
+}
+
+/// Starts a run of a job now rather than waiting for its interval. The job
+/// runs in the background; its outcome shows in the job list.
+#[utoipa::path(
+    post,
+    path = "/admin/jobs/{name}/run",
+    tag = "admin",
+    params(("name" = String, Path, description = "Job name")),
+    responses(
+        (status = 200, description = "The started run", body = JobRun),
+        (status = 404, description = "No such job", body = ErrorResponse),
+        (status = 409, description = "The job is already running", body = ErrorResponse),
+    )
+)]
+pub async fn run_job(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(name): Path<String>,
+) -> ApiResult<Json<JobRun>> {
+    auth_user.require_admin()?;
+
+    if state.scheduler.job(&name).is_none() {
+        return Err(ApiError::NotFound("Job not found".to_string()));
+    }
+
+    let scheduler = state.scheduler.clone();
+    let run = scheduler
+        .trigger(&name, state, auth_user.user_id)
+        .await?
+        .ok_or_else(|| ApiError::Conflict("This job is already running".to_string()))?;
+
+    Ok(Json(run))
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/purge-log",
+    tag = "admin",
+    params(PurgeLogQuery),
+    responses((status = 200, description = "Purged documents", body = Vec<PurgeLogEntry>))
+)]
+pub async fn list_purge_log(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Query(query): Query<PurgeLogQuery>,
+) -> ApiResult<Json<Vec<PurgeLogEntry>>> {
+    auth_user.require_admin()?;
+
+    let limit = query.limit.unwrap_or(50).clamp(1, 200);
+    let offset = query.offset.unwrap_or(0).max(0);
+    let entries = db::purge_log::list_entries(&state.pool, limit, offset).await?;
+
+    Ok(Json(entries))
+}
+
+/// Login history of all users, newest first, including failed attempts for
+/// emails that match no account.
+#[utoipa::path(
+    get,
+    path = "/admin/auth-events",
+    tag = "admin",
+    params(AdminAuthEventQuery),
+    responses((status = 200, description = "Login history", body = Vec<AuthEvent>))
+)]
+pub async fn list_auth_events(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Query(query): Query<AdminAuthEventQuery>,
+) -> ApiResult<Json<Vec<AuthEvent>>> {
+    auth_user.require_admin()?;
+
+    let limit = query.limit.unwrap_or(50).clamp(1, 200);
+    let offset = query.offset.unwrap_or(0).max(0);
+    let events = db::auth_event::list_events(&state.pool, query.user_id, limit, offset).await?;
+
+    Ok(Json(events))
+}
+
+/// Tamper alerts raised by failed file and audit chain checks, newest first;
+/// only those not acknowledged yet unless `include_acknowledged` is set.
+#[utoipa::path(
+    get,
+    path = "/admin/security-events",
+    tag = "admin",
+    params(SecurityEventQuery),
+    responses((status = 200, description = "Security events", body = Vec<SecurityEvent>))
+)]
+pub async fn list_security_events(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Query(query): Query<SecurityEventQuery>,
+) -> ApiResult<Json<Vec<SecurityEvent>>> {
+    auth_user.require_admin()?;
+
+    let limit = query.limit.unwrap_or(50).clamp(1, 200);
+    let offset = query.offset.unwrap_or(0).max(0);
+    let events = db::security_event::list_events(
+        &state.pool,
+        query.include_acknowledged.unwrap_or(false),
+        limit,
+        offset,
+    )
+    .await?;
+
+    Ok(Json(events))
+}
+
+/// Closes a security event. The next failure of the same check on the
+/// document opens a new event and alerts again.
+#[utoipa::path(
+    post,
+    path = "/admin/security-events/{id}/acknowledge",
+    tag = "admin",
+    params(("id" = Uuid, Path, description = "Security event id")),
+    responses((status = 200, description = "The acknowledged event", body = SecurityEvent))
+)]
+pub async fn acknowledge_security_event(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(id): Path<Uuid>,
+) -> ApiResult<Json<SecurityEvent>> {
+    auth_user.require_admin()?;
+
+    if let Some(event) =
+        db::security_event::acknowledge_event(&state.pool, id, auth_user.user_id).await?
+    {
+        return Ok(Json(event));
+    }
+
+    match db::security_event::get_event(&state.pool, id).await? {
+        Some(_) => Err(ApiError::Conflict(
+            "Security event already acknowledged".to_string(),
+        )),
+        None => Err(ApiError::NotFound("Security event not found".to_string())),
+    }
+}
+
+/// Erases a signer's personal data, for erasure requests. Envelopes that are
+/// still pending are left untouched and listed in the report.
+#[utoipa::path(
+    post,
+    path = "/admin/signers/anonymize",
+    tag = "admin",
+    request_body = AnonymizeSignerRequest,
+    responses((status = 200, description = "What was erased", body = AnonymizationReport))
+)]
+pub async fn anonymize_signer(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    headers: axum::http::HeaderMap,
+    Json(req): Json<AnonymizeSignerRequest>,
+) -> ApiResult<Json<AnonymizationReport>> {
+    auth_user.require_admin()?;
+
+    req.validate()
+        .map_err(|e| ApiError::Validation(e.to_string()))?;
+
+    let (ip_address, user_agent) = extract_client_info_from_headers(&headers);
+
+    let report = anonymization::anonymize_signer(
+        &state.pool,
+        state.hash_algorithm,
+        state.email_service.as_deref(),
+        &req.email,
+        &AnonymizationActor {
+            user_id: auth_user.user_id,
+            ip_address: &ip_address,
+            user_agent: &user_agent,
+        },
+    )
+    .await?;
+
+    Ok(Json(report))
+}
+
+/// Checks a stored timestamp token against the hash it was requested for and
+/// the authority's signature.
+#[utoipa::path(
+    post,
+    path = "/admin/timestamps/{id}/verify",
+    tag = "admin",
+    params(("id" = Uuid, Path, description = "Timestamp id")),
+    responses((status = 200, description = "Whether the token is valid", body = TimestampVerification))
+)]
+pub async fn verify_timestamp(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(id): Path<Uuid>,
+) -> ApiResult<Json<TimestampVerification>> {
+    auth_user.require_admin()?;
+
+    let timestamp = db::timestamp::get_timestamp_by_id(&state.pool, id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Timestamp not found".to_string()))?;
+
+    Ok(Json(state.timestamps.verify(&timestamp)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/users/{id}/usage",
+    tag = "admin",
+    params(("id" = Uuid, Path, description = "User id")),
+    responses((status = 200, description = "Storage used by the user, and their quota", body = StorageUsage))
+)]
+pub async fn get_user_storage_usage(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(id): Path<Uuid>,
+) -> ApiResult<Json<StorageUsage>> {
+    auth_user.require_admin()?;
+
+    let usage = quota::get_usage(&state.pool, &state.config, id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("User not found".to_string()))?;
+
+    Ok(Json(usage))
+}
+
+/// Overrides a user's storage quota, or puts them back on the default.
+#[utoipa::path(
+    put,
+    path = "/admin/users/{id}/quota",
+    tag = "admin",
+    params(("id" = Uuid, Path, description = "User id")),
+    request_body = SetStorageQuotaRequest,
+    responses((status = 200, description = "The user's usage under the new quota", body = StorageUsage))
+)]
+pub async fn set_user_storage_quota(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(id): Path<Uuid>,
+    Json(req): Json<SetStorageQuotaRequest>,
+) -> ApiResult<Json<StorageUsage>> {
+    auth_user.require_admin()?;
+
+    req.validate()
+        .map_err(|e| ApiError::Validation(e.to_string()))?;
+
+    if !db::user::set_storage_quota(&state.pool, id, req.quota_mb).await? {
+        return Err(ApiError::NotFound("User not found".to_string()));
+    }
+
+    let usage = quota::get_usage(&state.pool, &state.config, id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("User not found".to_string()))?;
+
+    Ok(Json(usage))
+}
+
+/// Changes what a user may do. Tokens already issued keep the role they were
+/// issued with until they expire, unless `revoke_sessions` is set.
+#[utoipa::path(
+    put,
+    path = "/admin/users/{id}/role",
+    tag = "admin",
+    params(("id" = Uuid, Path, description = "User id")),
+    request_body = SetUserRoleRequest,
+    responses(
+        (status = 200, description = "The user with their new role", body = UserPublic),
+        (status = 404, description = "No such user", body = ErrorResponse),
+        (status = 409, description = "The user is the last admin", body = ErrorResponse),
+    )
+)]
+pub async fn set_user_role(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(id): Path<Uuid>,
+    Json(req): Json<SetUserRoleRequest>,
+) -> ApiResult<Json<UserPublic>> {
+    auth_user.require_admin()?;
+
+    let mut tx = state.pool.begin().await?;
+
+    let admins = db::user::lock_admin_ids(&mut *tx).await?;
+    if req.role != Role::Admin && admins == [id] {
+        return Err(ApiError::Conflict(
+            "The last admin cannot be given another role".to_string(),
+        ));
+    }
+
+    let user = db::user::set_role(&mut *tx, id, req.role)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("User not found".to_string()))?;
+
+    if req.revoke_sessions {
+        db::session::revoke_all_sessions(&mut *tx, id).await?;
+    }
+
+    tx.commit().await?;
+
+    Ok(Json(user.into()))
+}
+
+/// Emails an invitation link, or warns when email is not configured; the
+/// admin can resend it later either way.
+async fn send_invitation(
+    state: &AppState,
+    invitation: &Invitation,
+    name: &str,
+    inviter: &AuthUser,
+    token: &str,
+) -> ApiResult<()> {
+    let Some(email_service) = &state.email_service else {
+        warn!(
+            "Email service not configured, invitation for {} was not delivered",
+            invitation.email
+        );
+        return Ok(());
+    };
+
+    let inviter_name = db::user::get_user_by_id(&state.pool, inviter.user_id)
+        .await?
+        .map(|user| user.name)
+        .unwrap_or_else(|| "An administrator".to_string());
+
+    if let Err(e) = email_service
+        .send_invitation(
+            &invitation.email,
+            name,
+            &inviter_name,
+            token,
+            invitation.expires_at,
+        )
+        .await
+    {
+        warn!("Failed to send invitation to {}: {}", invitation.email, e);
+    }
+
+    Ok(())
+}
+
+/// Creates an account without a usable password and emails its owner a link
+/// to choose one.
+#[utoipa::path(
+    post,
+    path = "/admin/invitations",
+    tag = "admin",
+    request_body = CreateInvitationRequest,
+    responses((status = 200, description = "The invitation", body = Invitation))
+)]
+pub async fn create_invitation(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Json(req): Json<CreateInvitationRequest>,
+) -> ApiResult<Json<Invitation>> {
+    auth_user.require_admin()?;
+
+    req.validate()
+        .map_err(|e| ApiError::Validation(e.to_string()))?;
+
+    let email = req.email.trim();
+    let name = req.name.as_deref().map(str::trim).unwrap_or_default();
+    let token = crypto::generate_access_token();
+
+    let invitation = db::invitation::create_invitation(
+        &state.pool,
+        email,
+        name,
+        req.role,
+        &crypto::hash_token(&token),
+        auth_user.user_id,
+        Utc::now() + chrono::Duration::hours(state.config.invitation_expiry_hours),
+    )
+    .await?
+    .ok_or_else(|| ApiError::Conflict("This email address is already in use".to_string()))?;
+
+    send_invitation(&state, &invitation, name, &auth_user, &token).await?;
+
+    Ok(Json(invitation))
+}
+
+/// Invitations that have not been accepted, expired ones included.
+#[utoipa::path(
+    get,
+    path = "/admin/invitations",
+    tag = "admin",
+    responses((status = 200, description = "Pending invitations", body = Vec<Invitation>))
+)]
+pub async fn list_invitations(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+) -> ApiResult<Json<Vec<Invitation>>> {
+    auth_user.require_admin()?;
+
+    let invitations = db::invitation::list_pending_invitations(&state.pool).await?;
+
+    Ok(Json(invitations))
+}
+
+/// Emails a pending invitation again with a new link, valid for the full
+/// expiry period; earlier links stop working.
+#[utoipa::path(
+    post,
+    path = "/admin/invitations/{id}/resend",
+    tag = "admin",
+    params(("id" = Uuid, Path, description = "Invitation id")),
+    responses((status = 200, description = "The renewed invitation", body = Invitation))
+)]
+pub async fn resend_invitation(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(id): Path<Uuid>,
+) -> ApiResult<Json<Invitation>> {
+    auth_user.require_admin()?;
+
+    let token = crypto::generate_access_token();
+
+    let invitation = db::invitation::renew_invitation(
+        &state.pool,
+        id,
+        &crypto::hash_token(&token),
+        Utc::now() + chrono::Duration::hours(state.config.invitation_expiry_hours),
+    )
+    .await?
+    .ok_or_else(|| ApiError::NotFound("Invitation not found".to_string()))?;
+
+    let name = db::user::get_user_by_id(&state.pool, invitation.user_id)
+        .await?
+        .map(|user| user.name)
+        .unwrap_or_default();
+
+    send_invitation(&state, &invitation, &name, &auth_user, &token).await?;
+
+    Ok(Json(invitation))
+}
+
+/// Withdraws a pending invitation, deleting the account it created.
+#[utoipa::path(
+    delete,
+    path = "/admin/invitations/{id}",
+    tag = "admin",
+    params(("id" = Uuid, Path, description = "Invitation id")),
+    responses((status = 200, description = "The invitation was revoked", body = serde_json::Value))
+)]
+pub async fn revoke_invitation(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(id): Path<Uuid>,
+) -> ApiResult<Json<serde_json::Value>> {
+    auth_user.require_admin()?;
+
+    if !db::invitation::delete_pending_invitation(&state.pool, id).await? {
+        return Err(ApiError::NotFound("Invitation not found".to_string()));
+    }
+
+    Ok(Json(serde_json::json!({ "success": true })))
+}
@@ -1,73 +1,620 @@
-use axum::{extract::State, Extension, Json};
+use axum::{
+    extract::{Path, Query, State},
+    Extension, Json,
+};
 use chrono::Utc;
-use jsonwebtoken::{encode, EncodingKey, Header};
+use serde::Deserialize;
+use tracing::warn;
+use utoipa::IntoParams;
+use uuid::Uuid;
 use validator::Validate;
 
 use crate::api::error::{ApiError, ApiResult};
-use crate::api::middleware::AuthUser;
+use crate::api::middleware::{extract_client_info_from_headers, AuthUser};
 use crate::api::state::AppState;
+use crate::api::two_factor;
 use crate::db;
-use crate::models::user::{Claims, LoginRequest, LoginResponse, UserPublic};
+use crate::models::auth_event::{
+    AuthEvent, AuthEventType, FAILURE_UNKNOWN_EMAIL, FAILURE_WRONG_PASSWORD,
+};
+use crate::models::email_change::{ChangeEmailRequest, ConfirmEmailChangeRequest, EmailChange};
+use crate::models::invitation::AcceptInvitationRequest;
+use crate::models::session::UserSession;
+use crate::models::signature::{CreateUserSignatureRequest, UserSignature};
+use crate::models::user::{
+    Claims, LoginOutcome, LoginRequest, LoginResponse, StorageUsage, UpdateProfileRequest, User,
+    UserPublic,
+};
+use crate::services::{crypto, quota, signature_image};
 
+#[utoipa::path(
+    post,
+    path = "/auth/login",
+    tag = "auth",
+    request_body = LoginRequest,
+    responses((status = 200, description = "A bearer token and the user, or a two-factor challenge", body = LoginOutcome)),
+    security(())
+)]
 pub async fn login(
     State(state): State<AppState>,
+    headers: axum::http::HeaderMap,
     Json(req): Json<LoginRequest>,
-) -> ApiResult<Json<LoginResponse>> {
+) -> ApiResult<Json<LoginOutcome>> {
     req.validate()
         .map_err(|e| ApiError::Validation(e.to_string()))?;
 
-    let user = db::user::get_user_by_email(&state.pool, &req.email)
-        .await?
-        .ok_or_else(|| ApiError::Unauthorized)?;
+    let (ip_address, user_agent) = extract_client_info_from_headers(&headers);
+
+    let user = match db::user::get_user_by_email(&state.pool, &req.email).await? {
+        Some(user) if bcrypt::verify(&req.password, &user.password_hash).unwrap_or(false) => user,
+        user => {
+            let (user_id, reason) = match user {
+                Some(user) => (Some(user.id), FAILURE_WRONG_PASSWORD),
+                None => (None, FAILURE_UNKNOWN_EMAIL),
+            };
+            db::auth_event::create_event(
+                &state.pool,
+                user_id,
+                AuthEventType::LoginFailed,
+                req.email.trim(),
+                Some(reason),
+                None,
+                Some(&ip_address),
+                Some(&user_agent),
+            )
+            .await?;
+
+            return Err(ApiError::Unauthorized);
+        }
+    };
 
-    let valid =
-        bcrypt::verify(&req.password, &user.password_hash).map_err(|_| ApiError::Unauthorized)?;
+    if two_factor::two_factor_enabled(&state, user.id).await? {
+        let challenge = two_factor::issue_challenge(&state, &user)?;
 
-    if !valid {
-        return Err(ApiError::Unauthorized);
+        db::auth_event::create_event(
+            &state.pool,
+            Some(user.id),
+            AuthEventType::TwoFactorRequired,
+            &user.email,
+            None,
+            None,
+            Some(&ip_address),
+            Some(&user_agent),
+        )
+        .await?;
+
+        return Ok(Json(LoginOutcome::TwoFactorRequired(challenge)));
     }
 
+    Ok(Json(LoginOutcome::Authenticated(
+        issue_token(&state, user, &ip_address, &user_agent).await?,
+    )))
+}
+
+/// Issues a bearer token to a user who has given all their factors.
+pub(crate) async fn issue_token(
+    state: &AppState,
+    user: User,
+    ip_address: &str,
+    user_agent: &str,
+) -> ApiResult<LoginResponse> {
     let now = Utc::now();
     let exp = now + chrono::Duration::hours(state.config.jwt_expiration_hours);
 
+    let session = db::session::create_session(
+        &state.pool,
+        user.id,
+        Some(ip_address),
+        Some(user_agent),
+        exp,
+    )
+    .await?;
+
     let claims = Claims {
         sub: user.id.to_string(),
         user_id: user.id,
         email: user.email.clone(),
-        is_admin: user.is_admin,
+        role: user.role,
+        sid: session.id,
         iat: now.timestamp(),
         exp: exp.timestamp(),
     };
 
-    let token = encode(
-        &Header::default(),
-        &claims,
-        &EncodingKey::from_secret(state.config.jwt_secret.as_bytes()),
+    let token = state.jwt_keys.encode(&claims)?;
+
+    db::auth_event::create_event(
+        &state.pool,
+        Some(user.id),
+        AuthEventType::LoginSucceeded,
+        &user.email,
+        None,
+        Some(exp),
+        Some(ip_address),
+        Some(user_agent),
     )
-    .map_err(|e| ApiError::Internal(anyhow::anyhow!("Token encoding failed: {}", e)))?;
+    .await?;
 
-    Ok(Json(LoginResponse {
+    Ok(LoginResponse {
         token,
         user: UserPublic::from(user),
-    }))
+    })
 }
 
-pub async fn me(Extension(auth_user): Extension<AuthUser>) -> ApiResult<Json<UserPublic>> {
-    Ok(Json(UserPublic {
-        id: auth_user.user_id,
-        email: auth_user.email,
-        name: String::new(),
-        is_admin: auth_user.is_admin,
-    }))
+#[utoipa::path(
+    get,
+    path = "/auth/me",
+    tag = "auth",
+    responses((status = 200, description = "The authenticated user", body = UserPublic))
+)]
+pub async fn get_current_user(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+) -> ApiResult<Json<UserPublic>> {
+    let user = db::user::get_user_by_id(&state.pool, auth_user.user_id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("User not found".to_string()))?;
+
+    Ok(Json(UserPublic::from(user)))
 }
 
-pub async fn get_current_user(
+#[utoipa::path(
+    patch,
+    path = "/auth/me",
+    tag = "auth",
+    request_body = UpdateProfileRequest,
+    responses((status = 200, description = "The updated user", body = UserPublic))
+)]
+pub async fn update_current_user(
     State(state): State<AppState>,
     Extension(auth_user): Extension<AuthUser>,
+    Json(req): Json<UpdateProfileRequest>,
 ) -> ApiResult<Json<UserPublic>> {
+    let mut user = db::user::get_user_by_id(&state.pool, auth_user.user_id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("User not found".to_string()))?;
+
+    if let Some(timezone) = req.display_timezone.as_deref().map(str::trim) {
+        let timezone = match timezone {
+            "" => None,
+            name => Some(
+                name.parse::<chrono_tz::Tz>()
+                    .map_err(|_| {
+                        ApiError::Validation(
+                            "Display timezone must be an IANA timezone, e.g. America/New_York"
+                                .to_string(),
+                        )
+                    })?
+                    .name(),
+            ),
+        };
+
+        user = db::user::set_display_timezone(&state.pool, user.id, timezone)
+            .await?
+            .ok_or_else(|| ApiError::NotFound("User not found".to_string()))?;
+    }
+
+    if let Some(reply_to_enabled) = req.reply_to_enabled {
+        user = db::user::set_reply_to_enabled(&state.pool, user.id, reply_to_enabled)
+            .await?
+            .ok_or_else(|| ApiError::NotFound("User not found".to_string()))?;
+    }
+
+    Ok(Json(UserPublic::from(user)))
+}
+
+/// Starts changing the authenticated user's login email: emails a
+/// confirmation link to the new address and a notice to the current one. The
+/// change only applies once the link is followed.
+#[utoipa::path(
+    post,
+    path = "/auth/email",
+    tag = "auth",
+    request_body = ChangeEmailRequest,
+    responses((status = 200, description = "The pending change", body = EmailChange))
+)]
+pub async fn request_email_change(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    headers: axum::http::HeaderMap,
+    Json(req): Json<ChangeEmailRequest>,
+) -> ApiResult<Json<EmailChange>> {
+    req.validate()
+        .map_err(|e| ApiError::Validation(e.to_string()))?;
+
+    let (ip_address, user_agent) = extract_client_info_from_headers(&headers);
+
     let user = db::user::get_user_by_id(&state.pool, auth_user.user_id)
+        .await?
+        .ok_or(ApiError::Unauthorized)?;
+
+    if !bcrypt::verify(&req.password, &user.password_hash).unwrap_or(false) {
+        return Err(ApiError::BadRequest("Incorrect password".to_string()));
+    }
+
+    let new_email = req.new_email.trim();
+    if new_email == user.email {
+        return Err(ApiError::BadRequest(
+            "This is already your email address".to_string(),
+        ));
+    }
+    if db::user::get_user_by_email(&state.pool, new_email)
+        .await?
+        .is_some()
+    {
+        return Err(ApiError::Conflict(
+            "This email address is already in use".to_string(),
+        ));
+    }
+
+    let token = crypto::generate_access_token();
+    let expires_at = Utc::now() + chrono::Duration::hours(state.config.email_change_expiry_hours);
+
+    let change = db::email_change::create_email_change(
+        &state.pool,
+        user.id,
+        new_email,
+        &crypto::hash_token(&token),
+        expires_at,
+    )
+    .await?;
+
+    db::auth_event::create_event(
+        &state.pool,
+        Some(user.id),
+        AuthEventType::EmailChangeRequested,
+        new_email,
+        None,
+        None,
+        Some(&ip_address),
+        Some(&user_agent),
+    )
+    .await?;
+
+    if let Some(email_service) = &state.email_service {
+        email_service
+            .send_email_change_confirmation(new_email, &user.name, &token, expires_at)
+            .await?;
+
+        if let Err(e) = email_service
+            .send_email_change_notice(&user.email, &user.name, new_email)
+            .await
+        {
+            warn!("Failed to notify {} of an email change: {}", user.email, e);
+        }
+    } else {
+        warn!(
+            "Email service not configured, confirmation link for {} was not delivered",
+            new_email
+        );
+    }
+
+    Ok(Json(change))
+}
+
+/// Applies an email change from its confirmation link. Bearer tokens issued
+/// before the change stay valid, as users are identified by id.
+#[utoipa::path(
+    post,
+    path = "/auth/email/confirm",
+    tag = "auth",
+    request_body = ConfirmEmailChangeRequest,
+    responses((status = 200, description = "The user, with their new email", body = UserPublic)),
+    security(())
+)]
+pub async fn confirm_email_change(
+    State(state): State<AppState>,
+    headers: axum::http::HeaderMap,
+    Json(req): Json<ConfirmEmailChangeRequest>,
+) -> ApiResult<Json<UserPublic>> {
+    req.validate()
+        .map_err(|e| ApiError::Validation(e.to_string()))?;
+
+    let (ip_address, user_agent) = extract_client_info_from_headers(&headers);
+
+    let change = db::email_change::get_email_change_by_token_hash(
+        &state.pool,
+        &crypto::hash_token(&req.token),
+    )
+    .await?
+    .ok_or_else(|| ApiError::NotFound("Invalid confirmation link".to_string()))?;
+
+    if change.confirmed_at.is_some() {
+        return Err(ApiError::LinkExpired(
+            "This link has already been used".to_string(),
+        ));
+    }
+    if change.expires_at <= Utc::now() {
+        return Err(ApiError::LinkExpired("This link has expired".to_string()));
+    }
+
+    let mut tx = state.pool.begin().await?;
+
+    if !db::email_change::confirm_email_change(&mut *tx, change.id).await? {
+        return Err(ApiError::LinkExpired("This link has expired".to_string()));
+    }
+
+    if !db::user::update_user_email(&mut *tx, change.user_id, &change.new_email).await? {
+        return Err(ApiError::Conflict(
+            "This email address is already in use".to_string(),
+        ));
+    }
+
+    db::auth_event::create_event(
+        &mut *tx,
+        Some(change.user_id),
+        AuthEventType::EmailChanged,
+        &change.new_email,
+        None,
+        None,
+        Some(&ip_address),
+        Some(&user_agent),
+    )
+    .await?;
+
+    let user = db::user::get_user_by_id(&mut *tx, change.user_id)
         .await?
         .ok_or_else(|| ApiError::NotFound("User not found".to_string()))?;
 
+    tx.commit().await?;
+
     Ok(Json(UserPublic::from(user)))
 }
+
+/// The authenticated user's active sessions, most recently used first. The
+/// one making the request is marked `current`.
+#[utoipa::path(
+    get,
+    path = "/auth/sessions",
+    tag = "auth",
+    responses((status = 200, description = "Active sessions", body = Vec<UserSession>))
+)]
+pub async fn list_sessions(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+) -> ApiResult<Json<Vec<UserSession>>> {
+    let sessions = db::session::list_active_sessions(&state.pool, auth_user.user_id)
+        .await?
+        .into_iter()
+        .map(|session| UserSession {
+            current: session.id == auth_user.session_id,
+            ..session
+        })
+        .collect();
+
+    Ok(Json(sessions))
+}
+
+/// Signs out one session; its token stops working at once. Revoking the
+/// current session signs out the caller.
+#[utoipa::path(
+    delete,
+    path = "/auth/sessions/{id}",
+    tag = "auth",
+    params(("id" = Uuid, Path, description = "Session id")),
+    responses((status = 200, description = "The session was revoked", body = serde_json::Value))
+)]
+pub async fn revoke_session(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(id): Path<Uuid>,
+) -> ApiResult<Json<serde_json::Value>> {
+    if !db::session::revoke_session(&state.pool, id, auth_user.user_id).await? {
+        return Err(ApiError::NotFound("Session not found".to_string()));
+    }
+
+    Ok(Json(serde_json::json!({ "success": true })))
+}
+
+/// Signs out every session of the authenticated user but the current one.
+#[utoipa::path(
+    delete,
+    path = "/auth/sessions",
+    tag = "auth",
+    responses((status = 200, description = "How many sessions were revoked", body = serde_json::Value))
+)]
+pub async fn revoke_other_sessions(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+) -> ApiResult<Json<serde_json::Value>> {
+    let revoked =
+        db::session::revoke_other_sessions(&state.pool, auth_user.user_id, auth_user.session_id)
+            .await?;
+
+    Ok(Json(serde_json::json!({ "revoked": revoked })))
+}
+
+/// Completes an account created by an admin invitation: sets the invitee's
+/// name and password and signs them in.
+#[utoipa::path(
+    post,
+    path = "/auth/invitations/{token}/accept",
+    tag = "auth",
+    params(("token" = String, Path, description = "Token from the invitation link")),
+    request_body = AcceptInvitationRequest,
+    responses((status = 200, description = "A bearer token and the new user", body = LoginResponse)),
+    security(())
+)]
+pub async fn accept_invitation(
+    State(state): State<AppState>,
+    Path(token): Path<String>,
+    headers: axum::http::HeaderMap,
+    Json(req): Json<AcceptInvitationRequest>,
+) -> ApiResult<Json<LoginResponse>> {
+    req.validate()
+        .map_err(|e| ApiError::Validation(e.to_string()))?;
+
+    let (ip_address, user_agent) = extract_client_info_from_headers(&headers);
+
+    let invitation =
+        db::invitation::get_invitation_by_token_hash(&state.pool, &crypto::hash_token(&token))
+            .await?
+            .ok_or_else(|| ApiError::NotFound("Invalid invitation link".to_string()))?;
+
+    let already_accepted =
+        || ApiError::Conflict("This invitation has already been accepted".to_string());
+    let expired = || ApiError::LinkExpired("This invitation has expired".to_string());
+
+    if invitation.accepted_at.is_some() {
+        return Err(already_accepted());
+    }
+    if invitation.expires_at <= Utc::now() {
+        return Err(expired());
+    }
+
+    let password_hash = bcrypt::hash(&req.password, state.config.bcrypt_cost)
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!("Password hashing failed: {}", e)))?;
+
+    let mut tx = state.pool.begin().await?;
+
+    // Only one request can accept the invitation; a concurrent one finds it
+    // accepted here
+    if !db::invitation::accept_invitation(&mut *tx, invitation.id).await? {
+        return Err(if invitation.expires_at <= Utc::now() {
+            expired()
+        } else {
+            already_accepted()
+        });
+    }
+
+    db::user::set_user_credentials(
+        &mut *tx,
+        invitation.user_id,
+        req.name.trim(),
+        &password_hash,
+    )
+    .await?;
+
+    let user = db::user::get_user_by_id(&mut *tx, invitation.user_id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("User not found".to_string()))?;
+
+    tx.commit().await?;
+
+    Ok(Json(
+        issue_token(&state, user, &ip_address, &user_agent).await?,
+    ))
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AuthEventQuery {
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+/// The authenticated user's login history, newest first, including failed
+/// attempts on their account.
+#[utoipa::path(
+    get,
+    path = "/auth/events",
+    tag = "auth",
+    params(AuthEventQuery),
+    responses((status = 200, description = "Login history", body = Vec<AuthEvent>))
+)]
+pub async fn list_auth_events(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Query(query): Query<AuthEventQuery>,
+) -> ApiResult<Json<Vec<AuthEvent>>> {
+    let limit = query.limit.unwrap_or(50).clamp(1, 200);
+    let offset = query.offset.unwrap_or(0).max(0);
+
+    let events = db::auth_event::list_events(&state.pool, Some(auth_user.user_id), limit, offset)
+        .await?
+        .into_iter()
+        .map(|event| AuthEvent {
+            failure_reason: None,
+            ..event
+        })
+        .collect();
+
+    Ok(Json(events))
+}
+
+#[utoipa::path(
+    get,
+    path = "/auth/usage",
+    tag = "auth",
+    responses((status = 200, description = "Storage used by the authenticated user, and their quota", body = StorageUsage))
+)]
+pub async fn get_storage_usage(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+) -> ApiResult<Json<StorageUsage>> {
+    let usage = quota::get_usage(&state.pool, &state.config, auth_user.user_id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("User not found".to_string()))?;
+
+    Ok(Json(usage))
+}
+
+#[utoipa::path(
+    get,
+    path = "/auth/signatures",
+    tag = "auth",
+    responses((status = 200, description = "Saved signatures", body = Vec<UserSignature>))
+)]
+pub async fn list_saved_signatures(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+) -> ApiResult<Json<Vec<UserSignature>>> {
+    let signatures = db::signature::get_user_signatures(&state.pool, auth_user.user_id).await?;
+
+    Ok(Json(signatures))
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/signatures",
+    tag = "auth",
+    request_body = CreateUserSignatureRequest,
+    responses((status = 200, description = "The saved signature", body = UserSignature))
+)]
+pub async fn create_saved_signature(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Json(req): Json<CreateUserSignatureRequest>,
+) -> ApiResult<Json<UserSignature>> {
+    req.validate()
+        .map_err(|e| ApiError::Validation(e.to_string()))?;
+
+    let signature_data = signature_image::sanitize_signature_data_url(
+        &req.signature_data,
+        state.config.max_signature_size_bytes(),
+    )
+    .map_err(|e| ApiError::BadRequest(format!("signature_data: {}", e)))?;
+
+    let signature_hash = crypto::hash_string(state.hash_algorithm, &signature_data);
+
+    let signature = db::signature::create_user_signature(
+        &state.pool,
+        auth_user.user_id,
+        req.name.trim(),
+        &signature_data,
+        &signature_hash,
+    )
+    .await?;
+
+    Ok(Json(signature))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/auth/signatures/{id}",
+    tag = "auth",
+    params(("id" = Uuid, Path, description = "Saved signature id")),
+    responses((status = 200, description = "The signature was deleted", body = serde_json::Value))
+)]
+pub async fn delete_saved_signature(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(id): Path<Uuid>,
+) -> ApiResult<Json<serde_json::Value>> {
+    let deleted = db::signature::delete_user_signature(&state.pool, id, auth_user.user_id).await?;
+
+    if !deleted {
+        return Err(ApiError::NotFound("Saved signature not found".to_string()));
+    }
+
+    Ok(Json(serde_json::json!({ "success": true })))
+}
@@ -0,0 +1,140 @@
+use axum::{
+    body::Body,
+    extract::{Path, State},
+    http::{header, Response},
+    Extension, Json,
+};
+use tracing::warn;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::api::error::{ApiError, ApiResult};
+use crate::api::middleware::AuthUser;
+use crate::api::state::AppState;
+use crate::db;
+use crate::models::branding::{Branding, UpdateBrandingRequest};
+use crate::services::{branding, storage};
+
+#[utoipa::path(
+    get,
+    path = "/auth/branding",
+    tag = "auth",
+    responses((status = 200, description = "The current user's branding; unset parts use the default styling", body = Branding))
+)]
+pub async fn get_branding(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+) -> ApiResult<Json<Branding>> {
+    let branding = db::branding::get_branding(&state.pool, auth_user.user_id).await?;
+
+    Ok(Json(
+        branding
+            .map(|b| b.public(&state.config.public_url))
+            .unwrap_or_default(),
+    ))
+}
+
+/// Replaces the branding applied to the emails the current user's signers get
+/// and to their signing page.
+#[utoipa::path(
+    put,
+    path = "/auth/branding",
+    tag = "auth",
+    request_body = UpdateBrandingRequest,
+    responses((status = 200, description = "The saved branding", body = Branding))
+)]
+pub async fn update_branding(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Json(req): Json<UpdateBrandingRequest>,
+) -> ApiResult<Json<Branding>> {
+    auth_user.require_sender()?;
+
+    req.validate()
+        .map_err(|e| ApiError::Validation(e.to_string()))?;
+
+    let company_name = req
+        .company_name
+        .as_deref()
+        .map(str::trim)
+        .filter(|name| !name.is_empty());
+    let primary_color = req
+        .primary_color
+        .as_deref()
+        .filter(|color| !color.trim().is_empty())
+        .map(branding::parse_color)
+        .transpose()
+        .map_err(|e| ApiError::Validation(format!("primary_color: {}", e)))?;
+
+    let current = db::branding::get_branding(&state.pool, auth_user.user_id).await?;
+    let current_logo = current
+        .as_ref()
+        .and_then(|b| b.logo_storage_key.clone().zip(b.logo_content_type.clone()));
+
+    let logo = match req.logo.as_deref().map(str::trim) {
+        None => current_logo.clone(),
+        Some("") => None,
+        Some(data_url) => {
+            let (content_type, bytes) =
+                branding::parse_logo(data_url, state.config.max_branding_logo_bytes())
+                    .map_err(|e| ApiError::BadRequest(format!("logo: {}", e)))?;
+            let key = storage::branding_logo_key(auth_user.user_id, Uuid::new_v4(), content_type);
+            state.storage.put(&key, bytes).await?;
+            Some((key, content_type.to_string()))
+        }
+    };
+
+    let saved = db::branding::save_branding(
+        &state.pool,
+        auth_user.user_id,
+        company_name,
+        primary_color.as_deref(),
+        logo.as_ref()
+            .map(|(key, content_type)| (key.as_str(), content_type.as_str())),
+    )
+    .await;
+
+    // Whichever logo is no longer referenced, the new one if saving failed
+    let unused = match &saved {
+        Ok(_) if logo != current_logo => current_logo,
+        Ok(_) => None,
+        Err(_) if logo != current_logo => logo,
+        Err(_) => None,
+    };
+    if let Some((key, _)) = unused {
+        if let Err(e) = state.storage.delete(&key).await {
+            warn!("Failed to delete unused branding logo {}: {:?}", key, e);
+        }
+    }
+
+    Ok(Json(saved?.public(&state.config.public_url)))
+}
+
+/// Serves an owner's logo. Public, since email clients load it without
+/// credentials.
+#[utoipa::path(
+    get,
+    path = "/branding/{user_id}/logo",
+    tag = "auth",
+    params(("user_id" = Uuid, Path, description = "Id of the owner whose logo to get")),
+    responses((status = 200, description = "The logo", content_type = "image/png")),
+    security(())
+)]
+pub async fn get_branding_logo(
+    State(state): State<AppState>,
+    Path(user_id): Path<Uuid>,
+) -> ApiResult<Response<Body>> {
+    let (key, content_type) = db::branding::get_branding(&state.pool, user_id)
+        .await?
+        .and_then(|b| b.logo_storage_key.zip(b.logo_content_type))
+        .ok_or_else(|| ApiError::NotFound("Logo not found".to_string()))?;
+
+    let bytes = state.storage.get(&key).await?;
+
+    Response::builder()
+        .header(header::CONTENT_TYPE, content_type)
+        .header(header::CACHE_CONTROL, "public, max-age=86400")
+        .header("X-Content-Type-Options", "nosniff")
+        .body(Body::from(bytes))
+        .map_err(|e| ApiError::Internal(e.into()))
+}
@@ -0,0 +1,45 @@
+use axum::{extract::State, Json};
+use serde_json::Value;
+
+use crate::api::error::{ApiError, ApiResult};
+use crate::api::state::AppState;
+use crate::models::audit::CertificateVerification;
+use crate::services::audit::{self, IssuedCertificate};
+
+/// Checks a certificate of completion as it was issued, e.g. the
+/// `certificate.json` of a document package: whether its hash matches its
+/// contents, and whether the server signed that hash.
+#[utoipa::path(
+    post,
+    path = "/certificates/verify",
+    tag = "certificates",
+    request_body(content = Object, description = "The certificate as issued"),
+    responses(
+        (status = 200, description = "Whether the certificate is intact", body = CertificateVerification),
+        (status = 400, description = "Not a certificate"),
+    ),
+    security(())
+)]
+pub async fn verify_certificate(
+    State(state): State<AppState>,
+    Json(certificate): Json<Value>,
+) -> ApiResult<Json<CertificateVerification>> {
+    let certificate = IssuedCertificate::from_json(certificate)
+        .map_err(|e| ApiError::BadRequest(format!("Not a certificate: {}", e)))?;
+    let hash_valid = audit::verify_certificate_hash(&certificate)
+        .map_err(|e| ApiError::BadRequest(format!("Invalid certificate_hash: {}", e)))?;
+
+    let signature = match &certificate {
+        IssuedCertificate::Current(certificate) => Some(state.server_key.verify(
+            certificate.certificate_hash.as_bytes(),
+            &certificate.server_signature.key_id,
+            &certificate.server_signature.signature,
+        )),
+        IssuedCertificate::Legacy(_) => None,
+    };
+
+    Ok(Json(CertificateVerification {
+        hash_valid,
+        signature,
+    }))
+}
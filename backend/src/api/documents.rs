@@ -1,55 +1,260 @@
 use axum::{
-    extract::{Multipart, Path, Query, State},
+    body::Bytes,
+    extract::{multipart::MultipartError, Multipart, Path, Query, State},
+    http::StatusCode,
     Extension, Json,
 };
+use chrono_tz::Tz;
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
-use tokio::fs;
-use tokio::io::AsyncWriteExt;
-use tracing::info;
+use std::collections::HashSet;
+use tracing::{info, warn};
+use utoipa::{IntoParams, ToSchema};
 use uuid::Uuid;
 use validator::Validate;
 
-use crate::api::error::{ApiError, ApiResult};
+use crate::api::access::{self, DocumentAccess};
+use crate::api::error::{ApiError, ApiResult, ErrorResponse};
+use crate::api::file_response;
 use crate::api::middleware::{extract_client_info_from_headers, AuthUser};
+use crate::api::precondition;
+use crate::api::signing::{signing_session, SigningSession};
 use crate::api::state::AppState;
 use crate::db;
-use crate::models::audit::AuditAction;
+use crate::models::audit::{AuditAction, AuditExport, AuditLog, Certificate, CertificateTimestamp};
 use crate::models::document::{
-    AddFieldRequest, Document, DocumentFieldRow, DocumentStatus, DocumentWithFields,
-    UpdateFieldRequest,
+    AcroFormImportResponse, AddFieldRequest, BulkDocumentAction, BulkDocumentRequest,
+    BulkDocumentResponse, BulkDocumentResult, CoordinateSpace, Document, DocumentFieldRow,
+    DocumentListItem, DocumentPage, DocumentSearchResult, DocumentStatus, DocumentWithFields,
+    FieldGeometry, FieldRect, FieldType, RecallDocumentRequest, SetLegalHoldRequest,
+    SkippedFormField, UpdateDocumentRequest, UpdateFieldRequest, UploadedDocument,
+    VoidDocumentRequest,
+};
+use crate::models::outbox::{OutboxEmailKind, OutboxEventType};
+use crate::models::security_event::SecurityEventSource;
+use crate::models::share::{DocumentShare, ShareDocumentRequest, SharedDocument};
+use crate::models::share_link::{
+    CreateShareLinkRequest, CreatedShareLink, ShareLink, MAX_SHARE_LINK_HOURS,
+};
+use crate::models::signature::{CompleteSigningRequest, Signature};
+use crate::models::signer::{
+    AddSignerRequest, CreateOpenLinkRequest, InPersonSessionResponse, OpenLinkResponse,
+    RemoveSignerRequest, ReplaceSignerRequest, RotateSignerTokenRequest, Signer, SignerRole,
+    SignerStatus, UpdateSignerRequest,
+};
+use crate::models::tag::{
+    normalize_tag, AddTagsRequest, TagFilter, TagMode, TagUsage, MAX_TAGS_PER_DOCUMENT,
+};
+use crate::models::timestamp::{DocumentTimestampResponse, TimestampSubject};
+use crate::services::integrity::{FileVerification, IntegrityActor};
+use crate::services::number_field::NumberConstraints;
+use crate::services::pdf::PdfValidationError;
+use crate::services::preflight::{self, PreflightCode, PreflightIssue, PreflightResponse};
+use crate::services::signing::{SigningActor, SigningContext, SigningSubmission};
+use crate::services::{
+    audit, blob_store, crypto, display_time, field_conditions, package, pdf, quota, radio_group,
+    search, server_key, signature_image, signature_store, signing,
 };
-use crate::models::signer::{AddSignerRequest, Signer};
-use crate::services::{audit, crypto, pdf};
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct ListQuery {
     pub limit: Option<i64>,
     pub offset: Option<i64>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct DocumentListQuery {
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+    /// Comma separated tags the documents must carry, e.g. `q3,vendor`.
+    pub tags: Option<String>,
+    /// Whether documents need `all` of the tags, the default, or `any` of them.
+    pub tag_mode: Option<TagMode>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
 pub struct DocumentListResponse {
-    pub documents: Vec<Document>,
+    pub documents: Vec<DocumentListItem>,
     pub total: i64,
 }
 
+#[utoipa::path(
+    get,
+    path = "/documents",
+    tag = "documents",
+    params(DocumentListQuery),
+    responses((status = 200, description = "The user's documents", body = DocumentListResponse))
+)]
 pub async fn list_documents(
     State(state): State<AppState>,
     Extension(auth_user): Extension<AuthUser>,
-    Query(query): Query<ListQuery>,
+    Query(query): Query<DocumentListQuery>,
 ) -> ApiResult<Json<DocumentListResponse>> {
     let limit = query.limit.unwrap_or(20).min(100);
     let offset = query.offset.unwrap_or(0);
+    let tags = query
+        .tags
+        .as_deref()
+        .and_then(|tags| TagFilter::parse(tags, query.tag_mode.unwrap_or_default()));
 
-    let documents =
-        db::document::get_documents_by_owner(&state.pool, auth_user.user_id, limit, offset).await?;
+    let documents = db::document::get_documents_by_owner(
+        &state.pool,
+        auth_user.user_id,
+        tags.as_ref(),
+        limit,
+        offset,
+    )
+    .await?;
 
-    let total = db::document::count_documents_by_owner(&state.pool, auth_user.user_id).await?;
+    let total =
+        db::document::count_documents_by_owner(&state.pool, auth_user.user_id, tags.as_ref())
+            .await?;
 
     Ok(Json(DocumentListResponse { documents, total }))
 }
 
+/// Longest search term accepted, in characters.
+const MAX_SEARCH_TERM_LENGTH: usize = 200;
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SearchQuery {
+    /// Text to find in titles, file names and signer names or emails.
+    pub q: String,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct DocumentSearchResponse {
+    pub documents: Vec<DocumentSearchResult>,
+    pub total: i64,
+}
+
+/// Finds the user's documents whose title, file name, or signer name or
+/// email contains the search term, ignoring case.
+#[utoipa::path(
+    get,
+    path = "/documents/search",
+    tag = "documents",
+    params(SearchQuery),
+    responses((status = 200, description = "Matching documents, best match first", body = DocumentSearchResponse))
+)]
+pub async fn search_documents(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Query(query): Query<SearchQuery>,
+) -> ApiResult<Json<DocumentSearchResponse>> {
+    let term = query.q.trim();
+    if term.is_empty() || term.chars().count() > MAX_SEARCH_TERM_LENGTH {
+        return Err(ApiError::Validation(format!(
+            "Search term must be 1 to {} characters",
+            MAX_SEARCH_TERM_LENGTH
+        )));
+    }
+
+    let limit = query.limit.unwrap_or(20).min(100);
+    let offset = query.offset.unwrap_or(0);
+
+    let (documents, total) =
+        search::search_documents(&state.pool, auth_user.user_id, term, limit, offset).await?;
+
+    Ok(Json(DocumentSearchResponse { documents, total }))
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TrashListResponse {
+    pub documents: Vec<Document>,
+    pub total: i64,
+    /// Days after deletion before a document is purged for good. Completed
+    /// documents are never purged.
+    pub retention_days: i64,
+}
+
+#[utoipa::path(
+    get,
+    path = "/documents/trash",
+    tag = "documents",
+    params(ListQuery),
+    responses((status = 200, description = "Documents in the trash", body = TrashListResponse))
+)]
+pub async fn list_trash(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Query(query): Query<ListQuery>,
+) -> ApiResult<Json<TrashListResponse>> {
+    let limit = query.limit.unwrap_or(20).min(100);
+    let offset = query.offset.unwrap_or(0);
+
+    let documents =
+        db::document::get_deleted_documents_by_owner(&state.pool, auth_user.user_id, limit, offset)
+            .await?;
+
+    let total =
+        db::document::count_deleted_documents_by_owner(&state.pool, auth_user.user_id).await?;
+
+    Ok(Json(TrashListResponse {
+        documents,
+        total,
+        retention_days: state.config.deleted_document_retention_days,
+    }))
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SharedDocumentListResponse {
+    pub documents: Vec<SharedDocument>,
+    pub total: i64,
+}
+
+/// Documents other users shared with the authenticated user.
+#[utoipa::path(
+    get,
+    path = "/documents/shared",
+    tag = "shares",
+    params(ListQuery),
+    responses((status = 200, description = "Documents shared with the user", body = SharedDocumentListResponse))
+)]
+pub async fn list_shared_documents(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Query(query): Query<ListQuery>,
+) -> ApiResult<Json<SharedDocumentListResponse>> {
+    let limit = query.limit.unwrap_or(20).min(100);
+    let offset = query.offset.unwrap_or(0);
+
+    let documents =
+        db::share::get_documents_shared_with(&state.pool, auth_user.user_id, limit, offset).await?;
+
+    let total = db::share::count_documents_shared_with(&state.pool, auth_user.user_id).await?;
+
+    Ok(Json(SharedDocumentListResponse { documents, total }))
+}
+
+/// The tags on the user's documents outside the trash, with how many
+/// documents carry each.
+#[utoipa::path(
+    get,
+    path = "/documents/tags",
+    tag = "tags",
+    responses((status = 200, description = "The user's tags", body = [TagUsage]))
+)]
+pub async fn list_tags(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+) -> ApiResult<Json<Vec<TagUsage>>> {
+    let tags = db::tag::get_tag_usage(&state.pool, auth_user.user_id).await?;
+
+    Ok(Json(tags))
+}
+
+#[utoipa::path(
+    get,
+    path = "/documents/{id}",
+    tag = "documents",
+    params(("id" = Uuid, Path, description = "Document id")),
+    responses((status = 200, description = "The document with its fields, signers and pages", body = DocumentWithFields))
+)]
 pub async fn get_document(
     State(state): State<AppState>,
     Extension(auth_user): Extension<AuthUser>,
@@ -59,10 +264,16 @@ pub async fn get_document(
         .await?
         .ok_or_else(|| ApiError::NotFound("Document not found".to_string()))?;
 
-    if document.owner_id != auth_user.user_id {
-        return Err(ApiError::Forbidden);
-    }
+    access::require(
+        &state.pool,
+        &document,
+        auth_user.user_id,
+        DocumentAccess::Viewer,
+    )
+    .await?;
 
+    // Loaded first: caching page sizes also normalizes the fields' coordinates
+    let pages = load_pages(&state, &document).await?;
     let fields = db::document::get_fields_by_document(&state.pool, id).await?;
     let signers = db::signer::get_signers_by_document(&state.pool, id).await?;
 
@@ -70,50 +281,107 @@ pub async fn get_document(
         document,
         fields,
         signers,
+        pages,
     }))
 }
 
-#[derive(Debug, Deserialize)]
+/// The multipart form `create_document` reads.
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct CreateDocumentForm {
     pub title: String,
     pub self_sign_only: Option<bool>,
+    pub verification_method: Option<String>,
+    pub reminder_interval_days: Option<i32>,
+    pub max_reminders: Option<i32>,
+    /// Let signers download the completed document from their status page.
+    pub signer_download_enabled: Option<bool>,
+    /// The PDF.
+    #[schema(value_type = String, format = Binary)]
+    pub file: Vec<u8>,
+}
+
+/// The multipart form `verify_file` reads.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct VerifyFileForm {
+    /// The copy to check.
+    #[schema(value_type = String, format = Binary)]
+    pub file: Vec<u8>,
+}
+
+/// Maps multipart read errors, reporting uploads cut off by the request body
+/// limit in terms of the configured maximum file size.
+fn multipart_error(state: &AppState, e: MultipartError) -> ApiError {
+    if e.status() == StatusCode::PAYLOAD_TOO_LARGE {
+        return ApiError::BadRequest(format!(
+            "File too large. Maximum size is {} MB",
+            state.config.max_file_size_mb
+        ));
+    }
+
+    ApiError::BadRequest(e.body_text())
 }
 
+#[utoipa::path(
+    post,
+    path = "/documents",
+    tag = "documents",
+    params(("Idempotency-Key" = Option<String>, Header, description = "Makes retries safe: a retry with the same key and payload gets the original response")),
+    request_body(content = CreateDocumentForm, content_type = "multipart/form-data"),
+    responses(
+        (status = 200, description = "The uploaded document", body = UploadedDocument),
+        (status = 409, description = "The Idempotency-Key was used for a different request", body = ErrorResponse),
+        (status = 413, description = "The upload does not fit in the storage quota", body = ErrorResponse),
+    )
+)]
 pub async fn create_document(
     State(state): State<AppState>,
     Extension(auth_user): Extension<AuthUser>,
     headers: axum::http::HeaderMap,
     mut multipart: Multipart,
-) -> ApiResult<Json<Document>> {
+) -> ApiResult<Json<UploadedDocument>> {
+    auth_user.require_sender()?;
+
     let (ip_address, user_agent) = extract_client_info_from_headers(&headers);
 
     let mut title: Option<String> = None;
     let mut self_sign_only = false;
-    let mut file_data: Option<(String, Vec<u8>)> = None;
+    let mut verification_method: Option<String> = None;
+    let mut reminder_interval_days: Option<i32> = None;
+    let mut max_reminders: Option<i32> = None;
+    let mut signer_download_enabled = false;
+    let mut file_data: Option<(String, Bytes)> = None;
 
     while let Some(field) = multipart
         .next_field()
         .await
-        .map_err(|e| ApiError::BadRequest(e.to_string()))?
+        .map_err(|e| multipart_error(&state, e))?
     {
         let name = field.name().unwrap_or("").to_string();
 
         match name.as_str() {
             "title" => {
-                title = Some(
-                    field
-                        .text()
-                        .await
-                        .map_err(|e| ApiError::BadRequest(e.to_string()))?,
-                );
+                title = Some(field.text().await.map_err(|e| multipart_error(&state, e))?);
             }
             "self_sign_only" => {
-                let value = field
-                    .text()
-                    .await
-                    .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+                let value = field.text().await.map_err(|e| multipart_error(&state, e))?;
                 self_sign_only = value == "true" || value == "1";
             }
+            "verification_method" => {
+                let value = field.text().await.map_err(|e| multipart_error(&state, e))?;
+                verification_method = Some(value.trim().to_string()).filter(|v| !v.is_empty());
+            }
+            "reminder_interval_days" => {
+                let value = field.text().await.map_err(|e| multipart_error(&state, e))?;
+                reminder_interval_days = parse_reminder_setting(&name, &value)?;
+            }
+            "max_reminders" => {
+                let value = field.text().await.map_err(|e| multipart_error(&state, e))?;
+                max_reminders = parse_reminder_setting(&name, &value)?;
+            }
+            "signer_download_enabled" => {
+                let value = field.text().await.map_err(|e| multipart_error(&state, e))?;
+                signer_download_enabled = value == "true" || value == "1";
+            }
             "file" => {
                 let filename = field
                     .file_name()
@@ -131,7 +399,7 @@ pub async fn create_document(
                 let data = field
                     .bytes()
                     .await
-                    .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+                    .map_err(|e| multipart_error(&state, e))?;
 
                 if data.len() as u64 > state.config.max_file_size_bytes() {
                     return Err(ApiError::BadRequest(format!(
@@ -140,7 +408,7 @@ pub async fn create_document(
                     )));
                 }
 
-                file_data = Some((filename, data.to_vec()));
+                file_data = Some((filename, data));
             }
             _ => {}
         }
@@ -150,43 +418,116 @@ pub async fn create_document(
     let (filename, data) =
         file_data.ok_or_else(|| ApiError::BadRequest("PDF file is required".to_string()))?;
 
-    let doc_id = Uuid::new_v4();
-    let file_hash = crypto::hash_data(&data);
+    check_reminder_settings(reminder_interval_days, max_reminders)?;
 
-    let storage_dir = PathBuf::from(&state.config.storage_path)
-        .join(auth_user.user_id.to_string())
-        .join(doc_id.to_string());
+    if let Some(method) = &verification_method {
+        if state.identity_verifiers.get(method).is_none() {
+            return Err(ApiError::BadRequest(format!(
+                "Unsupported verification method: {}",
+                method
+            )));
+        }
+    }
 
-    fs::create_dir_all(&storage_dir)
-        .await
-        .map_err(|e| ApiError::Internal(anyhow::anyhow!("Failed to create storage dir: {}", e)))?;
+    let workers = &state.pdf_workers;
 
-    let file_path = storage_dir.join("original.pdf");
+    workers
+        .validate(data.clone())
+        .await
+        .map_err(pdf_validation_error)?;
 
-    let mut file = fs::File::create(&file_path)
+    workers
+        .check_limits(data.clone(), state.config.pdf_limits())
         .await
-        .map_err(|e| ApiError::Internal(anyhow::anyhow!("Failed to create file: {}", e)))?;
+        .map_err(pdf_validation_error)?;
 
-    file.write_all(&data)
+    let pages: Vec<DocumentPage> = workers
+        .metadata(data.clone())
         .await
-        .map_err(|e| ApiError::Internal(anyhow::anyhow!("Failed to write file: {}", e)))?;
+        .map_err(|e| ApiError::BadRequest(format!("Invalid PDF file: {}", e)))?
+        .page_sizes
+        .iter()
+        .map(DocumentPage::from)
+        .collect();
 
-    pdf::validate_pdf(&file_path)
+    let mut security_scan = workers
+        .security_scan(data.clone())
+        .await
         .map_err(|e| ApiError::BadRequest(format!("Invalid PDF file: {}", e)))?;
 
+    let data = if security_scan.findings.is_empty() {
+        data.to_vec()
+    } else {
+        let found: Vec<&str> = security_scan.findings.iter().map(|f| f.label()).collect();
+
+        if !state.config.strip_pdf_active_content {
+            return Err(ApiError::BadRequest(format!(
+                "PDFs containing {} are not supported — please remove them and re-upload.",
+                found.join(", ")
+            )));
+        }
+
+        let stripped = workers.strip_active_content(data).await.map_err(|e| {
+            ApiError::Internal(anyhow::anyhow!("Failed to strip active content: {}", e))
+        })?;
+        security_scan.stripped = true;
+        info!("Stripped {} from uploaded PDF", found.join(", "));
+        stripped
+    };
+
+    check_storage_quota(&state, auth_user.user_id, data.len() as i64).await?;
+
+    let doc_id = Uuid::new_v4();
+    let file_hash = crypto::hash_data(state.hash_algorithm, &data);
+    let file_size = data.len() as i64;
+
+    let duplicate_of =
+        db::document::find_document_by_file_hash(&state.pool, auth_user.user_id, &file_hash)
+            .await?;
+
+    let stored = blob_store::store_original(
+        &state.pool,
+        state.storage.as_ref(),
+        state.config.upload_deduplication,
+        auth_user.user_id,
+        doc_id,
+        &file_hash,
+        data,
+    )
+    .await
+    .map_err(|e| ApiError::Internal(anyhow::anyhow!("Failed to store file: {}", e)))?;
+
     let document = db::document::create_document(
         &state.pool,
+        doc_id,
         auth_user.user_id,
         &title,
         &filename,
-        file_path.to_str().unwrap(),
+        &stored.storage_key,
         &file_hash,
         self_sign_only,
+        verification_method.as_deref(),
+        &pages,
+        reminder_interval_days,
+        max_reminders,
+        signer_download_enabled,
+        file_size,
+        stored.encryption.as_ref(),
     )
-    .await?;
+    .await;
+
+    let document = match document {
+        Ok(document) => document,
+        Err(e) => {
+            let _ =
+                blob_store::release(&state.pool, state.storage.as_ref(), &stored.storage_key).await;
+            return Err(e.into());
+        }
+    };
 
     audit::log_action(
         &state.pool,
+        state.hash_algorithm,
         document.id,
         None,
         Some(auth_user.user_id),
@@ -196,7 +537,9 @@ pub async fn create_document(
         Some(serde_json::json!({
             "title": title,
             "filename": filename,
-            "file_hash": file_hash
+            "file_hash": file_hash,
+            "security_scan": security_scan,
+            "duplicate_of": duplicate_of
         })),
     )
     .await?;
@@ -206,14 +549,35 @@ pub async fn create_document(
         document.id, auth_user.user_id
     );
 
-    Ok(Json(document))
+    Ok(Json(UploadedDocument {
+        document,
+        duplicate_of,
+    }))
 }
 
-pub async fn delete_document(
+#[utoipa::path(
+    patch,
+    path = "/documents/{id}",
+    tag = "documents",
+    params(("id" = Uuid, Path, description = "Document id")),
+    request_body = UpdateDocumentRequest,
+    responses((status = 200, description = "The updated document", body = Document))
+)]
+pub async fn update_document(
     State(state): State<AppState>,
     Extension(auth_user): Extension<AuthUser>,
     Path(id): Path<Uuid>,
-) -> ApiResult<Json<serde_json::Value>> {
+    headers: axum::http::HeaderMap,
+    Json(req): Json<UpdateDocumentRequest>,
+) -> ApiResult<Json<Document>> {
+    auth_user.require_sender()?;
+
+    let (ip_address, user_agent) = extract_client_info_from_headers(&headers);
+
+    req.validate()
+        .map_err(|e| ApiError::Validation(e.to_string()))?;
+    let expected_updated_at = precondition::expected_updated_at(&headers, req.expected_updated_at)?;
+
     let document = db::document::get_document_by_id(&state.pool, id)
         .await?
         .ok_or_else(|| ApiError::NotFound("Document not found".to_string()))?;
@@ -222,125 +586,293 @@ pub async fn delete_document(
         return Err(ApiError::Forbidden);
     }
 
-    if document.status == DocumentStatus::Completed {
+    if matches!(
+        document.status,
+        DocumentStatus::Completed | DocumentStatus::Voided
+    ) {
         return Err(ApiError::BadRequest(
-            "Cannot delete completed documents".to_string(),
+            "Cannot modify completed or voided documents".to_string(),
         ));
     }
 
-    let file_path = PathBuf::from(&document.file_path);
-    if let Some(parent) = file_path.parent() {
-        let _ = fs::remove_dir_all(parent).await;
+    let title = req.title.as_deref().map(str::trim);
+    if title == Some("") {
+        return Err(ApiError::Validation("Title is required".to_string()));
     }
 
-    db::document::delete_document(&state.pool, id).await?;
+    let effective = |requested: Option<i32>, current: Option<i32>| match requested {
+        Some(0) => None,
+        Some(value) => Some(value),
+        None => current,
+    };
+    check_reminder_settings(
+        effective(req.reminder_interval_days, document.reminder_interval_days),
+        effective(req.max_reminders, document.max_reminders),
+    )?;
 
-    Ok(Json(serde_json::json!({ "success": true })))
+    let Some(updated) = db::document::update_document(
+        &state.pool,
+        id,
+        title,
+        req.expires_at,
+        req.reminder_interval_days,
+        req.max_reminders,
+        req.signer_download_enabled,
+        req.progress_notifications.as_deref(),
+        req.locale.as_deref().map(str::trim),
+        expected_updated_at,
+    )
+    .await?
+    else {
+        let current = db::document::get_document_by_id(&state.pool, id)
+            .await?
+            .ok_or_else(|| ApiError::NotFound("Document not found".to_string()))?;
+        return Err(ApiError::EditConflict(serde_json::json!(current)));
+    };
+
+    audit::log_action(
+        &state.pool,
+        state.hash_algorithm,
+        id,
+        None,
+        Some(auth_user.user_id),
+        AuditAction::DocumentUpdated,
+        Some(&ip_address),
+        Some(&user_agent),
+        Some(serde_json::json!({
+            "title": updated.title,
+            "previous_title": document.title,
+            "expires_at": updated.expires_at,
+            "reminder_interval_days": updated.reminder_interval_days,
+            "max_reminders": updated.max_reminders,
+            "signer_download_enabled": updated.signer_download_enabled,
+            "progress_notifications": updated.progress_notifications,
+            "locale": updated.locale
+        })),
+    )
+    .await?;
+
+    // Signers who have not finished get the deadline's calendar event updated.
+    if updated.status == DocumentStatus::Pending
+        && updated.expires_at != document.expires_at
+        && state.email_outbox.enabled()
+    {
+        for signer in db::signer::get_signers_by_document(&state.pool, id).await? {
+            if matches!(signer.status, SignerStatus::Sent | SignerStatus::Viewed) {
+                db::outbox::enqueue_email(
+                    &state.pool,
+                    OutboxEmailKind::DeadlineChanged,
+                    id,
+                    Some(signer.id),
+                )
+                .await?;
+            }
+        }
+        state.email_outbox.deliver_document(id).await;
+    }
+
+    Ok(Json(updated))
 }
 
-pub async fn add_field(
+/// Rejects storing `additional` more bytes for a user whose documents would
+/// then take up more than their storage quota.
+async fn check_storage_quota(state: &AppState, user_id: Uuid, additional: i64) -> ApiResult<()> {
+    let usage = quota::get_usage(&state.pool, &state.config, user_id)
+        .await?
+        .ok_or(ApiError::Unauthorized)?;
+
+    match usage.quota_bytes {
+        Some(quota_bytes) if !usage.allows(additional) => Err(ApiError::QuotaExceeded(format!(
+            "Storage quota exceeded: {} of {} used, and this document needs {}",
+            quota::format_size(usage.used_bytes),
+            quota::format_size(quota_bytes),
+            quota::format_size(additional)
+        ))),
+        _ => Ok(()),
+    }
+}
+
+fn parse_reminder_setting(name: &str, value: &str) -> ApiResult<Option<i32>> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Ok(None);
+    }
+
+    match value.parse::<i32>() {
+        Ok(0) => Ok(None),
+        Ok(value) => Ok(Some(value)),
+        Err(_) => Err(ApiError::BadRequest(format!("{} must be a number", name))),
+    }
+}
+
+/// Automatic reminders need both an interval and a cap.
+fn check_reminder_settings(
+    interval_days: Option<i32>,
+    max_reminders: Option<i32>,
+) -> ApiResult<()> {
+    if interval_days.is_some() != max_reminders.is_some() {
+        return Err(ApiError::Validation(
+            "reminder_interval_days and max_reminders must be set together".to_string(),
+        ));
+    }
+
+    if interval_days.is_some_and(|days| !(1..=365).contains(&days)) {
+        return Err(ApiError::Validation(
+            "reminder_interval_days must be between 1 and 365".to_string(),
+        ));
+    }
+
+    if max_reminders.is_some_and(|max| !(1..=20).contains(&max)) {
+        return Err(ApiError::Validation(
+            "max_reminders must be between 1 and 20".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+#[utoipa::path(
+    delete,
+    path = "/documents/{id}",
+    tag = "documents",
+    params(("id" = Uuid, Path, description = "Document id")),
+    responses((status = 200, description = "The document was moved to the trash", body = serde_json::Value))
+)]
+pub async fn delete_document(
     State(state): State<AppState>,
     Extension(auth_user): Extension<AuthUser>,
     Path(id): Path<Uuid>,
     headers: axum::http::HeaderMap,
-    Json(req): Json<AddFieldRequest>,
-) -> ApiResult<Json<DocumentFieldRow>> {
+) -> ApiResult<Json<serde_json::Value>> {
+    auth_user.require_sender()?;
+
     let (ip_address, user_agent) = extract_client_info_from_headers(&headers);
 
+    let document = trash_document(&state, auth_user.user_id, id, &ip_address, &user_agent).await?;
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "deleted_at": document.deleted_at
+    })))
+}
+
+/// Moves one of the user's documents to the trash.
+async fn trash_document(
+    state: &AppState,
+    user_id: Uuid,
+    id: Uuid,
+    ip_address: &str,
+    user_agent: &str,
+) -> ApiResult<Document> {
     let document = db::document::get_document_by_id(&state.pool, id)
         .await?
         .ok_or_else(|| ApiError::NotFound("Document not found".to_string()))?;
 
-    if document.owner_id != auth_user.user_id {
+    if document.owner_id != user_id {
         return Err(ApiError::Forbidden);
     }
 
-    if document.status != DocumentStatus::Draft {
+    if document.status == DocumentStatus::Completed && !state.config.soft_delete_completed_documents
+    {
         return Err(ApiError::BadRequest(
-            "Cannot modify non-draft documents".to_string(),
+            "Cannot delete completed documents".to_string(),
         ));
     }
 
-    let field = db::document::add_field(&state.pool, id, &req).await?;
+    let mut tx = state.pool.begin().await?;
 
-    audit::log_action(
-        &state.pool,
+    let document = db::document::soft_delete_document(&mut *tx, id).await?;
+
+    audit::log_action_in(
+        &mut tx,
+        state.hash_algorithm,
         id,
         None,
-        Some(auth_user.user_id),
-        AuditAction::FieldAdded,
-        Some(&ip_address),
-        Some(&user_agent),
-        Some(serde_json::json!({
-            "field_id": field.id,
-            "field_type": format!("{:?}", req.field_type),
-            "page": req.page
-        })),
+        Some(user_id),
+        AuditAction::DocumentDeleted,
+        Some(ip_address),
+        Some(user_agent),
+        Some(serde_json::json!({ "status": document.status })),
     )
     .await?;
 
-    Ok(Json(field))
+    tx.commit().await?;
+
+    Ok(document)
 }
 
-pub async fn update_field(
+#[utoipa::path(
+    post,
+    path = "/documents/{id}/restore",
+    tag = "documents",
+    params(("id" = Uuid, Path, description = "Document id")),
+    responses(
+        (status = 200, description = "The restored document", body = Document),
+        (status = 413, description = "The document does not fit in the storage quota", body = ErrorResponse),
+    )
+)]
+pub async fn restore_document(
     State(state): State<AppState>,
     Extension(auth_user): Extension<AuthUser>,
-    Path((doc_id, field_id)): Path<(Uuid, Uuid)>,
+    Path(id): Path<Uuid>,
     headers: axum::http::HeaderMap,
-    Json(req): Json<UpdateFieldRequest>,
-) -> ApiResult<Json<DocumentFieldRow>> {
+) -> ApiResult<Json<Document>> {
+    auth_user.require_sender()?;
+
     let (ip_address, user_agent) = extract_client_info_from_headers(&headers);
 
-    let document = db::document::get_document_by_id(&state.pool, doc_id)
+    let document = db::document::get_deleted_document_by_id(&state.pool, id)
         .await?
-        .ok_or_else(|| ApiError::NotFound("Document not found".to_string()))?;
+        .ok_or_else(|| ApiError::NotFound("Document not found in trash".to_string()))?;
 
     if document.owner_id != auth_user.user_id {
         return Err(ApiError::Forbidden);
     }
 
-    if document.status != DocumentStatus::Draft {
-        return Err(ApiError::BadRequest(
-            "Cannot modify non-draft documents".to_string(),
-        ));
-    }
-
-    let field = db::document::get_field_by_id(&state.pool, field_id)
-        .await?
-        .ok_or_else(|| ApiError::NotFound("Field not found".to_string()))?;
-
-    if field.document_id != doc_id {
-        return Err(ApiError::NotFound("Field not found".to_string()));
-    }
+    let size = db::document::get_stored_size(&state.pool, id).await?;
+    check_storage_quota(&state, auth_user.user_id, size).await?;
 
-    let updated = db::document::update_field(&state.pool, field_id, &req).await?;
+    let document = db::document::restore_document(&state.pool, id).await?;
 
     audit::log_action(
         &state.pool,
-        doc_id,
+        state.hash_algorithm,
+        id,
         None,
         Some(auth_user.user_id),
-        AuditAction::FieldUpdated,
+        AuditAction::DocumentRestored,
         Some(&ip_address),
         Some(&user_agent),
-        Some(serde_json::json!({
-            "field_id": field_id
-        })),
+        None,
     )
     .await?;
 
-    Ok(Json(updated))
+    info!("Document restored from trash: {}", id);
+
+    Ok(Json(document))
 }
 
-pub async fn delete_field(
+/// Documents under legal hold are never purged by the retention policy.
+#[utoipa::path(
+    put,
+    path = "/documents/{id}/legal-hold",
+    tag = "documents",
+    params(("id" = Uuid, Path, description = "Document id")),
+    request_body = SetLegalHoldRequest,
+    responses((status = 200, description = "The document", body = Document))
+)]
+pub async fn set_legal_hold(
     State(state): State<AppState>,
     Extension(auth_user): Extension<AuthUser>,
-    Path((doc_id, field_id)): Path<(Uuid, Uuid)>,
+    Path(id): Path<Uuid>,
     headers: axum::http::HeaderMap,
-) -> ApiResult<Json<serde_json::Value>> {
+    Json(req): Json<SetLegalHoldRequest>,
+) -> ApiResult<Json<Document>> {
+    auth_user.require_sender()?;
+
     let (ip_address, user_agent) = extract_client_info_from_headers(&headers);
 
-    let document = db::document::get_document_by_id(&state.pool, doc_id)
+    let document = db::document::get_document_by_id(&state.pool, id)
         .await?
         .ok_or_else(|| ApiError::NotFound("Document not found".to_string()))?;
 
@@ -348,46 +880,71 @@ pub async fn delete_field(
         return Err(ApiError::Forbidden);
     }
 
-    if document.status != DocumentStatus::Draft {
-        return Err(ApiError::BadRequest(
-            "Cannot modify non-draft documents".to_string(),
-        ));
+    if document.legal_hold == req.legal_hold {
+        return Ok(Json(document));
     }
 
-    let field = db::document::get_field_by_id(&state.pool, field_id)
-        .await?
-        .ok_or_else(|| ApiError::NotFound("Field not found".to_string()))?;
-
-    if field.document_id != doc_id {
-        return Err(ApiError::NotFound("Field not found".to_string()));
-    }
-
-    db::document::delete_field(&state.pool, field_id).await?;
+    let document = db::document::set_legal_hold(&state.pool, id, req.legal_hold).await?;
 
     audit::log_action(
         &state.pool,
-        doc_id,
+        state.hash_algorithm,
+        id,
         None,
         Some(auth_user.user_id),
-        AuditAction::FieldDeleted,
+        AuditAction::LegalHoldChanged,
         Some(&ip_address),
         Some(&user_agent),
-        Some(serde_json::json!({
-            "field_id": field_id
-        })),
+        Some(serde_json::json!({ "legal_hold": req.legal_hold })),
     )
     .await?;
 
-    Ok(Json(serde_json::json!({ "success": true })))
+    Ok(Json(document))
 }
 
-pub async fn add_signer(
+#[utoipa::path(
+    get,
+    path = "/documents/{id}/shares",
+    tag = "shares",
+    params(("id" = Uuid, Path, description = "Document id")),
+    responses((status = 200, description = "Users the document is shared with", body = Vec<DocumentShare>))
+)]
+pub async fn list_shares(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(id): Path<Uuid>,
+) -> ApiResult<Json<Vec<DocumentShare>>> {
+    let document = db::document::get_document_by_id(&state.pool, id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Document not found".to_string()))?;
+
+    if document.owner_id != auth_user.user_id {
+        return Err(ApiError::Forbidden);
+    }
+
+    let shares = db::share::get_shares_by_document(&state.pool, id).await?;
+
+    Ok(Json(shares))
+}
+
+/// Shares a document with another user, or changes the role they were given.
+#[utoipa::path(
+    post,
+    path = "/documents/{id}/shares",
+    tag = "shares",
+    params(("id" = Uuid, Path, description = "Document id")),
+    request_body = ShareDocumentRequest,
+    responses((status = 200, description = "The share", body = DocumentShare))
+)]
+pub async fn share_document(
     State(state): State<AppState>,
     Extension(auth_user): Extension<AuthUser>,
     Path(id): Path<Uuid>,
     headers: axum::http::HeaderMap,
-    Json(req): Json<AddSignerRequest>,
-) -> ApiResult<Json<Signer>> {
+    Json(req): Json<ShareDocumentRequest>,
+) -> ApiResult<Json<DocumentShare>> {
+    auth_user.require_sender()?;
+
     let (ip_address, user_agent) = extract_client_info_from_headers(&headers);
 
     req.validate()
@@ -401,63 +958,63 @@ pub async fn add_signer(
         return Err(ApiError::Forbidden);
     }
 
-    if document.status != DocumentStatus::Draft {
-        return Err(ApiError::BadRequest(
-            "Cannot modify non-draft documents".to_string(),
-        ));
-    }
+    let user = db::user::get_user_by_email(&state.pool, req.email.trim())
+        .await?
+        .ok_or_else(|| ApiError::NotFound("No user with that email".to_string()))?;
 
-    if document.self_sign_only {
+    if user.id == document.owner_id {
         return Err(ApiError::BadRequest(
-            "Cannot add signers to self-sign documents".to_string(),
+            "Documents cannot be shared with their owner".to_string(),
         ));
     }
 
-    let existing_signers = db::signer::get_signers_by_document(&state.pool, id).await?;
-    let order_index = req.order_index.unwrap_or(existing_signers.len() as i32);
-
-    let access_token = crypto::generate_access_token();
-
-    let signer = db::signer::create_signer(
-        &state.pool,
-        id,
-        &req.email,
-        &req.name,
-        order_index,
-        &access_token,
-    )
-    .await?;
+    let mut tx = state.pool.begin().await?;
 
-    db::document::update_total_signers(&state.pool, id, (existing_signers.len() + 1) as i32)
-        .await?;
+    let share = db::share::upsert_share(&mut *tx, id, user.id, req.role).await?;
 
-    audit::log_action(
-        &state.pool,
+    audit::log_action_in(
+        &mut tx,
+        state.hash_algorithm,
         id,
-        Some(signer.id),
+        None,
         Some(auth_user.user_id),
-        AuditAction::SignerAdded,
+        AuditAction::DocumentShared,
         Some(&ip_address),
         Some(&user_agent),
         Some(serde_json::json!({
-            "signer_email": req.email,
-            "signer_name": req.name
+            "user_id": user.id,
+            "email": user.email,
+            "role": share.role
         })),
     )
     .await?;
 
-    Ok(Json(signer))
+    tx.commit().await?;
+
+    Ok(Json(share))
 }
 
-pub async fn remove_signer(
+#[utoipa::path(
+    delete,
+    path = "/documents/{id}/shares/{user_id}",
+    tag = "shares",
+    params(
+        ("id" = Uuid, Path, description = "Document id"),
+        ("user_id" = Uuid, Path, description = "Id of the user the document is shared with"),
+    ),
+    responses((status = 200, description = "The share was removed", body = serde_json::Value))
+)]
+pub async fn unshare_document(
     State(state): State<AppState>,
     Extension(auth_user): Extension<AuthUser>,
-    Path((doc_id, signer_id)): Path<(Uuid, Uuid)>,
+    Path((id, user_id)): Path<(Uuid, Uuid)>,
     headers: axum::http::HeaderMap,
 ) -> ApiResult<Json<serde_json::Value>> {
+    auth_user.require_sender()?;
+
     let (ip_address, user_agent) = extract_client_info_from_headers(&headers);
 
-    let document = db::document::get_document_by_id(&state.pool, doc_id)
+    let document = db::document::get_document_by_id(&state.pool, id)
         .await?
         .ok_or_else(|| ApiError::NotFound("Document not found".to_string()))?;
 
@@ -465,50 +1022,57 @@ pub async fn remove_signer(
         return Err(ApiError::Forbidden);
     }
 
-    if document.status != DocumentStatus::Draft {
-        return Err(ApiError::BadRequest(
-            "Cannot modify non-draft documents".to_string(),
-        ));
-    }
+    let mut tx = state.pool.begin().await?;
 
-    let signer = db::signer::get_signer_by_id(&state.pool, signer_id)
+    let role = db::share::delete_share(&mut *tx, id, user_id)
         .await?
-        .ok_or_else(|| ApiError::NotFound("Signer not found".to_string()))?;
-
-    if signer.document_id != doc_id {
-        return Err(ApiError::NotFound("Signer not found".to_string()));
-    }
+        .ok_or_else(|| ApiError::NotFound("Share not found".to_string()))?;
 
-    audit::log_action(
-        &state.pool,
-        doc_id,
-        Some(signer_id),
+    audit::log_action_in(
+        &mut tx,
+        state.hash_algorithm,
+        id,
+        None,
         Some(auth_user.user_id),
-        AuditAction::SignerRemoved,
+        AuditAction::DocumentUnshared,
         Some(&ip_address),
         Some(&user_agent),
         Some(serde_json::json!({
-            "signer_email": signer.email
+            "user_id": user_id,
+            "role": role
         })),
     )
     .await?;
 
-    db::signer::delete_signer(&state.pool, signer_id).await?;
-
-    let remaining = db::signer::count_signers_by_document(&state.pool, doc_id).await?;
-    db::document::update_total_signers(&state.pool, doc_id, remaining as i32).await?;
+    tx.commit().await?;
 
     Ok(Json(serde_json::json!({ "success": true })))
 }
 
-pub async fn send_document(
+/// Creates a read-only link to a completed document's PDF and certificate,
+/// for people without an account.
+#[utoipa::path(
+    post,
+    path = "/documents/{id}/share-links",
+    tag = "shares",
+    params(("id" = Uuid, Path, description = "Document id")),
+    request_body = CreateShareLinkRequest,
+    responses((status = 200, description = "The link, with its token", body = CreatedShareLink))
+)]
+pub async fn create_share_link(
     State(state): State<AppState>,
     Extension(auth_user): Extension<AuthUser>,
     Path(id): Path<Uuid>,
     headers: axum::http::HeaderMap,
-) -> ApiResult<Json<Document>> {
+    Json(req): Json<CreateShareLinkRequest>,
+) -> ApiResult<Json<CreatedShareLink>> {
+    auth_user.require_sender()?;
+
     let (ip_address, user_agent) = extract_client_info_from_headers(&headers);
 
+    req.validate()
+        .map_err(|e| ApiError::Validation(e.to_string()))?;
+
     let document = db::document::get_document_by_id(&state.pool, id)
         .await?
         .ok_or_else(|| ApiError::NotFound("Document not found".to_string()))?;
@@ -517,89 +1081,107 @@ pub async fn send_document(
         return Err(ApiError::Forbidden);
     }
 
-    if document.status != DocumentStatus::Draft {
-        return Err(ApiError::BadRequest(
-            "Document already sent or completed".to_string(),
-        ));
-    }
-
-    let signers = db::signer::get_signers_by_document(&state.pool, id).await?;
-
-    if signers.is_empty() && !document.self_sign_only {
+    if document.status != DocumentStatus::Completed {
         return Err(ApiError::BadRequest(
-            "Add at least one signer before sending".to_string(),
+            "Share links are only available for completed documents".to_string(),
         ));
     }
 
-    let owner = db::user::get_user_by_id(&state.pool, auth_user.user_id)
-        .await?
-        .ok_or_else(|| ApiError::NotFound("Owner not found".to_string()))?;
-
-    if let Some(email_service) = &state.email_service {
-        for signer in &signers {
-            email_service
-                .send_signing_request(
-                    &signer.email,
-                    &signer.name,
-                    &document.title,
-                    &owner.name,
-                    &signer.access_token,
-                )
-                .await
-                .map_err(|e| ApiError::Internal(anyhow::anyhow!("Failed to send email: {}", e)))?;
+    let passcode_hash = req
+        .passcode
+        .as_deref()
+        .map(|passcode| bcrypt::hash(passcode, state.config.bcrypt_cost))
+        .transpose()
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!("Failed to hash passcode: {}", e)))?;
 
-            db::signer::mark_email_sent(&state.pool, signer.id).await?;
+    let hours = req
+        .expires_in_hours
+        .unwrap_or(state.config.share_link_expiry_hours)
+        .clamp(1, MAX_SHARE_LINK_HOURS);
+    let expires_at = chrono::Utc::now() + chrono::Duration::hours(hours);
+    let token = crypto::generate_access_token();
 
-            audit::log_action(
-                &state.pool,
-                id,
-                Some(signer.id),
-                Some(auth_user.user_id),
-                AuditAction::SignerEmailSent,
-                Some(&ip_address),
-                Some(&user_agent),
-                Some(serde_json::json!({
-                    "signer_email": signer.email
-                })),
-            )
-            .await?;
-        }
-    } else {
-        info!("Email service not configured. Signers would need manual access tokens.");
-        for signer in &signers {
-            info!(
-                "Signing link for {}: {}/sign/{}",
-                signer.email, state.config.public_url, signer.access_token
-            );
-        }
-    }
+    let mut tx = state.pool.begin().await?;
 
-    let updated =
-        db::document::update_document_status(&state.pool, id, DocumentStatus::Pending).await?;
+    let link = db::share_link::create_share_link(
+        &mut *tx,
+        id,
+        &crypto::hash_token(&token),
+        passcode_hash.as_deref(),
+        expires_at,
+        auth_user.user_id,
+    )
+    .await?;
 
-    audit::log_action(
-        &state.pool,
+    audit::log_action_in(
+        &mut tx,
+        state.hash_algorithm,
         id,
         None,
         Some(auth_user.user_id),
-        AuditAction::DocumentSent,
+        AuditAction::ShareLinkCreated,
         Some(&ip_address),
         Some(&user_agent),
         Some(serde_json::json!({
-            "signer_count": signers.len()
+            "share_link_id": link.id,
+            "expires_at": link.expires_at,
+            "has_passcode": link.has_passcode
         })),
     )
     .await?;
 
-    Ok(Json(updated))
+    tx.commit().await?;
+
+    Ok(Json(CreatedShareLink {
+        url: format!("{}/shared/{}", state.config.public_url, token),
+        link,
+        token,
+    }))
 }
 
-pub async fn void_document(
+#[utoipa::path(
+    get,
+    path = "/documents/{id}/share-links",
+    tag = "shares",
+    params(("id" = Uuid, Path, description = "Document id")),
+    responses((status = 200, description = "The document's share links, newest first, revoked and expired ones included", body = Vec<ShareLink>))
+)]
+pub async fn list_share_links(
     State(state): State<AppState>,
     Extension(auth_user): Extension<AuthUser>,
     Path(id): Path<Uuid>,
+) -> ApiResult<Json<Vec<ShareLink>>> {
+    let document = db::document::get_document_by_id(&state.pool, id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Document not found".to_string()))?;
+
+    if document.owner_id != auth_user.user_id {
+        return Err(ApiError::Forbidden);
+    }
+
+    let links = db::share_link::get_share_links_by_document(&state.pool, id).await?;
+
+    Ok(Json(links))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/documents/{id}/share-links/{link_id}",
+    tag = "shares",
+    params(
+        ("id" = Uuid, Path, description = "Document id"),
+        ("link_id" = Uuid, Path, description = "Share link id"),
+    ),
+    responses((status = 200, description = "The revoked link", body = ShareLink))
+)]
+pub async fn revoke_share_link(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path((id, link_id)): Path<(Uuid, Uuid)>,
     headers: axum::http::HeaderMap,
-) -> ApiResult<Json<Document>> {
+) -> ApiResult<Json<ShareLink>> {
+    auth_user.require_sender()?;
+
     let (ip_address, user_agent) = extract_client_info_from_headers(&headers);
 
     let document = db::document::get_document_by_id(&state.pool, id)
@@ -610,36 +1192,64 @@ pub async fn void_document(
         return Err(ApiError::Forbidden);
     }
 
-    if document.status == DocumentStatus::Completed {
-        return Err(ApiError::BadRequest(
-            "Cannot void completed documents".to_string(),
-        ));
-    }
+    let mut tx = state.pool.begin().await?;
 
-    let updated =
-        db::document::update_document_status(&state.pool, id, DocumentStatus::Voided).await?;
+    let link = db::share_link::revoke_share_link(&mut *tx, id, link_id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Active share link not found".to_string()))?;
 
-    audit::log_action(
-        &state.pool,
+    audit::log_action_in(
+        &mut tx,
+        state.hash_algorithm,
         id,
         None,
         Some(auth_user.user_id),
-        AuditAction::DocumentVoided,
+        AuditAction::ShareLinkRevoked,
         Some(&ip_address),
         Some(&user_agent),
-        None,
+        Some(serde_json::json!({ "share_link_id": link.id })),
     )
     .await?;
 
-    Ok(Json(updated))
+    tx.commit().await?;
+
+    Ok(Json(link))
 }
 
-pub async fn get_audit_logs(
+/// Tags one of the user's documents. Tags it already has, in any case, are
+/// kept as they are.
+#[utoipa::path(
+    post,
+    path = "/documents/{id}/tags",
+    tag = "tags",
+    params(("id" = Uuid, Path, description = "Document id")),
+    request_body = AddTagsRequest,
+    responses((status = 200, description = "The document's tags", body = [String]))
+)]
+pub async fn add_document_tags(
     State(state): State<AppState>,
     Extension(auth_user): Extension<AuthUser>,
     Path(id): Path<Uuid>,
-) -> ApiResult<Json<Vec<crate::models::audit::AuditLog>>> {
-    let document = db::document::get_document_by_id(&state.pool, id)
+    Json(req): Json<AddTagsRequest>,
+) -> ApiResult<Json<Vec<String>>> {
+    auth_user.require_sender()?;
+
+    req.validate()
+        .map_err(|e| ApiError::Validation(e.to_string()))?;
+
+    let mut seen = HashSet::new();
+    let mut tags = Vec::with_capacity(req.tags.len());
+    for tag in &req.tags {
+        let tag = normalize_tag(tag).map_err(ApiError::Validation)?;
+        if seen.insert(tag.to_lowercase()) {
+            tags.push(tag);
+        }
+    }
+
+    let mut tx = state.pool.begin().await?;
+
+    // Locked so concurrent requests cannot together go over the limit.
+    let document = db::document::lock_document(&mut tx, id)
         .await?
         .ok_or_else(|| ApiError::NotFound("Document not found".to_string()))?;
 
@@ -647,16 +1257,45 @@ pub async fn get_audit_logs(
         return Err(ApiError::Forbidden);
     }
 
-    let logs = db::audit::get_audit_logs_by_document(&state.pool, id).await?;
+    let existing: HashSet<String> = db::tag::get_tags(&mut *tx, id)
+        .await?
+        .iter()
+        .map(|tag| tag.to_lowercase())
+        .collect();
+    let added = seen.difference(&existing).count();
+    if existing.len() + added > MAX_TAGS_PER_DOCUMENT {
+        return Err(ApiError::BadRequest(format!(
+            "Documents can have at most {} tags",
+            MAX_TAGS_PER_DOCUMENT
+        )));
+    }
+
+    db::tag::add_tags(&mut *tx, id, &tags).await?;
+    let tags = db::tag::get_tags(&mut *tx, id).await?;
 
-    Ok(Json(logs))
+    tx.commit().await?;
+
+    Ok(Json(tags))
 }
 
-pub async fn get_certificate(
+/// Removes a tag from one of the user's documents, whatever its case.
+#[utoipa::path(
+    delete,
+    path = "/documents/{id}/tags/{name}",
+    tag = "tags",
+    params(
+        ("id" = Uuid, Path, description = "Document id"),
+        ("name" = String, Path, description = "The tag, in any case"),
+    ),
+    responses((status = 200, description = "The document's remaining tags", body = [String]))
+)]
+pub async fn remove_document_tag(
     State(state): State<AppState>,
     Extension(auth_user): Extension<AuthUser>,
-    Path(id): Path<Uuid>,
-) -> ApiResult<Json<crate::models::audit::Certificate>> {
+    Path((id, name)): Path<(Uuid, String)>,
+) -> ApiResult<Json<Vec<String>>> {
+    auth_user.require_sender()?;
+
     let document = db::document::get_document_by_id(&state.pool, id)
         .await?
         .ok_or_else(|| ApiError::NotFound("Document not found".to_string()))?;
@@ -665,25 +1304,31 @@ pub async fn get_certificate(
         return Err(ApiError::Forbidden);
     }
 
-    if document.status != DocumentStatus::Completed {
-        return Err(ApiError::BadRequest(
-            "Certificate only available for completed documents".to_string(),
-        ));
+    if !db::tag::remove_tag(&state.pool, id, name.trim()).await? {
+        return Err(ApiError::NotFound("Tag not found".to_string()));
     }
 
-    let certificate = audit::generate_certificate(&state.pool, id).await?;
+    let tags = db::tag::get_tags(&state.pool, id).await?;
 
-    Ok(Json(certificate))
+    Ok(Json(tags))
 }
 
-pub async fn download_document(
+#[utoipa::path(
+    post,
+    path = "/documents/{id}/fields",
+    tag = "fields",
+    params(("id" = Uuid, Path, description = "Document id")),
+    request_body = AddFieldRequest,
+    responses((status = 200, description = "The added field", body = DocumentFieldRow))
+)]
+pub async fn add_field(
     State(state): State<AppState>,
     Extension(auth_user): Extension<AuthUser>,
     Path(id): Path<Uuid>,
     headers: axum::http::HeaderMap,
-) -> ApiResult<axum::response::Response> {
-    use axum::body::Body;
-    use axum::http::{header, Response};
+    Json(mut req): Json<AddFieldRequest>,
+) -> ApiResult<Json<DocumentFieldRow>> {
+    auth_user.require_sender()?;
 
     let (ip_address, user_agent) = extract_client_info_from_headers(&headers);
 
@@ -691,34 +1336,3604 @@ pub async fn download_document(
         .await?
         .ok_or_else(|| ApiError::NotFound("Document not found".to_string()))?;
 
-    if document.owner_id != auth_user.user_id {
-        return Err(ApiError::Forbidden);
+    access::require(
+        &state.pool,
+        &document,
+        auth_user.user_id,
+        DocumentAccess::Editor,
+    )
+    .await?;
+
+    if document.status != DocumentStatus::Draft {
+        return Err(ApiError::BadRequest(
+            "Cannot modify non-draft documents".to_string(),
+        ));
     }
 
-    let file_data = fs::read(&document.file_path)
-        .await
-        .map_err(|e| ApiError::Internal(anyhow::anyhow!("Failed to read file: {}", e)))?;
+    if let Some(signer_id) = req.signer_id {
+        let signer = db::signer::get_signer_by_id(&state.pool, signer_id)
+            .await?
+            .filter(|s| s.document_id == id)
+            .ok_or_else(|| ApiError::NotFound("Signer not found".to_string()))?;
+
+        if signer.role == SignerRole::Approver {
+            return Err(ApiError::BadRequest(
+                "Fields cannot be assigned to approvers".to_string(),
+            ));
+        }
+    }
+
+    let pages = load_pages(&state, &document).await?;
+    let geometry = field_geometry(
+        &pages,
+        req.page,
+        FieldRect {
+            x: req.x,
+            y: req.y,
+            width: req.width,
+            height: req.height,
+        },
+        req.coordinate_space,
+    )
+    .map_err(ApiError::BadRequest)?;
+
+    match (req.condition_field_id, req.condition_value.as_deref()) {
+        (None, None) => {}
+        (Some(source_id), Some(value)) => {
+            check_condition(&state, id, None, req.signer_id, source_id, value).await?
+        }
+        _ => {
+            return Err(ApiError::BadRequest(
+                "condition_field_id and condition_value must be given together".to_string(),
+            ))
+        }
+    }
+
+    let constraints = NumberConstraints {
+        min_value: req.min_value,
+        max_value: req.max_value,
+        decimal_places: req.decimal_places,
+    };
+    if let Some(value) = check_number_field(req.field_type, &constraints, req.value.as_deref())? {
+        req.value = Some(value);
+    }
+
+    check_read_only(req.field_type, req.read_only, req.value.as_deref())?;
+
+    req.group_key = req.group_key.map(|key| key.trim().to_string());
+    check_radio_group(
+        &state,
+        id,
+        None,
+        req.field_type,
+        req.signer_id,
+        req.group_key.as_deref(),
+    )
+    .await?;
+
+    let field = db::document::add_field(&state.pool, id, &req, &geometry).await?;
 
     audit::log_action(
         &state.pool,
+        state.hash_algorithm,
         id,
         None,
         Some(auth_user.user_id),
-        AuditAction::DocumentDownloaded,
+        AuditAction::FieldAdded,
         Some(&ip_address),
         Some(&user_agent),
-        None,
+        Some(serde_json::json!({
+            "field_id": field.id,
+            "field_type": format!("{:?}", req.field_type),
+            "page": req.page
+        })),
     )
     .await?;
 
-    let response = Response::builder()
-        .header(header::CONTENT_TYPE, "application/pdf")
-        .header(
-            header::CONTENT_DISPOSITION,
-            format!("attachment; filename=\"{}\"", document.original_filename),
-        )
-        .body(Body::from(file_data))
-        .map_err(|e| ApiError::Internal(anyhow::anyhow!("Failed to build response: {}", e)))?;
+    Ok(Json(field))
+}
+
+#[utoipa::path(
+    post,
+    path = "/documents/{id}/fields/import-acroform",
+    tag = "fields",
+    params(("id" = Uuid, Path, description = "Document id")),
+    responses((status = 200, description = "Imported and skipped form fields", body = AcroFormImportResponse))
+)]
+pub async fn import_acroform_fields(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(id): Path<Uuid>,
+    headers: axum::http::HeaderMap,
+) -> ApiResult<Json<AcroFormImportResponse>> {
+    auth_user.require_sender()?;
+
+    let (ip_address, user_agent) = extract_client_info_from_headers(&headers);
+
+    let document = db::document::get_document_by_id(&state.pool, id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Document not found".to_string()))?;
+
+    access::require(
+        &state.pool,
+        &document,
+        auth_user.user_id,
+        DocumentAccess::Editor,
+    )
+    .await?;
+
+    if document.status != DocumentStatus::Draft {
+        return Err(ApiError::BadRequest(
+            "Cannot modify non-draft documents".to_string(),
+        ));
+    }
+
+    let data = state
+        .storage
+        .get_file(&document.file_path, document.original_encryption().as_ref())
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!("Failed to read file: {}", e)))?;
+
+    let form = state
+        .pdf_workers
+        .acroform(Bytes::from(data))
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!("Failed to read form fields: {}", e)))?;
+
+    let pages = load_pages(&state, &document).await?;
+    let mut imported = Vec::new();
+    let mut already_imported = Vec::new();
+    let mut skipped = form.skipped;
+
+    for widget in form.widgets {
+        let rect = FieldRect {
+            x: widget.x,
+            y: widget.y,
+            width: widget.width,
+            height: widget.height,
+        };
+        let geometry =
+            match field_geometry(&pages, widget.page as i32, rect, CoordinateSpace::Points) {
+                Ok(geometry) => geometry,
+                Err(reason) => {
+                    skipped.push(SkippedFormField {
+                        name: widget.name,
+                        reason,
+                    });
+                    continue;
+                }
+            };
+
+        let req = AddFieldRequest {
+            field_type: widget.field_type,
+            page: widget.page as i32,
+            x: widget.x,
+            y: widget.y,
+            width: widget.width,
+            height: widget.height,
+            signer_id: None,
+            value: None,
+            font_size: None,
+            font_family: None,
+            date_format: None,
+            coordinate_space: CoordinateSpace::Points,
+            required: false,
+            condition_field_id: None,
+            condition_value: None,
+            label: Some(widget.name.clone()),
+            min_value: None,
+            max_value: None,
+            decimal_places: None,
+            read_only: false,
+            group_key: None,
+        };
+
+        match db::document::add_imported_field(&state.pool, id, &widget.name, &req, &geometry)
+            .await?
+        {
+            Some(field) => imported.push(field),
+            None => already_imported.push(widget.name),
+        }
+    }
+
+    if !imported.is_empty() {
+        audit::log_action(
+            &state.pool,
+            state.hash_algorithm,
+            id,
+            None,
+            Some(auth_user.user_id),
+            AuditAction::FormFieldsImported,
+            Some(&ip_address),
+            Some(&user_agent),
+            Some(serde_json::json!({
+                "field_ids": imported.iter().map(|f| f.id).collect::<Vec<_>>(),
+                "skipped": skipped.len()
+            })),
+        )
+        .await?;
+    }
+
+    Ok(Json(AcroFormImportResponse {
+        imported,
+        already_imported,
+        skipped,
+    }))
+}
+
+#[utoipa::path(
+    put,
+    path = "/documents/{id}/fields/{field_id}",
+    tag = "fields",
+    params(
+        ("id" = Uuid, Path, description = "Document id"),
+        ("field_id" = Uuid, Path, description = "Field id"),
+    ),
+    request_body = UpdateFieldRequest,
+    responses((status = 200, description = "The updated field", body = DocumentFieldRow))
+)]
+pub async fn update_field(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path((doc_id, field_id)): Path<(Uuid, Uuid)>,
+    headers: axum::http::HeaderMap,
+    Json(mut req): Json<UpdateFieldRequest>,
+) -> ApiResult<Json<DocumentFieldRow>> {
+    auth_user.require_sender()?;
+
+    let (ip_address, user_agent) = extract_client_info_from_headers(&headers);
+    let expected_updated_at = precondition::expected_updated_at(&headers, req.expected_updated_at)?;
+
+    let document = db::document::get_document_by_id(&state.pool, doc_id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Document not found".to_string()))?;
+
+    access::require(
+        &state.pool,
+        &document,
+        auth_user.user_id,
+        DocumentAccess::Editor,
+    )
+    .await?;
+
+    if document.status != DocumentStatus::Draft {
+        return Err(ApiError::BadRequest(
+            "Cannot modify non-draft documents".to_string(),
+        ));
+    }
+
+    let field = db::document::get_field_by_id(&state.pool, field_id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Field not found".to_string()))?;
+
+    if field.document_id != doc_id {
+        return Err(ApiError::NotFound("Field not found".to_string()));
+    }
+
+    let moved = req.x.is_some() || req.y.is_some() || req.width.is_some() || req.height.is_some();
+    let geometry = if moved {
+        let pages = load_pages(&state, &document).await?;
+        let page = pages
+            .iter()
+            .find(|p| p.number == field.page)
+            .ok_or_else(|| ApiError::BadRequest(format!("Page {} does not exist", field.page)))?;
+
+        let current = field.rect(req.coordinate_space, page);
+        let rect = FieldRect {
+            x: req.x.unwrap_or(current.x),
+            y: req.y.unwrap_or(current.y),
+            width: req.width.unwrap_or(current.width),
+            height: req.height.unwrap_or(current.height),
+        };
+        Some(FieldGeometry::new(rect, req.coordinate_space, page).map_err(ApiError::BadRequest)?)
+    } else {
+        None
+    };
+
+    match (
+        req.condition_field_id.or(field.condition_field_id),
+        req.condition_value.as_deref(),
+    ) {
+        (_, Some("")) => {}
+        (Some(source_id), Some(value)) => {
+            check_condition(
+                &state,
+                doc_id,
+                Some(field.id),
+                field.signer_id,
+                source_id,
+                value,
+            )
+            .await?
+        }
+        (None, Some(_)) => {
+            return Err(ApiError::BadRequest(
+                "condition_field_id is required to set a condition".to_string(),
+            ))
+        }
+        (_, None) if req.condition_field_id.is_some() => {
+            return Err(ApiError::BadRequest(
+                "condition_value is required to set a condition".to_string(),
+            ))
+        }
+        (_, None) => {}
+    }
+
+    let constraints = NumberConstraints {
+        min_value: req.min_value.or(field.min_value),
+        max_value: req.max_value.or(field.max_value),
+        decimal_places: req.decimal_places.or(field.decimal_places),
+    };
+    let constrained =
+        req.min_value.is_some() || req.max_value.is_some() || req.decimal_places.is_some();
+    let value = req.value.as_deref().or(field.value.as_deref());
+    if constrained || req.value.is_some() {
+        if let Some(value) = check_number_field(field.field_type, &constraints, value)? {
+            req.value = Some(value);
+        }
+    }
+
+    check_read_only(
+        field.field_type,
+        req.read_only.unwrap_or(field.read_only),
+        req.value.as_deref().or(field.value.as_deref()),
+    )?;
+
+    if let Some(key) = req.group_key.as_mut() {
+        *key = key.trim().to_string();
+        check_radio_group(
+            &state,
+            doc_id,
+            Some(field.id),
+            field.field_type,
+            field.signer_id,
+            Some(key),
+        )
+        .await?;
+    }
+
+    let Some(updated) = db::document::update_field(
+        &state.pool,
+        field_id,
+        &req,
+        geometry.as_ref(),
+        expected_updated_at,
+    )
+    .await?
+    else {
+        let current = db::document::get_field_by_id(&state.pool, field_id)
+            .await?
+            .ok_or_else(|| ApiError::NotFound("Field not found".to_string()))?;
+        return Err(ApiError::EditConflict(serde_json::json!(current)));
+    };
+
+    audit::log_action(
+        &state.pool,
+        state.hash_algorithm,
+        doc_id,
+        None,
+        Some(auth_user.user_id),
+        AuditAction::FieldUpdated,
+        Some(&ip_address),
+        Some(&user_agent),
+        Some(serde_json::json!({
+            "field_id": field_id,
+            "changed": audit::changes(&field, &updated, &["updated_at"])
+        })),
+    )
+    .await?;
+
+    Ok(Json(updated))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/documents/{id}/fields/{field_id}",
+    tag = "fields",
+    params(
+        ("id" = Uuid, Path, description = "Document id"),
+        ("field_id" = Uuid, Path, description = "Field id"),
+    ),
+    responses((status = 200, description = "The field was deleted", body = serde_json::Value))
+)]
+pub async fn delete_field(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path((doc_id, field_id)): Path<(Uuid, Uuid)>,
+    headers: axum::http::HeaderMap,
+) -> ApiResult<Json<serde_json::Value>> {
+    auth_user.require_sender()?;
+
+    let (ip_address, user_agent) = extract_client_info_from_headers(&headers);
+
+    let document = db::document::get_document_by_id(&state.pool, doc_id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Document not found".to_string()))?;
+
+    access::require(
+        &state.pool,
+        &document,
+        auth_user.user_id,
+        DocumentAccess::Editor,
+    )
+    .await?;
+
+    if document.status != DocumentStatus::Draft {
+        return Err(ApiError::BadRequest(
+            "Cannot modify non-draft documents".to_string(),
+        ));
+    }
+
+    let field = db::document::get_field_by_id(&state.pool, field_id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Field not found".to_string()))?;
+
+    if field.document_id != doc_id {
+        return Err(ApiError::NotFound("Field not found".to_string()));
+    }
+
+    db::document::delete_field(&state.pool, field_id).await?;
+
+    audit::log_action(
+        &state.pool,
+        state.hash_algorithm,
+        doc_id,
+        None,
+        Some(auth_user.user_id),
+        AuditAction::FieldDeleted,
+        Some(&ip_address),
+        Some(&user_agent),
+        Some(serde_json::json!({
+            "field_id": field_id,
+            "field": field
+        })),
+    )
+    .await?;
+
+    Ok(Json(serde_json::json!({ "success": true })))
+}
+
+#[utoipa::path(
+    post,
+    path = "/documents/{id}/signers",
+    tag = "signers",
+    params(
+        ("id" = Uuid, Path, description = "Document id"),
+        ("Idempotency-Key" = Option<String>, Header, description = "Makes retries safe: a retry with the same key and payload gets the original response"),
+    ),
+    request_body = AddSignerRequest,
+    responses(
+        (status = 200, description = "The added signer", body = Signer),
+        (status = 400, description = "The document has MAX_SIGNERS_PER_DOCUMENT signers", body = ErrorResponse),
+        (status = 409, description = "The email is already a signer's, or the Idempotency-Key was used for a different request", body = ErrorResponse),
+    )
+)]
+pub async fn add_signer(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(id): Path<Uuid>,
+    headers: axum::http::HeaderMap,
+    Json(req): Json<AddSignerRequest>,
+) -> ApiResult<Json<Signer>> {
+    auth_user.require_sender()?;
+
+    let (ip_address, user_agent) = extract_client_info_from_headers(&headers);
+
+    req.validate()
+        .map_err(|e| ApiError::Validation(e.to_string()))?;
+
+    let document = db::document::get_document_by_id(&state.pool, id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Document not found".to_string()))?;
+
+    access::require(
+        &state.pool,
+        &document,
+        auth_user.user_id,
+        DocumentAccess::Editor,
+    )
+    .await?;
+
+    if document.status != DocumentStatus::Draft {
+        return Err(ApiError::BadRequest(
+            "Cannot modify non-draft documents".to_string(),
+        ));
+    }
+
+    if document.self_sign_only {
+        return Err(ApiError::BadRequest(
+            "Cannot add signers to self-sign documents".to_string(),
+        ));
+    }
+
+    let existing_signers = db::signer::get_signers_by_document(&state.pool, id).await?;
+    check_signer_limit(&state, existing_signers.len())?;
+    check_signer_email(&state, id, &req.email, None).await?;
+    let order_index = req.order_index.unwrap_or(existing_signers.len() as i32);
+
+    let access_token = crypto::generate_access_token();
+
+    let signer = match db::signer::create_signer(
+        &state.pool,
+        id,
+        &req.email,
+        &req.name,
+        order_index,
+        req.role,
+        &access_token,
+        req.locale
+            .as_deref()
+            .map(str::trim)
+            .filter(|l| !l.is_empty()),
+    )
+    .await
+    {
+        Ok(signer) => signer,
+        Err(e) => return Err(signer_email_conflict(&state, id, &req.email, e).await),
+    };
+
+    if let Some(access_code) = &req.access_code {
+        let code_hash =
+            bcrypt::hash(access_code.trim(), state.config.bcrypt_cost).map_err(|e| {
+                ApiError::Internal(anyhow::anyhow!("Failed to hash access code: {}", e))
+            })?;
+        db::signer::set_access_code(&state.pool, signer.id, &code_hash).await?;
+    }
+
+    db::document::update_total_signers(&state.pool, id, (existing_signers.len() + 1) as i32)
+        .await?;
+
+    audit::log_action(
+        &state.pool,
+        state.hash_algorithm,
+        id,
+        Some(signer.id),
+        Some(auth_user.user_id),
+        AuditAction::SignerAdded,
+        Some(&ip_address),
+        Some(&user_agent),
+        Some(serde_json::json!({
+            "signer_email": req.email,
+            "signer_name": req.name,
+            "role": req.role,
+            "locale": signer.locale,
+            "access_code_required": req.access_code.is_some()
+        })),
+    )
+    .await?;
+
+    Ok(Json(signer))
+}
+
+/// Adds a signer slot anyone with the link can take: the first visitor enters
+/// their name and email to claim it. A document has at most one.
+#[utoipa::path(
+    post,
+    path = "/documents/{id}/open-link",
+    tag = "signers",
+    params(("id" = Uuid, Path, description = "Document id")),
+    request_body(content = Option<CreateOpenLinkRequest>, description = "Optional"),
+    responses(
+        (status = 200, description = "The open slot and its link", body = OpenLinkResponse),
+        (status = 409, description = "The document already has an open link", body = ErrorResponse),
+    )
+)]
+pub async fn create_open_link(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(id): Path<Uuid>,
+    headers: axum::http::HeaderMap,
+    req: Option<Json<CreateOpenLinkRequest>>,
+) -> ApiResult<Json<OpenLinkResponse>> {
+    auth_user.require_sender()?;
+
+    let (ip_address, user_agent) = extract_client_info_from_headers(&headers);
+
+    let req = req.map(|Json(req)| req).unwrap_or_default();
+    req.validate()
+        .map_err(|e| ApiError::Validation(e.to_string()))?;
+
+    let document = db::document::get_document_by_id(&state.pool, id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Document not found".to_string()))?;
+
+    access::require(
+        &state.pool,
+        &document,
+        auth_user.user_id,
+        DocumentAccess::Editor,
+    )
+    .await?;
+
+    if document.status != DocumentStatus::Draft {
+        return Err(ApiError::BadRequest(
+            "Cannot modify non-draft documents".to_string(),
+        ));
+    }
+
+    if document.self_sign_only {
+        return Err(ApiError::BadRequest(
+            "Cannot add signers to self-sign documents".to_string(),
+        ));
+    }
+
+    if !db::signer::get_open_links_by_document(&state.pool, id)
+        .await?
+        .is_empty()
+    {
+        return Err(ApiError::Conflict(
+            "This document already has an open link".to_string(),
+        ));
+    }
+
+    let existing_signers = db::signer::get_signers_by_document(&state.pool, id).await?;
+    check_signer_limit(&state, existing_signers.len())?;
+    let order_index = req.order_index.unwrap_or(existing_signers.len() as i32);
+    let label = req.label.as_deref().map(str::trim).unwrap_or("Open signer");
+
+    let mut tx = state.pool.begin().await?;
+
+    // The email stays empty until the slot is claimed.
+    let signer = db::signer::create_signer(
+        &mut *tx,
+        id,
+        "",
+        label,
+        order_index,
+        req.role,
+        &crypto::generate_access_token(),
+        None,
+    )
+    .await?;
+
+    let link = db::signer::create_open_link(
+        &mut *tx,
+        signer.id,
+        &crypto::generate_access_token(),
+        req.single_use,
+    )
+    .await?;
+
+    db::document::update_total_signers(&mut *tx, id, (existing_signers.len() + 1) as i32).await?;
+
+    audit::log_action_in(
+        &mut tx,
+        state.hash_algorithm,
+        id,
+        Some(signer.id),
+        Some(auth_user.user_id),
+        AuditAction::OpenLinkCreated,
+        Some(&ip_address),
+        Some(&user_agent),
+        Some(serde_json::json!({
+            "signer_name": signer.name,
+            "role": signer.role,
+            "single_use": link.single_use
+        })),
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(Json(OpenLinkResponse {
+        signer,
+        signing_url: format!("{}/sign/{}", state.config.public_url, link.token),
+        token: link.token,
+        single_use: link.single_use,
+    }))
+}
+
+#[utoipa::path(
+    put,
+    path = "/documents/{id}/signers/{signer_id}",
+    tag = "signers",
+    params(
+        ("id" = Uuid, Path, description = "Document id"),
+        ("signer_id" = Uuid, Path, description = "Signer id"),
+    ),
+    request_body = UpdateSignerRequest,
+    responses((status = 200, description = "The updated signer", body = Signer))
+)]
+pub async fn update_signer(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path((doc_id, signer_id)): Path<(Uuid, Uuid)>,
+    headers: axum::http::HeaderMap,
+    Json(req): Json<UpdateSignerRequest>,
+) -> ApiResult<Json<Signer>> {
+    auth_user.require_sender()?;
+
+    let (ip_address, user_agent) = extract_client_info_from_headers(&headers);
+
+    req.validate()
+        .map_err(|e| ApiError::Validation(e.to_string()))?;
+    let expected_updated_at = precondition::expected_updated_at(&headers, req.expected_updated_at)?;
+
+    let document = db::document::get_document_by_id(&state.pool, doc_id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Document not found".to_string()))?;
+
+    let access = access::require(
+        &state.pool,
+        &document,
+        auth_user.user_id,
+        DocumentAccess::Editor,
+    )
+    .await?;
+
+    let signer = db::signer::get_signer_by_id(&state.pool, signer_id)
+        .await?
+        .filter(|s| s.document_id == doc_id)
+        .ok_or_else(|| ApiError::NotFound("Signer not found".to_string()))?;
+
+    // Editors prepare drafts; once sent, the document is the owner's to manage
+    if access == DocumentAccess::Editor && document.status != DocumentStatus::Draft {
+        return Err(ApiError::Forbidden);
+    }
+
+    let details_changed = req.name.is_some() || req.email.is_some() || req.order_index.is_some();
+
+    if details_changed && document.status != DocumentStatus::Draft {
+        return Err(ApiError::BadRequest(
+            "Cannot modify non-draft documents".to_string(),
+        ));
+    }
+
+    // Access codes and languages may still be set once the document has been
+    // sent
+    if (req.access_code.is_some() || req.locale.is_some())
+        && !matches!(
+            document.status,
+            DocumentStatus::Draft | DocumentStatus::Pending
+        )
+    {
+        return Err(ApiError::BadRequest(
+            "Cannot change the access code or language of a finished document".to_string(),
+        ));
+    }
+
+    if let Some(email) = req.email.as_deref() {
+        check_signer_email(&state, doc_id, email, Some(signer.id)).await?;
+    }
+
+    // Checked and hashed before anything is written: `None` leaves the code
+    // as it is, `Some(None)` removes it.
+    let access_code_hash = match req.access_code.as_deref().map(str::trim) {
+        None => None,
+        Some("") => Some(None),
+        Some(access_code) if !(4..=64).contains(&access_code.chars().count()) => {
+            return Err(ApiError::Validation(
+                "Access code must be between 4 and 64 characters".to_string(),
+            ))
+        }
+        Some(access_code) => {
+            let code_hash = bcrypt::hash(access_code, state.config.bcrypt_cost).map_err(|e| {
+                ApiError::Internal(anyhow::anyhow!("Failed to hash access code: {}", e))
+            })?;
+            Some(Some(code_hash))
+        }
+    };
+
+    let mut tx = state.pool.begin().await?;
+
+    let updated = match db::signer::update_signer(
+        &mut *tx,
+        signer.id,
+        req.name.as_deref(),
+        req.email.as_deref(),
+        req.order_index,
+        req.locale.as_deref().map(str::trim),
+        expected_updated_at,
+    )
+    .await
+    {
+        Ok(updated) => updated,
+        Err(e) => {
+            drop(tx);
+            let email = req.email.as_deref().unwrap_or_default();
+            return Err(signer_email_conflict(&state, doc_id, email, e).await);
+        }
+    };
+    let Some(updated) = updated else {
+        drop(tx);
+        let current = db::signer::get_signer_by_id(&state.pool, signer.id)
+            .await?
+            .ok_or_else(|| ApiError::NotFound("Signer not found".to_string()))?;
+        return Err(ApiError::EditConflict(serde_json::json!(current)));
+    };
+
+    match &access_code_hash {
+        Some(Some(code_hash)) => {
+            db::signer::set_access_code(&mut *tx, signer.id, code_hash).await?
+        }
+        Some(None) => db::signer::delete_access_code(&mut *tx, signer.id).await?,
+        None => {}
+    }
+
+    audit::log_action_in(
+        &mut tx,
+        state.hash_algorithm,
+        doc_id,
+        Some(signer.id),
+        Some(auth_user.user_id),
+        AuditAction::SignerUpdated,
+        Some(&ip_address),
+        Some(&user_agent),
+        Some(serde_json::json!({
+            "changed": audit::changes(&signer, &updated, &["updated_at", "access_token"]),
+            "access_code_changed": req.access_code.is_some()
+        })),
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(Json(updated))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/documents/{id}/signers/{signer_id}",
+    tag = "signers",
+    params(
+        ("id" = Uuid, Path, description = "Document id"),
+        ("signer_id" = Uuid, Path, description = "Signer id"),
+    ),
+    responses((status = 200, description = "The signer was removed", body = serde_json::Value))
+)]
+pub async fn remove_signer(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path((doc_id, signer_id)): Path<(Uuid, Uuid)>,
+    headers: axum::http::HeaderMap,
+) -> ApiResult<Json<serde_json::Value>> {
+    auth_user.require_sender()?;
+
+    let (ip_address, user_agent) = extract_client_info_from_headers(&headers);
+
+    let document = db::document::get_document_by_id(&state.pool, doc_id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Document not found".to_string()))?;
+
+    access::require(
+        &state.pool,
+        &document,
+        auth_user.user_id,
+        DocumentAccess::Editor,
+    )
+    .await?;
+
+    if document.status != DocumentStatus::Draft {
+        return Err(ApiError::BadRequest(
+            "Cannot modify non-draft documents".to_string(),
+        ));
+    }
+
+    let signer = db::signer::get_signer_by_id(&state.pool, signer_id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Signer not found".to_string()))?;
+
+    if signer.document_id != doc_id {
+        return Err(ApiError::NotFound("Signer not found".to_string()));
+    }
+
+    audit::log_action(
+        &state.pool,
+        state.hash_algorithm,
+        doc_id,
+        Some(signer_id),
+        Some(auth_user.user_id),
+        AuditAction::SignerRemoved,
+        Some(&ip_address),
+        Some(&user_agent),
+        Some(serde_json::json!({
+            "signer_email": signer.email
+        })),
+    )
+    .await?;
+
+    db::signer::delete_signer(&state.pool, signer_id).await?;
+
+    let remaining = db::signer::count_signers_by_document(&state.pool, doc_id).await?;
+    db::document::update_total_signers(&state.pool, doc_id, remaining as i32).await?;
+
+    Ok(Json(serde_json::json!({ "success": true })))
+}
+
+/// Emails a signer who has not finished a reminder now. Counts toward the
+/// document's `max_reminders`.
+#[utoipa::path(
+    post,
+    path = "/documents/{id}/signers/{signer_id}/remind",
+    tag = "signers",
+    params(
+        ("id" = Uuid, Path, description = "Document id"),
+        ("signer_id" = Uuid, Path, description = "Signer id"),
+    ),
+    responses((status = 200, description = "The reminded signer", body = Signer))
+)]
+pub async fn remind_signer(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path((doc_id, signer_id)): Path<(Uuid, Uuid)>,
+    headers: axum::http::HeaderMap,
+) -> ApiResult<Json<Signer>> {
+    auth_user.require_sender()?;
+
+    let (ip_address, user_agent) = extract_client_info_from_headers(&headers);
+
+    let document = db::document::get_document_by_id(&state.pool, doc_id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Document not found".to_string()))?;
+
+    if document.owner_id != auth_user.user_id {
+        return Err(ApiError::Forbidden);
+    }
+
+    if document.status != DocumentStatus::Pending {
+        return Err(ApiError::BadRequest(
+            "Reminders can only be sent for pending documents".to_string(),
+        ));
+    }
+
+    let signer = db::signer::get_signer_by_id(&state.pool, signer_id)
+        .await?
+        .filter(|s| s.document_id == doc_id)
+        .ok_or_else(|| ApiError::NotFound("Signer not found".to_string()))?;
+
+    if !matches!(signer.status, SignerStatus::Sent | SignerStatus::Viewed) {
+        return Err(ApiError::BadRequest(
+            "Only signers who were sent the document and have not responded can be reminded"
+                .to_string(),
+        ));
+    }
+
+    if db::signer::get_open_link(&state.pool, signer.id)
+        .await?
+        .is_some_and(|link| !link.emails_signer())
+    {
+        return Err(ApiError::BadRequest(
+            "This signer joined through a single-use link and cannot be emailed".to_string(),
+        ));
+    }
+
+    if !state.email_outbox.enabled() {
+        return Err(ApiError::BadRequest(
+            "Email is not configured on this server".to_string(),
+        ));
+    }
+
+    let mut tx = state.pool.begin().await?;
+
+    let reminded = db::signer::record_reminder(&mut *tx, signer.id).await?;
+
+    db::outbox::enqueue_email(
+        &mut *tx,
+        OutboxEmailKind::SigningReminder,
+        doc_id,
+        Some(signer.id),
+    )
+    .await?;
+
+    audit::log_action_in(
+        &mut tx,
+        state.hash_algorithm,
+        doc_id,
+        Some(signer.id),
+        Some(auth_user.user_id),
+        AuditAction::SignerReminded,
+        Some(&ip_address),
+        Some(&user_agent),
+        Some(serde_json::json!({
+            "signer_email": signer.email,
+            "reminder": reminded.reminders_sent,
+            "automatic": false
+        })),
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    state.email_outbox.deliver_document(doc_id).await;
+
+    Ok(Json(reminded))
+}
+
+/// Puts a signer who declined back to `sent` with a new link, for declines
+/// made by mistake. The document must still be pending.
+#[utoipa::path(
+    post,
+    path = "/documents/{id}/signers/{signer_id}/reset",
+    tag = "signers",
+    params(
+        ("id" = Uuid, Path, description = "Document id"),
+        ("signer_id" = Uuid, Path, description = "Signer id"),
+    ),
+    responses((status = 200, description = "The reset signer", body = Signer))
+)]
+pub async fn reset_signer(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path((doc_id, signer_id)): Path<(Uuid, Uuid)>,
+    headers: axum::http::HeaderMap,
+) -> ApiResult<Json<Signer>> {
+    auth_user.require_sender()?;
+
+    let (ip_address, user_agent) = extract_client_info_from_headers(&headers);
+
+    let mut tx = state.pool.begin().await?;
+
+    let document = db::document::lock_document(&mut tx, doc_id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Document not found".to_string()))?;
+
+    if document.owner_id != auth_user.user_id {
+        return Err(ApiError::Forbidden);
+    }
+
+    if document.status != DocumentStatus::Pending {
+        return Err(ApiError::BadRequest(
+            "Signers can only be reset on pending documents".to_string(),
+        ));
+    }
+
+    let signer = db::signer::get_signer_by_id(&mut *tx, signer_id)
+        .await?
+        .filter(|s| s.document_id == doc_id)
+        .ok_or_else(|| ApiError::NotFound("Signer not found".to_string()))?;
+
+    match signer.status {
+        SignerStatus::Declined => {}
+        SignerStatus::Signed | SignerStatus::Approved => {
+            return Err(ApiError::Conflict(
+                "Signer has already signed and cannot be reset".to_string(),
+            ))
+        }
+        _ => {
+            return Err(ApiError::Conflict(
+                "Only signers who declined can be reset".to_string(),
+            ))
+        }
+    }
+
+    let reset =
+        db::signer::reset_declined_signer(&mut tx, signer.id, &crypto::generate_access_token())
+            .await?
+            .ok_or_else(|| {
+                ApiError::Conflict("Only signers who declined can be reset".to_string())
+            })?;
+
+    let emails_signer = match db::signer::get_open_link(&mut *tx, reset.id).await? {
+        Some(link) => link.emails_signer(),
+        None => true,
+    };
+
+    if state.email_outbox.enabled() && emails_signer {
+        db::outbox::enqueue_email(
+            &mut *tx,
+            OutboxEmailKind::SigningRequest,
+            doc_id,
+            Some(reset.id),
+        )
+        .await?;
+    } else {
+        info!(
+            "Signing link for {}: {}/sign/{}",
+            reset.email, state.config.public_url, reset.access_token
+        );
+    }
+
+    audit::log_action_in(
+        &mut tx,
+        state.hash_algorithm,
+        doc_id,
+        Some(reset.id),
+        Some(auth_user.user_id),
+        AuditAction::SignerReset,
+        Some(&ip_address),
+        Some(&user_agent),
+        Some(serde_json::json!({
+            "signer_email": reset.email,
+            "declined_at": signer.declined_at,
+            "decline_reason": signer.decline_reason
+        })),
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    state.email_outbox.deliver_document(doc_id).await;
+
+    Ok(Json(reset))
+}
+
+/// Adds a signer to a document that was already sent, e.g. a co-signer who
+/// joined the deal. The new signer is invited at once, and the document is
+/// only completed once they have signed too, whoever signed before them.
+#[utoipa::path(
+    post,
+    path = "/documents/{id}/amend/signers",
+    tag = "signers",
+    params(("id" = Uuid, Path, description = "Document id")),
+    request_body = AddSignerRequest,
+    responses(
+        (status = 200, description = "The added signer", body = Signer),
+        (status = 400, description = "The document is not pending, has MAX_SIGNERS_PER_DOCUMENT signers, or the order_index is not after every signer who signed", body = ErrorResponse),
+        (status = 409, description = "The document is completed, or the email is already a signer's", body = ErrorResponse),
+    )
+)]
+pub async fn amend_add_signer(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(doc_id): Path<Uuid>,
+    headers: axum::http::HeaderMap,
+    Json(req): Json<AddSignerRequest>,
+) -> ApiResult<Json<Signer>> {
+    auth_user.require_sender()?;
+
+    let (ip_address, user_agent) = extract_client_info_from_headers(&headers);
+
+    req.validate()
+        .map_err(|e| ApiError::Validation(e.to_string()))?;
+
+    // The lock orders the amendment with signing submissions, so the last
+    // signature either completes the document before it or counts the new
+    // signer after it.
+    let mut tx = state.pool.begin().await?;
+
+    let document = lock_amendable_document(&mut tx, doc_id, auth_user.user_id).await?;
+
+    if document.self_sign_only {
+        return Err(ApiError::BadRequest(
+            "Cannot add signers to self-sign documents".to_string(),
+        ));
+    }
+
+    let signers = db::signer::get_signers_by_document(&mut *tx, doc_id).await?;
+    check_signer_limit(&state, signers.len())?;
+
+    if let Some(existing) =
+        db::signer::get_signer_by_document_email(&mut *tx, doc_id, &req.email).await?
+    {
+        return Err(email_taken(&existing));
+    }
+
+    // A signer cannot be slotted in before someone who already signed.
+    let last_signed = signers
+        .iter()
+        .filter(|s| matches!(s.status, SignerStatus::Signed | SignerStatus::Approved))
+        .map(|s| s.order_index)
+        .max();
+    let order_index = match req.order_index {
+        Some(index) if last_signed.is_some_and(|last| index <= last) => {
+            return Err(ApiError::BadRequest(format!(
+                "order_index must be after {}, the position of the last signer who signed",
+                last_signed.unwrap_or_default()
+            )))
+        }
+        Some(index) => index,
+        None => signers.iter().map(|s| s.order_index + 1).max().unwrap_or(0),
+    };
+
+    let signer = match db::signer::create_signer(
+        &mut *tx,
+        doc_id,
+        &req.email,
+        &req.name,
+        order_index,
+        req.role,
+        &crypto::generate_access_token(),
+        req.locale
+            .as_deref()
+            .map(str::trim)
+            .filter(|l| !l.is_empty()),
+    )
+    .await
+    {
+        Ok(signer) => signer,
+        Err(e) => return Err(signer_email_conflict(&state, doc_id, &req.email, e).await),
+    };
+
+    if let Some(access_code) = &req.access_code {
+        let code_hash =
+            bcrypt::hash(access_code.trim(), state.config.bcrypt_cost).map_err(|e| {
+                ApiError::Internal(anyhow::anyhow!("Failed to hash access code: {}", e))
+            })?;
+        db::signer::set_access_code(&mut *tx, signer.id, &code_hash).await?;
+    }
+
+    let progress = db::document::recount_signers(&mut *tx, doc_id).await?;
+
+    if state.email_outbox.enabled() {
+        db::outbox::enqueue_email(
+            &mut *tx,
+            OutboxEmailKind::SigningRequest,
+            doc_id,
+            Some(signer.id),
+        )
+        .await?;
+    } else {
+        info!(
+            "Signing link for {}: {}/sign/{}",
+            signer.email, state.config.public_url, signer.access_token
+        );
+    }
+
+    audit::log_action_in(
+        &mut tx,
+        state.hash_algorithm,
+        doc_id,
+        Some(signer.id),
+        Some(auth_user.user_id),
+        AuditAction::DocumentAmended,
+        Some(&ip_address),
+        Some(&user_agent),
+        Some(serde_json::json!({
+            "change": "signer_added",
+            "signer_email": signer.email,
+            "signer_name": signer.name,
+            "role": signer.role,
+            "order_index": signer.order_index,
+            "locale": signer.locale,
+            "access_code_required": req.access_code.is_some(),
+            "completed_signers": progress.completed_signers,
+            "total_signers": progress.total_signers,
+            "participants_before": participants(&signers),
+            "participants_after": participants(signers.iter().chain([&signer]))
+        })),
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    state.email_outbox.deliver_document(doc_id).await;
+
+    Ok(Json(signer))
+}
+
+/// Takes a signer who has not signed off a document that was already sent,
+/// e.g. one who left the company. Their link stops working and they are told
+/// by email. Once the signers left have all signed, the document completes.
+#[utoipa::path(
+    post,
+    path = "/documents/{id}/amend/signers/{signer_id}/remove",
+    tag = "signers",
+    params(
+        ("id" = Uuid, Path, description = "Document id"),
+        ("signer_id" = Uuid, Path, description = "Signer id"),
+    ),
+    request_body(content = Option<RemoveSignerRequest>, description = "Optional"),
+    responses(
+        (status = 200, description = "The removed signer", body = Signer),
+        (status = 400, description = "The document is not pending, or the signer is its last", body = ErrorResponse),
+        (status = 409, description = "The document is completed, or the signer already signed or approved", body = ErrorResponse),
+    )
+)]
+pub async fn amend_remove_signer(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path((doc_id, signer_id)): Path<(Uuid, Uuid)>,
+    headers: axum::http::HeaderMap,
+    req: Option<Json<RemoveSignerRequest>>,
+) -> ApiResult<Json<Signer>> {
+    auth_user.require_sender()?;
+
+    let (ip_address, user_agent) = extract_client_info_from_headers(&headers);
+    let req = req.map(|Json(req)| req).unwrap_or_default();
+
+    req.validate()
+        .map_err(|e| ApiError::Validation(e.to_string()))?;
+    let reason = req
+        .reason
+        .as_deref()
+        .map(str::trim)
+        .filter(|reason| !reason.is_empty());
+
+    let mut tx = state.pool.begin().await?;
+
+    lock_amendable_document(&mut tx, doc_id, auth_user.user_id).await?;
+
+    let signers = db::signer::get_signers_by_document(&mut *tx, doc_id).await?;
+    let signer = amendable_signer(&signers, signer_id)?;
+
+    if signers.len() == 1 {
+        return Err(ApiError::BadRequest(
+            "A document needs at least one signer; void it instead".to_string(),
+        ));
+    }
+
+    let removed = remove_amended_signer(&state, &mut tx, signer, reason).await?;
+
+    let progress = db::document::recount_signers(&mut *tx, doc_id).await?;
+
+    audit::log_action_in(
+        &mut tx,
+        state.hash_algorithm,
+        doc_id,
+        Some(removed.id),
+        Some(auth_user.user_id),
+        AuditAction::DocumentAmended,
+        Some(&ip_address),
+        Some(&user_agent),
+        Some(serde_json::json!({
+            "change": "signer_removed",
+            "signer_email": removed.email,
+            "signer_name": removed.name,
+            "reason": reason,
+            "completed_signers": progress.completed_signers,
+            "total_signers": progress.total_signers,
+            "participants_before": participants(&signers),
+            "participants_after": participants(signers.iter().filter(|s| s.id != removed.id))
+        })),
+    )
+    .await?;
+
+    // The signers left may all have signed already.
+    signing::record_participant_completion(&mut tx, state.hash_algorithm, &progress).await?;
+
+    tx.commit().await?;
+
+    let updated_doc = db::document::get_document_by_id(&state.pool, doc_id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Document not found".to_string()))?;
+
+    if updated_doc.status == DocumentStatus::Completed {
+        state.document_sealer.seal_completed(&updated_doc).await;
+        state.timestamps.stamp_completed(&updated_doc).await;
+    }
+
+    state.events.dispatch_document(doc_id).await;
+
+    Ok(Json(removed))
+}
+
+/// Hands a signer's place on a document that was already sent to someone
+/// else: the new signer takes over their position, role and fields and is
+/// invited at once, while the old signer's link stops working.
+#[utoipa::path(
+    post,
+    path = "/documents/{id}/amend/signers/{signer_id}/replace",
+    tag = "signers",
+    params(
+        ("id" = Uuid, Path, description = "Document id"),
+        ("signer_id" = Uuid, Path, description = "Signer id"),
+    ),
+    request_body = ReplaceSignerRequest,
+    responses(
+        (status = 200, description = "The new signer", body = Signer),
+        (status = 400, description = "The document is not pending", body = ErrorResponse),
+        (status = 409, description = "The document is completed, the signer already signed or approved, or the email is already a signer's", body = ErrorResponse),
+    )
+)]
+pub async fn amend_replace_signer(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path((doc_id, signer_id)): Path<(Uuid, Uuid)>,
+    headers: axum::http::HeaderMap,
+    Json(req): Json<ReplaceSignerRequest>,
+) -> ApiResult<Json<Signer>> {
+    auth_user.require_sender()?;
+
+    let (ip_address, user_agent) = extract_client_info_from_headers(&headers);
+
+    req.validate()
+        .map_err(|e| ApiError::Validation(e.to_string()))?;
+    let reason = req
+        .reason
+        .as_deref()
+        .map(str::trim)
+        .filter(|reason| !reason.is_empty());
+
+    let mut tx = state.pool.begin().await?;
+
+    lock_amendable_document(&mut tx, doc_id, auth_user.user_id).await?;
+
+    let signers = db::signer::get_signers_by_document(&mut *tx, doc_id).await?;
+    let signer = amendable_signer(&signers, signer_id)?;
+
+    match db::signer::get_signer_by_document_email(&mut *tx, doc_id, &req.email).await? {
+        Some(existing) if existing.id != signer.id => return Err(email_taken(&existing)),
+        _ => {}
+    }
+
+    let removed = remove_amended_signer(&state, &mut tx, signer, reason).await?;
+
+    let replacement = match db::signer::create_signer(
+        &mut *tx,
+        doc_id,
+        &req.email,
+        &req.name,
+        removed.order_index,
+        removed.role,
+        &crypto::generate_access_token(),
+        req.locale
+            .as_deref()
+            .map(str::trim)
+            .filter(|l| !l.is_empty())
+            .or(removed.locale.as_deref()),
+    )
+    .await
+    {
+        Ok(signer) => signer,
+        Err(e) => return Err(signer_email_conflict(&state, doc_id, &req.email, e).await),
+    };
+
+    if let Some(access_code) = &req.access_code {
+        let code_hash =
+            bcrypt::hash(access_code.trim(), state.config.bcrypt_cost).map_err(|e| {
+                ApiError::Internal(anyhow::anyhow!("Failed to hash access code: {}", e))
+            })?;
+        db::signer::set_access_code(&mut *tx, replacement.id, &code_hash).await?;
+    }
+
+    let fields_reassigned =
+        db::document::reassign_fields(&mut *tx, removed.id, replacement.id).await?;
+
+    let progress = db::document::recount_signers(&mut *tx, doc_id).await?;
+
+    if state.email_outbox.enabled() {
+        db::outbox::enqueue_email(
+            &mut *tx,
+            OutboxEmailKind::SigningRequest,
+            doc_id,
+            Some(replacement.id),
+        )
+        .await?;
+    } else {
+        info!(
+            "Signing link for {}: {}/sign/{}",
+            replacement.email, state.config.public_url, replacement.access_token
+        );
+    }
+
+    audit::log_action_in(
+        &mut tx,
+        state.hash_algorithm,
+        doc_id,
+        Some(replacement.id),
+        Some(auth_user.user_id),
+        AuditAction::DocumentAmended,
+        Some(&ip_address),
+        Some(&user_agent),
+        Some(serde_json::json!({
+            "change": "signer_replaced",
+            "replaced_signer_id": removed.id,
+            "replaced_signer_email": removed.email,
+            "signer_email": replacement.email,
+            "signer_name": replacement.name,
+            "fields_reassigned": fields_reassigned,
+            "access_code_required": req.access_code.is_some(),
+            "reason": reason,
+            "completed_signers": progress.completed_signers,
+            "total_signers": progress.total_signers,
+            "participants_before": participants(&signers),
+            "participants_after": participants(
+                signers
+                    .iter()
+                    .map(|s| if s.id == removed.id { &replacement } else { s })
+            )
+        })),
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    state.email_outbox.deliver_document(doc_id).await;
+
+    Ok(Json(replacement))
+}
+
+/// Locks a document whose signers the owner `user_id` is changing after it
+/// was sent, refusing any that is not awaiting signatures.
+async fn lock_amendable_document(
+    tx: &mut sqlx::PgConnection,
+    doc_id: Uuid,
+    user_id: Uuid,
+) -> ApiResult<Document> {
+    let document = db::document::lock_document(tx, doc_id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Document not found".to_string()))?;
+
+    if document.owner_id != user_id {
+        return Err(ApiError::Forbidden);
+    }
+
+    match document.status {
+        DocumentStatus::Pending => Ok(document),
+        DocumentStatus::Completed => Err(ApiError::Conflict(
+            "Document is already completed and cannot be amended".to_string(),
+        )),
+        DocumentStatus::Draft => Err(ApiError::BadRequest(
+            "Draft documents are edited directly, without an amendment".to_string(),
+        )),
+        _ => Err(ApiError::BadRequest(
+            "Only documents awaiting signatures can be amended".to_string(),
+        )),
+    }
+}
+
+/// The signer `signer_id` among a document's `signers`, provided they can
+/// still be taken off it.
+fn amendable_signer(signers: &[Signer], signer_id: Uuid) -> ApiResult<&Signer> {
+    let signer = signers
+        .iter()
+        .find(|s| s.id == signer_id)
+        .ok_or_else(|| ApiError::NotFound("Signer not found".to_string()))?;
+
+    if matches!(signer.status, SignerStatus::Signed | SignerStatus::Approved) {
+        return Err(ApiError::Conflict(
+            "Signer has already signed and cannot be removed".to_string(),
+        ));
+    }
+
+    Ok(signer)
+}
+
+/// Takes `signer` off their document: their link is revoked with `reason`
+/// and, if they were invited, they are told by email.
+async fn remove_amended_signer(
+    state: &AppState,
+    tx: &mut sqlx::PgConnection,
+    signer: &Signer,
+    reason: Option<&str>,
+) -> ApiResult<Signer> {
+    let emails_signer = match db::signer::get_open_link(&mut *tx, signer.id).await? {
+        Some(link) => link.emails_signer(),
+        None => true,
+    };
+    let was_invited = matches!(signer.status, SignerStatus::Sent | SignerStatus::Viewed);
+
+    let removed =
+        db::signer::mark_signer_removed(&mut *tx, signer.id, &crypto::generate_access_token())
+            .await?
+            .ok_or_else(|| {
+                ApiError::Conflict("Signer has already signed and cannot be removed".to_string())
+            })?;
+
+    db::signer::revoke_access_token(
+        &mut *tx,
+        signer.id,
+        &crypto::hash_token(&signer.access_token),
+        reason,
+    )
+    .await?;
+
+    if state.email_outbox.enabled() && emails_signer && was_invited {
+        db::outbox::enqueue_email(
+            &mut *tx,
+            OutboxEmailKind::SignerRemoved,
+            signer.document_id,
+            Some(removed.id),
+        )
+        .await?;
+    }
+
+    Ok(removed)
+}
+
+/// Who takes part in a document, as recorded before and after an amendment.
+fn participants<'a>(signers: impl IntoIterator<Item = &'a Signer>) -> serde_json::Value {
+    signers
+        .into_iter()
+        .map(|s| {
+            serde_json::json!({
+                "id": s.id,
+                "email": s.email,
+                "name": s.name,
+                "role": s.role,
+                "status": s.status,
+                "order_index": s.order_index
+            })
+        })
+        .collect()
+}
+
+/// Replaces a signer's link, for when it reached the wrong person. The old
+/// link stops working at once and says it was revoked.
+#[utoipa::path(
+    post,
+    path = "/documents/{id}/signers/{signer_id}/rotate-token",
+    tag = "signers",
+    params(
+        ("id" = Uuid, Path, description = "Document id"),
+        ("signer_id" = Uuid, Path, description = "Signer id"),
+    ),
+    request_body(content = Option<RotateSignerTokenRequest>, description = "Optional"),
+    responses(
+        (status = 200, description = "The signer with their new link", body = Signer),
+        (status = 409, description = "The signer already signed or approved", body = ErrorResponse),
+    )
+)]
+pub async fn rotate_signer_token(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path((doc_id, signer_id)): Path<(Uuid, Uuid)>,
+    headers: axum::http::HeaderMap,
+    req: Option<Json<RotateSignerTokenRequest>>,
+) -> ApiResult<Json<Signer>> {
+    auth_user.require_sender()?;
+
+    let (ip_address, user_agent) = extract_client_info_from_headers(&headers);
+    let req = req.map(|Json(req)| req).unwrap_or_default();
+
+    req.validate()
+        .map_err(|e| ApiError::Validation(e.to_string()))?;
+    let reason = req
+        .reason
+        .as_deref()
+        .map(str::trim)
+        .filter(|reason| !reason.is_empty());
+
+    let mut tx = state.pool.begin().await?;
+
+    let document = db::document::lock_document(&mut tx, doc_id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Document not found".to_string()))?;
+
+    if document.owner_id != auth_user.user_id {
+        return Err(ApiError::Forbidden);
+    }
+
+    if !matches!(
+        document.status,
+        DocumentStatus::Draft | DocumentStatus::Pending
+    ) {
+        return Err(ApiError::Conflict(
+            "Links can only be replaced on draft or pending documents".to_string(),
+        ));
+    }
+
+    let signer = db::signer::get_signer_by_id(&mut *tx, signer_id)
+        .await?
+        .filter(|s| s.document_id == doc_id)
+        .ok_or_else(|| ApiError::NotFound("Signer not found".to_string()))?;
+
+    if req.resend {
+        if document.status != DocumentStatus::Pending {
+            return Err(ApiError::BadRequest(
+                "Only signers of pending documents can be sent their new link".to_string(),
+            ));
+        }
+        if !state.email_outbox.enabled() {
+            return Err(ApiError::BadRequest(
+                "Email is not configured on this server".to_string(),
+            ));
+        }
+        if db::signer::get_open_link(&mut *tx, signer.id)
+            .await?
+            .is_some_and(|link| !link.emails_signer())
+        {
+            return Err(ApiError::BadRequest(
+                "This signer joined through an open link and cannot be emailed".to_string(),
+            ));
+        }
+    }
+
+    let rotated =
+        db::signer::rotate_access_token(&mut tx, signer.id, &crypto::generate_access_token())
+            .await?
+            .ok_or_else(|| {
+                ApiError::Conflict(
+                    "Signer has already signed and their link cannot be replaced".to_string(),
+                )
+            })?;
+
+    db::signer::revoke_access_token(
+        &mut *tx,
+        signer.id,
+        &crypto::hash_token(&signer.access_token),
+        reason,
+    )
+    .await?;
+
+    if req.resend {
+        db::outbox::enqueue_email(
+            &mut *tx,
+            OutboxEmailKind::SigningRequest,
+            doc_id,
+            Some(rotated.id),
+        )
+        .await?;
+    }
+
+    audit::log_action_in(
+        &mut tx,
+        state.hash_algorithm,
+        doc_id,
+        Some(rotated.id),
+        Some(auth_user.user_id),
+        AuditAction::SignerTokenRotated,
+        Some(&ip_address),
+        Some(&user_agent),
+        Some(serde_json::json!({
+            "signer_email": rotated.email,
+            "reason": reason,
+            "resent": req.resend
+        })),
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    if req.resend {
+        state.email_outbox.deliver_document(doc_id).await;
+    }
+
+    Ok(Json(rotated))
+}
+
+#[utoipa::path(
+    post,
+    path = "/documents/{id}/signers/{signer_id}/in-person-session",
+    tag = "signers",
+    params(
+        ("id" = Uuid, Path, description = "Document id"),
+        ("signer_id" = Uuid, Path, description = "Signer id"),
+    ),
+    responses((status = 200, description = "A signing link for the session", body = InPersonSessionResponse))
+)]
+pub async fn start_in_person_session(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path((doc_id, signer_id)): Path<(Uuid, Uuid)>,
+    headers: axum::http::HeaderMap,
+) -> ApiResult<Json<InPersonSessionResponse>> {
+    auth_user.require_sender()?;
+
+    let (ip_address, user_agent) = extract_client_info_from_headers(&headers);
+
+    let document = db::document::get_document_by_id(&state.pool, doc_id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Document not found".to_string()))?;
+
+    if document.owner_id != auth_user.user_id {
+        return Err(ApiError::Forbidden);
+    }
+
+    if document.status != DocumentStatus::Pending {
+        return Err(ApiError::BadRequest(
+            "In-person signing is only available for pending documents".to_string(),
+        ));
+    }
+
+    let signer = db::signer::get_signer_by_id(&state.pool, signer_id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Signer not found".to_string()))?;
+
+    if signer.document_id != doc_id {
+        return Err(ApiError::NotFound("Signer not found".to_string()));
+    }
+
+    if matches!(
+        signer.status,
+        SignerStatus::Signed | SignerStatus::Approved | SignerStatus::Declined
+    ) {
+        return Err(ApiError::BadRequest(
+            "Signer has already completed their part".to_string(),
+        ));
+    }
+
+    let token = crypto::generate_access_token();
+    let expires_at =
+        chrono::Utc::now() + chrono::Duration::minutes(state.config.in_person_session_minutes);
+
+    let session = db::signer::create_in_person_session(
+        &state.pool,
+        signer.id,
+        auth_user.user_id,
+        &token,
+        expires_at,
+    )
+    .await?;
+
+    audit::log_action(
+        &state.pool,
+        state.hash_algorithm,
+        doc_id,
+        Some(signer.id),
+        Some(auth_user.user_id),
+        AuditAction::InPersonSessionStarted,
+        Some(&ip_address),
+        Some(&user_agent),
+        Some(serde_json::json!({
+            "session_id": session.id,
+            "signer_email": signer.email,
+            "host_user_id": auth_user.user_id,
+            "expires_at": session.expires_at
+        })),
+    )
+    .await?;
+
+    Ok(Json(InPersonSessionResponse {
+        signer_id: signer.id,
+        signing_url: format!("{}/sign/{}", state.config.public_url, session.token),
+        token: session.token,
+        expires_at: session.expires_at,
+    }))
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SigningPreviewQuery {
+    /// The signer whose view to show.
+    pub signer_id: Uuid,
+}
+
+/// Loads a draft or pending document of the caller's for previewing what its
+/// signers see.
+async fn preview_document(state: &AppState, user_id: Uuid, id: Uuid) -> ApiResult<Document> {
+    let document = db::document::get_document_by_id(&state.pool, id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Document not found".to_string()))?;
+
+    if document.owner_id != user_id {
+        return Err(ApiError::Forbidden);
+    }
+
+    if !matches!(
+        document.status,
+        DocumentStatus::Draft | DocumentStatus::Pending
+    ) {
+        return Err(ApiError::Conflict(
+            "Only draft and pending documents can be previewed".to_string(),
+        ));
+    }
+
+    Ok(document)
+}
+
+/// The signing session the signer would get from their link, built the same
+/// way, without marking them as having viewed it. Fields are shown even where
+/// the signer must verify their identity first.
+#[utoipa::path(
+    get,
+    path = "/documents/{id}/preview",
+    tag = "documents",
+    params(("id" = Uuid, Path, description = "Document id"), SigningPreviewQuery),
+    responses(
+        (status = 200, description = "What the signer will see", body = SigningSession),
+        (status = 409, description = "The document is neither draft nor pending", body = ErrorResponse),
+    )
+)]
+pub async fn preview_signing_session(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(id): Path<Uuid>,
+    Query(query): Query<SigningPreviewQuery>,
+) -> ApiResult<Json<SigningSession>> {
+    let document = preview_document(&state, auth_user.user_id, id).await?;
+    let signer = db::signer::get_signer_by_id(&state.pool, query.signer_id)
+        .await?
+        .filter(|signer| signer.document_id == id)
+        .ok_or_else(|| ApiError::NotFound("Signer not found".to_string()))?;
+
+    Ok(Json(signing_session(&state, document, signer, true).await?))
+}
+
+/// The PDF signers are given, without recording a view.
+#[utoipa::path(
+    get,
+    path = "/documents/{id}/preview/pdf",
+    tag = "documents",
+    params(("id" = Uuid, Path, description = "Document id")),
+    responses(
+        (status = 200, description = "The document signers are given", content_type = "application/pdf"),
+        (status = 409, description = "The document is neither draft nor pending", body = ErrorResponse),
+    )
+)]
+pub async fn preview_signing_pdf(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(id): Path<Uuid>,
+    headers: axum::http::HeaderMap,
+) -> ApiResult<axum::response::Response> {
+    use axum::http::{header, Response};
+
+    let (ip_address, user_agent) = extract_client_info_from_headers(&headers);
+    let document = preview_document(&state, auth_user.user_id, id).await?;
+
+    file_response::ensure_intact(
+        &state,
+        &document,
+        &headers,
+        &IntegrityActor {
+            signer_id: None,
+            user_id: Some(auth_user.user_id),
+            ip_address: Some(&ip_address),
+            user_agent: Some(&user_agent),
+            source: SecurityEventSource::Request,
+        },
+    )
+    .await?;
+
+    let builder = Response::builder()
+        .header(header::CONTENT_TYPE, "application/pdf")
+        .header(header::CACHE_CONTROL, "private, max-age=0, must-revalidate");
+
+    file_response::stream_file(
+        state.storage.as_ref(),
+        &document.file_path,
+        document.original_encryption().as_ref(),
+        &file_response::etag_for_hash(&document.file_hash),
+        &headers,
+        builder,
+    )
+    .await
+}
+
+/// Runs every check `send_document` makes and reports all problems at once,
+/// without changing anything.
+#[utoipa::path(
+    get,
+    path = "/documents/{id}/preflight",
+    tag = "documents",
+    params(("id" = Uuid, Path, description = "Document id")),
+    responses(
+        (status = 200, description = "The problems that would stop or affect sending", body = PreflightResponse),
+        (status = 409, description = "The document was already sent", body = ErrorResponse),
+    )
+)]
+pub async fn preflight_document(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(id): Path<Uuid>,
+) -> ApiResult<Json<PreflightResponse>> {
+    let document = db::document::get_document_by_id(&state.pool, id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Document not found".to_string()))?;
+
+    if document.owner_id != auth_user.user_id {
+        return Err(ApiError::Forbidden);
+    }
+
+    if document.status != DocumentStatus::Draft {
+        return Err(ApiError::Conflict(
+            "Document already sent or completed".to_string(),
+        ));
+    }
+
+    let signers = db::signer::get_signers_by_document(&state.pool, id).await?;
+    let open_links = db::signer::get_open_links_by_document(&state.pool, id).await?;
+    let fields = db::document::get_fields_by_document(&state.pool, id).await?;
+    let pages = load_pages(&state, &document).await?;
+    let issues = preflight::check(
+        &document,
+        &signers,
+        &open_links,
+        &fields,
+        &pages,
+        chrono::Utc::now(),
+    );
+
+    Ok(Json(PreflightResponse {
+        ready: !issues.iter().any(PreflightIssue::is_error),
+        issues,
+    }))
+}
+
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SendQuery {
+    /// Send even if a signer has no signature or initial field, e.g. a
+    /// participant who only needs to view the document. Other preflight
+    /// errors still refuse the send.
+    #[serde(default)]
+    pub force: bool,
+}
+
+#[utoipa::path(
+    post,
+    path = "/documents/{id}/send",
+    tag = "documents",
+    params(
+        ("id" = Uuid, Path, description = "Document id"),
+        SendQuery,
+        ("Idempotency-Key" = Option<String>, Header, description = "Makes retries safe: a retry with the same key and payload gets the original response"),
+    ),
+    responses(
+        (status = 200, description = "The sent document", body = Document),
+        (status = 400, description = "The document fails a preflight check", body = ErrorResponse),
+        (status = 409, description = "The Idempotency-Key was used for a different request", body = ErrorResponse),
+    )
+)]
+pub async fn send_document(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(id): Path<Uuid>,
+    Query(query): Query<SendQuery>,
+    headers: axum::http::HeaderMap,
+) -> ApiResult<Json<Document>> {
+    auth_user.require_sender()?;
+
+    let (ip_address, user_agent) = extract_client_info_from_headers(&headers);
+
+    let document = db::document::get_document_by_id(&state.pool, id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Document not found".to_string()))?;
+
+    if document.owner_id != auth_user.user_id {
+        return Err(ApiError::Forbidden);
+    }
+
+    if document.status != DocumentStatus::Draft {
+        return Err(ApiError::Conflict(
+            "Document already sent or completed".to_string(),
+        ));
+    }
+
+    let signers = db::signer::get_signers_by_document(&state.pool, id).await?;
+    let open_links = db::signer::get_open_links_by_document(&state.pool, id).await?;
+
+    let fields = db::document::get_fields_by_document(&state.pool, id).await?;
+    let pages = load_pages(&state, &document).await?;
+    let errors: Vec<PreflightIssue> = preflight::check(
+        &document,
+        &signers,
+        &open_links,
+        &fields,
+        &pages,
+        chrono::Utc::now(),
+    )
+    .into_iter()
+    .filter(|issue| issue.is_error())
+    .filter(|issue| !(query.force && issue.code == PreflightCode::MissingSignatureField))
+    .collect();
+
+    if !errors.is_empty() {
+        let mut message = errors
+            .iter()
+            .map(|issue| issue.message.as_str())
+            .collect::<Vec<_>>()
+            .join("; ");
+        if errors
+            .iter()
+            .any(|issue| issue.code == PreflightCode::MissingSignatureField)
+        {
+            message.push_str(". Send with force=true to send anyway");
+        }
+        return Err(ApiError::BadRequest(message));
+    }
+
+    // The status transition is the gate: of two concurrent sends only one
+    // moves the document out of draft, and only that one records the event.
+    let mut tx = state.pool.begin().await?;
+
+    let updated = db::document::transition_document_status(
+        &mut *tx,
+        id,
+        DocumentStatus::Draft,
+        DocumentStatus::Pending,
+    )
+    .await?
+    .ok_or_else(|| ApiError::Conflict("Document already sent or completed".to_string()))?;
+
+    // Open slots are shared by the owner and have nobody to email yet.
+    let invited: Vec<&Signer> = signers
+        .iter()
+        .filter(|s| {
+            !open_links
+                .iter()
+                .any(|link| link.signer_id == s.id && !link.emails_signer())
+        })
+        .collect();
+
+    db::outbox::record_event(
+        &mut *tx,
+        OutboxEventType::DocumentSent,
+        id,
+        None,
+        serde_json::json!({ "signer_ids": invited.iter().map(|s| s.id).collect::<Vec<_>>() }),
+    )
+    .await?;
+
+    if !state.email_outbox.enabled() {
+        info!("Email service not configured. Signers would need manual access tokens.");
+        for signer in &invited {
+            info!(
+                "Signing link for {}: {}/sign/{}",
+                signer.email, state.config.public_url, signer.access_token
+            );
+        }
+    }
+
+    audit::log_action_in(
+        &mut tx,
+        state.hash_algorithm,
+        id,
+        None,
+        Some(auth_user.user_id),
+        AuditAction::DocumentSent,
+        Some(&ip_address),
+        Some(&user_agent),
+        Some(serde_json::json!({
+            "signer_count": signers.len(),
+            "self_sign": document.self_sign_only,
+            "forced": query.force
+        })),
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    state.events.dispatch_document(id).await;
+
+    Ok(Json(updated))
+}
+
+/// Takes a pending document back to draft so it can be edited and sent again.
+/// Only possible while nobody has signed or approved it; every signing link
+/// is replaced so the ones already emailed stop working.
+#[utoipa::path(
+    post,
+    path = "/documents/{id}/recall",
+    tag = "documents",
+    params(("id" = Uuid, Path, description = "Document id")),
+    request_body(content = Option<RecallDocumentRequest>, description = "Optional"),
+    responses((status = 200, description = "The document, back in draft", body = Document))
+)]
+pub async fn recall_document(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(id): Path<Uuid>,
+    headers: axum::http::HeaderMap,
+    req: Option<Json<RecallDocumentRequest>>,
+) -> ApiResult<Json<Document>> {
+    auth_user.require_sender()?;
+
+    let (ip_address, user_agent) = extract_client_info_from_headers(&headers);
+    let req = req.map(|Json(req)| req).unwrap_or_default();
+
+    // The lock keeps signing submissions out until the recall commits.
+    let mut tx = state.pool.begin().await?;
+
+    let document = db::document::lock_document(&mut tx, id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Document not found".to_string()))?;
+
+    if document.owner_id != auth_user.user_id {
+        return Err(ApiError::Forbidden);
+    }
+
+    if document.status != DocumentStatus::Pending {
+        return Err(ApiError::Conflict(
+            "Only documents awaiting signatures can be recalled".to_string(),
+        ));
+    }
+
+    let signers = db::signer::get_signers_by_document(&mut *tx, id).await?;
+
+    if signers
+        .iter()
+        .any(|s| matches!(s.status, SignerStatus::Signed | SignerStatus::Approved))
+    {
+        return Err(ApiError::Conflict(
+            "Someone has already signed this document, so it can no longer be recalled. \
+             Void it and send a corrected copy instead"
+                .to_string(),
+        ));
+    }
+
+    db::outbox::cancel_pending(&mut *tx, id, OutboxEmailKind::SigningRequest).await?;
+
+    let mut notified = 0;
+    for signer in &signers {
+        let was_sent = matches!(signer.status, SignerStatus::Sent | SignerStatus::Viewed);
+
+        db::signer::reset_signer_for_recall(&mut tx, signer.id, &crypto::generate_access_token())
+            .await?;
+
+        if req.notify_signers && was_sent && state.email_outbox.enabled() {
+            db::outbox::enqueue_email(
+                &mut *tx,
+                OutboxEmailKind::RequestWithdrawn,
+                id,
+                Some(signer.id),
+            )
+            .await?;
+            notified += 1;
+        }
+    }
+
+    let updated = db::document::transition_document_status(
+        &mut *tx,
+        id,
+        DocumentStatus::Pending,
+        DocumentStatus::Draft,
+    )
+    .await?
+    .ok_or_else(|| {
+        ApiError::Conflict("Only documents awaiting signatures can be recalled".to_string())
+    })?;
+
+    audit::log_action_in(
+        &mut tx,
+        state.hash_algorithm,
+        id,
+        None,
+        Some(auth_user.user_id),
+        AuditAction::DocumentRecalled,
+        Some(&ip_address),
+        Some(&user_agent),
+        Some(serde_json::json!({
+            "signer_count": signers.len(),
+            "notified_signers": notified
+        })),
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    if notified > 0 {
+        state.email_outbox.deliver_document(id).await;
+    }
+
+    Ok(Json(updated))
+}
+
+#[utoipa::path(
+    post,
+    path = "/documents/{id}/self-sign",
+    tag = "documents",
+    params(("id" = Uuid, Path, description = "Document id")),
+    request_body = CompleteSigningRequest,
+    responses((status = 200, description = "The document was signed", body = serde_json::Value))
+)]
+pub async fn self_sign_document(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(id): Path<Uuid>,
+    headers: axum::http::HeaderMap,
+    Json(req): Json<CompleteSigningRequest>,
+) -> ApiResult<Json<serde_json::Value>> {
+    auth_user.require_sender()?;
+
+    let (ip_address, user_agent) = extract_client_info_from_headers(&headers);
+
+    let document = db::document::get_document_by_id(&state.pool, id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Document not found".to_string()))?;
+
+    if document.owner_id != auth_user.user_id {
+        return Err(ApiError::Forbidden);
+    }
+
+    if !document.self_sign_only {
+        return Err(ApiError::BadRequest(
+            "Only self-sign documents can be signed by their owner".to_string(),
+        ));
+    }
+
+    match document.status {
+        DocumentStatus::Pending => {}
+        DocumentStatus::Draft => {
+            return Err(ApiError::BadRequest(
+                "Send the document before signing it".to_string(),
+            ))
+        }
+        _ => {
+            return Err(ApiError::BadRequest(
+                "Document is no longer open for signing".to_string(),
+            ))
+        }
+    }
+
+    let owner = db::user::get_user_by_id(&state.pool, auth_user.user_id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Owner not found".to_string()))?;
+
+    let existing_signers = db::signer::get_signers_by_document(&state.pool, id).await?;
+
+    let signer = match existing_signers
+        .into_iter()
+        .find(|s| s.email == owner.email)
+    {
+        Some(signer) => signer,
+        None => {
+            let signer = db::signer::create_signer(
+                &state.pool,
+                id,
+                &owner.email,
+                &owner.name,
+                0,
+                SignerRole::Signer,
+                &crypto::generate_access_token(),
+                None,
+            )
+            .await?;
+
+            db::document::update_total_signers(&state.pool, id, 1).await?;
+
+            audit::log_action(
+                &state.pool,
+                state.hash_algorithm,
+                id,
+                Some(signer.id),
+                Some(auth_user.user_id),
+                AuditAction::SignerAdded,
+                Some(&ip_address),
+                Some(&user_agent),
+                Some(serde_json::json!({
+                    "signer_email": owner.email,
+                    "signer_name": owner.name,
+                    "self_sign": true
+                })),
+            )
+            .await?;
+
+            signer
+        }
+    };
+
+    let location = state.geoip.lookup(&ip_address);
+
+    let ctx = SigningContext {
+        signer_id: signer.id,
+        document_id: id,
+        ip_address,
+        user_agent,
+        actor: SigningActor::Owner(auth_user.user_id),
+        location,
+        hash_algorithm: state.hash_algorithm,
+    };
+
+    let submission = signing::process_signing(
+        &state.pool,
+        state.storage.as_ref(),
+        &state.signature_renderer,
+        state.config.max_signature_size_bytes(),
+        &ctx,
+        &req,
+    )
+    .await
+    .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+    let already_submitted = submission == SigningSubmission::AlreadySubmitted;
+
+    let updated_doc = db::document::get_document_by_id(&state.pool, id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Document not found".to_string()))?;
+
+    if updated_doc.status == DocumentStatus::Completed && !already_submitted {
+        state.document_sealer.seal_completed(&updated_doc).await;
+        state.timestamps.stamp_completed(&updated_doc).await;
+    }
+
+    if !already_submitted {
+        state.events.dispatch_document(id).await;
+    }
+
+    info!("Document {} self-signed by user {}", id, auth_user.user_id);
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "document_completed": updated_doc.status == DocumentStatus::Completed,
+        "already_submitted": already_submitted
+    })))
+}
+
+#[utoipa::path(
+    post,
+    path = "/documents/{id}/void",
+    tag = "documents",
+    params(("id" = Uuid, Path, description = "Document id")),
+    request_body(content = Option<VoidDocumentRequest>, description = "Optional"),
+    responses((status = 200, description = "The voided document", body = Document))
+)]
+pub async fn void_document(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(id): Path<Uuid>,
+    headers: axum::http::HeaderMap,
+    req: Option<Json<VoidDocumentRequest>>,
+) -> ApiResult<Json<Document>> {
+    auth_user.require_sender()?;
+
+    let (ip_address, user_agent) = extract_client_info_from_headers(&headers);
+    let req = req.map(|Json(req)| req).unwrap_or_default();
+
+    req.validate()
+        .map_err(|e| ApiError::Validation(e.to_string()))?;
+
+    let reason = req
+        .reason
+        .as_deref()
+        .map(str::trim)
+        .filter(|r| !r.is_empty());
+
+    if reason.is_none() && state.config.void_reason_required {
+        return Err(ApiError::BadRequest(
+            "A reason is required to void a document".to_string(),
+        ));
+    }
+
+    let (updated, notified) = void_owned_document(
+        &state,
+        auth_user.user_id,
+        id,
+        reason,
+        &ip_address,
+        &user_agent,
+    )
+    .await?;
+
+    if notified > 0 {
+        state.email_outbox.deliver_document(id).await;
+    }
+
+    Ok(Json(updated))
+}
+
+/// Voids one of the user's documents, queuing an email to each signer who was
+/// sent a link and has not finished. Returns the document and how many
+/// signers are to be told; the caller delivers the emails.
+async fn void_owned_document(
+    state: &AppState,
+    user_id: Uuid,
+    id: Uuid,
+    reason: Option<&str>,
+    ip_address: &str,
+    user_agent: &str,
+) -> ApiResult<(Document, usize)> {
+    let document = db::document::get_document_by_id(&state.pool, id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Document not found".to_string()))?;
+
+    if document.owner_id != user_id {
+        return Err(ApiError::Forbidden);
+    }
+
+    if document.status == DocumentStatus::Completed {
+        return Err(ApiError::BadRequest(
+            "Cannot void completed documents".to_string(),
+        ));
+    }
+
+    let mut tx = state.pool.begin().await?;
+
+    let updated = db::document::void_document(&mut *tx, id, reason)
+        .await?
+        .ok_or_else(|| ApiError::Conflict("Document already voided or completed".to_string()))?;
+
+    db::outbox::cancel_pending(&mut *tx, id, OutboxEmailKind::SigningRequest).await?;
+
+    // Only signers who were sent a link and have not finished are told.
+    let mut notified = 0;
+    if state.email_outbox.enabled() {
+        for signer in db::signer::get_signers_by_document(&mut *tx, id).await? {
+            if matches!(signer.status, SignerStatus::Sent | SignerStatus::Viewed) {
+                db::outbox::enqueue_email(
+                    &mut *tx,
+                    OutboxEmailKind::RequestCancelled,
+                    id,
+                    Some(signer.id),
+                )
+                .await?;
+                notified += 1;
+            }
+        }
+    }
+
+    audit::log_action_in(
+        &mut tx,
+        state.hash_algorithm,
+        id,
+        None,
+        Some(user_id),
+        AuditAction::DocumentVoided,
+        Some(ip_address),
+        Some(user_agent),
+        Some(serde_json::json!({
+            "reason": reason,
+            "notified_signers": notified
+        })),
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    Ok((updated, notified))
+}
+
+/// Voids or deletes several of the user's documents, each in its own
+/// transaction and under the same rules as the single-document endpoints.
+/// One document failing does not stop the others.
+#[utoipa::path(
+    post,
+    path = "/documents/bulk",
+    tag = "documents",
+    request_body = BulkDocumentRequest,
+    responses((status = 200, description = "The outcome for each document", body = BulkDocumentResponse))
+)]
+pub async fn bulk_update_documents(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    headers: axum::http::HeaderMap,
+    Json(req): Json<BulkDocumentRequest>,
+) -> ApiResult<Json<BulkDocumentResponse>> {
+    auth_user.require_sender()?;
+
+    let (ip_address, user_agent) = extract_client_info_from_headers(&headers);
+
+    req.validate()
+        .map_err(|e| ApiError::Validation(e.to_string()))?;
+
+    let reason = req
+        .reason
+        .as_deref()
+        .map(str::trim)
+        .filter(|r| !r.is_empty());
+
+    if req.action == BulkDocumentAction::Void
+        && reason.is_none()
+        && state.config.void_reason_required
+    {
+        return Err(ApiError::BadRequest(
+            "A reason is required to void a document".to_string(),
+        ));
+    }
+
+    let mut seen = HashSet::new();
+    let mut results = Vec::with_capacity(req.ids.len());
+    let mut to_notify = Vec::new();
+
+    for id in req.ids.into_iter().filter(|id| seen.insert(*id)) {
+        let outcome = match req.action {
+            BulkDocumentAction::Void => void_owned_document(
+                &state,
+                auth_user.user_id,
+                id,
+                reason,
+                &ip_address,
+                &user_agent,
+            )
+            .await
+            .map(|(_, notified)| notified),
+            BulkDocumentAction::Delete => {
+                trash_document(&state, auth_user.user_id, id, &ip_address, &user_agent)
+                    .await
+                    .map(|_| 0)
+            }
+        };
+
+        results.push(match outcome {
+            Ok(notified) => {
+                if notified > 0 {
+                    to_notify.push(id);
+                }
+                BulkDocumentResult {
+                    id,
+                    success: true,
+                    error: None,
+                    message: None,
+                }
+            }
+            Err(e) => {
+                let (_, error, message) = e.describe();
+                BulkDocumentResult {
+                    id,
+                    success: false,
+                    error: Some(error.to_string()),
+                    message: Some(message),
+                }
+            }
+        });
+    }
+
+    for id in to_notify {
+        state.email_outbox.deliver_document(id).await;
+    }
+
+    let succeeded = results.iter().filter(|result| result.success).count();
+    info!(
+        "Bulk {:?} by user {}: {} succeeded, {} failed",
+        req.action,
+        auth_user.user_id,
+        succeeded,
+        results.len() - succeeded
+    );
+
+    Ok(Json(BulkDocumentResponse {
+        succeeded,
+        failed: results.len() - succeeded,
+        results,
+    }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/documents/{id}/audit",
+    tag = "audit",
+    params(("id" = Uuid, Path, description = "Document id")),
+    responses((status = 200, description = "The audit trail", body = Vec<AuditLog>))
+)]
+pub async fn get_audit_logs(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(id): Path<Uuid>,
+) -> ApiResult<Json<Vec<AuditLog>>> {
+    let document = db::document::get_document_by_id(&state.pool, id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Document not found".to_string()))?;
+
+    access::require(
+        &state.pool,
+        &document,
+        auth_user.user_id,
+        DocumentAccess::Viewer,
+    )
+    .await?;
+
+    let mut logs = db::audit::get_audit_logs_by_document(&state.pool, id).await?;
+
+    for log in &mut logs {
+        log.device = audit::device_description(log);
+    }
+
+    Ok(Json(logs))
+}
+
+/// Checks whether an uploaded file is exactly the document's original upload
+/// or its sealed copy. The upload is hashed as it streams in and not stored.
+#[utoipa::path(
+    post,
+    path = "/documents/{id}/verify-file",
+    tag = "documents",
+    params(("id" = Uuid, Path, description = "Document id")),
+    request_body(content = VerifyFileForm, content_type = "multipart/form-data"),
+    responses((status = 200, description = "Which stored file the upload matches, if any", body = FileVerification))
+)]
+pub async fn verify_file(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(id): Path<Uuid>,
+    headers: axum::http::HeaderMap,
+    mut multipart: Multipart,
+) -> ApiResult<Json<FileVerification>> {
+    use futures::TryStreamExt;
+
+    let (ip_address, user_agent) = extract_client_info_from_headers(&headers);
+
+    let document = db::document::get_document_by_id(&state.pool, id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Document not found".to_string()))?;
+
+    access::require(
+        &state.pool,
+        &document,
+        auth_user.user_id,
+        DocumentAccess::Viewer,
+    )
+    .await?;
+
+    while let Some(field) = multipart
+        .next_field()
+        .await
+        .map_err(|e| multipart_error(&state, e))?
+    {
+        if field.name() != Some("file") {
+            continue;
+        }
+
+        let mut upload_error = None;
+        let stream = field.map_err(|e| {
+            let error = std::io::Error::other(e.body_text());
+            upload_error = Some(e);
+            error
+        });
+
+        let result = state
+            .file_integrity
+            .identify(
+                &document,
+                stream,
+                &IntegrityActor {
+                    signer_id: None,
+                    user_id: Some(auth_user.user_id),
+                    ip_address: Some(&ip_address),
+                    user_agent: Some(&user_agent),
+                    source: SecurityEventSource::Request,
+                },
+            )
+            .await;
+
+        if let Some(e) = upload_error {
+            return Err(multipart_error(&state, e));
+        }
+
+        return Ok(Json(result?));
+    }
+
+    Err(ApiError::BadRequest("File is required".to_string()))
+}
+
+#[derive(Debug, Default, Clone, Copy, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum AuditExportFormat {
+    #[default]
+    Json,
+    /// One row per entry, with times in UTC and in the caller's display
+    /// timezone.
+    Csv,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AuditExportQuery {
+    #[serde(default)]
+    pub format: AuditExportFormat,
+}
+
+/// Downloads the audit trail with the server key signature of the body in
+/// the `X-SignVault-Key-Id` and `X-SignVault-Signature` headers.
+#[utoipa::path(
+    get,
+    path = "/documents/{id}/audit/export",
+    tag = "audit",
+    params(("id" = Uuid, Path, description = "Document id"), AuditExportQuery),
+    responses(
+        (status = 200, description = "The audit trail and hash chain status, signed with the server key", body = AuditExport),
+        (status = 200, description = "With `format=csv`, the audit trail as CSV, signed with the server key", content_type = "text/csv"),
+    )
+)]
+pub async fn export_audit_logs(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(id): Path<Uuid>,
+    Query(query): Query<AuditExportQuery>,
+) -> ApiResult<axum::response::Response> {
+    use axum::http::{header, Response};
+
+    let document = db::document::get_document_by_id(&state.pool, id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Document not found".to_string()))?;
+
+    access::require(
+        &state.pool,
+        &document,
+        auth_user.user_id,
+        DocumentAccess::Viewer,
+    )
+    .await?;
+
+    let (body, content_type, extension) = match query.format {
+        AuditExportFormat::Json => (
+            audit_export_body(&state, &document).await?,
+            "application/json",
+            "json",
+        ),
+        AuditExportFormat::Csv => {
+            let mut entries = db::audit::get_audit_logs_by_document(&state.pool, id).await?;
+            for log in &mut entries {
+                log.device = audit::device_description(log);
+            }
+            let timezone = display_timezone(&state, auth_user.user_id).await?;
+
+            (
+                audit::audit_csv(&entries, timezone).into_bytes(),
+                "text/csv; charset=utf-8",
+                "csv",
+            )
+        }
+    };
+    let signature = state.server_key.sign(&body);
+
+    Response::builder()
+        .header(header::CONTENT_TYPE, content_type)
+        .header(
+            header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"audit-{}.{}\"", id, extension),
+        )
+        .header(server_key::KEY_ID_HEADER, signature.key_id)
+        .header(server_key::SIGNATURE_HEADER, signature.signature)
+        .body(body.into())
+        .map_err(|e| ApiError::Internal(e.into()))
+}
+
+/// The document's audit trail and hash chain status, as the JSON body the
+/// server key signs.
+async fn audit_export_body(state: &AppState, document: &Document) -> ApiResult<Vec<u8>> {
+    let mut entries = db::audit::get_audit_logs_by_document(&state.pool, document.id).await?;
+
+    for log in &mut entries {
+        log.device = audit::device_description(log);
+    }
+
+    let export = AuditExport {
+        document_id: document.id,
+        document_title: document.title.clone(),
+        document_hash: document.file_hash.clone(),
+        exported_at: chrono::Utc::now(),
+        chain: state
+            .security_alerts
+            .verify_audit_chain(document, SecurityEventSource::Request)
+            .await?,
+        entries,
+    };
+
+    Ok(serde_json::to_vec_pretty(&export).map_err(anyhow::Error::from)?)
+}
+
+#[utoipa::path(
+    get,
+    path = "/documents/{id}/certificate",
+    tag = "audit",
+    params(("id" = Uuid, Path, description = "Document id")),
+    responses((status = 200, description = "The certificate of completion", body = Certificate))
+)]
+pub async fn get_certificate(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(id): Path<Uuid>,
+    headers: axum::http::HeaderMap,
+) -> ApiResult<Json<Certificate>> {
+    let (ip_address, user_agent) = extract_client_info_from_headers(&headers);
+
+    let document = db::document::get_document_by_id(&state.pool, id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Document not found".to_string()))?;
+
+    access::require(
+        &state.pool,
+        &document,
+        auth_user.user_id,
+        DocumentAccess::Viewer,
+    )
+    .await?;
+
+    if document.status != DocumentStatus::Completed {
+        return Err(ApiError::BadRequest(
+            "Certificate only available for completed documents".to_string(),
+        ));
+    }
+
+    let actor = IntegrityActor {
+        signer_id: None,
+        user_id: Some(auth_user.user_id),
+        ip_address: Some(&ip_address),
+        user_agent: Some(&user_agent),
+        source: SecurityEventSource::Certificate,
+    };
+
+    let timezone = display_timezone(&state, auth_user.user_id).await?;
+
+    Ok(Json(
+        completion_certificate(&state, &document, &actor, timezone).await?,
+    ))
+}
+
+/// The timezone a user reads certificate and audit export times in.
+pub(crate) async fn display_timezone(state: &AppState, user_id: Uuid) -> ApiResult<Tz> {
+    let user = db::user::get_user_by_id(&state.pool, user_id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("User not found".to_string()))?;
+
+    Ok(display_time::resolve(
+        user.display_timezone.as_deref(),
+        state.config.display_timezone,
+    ))
+}
+
+/// Generates the certificate of a completed document, with its hash
+/// timestamped when a timestamp authority is configured. The stored file and
+/// the audit chain are verified first; failures raise security alerts.
+pub(crate) async fn completion_certificate(
+    state: &AppState,
+    document: &Document,
+    actor: &IntegrityActor<'_>,
+    timezone: Tz,
+) -> ApiResult<Certificate> {
+    state.file_integrity.check(document, true, actor).await?;
+
+    let mut certificate = audit::generate_certificate(
+        &state.pool,
+        state.hash_algorithm,
+        &state.server_key,
+        &state.security_alerts,
+        document.id,
+        timezone,
+    )
+    .await?;
+
+    if let Some(timestamp) = state
+        .timestamps
+        .stamp(
+            document.id,
+            TimestampSubject::Certificate,
+            &certificate.certificate_hash,
+        )
+        .await?
+    {
+        certificate.certificate_timestamp = CertificateTimestamp::from_granted(&timestamp);
+    }
+
+    Ok(certificate)
+}
+
+/// Everything worth archiving about a completed document in one ZIP: the
+/// original, its sealed copy, the certificate, the signed audit trail and a
+/// manifest of their SHA-256. A document not sealed yet is sealed first when
+/// sealing is configured.
+#[utoipa::path(
+    get,
+    path = "/documents/{id}/package.zip",
+    tag = "documents",
+    params(("id" = Uuid, Path, description = "Document id")),
+    responses((status = 200, description = "The ZIP package", content_type = "application/zip"))
+)]
+pub async fn download_package(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(id): Path<Uuid>,
+    headers: axum::http::HeaderMap,
+) -> ApiResult<axum::response::Response> {
+    use axum::http::{header, Response};
+
+    let (ip_address, user_agent) = extract_client_info_from_headers(&headers);
+
+    let mut document = db::document::get_document_by_id(&state.pool, id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Document not found".to_string()))?;
+
+    access::require(
+        &state.pool,
+        &document,
+        auth_user.user_id,
+        DocumentAccess::Viewer,
+    )
+    .await?;
+
+    if document.status != DocumentStatus::Completed {
+        return Err(ApiError::BadRequest(
+            "Packages are only available for completed documents".to_string(),
+        ));
+    }
+
+    file_response::ensure_intact(
+        &state,
+        &document,
+        &axum::http::HeaderMap::new(),
+        &IntegrityActor {
+            signer_id: None,
+            user_id: Some(auth_user.user_id),
+            ip_address: Some(&ip_address),
+            user_agent: Some(&user_agent),
+            source: SecurityEventSource::Request,
+        },
+    )
+    .await?;
+
+    if document.sealed_file_path.is_none() {
+        if let Some(sealed) = state.document_sealer.seal(&document).await? {
+            document = sealed;
+        }
+    }
+
+    let certificate = completion_certificate(
+        &state,
+        &document,
+        &IntegrityActor {
+            signer_id: None,
+            user_id: Some(auth_user.user_id),
+            ip_address: Some(&ip_address),
+            user_agent: Some(&user_agent),
+            source: SecurityEventSource::Certificate,
+        },
+        display_timezone(&state, auth_user.user_id).await?,
+    )
+    .await?;
+
+    audit::log_action(
+        &state.pool,
+        state.hash_algorithm,
+        id,
+        None,
+        Some(auth_user.user_id),
+        AuditAction::DocumentPackageDownloaded,
+        Some(&ip_address),
+        Some(&user_agent),
+        Some(serde_json::json!({ "sealed": document.sealed_file_path.is_some() })),
+    )
+    .await?;
+
+    let audit_trail = audit_export_body(&state, &document).await?;
+    let audit_trail_signature = state.server_key.sign(&audit_trail);
+
+    let mut entries = vec![package::PackageEntry {
+        name: package::ORIGINAL_ENTRY,
+        source: package::PackageSource::Stored(
+            document.file_path.clone(),
+            document.original_encryption(),
+        ),
+        server_signature: None,
+    }];
+    if let Some(sealed_file_path) = &document.sealed_file_path {
+        entries.push(package::PackageEntry {
+            name: package::SEALED_ENTRY,
+            source: package::PackageSource::Stored(
+                sealed_file_path.clone(),
+                document.sealed_encryption(),
+            ),
+            server_signature: None,
+        });
+    }
+    entries.push(package::PackageEntry {
+        name: package::CERTIFICATE_ENTRY,
+        source: package::PackageSource::Generated(
+            serde_json::to_vec_pretty(&certificate).map_err(anyhow::Error::from)?,
+        ),
+        server_signature: None,
+    });
+    entries.push(package::PackageEntry {
+        name: package::AUDIT_TRAIL_ENTRY,
+        source: package::PackageSource::Generated(audit_trail),
+        server_signature: Some(audit_trail_signature),
+    });
+
+    let stream = package::stream_package(
+        state.storage.clone(),
+        id,
+        document.title.clone(),
+        document.completed_at.unwrap_or(document.updated_at),
+        entries,
+    );
+
+    Response::builder()
+        .header(header::CONTENT_TYPE, "application/zip")
+        .header(
+            header::CONTENT_DISPOSITION,
+            format!(
+                "attachment; filename=\"{}-package.zip\"",
+                pdf_stem(&document.original_filename)
+            ),
+        )
+        .body(axum::body::Body::from_stream(stream))
+        .map_err(|e| ApiError::Internal(e.into()))
+}
+
+#[utoipa::path(
+    get,
+    path = "/documents/{id}/timestamps",
+    tag = "audit",
+    params(("id" = Uuid, Path, description = "Document id")),
+    responses((status = 200, description = "The document's RFC 3161 timestamps", body = Vec<DocumentTimestampResponse>))
+)]
+pub async fn list_timestamps(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(id): Path<Uuid>,
+) -> ApiResult<Json<Vec<DocumentTimestampResponse>>> {
+    let document = db::document::get_document_by_id(&state.pool, id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Document not found".to_string()))?;
+
+    access::require(
+        &state.pool,
+        &document,
+        auth_user.user_id,
+        DocumentAccess::Viewer,
+    )
+    .await?;
+
+    let timestamps = db::timestamp::get_timestamps_by_document(&state.pool, id).await?;
+
+    Ok(Json(timestamps.into_iter().map(Into::into).collect()))
+}
+
+#[utoipa::path(
+    post,
+    path = "/documents/{id}/seal",
+    tag = "documents",
+    params(("id" = Uuid, Path, description = "Document id")),
+    responses((status = 200, description = "The sealed document", body = Document))
+)]
+pub async fn seal_document(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(id): Path<Uuid>,
+) -> ApiResult<Json<Document>> {
+    auth_user.require_sender()?;
+
+    let document = db::document::get_document_by_id(&state.pool, id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Document not found".to_string()))?;
+
+    if document.owner_id != auth_user.user_id {
+        return Err(ApiError::Forbidden);
+    }
+
+    if document.status != DocumentStatus::Completed {
+        return Err(ApiError::BadRequest(
+            "Only completed documents can be sealed".to_string(),
+        ));
+    }
+
+    let sealed = state
+        .document_sealer
+        .seal(&document)
+        .await?
+        .ok_or_else(|| ApiError::BadRequest("Document sealing is not configured".to_string()))?;
+
+    Ok(Json(sealed))
+}
+
+#[utoipa::path(
+    get,
+    path = "/documents/{id}/pages/{page}/preview.png",
+    tag = "documents",
+    params(
+        ("id" = Uuid, Path, description = "Document id"),
+        ("page" = u32, Path, description = "Page number, from 1"),
+        file_response::PreviewQuery,
+    ),
+    responses((status = 200, description = "PNG preview of the page", content_type = "image/png"))
+)]
+pub async fn get_page_preview(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path((id, page)): Path<(Uuid, u32)>,
+    Query(query): Query<file_response::PreviewQuery>,
+    headers: axum::http::HeaderMap,
+) -> ApiResult<axum::response::Response> {
+    let (ip_address, user_agent) = extract_client_info_from_headers(&headers);
+
+    let document = db::document::get_document_by_id(&state.pool, id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Document not found".to_string()))?;
+
+    access::require(
+        &state.pool,
+        &document,
+        auth_user.user_id,
+        DocumentAccess::Viewer,
+    )
+    .await?;
+
+    file_response::page_preview(
+        &state,
+        &document,
+        page,
+        &query,
+        &headers,
+        &IntegrityActor {
+            signer_id: None,
+            user_id: Some(auth_user.user_id),
+            ip_address: Some(&ip_address),
+            user_agent: Some(&user_agent),
+            source: SecurityEventSource::Request,
+        },
+    )
+    .await
+}
+
+#[utoipa::path(
+    get,
+    path = "/documents/{id}/download",
+    tag = "documents",
+    params(("id" = Uuid, Path, description = "Document id")),
+    responses(
+        (status = 200, description = "The document", content_type = "application/pdf"),
+        (status = 206, description = "The requested range of the document", content_type = "application/pdf"),
+        (status = 304, description = "Unchanged since the `If-None-Match` ETag"),
+    )
+)]
+pub async fn download_document(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(id): Path<Uuid>,
+    headers: axum::http::HeaderMap,
+) -> ApiResult<axum::response::Response> {
+    use axum::http::{header, Response};
+
+    let (ip_address, user_agent) = extract_client_info_from_headers(&headers);
+
+    let document = db::document::get_document_by_id(&state.pool, id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Document not found".to_string()))?;
+
+    access::require(
+        &state.pool,
+        &document,
+        auth_user.user_id,
+        DocumentAccess::Viewer,
+    )
+    .await?;
+
+    file_response::ensure_intact(
+        &state,
+        &document,
+        &headers,
+        &IntegrityActor {
+            signer_id: None,
+            user_id: Some(auth_user.user_id),
+            ip_address: Some(&ip_address),
+            user_agent: Some(&user_agent),
+            source: SecurityEventSource::Request,
+        },
+    )
+    .await?;
+
+    if file_response::is_initial_request(&headers) {
+        audit::log_action(
+            &state.pool,
+            state.hash_algorithm,
+            id,
+            None,
+            Some(auth_user.user_id),
+            AuditAction::DocumentDownloaded,
+            Some(&ip_address),
+            Some(&user_agent),
+            None,
+        )
+        .await?;
+    }
+
+    let builder = Response::builder()
+        .header(header::CONTENT_TYPE, "application/pdf")
+        .header(
+            header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"{}\"", document.original_filename),
+        );
+
+    file_response::stream_file(
+        state.storage.as_ref(),
+        &document.file_path,
+        document.original_encryption().as_ref(),
+        &file_response::etag_for_hash(&document.file_hash),
+        &headers,
+        builder,
+    )
+    .await
+}
+
+#[utoipa::path(
+    get,
+    path = "/documents/{id}/download/sealed",
+    tag = "documents",
+    params(("id" = Uuid, Path, description = "Document id")),
+    responses((status = 200, description = "The sealed copy", content_type = "application/pdf"))
+)]
+pub async fn download_sealed_document(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(id): Path<Uuid>,
+    headers: axum::http::HeaderMap,
+) -> ApiResult<axum::response::Response> {
+    use axum::http::{header, Response};
+
+    let (ip_address, user_agent) = extract_client_info_from_headers(&headers);
+
+    let document = db::document::get_document_by_id(&state.pool, id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Document not found".to_string()))?;
+
+    access::require(
+        &state.pool,
+        &document,
+        auth_user.user_id,
+        DocumentAccess::Viewer,
+    )
+    .await?;
+
+    let (Some(sealed_file_path), Some(sealed_file_hash)) =
+        (&document.sealed_file_path, &document.sealed_file_hash)
+    else {
+        return Err(ApiError::NotFound(
+            "Document has not been sealed".to_string(),
+        ));
+    };
+
+    if file_response::is_initial_request(&headers) {
+        audit::log_action(
+            &state.pool,
+            state.hash_algorithm,
+            id,
+            None,
+            Some(auth_user.user_id),
+            AuditAction::DocumentDownloaded,
+            Some(&ip_address),
+            Some(&user_agent),
+            Some(serde_json::json!({ "sealed": true })),
+        )
+        .await?;
+    }
+
+    let builder = Response::builder()
+        .header(header::CONTENT_TYPE, "application/pdf")
+        .header(
+            header::CONTENT_DISPOSITION,
+            format!(
+                "attachment; filename=\"{}\"",
+                sealed_filename(&document.original_filename)
+            ),
+        );
+
+    file_response::stream_file(
+        state.storage.as_ref(),
+        sealed_file_path,
+        document.sealed_encryption().as_ref(),
+        &file_response::etag_for_hash(sealed_file_hash),
+        &headers,
+        builder,
+    )
+    .await
+}
+
+/// The signatures applied to the document, without their images.
+#[utoipa::path(
+    get,
+    path = "/documents/{id}/signatures",
+    tag = "documents",
+    params(("id" = Uuid, Path, description = "Document id")),
+    responses((status = 200, description = "The document's signatures", body = Vec<Signature>))
+)]
+pub async fn list_signatures(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(id): Path<Uuid>,
+) -> ApiResult<Json<Vec<Signature>>> {
+    let document = db::document::get_document_by_id(&state.pool, id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Document not found".to_string()))?;
+
+    access::require(
+        &state.pool,
+        &document,
+        auth_user.user_id,
+        DocumentAccess::Owner,
+    )
+    .await?;
+
+    Ok(Json(
+        db::signature::get_signatures_by_document(&state.pool, id).await?,
+    ))
+}
+
+/// The image a signer drew or typed for a field, checked against the hash
+/// recorded when they signed.
+#[utoipa::path(
+    get,
+    path = "/documents/{id}/signatures/{signature_id}/image",
+    tag = "documents",
+    params(
+        ("id" = Uuid, Path, description = "Document id"),
+        ("signature_id" = Uuid, Path, description = "Signature id"),
+    ),
+    responses(
+        (status = 200, description = "The signature image", content_type = "image/png"),
+        (status = 404, description = "No such signature on this document", body = ErrorResponse),
+    )
+)]
+pub async fn get_signature_image(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path((id, signature_id)): Path<(Uuid, Uuid)>,
+) -> ApiResult<axum::response::Response> {
+    use axum::http::{header, Response};
+
+    let document = db::document::get_document_by_id(&state.pool, id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Document not found".to_string()))?;
+
+    access::require(
+        &state.pool,
+        &document,
+        auth_user.user_id,
+        DocumentAccess::Owner,
+    )
+    .await?;
+
+    let signature = db::signature::get_signature_by_id(&state.pool, signature_id)
+        .await?
+        .filter(|signature| signature.document_id == id)
+        .ok_or_else(|| ApiError::NotFound("Signature not found".to_string()))?;
+
+    let (content_type, bytes) =
+        signature_store::load_image(&state.pool, state.storage.as_ref(), &document, &signature)
+            .await?;
+
+    let data_url = signature_image::join_data_url(&content_type, &bytes);
+    if !crypto::verify_string(&signature.signature_hash, &data_url).unwrap_or(false) {
+        warn!(
+            "Image of signature {} does not match its recorded hash",
+            signature.id
+        );
+        return Err(ApiError::FileIntegrity);
+    }
+
+    Response::builder()
+        .header(header::CONTENT_TYPE, content_type)
+        .header(header::CACHE_CONTROL, "private, max-age=3600")
+        .body(bytes.into())
+        .map_err(|e| ApiError::Internal(e.into()))
+}
+
+/// `contract.pdf` becomes `contract-sealed.pdf`.
+fn pdf_stem(original_filename: &str) -> &str {
+    original_filename
+        .strip_suffix(".pdf")
+        .or_else(|| original_filename.strip_suffix(".PDF"))
+        .unwrap_or(original_filename)
+}
+
+pub(crate) fn sealed_filename(original_filename: &str) -> String {
+    format!("{}-sealed.pdf", pdf_stem(original_filename))
+}
+
+fn pdf_validation_error(e: PdfValidationError) -> ApiError {
+    match e {
+        PdfValidationError::PasswordProtected { .. } => ApiError::BadRequest(
+            "Password-protected PDFs are not supported — please remove the password and re-upload."
+                .to_string(),
+        ),
+        PdfValidationError::Other(e) => ApiError::Internal(e),
+        _ => ApiError::BadRequest(e.to_string()),
+    }
+}
+
+async fn load_pages(state: &AppState, document: &Document) -> ApiResult<Vec<DocumentPage>> {
+    pdf::load_pages(
+        &state.pool,
+        state.storage.as_ref(),
+        &state.pdf_workers,
+        document,
+    )
+    .await
+    .map_err(|e| ApiError::Internal(anyhow::anyhow!("Failed to read PDF: {}", e)))
+}
+
+/// Positions a field on page `page` of the document, in both coordinate
+/// spaces.
+fn field_geometry(
+    pages: &[DocumentPage],
+    page: i32,
+    rect: FieldRect,
+    space: CoordinateSpace,
+) -> Result<FieldGeometry, String> {
+    let page = pages
+        .iter()
+        .find(|p| p.number == page)
+        .ok_or_else(|| format!("Page {} does not exist", page))?;
+
+    FieldGeometry::new(rect, space, page)
+}
+
+/// Checks that a field of the document can be shown depending on `source_id`
+/// holding `value`.
+async fn check_condition(
+    state: &AppState,
+    document_id: Uuid,
+    field_id: Option<Uuid>,
+    signer_id: Option<Uuid>,
+    source_id: Uuid,
+    value: &str,
+) -> ApiResult<()> {
+    let fields = db::document::get_fields_by_document(&state.pool, document_id).await?;
+
+    field_conditions::validate_condition(field_id, signer_id, source_id, value, &fields)
+        .map_err(ApiError::BadRequest)
+}
+
+/// Refuses another signer on a document that already has `count`.
+fn check_signer_limit(state: &AppState, count: usize) -> ApiResult<()> {
+    let max = state.config.max_signers_per_document;
+    if count >= max {
+        return Err(ApiError::BadRequest(format!(
+            "A document can have at most {} signers",
+            max
+        )));
+    }
+
+    Ok(())
+}
+
+/// Refuses `email` for a signer of the document, other than `signer_id`, when
+/// another of its signers already has it, ignoring case and surrounding
+/// spaces.
+pub(crate) async fn check_signer_email(
+    state: &AppState,
+    document_id: Uuid,
+    email: &str,
+    signer_id: Option<Uuid>,
+) -> ApiResult<()> {
+    match db::signer::get_signer_by_document_email(&state.pool, document_id, email).await? {
+        Some(existing) if Some(existing.id) != signer_id => Err(email_taken(&existing)),
+        _ => Ok(()),
+    }
+}
+
+/// The error for a failed signer insert or update: a 409 naming the signer
+/// who has the email when a concurrent request got past
+/// `check_signer_email`.
+pub(crate) async fn signer_email_conflict(
+    state: &AppState,
+    document_id: Uuid,
+    email: &str,
+    error: anyhow::Error,
+) -> ApiError {
+    if !db::signer::is_duplicate_email(&error) {
+        return ApiError::Internal(error);
+    }
+
+    match db::signer::get_signer_by_document_email(&state.pool, document_id, email).await {
+        Ok(Some(existing)) => email_taken(&existing),
+        _ => ApiError::Conflict(format!(
+            "{} is already a signer of this document",
+            email.trim()
+        )),
+    }
+}
+
+fn email_taken(existing: &Signer) -> ApiError {
+    ApiError::Conflict(format!(
+        "{} is already a signer of this document: {} ({})",
+        existing.email, existing.name, existing.id
+    ))
+}
+
+/// Checks that a radio field is an option of a choice made by one signer.
+async fn check_radio_group(
+    state: &AppState,
+    document_id: Uuid,
+    field_id: Option<Uuid>,
+    field_type: FieldType,
+    signer_id: Option<Uuid>,
+    group_key: Option<&str>,
+) -> ApiResult<()> {
+    let fields = if field_type == FieldType::Radio && group_key.is_some() {
+        db::document::get_fields_by_document(&state.pool, document_id).await?
+    } else {
+        Vec::new()
+    };
+
+    radio_group::validate_group(field_id, field_type, signer_id, group_key, &fields)
+        .map_err(ApiError::BadRequest)
+}
+
+/// Checks the constraints of a field and its prefilled value, returning the
+/// value normalized to the field's precision for a number field.
+fn check_number_field(
+    field_type: FieldType,
+    constraints: &NumberConstraints,
+    value: Option<&str>,
+) -> ApiResult<Option<String>> {
+    if field_type != FieldType::Number {
+        if *constraints != NumberConstraints::default() {
+            return Err(ApiError::BadRequest(
+                "min_value, max_value and decimal_places only apply to number fields".to_string(),
+            ));
+        }
+        return Ok(None);
+    }
+
+    constraints.validate().map_err(ApiError::BadRequest)?;
+
+    value
+        .filter(|v| !v.trim().is_empty())
+        .map(|v| {
+            constraints
+                .normalize(v)
+                .map_err(|e| ApiError::BadRequest(format!("Invalid value: {}", e)))
+        })
+        .transpose()
+}
+
+/// Checks that a read-only field holds a value for signers to see. Signature
+/// and initial fields cannot be prefilled.
+fn check_read_only(field_type: FieldType, read_only: bool, value: Option<&str>) -> ApiResult<()> {
+    if !read_only {
+        return Ok(());
+    }
+
+    if matches!(field_type, FieldType::Signature | FieldType::Initial) {
+        return Err(ApiError::BadRequest(
+            "Signature and initial fields cannot be read-only".to_string(),
+        ));
+    }
+
+    if value.map(str::trim).unwrap_or_default().is_empty() {
+        return Err(ApiError::BadRequest(
+            "A read-only field needs a value".to_string(),
+        ));
+    }
 
-    Ok(response)
+    Ok(())
 }
@@ -6,6 +6,9 @@ use axum::{
 use serde::Serialize;
 use thiserror::Error;
 use tracing::error;
+use utoipa::ToSchema;
+
+use crate::utils::request_id;
 
 #[derive(Error, Debug)]
 pub enum ApiError {
@@ -24,9 +27,35 @@ pub enum ApiError {
     #[error("Conflict: {0}")]
     Conflict(String),
 
+    /// A conditional edit whose resource changed since the client read it.
+    /// Carries the resource as it is now, so the client can merge.
+    #[error("Conflict: modified since it was read")]
+    EditConflict(serde_json::Value),
+
     #[error("Validation error: {0}")]
     Validation(String),
 
+    /// An upload that does not fit in the user's storage quota.
+    #[error("Storage quota exceeded: {0}")]
+    QuotaExceeded(String),
+
+    #[error("Too many requests: {0}")]
+    TooManyRequests(String),
+
+    #[error("File integrity check failed")]
+    FileIntegrity,
+
+    /// A share or signing link that expired or was revoked.
+    #[error("Link expired: {0}")]
+    LinkExpired(String),
+
+    /// A share link protected by a passcode that was missing or wrong.
+    #[error("Passcode required: {0}")]
+    PasscodeRequired(String),
+
+    #[error("Service unavailable: {0}")]
+    ServiceUnavailable(String),
+
     #[error("Internal server error")]
     Internal(#[from] anyhow::Error),
 
@@ -34,25 +63,61 @@ pub enum ApiError {
     Database(#[from] sqlx::Error),
 }
 
-#[derive(Serialize)]
-struct ErrorResponse {
-    error: String,
-    message: String,
+/// The body of every error response.
+#[derive(Serialize, ToSchema)]
+pub struct ErrorResponse {
+    /// Machine-readable kind, e.g. `not_found`.
+    pub error: String,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+    /// The resource as it is now, for `edit_conflict` errors.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub current: Option<serde_json::Value>,
 }
 
-impl IntoResponse for ApiError {
-    fn into_response(self) -> Response {
-        let (status, error_type, message) = match &self {
+impl ApiError {
+    /// The status, machine-readable kind and message the error is reported
+    /// with. Internal errors are logged here, as their details are not.
+    pub fn describe(&self) -> (StatusCode, &'static str, String) {
+        match self {
             ApiError::Unauthorized => (StatusCode::UNAUTHORIZED, "unauthorized", self.to_string()),
             ApiError::Forbidden => (StatusCode::FORBIDDEN, "forbidden", self.to_string()),
             ApiError::NotFound(msg) => (StatusCode::NOT_FOUND, "not_found", msg.clone()),
             ApiError::BadRequest(msg) => (StatusCode::BAD_REQUEST, "bad_request", msg.clone()),
             ApiError::Conflict(msg) => (StatusCode::CONFLICT, "conflict", msg.clone()),
+            ApiError::EditConflict(_) => (
+                StatusCode::CONFLICT,
+                "edit_conflict",
+                "The resource was modified since it was read".to_string(),
+            ),
             ApiError::Validation(msg) => (
                 StatusCode::UNPROCESSABLE_ENTITY,
                 "validation_error",
                 msg.clone(),
             ),
+            ApiError::QuotaExceeded(msg) => {
+                (StatusCode::PAYLOAD_TOO_LARGE, "quota_exceeded", msg.clone())
+            }
+            ApiError::TooManyRequests(msg) => (
+                StatusCode::TOO_MANY_REQUESTS,
+                "too_many_requests",
+                msg.clone(),
+            ),
+            ApiError::FileIntegrity => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "file_integrity_violation",
+                "The document file failed its integrity check and cannot be served".to_string(),
+            ),
+            ApiError::LinkExpired(msg) => (StatusCode::GONE, "link_expired", msg.clone()),
+            ApiError::PasscodeRequired(msg) => {
+                (StatusCode::UNAUTHORIZED, "passcode_required", msg.clone())
+            }
+            ApiError::ServiceUnavailable(msg) => (
+                StatusCode::SERVICE_UNAVAILABLE,
+                "service_unavailable",
+                msg.clone(),
+            ),
             ApiError::Internal(e) => {
                 error!("Internal error: {:?}", e);
                 (
@@ -69,11 +134,24 @@ impl IntoResponse for ApiError {
                     "A database error occurred".to_string(),
                 )
             }
+        }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let (status, error_type, message) = self.describe();
+
+        let current = match self {
+            ApiError::EditConflict(current) => Some(current),
+            _ => None,
         };
 
         let body = Json(ErrorResponse {
             error: error_type.to_string(),
             message,
+            request_id: request_id::current(),
+            current,
         });
 
         (status, body).into_response()
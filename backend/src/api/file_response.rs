@@ -0,0 +1,347 @@
+use axum::{
+    body::Body,
+    http::{header, response::Builder, HeaderMap, Response, StatusCode},
+};
+use serde::Deserialize;
+use utoipa::IntoParams;
+
+use crate::api::error::{ApiError, ApiResult};
+use crate::api::state::AppState;
+use crate::models::document::Document;
+use crate::models::encryption::FileEncryption;
+use crate::services::integrity::IntegrityActor;
+use crate::services::pdf::{self, RenderError};
+use crate::services::preview::{DEFAULT_PREVIEW_WIDTH, MIN_PREVIEW_WIDTH};
+use crate::services::storage::Storage;
+
+/// A satisfiable byte range, inclusive on both ends.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ByteRange {
+    pub start: u64,
+    pub end: u64,
+}
+
+/// How a `Range` request header should be answered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RangeRequest {
+    /// Send the whole file, also used for multi-range requests as only single
+    /// ranges are supported.
+    Full,
+    Partial(ByteRange),
+    /// Malformed or out of bounds.
+    Unsatisfiable,
+}
+
+/// Parses a `Range` header against a file of `len` bytes.
+pub fn parse_range(value: &str, len: u64) -> RangeRequest {
+    let Some(spec) = value.trim().strip_prefix("bytes=") else {
+        return RangeRequest::Unsatisfiable;
+    };
+
+    if spec.contains(',') {
+        return RangeRequest::Full;
+    }
+
+    parse_single_range(spec, len).map_or(RangeRequest::Unsatisfiable, RangeRequest::Partial)
+}
+
+fn parse_single_range(spec: &str, len: u64) -> Option<ByteRange> {
+    let (start, end) = spec.split_once('-')?;
+    let (start, end) = (start.trim(), end.trim());
+
+    if start.is_empty() {
+        let suffix: u64 = end.parse().ok()?;
+        if suffix == 0 || len == 0 {
+            return None;
+        }
+        return Some(ByteRange {
+            start: len.saturating_sub(suffix),
+            end: len - 1,
+        });
+    }
+
+    let start: u64 = start.parse().ok()?;
+    let end = if end.is_empty() {
+        len.saturating_sub(1)
+    } else {
+        end.parse::<u64>().ok()?.min(len.saturating_sub(1))
+    };
+
+    if start >= len || start > end {
+        return None;
+    }
+
+    Some(ByteRange { start, end })
+}
+
+/// Whether the request asks for the beginning of the file, as opposed to a
+/// later chunk fetched by a viewer that is seeking through it.
+pub fn is_initial_request(headers: &HeaderMap) -> bool {
+    let spec = headers
+        .get(header::RANGE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().strip_prefix("bytes="));
+
+    match spec {
+        Some(spec) => spec.trim_start().starts_with("0-"),
+        None => true,
+    }
+}
+
+/// Strong entity tag for a file identified by its content hash.
+pub fn etag_for_hash(file_hash: &str) -> String {
+    format!("\"{}\"", file_hash)
+}
+
+/// Whether an `If-None-Match` header matches `etag`, using the weak comparison
+/// required for that header.
+pub fn if_none_match(request_headers: &HeaderMap, etag: &str) -> bool {
+    let etag = etag.trim_start_matches("W/");
+
+    request_headers
+        .get_all(header::IF_NONE_MATCH)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .map(str::trim)
+        .any(|candidate| candidate == "*" || candidate.trim_start_matches("W/") == etag)
+}
+
+/// Refuses to serve a document whose stored file no longer matches its upload
+/// hash. Ranged requests reuse a recent verdict rather than hashing the whole
+/// file for every chunk, and requests answered with 304 are not checked.
+pub async fn ensure_intact(
+    state: &AppState,
+    document: &Document,
+    request_headers: &HeaderMap,
+    actor: &IntegrityActor<'_>,
+) -> ApiResult<()> {
+    if if_none_match(request_headers, &etag_for_hash(&document.file_hash)) {
+        return Ok(());
+    }
+
+    let allow_cached = request_headers.contains_key(header::RANGE);
+    let intact = state
+        .file_integrity
+        .check(document, allow_cached, actor)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!("Failed to verify file: {}", e)))?;
+
+    if !intact {
+        return Err(ApiError::FileIntegrity);
+    }
+
+    Ok(())
+}
+
+/// Streams a stored file, decrypting it if it is encrypted, honouring
+/// `If-None-Match` against `etag` and a single-range `Range` request header.
+/// `builder` carries the response headers specific to the caller.
+pub async fn stream_file(
+    storage: &dyn Storage,
+    key: &str,
+    encryption: Option<&FileEncryption>,
+    etag: &str,
+    request_headers: &HeaderMap,
+    builder: Builder,
+) -> ApiResult<Response<Body>> {
+    let builder = builder.header(header::ETAG, etag);
+
+    if if_none_match(request_headers, etag) {
+        return builder
+            .status(StatusCode::NOT_MODIFIED)
+            .body(Body::empty())
+            .map_err(|e| ApiError::Internal(anyhow::anyhow!("Failed to build response: {}", e)));
+    }
+
+    let len = storage
+        .file_size(key, encryption)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!("Failed to read file: {}", e)))?;
+
+    let range = match request_headers.get(header::RANGE) {
+        None => RangeRequest::Full,
+        Some(value) => value
+            .to_str()
+            .map_or(RangeRequest::Unsatisfiable, |v| parse_range(v, len)),
+    };
+
+    let builder = builder.header(header::ACCEPT_RANGES, "bytes");
+
+    let response = match range {
+        RangeRequest::Full => builder
+            .header(header::CONTENT_LENGTH, len)
+            .body(Body::from_stream(
+                read(storage, key, None, encryption).await?,
+            )),
+        RangeRequest::Unsatisfiable => Response::builder()
+            .status(StatusCode::RANGE_NOT_SATISFIABLE)
+            .header(header::CONTENT_RANGE, format!("bytes */{}", len))
+            .body(Body::empty()),
+        RangeRequest::Partial(ByteRange { start, end }) => builder
+            .status(StatusCode::PARTIAL_CONTENT)
+            .header(
+                header::CONTENT_RANGE,
+                format!("bytes {}-{}/{}", start, end, len),
+            )
+            .header(header::CONTENT_LENGTH, end - start + 1)
+            .body(Body::from_stream(
+                read(storage, key, Some(start..end + 1), encryption).await?,
+            )),
+    };
+
+    response.map_err(|e| ApiError::Internal(anyhow::anyhow!("Failed to build response: {}", e)))
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct PreviewQuery {
+    /// Width in pixels, 800 by default.
+    pub width: Option<u32>,
+}
+
+/// Serves a PNG preview of page `page` of a document the caller may view,
+/// rendering it on first request.
+pub async fn page_preview(
+    state: &AppState,
+    document: &Document,
+    page: u32,
+    query: &PreviewQuery,
+    request_headers: &HeaderMap,
+    actor: &IntegrityActor<'_>,
+) -> ApiResult<Response<Body>> {
+    let max_width = state.page_previews.max_width();
+    let width = query
+        .width
+        .unwrap_or_else(|| DEFAULT_PREVIEW_WIDTH.min(max_width));
+
+    if !(MIN_PREVIEW_WIDTH..=max_width).contains(&width) {
+        return Err(ApiError::BadRequest(format!(
+            "Preview width must be between {} and {} pixels",
+            MIN_PREVIEW_WIDTH, max_width
+        )));
+    }
+
+    let pages = pdf::load_pages(
+        &state.pool,
+        state.storage.as_ref(),
+        &state.pdf_workers,
+        document,
+    )
+    .await
+    .map_err(|e| ApiError::Internal(anyhow::anyhow!("Failed to read PDF: {}", e)))?;
+
+    if page == 0 || page as usize > pages.len() {
+        return Err(ApiError::NotFound("Page not found".to_string()));
+    }
+
+    let etag = format!("\"{}-{}-{}\"", document.file_hash, page, width);
+    let builder = Response::builder()
+        .header(header::CONTENT_TYPE, "image/png")
+        .header(header::CACHE_CONTROL, "private, max-age=0, must-revalidate")
+        .header(header::ETAG, &etag);
+
+    if if_none_match(request_headers, &etag) {
+        return builder
+            .status(StatusCode::NOT_MODIFIED)
+            .body(Body::empty())
+            .map_err(|e| ApiError::Internal(anyhow::anyhow!("Failed to build response: {}", e)));
+    }
+
+    // Previews are requested a page at a time, so a recent verdict is enough
+    let intact = state
+        .file_integrity
+        .check(document, true, actor)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!("Failed to verify file: {}", e)))?;
+
+    if !intact {
+        return Err(ApiError::FileIntegrity);
+    }
+
+    let png = state
+        .page_previews
+        .get(document, page, width)
+        .await
+        .map_err(|e| match e {
+            RenderError::Unavailable(_) => {
+                tracing::warn!("{}", e);
+                ApiError::ServiceUnavailable("Page previews are not available".to_string())
+            }
+            RenderError::Other(e) => ApiError::Internal(e),
+        })?;
+
+    builder
+        .header(header::CONTENT_LENGTH, png.len())
+        .body(Body::from(png))
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!("Failed to build response: {}", e)))
+}
+
+async fn read(
+    storage: &dyn Storage,
+    key: &str,
+    range: Option<std::ops::Range<u64>>,
+    encryption: Option<&FileEncryption>,
+) -> ApiResult<crate::services::storage::ByteStream> {
+    storage
+        .stream_file(key, range, encryption)
+        .await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!("Failed to read file: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn partial(start: u64, end: u64) -> RangeRequest {
+        RangeRequest::Partial(ByteRange { start, end })
+    }
+
+    #[test]
+    fn test_parse_range_forms() {
+        assert_eq!(parse_range("bytes=0-99", 1000), partial(0, 99));
+        assert_eq!(parse_range("bytes=900-", 1000), partial(900, 999));
+        assert_eq!(parse_range("bytes=-100", 1000), partial(900, 999));
+        assert_eq!(parse_range("bytes=990-2000", 1000), partial(990, 999));
+    }
+
+    #[test]
+    fn test_if_none_match() {
+        let etag = etag_for_hash("abc123");
+        let headers = |value: &str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(header::IF_NONE_MATCH, value.parse().unwrap());
+            headers
+        };
+
+        assert!(if_none_match(&headers("\"abc123\""), &etag));
+        assert!(if_none_match(&headers("\"other\", W/\"abc123\""), &etag));
+        assert!(if_none_match(&headers("*"), &etag));
+        assert!(!if_none_match(&headers("\"other\""), &etag));
+        assert!(!if_none_match(&HeaderMap::new(), &etag));
+    }
+
+    #[test]
+    fn test_parse_range_multiple_ranges_serve_whole_file() {
+        assert_eq!(parse_range("bytes=0-10, 20-30", 1000), RangeRequest::Full);
+    }
+
+    #[test]
+    fn test_parse_range_rejects_invalid() {
+        for value in [
+            "bytes=1000-",
+            "bytes=50-10",
+            "bytes=-0",
+            "bytes=abc",
+            "items=0-10",
+            "bytes=x-10",
+        ] {
+            assert_eq!(
+                parse_range(value, 1000),
+                RangeRequest::Unsatisfiable,
+                "{}",
+                value
+            );
+        }
+    }
+}
@@ -0,0 +1,288 @@
+use axum::{
+    body::{Body, Bytes},
+    extract::{FromRequest, Request, State},
+    http::{header, HeaderValue, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Extension,
+};
+use sha2::{Digest, Sha256};
+use tracing::{error, warn};
+
+use crate::api::error::{ApiError, ApiResult};
+use crate::api::middleware::AuthUser;
+use crate::api::state::AppState;
+use crate::db;
+use crate::models::idempotency::KeyClaim;
+
+pub const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+/// Set on responses replayed from an earlier request with the same key.
+pub const IDEMPOTENT_REPLAYED_HEADER: &str = "idempotent-replayed";
+
+/// How long a key is remembered after its first use.
+pub const KEY_TTL_HOURS: i64 = 24;
+const MAX_KEY_LEN: usize = 255;
+/// How long a key stays claimed by a request that has not finished. A retry
+/// after that runs the request again, e.g. when the server stopped mid-request.
+const CLAIM_LEASE_SECS: i64 = 600;
+
+/// Honours an `Idempotency-Key` header: the first request with a key is
+/// executed and its successful response stored; retries with the same key and
+/// payload get that response back instead of executing again, and reusing the
+/// key for a different payload is a 409. Failed requests are not stored, so
+/// they can be retried. Requests without the header pass straight through.
+///
+/// The key is claimed before the request runs and its response stored after,
+/// each in a short transaction of its own, so no connection is held while the
+/// handler runs. A request with a key that is still claimed by one in progress
+/// is a 409.
+pub async fn idempotency_middleware(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    request: Request,
+    next: Next,
+) -> ApiResult<Response> {
+    let Some(key) = request.headers().get(IDEMPOTENCY_KEY_HEADER) else {
+        return Ok(next.run(request).await);
+    };
+    let key = key
+        .to_str()
+        .ok()
+        .map(str::trim)
+        .filter(|key| !key.is_empty() && key.len() <= MAX_KEY_LEN)
+        .ok_or_else(|| {
+            ApiError::BadRequest(format!(
+                "Idempotency-Key must be 1 to {} visible ASCII characters",
+                MAX_KEY_LEN
+            ))
+        })?
+        .to_string();
+
+    let (parts, body) = request.into_parts();
+
+    // Read the body through the extractor so the route's body limit applies.
+    let mut buffered = Request::new(body);
+    *buffered.headers_mut() = parts.headers.clone();
+    *buffered.extensions_mut() = parts.extensions.clone();
+    let body = match Bytes::from_request(buffered, &()).await {
+        Ok(body) => body,
+        Err(rejection) => return Ok(rejection.into_response()),
+    };
+
+    let boundary = parts
+        .headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(multipart_boundary);
+    let request_hash = fingerprint(&parts.method, parts.uri.path(), boundary, &body);
+
+    let claim = db::idempotency::claim_key(
+        &state.pool,
+        auth_user.user_id,
+        &key,
+        &request_hash,
+        KEY_TTL_HOURS,
+        CLAIM_LEASE_SECS,
+    )
+    .await?;
+
+    let claimed_at = match claim {
+        KeyClaim::Claimed { claimed_at } => claimed_at,
+        KeyClaim::Taken(existing) => {
+            if existing.request_hash != request_hash {
+                return Err(ApiError::Conflict(
+                    "Idempotency-Key was already used for a different request".to_string(),
+                ));
+            }
+
+            return match existing.response_status {
+                Some(status) => Ok(replay(
+                    status,
+                    existing.response_content_type.as_deref(),
+                    existing.response_body.unwrap_or_default(),
+                )),
+                None => Err(ApiError::Conflict(
+                    "A request with this Idempotency-Key is still in progress".to_string(),
+                )),
+            };
+        }
+    };
+
+    let response = next.run(Request::from_parts(parts, Body::from(body))).await;
+    if !response.status().is_success() {
+        if let Err(e) =
+            db::idempotency::release_key(&state.pool, auth_user.user_id, &key, claimed_at).await
+        {
+            // The key frees itself once its claim lapses.
+            error!("Failed to release idempotency key: {:?}", e);
+        }
+        return Ok(response);
+    }
+
+    let (parts, body) = response.into_parts();
+    let body = axum::body::to_bytes(body, usize::MAX)
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to read response body: {}", e))?;
+
+    let content_type = parts
+        .headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok());
+    match db::idempotency::store_response(
+        &state.pool,
+        auth_user.user_id,
+        &key,
+        claimed_at,
+        parts.status.as_u16() as i16,
+        content_type,
+        &body,
+    )
+    .await
+    {
+        Ok(true) => {}
+        // The request outlived its claim and a retry took the key over.
+        Ok(false) => warn!("Idempotency key was claimed by a retry before its response was stored"),
+        // The request did go through; only a retry would run it again.
+        Err(e) => error!("Failed to store response for idempotency key: {:?}", e),
+    }
+
+    Ok(Response::from_parts(parts, Body::from(body)))
+}
+
+fn replay(status: i16, content_type: Option<&str>, body: Vec<u8>) -> Response {
+    let mut response = Response::new(Body::from(body));
+    *response.status_mut() = StatusCode::from_u16(status as u16).unwrap_or(StatusCode::OK);
+
+    let headers = response.headers_mut();
+    if let Some(value) = content_type.and_then(|value| HeaderValue::from_str(value).ok()) {
+        headers.insert(header::CONTENT_TYPE, value);
+    }
+    headers.insert(IDEMPOTENT_REPLAYED_HEADER, HeaderValue::from_static("true"));
+
+    response
+}
+
+/// The `boundary` parameter of a `multipart/*` content type.
+fn multipart_boundary(content_type: &str) -> Option<&str> {
+    let mut params = content_type.split(';');
+    if !params
+        .next()?
+        .trim()
+        .to_ascii_lowercase()
+        .starts_with("multipart/")
+    {
+        return None;
+    }
+
+    params.find_map(|param| {
+        let (name, value) = param.split_once('=')?;
+        name.trim()
+            .eq_ignore_ascii_case("boundary")
+            .then(|| value.trim().trim_matches('"'))
+            .filter(|boundary| !boundary.is_empty())
+    })
+}
+
+/// Hex SHA-256 of the method, path and body of a request. Multipart
+/// boundaries are left out, as clients pick a new random one on each retry.
+fn fingerprint(method: &Method, path: &str, boundary: Option<&str>, body: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(method.as_str());
+    hasher.update(b" ");
+    hasher.update(path);
+    hasher.update(b"\n");
+
+    match boundary {
+        Some(boundary) => {
+            let boundary = boundary.as_bytes();
+            let mut rest = body;
+            while let Some(at) = rest
+                .windows(boundary.len())
+                .position(|window| window == boundary)
+            {
+                hasher.update(&rest[..at]);
+                hasher.update(b"\0boundary\0");
+                rest = &rest[at + boundary.len()..];
+            }
+            hasher.update(rest);
+        }
+        None => hasher.update(body),
+    }
+
+    hex::encode(hasher.finalize())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_multipart_boundary() {
+        assert_eq!(
+            multipart_boundary("multipart/form-data; boundary=----abc123"),
+            Some("----abc123")
+        );
+        assert_eq!(
+            multipart_boundary("Multipart/Form-Data;charset=utf-8; Boundary=\"x y\""),
+            Some("x y")
+        );
+        assert_eq!(multipart_boundary("multipart/form-data"), None);
+        assert_eq!(multipart_boundary("multipart/form-data; boundary="), None);
+        assert_eq!(multipart_boundary("application/json"), None);
+    }
+
+    #[test]
+    fn test_fingerprint_ignores_multipart_boundaries() {
+        let body = |boundary: &str| {
+            format!(
+                "--{b}\r\nContent-Disposition: form-data; name=\"title\"\r\n\r\nLease\r\n--{b}--\r\n",
+                b = boundary
+            )
+        };
+
+        let first = fingerprint(
+            &Method::POST,
+            "/documents",
+            Some("aaaa"),
+            body("aaaa").as_bytes(),
+        );
+        let retry = fingerprint(
+            &Method::POST,
+            "/documents",
+            Some("bbbbbbbb"),
+            body("bbbbbbbb").as_bytes(),
+        );
+        assert_eq!(first, retry);
+
+        let changed = body("aaaa").replace("Lease", "Loan");
+        assert_ne!(
+            first,
+            fingerprint(
+                &Method::POST,
+                "/documents",
+                Some("aaaa"),
+                changed.as_bytes()
+            )
+        );
+    }
+
+    #[test]
+    fn test_fingerprint_covers_method_and_path() {
+        let body = br#"{"email":"a@example.com"}"#;
+        let hash = fingerprint(&Method::POST, "/documents/1/signers", None, body);
+
+        assert_eq!(hash.len(), 64);
+        assert_eq!(
+            hash,
+            fingerprint(&Method::POST, "/documents/1/signers", None, body)
+        );
+        assert_ne!(
+            hash,
+            fingerprint(&Method::POST, "/documents/2/signers", None, body)
+        );
+        assert_ne!(
+            hash,
+            fingerprint(&Method::PUT, "/documents/1/signers", None, body)
+        );
+    }
+}
@@ -1,21 +1,46 @@
 use axum::{
-    extract::{Request, State},
-    http::header,
+    extract::{ConnectInfo, Request, State},
+    http::{header, HeaderMap, HeaderValue},
     middleware::Next,
     response::Response,
 };
-use jsonwebtoken::{decode, DecodingKey, Validation};
+use ipnetwork::IpNetwork;
+use std::net::{IpAddr, SocketAddr};
+use tracing::Instrument;
 use uuid::Uuid;
 
 use crate::api::error::{ApiError, ApiResult};
 use crate::api::state::AppState;
-use crate::models::user::Claims;
+use crate::db;
+use crate::models::user::{Claims, Role};
+use crate::utils::request_id::{self, REQUEST_ID_HEADER};
 
+/// The caller of a protected route. Only the id identifies the user: claims
+/// such as the email can be stale, as they are fixed when the token is
+/// issued.
 #[derive(Clone, Debug)]
 pub struct AuthUser {
     pub user_id: Uuid,
-    pub email: String,
-    pub is_admin: bool,
+    pub role: Role,
+    pub session_id: Uuid,
+}
+
+impl AuthUser {
+    pub fn require_admin(&self) -> ApiResult<()> {
+        if !self.role.is_admin() {
+            return Err(ApiError::Forbidden);
+        }
+        Ok(())
+    }
+
+    /// For routes that create, change or share documents, which viewers may
+    /// not.
+    pub fn require_sender(&self) -> ApiResult<()> {
+        if !self.role.can_send() {
+            return Err(ApiError::Forbidden);
+        }
+        Ok(())
+    }
 }
 
 pub async fn auth_middleware(
@@ -33,17 +58,19 @@ pub async fn auth_middleware(
         .strip_prefix("Bearer ")
         .ok_or(ApiError::Unauthorized)?;
 
-    let token_data = decode::<Claims>(
-        token,
-        &DecodingKey::from_secret(state.config.jwt_secret.as_bytes()),
-        &Validation::default(),
-    )
-    .map_err(|_| ApiError::Unauthorized)?;
+    let claims: Claims = state
+        .jwt_keys
+        .decode(token)
+        .map_err(|_| ApiError::Unauthorized)?;
+
+    if !db::session::use_session(&state.pool, claims.sid, claims.user_id).await? {
+        return Err(ApiError::Unauthorized);
+    }
 
     let auth_user = AuthUser {
-        user_id: token_data.claims.user_id,
-        email: token_data.claims.email,
-        is_admin: token_data.claims.is_admin,
+        user_id: claims.user_id,
+        role: claims.role,
+        session_id: claims.sid,
     };
 
     request.extensions_mut().insert(auth_user);
@@ -51,22 +78,112 @@ pub async fn auth_middleware(
     Ok(next.run(request).await)
 }
 
+/// Tags each request with an id, honouring a valid incoming `X-Request-Id`.
+/// The id is attached to the tracing span, available to audit logging and
+/// error responses through `request_id::current()`, and echoed back.
+pub async fn request_id_middleware(request: Request, next: Next) -> Response {
+    let id = request_id::from_incoming(
+        request
+            .headers()
+            .get(REQUEST_ID_HEADER)
+            .and_then(|h| h.to_str().ok()),
+    );
+
+    let span = tracing::info_span!("request", request_id = %id);
+    let mut response = request_id::scope(id.clone(), next.run(request))
+        .instrument(span)
+        .await;
+
+    if let Ok(value) = HeaderValue::from_str(&id) {
+        response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+
+    response
+}
+
+/// Internal header carrying the client IP resolved by `client_ip_middleware`,
+/// in the form `IP_ADDRESS_MODE` records it. Any value sent by the client
+/// itself is overwritten.
+pub const CLIENT_IP_HEADER: &str = "x-signvault-client-ip";
+
+pub async fn client_ip_middleware(
+    State(state): State<AppState>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let peer = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip());
+
+    let client_ip = resolve_client_ip(peer, request.headers(), &state.config.trusted_proxies)
+        .map(|ip| state.ip_privacy.record(ip))
+        .unwrap_or_else(|| "unknown".to_string());
+
+    if let Ok(value) = HeaderValue::from_str(&client_ip) {
+        request.headers_mut().insert(CLIENT_IP_HEADER, value);
+    }
+
+    next.run(request).await
+}
+
+/// Determines the originating client IP. Forwarding headers are only honoured
+/// when the connection comes from a trusted proxy, in which case the
+/// `X-Forwarded-For` chain is walked from the right, skipping trusted hops.
+pub fn resolve_client_ip(
+    peer: Option<IpAddr>,
+    headers: &HeaderMap,
+    trusted_proxies: &[IpNetwork],
+) -> Option<IpAddr> {
+    let peer = peer?;
+    let is_trusted = |ip: IpAddr| trusted_proxies.iter().any(|net| net.contains(ip));
+
+    if !is_trusted(peer) {
+        return Some(peer);
+    }
+
+    let forwarded: Vec<&str> = headers
+        .get_all("x-forwarded-for")
+        .iter()
+        .filter_map(|h| h.to_str().ok())
+        .flat_map(|h| h.split(','))
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .collect();
+
+    if forwarded.is_empty() {
+        return headers
+            .get("x-real-ip")
+            .and_then(|h| h.to_str().ok())
+            .and_then(|h| h.trim().parse().ok())
+            .or(Some(peer));
+    }
+
+    let mut client = peer;
+    for entry in forwarded.iter().rev() {
+        // Stop at malformed entries; anything left of them is untrustworthy
+        let Ok(ip) = entry.parse::<IpAddr>() else {
+            break;
+        };
+        client = ip;
+        if !is_trusted(ip) {
+            break;
+        }
+    }
+
+    Some(client)
+}
+
 pub fn extract_client_info(request: &Request) -> (String, String) {
     extract_client_info_from_headers(request.headers())
 }
 
-pub fn extract_client_info_from_headers(headers: &axum::http::HeaderMap) -> (String, String) {
+pub fn extract_client_info_from_headers(headers: &HeaderMap) -> (String, String) {
     let ip = headers
-        .get("x-forwarded-for")
+        .get(CLIENT_IP_HEADER)
         .and_then(|h| h.to_str().ok())
-        .map(|s| s.split(',').next().unwrap_or("unknown").trim().to_string())
-        .or_else(|| {
-            headers
-                .get("x-real-ip")
-                .and_then(|h| h.to_str().ok())
-                .map(|s| s.to_string())
-        })
-        .unwrap_or_else(|| "unknown".to_string());
+        .unwrap_or("unknown")
+        .to_string();
 
     let user_agent = headers
         .get(header::USER_AGENT)
@@ -76,3 +193,55 @@ pub fn extract_client_info_from_headers(headers: &axum::http::HeaderMap) -> (Str
 
     (ip, user_agent)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(pairs: &[(&'static str, &str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.append(*name, HeaderValue::from_str(value).unwrap());
+        }
+        headers
+    }
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_untrusted_peer_ignores_forwarded_headers() {
+        let headers = headers(&[("x-forwarded-for", "1.2.3.4"), ("x-real-ip", "1.2.3.4")]);
+        let resolved = resolve_client_ip(Some(ip("203.0.113.9")), &headers, &[]);
+        assert_eq!(resolved, Some(ip("203.0.113.9")));
+    }
+
+    #[test]
+    fn test_trusted_proxy_chain_is_walked_from_the_right() {
+        let trusted = vec!["10.0.0.0/8".parse().unwrap()];
+        let headers = headers(&[("x-forwarded-for", "6.6.6.6, 198.51.100.7, 10.0.0.3")]);
+        let resolved = resolve_client_ip(Some(ip("10.0.0.2")), &headers, &trusted);
+        assert_eq!(resolved, Some(ip("198.51.100.7")));
+    }
+
+    #[test]
+    fn test_trusted_proxy_falls_back_to_real_ip_then_peer() {
+        let trusted = vec!["10.0.0.2".parse().unwrap()];
+
+        let with_real_ip = headers(&[("x-real-ip", "198.51.100.7")]);
+        let resolved = resolve_client_ip(Some(ip("10.0.0.2")), &with_real_ip, &trusted);
+        assert_eq!(resolved, Some(ip("198.51.100.7")));
+
+        let resolved = resolve_client_ip(Some(ip("10.0.0.2")), &HeaderMap::new(), &trusted);
+        assert_eq!(resolved, Some(ip("10.0.0.2")));
+    }
+
+    #[test]
+    fn test_malformed_forwarded_entry_stops_the_walk() {
+        let trusted = vec!["10.0.0.0/8".parse().unwrap()];
+        let headers = headers(&[("x-forwarded-for", "198.51.100.7, garbage, 10.0.0.3")]);
+        let resolved = resolve_client_ip(Some(ip("10.0.0.2")), &headers, &trusted);
+        assert_eq!(resolved, Some(ip("10.0.0.3")));
+    }
+}
@@ -1,7 +1,341 @@
+pub mod access;
+pub mod admin;
 pub mod auth;
+pub mod branding;
+pub mod certificates;
 pub mod documents;
 pub mod error;
+pub mod file_response;
+pub mod idempotency;
 pub mod middleware;
+pub mod precondition;
 pub mod routes;
+pub mod server_key;
+pub mod shared;
 pub mod signing;
 pub mod state;
+pub mod status;
+pub mod two_factor;
+
+use utoipa::openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme};
+use utoipa::openapi::{ContentBuilder, RefOr, ResponseBuilder};
+use utoipa::{Modify, OpenApi};
+
+use crate::api::error::ErrorResponse;
+
+/// Name of the bearer token security scheme in the OpenAPI document.
+const BEARER_SCHEME: &str = "bearer";
+const ERROR_RESPONSE: &str = "Error";
+
+/// The OpenAPI description of the routes in `routes::create_routes`, served at
+/// `/api/openapi.json` when `API_DOCS_ENABLED=true`.
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "SignVault API",
+        description = "Electronic signature platform. Routes need a bearer token from `/auth/login` unless marked otherwise; signing routes are authorized by the token in the signer's link, and shared routes by the token of a share link."
+    ),
+    servers((url = "/api")),
+    paths(
+        routes::health_check,
+        routes::detailed_health_check,
+        auth::login,
+        auth::get_current_user,
+        auth::update_current_user,
+        auth::request_email_change,
+        auth::confirm_email_change,
+        auth::accept_invitation,
+        auth::get_storage_usage,
+        auth::list_auth_events,
+        auth::list_sessions,
+        auth::revoke_session,
+        auth::revoke_other_sessions,
+        auth::list_saved_signatures,
+        auth::create_saved_signature,
+        auth::delete_saved_signature,
+        branding::get_branding,
+        branding::update_branding,
+        branding::get_branding_logo,
+        two_factor::get_two_factor_status,
+        two_factor::setup_two_factor,
+        two_factor::enable_two_factor,
+        two_factor::disable_two_factor,
+        two_factor::verify_two_factor,
+        server_key::get_public_keys,
+        server_key::verify_signature,
+        certificates::verify_certificate,
+        signing::create_signing_session,
+        signing::get_signing_session,
+        signing::get_signing_pdf,
+        signing::get_signing_page_preview,
+        signing::submit_signing,
+        signing::save_signing_progress,
+        signing::unlock_signing,
+        signing::claim_signing_link,
+        signing::approve_signing,
+        signing::give_consent,
+        signing::start_verification,
+        signing::complete_verification,
+        signing::decline_signing_request,
+        status::get_envelope_status,
+        status::get_status_pdf,
+        shared::get_shared_document,
+        shared::get_shared_pdf,
+        shared::get_shared_certificate,
+        documents::list_documents,
+        documents::create_document,
+        documents::bulk_update_documents,
+        documents::list_trash,
+        documents::list_shared_documents,
+        documents::search_documents,
+        documents::list_tags,
+        documents::get_document,
+        documents::update_document,
+        documents::delete_document,
+        documents::add_field,
+        documents::import_acroform_fields,
+        documents::update_field,
+        documents::delete_field,
+        documents::add_signer,
+        documents::create_open_link,
+        documents::update_signer,
+        documents::remove_signer,
+        documents::remind_signer,
+        documents::reset_signer,
+        documents::amend_add_signer,
+        documents::amend_remove_signer,
+        documents::amend_replace_signer,
+        documents::rotate_signer_token,
+        documents::start_in_person_session,
+        documents::preview_signing_session,
+        documents::preview_signing_pdf,
+        documents::preflight_document,
+        documents::send_document,
+        documents::self_sign_document,
+        documents::recall_document,
+        documents::void_document,
+        documents::restore_document,
+        documents::list_shares,
+        documents::share_document,
+        documents::unshare_document,
+        documents::create_share_link,
+        documents::list_share_links,
+        documents::revoke_share_link,
+        documents::add_document_tags,
+        documents::remove_document_tag,
+        documents::set_legal_hold,
+        documents::get_audit_logs,
+        documents::export_audit_logs,
+        documents::get_certificate,
+        documents::download_document,
+        documents::download_sealed_document,
+        documents::list_signatures,
+        documents::get_signature_image,
+        documents::download_package,
+        documents::seal_document,
+        documents::verify_file,
+        documents::list_timestamps,
+        documents::get_page_preview,
+        admin::verify_document_integrity,
+        admin::list_storage_scans,
+        admin::run_storage_scan,
+        admin::list_jobs,
+        admin::run_job,
+        admin::list_purge_log,
+        admin::list_auth_events,
+        admin::list_security_events,
+        admin::acknowledge_security_event,
+        admin::anonymize_signer,
+        admin::get_user_storage_usage,
+        admin::set_user_storage_quota,
+        admin::set_user_role,
+        admin::create_invitation,
+        admin::list_invitations,
+        admin::resend_invitation,
+        admin::revoke_invitation,
+        admin::verify_timestamp,
+    ),
+    components(schemas(ErrorResponse)),
+    security(("bearer" = [])),
+    modifiers(&ApiDocDefaults)
+)]
+pub struct ApiDoc;
+
+/// Adds the bearer token scheme, and documents `ErrorResponse` as the body
+/// of every operation's error responses.
+struct ApiDocDefaults;
+
+impl Modify for ApiDocDefaults {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            BEARER_SCHEME,
+            SecurityScheme::Http(
+                HttpBuilder::new()
+                    .scheme(HttpAuthScheme::Bearer)
+                    .bearer_format("JWT")
+                    .build(),
+            ),
+        );
+        components.responses.insert(
+            ERROR_RESPONSE.to_string(),
+            RefOr::T(
+                ResponseBuilder::new()
+                    .description("The request failed; `error` says why")
+                    .content(
+                        "application/json",
+                        ContentBuilder::new()
+                            .schema(Some(RefOr::Ref(utoipa::openapi::Ref::from_schema_name(
+                                "ErrorResponse",
+                            ))))
+                            .build(),
+                    )
+                    .build(),
+            ),
+        );
+
+        for item in openapi.paths.paths.values_mut() {
+            for operation in [
+                &mut item.get,
+                &mut item.put,
+                &mut item.post,
+                &mut item.delete,
+                &mut item.patch,
+            ]
+            .into_iter()
+            .flatten()
+            {
+                operation
+                    .responses
+                    .responses
+                    .entry("default".to_string())
+                    .or_insert_with(|| {
+                        RefOr::Ref(utoipa::openapi::Ref::from_response_name(ERROR_RESPONSE))
+                    });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use utoipa::openapi::path::HttpMethod;
+    use utoipa::openapi::security::SecurityRequirement;
+
+    /// The `(path, method)` pairs registered in `routes::create_routes`, with
+    /// axum's `:param` segments written the OpenAPI way.
+    fn registered_routes() -> Vec<(String, String)> {
+        let source = include_str!("routes.rs");
+        let start = source.find("pub fn create_routes").unwrap();
+        let end = source[start..].find("\n}\n").unwrap() + start;
+
+        source[start..end]
+            .split(".route(")
+            .skip(1)
+            .map(|route| {
+                let route = route.trim_start().strip_prefix('"').unwrap();
+                let (path, rest) = route.split_once('"').unwrap();
+                let rest = rest.trim_start().strip_prefix(',').unwrap().trim_start();
+                let method = &rest[..rest.find('(').unwrap()];
+
+                let path = path
+                    .split('/')
+                    .map(|segment| match segment.strip_prefix(':') {
+                        Some(param) => format!("{{{}}}", param),
+                        None => segment.to_string(),
+                    })
+                    .collect::<Vec<_>>()
+                    .join("/");
+
+                (path, method.to_string())
+            })
+            .collect()
+    }
+
+    fn method(name: &str) -> HttpMethod {
+        match name {
+            "get" => HttpMethod::Get,
+            "post" => HttpMethod::Post,
+            "put" => HttpMethod::Put,
+            "patch" => HttpMethod::Patch,
+            "delete" => HttpMethod::Delete,
+            other => panic!("unexpected route method {}", other),
+        }
+    }
+
+    fn operation<'a>(
+        spec: &'a utoipa::openapi::OpenApi,
+        path: &str,
+        method: HttpMethod,
+    ) -> Option<&'a utoipa::openapi::path::Operation> {
+        let item = spec.paths.paths.get(path)?;
+        match method {
+            HttpMethod::Get => item.get.as_ref(),
+            HttpMethod::Post => item.post.as_ref(),
+            HttpMethod::Put => item.put.as_ref(),
+            HttpMethod::Patch => item.patch.as_ref(),
+            HttpMethod::Delete => item.delete.as_ref(),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_openapi_covers_every_route() {
+        let spec = ApiDoc::openapi();
+        let routes = registered_routes();
+        assert!(routes.len() > 50);
+
+        let missing: Vec<_> = routes
+            .iter()
+            .filter(|(path, name)| operation(&spec, path, method(name)).is_none())
+            .collect();
+        assert!(
+            missing.is_empty(),
+            "routes missing from the spec: {:?}",
+            missing
+        );
+
+        let documented: usize = spec
+            .paths
+            .paths
+            .values()
+            .map(|item| {
+                [&item.get, &item.post, &item.put, &item.patch, &item.delete]
+                    .into_iter()
+                    .flatten()
+                    .count()
+            })
+            .sum();
+        assert_eq!(
+            documented,
+            routes.len(),
+            "spec documents unregistered routes"
+        );
+
+        assert!(spec.to_json().is_ok());
+    }
+
+    #[test]
+    fn test_openapi_security_and_uploads() {
+        let spec = ApiDoc::openapi();
+
+        let components = spec.components.as_ref().unwrap();
+        assert!(components.security_schemes.contains_key(BEARER_SCHEME));
+        assert!(components.schemas.contains_key("ErrorResponse"));
+
+        let upload = operation(&spec, "/documents", HttpMethod::Post).unwrap();
+        let body = upload.request_body.as_ref().unwrap();
+        assert!(body.content.contains_key("multipart/form-data"));
+
+        // Signing routes are authorized by the token in the path, not a bearer token.
+        let signing = operation(&spec, "/sign/{token}/submit", HttpMethod::Post).unwrap();
+        assert!(signing.security.as_ref().is_some_and(|security| security
+            .iter()
+            .all(|s| *s == SecurityRequirement::default())));
+        assert!(operation(&spec, "/documents/{id}", HttpMethod::Get)
+            .unwrap()
+            .security
+            .is_none());
+    }
+}
@@ -0,0 +1,66 @@
+use axum::http::{header, HeaderMap};
+use chrono::{DateTime, Utc};
+
+use crate::api::error::{ApiError, ApiResult};
+
+/// The `updated_at` a conditional edit expects the resource to still have,
+/// taken from `If-Match` (the quoted timestamp) or else from the request's
+/// `expected_updated_at`. `None`, when neither is given or `If-Match` is `*`,
+/// keeps last-write-wins.
+pub fn expected_updated_at(
+    headers: &HeaderMap,
+    body: Option<DateTime<Utc>>,
+) -> ApiResult<Option<DateTime<Utc>>> {
+    let Some(value) = headers.get(header::IF_MATCH) else {
+        return Ok(body);
+    };
+
+    let invalid = || {
+        ApiError::BadRequest("If-Match must be the quoted updated_at of the resource".to_string())
+    };
+
+    let value = value.to_str().map_err(|_| invalid())?.trim();
+    if value == "*" {
+        return Ok(None);
+    }
+
+    let tag = value.trim_start_matches("W/").trim_matches('"');
+    DateTime::parse_from_rfc3339(tag)
+        .map(|t| Some(t.with_timezone(&Utc)))
+        .map_err(|_| invalid())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn if_match(value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::IF_MATCH, value.parse().unwrap());
+        headers
+    }
+
+    #[test]
+    fn test_expected_updated_at() {
+        let at = "2024-05-01T10:20:30.123456Z"
+            .parse::<DateTime<Utc>>()
+            .unwrap();
+        let other = at + chrono::Duration::seconds(1);
+
+        assert_eq!(expected_updated_at(&HeaderMap::new(), None).unwrap(), None);
+        assert_eq!(
+            expected_updated_at(&HeaderMap::new(), Some(at)).unwrap(),
+            Some(at)
+        );
+        assert_eq!(
+            expected_updated_at(&if_match("\"2024-05-01T10:20:30.123456Z\""), Some(other)).unwrap(),
+            Some(at)
+        );
+        assert_eq!(
+            expected_updated_at(&if_match("W/\"2024-05-01T12:20:30.123456+02:00\""), None).unwrap(),
+            Some(at)
+        );
+        assert_eq!(expected_updated_at(&if_match("*"), Some(at)).unwrap(), None);
+        assert!(expected_updated_at(&if_match("\"v2\""), None).is_err());
+    }
+}
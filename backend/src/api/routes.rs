@@ -1,35 +1,137 @@
 use axum::{
-    extract::State,
+    extract::{DefaultBodyLimit, State},
     middleware,
-    routing::{delete, get, post, put},
+    routing::{delete, get, patch, post, put},
     Json, Router,
 };
 use serde::Serialize;
+use sqlx::PgPool;
+use tower::ServiceBuilder;
+use utoipa::{OpenApi, ToSchema};
+use utoipa_swagger_ui::SwaggerUi;
 
-use crate::api::{auth, documents, middleware::auth_middleware, signing, state::AppState};
+use crate::api::{
+    admin, auth, branding, certificates, documents,
+    idempotency::idempotency_middleware,
+    middleware::{auth_middleware, client_ip_middleware},
+    server_key, shared, signing,
+    state::AppState,
+    status, two_factor, ApiDoc,
+};
+use crate::services::storage;
+
+/// Allowance for multipart boundaries and the non-file form fields of an upload.
+const MULTIPART_OVERHEAD_BYTES: usize = 1024 * 1024;
+
+fn upload_body_limit(state: &AppState) -> usize {
+    state.config.max_file_size_bytes() as usize + MULTIPART_OVERHEAD_BYTES
+}
 
 pub fn create_routes(state: AppState) -> Router {
     let public_routes = Router::new()
         .route("/health", get(health_check))
         .route("/health/detailed", get(detailed_health_check))
-        .route("/auth/login", post(auth::login));
+        .route("/auth/login", post(auth::login))
+        .route("/auth/email/confirm", post(auth::confirm_email_change))
+        .route("/auth/2fa/verify", post(two_factor::verify_two_factor))
+        .route(
+            "/auth/invitations/:token/accept",
+            post(auth::accept_invitation),
+        )
+        .route("/branding/:user_id/logo", get(branding::get_branding_logo))
+        .route("/server-key", get(server_key::get_public_keys))
+        .route("/server-key/verify", post(server_key::verify_signature))
+        .route(
+            "/certificates/verify",
+            post(certificates::verify_certificate),
+        );
 
     let signing_routes = Router::new()
         .route("/sign/:token", get(signing::get_signing_session))
+        .route(
+            "/sign/:token/session",
+            post(signing::create_signing_session),
+        )
         .route("/sign/:token/pdf", get(signing::get_signing_pdf))
+        .route(
+            "/sign/:token/pages/:page/preview.png",
+            get(signing::get_signing_page_preview),
+        )
         .route("/sign/:token/submit", post(signing::submit_signing))
+        .route("/sign/:token/progress", put(signing::save_signing_progress))
+        .route("/sign/:token/unlock", post(signing::unlock_signing))
+        .route("/sign/:token/claim", post(signing::claim_signing_link))
+        .route("/sign/:token/approve", post(signing::approve_signing))
+        .route("/sign/:token/consent", post(signing::give_consent))
+        .route(
+            "/sign/:token/verification/start",
+            post(signing::start_verification),
+        )
+        .route(
+            "/sign/:token/verification/complete",
+            post(signing::complete_verification),
+        )
         .route(
             "/sign/:token/decline",
             post(signing::decline_signing_request),
         );
 
+    let status_routes = Router::new()
+        .route("/status/:token", get(status::get_envelope_status))
+        .route("/status/:token/pdf", get(status::get_status_pdf));
+
+    let shared_routes = Router::new()
+        .route("/shared/:token", get(shared::get_shared_document))
+        .route("/shared/:token/pdf", get(shared::get_shared_pdf))
+        .route(
+            "/shared/:token/certificate",
+            get(shared::get_shared_certificate),
+        );
+
     let protected_routes = Router::new()
         .route("/auth/me", get(auth::get_current_user))
+        .route("/auth/me", patch(auth::update_current_user))
+        .route("/auth/email", post(auth::request_email_change))
+        .route("/auth/usage", get(auth::get_storage_usage))
+        .route("/auth/events", get(auth::list_auth_events))
+        .route("/auth/sessions", get(auth::list_sessions))
+        .route("/auth/sessions", delete(auth::revoke_other_sessions))
+        .route("/auth/sessions/:id", delete(auth::revoke_session))
+        .route("/auth/branding", get(branding::get_branding))
+        .route("/auth/branding", put(branding::update_branding))
+        .route("/auth/2fa", get(two_factor::get_two_factor_status))
+        .route("/auth/2fa/setup", post(two_factor::setup_two_factor))
+        .route("/auth/2fa/enable", post(two_factor::enable_two_factor))
+        .route("/auth/2fa/disable", post(two_factor::disable_two_factor))
+        .route("/auth/signatures", get(auth::list_saved_signatures))
+        .route("/auth/signatures", post(auth::create_saved_signature))
+        .route("/auth/signatures/:id", delete(auth::delete_saved_signature))
         .route("/documents", get(documents::list_documents))
-        .route("/documents", post(documents::create_document))
+        .route(
+            "/documents",
+            post(documents::create_document).layer(
+                // The idempotency layer reads the body under the upload limit.
+                ServiceBuilder::new()
+                    .layer(DefaultBodyLimit::max(upload_body_limit(&state)))
+                    .layer(middleware::from_fn_with_state(
+                        state.clone(),
+                        idempotency_middleware,
+                    )),
+            ),
+        )
+        .route("/documents/bulk", post(documents::bulk_update_documents))
+        .route("/documents/trash", get(documents::list_trash))
+        .route("/documents/shared", get(documents::list_shared_documents))
+        .route("/documents/search", get(documents::search_documents))
+        .route("/documents/tags", get(documents::list_tags))
         .route("/documents/:id", get(documents::get_document))
+        .route("/documents/:id", patch(documents::update_document))
         .route("/documents/:id", delete(documents::delete_document))
         .route("/documents/:id/fields", post(documents::add_field))
+        .route(
+            "/documents/:id/fields/import-acroform",
+            post(documents::import_acroform_fields),
+        )
         .route(
             "/documents/:id/fields/:field_id",
             put(documents::update_field),
@@ -38,19 +140,166 @@ pub fn create_routes(state: AppState) -> Router {
             "/documents/:id/fields/:field_id",
             delete(documents::delete_field),
         )
-        .route("/documents/:id/signers", post(documents::add_signer))
+        .route(
+            "/documents/:id/signers",
+            post(documents::add_signer).layer(middleware::from_fn_with_state(
+                state.clone(),
+                idempotency_middleware,
+            )),
+        )
+        .route(
+            "/documents/:id/open-link",
+            post(documents::create_open_link),
+        )
+        .route(
+            "/documents/:id/signers/:signer_id",
+            put(documents::update_signer),
+        )
         .route(
             "/documents/:id/signers/:signer_id",
             delete(documents::remove_signer),
         )
-        .route("/documents/:id/send", post(documents::send_document))
+        .route(
+            "/documents/:id/signers/:signer_id/remind",
+            post(documents::remind_signer),
+        )
+        .route(
+            "/documents/:id/signers/:signer_id/reset",
+            post(documents::reset_signer),
+        )
+        .route(
+            "/documents/:id/amend/signers",
+            post(documents::amend_add_signer),
+        )
+        .route(
+            "/documents/:id/amend/signers/:signer_id/remove",
+            post(documents::amend_remove_signer),
+        )
+        .route(
+            "/documents/:id/amend/signers/:signer_id/replace",
+            post(documents::amend_replace_signer),
+        )
+        .route(
+            "/documents/:id/signers/:signer_id/rotate-token",
+            post(documents::rotate_signer_token),
+        )
+        .route(
+            "/documents/:id/signers/:signer_id/in-person-session",
+            post(documents::start_in_person_session),
+        )
+        .route(
+            "/documents/:id/preview",
+            get(documents::preview_signing_session),
+        )
+        .route(
+            "/documents/:id/preview/pdf",
+            get(documents::preview_signing_pdf),
+        )
+        .route(
+            "/documents/:id/preflight",
+            get(documents::preflight_document),
+        )
+        .route(
+            "/documents/:id/send",
+            post(documents::send_document).layer(middleware::from_fn_with_state(
+                state.clone(),
+                idempotency_middleware,
+            )),
+        )
+        .route(
+            "/documents/:id/self-sign",
+            post(documents::self_sign_document),
+        )
+        .route("/documents/:id/recall", post(documents::recall_document))
         .route("/documents/:id/void", post(documents::void_document))
+        .route("/documents/:id/restore", post(documents::restore_document))
+        .route("/documents/:id/shares", get(documents::list_shares))
+        .route("/documents/:id/shares", post(documents::share_document))
+        .route(
+            "/documents/:id/shares/:user_id",
+            delete(documents::unshare_document),
+        )
+        .route(
+            "/documents/:id/share-links",
+            get(documents::list_share_links),
+        )
+        .route(
+            "/documents/:id/share-links",
+            post(documents::create_share_link),
+        )
+        .route(
+            "/documents/:id/share-links/:link_id",
+            delete(documents::revoke_share_link),
+        )
+        .route("/documents/:id/tags", post(documents::add_document_tags))
+        .route(
+            "/documents/:id/tags/:name",
+            delete(documents::remove_document_tag),
+        )
+        .route("/documents/:id/legal-hold", put(documents::set_legal_hold))
         .route("/documents/:id/audit", get(documents::get_audit_logs))
+        .route(
+            "/documents/:id/audit/export",
+            get(documents::export_audit_logs),
+        )
         .route(
             "/documents/:id/certificate",
             get(documents::get_certificate),
         )
         .route("/documents/:id/download", get(documents::download_document))
+        .route(
+            "/documents/:id/package.zip",
+            get(documents::download_package),
+        )
+        .route(
+            "/documents/:id/download/sealed",
+            get(documents::download_sealed_document),
+        )
+        .route("/documents/:id/signatures", get(documents::list_signatures))
+        .route(
+            "/documents/:id/signatures/:signature_id/image",
+            get(documents::get_signature_image),
+        )
+        .route("/documents/:id/seal", post(documents::seal_document))
+        .route(
+            "/documents/:id/verify-file",
+            post(documents::verify_file).layer(DefaultBodyLimit::max(upload_body_limit(&state))),
+        )
+        .route("/documents/:id/timestamps", get(documents::list_timestamps))
+        .route(
+            "/documents/:id/pages/:page/preview.png",
+            get(documents::get_page_preview),
+        )
+        .route(
+            "/admin/documents/:id/verify-integrity",
+            post(admin::verify_document_integrity),
+        )
+        .route("/admin/storage/scans", get(admin::list_storage_scans))
+        .route("/admin/storage/scans", post(admin::run_storage_scan))
+        .route("/admin/jobs", get(admin::list_jobs))
+        .route("/admin/jobs/:name/run", post(admin::run_job))
+        .route("/admin/purge-log", get(admin::list_purge_log))
+        .route("/admin/auth-events", get(admin::list_auth_events))
+        .route("/admin/security-events", get(admin::list_security_events))
+        .route(
+            "/admin/security-events/:id/acknowledge",
+            post(admin::acknowledge_security_event),
+        )
+        .route("/admin/signers/anonymize", post(admin::anonymize_signer))
+        .route("/admin/users/:id/usage", get(admin::get_user_storage_usage))
+        .route("/admin/users/:id/quota", put(admin::set_user_storage_quota))
+        .route("/admin/users/:id/role", put(admin::set_user_role))
+        .route("/admin/invitations", get(admin::list_invitations))
+        .route("/admin/invitations", post(admin::create_invitation))
+        .route(
+            "/admin/invitations/:id/resend",
+            post(admin::resend_invitation),
+        )
+        .route("/admin/invitations/:id", delete(admin::revoke_invitation))
+        .route(
+            "/admin/timestamps/:id/verify",
+            post(admin::verify_timestamp),
+        )
         .route_layer(middleware::from_fn_with_state(
             state.clone(),
             auth_middleware,
@@ -59,91 +308,222 @@ pub fn create_routes(state: AppState) -> Router {
     Router::new()
         .merge(public_routes)
         .merge(signing_routes)
+        .merge(status_routes)
+        .merge(shared_routes)
         .merge(protected_routes)
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            client_ip_middleware,
+        ))
         .with_state(state)
 }
 
+/// The OpenAPI document at `/api/openapi.json` and Swagger UI at `/api/docs`.
+pub fn create_docs_routes() -> Router {
+    SwaggerUi::new("/api/docs")
+        .url("/api/openapi.json", ApiDoc::openapi())
+        .into()
+}
+
+/// Routes served outside `/api` at well-known locations.
+pub fn create_well_known_routes(state: AppState) -> Router {
+    Router::new()
+        .route(
+            "/.well-known/signvault-public-key",
+            get(server_key::get_public_keys),
+        )
+        .with_state(state)
+}
+
+#[utoipa::path(
+    get,
+    path = "/health",
+    tag = "health",
+    responses((status = 200, description = "The server is up", body = String, content_type = "text/plain")),
+    security(())
+)]
 async fn health_check() -> &'static str {
     "OK"
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 struct HealthStatus {
     status: String,
     version: String,
     database: DatabaseHealth,
+    pool: PoolHealth,
+    /// `None` when the database could not be queried.
+    queues: Option<QueueHealth>,
     storage: StorageHealth,
+    upload_limits: UploadLimits,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 struct DatabaseHealth {
     connected: bool,
     latency_ms: Option<u64>,
     error: Option<String>,
 }
 
-#[derive(Serialize)]
+/// Connections of this instance's database pool.
+#[derive(Serialize, ToSchema)]
+struct PoolHealth {
+    size: u32,
+    idle: usize,
+    in_use: u32,
+    max_connections: u32,
+    acquire_timeout_secs: u64,
+    /// Every connection is open and busy, so requests wait for one.
+    saturated: bool,
+}
+
+impl PoolHealth {
+    fn of(pool: &PgPool) -> Self {
+        let size = pool.size();
+        let idle = pool.num_idle();
+        let max_connections = pool.options().get_max_connections();
+
+        Self {
+            size,
+            idle,
+            in_use: size.saturating_sub(idle as u32),
+            max_connections,
+            acquire_timeout_secs: pool.options().get_acquire_timeout().as_secs(),
+            saturated: size >= max_connections && idle == 0,
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+struct QueueHealth {
+    email_outbox: QueueDepth,
+    outbox_events: QueueDepth,
+    /// Pending documents past their expiry date, which should have been
+    /// expired.
+    overdue_pending_documents: i64,
+}
+
+#[derive(Serialize, ToSchema)]
+struct QueueDepth {
+    pending: i64,
+    oldest_pending_age_secs: Option<i64>,
+}
+
+#[derive(Serialize, ToSchema)]
 struct StorageHealth {
     writable: bool,
+    backend: String,
     path: String,
     error: Option<String>,
 }
 
+#[derive(Serialize, ToSchema)]
+struct UploadLimits {
+    max_file_size_mb: u64,
+    max_pdf_pages: usize,
+    max_pdf_page_dimension_pt: f64,
+}
+
+#[utoipa::path(
+    get,
+    path = "/health/detailed",
+    tag = "health",
+    responses((status = 200, description = "Database, connection pool, queue, storage and upload limit status", body = HealthStatus)),
+    security(())
+)]
 async fn detailed_health_check(State(state): State<AppState>) -> Json<HealthStatus> {
-    // Check database connectivity
-    let db_start = std::time::Instant::now();
-    let db_result: Result<_, sqlx::Error> = sqlx::query("SELECT 1").execute(&state.pool).await;
-    let db_health = match db_result {
-        Ok(_) => DatabaseHealth {
-            connected: true,
-            latency_ms: Some(db_start.elapsed().as_millis() as u64),
-            error: None,
-        },
-        Err(e) => DatabaseHealth {
-            connected: false,
-            latency_ms: None,
-            error: Some(e.to_string()),
-        },
-    };
+    // Read before the queries below take a connection
+    let pool_health = PoolHealth::of(&state.pool);
 
-    // Check storage directory
-    let storage_path = &state.config.storage_path;
-    let storage_health = if std::path::Path::new(storage_path).exists() {
-        // Try to write a test file
-        let test_file = format!("{}/.health_check", storage_path);
-        match std::fs::write(&test_file, "test") {
-            Ok(_) => {
-                let _ = std::fs::remove_file(&test_file);
-                StorageHealth {
-                    writable: true,
-                    path: storage_path.clone(),
-                    error: None,
-                }
-            }
-            Err(e) => StorageHealth {
-                writable: false,
-                path: storage_path.clone(),
+    // Check database connectivity with the one query for the queue depths
+    let db_start = std::time::Instant::now();
+    let db_result = sqlx::query_as::<_, (i64, Option<i64>, i64, Option<i64>, i64)>(
+        r#"
+        SELECT
+            (SELECT COUNT(*) FROM email_outbox
+             WHERE sent_at IS NULL AND next_attempt_at IS NOT NULL),
+            (SELECT EXTRACT(EPOCH FROM NOW() - MIN(created_at))::BIGINT FROM email_outbox
+             WHERE sent_at IS NULL AND next_attempt_at IS NOT NULL),
+            (SELECT COUNT(*) FROM outbox_events
+             WHERE processed_at IS NULL AND next_attempt_at IS NOT NULL),
+            (SELECT EXTRACT(EPOCH FROM NOW() - MIN(created_at))::BIGINT FROM outbox_events
+             WHERE processed_at IS NULL AND next_attempt_at IS NOT NULL),
+            (SELECT COUNT(*) FROM documents
+             WHERE status = 'pending' AND expires_at < NOW() AND deleted_at IS NULL)
+        "#,
+    )
+    .fetch_one(&state.pool)
+    .await;
+    let (db_health, queues) = match db_result {
+        Ok((
+            pending_emails,
+            oldest_email_age,
+            pending_events,
+            oldest_event_age,
+            overdue_documents,
+        )) => (
+            DatabaseHealth {
+                connected: true,
+                latency_ms: Some(db_start.elapsed().as_millis() as u64),
+                error: None,
+            },
+            Some(QueueHealth {
+                email_outbox: QueueDepth {
+                    pending: pending_emails,
+                    oldest_pending_age_secs: oldest_email_age,
+                },
+                outbox_events: QueueDepth {
+                    pending: pending_events,
+                    oldest_pending_age_secs: oldest_event_age,
+                },
+                overdue_pending_documents: overdue_documents,
+            }),
+        ),
+        Err(e) => (
+            DatabaseHealth {
+                connected: false,
+                latency_ms: None,
                 error: Some(e.to_string()),
             },
-        }
-    } else {
-        StorageHealth {
-            writable: false,
-            path: storage_path.clone(),
-            error: Some("Storage directory does not exist".to_string()),
-        }
+            None,
+        ),
     };
 
-    let overall_status = if db_health.connected && storage_health.writable {
-        "healthy"
-    } else {
+    // Check storage is writable
+    let storage = &state.storage;
+    let storage_result = async {
+        storage
+            .put(storage::HEALTH_CHECK_KEY, b"test".to_vec())
+            .await?;
+        storage.delete(storage::HEALTH_CHECK_KEY).await
+    }
+    .await;
+    let storage_health = StorageHealth {
+        writable: storage_result.is_ok(),
+        backend: state.config.storage_backend.clone(),
+        path: storage.location(),
+        error: storage_result.err().map(|e| e.to_string()),
+    };
+
+    let overall_status = if !db_health.connected || !storage_health.writable {
         "unhealthy"
+    } else if pool_health.saturated {
+        "degraded"
+    } else {
+        "healthy"
     };
 
     Json(HealthStatus {
         status: overall_status.to_string(),
         version: env!("CARGO_PKG_VERSION").to_string(),
         database: db_health,
+        pool: pool_health,
+        queues,
         storage: storage_health,
+        upload_limits: UploadLimits {
+            max_file_size_mb: state.config.max_file_size_mb,
+            max_pdf_pages: state.config.max_pdf_pages,
+            max_pdf_page_dimension_pt: state.config.max_pdf_page_dimension_pt,
+        },
     })
 }
@@ -0,0 +1,38 @@
+use axum::{extract::State, Json};
+
+use crate::api::state::AppState;
+use crate::models::server_key::{
+    ServerPublicKeySet, ServerSignatureVerification, VerifyServerSignatureRequest,
+};
+
+/// Current and retired server public keys, for verifying certificates and
+/// audit exports offline.
+#[utoipa::path(
+    get,
+    path = "/server-key",
+    tag = "server-key",
+    responses((status = 200, description = "Current and retired server public keys", body = ServerPublicKeySet)),
+    security(())
+)]
+pub async fn get_public_keys(State(state): State<AppState>) -> Json<ServerPublicKeySet> {
+    Json(state.server_key.public_keys())
+}
+
+#[utoipa::path(
+    post,
+    path = "/server-key/verify",
+    tag = "server-key",
+    request_body = VerifyServerSignatureRequest,
+    responses((status = 200, description = "Whether the signature is valid", body = ServerSignatureVerification)),
+    security(())
+)]
+pub async fn verify_signature(
+    State(state): State<AppState>,
+    Json(req): Json<VerifyServerSignatureRequest>,
+) -> Json<ServerSignatureVerification> {
+    Json(
+        state
+            .server_key
+            .verify(req.message.as_bytes(), &req.key_id, &req.signature),
+    )
+}
@@ -0,0 +1,277 @@
+use axum::{
+    body::Body,
+    extract::{Path, State},
+    http::{header, HeaderMap, Response},
+    Json,
+};
+use chrono::{Duration, Utc};
+
+use crate::api::documents::{completion_certificate, display_timezone, sealed_filename};
+use crate::api::error::{ApiError, ApiResult};
+use crate::api::file_response;
+use crate::api::middleware::extract_client_info_from_headers;
+use crate::api::state::AppState;
+use crate::db;
+use crate::models::audit::{AuditAction, Certificate};
+use crate::models::document::Document;
+use crate::models::security_event::SecurityEventSource;
+use crate::models::share_link::{ShareLink, ShareLinkDocument};
+use crate::services::integrity::IntegrityActor;
+use crate::services::{audit, crypto};
+
+/// Header carrying the passcode of a share link that has one.
+pub const PASSCODE_HEADER: &str = "x-share-passcode";
+
+/// Resolves a share link token to its link and document. Revoked and expired
+/// links, and links to documents since deleted, are reported as expired
+/// rather than not found; links with a passcode require it in
+/// `X-Share-Passcode`.
+async fn resolve_share_link(
+    state: &AppState,
+    token: &str,
+    headers: &HeaderMap,
+) -> ApiResult<(ShareLink, Document)> {
+    let (ip_address, user_agent) = extract_client_info_from_headers(headers);
+
+    let link =
+        db::share_link::get_share_link_by_token_hash(&state.pool, &crypto::hash_token(token))
+            .await?
+            .ok_or_else(|| ApiError::NotFound("Invalid share link".to_string()))?;
+
+    if link.revoked_at.is_some() {
+        return Err(ApiError::LinkExpired(
+            "This link has been revoked".to_string(),
+        ));
+    }
+    if link.expires_at <= Utc::now() {
+        return Err(ApiError::LinkExpired("This link has expired".to_string()));
+    }
+
+    let document = db::document::get_document_by_id(&state.pool, link.document_id)
+        .await?
+        .filter(|document| document.deleted_at.is_none())
+        .ok_or_else(|| ApiError::LinkExpired("This document is no longer available".to_string()))?;
+
+    let Some(passcode_hash) = &link.passcode_hash else {
+        return Ok((link, document));
+    };
+
+    if link
+        .locked_until
+        .is_some_and(|locked_until| locked_until > Utc::now())
+    {
+        return Err(ApiError::TooManyRequests(
+            "Too many incorrect passcodes, please try again later".to_string(),
+        ));
+    }
+
+    let passcode = headers
+        .get(PASSCODE_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(str::trim)
+        .filter(|passcode| !passcode.is_empty())
+        .ok_or_else(|| ApiError::PasscodeRequired("This link requires a passcode".to_string()))?;
+
+    if !bcrypt::verify(passcode, passcode_hash).unwrap_or(false) {
+        db::share_link::record_passcode_failure(
+            &state.pool,
+            link.id,
+            state.config.verification_max_attempts,
+            Utc::now() + Duration::minutes(state.config.verification_lockout_minutes),
+        )
+        .await?;
+
+        audit::log_action(
+            &state.pool,
+            state.hash_algorithm,
+            document.id,
+            None,
+            None,
+            AuditAction::ShareLinkPasscodeFailed,
+            Some(&ip_address),
+            Some(&user_agent),
+            Some(serde_json::json!({ "share_link_id": link.id })),
+        )
+        .await?;
+
+        return Err(ApiError::PasscodeRequired("Incorrect passcode".to_string()));
+    }
+
+    Ok((link, document))
+}
+
+/// Counts an access through the link and records it in the audit trail.
+async fn record_access(
+    state: &AppState,
+    link: &ShareLink,
+    headers: &HeaderMap,
+    resource: &str,
+) -> ApiResult<()> {
+    let (ip_address, user_agent) = extract_client_info_from_headers(headers);
+
+    db::share_link::record_access(&state.pool, link.id).await?;
+
+    audit::log_action(
+        &state.pool,
+        state.hash_algorithm,
+        link.document_id,
+        None,
+        None,
+        AuditAction::ShareLinkAccessed,
+        Some(&ip_address),
+        Some(&user_agent),
+        Some(serde_json::json!({
+            "share_link_id": link.id,
+            "resource": resource,
+            "location": state.geoip.lookup(&ip_address)
+        })),
+    )
+    .await?;
+
+    Ok(())
+}
+
+#[utoipa::path(
+    get,
+    path = "/shared/{token}",
+    tag = "shared",
+    params(
+        ("token" = String, Path, description = "Token from the share link"),
+        ("X-Share-Passcode" = Option<String>, Header, description = "The link's passcode, if it has one"),
+    ),
+    responses((status = 200, description = "The shared document", body = ShareLinkDocument)),
+    security(())
+)]
+pub async fn get_shared_document(
+    State(state): State<AppState>,
+    Path(token): Path<String>,
+    headers: HeaderMap,
+) -> ApiResult<Json<ShareLinkDocument>> {
+    let (link, document) = resolve_share_link(&state, &token, &headers).await?;
+
+    record_access(&state, &link, &headers, "document").await?;
+
+    Ok(Json(ShareLinkDocument {
+        title: document.title,
+        status: document.status,
+        completed_at: document.completed_at,
+        sealed: document.sealed_file_path.is_some(),
+        expires_at: link.expires_at,
+        pdf_url: format!("/api/shared/{}/pdf", token),
+        certificate_url: format!("/api/shared/{}/certificate", token),
+    }))
+}
+
+/// The signed PDF: the sealed copy when the document has one, the original
+/// otherwise.
+#[utoipa::path(
+    get,
+    path = "/shared/{token}/pdf",
+    tag = "shared",
+    params(
+        ("token" = String, Path, description = "Token from the share link"),
+        ("X-Share-Passcode" = Option<String>, Header, description = "The link's passcode, if it has one"),
+    ),
+    responses((status = 200, description = "The document", content_type = "application/pdf")),
+    security(())
+)]
+pub async fn get_shared_pdf(
+    State(state): State<AppState>,
+    Path(token): Path<String>,
+    headers: HeaderMap,
+) -> ApiResult<Response<Body>> {
+    let (ip_address, user_agent) = extract_client_info_from_headers(&headers);
+
+    let (link, document) = resolve_share_link(&state, &token, &headers).await?;
+
+    let (key, hash, filename, encryption) =
+        match (&document.sealed_file_path, &document.sealed_file_hash) {
+            (Some(path), Some(hash)) => (
+                path.clone(),
+                hash.clone(),
+                sealed_filename(&document.original_filename),
+                document.sealed_encryption(),
+            ),
+            _ => {
+                file_response::ensure_intact(
+                    &state,
+                    &document,
+                    &headers,
+                    &IntegrityActor {
+                        signer_id: None,
+                        user_id: None,
+                        ip_address: Some(&ip_address),
+                        user_agent: Some(&user_agent),
+                        source: SecurityEventSource::Request,
+                    },
+                )
+                .await?;
+
+                (
+                    document.file_path.clone(),
+                    document.file_hash.clone(),
+                    document.original_filename.clone(),
+                    document.original_encryption(),
+                )
+            }
+        };
+
+    if file_response::is_initial_request(&headers) {
+        record_access(&state, &link, &headers, "pdf").await?;
+    }
+
+    let builder = Response::builder()
+        .header(header::CONTENT_TYPE, "application/pdf")
+        .header(
+            header::CONTENT_DISPOSITION,
+            format!("inline; filename=\"{}\"", filename),
+        );
+
+    file_response::stream_file(
+        state.storage.as_ref(),
+        &key,
+        encryption.as_ref(),
+        &file_response::etag_for_hash(&hash),
+        &headers,
+        builder,
+    )
+    .await
+}
+
+#[utoipa::path(
+    get,
+    path = "/shared/{token}/certificate",
+    tag = "shared",
+    params(
+        ("token" = String, Path, description = "Token from the share link"),
+        ("X-Share-Passcode" = Option<String>, Header, description = "The link's passcode, if it has one"),
+    ),
+    responses((status = 200, description = "The certificate of completion", body = Certificate)),
+    security(())
+)]
+pub async fn get_shared_certificate(
+    State(state): State<AppState>,
+    Path(token): Path<String>,
+    headers: HeaderMap,
+) -> ApiResult<Json<Certificate>> {
+    let (ip_address, user_agent) = extract_client_info_from_headers(&headers);
+
+    let (link, document) = resolve_share_link(&state, &token, &headers).await?;
+
+    record_access(&state, &link, &headers, "certificate").await?;
+
+    let actor = IntegrityActor {
+        signer_id: None,
+        user_id: None,
+        ip_address: Some(&ip_address),
+        user_agent: Some(&user_agent),
+        source: SecurityEventSource::Certificate,
+    };
+
+    // Whoever the link was given to reads times as the owner does
+    let timezone = display_timezone(&state, document.owner_id).await?;
+
+    Ok(Json(
+        completion_certificate(&state, &document, &actor, timezone).await?,
+    ))
+}
@@ -1,59 +1,551 @@
+use async_trait::async_trait;
 use axum::{
     body::Body,
-    extract::{Path, Request, State},
-    http::{header, Response},
+    extract::{FromRequestParts, Path, Query, RawPathParams, Request, State},
+    http::{header, request::Parts, Response},
     Json,
 };
+use chrono::{Duration, Utc};
 use serde::Serialize;
-use tokio::fs;
+use std::sync::Arc;
+use utoipa::ToSchema;
 use uuid::Uuid;
+use validator::Validate;
 
-use crate::api::error::{ApiError, ApiResult};
+use crate::api::documents::{check_signer_email, signer_email_conflict};
+use crate::api::error::{ApiError, ApiResult, ErrorResponse};
+use crate::api::file_response;
 use crate::api::middleware::{extract_client_info, extract_client_info_from_headers};
 use crate::api::state::AppState;
 use crate::db;
 use crate::models::audit::AuditAction;
-use crate::models::document::{DocumentFieldRow, DocumentStatus};
-use crate::models::signature::CompleteSigningRequest;
-use crate::models::signer::{DeclineRequest, Signer, SignerStatus};
-use crate::services::{audit, signing};
+use crate::models::branding::Branding;
+use crate::models::document::{Document, DocumentFieldRow, DocumentPage, DocumentStatus};
+use crate::models::outbox::OutboxEmailKind;
+use crate::models::security_event::SecurityEventSource;
+use crate::models::signature::{CompleteSigningRequest, SigningProgress};
+use crate::models::signer::{
+    ApprovalDecision, ApprovalRequest, ClaimSigningLinkRequest, ClaimSigningLinkResponse,
+    ConsentRequest, DeclineRequest, Signer, SignerRole, SignerStatus, SigningSessionClaims,
+    SigningSessionToken, UnlockRequest,
+};
+use crate::services::crypto;
+use crate::services::integrity::IntegrityActor;
+use crate::services::jwt::Audience;
+use crate::services::radio_group::{self, RadioGroup};
+use crate::services::signing::{SigningActor, SigningSubmission};
+use crate::services::verification::{
+    self, IdentityVerifier, VerificationChallenge, VerificationError, VerificationResult,
+};
+use crate::services::{audit, i18n, pdf, signing};
+
+struct ResolvedToken {
+    signer: Signer,
+    host_user_id: Option<Uuid>,
+}
+
+/// Resolves a signing token to its signer, accepting either the signer's emailed
+/// access token or an unexpired in-person session token minted by the owner.
+/// Open link tokens are refused with the reason, see `claim_signing_link`.
+async fn resolve_token(state: &AppState, token: &str) -> ApiResult<ResolvedToken> {
+    if let Some(signer) = db::signer::get_signer_by_access_token(&state.pool, token).await? {
+        return Ok(ResolvedToken {
+            signer,
+            host_user_id: None,
+        });
+    }
+
+    // An open link never opens a session itself: it is claimed once, and
+    // the claimant continues with their own token.
+    if let Some(link) = db::signer::get_open_link_by_token(&state.pool, token).await? {
+        return Err(match link.claimed_at {
+            Some(_) => ApiError::Conflict("This link has been claimed".to_string()),
+            None => ApiError::BadRequest(
+                "Enter your name and email to claim this signing link".to_string(),
+            ),
+        });
+    }
+
+    let Some(session) = db::signer::get_in_person_session_by_token(&state.pool, token).await?
+    else {
+        return Err(revoked_or_not_found(state, token, "Invalid signing link").await?);
+    };
+
+    if session.expires_at <= Utc::now() {
+        return Err(ApiError::BadRequest(
+            "This in-person signing session has expired".to_string(),
+        ));
+    }
+
+    let signer = db::signer::get_signer_by_id(&state.pool, session.signer_id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Invalid signing link".to_string()))?;
+
+    Ok(ResolvedToken {
+        signer,
+        host_user_id: Some(session.host_user_id),
+    })
+}
+
+/// The error for a token that resolves to no signer: revoked if the owner
+/// replaced it, else `not_found` with `message`.
+pub(crate) async fn revoked_or_not_found(
+    state: &AppState,
+    token: &str,
+    message: &str,
+) -> ApiResult<ApiError> {
+    if db::signer::is_access_token_revoked(&state.pool, &crypto::hash_token(token)).await? {
+        return Ok(ApiError::LinkExpired(
+            "This signing link has been revoked. Contact the sender for a new one".to_string(),
+        ));
+    }
+
+    Ok(ApiError::NotFound(message.to_string()))
+}
+
+/// The path segment clients put in place of the link token when they send a
+/// session token instead.
+const SESSION_PATH_SEGMENT: &str = "session";
+
+/// How a request to a signing endpoint identifies its signer: a session token
+/// in the `Authorization` header, or, while `SIGNING_PATH_TOKENS` allows it,
+/// the link token in the path. Other bearer tokens, such as the login of an
+/// owner opening a link in their own browser, are ignored.
+pub enum SigningCredential {
+    PathToken(String),
+    Session(SigningSessionClaims),
+}
+
+#[async_trait]
+impl FromRequestParts<AppState> for SigningCredential {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> ApiResult<Self> {
+        let session = parts
+            .headers
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .and_then(|token| {
+                state
+                    .jwt_keys
+                    .decode_for::<SigningSessionClaims>(Audience::Signing, token)
+                    .ok()
+            });
+        if let Some(claims) = session {
+            return Ok(Self::Session(claims));
+        }
+
+        let params = RawPathParams::from_request_parts(parts, state)
+            .await
+            .map_err(|_| ApiError::NotFound("Invalid signing link".to_string()))?;
+        let token = params
+            .iter()
+            .find(|(name, _)| *name == "token")
+            .map(|(_, token)| token.to_string())
+            .ok_or_else(|| ApiError::NotFound("Invalid signing link".to_string()))?;
+
+        // Also what an expired session gets: the client exchanges its link again
+        if token == SESSION_PATH_SEGMENT || !state.config.signing_path_tokens {
+            return Err(ApiError::Unauthorized);
+        }
+
+        Ok(Self::PathToken(token))
+    }
+}
+
+/// Resolves a credential like `resolve_token`. A session only resolves while
+/// the token it was exchanged for is still the signer's: recalling the
+/// document, resetting the signer or ending the in-person session ends it.
+async fn resolve_credential(
+    state: &AppState,
+    credential: &SigningCredential,
+) -> ApiResult<ResolvedToken> {
+    let claims = match credential {
+        SigningCredential::PathToken(token) => return resolve_token(state, token).await,
+        SigningCredential::Session(claims) => claims,
+    };
+
+    let signer = db::signer::get_signer_by_id(&state.pool, claims.signer_id)
+        .await?
+        .ok_or(ApiError::Unauthorized)?;
+
+    let host_user_id = match claims.in_person_session_id {
+        None => {
+            if crypto::hash_token(&signer.access_token) != claims.token_hash {
+                return Err(ApiError::Unauthorized);
+            }
+            None
+        }
+        Some(session_id) => {
+            let session = db::signer::get_in_person_session_by_id(&state.pool, session_id)
+                .await?
+                .filter(|session| {
+                    session.signer_id == signer.id
+                        && session.expires_at > Utc::now()
+                        && crypto::hash_token(&session.token) == claims.token_hash
+                })
+                .ok_or(ApiError::Unauthorized)?;
+            Some(session.host_user_id)
+        }
+    };
+
+    Ok(ResolvedToken {
+        signer,
+        host_user_id,
+    })
+}
+
+/// Resolves a credential like `resolve_credential`, additionally requiring
+/// that signers protected by an access code have unlocked the session, and
+/// that open slots have been claimed.
+async fn resolve_signing_token(
+    state: &AppState,
+    credential: &SigningCredential,
+) -> ApiResult<ResolvedToken> {
+    let resolved = resolve_credential(state, credential).await?;
+
+    if resolved.signer.status == SignerStatus::Removed {
+        return Err(ApiError::LinkExpired(
+            "You are no longer a signer of this document".to_string(),
+        ));
+    }
+
+    if let Some(link) = db::signer::get_open_link(&state.pool, resolved.signer.id).await? {
+        if link.claimed_at.is_none() {
+            return Err(ApiError::BadRequest(
+                "This signing link has not been claimed yet".to_string(),
+            ));
+        }
+    }
+
+    if let Some(access_code) = db::signer::get_access_code(&state.pool, resolved.signer.id).await? {
+        if access_code.unlocked_at.is_none() {
+            return Err(ApiError::BadRequest(
+                "An access code is required to open this signing session".to_string(),
+            ));
+        }
+    }
+
+    Ok(resolved)
+}
+
+/// Exchanges a signer's link token for a short-lived session token, so the
+/// link token does not have to travel in the path of every request. When the
+/// session expires mid-signing, exchanging the link token again continues it.
+#[utoipa::path(
+    post,
+    path = "/sign/{token}/session",
+    tag = "signing",
+    params(("token" = String, Path, description = "Signing token from the signer's link")),
+    responses((status = 200, description = "A session token for the signing endpoints", body = SigningSessionToken)),
+    security(())
+)]
+pub async fn create_signing_session(
+    State(state): State<AppState>,
+    Path(token): Path<String>,
+) -> ApiResult<Json<SigningSessionToken>> {
+    let ResolvedToken { signer, .. } = resolve_token(&state, &token).await?;
+
+    let now = Utc::now();
+    let mut expires_at = now + Duration::minutes(state.config.signing_session_minutes);
+
+    let in_person_session = if signer.access_token == token {
+        None
+    } else {
+        db::signer::get_in_person_session_by_token(&state.pool, &token).await?
+    };
+    if let Some(session) = &in_person_session {
+        expires_at = expires_at.min(session.expires_at);
+    }
+
+    let claims = SigningSessionClaims {
+        sub: signer.id.to_string(),
+        signer_id: signer.id,
+        token_hash: crypto::hash_token(&token),
+        in_person_session_id: in_person_session.map(|session| session.id),
+        iat: now.timestamp(),
+        exp: expires_at.timestamp(),
+    };
+
+    Ok(Json(SigningSessionToken {
+        session_token: state.jwt_keys.encode_for(Audience::Signing, &claims)?,
+        expires_at,
+    }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/sign/{token}/unlock",
+    tag = "signing",
+    params(("token" = String, Path, description = "Signing token from the signer's link")),
+    request_body = UnlockRequest,
+    responses((status = 200, description = "The link is unlocked", body = serde_json::Value)),
+    security(())
+)]
+pub async fn unlock_signing(
+    State(state): State<AppState>,
+    credential: SigningCredential,
+    headers: axum::http::HeaderMap,
+    Json(req): Json<UnlockRequest>,
+) -> ApiResult<Json<serde_json::Value>> {
+    let (ip_address, user_agent) = extract_client_info_from_headers(&headers);
+
+    let ResolvedToken {
+        signer,
+        host_user_id,
+    } = resolve_credential(&state, &credential).await?;
+
+    let access_code = db::signer::get_access_code(&state.pool, signer.id)
+        .await?
+        .ok_or_else(|| {
+            ApiError::BadRequest("This signing session does not require an access code".to_string())
+        })?;
+
+    if access_code
+        .locked_until
+        .is_some_and(|locked_until| locked_until > Utc::now())
+    {
+        return Err(ApiError::TooManyRequests(
+            "Too many incorrect access codes, please try again later".to_string(),
+        ));
+    }
+
+    let valid = bcrypt::verify(req.access_code.trim(), &access_code.code_hash).unwrap_or(false);
+
+    if !valid {
+        db::signer::record_access_code_failure(
+            &state.pool,
+            signer.id,
+            state.config.verification_max_attempts,
+            Utc::now() + Duration::minutes(state.config.verification_lockout_minutes),
+        )
+        .await?;
+
+        audit::log_action(
+            &state.pool,
+            state.hash_algorithm,
+            signer.document_id,
+            Some(signer.id),
+            host_user_id,
+            AuditAction::AccessCodeFailed,
+            Some(&ip_address),
+            Some(&user_agent),
+            Some(signing::in_person_audit_details(
+                host_user_id,
+                serde_json::json!({
+                    "signer_email": signer.email
+                }),
+            )),
+        )
+        .await?;
+
+        return Err(ApiError::BadRequest("Incorrect access code".to_string()));
+    }
+
+    db::signer::mark_access_code_unlocked(&state.pool, signer.id).await?;
+
+    audit::log_action(
+        &state.pool,
+        state.hash_algorithm,
+        signer.document_id,
+        Some(signer.id),
+        host_user_id,
+        AuditAction::SignerUnlocked,
+        Some(&ip_address),
+        Some(&user_agent),
+        Some(signing::in_person_audit_details(
+            host_user_id,
+            serde_json::json!({
+                "signer_email": signer.email
+            }),
+        )),
+    )
+    .await?;
+
+    Ok(Json(serde_json::json!({ "success": true })))
+}
+
+/// Claims an open signer slot: the visitor's name and email replace the
+/// slot's placeholders and they get the slot's own signing token. Only the
+/// first claim succeeds; later visitors are told the link has been claimed.
+#[utoipa::path(
+    post,
+    path = "/sign/{token}/claim",
+    tag = "signing",
+    params(("token" = String, Path, description = "Token from the document's open link")),
+    request_body = ClaimSigningLinkRequest,
+    responses(
+        (status = 200, description = "The claimant's signing link", body = ClaimSigningLinkResponse),
+        (status = 409, description = "Someone else already claimed the link, or the email is already a signer's", body = ErrorResponse),
+    ),
+    security(())
+)]
+pub async fn claim_signing_link(
+    State(state): State<AppState>,
+    Path(token): Path<String>,
+    headers: axum::http::HeaderMap,
+    Json(req): Json<ClaimSigningLinkRequest>,
+) -> ApiResult<Json<ClaimSigningLinkResponse>> {
+    let (ip_address, user_agent) = extract_client_info_from_headers(&headers);
+
+    req.validate()
+        .map_err(|e| ApiError::Validation(e.to_string()))?;
+
+    let link = db::signer::get_open_link_by_token(&state.pool, &token)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Invalid signing link".to_string()))?;
+
+    if link.claimed_at.is_some() {
+        return Err(ApiError::Conflict("This link has been claimed".to_string()));
+    }
+
+    let signer = db::signer::get_signer_by_id(&state.pool, link.signer_id)
+        .await?
+        .filter(|s| s.status != SignerStatus::Removed)
+        .ok_or_else(|| ApiError::NotFound("Invalid signing link".to_string()))?;
+
+    let document = db::document::get_document_by_id(&state.pool, signer.document_id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Document not found".to_string()))?;
+
+    if document.status != DocumentStatus::Pending
+        || document
+            .expires_at
+            .is_some_and(|expires_at| expires_at <= Utc::now())
+    {
+        return Err(ApiError::BadRequest(
+            "This document is not open for signing".to_string(),
+        ));
+    }
+
+    let name = req.name.trim();
+    let email = req.email.trim();
 
-#[derive(Debug, Serialize)]
+    check_signer_email(&state, document.id, email, None).await?;
+
+    let mut tx = state.pool.begin().await?;
+
+    let link = db::signer::claim_open_link(&mut tx, &token, &ip_address, &user_agent)
+        .await?
+        .ok_or_else(|| ApiError::Conflict("This link has been claimed".to_string()))?;
+
+    let signer = match db::signer::set_signer_identity(&mut tx, link.signer_id, name, email).await {
+        Ok(signer) => signer,
+        Err(e) => return Err(signer_email_conflict(&state, document.id, email, e).await),
+    };
+
+    if link.emails_signer() && state.email_outbox.enabled() {
+        db::outbox::enqueue_email(
+            &mut *tx,
+            OutboxEmailKind::SigningRequest,
+            document.id,
+            Some(signer.id),
+        )
+        .await?;
+    }
+
+    audit::log_action_in(
+        &mut tx,
+        state.hash_algorithm,
+        document.id,
+        Some(signer.id),
+        None,
+        AuditAction::SignerClaimed,
+        Some(&ip_address),
+        Some(&user_agent),
+        Some(serde_json::json!({
+            "signer_name": signer.name,
+            "signer_email": signer.email,
+            "claimed_at": link.claimed_at,
+            "single_use": link.single_use
+        })),
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    if link.emails_signer() {
+        state.email_outbox.deliver_document(document.id).await;
+    }
+
+    Ok(Json(ClaimSigningLinkResponse {
+        signer_id: signer.id,
+        signing_url: format!("{}/sign/{}", state.config.public_url, signer.access_token),
+        token: signer.access_token,
+    }))
+}
+
+#[derive(Debug, Serialize, ToSchema)]
 pub struct SigningSession {
     pub document_id: Uuid,
     pub document_title: String,
     pub signer: SignerInfo,
     pub fields: Vec<DocumentFieldRow>,
+    /// The choices among the radio fields above, each selected by setting
+    /// exactly one of its options to `true`.
+    pub radio_groups: Vec<RadioGroup>,
     pub page_count: usize,
+    pub pages: Vec<DocumentPage>,
+    pub signature_fonts: Vec<&'static str>,
+    pub verification_required: bool,
+    pub verification_method: Option<String>,
+    /// The text the signer agrees to before signing, and its version.
+    pub consent_text: String,
+    pub consent_version: String,
+    /// Whether the signature or approval is refused until they agree.
+    pub consent_required: bool,
+    /// Whether they agreed to the current version of the text.
+    pub consent_given: bool,
+    /// Language the signer is written to in, e.g. `fr`, for the signing page
+    /// to use.
+    pub locale: String,
+    /// The owner's branding, for the signing page to theme itself with; `None`
+    /// when they have not set any.
+    pub branding: Option<Branding>,
+    /// What the signer saved with `PUT /sign/{token}/progress`, limited to
+    /// the fields above they can change, for the page to fill back in.
+    pub saved_progress: Option<SigningProgress>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct SignerInfo {
     pub id: Uuid,
     pub name: String,
     pub email: String,
+    pub role: SignerRole,
     pub status: SignerStatus,
 }
 
+#[utoipa::path(
+    get,
+    path = "/sign/{token}",
+    tag = "signing",
+    params(("token" = String, Path, description = "Signing token from the signer's link")),
+    responses((status = 200, description = "The document and fields to sign", body = SigningSession)),
+    security(())
+)]
 pub async fn get_signing_session(
     State(state): State<AppState>,
-    Path(token): Path<String>,
+    credential: SigningCredential,
     request: Request,
 ) -> ApiResult<Json<SigningSession>> {
     let (ip_address, user_agent) = extract_client_info(&request);
 
-    let signer = db::signer::get_signer_by_access_token(&state.pool, &token)
-        .await?
-        .ok_or_else(|| ApiError::NotFound("Invalid signing link".to_string()))?;
+    let ResolvedToken {
+        signer,
+        host_user_id,
+    } = resolve_signing_token(&state, &credential).await?;
 
     let document = db::document::get_document_by_id(&state.pool, signer.document_id)
         .await?
         .ok_or_else(|| ApiError::NotFound("Document not found".to_string()))?;
 
     if document.status == DocumentStatus::Voided {
-        return Err(ApiError::BadRequest(
-            "This document has been voided".to_string(),
-        ));
+        return Err(ApiError::BadRequest(match &document.void_reason {
+            Some(reason) => format!("This document has been voided: {}", reason),
+            None => "This document has been voided".to_string(),
+        }));
     }
 
     if document.status == DocumentStatus::Expired {
@@ -74,59 +566,146 @@ pub async fn get_signing_session(
         ));
     }
 
+    if signer.status == SignerStatus::Approved {
+        return Err(ApiError::BadRequest(
+            "You have already approved this document".to_string(),
+        ));
+    }
+
     if signer.viewed_at.is_none() {
         db::signer::mark_signer_viewed(&state.pool, signer.id, &ip_address, &user_agent).await?;
 
         audit::log_action(
             &state.pool,
+            state.hash_algorithm,
             document.id,
             Some(signer.id),
-            None,
+            host_user_id,
             AuditAction::SignerViewed,
             Some(&ip_address),
             Some(&user_agent),
-            Some(serde_json::json!({
-                "signer_email": signer.email
-            })),
+            Some(signing::in_person_audit_details(
+                host_user_id,
+                serde_json::json!({
+                    "signer_email": signer.email,
+                    "location": state.geoip.lookup(&ip_address)
+                }),
+            )),
         )
         .await?;
     }
 
+    Ok(Json(
+        signing_session(&state, document, signer, false).await?,
+    ))
+}
+
+/// What `signer` is shown on opening their link: the document, its pages and
+/// the fields they fill in. Fields stay hidden until the signer has verified
+/// their identity, unless `preview`, for the owner checking what the signer
+/// will see once through.
+pub(crate) async fn signing_session(
+    state: &AppState,
+    document: Document,
+    signer: Signer,
+    preview: bool,
+) -> ApiResult<SigningSession> {
+    // Loaded first: caching page sizes also normalizes the fields' coordinates
+    let pages = pdf::load_pages(
+        &state.pool,
+        state.storage.as_ref(),
+        &state.pdf_workers,
+        &document,
+    )
+    .await
+    .map_err(|e| ApiError::Internal(anyhow::anyhow!("Failed to read PDF: {}", e)))?;
     let fields = db::document::get_fields_by_document(&state.pool, document.id).await?;
 
-    let signer_fields: Vec<DocumentFieldRow> = fields
-        .into_iter()
-        .filter(|f| f.signer_id.is_none() || f.signer_id == Some(signer.id))
-        .collect();
+    let verification_required =
+        verification::verification_required(&document, &signer, &state.config);
+
+    let signer_fields: Vec<DocumentFieldRow> =
+        if signer.role == SignerRole::Approver || (verification_required && !preview) {
+            Vec::new()
+        } else {
+            fields
+                .into_iter()
+                .filter(|f| f.signer_id.is_none() || f.signer_id == Some(signer.id))
+                .collect()
+        };
 
-    let metadata =
-        crate::services::pdf::get_pdf_metadata(std::path::Path::new(&document.file_path))
-            .map_err(|e| ApiError::Internal(anyhow::anyhow!("Failed to read PDF: {}", e)))?;
+    // The owner previewing the page does not see what the signer filled in
+    let saved_progress = if preview || signer_fields.is_empty() {
+        None
+    } else {
+        db::signer::get_signing_progress(&state.pool, signer.id)
+            .await?
+            .map(|mut progress| {
+                let values = &mut progress.values.0;
+                let shown = |field_id: &Uuid| signer_fields.iter().any(|f| f.id == *field_id);
+                let editable = |field_id: &Uuid| {
+                    signer_fields
+                        .iter()
+                        .any(|f| f.id == *field_id && !f.read_only)
+                };
+                values.signatures.retain(|s| shown(&s.field_id));
+                values.field_values.retain(|v| editable(&v.field_id));
+                progress
+            })
+    };
+
+    let consent_given = has_consented(state, &signer);
+    let locale = i18n::signer_locale(&signer, &document, state.config.default_locale);
+    let branding = db::branding::get_branding(&state.pool, document.owner_id)
+        .await?
+        .map(|b| b.public(&state.config.public_url));
 
-    Ok(Json(SigningSession {
+    Ok(SigningSession {
         document_id: document.id,
         document_title: document.title,
         signer: SignerInfo {
             id: signer.id,
             name: signer.name,
             email: signer.email,
+            role: signer.role,
             status: signer.status,
         },
+        radio_groups: radio_group::groups(&signer_fields),
         fields: signer_fields,
-        page_count: metadata.page_count,
-    }))
+        page_count: pages.len(),
+        pages,
+        signature_fonts: state.signature_renderer.available_fonts(),
+        verification_required,
+        verification_method: document.verification_method,
+        consent_text: state.config.esign_consent_text.clone(),
+        consent_version: state.config.consent_version(),
+        consent_required: state.config.esign_consent_required,
+        consent_given,
+        locale: locale.code().to_string(),
+        branding,
+        saved_progress,
+    })
 }
 
+#[utoipa::path(
+    get,
+    path = "/sign/{token}/pdf",
+    tag = "signing",
+    params(("token" = String, Path, description = "Signing token from the signer's link")),
+    responses((status = 200, description = "The document to sign", content_type = "application/pdf")),
+    security(())
+)]
 pub async fn get_signing_pdf(
     State(state): State<AppState>,
-    Path(token): Path<String>,
+    credential: SigningCredential,
     request: Request,
 ) -> ApiResult<Response<Body>> {
     let (ip_address, user_agent) = extract_client_info(&request);
 
-    let signer = db::signer::get_signer_by_access_token(&state.pool, &token)
-        .await?
-        .ok_or_else(|| ApiError::NotFound("Invalid signing link".to_string()))?;
+    let ResolvedToken {
+        signer,
+        host_user_id,
+    } = resolve_signing_token(&state, &credential).await?;
 
     let document = db::document::get_document_by_id(&state.pool, signer.document_id)
         .await?
@@ -136,42 +715,290 @@ pub async fn get_signing_pdf(
         return Err(ApiError::BadRequest("Document not available".to_string()));
     }
 
-    audit::log_action(
-        &state.pool,
-        document.id,
-        Some(signer.id),
-        None,
-        AuditAction::DocumentViewed,
-        Some(&ip_address),
-        Some(&user_agent),
-        None,
+    file_response::ensure_intact(
+        &state,
+        &document,
+        request.headers(),
+        &IntegrityActor {
+            signer_id: Some(signer.id),
+            user_id: host_user_id,
+            ip_address: Some(&ip_address),
+            user_agent: Some(&user_agent),
+            source: SecurityEventSource::Request,
+        },
     )
     .await?;
 
-    let file_data = fs::read(&document.file_path)
-        .await
-        .map_err(|e| ApiError::Internal(anyhow::anyhow!("Failed to read file: {}", e)))?;
+    // Viewers fetch large PDFs in chunks; only the first request counts as a view
+    if file_response::is_initial_request(request.headers()) {
+        audit::log_action(
+            &state.pool,
+            state.hash_algorithm,
+            document.id,
+            Some(signer.id),
+            host_user_id,
+            AuditAction::DocumentViewed,
+            Some(&ip_address),
+            Some(&user_agent),
+            host_user_id
+                .map(|_| signing::in_person_audit_details(host_user_id, serde_json::json!({}))),
+        )
+        .await?;
+    }
 
-    let response = Response::builder()
+    let builder = Response::builder()
         .header(header::CONTENT_TYPE, "application/pdf")
-        .header(header::CACHE_CONTROL, "no-cache, no-store, must-revalidate")
-        .body(Body::from(file_data))
-        .map_err(|e| ApiError::Internal(anyhow::anyhow!("Failed to build response: {}", e)))?;
+        .header(header::CACHE_CONTROL, "private, max-age=0, must-revalidate");
 
-    Ok(response)
+    file_response::stream_file(
+        state.storage.as_ref(),
+        &document.file_path,
+        document.original_encryption().as_ref(),
+        &file_response::etag_for_hash(&document.file_hash),
+        request.headers(),
+        builder,
+    )
+    .await
 }
 
+#[utoipa::path(
+    get,
+    path = "/sign/{token}/pages/{page}/preview.png",
+    tag = "signing",
+    params(
+        ("token" = String, Path, description = "Signing token from the signer's link"),
+        ("page" = u32, Path, description = "Page number, from 1"),
+        file_response::PreviewQuery,
+    ),
+    responses((status = 200, description = "PNG preview of the page", content_type = "image/png")),
+    security(())
+)]
+pub async fn get_signing_page_preview(
+    State(state): State<AppState>,
+    Path((_token, page)): Path<(String, u32)>,
+    credential: SigningCredential,
+    Query(query): Query<file_response::PreviewQuery>,
+    request: Request,
+) -> ApiResult<Response<Body>> {
+    let (ip_address, user_agent) = extract_client_info(&request);
+
+    let ResolvedToken {
+        signer,
+        host_user_id,
+    } = resolve_signing_token(&state, &credential).await?;
+
+    let document = db::document::get_document_by_id(&state.pool, signer.document_id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Document not found".to_string()))?;
+
+    if document.status == DocumentStatus::Voided || document.status == DocumentStatus::Expired {
+        return Err(ApiError::BadRequest("Document not available".to_string()));
+    }
+
+    file_response::page_preview(
+        &state,
+        &document,
+        page,
+        &query,
+        request.headers(),
+        &IntegrityActor {
+            signer_id: Some(signer.id),
+            user_id: host_user_id,
+            ip_address: Some(&ip_address),
+            user_agent: Some(&user_agent),
+            source: SecurityEventSource::Request,
+        },
+    )
+    .await
+}
+
+#[utoipa::path(
+    post,
+    path = "/sign/{token}/submit",
+    tag = "signing",
+    params(("token" = String, Path, description = "Signing token from the signer's link")),
+    request_body = CompleteSigningRequest,
+    responses((status = 200, description = "The signatures were applied", body = serde_json::Value)),
+    security(())
+)]
 pub async fn submit_signing(
     State(state): State<AppState>,
-    Path(token): Path<String>,
+    credential: SigningCredential,
     headers: axum::http::HeaderMap,
     Json(req): Json<CompleteSigningRequest>,
 ) -> ApiResult<Json<serde_json::Value>> {
     let (ip_address, user_agent) = extract_client_info_from_headers(&headers);
 
-    let signer = db::signer::get_signer_by_access_token(&state.pool, &token)
+    let ResolvedToken {
+        signer,
+        host_user_id,
+    } = resolve_signing_token(&state, &credential).await?;
+
+    let document = db::document::get_document_by_id(&state.pool, signer.document_id)
         .await?
-        .ok_or_else(|| ApiError::NotFound("Invalid signing link".to_string()))?;
+        .ok_or_else(|| ApiError::NotFound("Document not found".to_string()))?;
+
+    if document.status == DocumentStatus::Voided {
+        return Err(ApiError::BadRequest("Document has been voided".to_string()));
+    }
+
+    ensure_verified(&state, &document, &signer)?;
+    ensure_consented(&state, &signer)?;
+
+    let location = state.geoip.lookup(&ip_address);
+
+    let ctx = signing::SigningContext {
+        signer_id: signer.id,
+        document_id: document.id,
+        ip_address,
+        user_agent,
+        actor: host_user_id
+            .map(SigningActor::InPersonHost)
+            .unwrap_or(SigningActor::AccessToken),
+        location,
+        hash_algorithm: state.hash_algorithm,
+    };
+
+    // Completed documents are still let through: the signer who completed
+    // it may be retrying a submission whose response never arrived.
+    let submission = signing::process_signing(
+        &state.pool,
+        state.storage.as_ref(),
+        &state.signature_renderer,
+        state.config.max_signature_size_bytes(),
+        &ctx,
+        &req,
+    )
+    .await
+    .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+    let already_submitted = submission == SigningSubmission::AlreadySubmitted;
+
+    let updated_doc = db::document::get_document_by_id(&state.pool, document.id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Document not found".to_string()))?;
+
+    if updated_doc.status == DocumentStatus::Completed && !already_submitted {
+        state.document_sealer.seal_completed(&updated_doc).await;
+        state.timestamps.stamp_completed(&updated_doc).await;
+    }
+
+    if !already_submitted {
+        state.events.dispatch_document(document.id).await;
+    }
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "document_completed": updated_doc.status == DocumentStatus::Completed,
+        "already_submitted": already_submitted
+    })))
+}
+
+/// Saves what the signer has filled in so far, replacing anything saved
+/// before, so they can close the page and pick up where they left off.
+/// Nothing is signed or recorded in the audit trail until they submit.
+#[utoipa::path(
+    put,
+    path = "/sign/{token}/progress",
+    tag = "signing",
+    params(("token" = String, Path, description = "Signing token from the signer's link")),
+    request_body = CompleteSigningRequest,
+    responses((status = 200, description = "The progress was saved", body = serde_json::Value)),
+    security(())
+)]
+pub async fn save_signing_progress(
+    State(state): State<AppState>,
+    credential: SigningCredential,
+    Json(req): Json<CompleteSigningRequest>,
+) -> ApiResult<Json<serde_json::Value>> {
+    let ResolvedToken { signer, .. } = resolve_signing_token(&state, &credential).await?;
+
+    let document = db::document::get_document_by_id(&state.pool, signer.document_id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Document not found".to_string()))?;
+
+    if document.status != DocumentStatus::Pending {
+        return Err(ApiError::BadRequest(
+            "This document is not awaiting signatures".to_string(),
+        ));
+    }
+
+    if signer.role == SignerRole::Approver {
+        return Err(ApiError::BadRequest(
+            "Approvers have no fields to fill in".to_string(),
+        ));
+    }
+
+    ensure_verified(&state, &document, &signer)?;
+
+    let fields = db::document::get_fields_by_document(&state.pool, document.id).await?;
+    let is_theirs = |field_id: Uuid| {
+        fields
+            .iter()
+            .any(|f| f.id == field_id && (f.signer_id.is_none() || f.signer_id == Some(signer.id)))
+    };
+    if let Some(field_id) = req
+        .signatures
+        .iter()
+        .map(|s| s.field_id)
+        .chain(req.field_values.iter().map(|v| v.field_id))
+        .find(|field_id| !is_theirs(*field_id))
+    {
+        return Err(ApiError::BadRequest(format!(
+            "Field {} is not one of yours",
+            field_id
+        )));
+    }
+
+    let max_bytes = state.config.max_signing_progress_bytes();
+    let size = serde_json::to_vec(&req)
+        .map_err(|e| ApiError::Internal(e.into()))?
+        .len();
+    if size > max_bytes {
+        return Err(ApiError::BadRequest(format!(
+            "Saved progress exceeds the maximum size of {} KB",
+            max_bytes / 1024
+        )));
+    }
+
+    let saved_at = db::signer::save_signing_progress(&state.pool, signer.id, &req)
+        .await?
+        .ok_or_else(|| {
+            ApiError::BadRequest("You have already responded to this document".to_string())
+        })?;
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "saved_at": saved_at
+    })))
+}
+
+#[utoipa::path(
+    post,
+    path = "/sign/{token}/approve",
+    tag = "signing",
+    params(("token" = String, Path, description = "Signing token from the signer's link")),
+    request_body = ApprovalRequest,
+    responses((status = 200, description = "The document was approved", body = serde_json::Value)),
+    security(())
+)]
+pub async fn approve_signing(
+    State(state): State<AppState>,
+    credential: SigningCredential,
+    headers: axum::http::HeaderMap,
+    Json(req): Json<ApprovalRequest>,
+) -> ApiResult<Json<serde_json::Value>> {
+    let (ip_address, user_agent) = extract_client_info_from_headers(&headers);
+
+    let ResolvedToken {
+        signer,
+        host_user_id,
+    } = resolve_signing_token(&state, &credential).await?;
+
+    if signer.role != SignerRole::Approver {
+        return Err(ApiError::BadRequest(
+            "Only approvers can approve this document".to_string(),
+        ));
+    }
 
     let document = db::document::get_document_by_id(&state.pool, signer.document_id)
         .await?
@@ -187,14 +1014,47 @@ pub async fn submit_signing(
         ));
     }
 
+    ensure_verified(&state, &document, &signer)?;
+    ensure_consented(&state, &signer)?;
+
+    let location = state.geoip.lookup(&ip_address);
+
     let ctx = signing::SigningContext {
         signer_id: signer.id,
         document_id: document.id,
         ip_address,
         user_agent,
+        actor: host_user_id
+            .map(SigningActor::InPersonHost)
+            .unwrap_or(SigningActor::AccessToken),
+        location,
+        hash_algorithm: state.hash_algorithm,
     };
 
-    signing::process_signing(&state.pool, &ctx, &req)
+    if req.decision == ApprovalDecision::Reject {
+        let reason = req
+            .reason
+            .as_deref()
+            .map(str::trim)
+            .filter(|r| !r.is_empty())
+            .ok_or_else(|| {
+                ApiError::BadRequest("A reason is required to reject a document".to_string())
+            })?;
+
+        signing::decline_signing(&state.pool, &ctx, Some(reason))
+            .await
+            .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+
+        state.events.dispatch_document(document.id).await;
+
+        return Ok(Json(serde_json::json!({
+            "success": true,
+            "approved": false,
+            "document_completed": false
+        })));
+    }
+
+    signing::process_approval(&state.pool, &ctx)
         .await
         .map_err(|e| ApiError::BadRequest(e.to_string()))?;
 
@@ -203,53 +1063,369 @@ pub async fn submit_signing(
         .ok_or_else(|| ApiError::NotFound("Document not found".to_string()))?;
 
     if updated_doc.status == DocumentStatus::Completed {
-        if let Some(email_service) = &state.email_service {
-            let owner = db::user::get_user_by_id(&state.pool, document.owner_id).await?;
-            if let Some(owner) = owner {
-                let _ = email_service
-                    .send_completion_notification(&owner.email, &owner.name, &document.title)
-                    .await;
-            }
-
-            let signers = db::signer::get_signers_by_document(&state.pool, document.id).await?;
-            for s in signers {
-                if s.status == SignerStatus::Signed {
-                    let _ = email_service
-                        .send_completion_notification(&s.email, &s.name, &document.title)
-                        .await;
-                }
-            }
-        }
+        state.document_sealer.seal_completed(&updated_doc).await;
+        state.timestamps.stamp_completed(&updated_doc).await;
     }
 
+    state.events.dispatch_document(document.id).await;
+
     Ok(Json(serde_json::json!({
         "success": true,
+        "approved": true,
         "document_completed": updated_doc.status == DocumentStatus::Completed
     })))
 }
 
-pub async fn decline_signing_request(
+fn ensure_verified(state: &AppState, document: &Document, signer: &Signer) -> ApiResult<()> {
+    if verification::verification_required(document, signer, &state.config) {
+        return Err(ApiError::BadRequest(
+            "Identity verification is required before signing".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Whether the signer agreed to the consent text in its current version.
+fn has_consented(state: &AppState, signer: &Signer) -> bool {
+    signer.consented_at.is_some()
+        && signer.consent_version.as_deref() == Some(state.config.consent_version().as_str())
+}
+
+fn ensure_consented(state: &AppState, signer: &Signer) -> ApiResult<()> {
+    if state.config.esign_consent_required && !has_consented(state, signer) {
+        return Err(ApiError::BadRequest(
+            "You must agree to sign electronically before signing".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+fn verification_error(e: VerificationError) -> ApiError {
+    match e {
+        VerificationError::LockedOut | VerificationError::TooManyCodes => {
+            ApiError::TooManyRequests(e.to_string())
+        }
+        VerificationError::NoActiveCode | VerificationError::InvalidProof(_) => {
+            ApiError::BadRequest(e.to_string())
+        }
+        VerificationError::Other(e) => ApiError::Internal(e),
+    }
+}
+
+/// Loads the document for a verification request along with the provider it
+/// requires, rejecting documents that do not require verification.
+async fn verification_provider(
+    state: &AppState,
+    signer: &Signer,
+) -> ApiResult<(Document, Arc<dyn IdentityVerifier>)> {
+    let document = db::document::get_document_by_id(&state.pool, signer.document_id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Document not found".to_string()))?;
+
+    let method = document.verification_method.as_deref().ok_or_else(|| {
+        ApiError::BadRequest("Identity verification is not required for this document".to_string())
+    })?;
+
+    if document.status != DocumentStatus::Pending {
+        return Err(ApiError::BadRequest(
+            "Document is not awaiting signatures".to_string(),
+        ));
+    }
+
+    let verifier = state.identity_verifiers.get(method).ok_or_else(|| {
+        ApiError::Internal(anyhow::anyhow!(
+            "Verification provider {} is not enabled",
+            method
+        ))
+    })?;
+
+    Ok((document, verifier))
+}
+
+/// Records the signer's consent to do business electronically. `version`
+/// must be the one the signer was shown, so the consent recorded is to the
+/// text they read.
+#[utoipa::path(
+    post,
+    path = "/sign/{token}/consent",
+    tag = "signing",
+    params(("token" = String, Path, description = "Signing token from the signer's link")),
+    request_body = ConsentRequest,
+    responses(
+        (status = 200, description = "The consent was recorded", body = serde_json::Value),
+        (status = 409, description = "The consent text has changed since it was shown", body = ErrorResponse)
+    ),
+    security(())
+)]
+pub async fn give_consent(
     State(state): State<AppState>,
-    Path(token): Path<String>,
+    credential: SigningCredential,
     headers: axum::http::HeaderMap,
-    Json(req): Json<DeclineRequest>,
+    Json(req): Json<ConsentRequest>,
 ) -> ApiResult<Json<serde_json::Value>> {
     let (ip_address, user_agent) = extract_client_info_from_headers(&headers);
 
-    let signer = db::signer::get_signer_by_access_token(&state.pool, &token)
+    let ResolvedToken {
+        signer,
+        host_user_id,
+    } = resolve_signing_token(&state, &credential).await?;
+
+    let version = state.config.consent_version();
+    if req.version != version {
+        return Err(ApiError::Conflict(
+            "The consent text has changed, reload the page to read it".to_string(),
+        ));
+    }
+
+    let document = db::document::get_document_by_id(&state.pool, signer.document_id)
         .await?
-        .ok_or_else(|| ApiError::NotFound("Invalid signing link".to_string()))?;
+        .ok_or_else(|| ApiError::NotFound("Document not found".to_string()))?;
 
-    signing::decline_signing(
+    if document.status != DocumentStatus::Pending {
+        return Err(ApiError::BadRequest(
+            "Document is not awaiting signatures".to_string(),
+        ));
+    }
+
+    if !matches!(
+        signer.status,
+        SignerStatus::Pending | SignerStatus::Sent | SignerStatus::Viewed
+    ) {
+        return Err(ApiError::BadRequest(
+            "You have already responded to this document".to_string(),
+        ));
+    }
+
+    if has_consented(&state, &signer) {
+        return Ok(Json(serde_json::json!({
+            "success": true,
+            "consented_at": signer.consented_at,
+            "consent_version": signer.consent_version
+        })));
+    }
+
+    let signer =
+        db::signer::record_consent(&state.pool, signer.id, &version, &ip_address, &user_agent)
+            .await?;
+
+    audit::log_action(
         &state.pool,
-        signer.id,
-        signer.document_id,
-        req.reason.as_deref(),
-        &ip_address,
-        &user_agent,
+        state.hash_algorithm,
+        document.id,
+        Some(signer.id),
+        host_user_id,
+        AuditAction::ConsentGiven,
+        Some(&ip_address),
+        Some(&user_agent),
+        Some(signing::in_person_audit_details(
+            host_user_id,
+            serde_json::json!({
+                "signer_email": signer.email,
+                "consent_version": version,
+                "consent_text": state.config.esign_consent_text
+            }),
+        )),
     )
-    .await
-    .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+    .await?;
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "consented_at": signer.consented_at,
+        "consent_version": signer.consent_version
+    })))
+}
+
+#[utoipa::path(
+    post,
+    path = "/sign/{token}/verification/start",
+    tag = "signing",
+    params(("token" = String, Path, description = "Signing token from the signer's link")),
+    responses((status = 200, description = "What the signer needs to do to verify", body = VerificationChallenge)),
+    security(())
+)]
+pub async fn start_verification(
+    State(state): State<AppState>,
+    credential: SigningCredential,
+    headers: axum::http::HeaderMap,
+) -> ApiResult<Json<VerificationChallenge>> {
+    let (ip_address, user_agent) = extract_client_info_from_headers(&headers);
+
+    let ResolvedToken {
+        signer,
+        host_user_id,
+    } = resolve_signing_token(&state, &credential).await?;
+
+    let (document, verifier) = verification_provider(&state, &signer).await?;
+
+    let challenge = verifier
+        .start_verification(&signer)
+        .await
+        .map_err(verification_error)?;
+
+    audit::log_action(
+        &state.pool,
+        state.hash_algorithm,
+        document.id,
+        Some(signer.id),
+        host_user_id,
+        AuditAction::VerificationStarted,
+        Some(&ip_address),
+        Some(&user_agent),
+        Some(signing::in_person_audit_details(
+            host_user_id,
+            serde_json::json!({
+                "signer_email": signer.email,
+                "method": verifier.method()
+            }),
+        )),
+    )
+    .await?;
+
+    Ok(Json(challenge))
+}
+
+#[utoipa::path(
+    post,
+    path = "/sign/{token}/verification/complete",
+    tag = "signing",
+    params(("token" = String, Path, description = "Signing token from the signer's link")),
+    request_body = serde_json::Value,
+    responses((status = 200, description = "The signer is verified", body = serde_json::Value)),
+    security(())
+)]
+pub async fn complete_verification(
+    State(state): State<AppState>,
+    credential: SigningCredential,
+    headers: axum::http::HeaderMap,
+    Json(proof): Json<serde_json::Value>,
+) -> ApiResult<Json<serde_json::Value>> {
+    let (ip_address, user_agent) = extract_client_info_from_headers(&headers);
+
+    let ResolvedToken {
+        signer,
+        host_user_id,
+    } = resolve_signing_token(&state, &credential).await?;
+
+    let (document, verifier) = verification_provider(&state, &signer).await?;
+    let method = verifier.method();
+
+    let result = verifier
+        .check(&signer, &proof)
+        .await
+        .map_err(verification_error)?;
+
+    match result {
+        VerificationResult::Verified => {
+            let verified = db::signer::record_verification_outcome(
+                &state.pool,
+                signer.id,
+                method,
+                verification::OUTCOME_VERIFIED,
+            )
+            .await?;
+
+            audit::log_action(
+                &state.pool,
+                state.hash_algorithm,
+                document.id,
+                Some(signer.id),
+                host_user_id,
+                AuditAction::SignerVerified,
+                Some(&ip_address),
+                Some(&user_agent),
+                Some(signing::in_person_audit_details(
+                    host_user_id,
+                    serde_json::json!({
+                        "signer_email": signer.email,
+                        "method": method
+                    }),
+                )),
+            )
+            .await?;
+
+            Ok(Json(serde_json::json!({
+                "success": true,
+                "method": method,
+                "verified_at": verified.verified_at
+            })))
+        }
+        VerificationResult::Failed { reason } => {
+            db::signer::record_verification_outcome(
+                &state.pool,
+                signer.id,
+                method,
+                verification::OUTCOME_FAILED,
+            )
+            .await?;
+
+            audit::log_action(
+                &state.pool,
+                state.hash_algorithm,
+                document.id,
+                Some(signer.id),
+                host_user_id,
+                AuditAction::VerificationFailed,
+                Some(&ip_address),
+                Some(&user_agent),
+                Some(signing::in_person_audit_details(
+                    host_user_id,
+                    serde_json::json!({
+                        "signer_email": signer.email,
+                        "method": method,
+                        "reason": reason
+                    }),
+                )),
+            )
+            .await?;
+
+            Err(ApiError::BadRequest(reason))
+        }
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/sign/{token}/decline",
+    tag = "signing",
+    params(("token" = String, Path, description = "Signing token from the signer's link")),
+    request_body = DeclineRequest,
+    responses((status = 200, description = "The request was declined", body = serde_json::Value)),
+    security(())
+)]
+pub async fn decline_signing_request(
+    State(state): State<AppState>,
+    credential: SigningCredential,
+    headers: axum::http::HeaderMap,
+    Json(req): Json<DeclineRequest>,
+) -> ApiResult<Json<serde_json::Value>> {
+    let (ip_address, user_agent) = extract_client_info_from_headers(&headers);
+
+    let ResolvedToken {
+        signer,
+        host_user_id,
+    } = resolve_signing_token(&state, &credential).await?;
+
+    let location = state.geoip.lookup(&ip_address);
+
+    let ctx = signing::SigningContext {
+        signer_id: signer.id,
+        document_id: signer.document_id,
+        ip_address,
+        user_agent,
+        actor: host_user_id
+            .map(SigningActor::InPersonHost)
+            .unwrap_or(SigningActor::AccessToken),
+        location,
+        hash_algorithm: state.hash_algorithm,
+    };
+
+    signing::decline_signing(&state.pool, &ctx, req.reason.as_deref())
+        .await
+        .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+
+    state.events.dispatch_document(signer.document_id).await;
 
     Ok(Json(serde_json::json!({ "success": true })))
 }
@@ -1,27 +1,261 @@
 use sqlx::PgPool;
+use std::path::Path;
 use std::sync::Arc;
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
 
 use crate::services::config::Config;
+use crate::services::crypto::HashAlgorithm;
 use crate::services::email::EmailService;
+use crate::services::geoip::GeoIp;
+use crate::services::integrity::FileIntegrity;
+use crate::services::ip_privacy::IpPrivacy;
+use crate::services::jwt::JwtKeys;
+use crate::services::outbox::{self, EmailOutbox, EventDispatcher};
+use crate::services::pdf::PdfWorkers;
+use crate::services::preview::PagePreviews;
+use crate::services::scheduler::{Job, Scheduler};
+use crate::services::seal::DocumentSealer;
+use crate::services::security_alerts::SecurityAlerts;
+use crate::services::server_key::ServerKey;
+use crate::services::storage::{self, Storage};
+use crate::services::storage_scan::StorageScanner;
+use crate::services::timestamp::Timestamps;
+use crate::services::totp::SecretCipher;
+use crate::services::typed_signature::TypedSignatureRenderer;
+use crate::services::verification::IdentityVerifiers;
+use crate::services::{reminders, retention};
 
 #[derive(Clone)]
 pub struct AppState {
     pub pool: PgPool,
     pub config: Config,
+    /// Algorithm for new hashes; stored hashes record their own.
+    pub hash_algorithm: HashAlgorithm,
+    pub jwt_keys: Arc<JwtKeys>,
     pub email_service: Option<Arc<EmailService>>,
+    pub email_outbox: Arc<EmailOutbox>,
+    pub events: Arc<EventDispatcher>,
+    pub signature_renderer: Arc<TypedSignatureRenderer>,
+    pub identity_verifiers: Arc<IdentityVerifiers>,
+    pub geoip: Arc<GeoIp>,
+    pub ip_privacy: Arc<IpPrivacy>,
+    pub storage: Arc<dyn Storage>,
+    pub security_alerts: Arc<SecurityAlerts>,
+    pub file_integrity: Arc<FileIntegrity>,
+    pub storage_scanner: Arc<StorageScanner>,
+    pub pdf_workers: Arc<PdfWorkers>,
+    pub page_previews: Arc<PagePreviews>,
+    pub document_sealer: Arc<DocumentSealer>,
+    pub timestamps: Arc<Timestamps>,
+    pub server_key: Arc<ServerKey>,
+    pub totp_cipher: Arc<SecretCipher>,
+    pub scheduler: Arc<Scheduler<AppState>>,
+    /// Cancelled when the server starts shutting down; long-running background
+    /// work should stop once it fires.
+    pub shutdown: CancellationToken,
 }
 
 impl AppState {
-    pub fn new(pool: PgPool, config: Config) -> Self {
+    pub fn new(pool: PgPool, config: Config) -> anyhow::Result<Self> {
+        let jwt_keys = Arc::new(JwtKeys::from_config(&config));
+
         let email_service = crate::services::email::create_email_service(&config)
             .ok()
             .flatten()
             .map(Arc::new);
 
-        Self {
+        let email_outbox = Arc::new(EmailOutbox::new(
+            pool.clone(),
+            config.hash_algorithm,
+            email_service.clone(),
+        ));
+        let events = Arc::new(EventDispatcher::new(pool.clone(), email_outbox.clone()));
+
+        let signature_renderer = Arc::new(TypedSignatureRenderer::load(Path::new(
+            &config.signature_fonts_dir,
+        )));
+
+        let identity_verifiers = Arc::new(IdentityVerifiers::from_config(
+            pool.clone(),
+            &config,
+            email_service.clone(),
+        ));
+
+        let geoip = Arc::new(GeoIp::load(config.geoip_database_path.as_deref()));
+        let ip_privacy = Arc::new(IpPrivacy::new(config.ip_address_mode, config.ip_hash_key()));
+
+        let storage = storage::from_config(&config)?;
+
+        let security_alerts = Arc::new(SecurityAlerts::new(pool.clone(), email_service.clone()));
+
+        let file_integrity = Arc::new(FileIntegrity::new(
+            pool.clone(),
+            config.hash_algorithm,
+            storage.clone(),
+            security_alerts.clone(),
+        ));
+
+        let storage_scanner = Arc::new(StorageScanner::new(
+            pool.clone(),
+            &config,
+            storage.clone(),
+            file_integrity.clone(),
+        ));
+
+        let pdf_workers = Arc::new(PdfWorkers::new(config.pdf_max_concurrent_jobs));
+        let page_previews = Arc::new(PagePreviews::new(
+            &config,
+            storage.clone(),
+            pdf_workers.clone(),
+        ));
+        let document_sealer = Arc::new(DocumentSealer::load(
+            pool.clone(),
+            &config,
+            storage.clone(),
+            pdf_workers.clone(),
+        )?);
+        let timestamps = Arc::new(Timestamps::from_config(pool.clone(), &config)?);
+        let server_key = Arc::new(ServerKey::from_config(&config)?);
+        let totp_cipher = Arc::new(SecretCipher::new(&config.totp_key()?));
+        let shutdown = CancellationToken::new();
+        let scheduler = Arc::new(Scheduler::new(
+            pool.clone(),
+            background_jobs(&config, email_service.is_some(), timestamps.enabled()),
+            shutdown.clone(),
+        ));
+
+        Ok(Self {
             pool,
+            hash_algorithm: config.hash_algorithm,
             config,
+            jwt_keys,
             email_service,
+            email_outbox,
+            events,
+            signature_renderer,
+            identity_verifiers,
+            geoip,
+            ip_privacy,
+            storage,
+            security_alerts,
+            file_integrity,
+            storage_scanner,
+            pdf_workers,
+            page_previews,
+            document_sealer,
+            timestamps,
+            server_key,
+            totp_cipher,
+            scheduler,
+            shutdown,
+        })
+    }
+}
+
+/// The jobs `AppState::scheduler` runs, leaving out those whose feature is
+/// off.
+fn background_jobs(
+    config: &Config,
+    email_enabled: bool,
+    timestamps_enabled: bool,
+) -> Vec<Job<AppState>> {
+    let mut jobs = vec![
+        Job::new(
+            "retention",
+            retention::PURGE_INTERVAL,
+            |state: AppState| async move {
+                retention::purge_expired(&state.pool, state.storage.as_ref(), &state.config)
+                    .await?;
+                Ok(())
+            },
+        ),
+        Job::new(
+            "outbox_events",
+            outbox::EVENT_DISPATCH_INTERVAL,
+            |state: AppState| async move {
+                let processed = state.events.dispatch(None).await?;
+                if processed > 0 {
+                    info!("Dispatched {} outbox event(s)", processed);
+                }
+                Ok(())
+            },
+        ),
+    ];
+
+    if config.storage_scan_interval_hours > 0 {
+        jobs.push(Job::new(
+            "storage_scan",
+            Duration::from_secs(config.storage_scan_interval_hours * 3600),
+            |state: AppState| async move {
+                if state.storage_scanner.scan().await?.is_none() {
+                    warn!("Skipping scheduled storage scan, one is already running");
+                }
+                Ok(())
+            },
+        ));
+    } else {
+        info!("Scheduled storage scans disabled");
+    }
+
+    if timestamps_enabled {
+        jobs.push(Job::new(
+            "timestamp_retry",
+            Duration::from_secs(config.tsa_retry_interval_secs),
+            |state: AppState| async move {
+                let granted = state.timestamps.retry_pending().await?;
+                if granted > 0 {
+                    info!("Obtained {} pending timestamp(s)", granted);
+                }
+                Ok(())
+            },
+        ));
+    }
+
+    if email_enabled {
+        jobs.push(Job::new(
+            "email_outbox",
+            Duration::from_secs(config.email_retry_interval_secs),
+            |state: AppState| async move {
+                let summary = state.email_outbox.deliver(None).await?;
+                if summary.sent > 0 {
+                    info!("Delivered {} queued email(s)", summary.sent);
+                }
+                if summary.dropped > 0 {
+                    info!(
+                        "Dropped {} queued email(s) no longer of use",
+                        summary.dropped
+                    );
+                }
+                Ok(())
+            },
+        ));
+        jobs.push(Job::new(
+            "signing_reminders",
+            reminders::CHECK_INTERVAL,
+            |state: AppState| async move {
+                reminders::send_due_reminders(&state.pool, &state.email_outbox, &state.config)
+                    .await?;
+                Ok(())
+            },
+        ));
+        if config.expiry_warning_days > 0 {
+            jobs.push(Job::new(
+                "expiry_warnings",
+                reminders::CHECK_INTERVAL,
+                |state: AppState| async move {
+                    if let Some(email_service) = &state.email_service {
+                        reminders::send_expiry_warnings(&state.pool, email_service, &state.config)
+                            .await?;
+                    }
+                    Ok(())
+                },
+            ));
         }
+    } else {
+        info!("Email delivery, reminders and expiry warnings disabled without email");
     }
+
+    jobs
 }
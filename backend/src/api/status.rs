@@ -0,0 +1,190 @@
+use axum::{
+    body::Body,
+    extract::{Path, State},
+    http::{header, HeaderMap, Response},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::api::documents::sealed_filename;
+use crate::api::error::{ApiError, ApiResult};
+use crate::api::file_response;
+use crate::api::middleware::extract_client_info_from_headers;
+use crate::api::signing;
+use crate::api::state::AppState;
+use crate::db;
+use crate::models::audit::AuditAction;
+use crate::models::document::{Document, DocumentStatus};
+use crate::models::security_event::SecurityEventSource;
+use crate::models::signer::{Signer, SignerRole, SignerStatus};
+use crate::services::audit;
+use crate::services::integrity::IntegrityActor;
+
+/// Where an envelope stands, as shown to one of its signers. Other signers
+/// are only counted.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct EnvelopeStatus {
+    pub document_title: String,
+    pub status: DocumentStatus,
+    pub completed_signers: i32,
+    pub total_signers: i32,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub signer: SignerProgress,
+    /// Whether `GET /status/{token}/pdf` serves the completed document.
+    pub download_available: bool,
+}
+
+/// The signer's own progress.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SignerProgress {
+    pub role: SignerRole,
+    pub status: SignerStatus,
+    pub email_sent_at: Option<DateTime<Utc>>,
+    pub viewed_at: Option<DateTime<Utc>>,
+    pub signed_at: Option<DateTime<Utc>>,
+    pub approved_at: Option<DateTime<Utc>>,
+    pub declined_at: Option<DateTime<Utc>>,
+}
+
+/// Resolves a signer's access token to the signer and their document. The
+/// status page accepts the token of the signer's own link even after they
+/// signed, but not in-person session tokens.
+async fn resolve_status_token(state: &AppState, token: &str) -> ApiResult<(Signer, Document)> {
+    let Some(signer) = db::signer::get_signer_by_access_token(&state.pool, token).await? else {
+        return Err(signing::revoked_or_not_found(state, token, "Invalid status link").await?);
+    };
+
+    let document = db::document::get_document_by_id(&state.pool, signer.document_id)
+        .await?
+        .filter(|document| document.deleted_at.is_none())
+        .ok_or_else(|| ApiError::NotFound("Document not found".to_string()))?;
+
+    Ok((signer, document))
+}
+
+fn download_available(document: &Document) -> bool {
+    document.status == DocumentStatus::Completed && document.signer_download_enabled
+}
+
+#[utoipa::path(
+    get,
+    path = "/status/{token}",
+    tag = "signing",
+    params(("token" = String, Path, description = "Signing token from the signer's link")),
+    responses((status = 200, description = "The envelope's status", body = EnvelopeStatus)),
+    security(())
+)]
+pub async fn get_envelope_status(
+    State(state): State<AppState>,
+    Path(token): Path<String>,
+) -> ApiResult<Json<EnvelopeStatus>> {
+    let (signer, document) = resolve_status_token(&state, &token).await?;
+
+    Ok(Json(EnvelopeStatus {
+        download_available: download_available(&document),
+        document_title: document.title,
+        status: document.status,
+        completed_signers: document.completed_signers,
+        total_signers: document.total_signers,
+        completed_at: document.completed_at,
+        signer: SignerProgress {
+            role: signer.role,
+            status: signer.status,
+            email_sent_at: signer.email_sent_at,
+            viewed_at: signer.viewed_at,
+            signed_at: signer.signed_at,
+            approved_at: signer.approved_at,
+            declined_at: signer.declined_at,
+        },
+    }))
+}
+
+/// The completed document, sealed copy when there is one, if the owner let
+/// signers download it.
+#[utoipa::path(
+    get,
+    path = "/status/{token}/pdf",
+    tag = "signing",
+    params(("token" = String, Path, description = "Signing token from the signer's link")),
+    responses((status = 200, description = "The completed document", content_type = "application/pdf")),
+    security(())
+)]
+pub async fn get_status_pdf(
+    State(state): State<AppState>,
+    Path(token): Path<String>,
+    headers: HeaderMap,
+) -> ApiResult<Response<Body>> {
+    let (ip_address, user_agent) = extract_client_info_from_headers(&headers);
+
+    let (signer, document) = resolve_status_token(&state, &token).await?;
+
+    if !download_available(&document) {
+        return Err(ApiError::Forbidden);
+    }
+
+    let (key, hash, filename, encryption) =
+        match (&document.sealed_file_path, &document.sealed_file_hash) {
+            (Some(path), Some(hash)) => (
+                path.clone(),
+                hash.clone(),
+                sealed_filename(&document.original_filename),
+                document.sealed_encryption(),
+            ),
+            _ => {
+                file_response::ensure_intact(
+                    &state,
+                    &document,
+                    &headers,
+                    &IntegrityActor {
+                        signer_id: Some(signer.id),
+                        user_id: None,
+                        ip_address: Some(&ip_address),
+                        user_agent: Some(&user_agent),
+                        source: SecurityEventSource::Request,
+                    },
+                )
+                .await?;
+
+                (
+                    document.file_path.clone(),
+                    document.file_hash.clone(),
+                    document.original_filename.clone(),
+                    document.original_encryption(),
+                )
+            }
+        };
+
+    if file_response::is_initial_request(&headers) {
+        audit::log_action(
+            &state.pool,
+            state.hash_algorithm,
+            document.id,
+            Some(signer.id),
+            None,
+            AuditAction::DocumentDownloaded,
+            Some(&ip_address),
+            Some(&user_agent),
+            Some(serde_json::json!({ "sealed": document.sealed_file_path.is_some() })),
+        )
+        .await?;
+    }
+
+    let builder = Response::builder()
+        .header(header::CONTENT_TYPE, "application/pdf")
+        .header(
+            header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"{}\"", filename),
+        );
+
+    file_response::stream_file(
+        state.storage.as_ref(),
+        &key,
+        encryption.as_ref(),
+        &file_response::etag_for_hash(&hash),
+        &headers,
+        builder,
+    )
+    .await
+}
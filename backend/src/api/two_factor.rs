@@ -0,0 +1,393 @@
+use axum::{extract::State, http::HeaderMap, Extension, Json};
+use chrono::{DateTime, Utc};
+use validator::Validate;
+
+use crate::api::auth::issue_token;
+use crate::api::error::{ApiError, ApiResult};
+use crate::api::middleware::{extract_client_info_from_headers, AuthUser};
+use crate::api::state::AppState;
+use crate::db;
+use crate::models::auth_event::{AuthEventType, FAILURE_WRONG_TWO_FACTOR_CODE};
+use crate::models::two_factor::{
+    DisableTwoFactorRequest, EnableTwoFactorRequest, RecoveryCodes, TwoFactorChallenge,
+    TwoFactorClaims, TwoFactorSetup, TwoFactorStatus, UserTwoFactor, VerifyTwoFactorRequest,
+    CHALLENGE_PURPOSE,
+};
+use crate::models::user::{LoginResponse, User};
+use crate::services::totp;
+
+/// How long a login challenge can be exchanged for a bearer token.
+const CHALLENGE_EXPIRY_MINUTES: i64 = 5;
+
+enum SecondFactor {
+    Code,
+    RecoveryCode,
+}
+
+/// Issues the token a login with the right password gets when the account has
+/// two-factor authentication on.
+pub(crate) fn issue_challenge(state: &AppState, user: &User) -> ApiResult<TwoFactorChallenge> {
+    let now = Utc::now();
+    let expires_at = now + chrono::Duration::minutes(CHALLENGE_EXPIRY_MINUTES);
+
+    let claims = TwoFactorClaims {
+        sub: user.id.to_string(),
+        user_id: user.id,
+        purpose: CHALLENGE_PURPOSE.to_string(),
+        iat: now.timestamp(),
+        exp: expires_at.timestamp(),
+    };
+
+    let challenge_token = state.jwt_keys.encode(&claims)?;
+
+    Ok(TwoFactorChallenge {
+        two_factor_required: true,
+        challenge_token,
+        expires_at,
+    })
+}
+
+fn locked(two_factor: &UserTwoFactor, now: DateTime<Utc>) -> bool {
+    two_factor
+        .locked_until
+        .is_some_and(|locked_until| locked_until > now)
+}
+
+/// Checks a code from the authenticator app, or else a recovery code, against
+/// an enabled second factor. Returns `None` for a wrong code, which counts
+/// towards locking the second factor for a while.
+async fn check_second_factor(
+    state: &AppState,
+    two_factor: &UserTwoFactor,
+    code: &str,
+) -> ApiResult<Option<SecondFactor>> {
+    let now = Utc::now();
+    if locked(two_factor, now) {
+        return Err(ApiError::TooManyRequests(
+            "Too many incorrect codes, please try again later".to_string(),
+        ));
+    }
+
+    let secret_encrypted = two_factor.secret_encrypted.as_deref().ok_or_else(|| {
+        ApiError::BadRequest("Two-factor authentication is not enabled".to_string())
+    })?;
+    let secret = state.totp_cipher.decrypt(secret_encrypted)?;
+
+    let factor = match totp::verify_code(&secret, code, now.timestamp(), two_factor.last_used_step)
+    {
+        Some(step) => db::two_factor::record_success(&state.pool, two_factor.user_id, Some(step))
+            .await?
+            .then_some(SecondFactor::Code),
+        None => {
+            let used = db::two_factor::use_recovery_code(
+                &state.pool,
+                two_factor.user_id,
+                &totp::hash_recovery_code(code),
+            )
+            .await?;
+            if used {
+                db::two_factor::record_success(&state.pool, two_factor.user_id, None).await?;
+            }
+            used.then_some(SecondFactor::RecoveryCode)
+        }
+    };
+
+    if factor.is_none() {
+        db::two_factor::record_failure(
+            &state.pool,
+            two_factor.user_id,
+            state.config.verification_max_attempts,
+            now + chrono::Duration::minutes(state.config.verification_lockout_minutes),
+        )
+        .await?;
+    }
+
+    Ok(factor)
+}
+
+async fn get_enabled_two_factor(
+    state: &AppState,
+    user_id: uuid::Uuid,
+) -> ApiResult<Option<UserTwoFactor>> {
+    Ok(db::two_factor::get_two_factor(&state.pool, user_id)
+        .await?
+        .filter(|two_factor| two_factor.enabled_at.is_some()))
+}
+
+pub(crate) async fn two_factor_enabled(state: &AppState, user_id: uuid::Uuid) -> ApiResult<bool> {
+    Ok(get_enabled_two_factor(state, user_id).await?.is_some())
+}
+
+#[utoipa::path(
+    get,
+    path = "/auth/2fa",
+    tag = "auth",
+    responses((status = 200, description = "Whether two-factor authentication is on", body = TwoFactorStatus))
+)]
+pub async fn get_two_factor_status(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+) -> ApiResult<Json<TwoFactorStatus>> {
+    let two_factor = get_enabled_two_factor(&state, auth_user.user_id).await?;
+    let recovery_codes_remaining = match &two_factor {
+        Some(_) => {
+            db::two_factor::count_unused_recovery_codes(&state.pool, auth_user.user_id).await?
+        }
+        None => 0,
+    };
+
+    Ok(Json(TwoFactorStatus {
+        enabled: two_factor.is_some(),
+        enabled_at: two_factor.and_then(|two_factor| two_factor.enabled_at),
+        recovery_codes_remaining,
+    }))
+}
+
+/// Generates a new TOTP secret for the authenticated user. Two-factor
+/// authentication is only turned on once a code from it is confirmed.
+#[utoipa::path(
+    post,
+    path = "/auth/2fa/setup",
+    tag = "auth",
+    responses((status = 200, description = "The secret to add to an authenticator app", body = TwoFactorSetup))
+)]
+pub async fn setup_two_factor(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+) -> ApiResult<Json<TwoFactorSetup>> {
+    let user = db::user::get_user_by_id(&state.pool, auth_user.user_id)
+        .await?
+        .ok_or(ApiError::Unauthorized)?;
+
+    if two_factor_enabled(&state, user.id).await? {
+        return Err(ApiError::Conflict(
+            "Two-factor authentication is already enabled".to_string(),
+        ));
+    }
+
+    let secret = totp::generate_secret()?;
+    db::two_factor::set_pending_secret(&state.pool, user.id, &state.totp_cipher.encrypt(&secret)?)
+        .await?;
+
+    Ok(Json(TwoFactorSetup {
+        secret: totp::base32_encode(&secret),
+        provisioning_uri: totp::provisioning_uri(&secret, &state.config.totp_issuer, &user.email),
+    }))
+}
+
+/// Turns on two-factor authentication with a code from the secret given by
+/// setup, and returns recovery codes.
+#[utoipa::path(
+    post,
+    path = "/auth/2fa/enable",
+    tag = "auth",
+    request_body = EnableTwoFactorRequest,
+    responses((status = 200, description = "Recovery codes, shown only once", body = RecoveryCodes))
+)]
+pub async fn enable_two_factor(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    headers: HeaderMap,
+    Json(req): Json<EnableTwoFactorRequest>,
+) -> ApiResult<Json<RecoveryCodes>> {
+    req.validate()
+        .map_err(|e| ApiError::Validation(e.to_string()))?;
+
+    let (ip_address, user_agent) = extract_client_info_from_headers(&headers);
+
+    let user = db::user::get_user_by_id(&state.pool, auth_user.user_id)
+        .await?
+        .ok_or(ApiError::Unauthorized)?;
+
+    let two_factor = db::two_factor::get_two_factor(&state.pool, user.id).await?;
+    if two_factor.as_ref().is_some_and(|t| t.enabled_at.is_some()) {
+        return Err(ApiError::Conflict(
+            "Two-factor authentication is already enabled".to_string(),
+        ));
+    }
+    let pending = two_factor
+        .and_then(|two_factor| two_factor.pending_secret_encrypted)
+        .ok_or_else(|| {
+            ApiError::BadRequest("Set up two-factor authentication first".to_string())
+        })?;
+
+    let secret = state.totp_cipher.decrypt(&pending)?;
+    let step = totp::verify_code(&secret, &req.code, Utc::now().timestamp(), None)
+        .ok_or_else(|| ApiError::BadRequest("Invalid code".to_string()))?;
+
+    let recovery_codes = totp::generate_recovery_codes()?;
+    let code_hashes: Vec<String> = recovery_codes
+        .iter()
+        .map(|code| totp::hash_recovery_code(code))
+        .collect();
+
+    let mut tx = state.pool.begin().await?;
+
+    if !db::two_factor::enable_two_factor(&mut *tx, user.id, step).await? {
+        return Err(ApiError::Conflict(
+            "Two-factor authentication is already enabled".to_string(),
+        ));
+    }
+    db::two_factor::replace_recovery_codes(&mut *tx, user.id, &code_hashes).await?;
+
+    db::auth_event::create_event(
+        &mut *tx,
+        Some(user.id),
+        AuthEventType::TwoFactorEnabled,
+        &user.email,
+        None,
+        None,
+        Some(&ip_address),
+        Some(&user_agent),
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(Json(RecoveryCodes { recovery_codes }))
+}
+
+/// Turns off two-factor authentication; needs the password and a code or
+/// recovery code.
+#[utoipa::path(
+    post,
+    path = "/auth/2fa/disable",
+    tag = "auth",
+    request_body = DisableTwoFactorRequest,
+    responses((status = 200, description = "Two-factor authentication was turned off", body = serde_json::Value))
+)]
+pub async fn disable_two_factor(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    headers: HeaderMap,
+    Json(req): Json<DisableTwoFactorRequest>,
+) -> ApiResult<Json<serde_json::Value>> {
+    req.validate()
+        .map_err(|e| ApiError::Validation(e.to_string()))?;
+
+    let (ip_address, user_agent) = extract_client_info_from_headers(&headers);
+
+    let user = db::user::get_user_by_id(&state.pool, auth_user.user_id)
+        .await?
+        .ok_or(ApiError::Unauthorized)?;
+
+    if !bcrypt::verify(&req.password, &user.password_hash).unwrap_or(false) {
+        return Err(ApiError::BadRequest("Incorrect password".to_string()));
+    }
+
+    let two_factor = get_enabled_two_factor(&state, user.id)
+        .await?
+        .ok_or_else(|| {
+            ApiError::BadRequest("Two-factor authentication is not enabled".to_string())
+        })?;
+
+    let factor = check_second_factor(&state, &two_factor, &req.code)
+        .await?
+        .ok_or_else(|| ApiError::BadRequest("Invalid code".to_string()))?;
+
+    if let SecondFactor::RecoveryCode = factor {
+        db::auth_event::create_event(
+            &state.pool,
+            Some(user.id),
+            AuthEventType::RecoveryCodeUsed,
+            &user.email,
+            None,
+            None,
+            Some(&ip_address),
+            Some(&user_agent),
+        )
+        .await?;
+    }
+
+    db::two_factor::disable_two_factor(&state.pool, user.id).await?;
+
+    db::auth_event::create_event(
+        &state.pool,
+        Some(user.id),
+        AuthEventType::TwoFactorDisabled,
+        &user.email,
+        None,
+        None,
+        Some(&ip_address),
+        Some(&user_agent),
+    )
+    .await?;
+
+    Ok(Json(serde_json::json!({ "success": true })))
+}
+
+/// Completes a login that asked for a second factor, exchanging the challenge
+/// token and a code or recovery code for a bearer token.
+#[utoipa::path(
+    post,
+    path = "/auth/2fa/verify",
+    tag = "auth",
+    request_body = VerifyTwoFactorRequest,
+    responses((status = 200, description = "A bearer token and the user", body = LoginResponse)),
+    security(())
+)]
+pub async fn verify_two_factor(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(req): Json<VerifyTwoFactorRequest>,
+) -> ApiResult<Json<LoginResponse>> {
+    req.validate()
+        .map_err(|e| ApiError::Validation(e.to_string()))?;
+
+    let (ip_address, user_agent) = extract_client_info_from_headers(&headers);
+
+    let claims: TwoFactorClaims = state
+        .jwt_keys
+        .decode(&req.challenge_token)
+        .map_err(|_| ApiError::Unauthorized)?;
+    if claims.purpose != CHALLENGE_PURPOSE {
+        return Err(ApiError::Unauthorized);
+    }
+
+    let user = db::user::get_user_by_id(&state.pool, claims.user_id)
+        .await?
+        .ok_or(ApiError::Unauthorized)?;
+
+    // Turned off since the challenge was issued: the password was still right
+    let Some(two_factor) = get_enabled_two_factor(&state, user.id).await? else {
+        return Ok(Json(
+            issue_token(&state, user, &ip_address, &user_agent).await?,
+        ));
+    };
+
+    match check_second_factor(&state, &two_factor, &req.code).await? {
+        None => {
+            db::auth_event::create_event(
+                &state.pool,
+                Some(user.id),
+                AuthEventType::LoginFailed,
+                &user.email,
+                Some(FAILURE_WRONG_TWO_FACTOR_CODE),
+                None,
+                Some(&ip_address),
+                Some(&user_agent),
+            )
+            .await?;
+
+            return Err(ApiError::Unauthorized);
+        }
+        Some(SecondFactor::RecoveryCode) => {
+            db::auth_event::create_event(
+                &state.pool,
+                Some(user.id),
+                AuthEventType::RecoveryCodeUsed,
+                &user.email,
+                None,
+                None,
+                Some(&ip_address),
+                Some(&user_agent),
+            )
+            .await?;
+        }
+        Some(SecondFactor::Code) => {}
+    }
+
+    Ok(Json(
+        issue_token(&state, user, &ip_address, &user_agent).await?,
+    ))
+}
@@ -0,0 +1,73 @@
+//! Encrypts the files stored before `STORAGE_ENCRYPTION_KEY` was set, and
+//! rewraps data keys still wrapped with `STORAGE_ENCRYPTION_PREVIOUS_KEY`.
+//! Safe to interrupt and run again; best run while the server is stopped.
+
+use anyhow::{Context, Result};
+use sqlx::postgres::PgPoolOptions;
+use tracing::info;
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+
+use signvault::services::config::Config;
+use signvault::services::encryption::{self, EncryptedStorage};
+use signvault::services::encryption_migration::{self, Phase};
+use signvault::services::quota;
+use signvault::services::storage::{self, Storage};
+
+/// Progress is reported every this many items, and at the end of each phase.
+const REPORT_EVERY: usize = 100;
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    dotenvy::dotenv().ok();
+
+    tracing_subscriber::registry()
+        .with(
+            tracing_subscriber::EnvFilter::try_from_default_env().unwrap_or_else(|_| "info".into()),
+        )
+        .with(tracing_subscriber::fmt::layer())
+        .init();
+
+    let config = Config::from_env()?;
+    let keys = encryption::key_provider(&config)?
+        .context("STORAGE_ENCRYPTION_KEY must be set to encrypt storage")?;
+
+    let pool = PgPoolOptions::new()
+        .max_connections(2)
+        .connect(&config.database_url)
+        .await?;
+    sqlx::migrate!("./migrations").run(&pool).await?;
+
+    let storage = EncryptedStorage::new(storage::backend_from_config(&config)?, keys);
+    info!("Encrypting files in {}", storage.location());
+
+    // Sizes still to be read from storage must be read from the plaintext
+    quota::backfill_file_sizes(&pool, &storage).await?;
+
+    let summary = encryption_migration::encrypt_existing_files(
+        &pool,
+        &storage,
+        |phase: Phase, done, total| {
+            if done % REPORT_EVERY == 0 || done == total {
+                info!("{}/{} {}", done, total, phase.label());
+            }
+        },
+    )
+    .await?;
+
+    info!(
+        "Done: {} original file(s), {} sealed copies and {} signature image(s) encrypted, {} data key(s) rewrapped",
+        summary.originals,
+        summary.sealed_files,
+        summary.signature_images,
+        summary.data_keys_rewrapped
+    );
+
+    if summary.failed > 0 {
+        anyhow::bail!(
+            "{} file(s) could not be encrypted; see the warnings above and run again",
+            summary.failed
+        );
+    }
+
+    Ok(())
+}
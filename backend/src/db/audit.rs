@@ -1,13 +1,17 @@
 use anyhow::Result;
-use sqlx::PgPool;
+use chrono::{DateTime, Utc};
+use sqlx::{PgConnection, PgExecutor, PgPool};
 use uuid::Uuid;
 
 use crate::models::audit::{AuditAction, AuditLog};
 
+/// `created_at` is part of the entry's hash, so it is given rather than left
+/// to the database.
 #[allow(clippy::too_many_arguments)]
 pub async fn create_audit_log(
-    pool: &PgPool,
+    executor: impl PgExecutor<'_>,
     document_id: Uuid,
+    sequence: i64,
     signer_id: Option<Uuid>,
     user_id: Option<Uuid>,
     action: AuditAction,
@@ -16,15 +20,17 @@ pub async fn create_audit_log(
     details: Option<serde_json::Value>,
     entry_hash: &str,
     previous_hash: Option<&str>,
+    created_at: DateTime<Utc>,
 ) -> Result<AuditLog> {
     let log = sqlx::query_as::<_, AuditLog>(
         r#"
-        INSERT INTO audit_logs (document_id, signer_id, user_id, action, ip_address, user_agent, details, entry_hash, previous_hash)
-        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
-        RETURNING id, document_id, signer_id, user_id, action, ip_address, user_agent, details, entry_hash, previous_hash, created_at
+        INSERT INTO audit_logs (document_id, sequence, signer_id, user_id, action, ip_address, user_agent, details, entry_hash, previous_hash, created_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+        RETURNING id, document_id, sequence, signer_id, user_id, action, ip_address, user_agent, details, entry_hash, previous_hash, created_at, anonymized_at
         "#,
     )
     .bind(document_id)
+    .bind(sequence)
     .bind(signer_id)
     .bind(user_id)
     .bind(action)
@@ -33,7 +39,8 @@ pub async fn create_audit_log(
     .bind(details)
     .bind(entry_hash)
     .bind(previous_hash)
-    .fetch_one(pool)
+    .bind(created_at)
+    .fetch_one(executor)
     .await?;
 
     Ok(log)
@@ -42,10 +49,10 @@ pub async fn create_audit_log(
 pub async fn get_audit_logs_by_document(pool: &PgPool, document_id: Uuid) -> Result<Vec<AuditLog>> {
     let logs = sqlx::query_as::<_, AuditLog>(
         r#"
-        SELECT id, document_id, signer_id, user_id, action, ip_address, user_agent, details, entry_hash, previous_hash, created_at
+        SELECT id, document_id, sequence, signer_id, user_id, action, ip_address, user_agent, details, entry_hash, previous_hash, created_at, anonymized_at
         FROM audit_logs
         WHERE document_id = $1
-        ORDER BY created_at ASC
+        ORDER BY sequence ASC
         "#,
     )
     .bind(document_id)
@@ -55,42 +62,58 @@ pub async fn get_audit_logs_by_document(pool: &PgPool, document_id: Uuid) -> Res
     Ok(logs)
 }
 
-pub async fn get_latest_audit_log(pool: &PgPool, document_id: Uuid) -> Result<Option<AuditLog>> {
+/// Locks the document row until the end of the transaction, so entries
+/// written concurrently are numbered and chained one after the other.
+pub async fn lock_audit_chain(conn: &mut PgConnection, document_id: Uuid) -> Result<()> {
+    sqlx::query("SELECT 1 FROM documents WHERE id = $1 FOR NO KEY UPDATE")
+        .bind(document_id)
+        .execute(conn)
+        .await?;
+
+    Ok(())
+}
+
+pub async fn get_latest_audit_log(
+    executor: impl PgExecutor<'_>,
+    document_id: Uuid,
+) -> Result<Option<AuditLog>> {
     let log = sqlx::query_as::<_, AuditLog>(
         r#"
-        SELECT id, document_id, signer_id, user_id, action, ip_address, user_agent, details, entry_hash, previous_hash, created_at
+        SELECT id, document_id, sequence, signer_id, user_id, action, ip_address, user_agent, details, entry_hash, previous_hash, created_at, anonymized_at
         FROM audit_logs
         WHERE document_id = $1
-        ORDER BY created_at DESC
+        ORDER BY sequence DESC
         LIMIT 1
         "#,
     )
     .bind(document_id)
-    .fetch_optional(pool)
+    .fetch_optional(executor)
     .await?;
 
     Ok(log)
 }
 
-pub async fn verify_audit_chain(pool: &PgPool, document_id: Uuid) -> Result<bool> {
-    let logs = get_audit_logs_by_document(pool, document_id).await?;
-
-    if logs.is_empty() {
-        return Ok(true);
-    }
-
-    for (i, log) in logs.iter().enumerate() {
-        if i == 0 {
-            if log.previous_hash.is_some() {
-                return Ok(false);
-            }
-        } else {
-            let prev_hash = &logs[i - 1].entry_hash;
-            if log.previous_hash.as_ref() != Some(prev_hash) {
-                return Ok(false);
-            }
-        }
-    }
+/// Replaces personal data in an entry without touching its hashes.
+pub async fn anonymize_audit_log(
+    pool: &PgPool,
+    id: Uuid,
+    ip_address: Option<&str>,
+    user_agent: Option<&str>,
+    details: Option<serde_json::Value>,
+) -> Result<()> {
+    sqlx::query(
+        r#"
+        UPDATE audit_logs
+        SET ip_address = $1, user_agent = $2, details = $3, anonymized_at = NOW()
+        WHERE id = $4
+        "#,
+    )
+    .bind(ip_address)
+    .bind(user_agent)
+    .bind(details)
+    .bind(id)
+    .execute(pool)
+    .await?;
 
-    Ok(true)
+    Ok(())
 }
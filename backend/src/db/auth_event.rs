@@ -0,0 +1,71 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use sqlx::{PgExecutor, PgPool};
+use uuid::Uuid;
+
+use crate::models::auth_event::{AuthEvent, AuthEventType};
+
+#[allow(clippy::too_many_arguments)]
+pub async fn create_event(
+    executor: impl PgExecutor<'_>,
+    user_id: Option<Uuid>,
+    event_type: AuthEventType,
+    email: &str,
+    failure_reason: Option<&str>,
+    token_expires_at: Option<DateTime<Utc>>,
+    ip_address: Option<&str>,
+    user_agent: Option<&str>,
+) -> Result<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO auth_events (user_id, event_type, email, failure_reason, token_expires_at, ip_address, user_agent)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        "#,
+    )
+    .bind(user_id)
+    .bind(event_type)
+    .bind(email)
+    .bind(failure_reason)
+    .bind(token_expires_at)
+    .bind(ip_address)
+    .bind(user_agent)
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+/// Events newest first, optionally only those of one user.
+pub async fn list_events(
+    pool: &PgPool,
+    user_id: Option<Uuid>,
+    limit: i64,
+    offset: i64,
+) -> Result<Vec<AuthEvent>> {
+    let events = sqlx::query_as::<_, AuthEvent>(
+        r#"
+        SELECT id, user_id, event_type, email, failure_reason, token_expires_at, ip_address, user_agent, created_at
+        FROM auth_events
+        WHERE $1::UUID IS NULL OR user_id = $1
+        ORDER BY created_at DESC, id
+        LIMIT $2 OFFSET $3
+        "#,
+    )
+    .bind(user_id)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(events)
+}
+
+/// Returns how many events were deleted.
+pub async fn delete_events_before(pool: &PgPool, before: DateTime<Utc>) -> Result<u64> {
+    let result = sqlx::query("DELETE FROM auth_events WHERE created_at < $1")
+        .bind(before)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected())
+}
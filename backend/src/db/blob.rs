@@ -0,0 +1,104 @@
+use anyhow::Result;
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::models::encryption::{FileEncryption, WrappedDataKey};
+
+/// Records a newly stored file used by one document.
+pub async fn create_blob(
+    pool: &PgPool,
+    storage_key: &str,
+    owner_id: Uuid,
+    file_hash: &str,
+) -> Result<()> {
+    sqlx::query("INSERT INTO blobs (storage_key, owner_id, file_hash) VALUES ($1, $2, $3)")
+        .bind(storage_key)
+        .bind(owner_id)
+        .bind(file_hash)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+/// Takes a reference to a file of the owner's with the given hash that is
+/// stored encrypted or in plaintext as `encrypted` says. Returns its storage
+/// key and how the documents using it have it encrypted, or `None` if they
+/// have no such file.
+pub async fn acquire_blob(
+    pool: &PgPool,
+    owner_id: Uuid,
+    file_hash: &str,
+    encrypted: bool,
+) -> Result<Option<(String, Option<FileEncryption>)>> {
+    let row = sqlx::query_as::<_, (String, Option<String>, Option<Vec<u8>>, Option<Vec<u8>>)>(
+        r#"
+        WITH source AS (
+            SELECT b.storage_key, d.encryption_key_id, d.wrapped_data_key, d.file_nonce
+            FROM blobs b
+            JOIN documents d ON d.file_path = b.storage_key
+            WHERE b.owner_id = $1 AND b.file_hash = $2 AND b.ref_count > 0
+              AND (d.file_nonce IS NOT NULL) = $3
+            ORDER BY b.created_at
+            LIMIT 1
+            FOR UPDATE OF b
+        )
+        UPDATE blobs
+        SET ref_count = ref_count + 1
+        FROM source
+        WHERE blobs.storage_key = source.storage_key
+        RETURNING source.storage_key, source.encryption_key_id, source.wrapped_data_key,
+                  source.file_nonce
+        "#,
+    )
+    .bind(owner_id)
+    .bind(file_hash)
+    .bind(encrypted)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row.map(|(storage_key, key_id, wrapped_key, nonce)| {
+        let encryption = match (key_id, wrapped_key, nonce) {
+            (Some(key_id), Some(wrapped_key), Some(nonce)) => Some(FileEncryption {
+                data_key: WrappedDataKey {
+                    key_id,
+                    wrapped_key,
+                },
+                nonce,
+            }),
+            _ => None,
+        };
+        (storage_key, encryption)
+    }))
+}
+
+/// Locks a file's row and returns how many documents use it, or `None` for
+/// files stored before they were counted.
+pub async fn lock_blob(conn: &mut PgConnection, storage_key: &str) -> Result<Option<i32>> {
+    let ref_count = sqlx::query_scalar::<_, i32>(
+        "SELECT ref_count FROM blobs WHERE storage_key = $1 FOR UPDATE",
+    )
+    .bind(storage_key)
+    .fetch_optional(conn)
+    .await?;
+
+    Ok(ref_count)
+}
+
+pub async fn decrement_blob(conn: &mut PgConnection, storage_key: &str) -> Result<()> {
+    sqlx::query("UPDATE blobs SET ref_count = ref_count - 1 WHERE storage_key = $1")
+        .bind(storage_key)
+        .execute(conn)
+        .await?;
+
+    Ok(())
+}
+
+pub async fn delete_blob(conn: &mut PgConnection, storage_key: &str) -> Result<()> {
+    sqlx::query("DELETE FROM blobs WHERE storage_key = $1")
+        .bind(storage_key)
+        .execute(conn)
+        .await?;
+
+    Ok(())
+}
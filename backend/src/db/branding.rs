@@ -0,0 +1,66 @@
+use anyhow::Result;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::models::branding::UserBranding;
+
+pub async fn get_branding(pool: &PgPool, user_id: Uuid) -> Result<Option<UserBranding>> {
+    let branding = sqlx::query_as::<_, UserBranding>(
+        r#"
+        SELECT user_id, company_name, primary_color, logo_storage_key, logo_content_type,
+               updated_at
+        FROM user_branding
+        WHERE user_id = $1
+        "#,
+    )
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(branding)
+}
+
+/// Replaces a user's branding. `logo` is the storage key and content type of
+/// the logo to keep, if any.
+pub async fn save_branding(
+    pool: &PgPool,
+    user_id: Uuid,
+    company_name: Option<&str>,
+    primary_color: Option<&str>,
+    logo: Option<(&str, &str)>,
+) -> Result<UserBranding> {
+    let branding = sqlx::query_as::<_, UserBranding>(
+        r#"
+        INSERT INTO user_branding
+            (user_id, company_name, primary_color, logo_storage_key, logo_content_type)
+        VALUES ($1, $2, $3, $4, $5)
+        ON CONFLICT (user_id) DO UPDATE SET
+            company_name = EXCLUDED.company_name,
+            primary_color = EXCLUDED.primary_color,
+            logo_storage_key = EXCLUDED.logo_storage_key,
+            logo_content_type = EXCLUDED.logo_content_type,
+            updated_at = NOW()
+        RETURNING user_id, company_name, primary_color, logo_storage_key, logo_content_type,
+                  updated_at
+        "#,
+    )
+    .bind(user_id)
+    .bind(company_name)
+    .bind(primary_color)
+    .bind(logo.map(|(key, _)| key))
+    .bind(logo.map(|(_, content_type)| content_type))
+    .fetch_one(pool)
+    .await?;
+
+    Ok(branding)
+}
+
+pub async fn get_logo_storage_keys(pool: &PgPool) -> Result<Vec<String>> {
+    let keys = sqlx::query_scalar::<_, String>(
+        "SELECT logo_storage_key FROM user_branding WHERE logo_storage_key IS NOT NULL",
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(keys)
+}
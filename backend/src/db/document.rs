@@ -1,46 +1,129 @@
 use anyhow::Result;
-use sqlx::PgPool;
+use chrono::{DateTime, Utc};
+use sqlx::types::Json;
+use sqlx::{PgConnection, PgExecutor, PgPool};
 use uuid::Uuid;
 
 use crate::models::document::{
-    AddFieldRequest, Document, DocumentFieldRow, DocumentStatus, UpdateFieldRequest,
+    AddFieldRequest, Document, DocumentFieldRow, DocumentListItem, DocumentPage, DocumentSearchRow,
+    DocumentStatus, FieldGeometry, ProgressNotification, UpdateFieldRequest,
 };
+use crate::models::encryption::FileEncryption;
+use crate::models::tag::{TagFilter, TagMode};
 
+#[allow(clippy::too_many_arguments)]
 pub async fn create_document(
     pool: &PgPool,
+    id: Uuid,
     owner_id: Uuid,
     title: &str,
     original_filename: &str,
     file_path: &str,
     file_hash: &str,
     self_sign_only: bool,
+    verification_method: Option<&str>,
+    page_sizes: &[DocumentPage],
+    reminder_interval_days: Option<i32>,
+    max_reminders: Option<i32>,
+    signer_download_enabled: bool,
+    file_size: i64,
+    encryption: Option<&FileEncryption>,
 ) -> Result<Document> {
     let doc = sqlx::query_as::<_, Document>(
         r#"
-        INSERT INTO documents (owner_id, title, original_filename, file_path, file_hash, self_sign_only)
-        VALUES ($1, $2, $3, $4, $5, $6)
+        INSERT INTO documents (id, owner_id, title, original_filename, file_path, file_hash, self_sign_only, verification_method, page_count, page_sizes,
+                               reminder_interval_days, max_reminders, signer_download_enabled, file_size,
+                               encryption_key_id, wrapped_data_key, file_nonce)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17)
         RETURNING id, owner_id, title, original_filename, file_path, file_hash, status,
-                  self_sign_only, total_signers, completed_signers, expires_at, completed_at,
+                  self_sign_only, verification_method, total_signers, completed_signers, expires_at, completed_at, deleted_at, legal_hold,
+                  page_count, page_sizes,
+                  sealed_file_path, sealed_file_hash, sealed_at, seal_signer, void_reason, reminder_interval_days, max_reminders, signer_download_enabled, progress_notifications, locale,
+                  encryption_key_id, wrapped_data_key, file_nonce, sealed_file_nonce,
                   created_at, updated_at
         "#,
     )
+    .bind(id)
     .bind(owner_id)
     .bind(title)
     .bind(original_filename)
     .bind(file_path)
     .bind(file_hash)
     .bind(self_sign_only)
+    .bind(verification_method)
+    .bind(page_sizes.len() as i32)
+    .bind(Json(page_sizes))
+    .bind(reminder_interval_days)
+    .bind(max_reminders)
+    .bind(signer_download_enabled)
+    .bind(file_size)
+    .bind(encryption.map(|e| &e.data_key.key_id))
+    .bind(encryption.map(|e| &e.data_key.wrapped_key))
+    .bind(encryption.map(|e| &e.nonce))
     .fetch_one(pool)
     .await?;
 
     Ok(doc)
 }
 
-pub async fn get_document_by_id(pool: &PgPool, id: Uuid) -> Result<Option<Document>> {
+pub async fn get_document_by_id(
+    executor: impl PgExecutor<'_>,
+    id: Uuid,
+) -> Result<Option<Document>> {
+    let doc = sqlx::query_as::<_, Document>(
+        r#"
+        SELECT id, owner_id, title, original_filename, file_path, file_hash, status,
+               self_sign_only, verification_method, total_signers, completed_signers, expires_at, completed_at, deleted_at, legal_hold,
+               page_count, page_sizes,
+               sealed_file_path, sealed_file_hash, sealed_at, seal_signer, void_reason, reminder_interval_days, max_reminders, signer_download_enabled, progress_notifications, locale,
+               encryption_key_id, wrapped_data_key, file_nonce, sealed_file_nonce,
+               created_at, updated_at
+        FROM documents
+        WHERE id = $1 AND deleted_at IS NULL
+        "#,
+    )
+    .bind(id)
+    .fetch_optional(executor)
+    .await?;
+
+    Ok(doc)
+}
+
+/// Like `get_document_by_id`, but locks the row until the transaction ends so
+/// signing actions on the same document run one at a time.
+pub async fn lock_document(conn: &mut PgConnection, id: Uuid) -> Result<Option<Document>> {
+    let doc = sqlx::query_as::<_, Document>(
+        r#"
+        SELECT id, owner_id, title, original_filename, file_path, file_hash, status,
+               self_sign_only, verification_method, total_signers, completed_signers, expires_at, completed_at, deleted_at, legal_hold,
+               page_count, page_sizes,
+               sealed_file_path, sealed_file_hash, sealed_at, seal_signer, void_reason, reminder_interval_days, max_reminders, signer_download_enabled, progress_notifications, locale,
+               encryption_key_id, wrapped_data_key, file_nonce, sealed_file_nonce,
+               created_at, updated_at
+        FROM documents
+        WHERE id = $1 AND deleted_at IS NULL
+        FOR UPDATE
+        "#,
+    )
+    .bind(id)
+    .fetch_optional(conn)
+    .await?;
+
+    Ok(doc)
+}
+
+/// Looks a document up whether or not it is in the trash.
+pub async fn get_document_by_id_including_deleted(
+    pool: &PgPool,
+    id: Uuid,
+) -> Result<Option<Document>> {
     let doc = sqlx::query_as::<_, Document>(
         r#"
         SELECT id, owner_id, title, original_filename, file_path, file_hash, status,
-               self_sign_only, total_signers, completed_signers, expires_at, completed_at,
+               self_sign_only, verification_method, total_signers, completed_signers, expires_at, completed_at, deleted_at, legal_hold,
+               page_count, page_sizes,
+               sealed_file_path, sealed_file_hash, sealed_at, seal_signer, void_reason, reminder_interval_days, max_reminders, signer_download_enabled, progress_notifications, locale,
+               encryption_key_id, wrapped_data_key, file_nonce, sealed_file_nonce,
                created_at, updated_at
         FROM documents
         WHERE id = $1
@@ -53,32 +136,178 @@ pub async fn get_document_by_id(pool: &PgPool, id: Uuid) -> Result<Option<Docume
     Ok(doc)
 }
 
+pub async fn get_all_documents(pool: &PgPool) -> Result<Vec<Document>> {
+    let docs = sqlx::query_as::<_, Document>(
+        r#"
+        SELECT id, owner_id, title, original_filename, file_path, file_hash, status,
+               self_sign_only, verification_method, total_signers, completed_signers, expires_at, completed_at, deleted_at, legal_hold,
+               page_count, page_sizes,
+               sealed_file_path, sealed_file_hash, sealed_at, seal_signer, void_reason, reminder_interval_days, max_reminders, signer_download_enabled, progress_notifications, locale,
+               encryption_key_id, wrapped_data_key, file_nonce, sealed_file_nonce,
+               created_at, updated_at
+        FROM documents
+        ORDER BY created_at ASC
+        "#,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(docs)
+}
+
+/// The owner's documents outside the trash, newest first, optionally only
+/// those with the tags in `tags`.
 pub async fn get_documents_by_owner(
     pool: &PgPool,
     owner_id: Uuid,
+    tags: Option<&TagFilter>,
     limit: i64,
     offset: i64,
-) -> Result<Vec<Document>> {
-    let docs = sqlx::query_as::<_, Document>(
+) -> Result<Vec<DocumentListItem>> {
+    let docs = sqlx::query_as::<_, DocumentListItem>(
         r#"
-        SELECT id, owner_id, title, original_filename, file_path, file_hash, status,
-               self_sign_only, total_signers, completed_signers, expires_at, completed_at,
-               created_at, updated_at
-        FROM documents
-        WHERE owner_id = $1
-        ORDER BY created_at DESC
+        SELECT d.id, d.owner_id, d.title, d.original_filename, d.file_path, d.file_hash, d.status,
+               d.self_sign_only, d.verification_method, d.total_signers, d.completed_signers, d.expires_at, d.completed_at, d.deleted_at, d.legal_hold,
+               d.page_count, d.page_sizes,
+               d.sealed_file_path, d.sealed_file_hash, d.sealed_at, d.seal_signer, d.void_reason, d.reminder_interval_days, d.max_reminders, d.signer_download_enabled, d.progress_notifications, d.locale,
+               d.encryption_key_id, d.wrapped_data_key, d.file_nonce, d.sealed_file_nonce,
+               d.created_at, d.updated_at,
+               waiting_on.name AS waiting_on_name, waiting_on.email AS waiting_on_email,
+               activity.last_activity_at,
+               views.viewed_signers, views.unviewed_signers,
+               ARRAY(
+                   SELECT t.name::TEXT FROM document_tags t
+                   WHERE t.document_id = d.id
+                   ORDER BY LOWER(t.name)
+               ) AS tags
+        FROM documents d
+        LEFT JOIN LATERAL (
+            SELECT s.name, s.email
+            FROM signers s
+            WHERE s.document_id = d.id AND d.status = 'pending'
+              AND s.status IN ('pending', 'sent', 'viewed')
+            ORDER BY s.order_index, s.created_at
+            LIMIT 1
+        ) waiting_on ON TRUE
+        LEFT JOIN LATERAL (
+            SELECT MAX(a.created_at) AS last_activity_at
+            FROM audit_logs a
+            WHERE a.document_id = d.id
+        ) activity ON TRUE
+        LEFT JOIN LATERAL (
+            SELECT COUNT(*) FILTER (WHERE s.viewed_at IS NOT NULL) AS viewed_signers,
+                   COUNT(*) FILTER (WHERE s.viewed_at IS NULL) AS unviewed_signers
+            FROM signers s
+            WHERE s.document_id = d.id AND s.status <> 'removed'
+        ) views ON TRUE
+        WHERE d.owner_id = $1 AND d.deleted_at IS NULL
+          AND ($4::TEXT[] IS NULL OR (
+              SELECT COUNT(*)
+              FROM document_tags t
+              WHERE t.document_id = d.id
+                AND LOWER(t.name) IN (SELECT LOWER(tag) FROM UNNEST($4::TEXT[]) AS tag)
+          ) >= CASE
+              WHEN $5 THEN (SELECT COUNT(DISTINCT LOWER(tag)) FROM UNNEST($4::TEXT[]) AS tag)
+              ELSE 1
+          END)
+        ORDER BY d.created_at DESC
         LIMIT $2 OFFSET $3
         "#,
     )
     .bind(owner_id)
     .bind(limit)
     .bind(offset)
+    .bind(tags.map(|filter| filter.tags.as_slice()))
+    .bind(tags.is_some_and(|filter| filter.mode == TagMode::All))
     .fetch_all(pool)
     .await?;
 
     Ok(docs)
 }
 
+/// The owner's documents outside the trash whose title, file name or signer
+/// name or email matches the ILIKE `pattern`, best match first. `term` is the
+/// search term itself, which ranks matches by trigram word similarity.
+pub async fn search_documents_by_owner(
+    pool: &PgPool,
+    owner_id: Uuid,
+    pattern: &str,
+    term: &str,
+    limit: i64,
+    offset: i64,
+) -> Result<Vec<DocumentSearchRow>> {
+    let docs = sqlx::query_as::<_, DocumentSearchRow>(
+        r#"
+        SELECT d.id, d.owner_id, d.title, d.original_filename, d.file_path, d.file_hash, d.status,
+               d.self_sign_only, d.verification_method, d.total_signers, d.completed_signers, d.expires_at, d.completed_at, d.deleted_at, d.legal_hold,
+               d.page_count, d.page_sizes,
+               d.sealed_file_path, d.sealed_file_hash, d.sealed_at, d.seal_signer, d.void_reason, d.reminder_interval_days, d.max_reminders, d.signer_download_enabled, d.progress_notifications, d.locale,
+               d.encryption_key_id, d.wrapped_data_key, d.file_nonce, d.sealed_file_nonce,
+               d.created_at, d.updated_at,
+               GREATEST(
+                   CASE WHEN d.title ILIKE $2 THEN 1.0 + WORD_SIMILARITY($3, d.title) ELSE 0 END,
+                   CASE WHEN d.original_filename ILIKE $2 THEN 0.6 + WORD_SIMILARITY($3, d.original_filename) ELSE 0 END,
+                   CASE WHEN signer_names.names <> '{}' THEN 0.8 + signer_names.similarity ELSE 0 END,
+                   CASE WHEN signer_emails.emails <> '{}' THEN 0.7 + signer_emails.similarity ELSE 0 END
+               )::REAL AS rank,
+               signer_names.names AS matched_signer_names,
+               signer_emails.emails AS matched_signer_emails
+        FROM documents d
+        CROSS JOIN LATERAL (
+            SELECT COALESCE(ARRAY_AGG(s.name::TEXT ORDER BY s.order_index, s.created_at), '{}') AS names,
+                   COALESCE(MAX(WORD_SIMILARITY($3, s.name)), 0) AS similarity
+            FROM signers s
+            WHERE s.document_id = d.id AND s.name ILIKE $2
+        ) signer_names
+        CROSS JOIN LATERAL (
+            SELECT COALESCE(ARRAY_AGG(s.email::TEXT ORDER BY s.order_index, s.created_at), '{}') AS emails,
+                   COALESCE(MAX(WORD_SIMILARITY($3, s.email)), 0) AS similarity
+            FROM signers s
+            WHERE s.document_id = d.id AND s.email ILIKE $2
+        ) signer_emails
+        WHERE d.owner_id = $1 AND d.deleted_at IS NULL
+          AND (d.title ILIKE $2 OR d.original_filename ILIKE $2
+               OR signer_names.names <> '{}' OR signer_emails.emails <> '{}')
+        ORDER BY rank DESC, d.created_at DESC
+        LIMIT $4 OFFSET $5
+        "#,
+    )
+    .bind(owner_id)
+    .bind(pattern)
+    .bind(term)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(docs)
+}
+
+pub async fn count_search_results_by_owner(
+    pool: &PgPool,
+    owner_id: Uuid,
+    pattern: &str,
+) -> Result<i64> {
+    let count = sqlx::query_scalar::<_, i64>(
+        r#"
+        SELECT COUNT(*)
+        FROM documents d
+        WHERE d.owner_id = $1 AND d.deleted_at IS NULL
+          AND (d.title ILIKE $2 OR d.original_filename ILIKE $2
+               OR EXISTS (
+                   SELECT 1 FROM signers s
+                   WHERE s.document_id = d.id AND (s.name ILIKE $2 OR s.email ILIKE $2)
+               ))
+        "#,
+    )
+    .bind(owner_id)
+    .bind(pattern)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(count)
+}
+
 pub async fn update_document_status(
     pool: &PgPool,
     id: Uuid,
@@ -88,8 +317,12 @@ pub async fn update_document_status(
         r#"
         UPDATE documents
         SET status = $1
+        WHERE id = $2
         RETURNING id, owner_id, title, original_filename, file_path, file_hash, status,
-                  self_sign_only, total_signers, completed_signers, expires_at, completed_at,
+                  self_sign_only, verification_method, total_signers, completed_signers, expires_at, completed_at, deleted_at, legal_hold,
+                  page_count, page_sizes,
+                  sealed_file_path, sealed_file_hash, sealed_at, seal_signer, void_reason, reminder_interval_days, max_reminders, signer_download_enabled, progress_notifications, locale,
+                  encryption_key_id, wrapped_data_key, file_nonce, sealed_file_nonce,
                   created_at, updated_at
         "#,
     )
@@ -101,62 +334,194 @@ pub async fn update_document_status(
     Ok(doc)
 }
 
-pub async fn update_document_title(pool: &PgPool, id: Uuid, title: &str) -> Result<Document> {
+/// Voids a document that is not completed or voided yet, recording why.
+pub async fn void_document(
+    executor: impl PgExecutor<'_>,
+    id: Uuid,
+    reason: Option<&str>,
+) -> Result<Option<Document>> {
     let doc = sqlx::query_as::<_, Document>(
         r#"
         UPDATE documents
-        SET title = $1
-        WHERE id = $2
+        SET status = 'voided', void_reason = $2
+        WHERE id = $1 AND status NOT IN ('completed', 'voided')
+        RETURNING id, owner_id, title, original_filename, file_path, file_hash, status,
+                  self_sign_only, verification_method, total_signers, completed_signers, expires_at, completed_at, deleted_at, legal_hold,
+                  page_count, page_sizes,
+                  sealed_file_path, sealed_file_hash, sealed_at, seal_signer, void_reason, reminder_interval_days, max_reminders, signer_download_enabled, progress_notifications, locale,
+                  encryption_key_id, wrapped_data_key, file_nonce, sealed_file_nonce,
+                  created_at, updated_at
+        "#,
+    )
+    .bind(id)
+    .bind(reason)
+    .fetch_optional(executor)
+    .await?;
+
+    Ok(doc)
+}
+
+/// Moves the document from `from` to `to`. Returns `None` if it was no longer
+/// in `from`, e.g. because a concurrent request changed it first.
+pub async fn transition_document_status(
+    executor: impl PgExecutor<'_>,
+    id: Uuid,
+    from: DocumentStatus,
+    to: DocumentStatus,
+) -> Result<Option<Document>> {
+    let doc = sqlx::query_as::<_, Document>(
+        r#"
+        UPDATE documents
+        SET status = $1
+        WHERE id = $2 AND status = $3
+        RETURNING id, owner_id, title, original_filename, file_path, file_hash, status,
+                  self_sign_only, verification_method, total_signers, completed_signers, expires_at, completed_at, deleted_at, legal_hold,
+                  page_count, page_sizes,
+                  sealed_file_path, sealed_file_hash, sealed_at, seal_signer, void_reason, reminder_interval_days, max_reminders, signer_download_enabled, progress_notifications, locale,
+                  encryption_key_id, wrapped_data_key, file_nonce, sealed_file_nonce,
+                  created_at, updated_at
+        "#,
+    )
+    .bind(to)
+    .bind(id)
+    .bind(from)
+    .fetch_optional(executor)
+    .await?;
+
+    Ok(doc)
+}
+
+/// The owner's most recent document, not in the trash, whose file has the
+/// given hash.
+pub async fn find_document_by_file_hash(
+    pool: &PgPool,
+    owner_id: Uuid,
+    file_hash: &str,
+) -> Result<Option<Uuid>> {
+    let id = sqlx::query_scalar::<_, Uuid>(
+        r#"
+        SELECT id
+        FROM documents
+        WHERE owner_id = $1 AND file_hash = $2 AND deleted_at IS NULL
+        ORDER BY created_at DESC
+        LIMIT 1
+        "#,
+    )
+    .bind(owner_id)
+    .bind(file_hash)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(id)
+}
+
+/// Returns `None` if `expected_updated_at` is given and no longer matches.
+#[allow(clippy::too_many_arguments)]
+pub async fn update_document(
+    pool: &PgPool,
+    id: Uuid,
+    title: Option<&str>,
+    expires_at: Option<DateTime<Utc>>,
+    reminder_interval_days: Option<i32>,
+    max_reminders: Option<i32>,
+    signer_download_enabled: Option<bool>,
+    progress_notifications: Option<&[ProgressNotification]>,
+    locale: Option<&str>,
+    expected_updated_at: Option<DateTime<Utc>>,
+) -> Result<Option<Document>> {
+    // A reminder setting of 0, or an empty locale, clears it.
+    let doc = sqlx::query_as::<_, Document>(
+        r#"
+        UPDATE documents
+        SET title = COALESCE($1, title), expires_at = COALESCE($2, expires_at),
+            reminder_interval_days = CASE WHEN $5::INT IS NULL THEN reminder_interval_days ELSE NULLIF($5, 0) END,
+            max_reminders = CASE WHEN $6::INT IS NULL THEN max_reminders ELSE NULLIF($6, 0) END,
+            signer_download_enabled = COALESCE($7, signer_download_enabled),
+            progress_notifications = COALESCE($8, progress_notifications),
+            locale = CASE WHEN $9::TEXT IS NULL THEN locale ELSE NULLIF($9, '') END
+        WHERE id = $3 AND ($4::TIMESTAMPTZ IS NULL OR updated_at = $4)
         RETURNING id, owner_id, title, original_filename, file_path, file_hash, status,
-                  self_sign_only, total_signers, completed_signers, expires_at, completed_at,
+                  self_sign_only, verification_method, total_signers, completed_signers, expires_at, completed_at, deleted_at, legal_hold,
+                  page_count, page_sizes,
+                  sealed_file_path, sealed_file_hash, sealed_at, seal_signer, void_reason, reminder_interval_days, max_reminders, signer_download_enabled, progress_notifications, locale,
+                  encryption_key_id, wrapped_data_key, file_nonce, sealed_file_nonce,
                   created_at, updated_at
         "#,
     )
     .bind(title)
+    .bind(expires_at)
     .bind(id)
-    .fetch_one(pool)
+    .bind(expected_updated_at)
+    .bind(reminder_interval_days)
+    .bind(max_reminders)
+    .bind(signer_download_enabled)
+    .bind(progress_notifications.map(Json))
+    .bind(locale)
+    .fetch_optional(pool)
     .await?;
 
     Ok(doc)
 }
 
-pub async fn mark_document_completed(pool: &PgPool, id: Uuid) -> Result<Document> {
+/// Returns `None` if the document was already completed.
+pub async fn mark_document_completed(
+    executor: impl PgExecutor<'_>,
+    id: Uuid,
+) -> Result<Option<Document>> {
     let doc = sqlx::query_as::<_, Document>(
         r#"
         UPDATE documents
         SET status = 'completed', completed_at = NOW()
-        WHERE id = $1
+        WHERE id = $1 AND status <> 'completed'
         RETURNING id, owner_id, title, original_filename, file_path, file_hash, status,
-                  self_sign_only, total_signers, completed_signers, expires_at, completed_at,
+                  self_sign_only, verification_method, total_signers, completed_signers, expires_at, completed_at, deleted_at, legal_hold,
+                  page_count, page_sizes,
+                  sealed_file_path, sealed_file_hash, sealed_at, seal_signer, void_reason, reminder_interval_days, max_reminders, signer_download_enabled, progress_notifications, locale,
+                  encryption_key_id, wrapped_data_key, file_nonce, sealed_file_nonce,
                   created_at, updated_at
         "#,
     )
     .bind(id)
-    .fetch_one(pool)
+    .fetch_optional(executor)
     .await?;
 
     Ok(doc)
 }
 
-pub async fn increment_completed_signers(pool: &PgPool, id: Uuid) -> Result<Document> {
+/// Recounts finished participants (signed or approved) and participants from
+/// the signers table rather than adjusting the stored counters.
+pub async fn recount_signers(executor: impl PgExecutor<'_>, id: Uuid) -> Result<Document> {
     let doc = sqlx::query_as::<_, Document>(
         r#"
         UPDATE documents
-        SET completed_signers = completed_signers + 1
+        SET completed_signers = counts.completed, total_signers = counts.total
+        FROM (
+            SELECT COUNT(*) FILTER (WHERE status IN ('signed', 'approved'))::INT AS completed,
+                   COUNT(*)::INT AS total
+            FROM signers
+            WHERE document_id = $1 AND status <> 'removed'
+        ) AS counts
         WHERE id = $1
         RETURNING id, owner_id, title, original_filename, file_path, file_hash, status,
-                  self_sign_only, total_signers, completed_signers, expires_at, completed_at,
+                  self_sign_only, verification_method, total_signers, completed_signers, expires_at, completed_at, deleted_at, legal_hold,
+                  page_count, page_sizes,
+                  sealed_file_path, sealed_file_hash, sealed_at, seal_signer, void_reason, reminder_interval_days, max_reminders, signer_download_enabled, progress_notifications, locale,
+                  encryption_key_id, wrapped_data_key, file_nonce, sealed_file_nonce,
                   created_at, updated_at
         "#,
     )
     .bind(id)
-    .fetch_one(pool)
+    .fetch_one(executor)
     .await?;
 
     Ok(doc)
 }
 
-pub async fn update_total_signers(pool: &PgPool, id: Uuid, total: i32) -> Result<()> {
+pub async fn update_total_signers(
+    executor: impl PgExecutor<'_>,
+    id: Uuid,
+    total: i32,
+) -> Result<()> {
     sqlx::query(
         r#"
         UPDATE documents
@@ -166,12 +531,241 @@ pub async fn update_total_signers(pool: &PgPool, id: Uuid, total: i32) -> Result
     )
     .bind(total)
     .bind(id)
-    .execute(pool)
+    .execute(executor)
     .await?;
 
     Ok(())
 }
 
+pub async fn get_deleted_document_by_id(pool: &PgPool, id: Uuid) -> Result<Option<Document>> {
+    let doc = sqlx::query_as::<_, Document>(
+        r#"
+        SELECT id, owner_id, title, original_filename, file_path, file_hash, status,
+               self_sign_only, verification_method, total_signers, completed_signers, expires_at, completed_at, deleted_at, legal_hold,
+               page_count, page_sizes,
+               sealed_file_path, sealed_file_hash, sealed_at, seal_signer, void_reason, reminder_interval_days, max_reminders, signer_download_enabled, progress_notifications, locale,
+               encryption_key_id, wrapped_data_key, file_nonce, sealed_file_nonce,
+               created_at, updated_at
+        FROM documents
+        WHERE id = $1 AND deleted_at IS NOT NULL
+        "#,
+    )
+    .bind(id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(doc)
+}
+
+pub async fn get_deleted_documents_by_owner(
+    pool: &PgPool,
+    owner_id: Uuid,
+    limit: i64,
+    offset: i64,
+) -> Result<Vec<Document>> {
+    let docs = sqlx::query_as::<_, Document>(
+        r#"
+        SELECT id, owner_id, title, original_filename, file_path, file_hash, status,
+               self_sign_only, verification_method, total_signers, completed_signers, expires_at, completed_at, deleted_at, legal_hold,
+               page_count, page_sizes,
+               sealed_file_path, sealed_file_hash, sealed_at, seal_signer, void_reason, reminder_interval_days, max_reminders, signer_download_enabled, progress_notifications, locale,
+               encryption_key_id, wrapped_data_key, file_nonce, sealed_file_nonce,
+               created_at, updated_at
+        FROM documents
+        WHERE owner_id = $1 AND deleted_at IS NOT NULL
+        ORDER BY deleted_at DESC
+        LIMIT $2 OFFSET $3
+        "#,
+    )
+    .bind(owner_id)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(docs)
+}
+
+/// Soft-deleted documents past retention. Completed documents and documents
+/// under legal hold are kept.
+pub async fn get_trashed_documents_to_purge(
+    pool: &PgPool,
+    deleted_before: DateTime<Utc>,
+) -> Result<Vec<Document>> {
+    let docs = sqlx::query_as::<_, Document>(
+        r#"
+        SELECT id, owner_id, title, original_filename, file_path, file_hash, status,
+               self_sign_only, verification_method, total_signers, completed_signers, expires_at, completed_at, deleted_at, legal_hold,
+               page_count, page_sizes,
+               sealed_file_path, sealed_file_hash, sealed_at, seal_signer, void_reason, reminder_interval_days, max_reminders, signer_download_enabled, progress_notifications, locale,
+               encryption_key_id, wrapped_data_key, file_nonce, sealed_file_nonce,
+               created_at, updated_at
+        FROM documents
+        WHERE deleted_at < $1 AND status <> 'completed' AND NOT legal_hold
+        "#,
+    )
+    .bind(deleted_before)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(docs)
+}
+
+/// Documents in `status` whose last change, or completion, is older than
+/// `before`, excluding documents under legal hold.
+pub async fn get_documents_past_retention(
+    pool: &PgPool,
+    status: DocumentStatus,
+    before: DateTime<Utc>,
+) -> Result<Vec<Document>> {
+    let docs = sqlx::query_as::<_, Document>(
+        r#"
+        SELECT id, owner_id, title, original_filename, file_path, file_hash, status,
+               self_sign_only, verification_method, total_signers, completed_signers, expires_at, completed_at, deleted_at, legal_hold,
+               page_count, page_sizes,
+               sealed_file_path, sealed_file_hash, sealed_at, seal_signer, void_reason, reminder_interval_days, max_reminders, signer_download_enabled, progress_notifications, locale,
+               encryption_key_id, wrapped_data_key, file_nonce, sealed_file_nonce,
+               created_at, updated_at
+        FROM documents
+        WHERE status = $1 AND NOT legal_hold AND COALESCE(completed_at, updated_at) < $2
+        "#,
+    )
+    .bind(status)
+    .bind(before)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(docs)
+}
+
+/// The owner's pending documents that expire before `before`, soonest first.
+pub async fn get_expiring_documents(
+    pool: &PgPool,
+    owner_id: Uuid,
+    before: DateTime<Utc>,
+) -> Result<Vec<Document>> {
+    let docs = sqlx::query_as::<_, Document>(
+        r#"
+        SELECT id, owner_id, title, original_filename, file_path, file_hash, status,
+               self_sign_only, verification_method, total_signers, completed_signers, expires_at, completed_at, deleted_at, legal_hold,
+               page_count, page_sizes,
+               sealed_file_path, sealed_file_hash, sealed_at, seal_signer, void_reason, reminder_interval_days, max_reminders, signer_download_enabled, progress_notifications, locale,
+               encryption_key_id, wrapped_data_key, file_nonce, sealed_file_nonce,
+               created_at, updated_at
+        FROM documents
+        WHERE owner_id = $1 AND status = 'pending' AND deleted_at IS NULL
+          AND expires_at > NOW() AND expires_at <= $2
+        ORDER BY expires_at
+        "#,
+    )
+    .bind(owner_id)
+    .bind(before)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(docs)
+}
+
+pub async fn set_legal_hold(pool: &PgPool, id: Uuid, legal_hold: bool) -> Result<Document> {
+    let doc = sqlx::query_as::<_, Document>(
+        r#"
+        UPDATE documents
+        SET legal_hold = $1
+        WHERE id = $2
+        RETURNING id, owner_id, title, original_filename, file_path, file_hash, status,
+                  self_sign_only, verification_method, total_signers, completed_signers, expires_at, completed_at, deleted_at, legal_hold,
+                  page_count, page_sizes,
+                  sealed_file_path, sealed_file_hash, sealed_at, seal_signer, void_reason, reminder_interval_days, max_reminders, signer_download_enabled, progress_notifications, locale,
+                  encryption_key_id, wrapped_data_key, file_nonce, sealed_file_nonce,
+                  created_at, updated_at
+        "#,
+    )
+    .bind(legal_hold)
+    .bind(id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(doc)
+}
+
+#[allow(clippy::too_many_arguments)]
+pub async fn set_seal(
+    pool: &PgPool,
+    id: Uuid,
+    sealed_file_path: &str,
+    sealed_file_nonce: Option<&[u8]>,
+    sealed_file_hash: &str,
+    sealed_file_size: i64,
+    seal_signer: &str,
+) -> Result<Document> {
+    let doc = sqlx::query_as::<_, Document>(
+        r#"
+        UPDATE documents
+        SET sealed_file_path = $1, sealed_file_hash = $2, sealed_file_size = $3, sealed_at = NOW(),
+            seal_signer = $4, sealed_file_nonce = $6
+        WHERE id = $5
+        RETURNING id, owner_id, title, original_filename, file_path, file_hash, status,
+                  self_sign_only, verification_method, total_signers, completed_signers, expires_at, completed_at, deleted_at, legal_hold,
+                  page_count, page_sizes,
+                  sealed_file_path, sealed_file_hash, sealed_at, seal_signer, void_reason, reminder_interval_days, max_reminders, signer_download_enabled, progress_notifications, locale,
+                  encryption_key_id, wrapped_data_key, file_nonce, sealed_file_nonce,
+                  created_at, updated_at
+        "#,
+    )
+    .bind(sealed_file_path)
+    .bind(sealed_file_hash)
+    .bind(sealed_file_size)
+    .bind(seal_signer)
+    .bind(id)
+    .bind(sealed_file_nonce)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(doc)
+}
+
+pub async fn soft_delete_document(executor: impl PgExecutor<'_>, id: Uuid) -> Result<Document> {
+    let doc = sqlx::query_as::<_, Document>(
+        r#"
+        UPDATE documents
+        SET deleted_at = NOW()
+        WHERE id = $1
+        RETURNING id, owner_id, title, original_filename, file_path, file_hash, status,
+                  self_sign_only, verification_method, total_signers, completed_signers, expires_at, completed_at, deleted_at, legal_hold,
+                  page_count, page_sizes,
+                  sealed_file_path, sealed_file_hash, sealed_at, seal_signer, void_reason, reminder_interval_days, max_reminders, signer_download_enabled, progress_notifications, locale,
+                  encryption_key_id, wrapped_data_key, file_nonce, sealed_file_nonce,
+                  created_at, updated_at
+        "#,
+    )
+    .bind(id)
+    .fetch_one(executor)
+    .await?;
+
+    Ok(doc)
+}
+
+pub async fn restore_document(pool: &PgPool, id: Uuid) -> Result<Document> {
+    let doc = sqlx::query_as::<_, Document>(
+        r#"
+        UPDATE documents
+        SET deleted_at = NULL
+        WHERE id = $1
+        RETURNING id, owner_id, title, original_filename, file_path, file_hash, status,
+                  self_sign_only, verification_method, total_signers, completed_signers, expires_at, completed_at, deleted_at, legal_hold,
+                  page_count, page_sizes,
+                  sealed_file_path, sealed_file_hash, sealed_at, seal_signer, void_reason, reminder_interval_days, max_reminders, signer_download_enabled, progress_notifications, locale,
+                  encryption_key_id, wrapped_data_key, file_nonce, sealed_file_nonce,
+                  created_at, updated_at
+        "#,
+    )
+    .bind(id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(doc)
+}
+
 pub async fn delete_document(pool: &PgPool, id: Uuid) -> Result<()> {
     sqlx::query("DELETE FROM documents WHERE id = $1")
         .bind(id)
@@ -185,75 +779,192 @@ pub async fn add_field(
     pool: &PgPool,
     document_id: Uuid,
     req: &AddFieldRequest,
+    geometry: &FieldGeometry,
 ) -> Result<DocumentFieldRow> {
     let field = sqlx::query_as::<_, DocumentFieldRow>(
         r#"
         INSERT INTO document_fields (document_id, field_type, page, x, y, width, height,
-                                     signer_id, value, font_size, font_family, date_format)
-        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
+                                     signer_id, value, font_size, font_family, date_format,
+                                     norm_x, norm_y, norm_width, norm_height, required,
+                                     condition_field_id, condition_value, label, min_value,
+                                     max_value, decimal_places, read_only, group_key)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17,
+                $18, $19, $20, $21, $22, $23, $24, $25)
         RETURNING id, document_id, field_type, page, x, y, width, height, signer_id,
-                  value, font_size, font_family, date_format, created_at, updated_at
+                  value, font_size, font_family, date_format, norm_x, norm_y, norm_width,
+                  norm_height, required, condition_field_id, condition_value, label, min_value,
+                  max_value, decimal_places, read_only, group_key, created_at, updated_at
         "#,
     )
     .bind(document_id)
     .bind(req.field_type)
     .bind(req.page)
-    .bind(req.x)
-    .bind(req.y)
-    .bind(req.width)
-    .bind(req.height)
+    .bind(geometry.points.x)
+    .bind(geometry.points.y)
+    .bind(geometry.points.width)
+    .bind(geometry.points.height)
     .bind(req.signer_id)
     .bind(&req.value)
     .bind(req.font_size.unwrap_or(12))
     .bind(req.font_family.as_deref().unwrap_or("Arial"))
     .bind(req.date_format.as_deref().unwrap_or("YYYY-MM-DD"))
+    .bind(geometry.normalized.x)
+    .bind(geometry.normalized.y)
+    .bind(geometry.normalized.width)
+    .bind(geometry.normalized.height)
+    .bind(req.required)
+    .bind(req.condition_field_id)
+    .bind(&req.condition_value)
+    .bind(&req.label)
+    .bind(req.min_value)
+    .bind(req.max_value)
+    .bind(req.decimal_places)
+    .bind(req.read_only)
+    .bind(&req.group_key)
     .fetch_one(pool)
     .await?;
 
     Ok(field)
 }
 
-pub async fn get_fields_by_document(
+/// Adds a field imported from the AcroForm field `acroform_name`, or returns
+/// `None` if that form field was already imported into the document.
+pub async fn add_imported_field(
     pool: &PgPool,
     document_id: Uuid,
+    acroform_name: &str,
+    req: &AddFieldRequest,
+    geometry: &FieldGeometry,
+) -> Result<Option<DocumentFieldRow>> {
+    let field = sqlx::query_as::<_, DocumentFieldRow>(
+        r#"
+        INSERT INTO document_fields (document_id, field_type, page, x, y, width, height,
+                                     signer_id, value, font_size, font_family, date_format,
+                                     norm_x, norm_y, norm_width, norm_height, acroform_name,
+                                     label)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17,
+                $18)
+        ON CONFLICT (document_id, acroform_name) WHERE acroform_name IS NOT NULL DO NOTHING
+        RETURNING id, document_id, field_type, page, x, y, width, height, signer_id,
+                  value, font_size, font_family, date_format, norm_x, norm_y, norm_width,
+                  norm_height, required, condition_field_id, condition_value, label, min_value,
+                  max_value, decimal_places, read_only, group_key, created_at, updated_at
+        "#,
+    )
+    .bind(document_id)
+    .bind(req.field_type)
+    .bind(req.page)
+    .bind(geometry.points.x)
+    .bind(geometry.points.y)
+    .bind(geometry.points.width)
+    .bind(geometry.points.height)
+    .bind(req.signer_id)
+    .bind(&req.value)
+    .bind(req.font_size.unwrap_or(12))
+    .bind(req.font_family.as_deref().unwrap_or("Arial"))
+    .bind(req.date_format.as_deref().unwrap_or("YYYY-MM-DD"))
+    .bind(geometry.normalized.x)
+    .bind(geometry.normalized.y)
+    .bind(geometry.normalized.width)
+    .bind(geometry.normalized.height)
+    .bind(acroform_name)
+    .bind(&req.label)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(field)
+}
+
+/// Caches page metadata for a document uploaded before it was recorded, and
+/// computes the normalized coordinates of its fields, which need the page
+/// sizes.
+pub async fn set_page_metadata(pool: &PgPool, id: Uuid, page_sizes: &[DocumentPage]) -> Result<()> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query("UPDATE documents SET page_count = $1, page_sizes = $2 WHERE id = $3")
+        .bind(page_sizes.len() as i32)
+        .bind(Json(page_sizes))
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query(
+        r#"
+        UPDATE document_fields f
+        SET norm_x = f.x / (p.size->>'width')::DOUBLE PRECISION,
+            norm_y = f.y / (p.size->>'height')::DOUBLE PRECISION,
+            norm_width = f.width / (p.size->>'width')::DOUBLE PRECISION,
+            norm_height = f.height / (p.size->>'height')::DOUBLE PRECISION
+        FROM documents d
+        CROSS JOIN LATERAL jsonb_array_elements(d.page_sizes) AS p(size)
+        WHERE d.id = $1
+          AND f.document_id = d.id
+          AND f.norm_x IS NULL
+          AND (p.size->>'number')::INTEGER = f.page
+          AND (p.size->>'width')::DOUBLE PRECISION > 0
+          AND (p.size->>'height')::DOUBLE PRECISION > 0
+        "#,
+    )
+    .bind(id)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(())
+}
+
+pub async fn get_fields_by_document(
+    executor: impl PgExecutor<'_>,
+    document_id: Uuid,
 ) -> Result<Vec<DocumentFieldRow>> {
     let fields = sqlx::query_as::<_, DocumentFieldRow>(
         r#"
         SELECT id, document_id, field_type, page, x, y, width, height, signer_id,
-               value, font_size, font_family, date_format, created_at, updated_at
+               value, font_size, font_family, date_format, norm_x, norm_y, norm_width,
+               norm_height, required, condition_field_id, condition_value, label, min_value,
+               max_value, decimal_places, read_only, group_key, created_at, updated_at
         FROM document_fields
         WHERE document_id = $1
         ORDER BY page, y, x
         "#,
     )
     .bind(document_id)
-    .fetch_all(pool)
+    .fetch_all(executor)
     .await?;
 
     Ok(fields)
 }
 
-pub async fn get_field_by_id(pool: &PgPool, id: Uuid) -> Result<Option<DocumentFieldRow>> {
+pub async fn get_field_by_id(
+    executor: impl PgExecutor<'_>,
+    id: Uuid,
+) -> Result<Option<DocumentFieldRow>> {
     let field = sqlx::query_as::<_, DocumentFieldRow>(
         r#"
         SELECT id, document_id, field_type, page, x, y, width, height, signer_id,
-               value, font_size, font_family, date_format, created_at, updated_at
+               value, font_size, font_family, date_format, norm_x, norm_y, norm_width,
+               norm_height, required, condition_field_id, condition_value, label, min_value,
+               max_value, decimal_places, read_only, group_key, created_at, updated_at
         FROM document_fields
         WHERE id = $1
         "#,
     )
     .bind(id)
-    .fetch_optional(pool)
+    .fetch_optional(executor)
     .await?;
 
     Ok(field)
 }
 
+/// Returns `None` if `expected_updated_at` is given and no longer matches.
 pub async fn update_field(
     pool: &PgPool,
     id: Uuid,
     req: &UpdateFieldRequest,
-) -> Result<DocumentFieldRow> {
+    geometry: Option<&FieldGeometry>,
+    expected_updated_at: Option<DateTime<Utc>>,
+) -> Result<Option<DocumentFieldRow>> {
     let field = sqlx::query_as::<_, DocumentFieldRow>(
         r#"
         UPDATE document_fields
@@ -261,31 +972,67 @@ pub async fn update_field(
             y = COALESCE($2, y),
             width = COALESCE($3, width),
             height = COALESCE($4, height),
-            value = COALESCE($5, value),
-            font_size = COALESCE($6, font_size),
-            font_family = COALESCE($7, font_family),
-            date_format = COALESCE($8, date_format)
-        WHERE id = $9
+            norm_x = COALESCE($5, norm_x),
+            norm_y = COALESCE($6, norm_y),
+            norm_width = COALESCE($7, norm_width),
+            norm_height = COALESCE($8, norm_height),
+            value = COALESCE($9, value),
+            font_size = COALESCE($10, font_size),
+            font_family = COALESCE($11, font_family),
+            date_format = COALESCE($12, date_format),
+            required = COALESCE($13, required),
+            condition_field_id = CASE WHEN $15::TEXT IS NULL THEN condition_field_id
+                                      WHEN $15 = '' THEN NULL
+                                      ELSE COALESCE($14::UUID, condition_field_id) END,
+            condition_value = CASE WHEN $15::TEXT IS NULL THEN condition_value
+                                   ELSE NULLIF($15, '') END,
+            label = COALESCE($16, label),
+            min_value = COALESCE($17, min_value),
+            max_value = COALESCE($18, max_value),
+            decimal_places = COALESCE($19, decimal_places),
+            read_only = COALESCE($20, read_only),
+            group_key = COALESCE($21, group_key)
+        WHERE id = $22 AND ($23::TIMESTAMPTZ IS NULL OR updated_at = $23)
         RETURNING id, document_id, field_type, page, x, y, width, height, signer_id,
-                  value, font_size, font_family, date_format, created_at, updated_at
+                  value, font_size, font_family, date_format, norm_x, norm_y, norm_width,
+                  norm_height, required, condition_field_id, condition_value, label, min_value,
+                  max_value, decimal_places, read_only, group_key, created_at, updated_at
         "#,
     )
-    .bind(req.x)
-    .bind(req.y)
-    .bind(req.width)
-    .bind(req.height)
+    .bind(geometry.map(|g| g.points.x))
+    .bind(geometry.map(|g| g.points.y))
+    .bind(geometry.map(|g| g.points.width))
+    .bind(geometry.map(|g| g.points.height))
+    .bind(geometry.map(|g| g.normalized.x))
+    .bind(geometry.map(|g| g.normalized.y))
+    .bind(geometry.map(|g| g.normalized.width))
+    .bind(geometry.map(|g| g.normalized.height))
     .bind(&req.value)
     .bind(req.font_size)
     .bind(&req.font_family)
     .bind(&req.date_format)
+    .bind(req.required)
+    .bind(req.condition_field_id)
+    .bind(&req.condition_value)
+    .bind(&req.label)
+    .bind(req.min_value)
+    .bind(req.max_value)
+    .bind(req.decimal_places)
+    .bind(req.read_only)
+    .bind(&req.group_key)
     .bind(id)
-    .fetch_one(pool)
+    .bind(expected_updated_at)
+    .fetch_optional(pool)
     .await?;
 
     Ok(field)
 }
 
-pub async fn update_field_value(pool: &PgPool, id: Uuid, value: &str) -> Result<()> {
+pub async fn update_field_value(
+    executor: impl PgExecutor<'_>,
+    id: Uuid,
+    value: &str,
+) -> Result<()> {
     sqlx::query(
         r#"
         UPDATE document_fields
@@ -295,26 +1042,148 @@ pub async fn update_field_value(pool: &PgPool, id: Uuid, value: &str) -> Result<
     )
     .bind(value)
     .bind(id)
-    .execute(pool)
+    .execute(executor)
     .await?;
 
     Ok(())
 }
 
+/// Assigns the fields of the signer `from` to the signer `to`, returning how
+/// many were.
+pub async fn reassign_fields(executor: impl PgExecutor<'_>, from: Uuid, to: Uuid) -> Result<u64> {
+    let result = sqlx::query(
+        r#"
+        UPDATE document_fields
+        SET signer_id = $2, updated_at = NOW()
+        WHERE signer_id = $1
+        "#,
+    )
+    .bind(from)
+    .bind(to)
+    .execute(executor)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Deletes a field, removing the conditions of the fields shown depending on
+/// it.
 pub async fn delete_field(pool: &PgPool, id: Uuid) -> Result<()> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query(
+        r#"
+        UPDATE document_fields
+        SET condition_field_id = NULL, condition_value = NULL
+        WHERE condition_field_id = $1
+        "#,
+    )
+    .bind(id)
+    .execute(&mut *tx)
+    .await?;
+
     sqlx::query("DELETE FROM document_fields WHERE id = $1")
         .bind(id)
-        .execute(pool)
+        .execute(&mut *tx)
         .await?;
 
+    tx.commit().await?;
+
     Ok(())
 }
 
-pub async fn count_documents_by_owner(pool: &PgPool, owner_id: Uuid) -> Result<i64> {
-    let count: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM documents WHERE owner_id = $1")
-        .bind(owner_id)
-        .fetch_one(pool)
-        .await?;
+pub async fn count_documents_by_owner(
+    pool: &PgPool,
+    owner_id: Uuid,
+    tags: Option<&TagFilter>,
+) -> Result<i64> {
+    let count: (i64,) = sqlx::query_as(
+        r#"
+        SELECT COUNT(*)
+        FROM documents d
+        WHERE d.owner_id = $1 AND d.deleted_at IS NULL
+          AND ($2::TEXT[] IS NULL OR (
+              SELECT COUNT(*)
+              FROM document_tags t
+              WHERE t.document_id = d.id
+                AND LOWER(t.name) IN (SELECT LOWER(tag) FROM UNNEST($2::TEXT[]) AS tag)
+          ) >= CASE
+              WHEN $3 THEN (SELECT COUNT(DISTINCT LOWER(tag)) FROM UNNEST($2::TEXT[]) AS tag)
+              ELSE 1
+          END)
+        "#,
+    )
+    .bind(owner_id)
+    .bind(tags.map(|filter| filter.tags.as_slice()))
+    .bind(tags.is_some_and(|filter| filter.mode == TagMode::All))
+    .fetch_one(pool)
+    .await?;
 
     Ok(count.0)
 }
+
+pub async fn count_deleted_documents_by_owner(pool: &PgPool, owner_id: Uuid) -> Result<i64> {
+    let count: (i64,) = sqlx::query_as(
+        "SELECT COUNT(*) FROM documents WHERE owner_id = $1 AND deleted_at IS NOT NULL",
+    )
+    .bind(owner_id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(count.0)
+}
+
+/// Bytes stored for a document: its original and sealed files.
+pub async fn get_stored_size(pool: &PgPool, id: Uuid) -> Result<i64> {
+    let size: (i64,) = sqlx::query_as(
+        "SELECT COALESCE(file_size, 0) + COALESCE(sealed_file_size, 0) FROM documents WHERE id = $1",
+    )
+    .bind(id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(size.0)
+}
+
+/// Documents whose file sizes are not recorded yet, with the storage keys of
+/// their original and sealed files.
+pub async fn get_documents_without_sizes(
+    pool: &PgPool,
+    limit: i64,
+) -> Result<Vec<(Uuid, String, Option<String>)>> {
+    let docs = sqlx::query_as::<_, (Uuid, String, Option<String>)>(
+        r#"
+        SELECT id, file_path, sealed_file_path
+        FROM documents
+        WHERE file_size IS NULL OR (sealed_file_path IS NOT NULL AND sealed_file_size IS NULL)
+        LIMIT $1
+        "#,
+    )
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(docs)
+}
+
+pub async fn set_file_sizes(
+    pool: &PgPool,
+    id: Uuid,
+    file_size: i64,
+    sealed_file_size: Option<i64>,
+) -> Result<()> {
+    sqlx::query(
+        r#"
+        UPDATE documents
+        SET file_size = $1, sealed_file_size = COALESCE($2, sealed_file_size)
+        WHERE id = $3
+        "#,
+    )
+    .bind(file_size)
+    .bind(sealed_file_size)
+    .bind(id)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
@@ -0,0 +1,76 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use sqlx::{PgExecutor, PgPool};
+use uuid::Uuid;
+
+use crate::models::email_change::EmailChange;
+
+/// Creates a pending email change, replacing any the user has not confirmed,
+/// so only the latest link works.
+pub async fn create_email_change(
+    pool: &PgPool,
+    user_id: Uuid,
+    new_email: &str,
+    token_hash: &str,
+    expires_at: DateTime<Utc>,
+) -> Result<EmailChange> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query("DELETE FROM email_changes WHERE user_id = $1 AND confirmed_at IS NULL")
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+
+    let change = sqlx::query_as::<_, EmailChange>(
+        r#"
+        INSERT INTO email_changes (user_id, new_email, token_hash, expires_at)
+        VALUES ($1, $2, $3, $4)
+        RETURNING id, user_id, new_email, token_hash, expires_at, created_at, confirmed_at
+        "#,
+    )
+    .bind(user_id)
+    .bind(new_email)
+    .bind(token_hash)
+    .bind(expires_at)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(change)
+}
+
+pub async fn get_email_change_by_token_hash(
+    pool: &PgPool,
+    token_hash: &str,
+) -> Result<Option<EmailChange>> {
+    let change = sqlx::query_as::<_, EmailChange>(
+        r#"
+        SELECT id, user_id, new_email, token_hash, expires_at, created_at, confirmed_at
+        FROM email_changes
+        WHERE token_hash = $1
+        "#,
+    )
+    .bind(token_hash)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(change)
+}
+
+/// Marks a change confirmed. Returns `false` if it already was, or expired
+/// in the meantime.
+pub async fn confirm_email_change(executor: impl PgExecutor<'_>, id: Uuid) -> Result<bool> {
+    let result = sqlx::query(
+        r#"
+        UPDATE email_changes
+        SET confirmed_at = NOW()
+        WHERE id = $1 AND confirmed_at IS NULL AND expires_at > NOW()
+        "#,
+    )
+    .bind(id)
+    .execute(executor)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
@@ -0,0 +1,321 @@
+use anyhow::Result;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::models::encryption::WrappedDataKey;
+
+/// Documents after `after` whose data key is wrapped with a master key other
+/// than `key_id`, in id order.
+pub async fn get_documents_with_other_keys(
+    pool: &PgPool,
+    key_id: &str,
+    after: Uuid,
+    limit: i64,
+) -> Result<Vec<(Uuid, WrappedDataKey)>> {
+    let rows = sqlx::query_as::<_, (Uuid, String, Vec<u8>)>(
+        r#"
+        SELECT id, encryption_key_id, wrapped_data_key
+        FROM documents
+        WHERE encryption_key_id <> $1 AND wrapped_data_key IS NOT NULL AND id > $2
+        ORDER BY id
+        LIMIT $3
+        "#,
+    )
+    .bind(key_id)
+    .bind(after)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|(id, key_id, wrapped_key)| {
+            (
+                id,
+                WrappedDataKey {
+                    key_id,
+                    wrapped_key,
+                },
+            )
+        })
+        .collect())
+}
+
+/// Replaces a document's wrapped data key with the same key wrapped
+/// differently.
+pub async fn set_data_key(pool: &PgPool, id: Uuid, data_key: &WrappedDataKey) -> Result<()> {
+    sqlx::query("UPDATE documents SET encryption_key_id = $2, wrapped_data_key = $3 WHERE id = $1")
+        .bind(id)
+        .bind(&data_key.key_id)
+        .bind(&data_key.wrapped_key)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+pub async fn count_documents_with_other_keys(pool: &PgPool, key_id: &str) -> Result<i64> {
+    let count = sqlx::query_scalar::<_, i64>(
+        "SELECT COUNT(*) FROM documents WHERE encryption_key_id <> $1 AND wrapped_data_key IS NOT NULL",
+    )
+    .bind(key_id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(count)
+}
+
+/// Storage keys of original files after `after` stored in plaintext, in key
+/// order.
+pub async fn get_plaintext_originals(
+    pool: &PgPool,
+    after: &str,
+    limit: i64,
+) -> Result<Vec<String>> {
+    let keys = sqlx::query_scalar::<_, String>(
+        r#"
+        SELECT DISTINCT file_path
+        FROM documents
+        WHERE file_nonce IS NULL AND file_path > $1
+        ORDER BY file_path
+        LIMIT $2
+        "#,
+    )
+    .bind(after)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(keys)
+}
+
+pub async fn count_plaintext_originals(pool: &PgPool) -> Result<i64> {
+    let count = sqlx::query_scalar::<_, i64>(
+        "SELECT COUNT(DISTINCT file_path) FROM documents WHERE file_nonce IS NULL",
+    )
+    .fetch_one(pool)
+    .await?;
+
+    Ok(count)
+}
+
+/// Gives every document using the file at `file_path` a data key: the one a
+/// document using it already has, or `data_key`. Returns the key they share.
+pub async fn assign_data_key(
+    pool: &PgPool,
+    file_path: &str,
+    data_key: &WrappedDataKey,
+) -> Result<WrappedDataKey> {
+    let mut tx = pool.begin().await?;
+
+    let ids = sqlx::query_scalar::<_, Uuid>(
+        "SELECT id FROM documents WHERE file_path = $1 ORDER BY created_at FOR UPDATE",
+    )
+    .bind(file_path)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let existing = sqlx::query_as::<_, (String, Vec<u8>)>(
+        r#"
+        SELECT encryption_key_id, wrapped_data_key
+        FROM documents
+        WHERE id = ANY($1) AND wrapped_data_key IS NOT NULL
+        ORDER BY created_at
+        LIMIT 1
+        "#,
+    )
+    .bind(&ids)
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    let data_key = match existing {
+        Some((key_id, wrapped_key)) => WrappedDataKey {
+            key_id,
+            wrapped_key,
+        },
+        None => data_key.clone(),
+    };
+
+    sqlx::query(
+        r#"
+        UPDATE documents
+        SET encryption_key_id = $2, wrapped_data_key = $3
+        WHERE id = ANY($1) AND wrapped_data_key IS NULL
+        "#,
+    )
+    .bind(&ids)
+    .bind(&data_key.key_id)
+    .bind(&data_key.wrapped_key)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(data_key)
+}
+
+/// Sealed copies after `after` stored in plaintext for documents that have a
+/// data key, in document id order, with their storage key.
+pub async fn get_plaintext_sealed_files(
+    pool: &PgPool,
+    after: Uuid,
+    limit: i64,
+) -> Result<Vec<(Uuid, String, WrappedDataKey)>> {
+    let rows = sqlx::query_as::<_, (Uuid, String, String, Vec<u8>)>(
+        r#"
+        SELECT id, sealed_file_path, encryption_key_id, wrapped_data_key
+        FROM documents
+        WHERE sealed_file_path IS NOT NULL AND sealed_file_nonce IS NULL
+          AND wrapped_data_key IS NOT NULL AND id > $1
+        ORDER BY id
+        LIMIT $2
+        "#,
+    )
+    .bind(after)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|(id, key, key_id, wrapped_key)| {
+            (
+                id,
+                key,
+                WrappedDataKey {
+                    key_id,
+                    wrapped_key,
+                },
+            )
+        })
+        .collect())
+}
+
+pub async fn count_plaintext_sealed_files(pool: &PgPool) -> Result<i64> {
+    let count = sqlx::query_scalar::<_, i64>(
+        r#"
+        SELECT COUNT(*)
+        FROM documents
+        WHERE sealed_file_path IS NOT NULL AND sealed_file_nonce IS NULL
+          AND wrapped_data_key IS NOT NULL
+        "#,
+    )
+    .fetch_one(pool)
+    .await?;
+
+    Ok(count)
+}
+
+/// Signature images after `after` stored in plaintext whose document has a
+/// data key, in signature id order, with their storage key.
+pub async fn get_plaintext_signature_images(
+    pool: &PgPool,
+    after: Uuid,
+    limit: i64,
+) -> Result<Vec<(Uuid, String, WrappedDataKey)>> {
+    let rows = sqlx::query_as::<_, (Uuid, String, String, Vec<u8>)>(
+        r#"
+        SELECT s.id, s.storage_key, d.encryption_key_id, d.wrapped_data_key
+        FROM signatures s
+        JOIN documents d ON d.id = s.document_id
+        WHERE s.storage_key IS NOT NULL AND s.image_nonce IS NULL
+          AND d.wrapped_data_key IS NOT NULL AND s.id > $1
+        ORDER BY s.id
+        LIMIT $2
+        "#,
+    )
+    .bind(after)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|(id, key, key_id, wrapped_key)| {
+            (
+                id,
+                key,
+                WrappedDataKey {
+                    key_id,
+                    wrapped_key,
+                },
+            )
+        })
+        .collect())
+}
+
+pub async fn count_plaintext_signature_images(pool: &PgPool) -> Result<i64> {
+    let count = sqlx::query_scalar::<_, i64>(
+        r#"
+        SELECT COUNT(*)
+        FROM signatures s
+        JOIN documents d ON d.id = s.document_id
+        WHERE s.storage_key IS NOT NULL AND s.image_nonce IS NULL
+          AND d.wrapped_data_key IS NOT NULL
+        "#,
+    )
+    .fetch_one(pool)
+    .await?;
+
+    Ok(count)
+}
+
+/// Records that the original, sealed copy or signature image stored at
+/// `storage_key` is now encrypted with `nonce`, unless it already was. Returns
+/// whether `nonce` is the one recorded for it.
+pub async fn set_file_nonce(pool: &PgPool, storage_key: &str, nonce: &[u8]) -> Result<bool> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query(
+        r#"
+        UPDATE documents SET file_nonce = $2
+        WHERE file_path = $1 AND file_nonce IS NULL AND wrapped_data_key IS NOT NULL
+        "#,
+    )
+    .bind(storage_key)
+    .bind(nonce)
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query(
+        r#"
+        UPDATE documents SET sealed_file_nonce = $2
+        WHERE sealed_file_path = $1 AND sealed_file_nonce IS NULL AND wrapped_data_key IS NOT NULL
+        "#,
+    )
+    .bind(storage_key)
+    .bind(nonce)
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query(
+        r#"
+        UPDATE signatures SET image_nonce = $2
+        WHERE storage_key = $1 AND image_nonce IS NULL
+          AND EXISTS (
+              SELECT 1 FROM documents d
+              WHERE d.id = signatures.document_id AND d.wrapped_data_key IS NOT NULL
+          )
+        "#,
+    )
+    .bind(storage_key)
+    .bind(nonce)
+    .execute(&mut *tx)
+    .await?;
+
+    let recorded = sqlx::query_scalar::<_, bool>(
+        r#"
+        SELECT EXISTS (SELECT 1 FROM documents WHERE file_path = $1 AND file_nonce = $2)
+            OR EXISTS (SELECT 1 FROM documents WHERE sealed_file_path = $1 AND sealed_file_nonce = $2)
+            OR EXISTS (SELECT 1 FROM signatures WHERE storage_key = $1 AND image_nonce = $2)
+        "#,
+    )
+    .bind(storage_key)
+    .bind(nonce)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(recorded)
+}
@@ -0,0 +1,137 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use sqlx::{PgExecutor, PgPool};
+use uuid::Uuid;
+
+use crate::models::idempotency::{IdempotencyKey, KeyClaim};
+
+/// Claims `key` for the user in a transaction of its own. A caller that gets
+/// the key must then store the response or release the key, under the
+/// `claimed_at` it got.
+///
+/// A claim whose request has not finished within `lease_secs`, e.g. because
+/// the server stopped mid-request, is taken over as if the key were free.
+pub async fn claim_key(
+    pool: &PgPool,
+    user_id: Uuid,
+    key: &str,
+    request_hash: &str,
+    ttl_hours: i64,
+    lease_secs: i64,
+) -> Result<KeyClaim> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query(
+        r#"
+        DELETE FROM idempotency_keys
+        WHERE user_id = $1 AND key = $2
+          AND (expires_at <= NOW()
+               OR (response_status IS NULL
+                   AND created_at <= NOW() - make_interval(secs => $3)))
+        "#,
+    )
+    .bind(user_id)
+    .bind(key)
+    .bind(lease_secs as f64)
+    .execute(&mut *tx)
+    .await?;
+
+    let claimed_at: Option<DateTime<Utc>> = sqlx::query_scalar(
+        r#"
+        INSERT INTO idempotency_keys (user_id, key, request_hash, expires_at)
+        VALUES ($1, $2, $3, NOW() + make_interval(hours => $4))
+        ON CONFLICT (user_id, key) DO NOTHING
+        RETURNING created_at
+        "#,
+    )
+    .bind(user_id)
+    .bind(key)
+    .bind(request_hash)
+    .bind(ttl_hours as i32)
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    let claim = match claimed_at {
+        Some(claimed_at) => KeyClaim::Claimed { claimed_at },
+        None => KeyClaim::Taken(
+            sqlx::query_as::<_, IdempotencyKey>(
+                r#"
+                SELECT user_id, key, request_hash, response_status, response_content_type,
+                       response_body, created_at, expires_at
+                FROM idempotency_keys
+                WHERE user_id = $1 AND key = $2
+                "#,
+            )
+            .bind(user_id)
+            .bind(key)
+            .fetch_one(&mut *tx)
+            .await?,
+        ),
+    };
+
+    tx.commit().await?;
+
+    Ok(claim)
+}
+
+/// Stores the response of the request holding `key` under the claim made at
+/// `claimed_at`. Returns false if that claim lapsed and was taken over.
+#[allow(clippy::too_many_arguments)]
+pub async fn store_response(
+    executor: impl PgExecutor<'_>,
+    user_id: Uuid,
+    key: &str,
+    claimed_at: DateTime<Utc>,
+    status: i16,
+    content_type: Option<&str>,
+    body: &[u8],
+) -> Result<bool> {
+    let result = sqlx::query(
+        r#"
+        UPDATE idempotency_keys
+        SET response_status = $4, response_content_type = $5, response_body = $6
+        WHERE user_id = $1 AND key = $2 AND created_at = $3 AND response_status IS NULL
+        "#,
+    )
+    .bind(user_id)
+    .bind(key)
+    .bind(claimed_at)
+    .bind(status)
+    .bind(content_type)
+    .bind(body)
+    .execute(executor)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Frees `key` after its request failed, so that it can be retried, unless
+/// the claim made at `claimed_at` lapsed and was taken over.
+pub async fn release_key(
+    executor: impl PgExecutor<'_>,
+    user_id: Uuid,
+    key: &str,
+    claimed_at: DateTime<Utc>,
+) -> Result<()> {
+    sqlx::query(
+        r#"
+        DELETE FROM idempotency_keys
+        WHERE user_id = $1 AND key = $2 AND created_at = $3 AND response_status IS NULL
+        "#,
+    )
+    .bind(user_id)
+    .bind(key)
+    .bind(claimed_at)
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn delete_expired(pool: &PgPool) -> Result<u64> {
+    let result = sqlx::query("DELETE FROM idempotency_keys WHERE expires_at <= NOW()")
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected())
+}
@@ -0,0 +1,151 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use sqlx::{PgExecutor, PgPool};
+use uuid::Uuid;
+
+use crate::models::invitation::Invitation;
+use crate::models::user::Role;
+
+/// Creates the invited user, with an empty password hash, and their
+/// invitation. Returns `None` if the email already belongs to a user.
+pub async fn create_invitation(
+    pool: &PgPool,
+    email: &str,
+    name: &str,
+    role: Role,
+    token_hash: &str,
+    invited_by: Uuid,
+    expires_at: DateTime<Utc>,
+) -> Result<Option<Invitation>> {
+    let mut tx = pool.begin().await?;
+
+    let user_id: Option<Uuid> = sqlx::query_scalar(
+        r#"
+        INSERT INTO users (email, password_hash, name, role)
+        VALUES ($1, '', $2, $3)
+        ON CONFLICT (email) DO NOTHING
+        RETURNING id
+        "#,
+    )
+    .bind(email)
+    .bind(name)
+    .bind(role)
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    let Some(user_id) = user_id else {
+        return Ok(None);
+    };
+
+    let invitation = sqlx::query_as::<_, Invitation>(
+        r#"
+        INSERT INTO invitations (user_id, email, token_hash, invited_by, expires_at)
+        VALUES ($1, $2, $3, $4, $5)
+        RETURNING id, user_id, email, token_hash, invited_by, expires_at, sent_at, created_at, accepted_at
+        "#,
+    )
+    .bind(user_id)
+    .bind(email)
+    .bind(token_hash)
+    .bind(invited_by)
+    .bind(expires_at)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(Some(invitation))
+}
+
+/// Invitations not accepted yet, expired ones included, newest first.
+pub async fn list_pending_invitations(pool: &PgPool) -> Result<Vec<Invitation>> {
+    let invitations = sqlx::query_as::<_, Invitation>(
+        r#"
+        SELECT id, user_id, email, token_hash, invited_by, expires_at, sent_at, created_at, accepted_at
+        FROM invitations
+        WHERE accepted_at IS NULL
+        ORDER BY created_at DESC, id
+        "#,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(invitations)
+}
+
+pub async fn get_invitation_by_token_hash(
+    pool: &PgPool,
+    token_hash: &str,
+) -> Result<Option<Invitation>> {
+    let invitation = sqlx::query_as::<_, Invitation>(
+        r#"
+        SELECT id, user_id, email, token_hash, invited_by, expires_at, sent_at, created_at, accepted_at
+        FROM invitations
+        WHERE token_hash = $1
+        "#,
+    )
+    .bind(token_hash)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(invitation)
+}
+
+/// Replaces the token of a pending invitation, so only the newest link
+/// works, and extends it. Returns `None` if there is no such pending
+/// invitation.
+pub async fn renew_invitation(
+    pool: &PgPool,
+    id: Uuid,
+    token_hash: &str,
+    expires_at: DateTime<Utc>,
+) -> Result<Option<Invitation>> {
+    let invitation = sqlx::query_as::<_, Invitation>(
+        r#"
+        UPDATE invitations
+        SET token_hash = $2, expires_at = $3, sent_at = NOW()
+        WHERE id = $1 AND accepted_at IS NULL
+        RETURNING id, user_id, email, token_hash, invited_by, expires_at, sent_at, created_at, accepted_at
+        "#,
+    )
+    .bind(id)
+    .bind(token_hash)
+    .bind(expires_at)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(invitation)
+}
+
+/// Deletes a pending invitation together with the user it created. Returns
+/// `false` if there is no such pending invitation.
+pub async fn delete_pending_invitation(pool: &PgPool, id: Uuid) -> Result<bool> {
+    let result = sqlx::query(
+        r#"
+        DELETE FROM users
+        WHERE id = (SELECT user_id FROM invitations WHERE id = $1 AND accepted_at IS NULL)
+        "#,
+    )
+    .bind(id)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Marks an invitation accepted. Returns `false` if it already was, or
+/// expired in the meantime, so it can only be accepted once.
+pub async fn accept_invitation(executor: impl PgExecutor<'_>, id: Uuid) -> Result<bool> {
+    let result = sqlx::query(
+        r#"
+        UPDATE invitations
+        SET accepted_at = NOW()
+        WHERE id = $1 AND accepted_at IS NULL AND expires_at > NOW()
+        "#,
+    )
+    .bind(id)
+    .execute(executor)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
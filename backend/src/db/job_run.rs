@@ -0,0 +1,73 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::models::job::{JobRun, JobRunStatus};
+
+pub async fn start_run(
+    pool: &PgPool,
+    job_name: &str,
+    triggered_by: Option<Uuid>,
+) -> Result<JobRun> {
+    let run = sqlx::query_as::<_, JobRun>(
+        r#"
+        INSERT INTO job_runs (job_name, triggered_by)
+        VALUES ($1, $2)
+        RETURNING id, job_name, status, error, triggered_by, started_at, finished_at
+        "#,
+    )
+    .bind(job_name)
+    .bind(triggered_by)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(run)
+}
+
+pub async fn finish_run(
+    pool: &PgPool,
+    id: Uuid,
+    status: JobRunStatus,
+    error: Option<&str>,
+) -> Result<()> {
+    sqlx::query(
+        r#"
+        UPDATE job_runs
+        SET status = $1, error = $2, finished_at = NOW()
+        WHERE id = $3
+        "#,
+    )
+    .bind(status)
+    .bind(error)
+    .bind(id)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// The latest run of each job that has run.
+pub async fn get_latest_runs(pool: &PgPool) -> Result<Vec<JobRun>> {
+    let runs = sqlx::query_as::<_, JobRun>(
+        r#"
+        SELECT DISTINCT ON (job_name)
+               id, job_name, status, error, triggered_by, started_at, finished_at
+        FROM job_runs
+        ORDER BY job_name, started_at DESC
+        "#,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(runs)
+}
+
+pub async fn delete_runs_before(pool: &PgPool, before: DateTime<Utc>) -> Result<u64> {
+    let result = sqlx::query("DELETE FROM job_runs WHERE started_at < $1")
+        .bind(before)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected())
+}
@@ -1,5 +1,23 @@
 pub mod audit;
+pub mod auth_event;
+pub mod blob;
+pub mod branding;
 pub mod document;
+pub mod email_change;
+pub mod encryption;
+pub mod idempotency;
+pub mod invitation;
+pub mod job_run;
+pub mod outbox;
+pub mod purge_log;
+pub mod security_event;
+pub mod session;
+pub mod share;
+pub mod share_link;
 pub mod signature;
 pub mod signer;
+pub mod storage_scan;
+pub mod tag;
+pub mod timestamp;
+pub mod two_factor;
 pub mod user;
@@ -0,0 +1,244 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use sqlx::{PgConnection, PgExecutor, PgPool};
+use uuid::Uuid;
+
+use crate::models::outbox::{OutboxEmail, OutboxEmailKind, OutboxEvent, OutboxEventType};
+
+/// Queues an email to the signer, or to the document owner when `signer_id`
+/// is `None`.
+pub async fn enqueue_email(
+    executor: impl PgExecutor<'_>,
+    kind: OutboxEmailKind,
+    document_id: Uuid,
+    signer_id: Option<Uuid>,
+) -> Result<OutboxEmail> {
+    let email = sqlx::query_as::<_, OutboxEmail>(
+        r#"
+        INSERT INTO email_outbox (kind, document_id, signer_id, next_attempt_at)
+        VALUES ($1, $2, $3, NOW())
+        RETURNING id, kind, document_id, signer_id, attempts, last_error, next_attempt_at,
+                  sent_at, created_at
+        "#,
+    )
+    .bind(kind)
+    .bind(document_id)
+    .bind(signer_id)
+    .fetch_one(executor)
+    .await?;
+
+    Ok(email)
+}
+
+/// Claims due emails, optionally only those of one document, by pushing
+/// their next attempt `lease_secs` ahead so concurrent dispatchers skip them.
+pub async fn claim_due_emails(
+    pool: &PgPool,
+    document_id: Option<Uuid>,
+    lease_secs: i64,
+    limit: i64,
+) -> Result<Vec<OutboxEmail>> {
+    let emails = sqlx::query_as::<_, OutboxEmail>(
+        r#"
+        UPDATE email_outbox
+        SET next_attempt_at = NOW() + make_interval(secs => $1)
+        WHERE id IN (
+            SELECT id
+            FROM email_outbox
+            WHERE sent_at IS NULL AND next_attempt_at IS NOT NULL AND next_attempt_at <= NOW()
+              AND ($2::UUID IS NULL OR document_id = $2)
+            ORDER BY next_attempt_at
+            LIMIT $3
+            FOR UPDATE SKIP LOCKED
+        )
+        RETURNING id, kind, document_id, signer_id, attempts, last_error, next_attempt_at,
+                  sent_at, created_at
+        "#,
+    )
+    .bind(lease_secs as f64)
+    .bind(document_id)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(emails)
+}
+
+/// Stops delivery of a document's queued emails of `kind` that were not sent
+/// yet.
+pub async fn cancel_pending(
+    executor: impl PgExecutor<'_>,
+    document_id: Uuid,
+    kind: OutboxEmailKind,
+) -> Result<u64> {
+    let result = sqlx::query(
+        r#"
+        UPDATE email_outbox
+        SET next_attempt_at = NULL, last_error = 'Cancelled'
+        WHERE document_id = $1 AND kind = $2 AND sent_at IS NULL AND next_attempt_at IS NOT NULL
+        "#,
+    )
+    .bind(document_id)
+    .bind(kind)
+    .execute(executor)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// How many emails were queued for the email's signer before it.
+pub async fn count_earlier_signer_emails(pool: &PgPool, email_id: Uuid) -> Result<i64> {
+    let count = sqlx::query_scalar::<_, i64>(
+        r#"
+        SELECT COUNT(*)
+        FROM email_outbox earlier
+        JOIN email_outbox email ON email.id = $1
+        WHERE earlier.signer_id = email.signer_id
+          AND (earlier.created_at, earlier.id) < (email.created_at, email.id)
+        "#,
+    )
+    .bind(email_id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(count)
+}
+
+pub async fn mark_sent(executor: impl PgExecutor<'_>, id: Uuid) -> Result<()> {
+    sqlx::query(
+        r#"
+        UPDATE email_outbox
+        SET sent_at = NOW(), attempts = attempts + 1, last_error = NULL, next_attempt_at = NULL
+        WHERE id = $1
+        "#,
+    )
+    .bind(id)
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+/// Records a failed attempt. A `next_attempt_at` of `None` gives up.
+pub async fn record_failure(
+    pool: &PgPool,
+    id: Uuid,
+    error: &str,
+    next_attempt_at: Option<DateTime<Utc>>,
+) -> Result<()> {
+    sqlx::query(
+        r#"
+        UPDATE email_outbox
+        SET attempts = attempts + 1, last_error = $1, next_attempt_at = $2
+        WHERE id = $3
+        "#,
+    )
+    .bind(error)
+    .bind(next_attempt_at)
+    .bind(id)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn record_event(
+    executor: impl PgExecutor<'_>,
+    event_type: OutboxEventType,
+    document_id: Uuid,
+    signer_id: Option<Uuid>,
+    payload: serde_json::Value,
+) -> Result<OutboxEvent> {
+    let event = sqlx::query_as::<_, OutboxEvent>(
+        r#"
+        INSERT INTO outbox_events (event_type, document_id, signer_id, payload)
+        VALUES ($1, $2, $3, $4)
+        RETURNING id, event_type, document_id, signer_id, payload, attempts, last_error,
+                  next_attempt_at, processed_at, created_at
+        "#,
+    )
+    .bind(event_type)
+    .bind(document_id)
+    .bind(signer_id)
+    .bind(payload)
+    .fetch_one(executor)
+    .await?;
+
+    Ok(event)
+}
+
+/// Locks the oldest due event, optionally only of one document, for the rest
+/// of the transaction. Events locked by other dispatchers are skipped.
+pub async fn claim_next_event(
+    conn: &mut PgConnection,
+    document_id: Option<Uuid>,
+) -> Result<Option<OutboxEvent>> {
+    let event = sqlx::query_as::<_, OutboxEvent>(
+        r#"
+        SELECT id, event_type, document_id, signer_id, payload, attempts, last_error,
+               next_attempt_at, processed_at, created_at
+        FROM outbox_events
+        WHERE processed_at IS NULL AND next_attempt_at IS NOT NULL AND next_attempt_at <= NOW()
+          AND ($1::UUID IS NULL OR document_id = $1)
+        ORDER BY created_at, id
+        LIMIT 1
+        FOR UPDATE SKIP LOCKED
+        "#,
+    )
+    .bind(document_id)
+    .fetch_optional(conn)
+    .await?;
+
+    Ok(event)
+}
+
+pub async fn mark_event_processed(executor: impl PgExecutor<'_>, id: Uuid) -> Result<()> {
+    sqlx::query(
+        r#"
+        UPDATE outbox_events
+        SET processed_at = NOW(), attempts = attempts + 1, last_error = NULL,
+            next_attempt_at = NULL
+        WHERE id = $1
+        "#,
+    )
+    .bind(id)
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+/// Records a failed attempt to process an event. A `next_attempt_at` of
+/// `None` gives up.
+pub async fn record_event_failure(
+    pool: &PgPool,
+    id: Uuid,
+    error: &str,
+    next_attempt_at: Option<DateTime<Utc>>,
+) -> Result<()> {
+    sqlx::query(
+        r#"
+        UPDATE outbox_events
+        SET attempts = attempts + 1, last_error = $1, next_attempt_at = $2
+        WHERE id = $3 AND processed_at IS NULL
+        "#,
+    )
+    .bind(error)
+    .bind(next_attempt_at)
+    .bind(id)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn delete_processed_events_before(pool: &PgPool, before: DateTime<Utc>) -> Result<u64> {
+    let result = sqlx::query(
+        "DELETE FROM outbox_events WHERE processed_at IS NOT NULL AND processed_at < $1",
+    )
+    .bind(before)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
@@ -0,0 +1,42 @@
+use anyhow::Result;
+use sqlx::PgPool;
+
+use crate::models::document::Document;
+use crate::models::purge_log::PurgeLogEntry;
+
+pub async fn create_entry(pool: &PgPool, document: &Document, reason: &str) -> Result<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO purge_log (document_id, owner_id, title, status, file_hash, reason, document_created_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        "#,
+    )
+    .bind(document.id)
+    .bind(document.owner_id)
+    .bind(&document.title)
+    .bind(document.status)
+    .bind(&document.file_hash)
+    .bind(reason)
+    .bind(document.created_at)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn list_entries(pool: &PgPool, limit: i64, offset: i64) -> Result<Vec<PurgeLogEntry>> {
+    let entries = sqlx::query_as::<_, PurgeLogEntry>(
+        r#"
+        SELECT id, document_id, owner_id, title, status, file_hash, reason, document_created_at, purged_at
+        FROM purge_log
+        ORDER BY purged_at DESC
+        LIMIT $1 OFFSET $2
+        "#,
+    )
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(entries)
+}
@@ -0,0 +1,112 @@
+use anyhow::Result;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::models::security_event::{
+    SecurityEvent, SecurityEventSeverity, SecurityEventSource, SecurityEventType,
+};
+
+/// Opens an event, or counts another occurrence of the open event of the
+/// same document and kind. Returns the event only when it is new.
+pub async fn record_event(
+    pool: &PgPool,
+    document_id: Uuid,
+    event_type: SecurityEventType,
+    severity: SecurityEventSeverity,
+    source: SecurityEventSource,
+    details: Option<serde_json::Value>,
+) -> Result<Option<SecurityEvent>> {
+    let created = sqlx::query_as::<_, SecurityEvent>(
+        r#"
+        INSERT INTO security_events (document_id, event_type, severity, source, details)
+        VALUES ($1, $2, $3, $4, $5)
+        ON CONFLICT (document_id, event_type) WHERE acknowledged_at IS NULL DO NOTHING
+        RETURNING id, document_id, event_type, severity, source, details, occurrences, created_at, last_seen_at, acknowledged_at, acknowledged_by
+        "#,
+    )
+    .bind(document_id)
+    .bind(event_type)
+    .bind(severity)
+    .bind(source)
+    .bind(details)
+    .fetch_optional(pool)
+    .await?;
+
+    if created.is_none() {
+        sqlx::query(
+            r#"
+            UPDATE security_events
+            SET occurrences = occurrences + 1, last_seen_at = NOW()
+            WHERE document_id = $1 AND event_type = $2 AND acknowledged_at IS NULL
+            "#,
+        )
+        .bind(document_id)
+        .bind(event_type)
+        .execute(pool)
+        .await?;
+    }
+
+    Ok(created)
+}
+
+/// Events newest first; acknowledged ones only when asked for.
+pub async fn list_events(
+    pool: &PgPool,
+    include_acknowledged: bool,
+    limit: i64,
+    offset: i64,
+) -> Result<Vec<SecurityEvent>> {
+    let events = sqlx::query_as::<_, SecurityEvent>(
+        r#"
+        SELECT id, document_id, event_type, severity, source, details, occurrences, created_at, last_seen_at, acknowledged_at, acknowledged_by
+        FROM security_events
+        WHERE $1 OR acknowledged_at IS NULL
+        ORDER BY created_at DESC, id
+        LIMIT $2 OFFSET $3
+        "#,
+    )
+    .bind(include_acknowledged)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(events)
+}
+
+pub async fn get_event(pool: &PgPool, id: Uuid) -> Result<Option<SecurityEvent>> {
+    let event = sqlx::query_as::<_, SecurityEvent>(
+        r#"
+        SELECT id, document_id, event_type, severity, source, details, occurrences, created_at, last_seen_at, acknowledged_at, acknowledged_by
+        FROM security_events
+        WHERE id = $1
+        "#,
+    )
+    .bind(id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(event)
+}
+
+/// `None` if the event does not exist or was already acknowledged.
+pub async fn acknowledge_event(
+    pool: &PgPool,
+    id: Uuid,
+    user_id: Uuid,
+) -> Result<Option<SecurityEvent>> {
+    let event = sqlx::query_as::<_, SecurityEvent>(
+        r#"
+        UPDATE security_events
+        SET acknowledged_at = NOW(), acknowledged_by = $2
+        WHERE id = $1 AND acknowledged_at IS NULL
+        RETURNING id, document_id, event_type, severity, source, details, occurrences, created_at, last_seen_at, acknowledged_at, acknowledged_by
+        "#,
+    )
+    .bind(id)
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(event)
+}
@@ -0,0 +1,141 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use sqlx::{PgExecutor, PgPool};
+use uuid::Uuid;
+
+use crate::models::session::UserSession;
+
+/// How stale `last_used_at` may get before a request updates it, so that
+/// authenticating does not write on every request.
+const LAST_USED_RESOLUTION_SECS: i64 = 60;
+
+pub async fn create_session(
+    pool: &PgPool,
+    user_id: Uuid,
+    ip_address: Option<&str>,
+    user_agent: Option<&str>,
+    expires_at: DateTime<Utc>,
+) -> Result<UserSession> {
+    let session = sqlx::query_as::<_, UserSession>(
+        r#"
+        INSERT INTO user_sessions (user_id, ip_address, user_agent, expires_at)
+        VALUES ($1, $2, $3, $4)
+        RETURNING id, user_id, ip_address, user_agent, created_at, last_used_at, expires_at, revoked_at
+        "#,
+    )
+    .bind(user_id)
+    .bind(ip_address)
+    .bind(user_agent)
+    .bind(expires_at)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(session)
+}
+
+/// Whether the session is neither revoked nor expired, recording that it was
+/// used.
+pub async fn use_session(pool: &PgPool, id: Uuid, user_id: Uuid) -> Result<bool> {
+    let last_used_at: Option<DateTime<Utc>> = sqlx::query_scalar(
+        r#"
+        SELECT last_used_at
+        FROM user_sessions
+        WHERE id = $1 AND user_id = $2 AND revoked_at IS NULL AND expires_at > NOW()
+        "#,
+    )
+    .bind(id)
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await?;
+
+    let Some(last_used_at) = last_used_at else {
+        return Ok(false);
+    };
+
+    if last_used_at < Utc::now() - chrono::Duration::seconds(LAST_USED_RESOLUTION_SECS) {
+        sqlx::query("UPDATE user_sessions SET last_used_at = NOW() WHERE id = $1")
+            .bind(id)
+            .execute(pool)
+            .await?;
+    }
+
+    Ok(true)
+}
+
+/// The user's sessions that can still be used, most recently used first.
+pub async fn list_active_sessions(pool: &PgPool, user_id: Uuid) -> Result<Vec<UserSession>> {
+    let sessions = sqlx::query_as::<_, UserSession>(
+        r#"
+        SELECT id, user_id, ip_address, user_agent, created_at, last_used_at, expires_at, revoked_at
+        FROM user_sessions
+        WHERE user_id = $1 AND revoked_at IS NULL AND expires_at > NOW()
+        ORDER BY last_used_at DESC, id
+        "#,
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(sessions)
+}
+
+/// Returns `false` if the user has no such active session.
+pub async fn revoke_session(pool: &PgPool, id: Uuid, user_id: Uuid) -> Result<bool> {
+    let result = sqlx::query(
+        r#"
+        UPDATE user_sessions
+        SET revoked_at = NOW()
+        WHERE id = $1 AND user_id = $2 AND revoked_at IS NULL AND expires_at > NOW()
+        "#,
+    )
+    .bind(id)
+    .bind(user_id)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Revokes all of the user's active sessions except `keep`, returning how
+/// many were.
+pub async fn revoke_other_sessions(pool: &PgPool, user_id: Uuid, keep: Uuid) -> Result<u64> {
+    let result = sqlx::query(
+        r#"
+        UPDATE user_sessions
+        SET revoked_at = NOW()
+        WHERE user_id = $1 AND id <> $2 AND revoked_at IS NULL AND expires_at > NOW()
+        "#,
+    )
+    .bind(user_id)
+    .bind(keep)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Revokes all of the user's active sessions, returning how many were.
+pub async fn revoke_all_sessions(executor: impl PgExecutor<'_>, user_id: Uuid) -> Result<u64> {
+    let result = sqlx::query(
+        r#"
+        UPDATE user_sessions
+        SET revoked_at = NOW()
+        WHERE user_id = $1 AND revoked_at IS NULL AND expires_at > NOW()
+        "#,
+    )
+    .bind(user_id)
+    .execute(executor)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Deletes sessions whose tokens have expired; they can no longer be used
+/// either way.
+pub async fn delete_expired(pool: &PgPool) -> Result<u64> {
+    let result = sqlx::query("DELETE FROM user_sessions WHERE expires_at < NOW()")
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected())
+}
@@ -0,0 +1,144 @@
+use anyhow::Result;
+use sqlx::{PgExecutor, PgPool};
+use uuid::Uuid;
+
+use crate::models::share::{DocumentShare, ShareRole, SharedDocument};
+
+/// Shares a document with a user, changing the role of an existing share.
+pub async fn upsert_share(
+    executor: impl PgExecutor<'_>,
+    document_id: Uuid,
+    user_id: Uuid,
+    role: ShareRole,
+) -> Result<DocumentShare> {
+    let share = sqlx::query_as::<_, DocumentShare>(
+        r#"
+        WITH share AS (
+            INSERT INTO document_shares (document_id, user_id, role)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (document_id, user_id) DO UPDATE SET role = EXCLUDED.role
+            RETURNING document_id, user_id, role, created_at
+        )
+        SELECT share.document_id, share.user_id, u.email, u.name, share.role, share.created_at
+        FROM share
+        JOIN users u ON u.id = share.user_id
+        "#,
+    )
+    .bind(document_id)
+    .bind(user_id)
+    .bind(role)
+    .fetch_one(executor)
+    .await?;
+
+    Ok(share)
+}
+
+/// Returns the removed share, if there was one.
+pub async fn delete_share(
+    executor: impl PgExecutor<'_>,
+    document_id: Uuid,
+    user_id: Uuid,
+) -> Result<Option<ShareRole>> {
+    let role = sqlx::query_scalar::<_, ShareRole>(
+        r#"
+        DELETE FROM document_shares
+        WHERE document_id = $1 AND user_id = $2
+        RETURNING role
+        "#,
+    )
+    .bind(document_id)
+    .bind(user_id)
+    .fetch_optional(executor)
+    .await?;
+
+    Ok(role)
+}
+
+pub async fn get_share_role(
+    pool: &PgPool,
+    document_id: Uuid,
+    user_id: Uuid,
+) -> Result<Option<ShareRole>> {
+    let role = sqlx::query_scalar::<_, ShareRole>(
+        r#"
+        SELECT role
+        FROM document_shares
+        WHERE document_id = $1 AND user_id = $2
+        "#,
+    )
+    .bind(document_id)
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(role)
+}
+
+pub async fn get_shares_by_document(
+    pool: &PgPool,
+    document_id: Uuid,
+) -> Result<Vec<DocumentShare>> {
+    let shares = sqlx::query_as::<_, DocumentShare>(
+        r#"
+        SELECT s.document_id, s.user_id, u.email, u.name, s.role, s.created_at
+        FROM document_shares s
+        JOIN users u ON u.id = s.user_id
+        WHERE s.document_id = $1
+        ORDER BY s.created_at
+        "#,
+    )
+    .bind(document_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(shares)
+}
+
+pub async fn get_documents_shared_with(
+    pool: &PgPool,
+    user_id: Uuid,
+    limit: i64,
+    offset: i64,
+) -> Result<Vec<SharedDocument>> {
+    let docs = sqlx::query_as::<_, SharedDocument>(
+        r#"
+        SELECT d.id, d.owner_id, d.title, d.original_filename, d.file_path, d.file_hash, d.status,
+               d.self_sign_only, d.verification_method, d.total_signers, d.completed_signers,
+               d.expires_at, d.completed_at, d.deleted_at, d.legal_hold,
+               d.page_count, d.page_sizes,
+               d.sealed_file_path, d.sealed_file_hash, d.sealed_at, d.seal_signer, d.void_reason,
+               d.reminder_interval_days, d.max_reminders, d.signer_download_enabled, d.progress_notifications, d.locale,
+               d.encryption_key_id, d.wrapped_data_key, d.file_nonce, d.sealed_file_nonce,
+               d.created_at, d.updated_at,
+               s.role AS share_role
+        FROM document_shares s
+        JOIN documents d ON d.id = s.document_id
+        WHERE s.user_id = $1 AND d.deleted_at IS NULL
+        ORDER BY d.created_at DESC
+        LIMIT $2 OFFSET $3
+        "#,
+    )
+    .bind(user_id)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(docs)
+}
+
+pub async fn count_documents_shared_with(pool: &PgPool, user_id: Uuid) -> Result<i64> {
+    let count = sqlx::query_scalar::<_, i64>(
+        r#"
+        SELECT COUNT(*)
+        FROM document_shares s
+        JOIN documents d ON d.id = s.document_id
+        WHERE s.user_id = $1 AND d.deleted_at IS NULL
+        "#,
+    )
+    .bind(user_id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(count)
+}
@@ -0,0 +1,140 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use sqlx::{PgExecutor, PgPool};
+use uuid::Uuid;
+
+use crate::models::share_link::ShareLink;
+
+pub async fn create_share_link(
+    executor: impl PgExecutor<'_>,
+    document_id: Uuid,
+    token_hash: &str,
+    passcode_hash: Option<&str>,
+    expires_at: DateTime<Utc>,
+    created_by: Uuid,
+) -> Result<ShareLink> {
+    let link = sqlx::query_as::<_, ShareLink>(
+        r#"
+        INSERT INTO share_links (document_id, token_hash, passcode_hash, expires_at, created_by)
+        VALUES ($1, $2, $3, $4, $5)
+        RETURNING id, document_id, passcode_hash, failed_passcode_attempts, locked_until,
+                  passcode_hash IS NOT NULL AS has_passcode, expires_at, created_by, created_at,
+                  revoked_at, access_count, last_accessed_at
+        "#,
+    )
+    .bind(document_id)
+    .bind(token_hash)
+    .bind(passcode_hash)
+    .bind(expires_at)
+    .bind(created_by)
+    .fetch_one(executor)
+    .await?;
+
+    Ok(link)
+}
+
+pub async fn get_share_link_by_token_hash(
+    pool: &PgPool,
+    token_hash: &str,
+) -> Result<Option<ShareLink>> {
+    let link = sqlx::query_as::<_, ShareLink>(
+        r#"
+        SELECT id, document_id, passcode_hash, failed_passcode_attempts, locked_until,
+               passcode_hash IS NOT NULL AS has_passcode, expires_at, created_by, created_at,
+               revoked_at, access_count, last_accessed_at
+        FROM share_links
+        WHERE token_hash = $1
+        "#,
+    )
+    .bind(token_hash)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(link)
+}
+
+/// The document's links, newest first, revoked and expired ones included.
+pub async fn get_share_links_by_document(
+    pool: &PgPool,
+    document_id: Uuid,
+) -> Result<Vec<ShareLink>> {
+    let links = sqlx::query_as::<_, ShareLink>(
+        r#"
+        SELECT id, document_id, passcode_hash, failed_passcode_attempts, locked_until,
+               passcode_hash IS NOT NULL AS has_passcode, expires_at, created_by, created_at,
+               revoked_at, access_count, last_accessed_at
+        FROM share_links
+        WHERE document_id = $1
+        ORDER BY created_at DESC
+        "#,
+    )
+    .bind(document_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(links)
+}
+
+/// `None` if the document has no such link or it was already revoked.
+pub async fn revoke_share_link(
+    executor: impl PgExecutor<'_>,
+    document_id: Uuid,
+    id: Uuid,
+) -> Result<Option<ShareLink>> {
+    let link = sqlx::query_as::<_, ShareLink>(
+        r#"
+        UPDATE share_links
+        SET revoked_at = NOW()
+        WHERE id = $1 AND document_id = $2 AND revoked_at IS NULL
+        RETURNING id, document_id, passcode_hash, failed_passcode_attempts, locked_until,
+                  passcode_hash IS NOT NULL AS has_passcode, expires_at, created_by, created_at,
+                  revoked_at, access_count, last_accessed_at
+        "#,
+    )
+    .bind(id)
+    .bind(document_id)
+    .fetch_optional(executor)
+    .await?;
+
+    Ok(link)
+}
+
+pub async fn record_access(pool: &PgPool, id: Uuid) -> Result<()> {
+    sqlx::query(
+        r#"
+        UPDATE share_links
+        SET access_count = access_count + 1, last_accessed_at = NOW(),
+            failed_passcode_attempts = 0
+        WHERE id = $1
+        "#,
+    )
+    .bind(id)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Counts a wrong passcode, locking the link once `max_attempts` is reached.
+pub async fn record_passcode_failure(
+    pool: &PgPool,
+    id: Uuid,
+    max_attempts: i64,
+    locked_until: DateTime<Utc>,
+) -> Result<()> {
+    sqlx::query(
+        r#"
+        UPDATE share_links
+        SET failed_passcode_attempts = CASE WHEN failed_passcode_attempts + 1 >= $2 THEN 0 ELSE failed_passcode_attempts + 1 END,
+            locked_until = CASE WHEN failed_passcode_attempts + 1 >= $2 THEN $3 ELSE locked_until END
+        WHERE id = $1
+        "#,
+    )
+    .bind(id)
+    .bind(max_attempts)
+    .bind(locked_until)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
@@ -1,35 +1,61 @@
 use anyhow::Result;
-use sqlx::PgPool;
+use sqlx::{PgExecutor, PgPool};
 use uuid::Uuid;
 
-use crate::models::signature::Signature;
+use crate::models::signature::{Signature, SignatureType, StoredSignatureImage, UserSignature};
 
+/// Inserts the signer's signature for a field, replacing any earlier one. The
+/// image must already be stored under `storage_key`, named after `id`.
 #[allow(clippy::too_many_arguments)]
 pub async fn create_signature(
-    pool: &PgPool,
+    executor: impl PgExecutor<'_>,
+    id: Uuid,
     signer_id: Uuid,
     document_id: Uuid,
     field_id: Uuid,
-    signature_data: &str,
+    image: &StoredSignatureImage,
     signature_hash: &str,
+    signature_type: SignatureType,
+    typed_text: Option<&str>,
+    typed_font: Option<&str>,
     ip_address: &str,
     user_agent: &str,
 ) -> Result<Signature> {
     let sig = sqlx::query_as::<_, Signature>(
         r#"
-        INSERT INTO signatures (signer_id, document_id, field_id, signature_data, signature_hash, ip_address, user_agent)
-        VALUES ($1, $2, $3, $4, $5, $6, $7)
-        RETURNING id, signer_id, document_id, field_id, signature_data, signature_hash, ip_address, user_agent, created_at
+        INSERT INTO signatures (id, signer_id, document_id, field_id, storage_key, content_type, byte_length, image_nonce, signature_hash, signature_type, typed_text, typed_font, ip_address, user_agent)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14)
+        ON CONFLICT (field_id, signer_id) DO UPDATE
+        SET id = EXCLUDED.id,
+            signature_data = NULL,
+            storage_key = EXCLUDED.storage_key,
+            content_type = EXCLUDED.content_type,
+            byte_length = EXCLUDED.byte_length,
+            image_nonce = EXCLUDED.image_nonce,
+            signature_hash = EXCLUDED.signature_hash,
+            signature_type = EXCLUDED.signature_type,
+            typed_text = EXCLUDED.typed_text,
+            typed_font = EXCLUDED.typed_font,
+            ip_address = EXCLUDED.ip_address,
+            user_agent = EXCLUDED.user_agent
+        RETURNING id, signer_id, document_id, field_id, storage_key, content_type, byte_length, image_nonce, signature_hash, signature_type, typed_text, typed_font, ip_address, user_agent, created_at
         "#,
     )
+    .bind(id)
     .bind(signer_id)
     .bind(document_id)
     .bind(field_id)
-    .bind(signature_data)
+    .bind(&image.storage_key)
+    .bind(&image.content_type)
+    .bind(image.byte_length)
+    .bind(&image.nonce)
     .bind(signature_hash)
+    .bind(signature_type)
+    .bind(typed_text)
+    .bind(typed_font)
     .bind(ip_address)
     .bind(user_agent)
-    .fetch_one(pool)
+    .fetch_one(executor)
     .await?;
 
     Ok(sig)
@@ -41,7 +67,7 @@ pub async fn get_signatures_by_document(
 ) -> Result<Vec<Signature>> {
     let sigs = sqlx::query_as::<_, Signature>(
         r#"
-        SELECT id, signer_id, document_id, field_id, signature_data, signature_hash, ip_address, user_agent, created_at
+        SELECT id, signer_id, document_id, field_id, storage_key, content_type, byte_length, image_nonce, signature_hash, signature_type, typed_text, typed_font, ip_address, user_agent, created_at
         FROM signatures
         WHERE document_id = $1
         ORDER BY created_at
@@ -54,17 +80,20 @@ pub async fn get_signatures_by_document(
     Ok(sigs)
 }
 
-pub async fn get_signatures_by_signer(pool: &PgPool, signer_id: Uuid) -> Result<Vec<Signature>> {
+pub async fn get_signatures_by_signer(
+    executor: impl PgExecutor<'_>,
+    signer_id: Uuid,
+) -> Result<Vec<Signature>> {
     let sigs = sqlx::query_as::<_, Signature>(
         r#"
-        SELECT id, signer_id, document_id, field_id, signature_data, signature_hash, ip_address, user_agent, created_at
+        SELECT id, signer_id, document_id, field_id, storage_key, content_type, byte_length, image_nonce, signature_hash, signature_type, typed_text, typed_font, ip_address, user_agent, created_at
         FROM signatures
         WHERE signer_id = $1
         ORDER BY created_at
         "#,
     )
     .bind(signer_id)
-    .fetch_all(pool)
+    .fetch_all(executor)
     .await?;
 
     Ok(sigs)
@@ -73,7 +102,7 @@ pub async fn get_signatures_by_signer(pool: &PgPool, signer_id: Uuid) -> Result<
 pub async fn get_signature_by_field(pool: &PgPool, field_id: Uuid) -> Result<Option<Signature>> {
     let sig = sqlx::query_as::<_, Signature>(
         r#"
-        SELECT id, signer_id, document_id, field_id, signature_data, signature_hash, ip_address, user_agent, created_at
+        SELECT id, signer_id, document_id, field_id, storage_key, content_type, byte_length, image_nonce, signature_hash, signature_type, typed_text, typed_font, ip_address, user_agent, created_at
         FROM signatures
         WHERE field_id = $1
         "#,
@@ -84,3 +113,177 @@ pub async fn get_signature_by_field(pool: &PgPool, field_id: Uuid) -> Result<Opt
 
     Ok(sig)
 }
+
+pub async fn get_signature_by_id(pool: &PgPool, id: Uuid) -> Result<Option<Signature>> {
+    let sig = sqlx::query_as::<_, Signature>(
+        r#"
+        SELECT id, signer_id, document_id, field_id, storage_key, content_type, byte_length, image_nonce, signature_hash, signature_type, typed_text, typed_font, ip_address, user_agent, created_at
+        FROM signatures
+        WHERE id = $1
+        "#,
+    )
+    .bind(id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(sig)
+}
+
+/// The base64 data URL of a signature whose image is still kept in the table.
+pub async fn get_inline_signature_data(pool: &PgPool, id: Uuid) -> Result<Option<String>> {
+    let data = sqlx::query_scalar::<_, Option<String>>(
+        "SELECT signature_data FROM signatures WHERE id = $1 AND storage_key IS NULL",
+    )
+    .bind(id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(data.flatten())
+}
+
+/// Signatures after `after` whose images are still kept in the table, in id
+/// order, with their document.
+pub async fn get_signatures_with_inline_data(
+    pool: &PgPool,
+    after: Uuid,
+    limit: i64,
+) -> Result<Vec<(Uuid, Uuid)>> {
+    let rows = sqlx::query_as::<_, (Uuid, Uuid)>(
+        r#"
+        SELECT id, document_id
+        FROM signatures
+        WHERE storage_key IS NULL AND signature_data IS NOT NULL AND id > $1
+        ORDER BY id
+        LIMIT $2
+        "#,
+    )
+    .bind(after)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows)
+}
+
+/// Records that a signature's image was moved to storage and drops the inline
+/// copy. Returns false if another instance moved it first.
+pub async fn set_signature_image(
+    pool: &PgPool,
+    id: Uuid,
+    image: &StoredSignatureImage,
+) -> Result<bool> {
+    let result = sqlx::query(
+        r#"
+        UPDATE signatures
+        SET storage_key = $2, content_type = $3, byte_length = $4, image_nonce = $5,
+            signature_data = NULL
+        WHERE id = $1 AND storage_key IS NULL
+        "#,
+    )
+    .bind(id)
+    .bind(&image.storage_key)
+    .bind(&image.content_type)
+    .bind(image.byte_length)
+    .bind(&image.nonce)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Storage keys of every stored signature image, for the storage scan.
+pub async fn get_signature_storage_keys(pool: &PgPool) -> Result<Vec<String>> {
+    let keys = sqlx::query_scalar::<_, String>(
+        "SELECT storage_key FROM signatures WHERE storage_key IS NOT NULL",
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(keys)
+}
+
+/// Replaces the IP address and user agent recorded with a signer's
+/// signatures. Signature data and hashes are kept.
+pub async fn anonymize_signatures_by_signer(
+    pool: &PgPool,
+    signer_id: Uuid,
+    ip_address: &str,
+    user_agent: &str,
+) -> Result<()> {
+    sqlx::query("UPDATE signatures SET ip_address = $1, user_agent = $2 WHERE signer_id = $3")
+        .bind(ip_address)
+        .bind(user_agent)
+        .bind(signer_id)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+pub async fn create_user_signature(
+    pool: &PgPool,
+    user_id: Uuid,
+    name: &str,
+    signature_data: &str,
+    signature_hash: &str,
+) -> Result<UserSignature> {
+    let sig = sqlx::query_as::<_, UserSignature>(
+        r#"
+        INSERT INTO user_signatures (user_id, name, signature_data, signature_hash)
+        VALUES ($1, $2, $3, $4)
+        RETURNING id, user_id, name, signature_data, signature_hash, created_at
+        "#,
+    )
+    .bind(user_id)
+    .bind(name)
+    .bind(signature_data)
+    .bind(signature_hash)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(sig)
+}
+
+pub async fn get_user_signatures(pool: &PgPool, user_id: Uuid) -> Result<Vec<UserSignature>> {
+    let sigs = sqlx::query_as::<_, UserSignature>(
+        r#"
+        SELECT id, user_id, name, signature_data, signature_hash, created_at
+        FROM user_signatures
+        WHERE user_id = $1
+        ORDER BY created_at DESC
+        "#,
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(sigs)
+}
+
+pub async fn get_user_signature_by_id(
+    executor: impl PgExecutor<'_>,
+    id: Uuid,
+) -> Result<Option<UserSignature>> {
+    let sig = sqlx::query_as::<_, UserSignature>(
+        r#"
+        SELECT id, user_id, name, signature_data, signature_hash, created_at
+        FROM user_signatures
+        WHERE id = $1
+        "#,
+    )
+    .bind(id)
+    .fetch_optional(executor)
+    .await?;
+
+    Ok(sig)
+}
+
+pub async fn delete_user_signature(pool: &PgPool, id: Uuid, user_id: Uuid) -> Result<bool> {
+    let result = sqlx::query("DELETE FROM user_signatures WHERE id = $1 AND user_id = $2")
+        .bind(id)
+        .bind(user_id)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected() > 0)
+}
@@ -1,49 +1,79 @@
 use anyhow::Result;
-use sqlx::PgPool;
+use chrono::{DateTime, Utc};
+use sqlx::types::Json;
+use sqlx::{PgConnection, PgExecutor, PgPool};
 use uuid::Uuid;
 
-use crate::models::signer::{Signer, SignerStatus};
+use crate::models::signature::{CompleteSigningRequest, SigningProgress};
+use crate::models::signer::{
+    InPersonSession, Signer, SignerAccessCode, SignerOpenLink, SignerRole, SignerStatus,
+    VerificationCode,
+};
 
+/// Keeps each signer email, ignoring case and surrounding spaces, once on a
+/// document.
+const UNIQUE_EMAIL_INDEX: &str = "idx_signers_document_email";
+
+/// Whether `error` comes from giving a signer an email another signer of the
+/// document already has.
+pub fn is_duplicate_email(error: &anyhow::Error) -> bool {
+    matches!(
+        error.downcast_ref::<sqlx::Error>(),
+        Some(sqlx::Error::Database(e)) if e.constraint() == Some(UNIQUE_EMAIL_INDEX)
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
 pub async fn create_signer(
-    pool: &PgPool,
+    executor: impl PgExecutor<'_>,
     document_id: Uuid,
     email: &str,
     name: &str,
     order_index: i32,
+    role: SignerRole,
     access_token: &str,
+    locale: Option<&str>,
 ) -> Result<Signer> {
     let signer = sqlx::query_as::<_, Signer>(
         r#"
-        INSERT INTO signers (document_id, email, name, order_index, access_token)
-        VALUES ($1, $2, $3, $4, $5)
-        RETURNING id, document_id, email, name, order_index, status, access_token,
-                  ip_address, user_agent, viewed_at, signed_at, declined_at, decline_reason,
-                  email_sent_at, created_at, updated_at
+        INSERT INTO signers (document_id, email, name, order_index, role, access_token, locale)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        RETURNING id, document_id, email, name, order_index, role, status, access_token,
+                  ip_address, user_agent, viewed_at, signed_at, approved_at, verified_at,
+                  verification_method, verification_outcome, declined_at,
+                  consented_at, consent_version, consent_ip_address, consent_user_agent, locale,
+                  decline_reason, email_sent_at, reminders_sent, last_reminded_at, anonymized_at,
+                  created_at, updated_at
         "#,
     )
     .bind(document_id)
     .bind(email)
     .bind(name)
     .bind(order_index)
+    .bind(role)
     .bind(access_token)
-    .fetch_one(pool)
+    .bind(locale)
+    .fetch_one(executor)
     .await?;
 
     Ok(signer)
 }
 
-pub async fn get_signer_by_id(pool: &PgPool, id: Uuid) -> Result<Option<Signer>> {
+pub async fn get_signer_by_id(executor: impl PgExecutor<'_>, id: Uuid) -> Result<Option<Signer>> {
     let signer = sqlx::query_as::<_, Signer>(
         r#"
-        SELECT id, document_id, email, name, order_index, status, access_token,
-               ip_address, user_agent, viewed_at, signed_at, declined_at, decline_reason,
-               email_sent_at, created_at, updated_at
+        SELECT id, document_id, email, name, order_index, role, status, access_token,
+               ip_address, user_agent, viewed_at, signed_at, approved_at, verified_at,
+               verification_method, verification_outcome, declined_at,
+               consented_at, consent_version, consent_ip_address, consent_user_agent, locale,
+               decline_reason, email_sent_at, reminders_sent, last_reminded_at, anonymized_at,
+               created_at, updated_at
         FROM signers
         WHERE id = $1
         "#,
     )
     .bind(id)
-    .fetch_optional(pool)
+    .fetch_optional(executor)
     .await?;
 
     Ok(signer)
@@ -52,9 +82,12 @@ pub async fn get_signer_by_id(pool: &PgPool, id: Uuid) -> Result<Option<Signer>>
 pub async fn get_signer_by_access_token(pool: &PgPool, token: &str) -> Result<Option<Signer>> {
     let signer = sqlx::query_as::<_, Signer>(
         r#"
-        SELECT id, document_id, email, name, order_index, status, access_token,
-               ip_address, user_agent, viewed_at, signed_at, declined_at, decline_reason,
-               email_sent_at, created_at, updated_at
+        SELECT id, document_id, email, name, order_index, role, status, access_token,
+               ip_address, user_agent, viewed_at, signed_at, approved_at, verified_at,
+               verification_method, verification_outcome, declined_at,
+               consented_at, consent_version, consent_ip_address, consent_user_agent, locale,
+               decline_reason, email_sent_at, reminders_sent, last_reminded_at, anonymized_at,
+               created_at, updated_at
         FROM signers
         WHERE access_token = $1
         "#,
@@ -66,33 +99,93 @@ pub async fn get_signer_by_access_token(pool: &PgPool, token: &str) -> Result<Op
     Ok(signer)
 }
 
-pub async fn get_signers_by_document(pool: &PgPool, document_id: Uuid) -> Result<Vec<Signer>> {
+pub async fn get_signers_by_document(
+    executor: impl PgExecutor<'_>,
+    document_id: Uuid,
+) -> Result<Vec<Signer>> {
     let signers = sqlx::query_as::<_, Signer>(
         r#"
-        SELECT id, document_id, email, name, order_index, status, access_token,
-               ip_address, user_agent, viewed_at, signed_at, declined_at, decline_reason,
-               email_sent_at, created_at, updated_at
+        SELECT id, document_id, email, name, order_index, role, status, access_token,
+               ip_address, user_agent, viewed_at, signed_at, approved_at, verified_at,
+               verification_method, verification_outcome, declined_at,
+               consented_at, consent_version, consent_ip_address, consent_user_agent, locale,
+               decline_reason, email_sent_at, reminders_sent, last_reminded_at, anonymized_at,
+               created_at, updated_at
         FROM signers
-        WHERE document_id = $1
+        WHERE document_id = $1 AND status <> 'removed'
         ORDER BY order_index
         "#,
     )
     .bind(document_id)
+    .fetch_all(executor)
+    .await?;
+
+    Ok(signers)
+}
+
+/// Signer rows for `email` across all documents, ignoring case and rows that
+/// were already anonymized.
+pub async fn get_signers_by_email(pool: &PgPool, email: &str) -> Result<Vec<Signer>> {
+    let signers = sqlx::query_as::<_, Signer>(
+        r#"
+        SELECT id, document_id, email, name, order_index, role, status, access_token,
+               ip_address, user_agent, viewed_at, signed_at, approved_at, verified_at,
+               verification_method, verification_outcome, declined_at,
+               consented_at, consent_version, consent_ip_address, consent_user_agent, locale,
+               decline_reason, email_sent_at, reminders_sent, last_reminded_at, anonymized_at,
+               created_at, updated_at
+        FROM signers
+        WHERE LOWER(email) = LOWER($1) AND anonymized_at IS NULL
+        ORDER BY created_at ASC
+        "#,
+    )
+    .bind(email)
     .fetch_all(pool)
     .await?;
 
     Ok(signers)
 }
 
+/// The signer of `document_id` with `email`, ignoring case and surrounding
+/// spaces. Removed signers are not matched.
+pub async fn get_signer_by_document_email(
+    executor: impl PgExecutor<'_>,
+    document_id: Uuid,
+    email: &str,
+) -> Result<Option<Signer>> {
+    let signer = sqlx::query_as::<_, Signer>(
+        r#"
+        SELECT id, document_id, email, name, order_index, role, status, access_token,
+               ip_address, user_agent, viewed_at, signed_at, approved_at, verified_at,
+               verification_method, verification_outcome, declined_at,
+               consented_at, consent_version, consent_ip_address, consent_user_agent, locale,
+               decline_reason, email_sent_at, reminders_sent, last_reminded_at, anonymized_at,
+               created_at, updated_at
+        FROM signers
+        WHERE document_id = $1 AND email <> '' AND status <> 'removed'
+          AND LOWER(TRIM(email)) = LOWER(TRIM($2))
+        "#,
+    )
+    .bind(document_id)
+    .bind(email)
+    .fetch_optional(executor)
+    .await?;
+
+    Ok(signer)
+}
+
 pub async fn update_signer_status(pool: &PgPool, id: Uuid, status: SignerStatus) -> Result<Signer> {
     let signer = sqlx::query_as::<_, Signer>(
         r#"
         UPDATE signers
         SET status = $1
         WHERE id = $2
-        RETURNING id, document_id, email, name, order_index, status, access_token,
-                  ip_address, user_agent, viewed_at, signed_at, declined_at, decline_reason,
-                  email_sent_at, created_at, updated_at
+        RETURNING id, document_id, email, name, order_index, role, status, access_token,
+                  ip_address, user_agent, viewed_at, signed_at, approved_at, verified_at,
+                  verification_method, verification_outcome, declined_at,
+                  consented_at, consent_version, consent_ip_address, consent_user_agent, locale,
+                  decline_reason, email_sent_at, reminders_sent, last_reminded_at, anonymized_at,
+                  created_at, updated_at
         "#,
     )
     .bind(status)
@@ -114,9 +207,12 @@ pub async fn mark_signer_viewed(
         UPDATE signers
         SET status = 'viewed', viewed_at = NOW(), ip_address = $1, user_agent = $2
         WHERE id = $3
-        RETURNING id, document_id, email, name, order_index, status, access_token,
-                  ip_address, user_agent, viewed_at, signed_at, declined_at, decline_reason,
-                  email_sent_at, created_at, updated_at
+        RETURNING id, document_id, email, name, order_index, role, status, access_token,
+                  ip_address, user_agent, viewed_at, signed_at, approved_at, verified_at,
+                  verification_method, verification_outcome, declined_at,
+                  consented_at, consent_version, consent_ip_address, consent_user_agent, locale,
+                  decline_reason, email_sent_at, reminders_sent, last_reminded_at, anonymized_at,
+                  created_at, updated_at
         "#,
     )
     .bind(ip_address)
@@ -128,65 +224,404 @@ pub async fn mark_signer_viewed(
     Ok(signer)
 }
 
+/// Returns `None` when the signer already finished or declined, so a repeated
+/// submission changes nothing.
 pub async fn mark_signer_signed(
-    pool: &PgPool,
+    executor: impl PgExecutor<'_>,
     id: Uuid,
     ip_address: &str,
     user_agent: &str,
-) -> Result<Signer> {
+) -> Result<Option<Signer>> {
     let signer = sqlx::query_as::<_, Signer>(
         r#"
         UPDATE signers
-        SET status = 'signed', signed_at = NOW(), ip_address = $1, user_agent = $2
-        WHERE id = $3
-        RETURNING id, document_id, email, name, order_index, status, access_token,
-                  ip_address, user_agent, viewed_at, signed_at, declined_at, decline_reason,
-                  email_sent_at, created_at, updated_at
+        SET status = 'signed', signed_at = NOW(), ip_address = $1, user_agent = $2,
+            signing_progress = NULL, signing_progress_saved_at = NULL
+        WHERE id = $3 AND status NOT IN ('signed', 'approved', 'declined')
+        RETURNING id, document_id, email, name, order_index, role, status, access_token,
+                  ip_address, user_agent, viewed_at, signed_at, approved_at, verified_at,
+                  verification_method, verification_outcome, declined_at,
+                  consented_at, consent_version, consent_ip_address, consent_user_agent, locale,
+                  decline_reason, email_sent_at, reminders_sent, last_reminded_at, anonymized_at,
+                  created_at, updated_at
         "#,
     )
     .bind(ip_address)
     .bind(user_agent)
     .bind(id)
-    .fetch_one(pool)
+    .fetch_optional(executor)
     .await?;
 
     Ok(signer)
 }
 
-pub async fn mark_signer_declined(pool: &PgPool, id: Uuid, reason: Option<&str>) -> Result<Signer> {
+pub async fn mark_signer_approved(
+    executor: impl PgExecutor<'_>,
+    id: Uuid,
+    ip_address: &str,
+    user_agent: &str,
+) -> Result<Option<Signer>> {
     let signer = sqlx::query_as::<_, Signer>(
         r#"
         UPDATE signers
-        SET status = 'declined', declined_at = NOW(), decline_reason = $1
+        SET status = 'approved', approved_at = NOW(), ip_address = $1, user_agent = $2
+        WHERE id = $3 AND status NOT IN ('signed', 'approved', 'declined')
+        RETURNING id, document_id, email, name, order_index, role, status, access_token,
+                  ip_address, user_agent, viewed_at, signed_at, approved_at, verified_at,
+                  verification_method, verification_outcome, declined_at,
+                  consented_at, consent_version, consent_ip_address, consent_user_agent, locale,
+                  decline_reason, email_sent_at, reminders_sent, last_reminded_at, anonymized_at,
+                  created_at, updated_at
+        "#,
+    )
+    .bind(ip_address)
+    .bind(user_agent)
+    .bind(id)
+    .fetch_optional(executor)
+    .await?;
+
+    Ok(signer)
+}
+
+pub async fn mark_signer_declined(
+    executor: impl PgExecutor<'_>,
+    id: Uuid,
+    reason: Option<&str>,
+) -> Result<Signer> {
+    let signer = sqlx::query_as::<_, Signer>(
+        r#"
+        UPDATE signers
+        SET status = 'declined', declined_at = NOW(), decline_reason = $1,
+            signing_progress = NULL, signing_progress_saved_at = NULL
         WHERE id = $2
-        RETURNING id, document_id, email, name, order_index, status, access_token,
-                  ip_address, user_agent, viewed_at, signed_at, declined_at, decline_reason,
-                  email_sent_at, created_at, updated_at
+        RETURNING id, document_id, email, name, order_index, role, status, access_token,
+                  ip_address, user_agent, viewed_at, signed_at, approved_at, verified_at,
+                  verification_method, verification_outcome, declined_at,
+                  consented_at, consent_version, consent_ip_address, consent_user_agent, locale,
+                  decline_reason, email_sent_at, reminders_sent, last_reminded_at, anonymized_at,
+                  created_at, updated_at
         "#,
     )
     .bind(reason)
     .bind(id)
-    .fetch_one(pool)
+    .fetch_one(executor)
     .await?;
 
     Ok(signer)
 }
 
-pub async fn mark_email_sent(pool: &PgPool, id: Uuid) -> Result<Signer> {
+pub async fn mark_email_sent(executor: impl PgExecutor<'_>, id: Uuid) -> Result<()> {
+    sqlx::query(
+        r#"
+        UPDATE signers
+        SET status = CASE WHEN status = 'pending' THEN 'sent' ELSE status END,
+            email_sent_at = NOW()
+        WHERE id = $1
+        "#,
+    )
+    .bind(id)
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+/// Puts a signer of a recalled document back to how it was before sending:
+/// a fresh access token, `sent`/`viewed` back to `pending`, and no identity
+/// verification, access code unlock or in-person session carried over.
+pub async fn reset_signer_for_recall(
+    conn: &mut PgConnection,
+    id: Uuid,
+    access_token: &str,
+) -> Result<Signer> {
+    let signer = sqlx::query_as::<_, Signer>(
+        r#"
+        UPDATE signers
+        SET access_token = $2,
+            status = CASE WHEN status IN ('sent', 'viewed') THEN 'pending' ELSE status END,
+            viewed_at = CASE WHEN status IN ('sent', 'viewed') THEN NULL ELSE viewed_at END,
+            email_sent_at = CASE WHEN status IN ('sent', 'viewed') THEN NULL ELSE email_sent_at END,
+            verified_at = NULL, reminders_sent = 0, last_reminded_at = NULL,
+            signing_progress = NULL, signing_progress_saved_at = NULL
+        WHERE id = $1
+        RETURNING id, document_id, email, name, order_index, role, status, access_token,
+                  ip_address, user_agent, viewed_at, signed_at, approved_at, verified_at,
+                  verification_method, verification_outcome, declined_at,
+                  consented_at, consent_version, consent_ip_address, consent_user_agent, locale,
+                  decline_reason, email_sent_at, reminders_sent, last_reminded_at, anonymized_at,
+                  created_at, updated_at
+        "#,
+    )
+    .bind(id)
+    .bind(access_token)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    end_signer_sessions(conn, id).await?;
+
+    Ok(signer)
+}
+
+/// Gives a signer who declined another chance: a fresh access token and back
+/// to `sent`, with the decline cleared. Returns `None` unless the signer had
+/// declined.
+pub async fn reset_declined_signer(
+    conn: &mut PgConnection,
+    id: Uuid,
+    access_token: &str,
+) -> Result<Option<Signer>> {
+    let signer = sqlx::query_as::<_, Signer>(
+        r#"
+        UPDATE signers
+        SET access_token = $2, status = 'sent', declined_at = NULL, decline_reason = NULL,
+            viewed_at = NULL, verified_at = NULL, reminders_sent = 0, last_reminded_at = NULL
+        WHERE id = $1 AND status = 'declined'
+        RETURNING id, document_id, email, name, order_index, role, status, access_token,
+                  ip_address, user_agent, viewed_at, signed_at, approved_at, verified_at,
+                  verification_method, verification_outcome, declined_at,
+                  consented_at, consent_version, consent_ip_address, consent_user_agent, locale,
+                  decline_reason, email_sent_at, reminders_sent, last_reminded_at, anonymized_at,
+                  created_at, updated_at
+        "#,
+    )
+    .bind(id)
+    .bind(access_token)
+    .fetch_optional(&mut *conn)
+    .await?;
+
+    if signer.is_some() {
+        end_signer_sessions(conn, id).await?;
+    }
+
+    Ok(signer)
+}
+
+/// Gives the signer a new link unless they already signed or approved, ending
+/// sessions opened with the old one.
+pub async fn rotate_access_token(
+    conn: &mut PgConnection,
+    id: Uuid,
+    access_token: &str,
+) -> Result<Option<Signer>> {
     let signer = sqlx::query_as::<_, Signer>(
         r#"
         UPDATE signers
-        SET status = 'sent', email_sent_at = NOW()
-        WHERE id = $1 AND status = 'pending'
-        RETURNING id, document_id, email, name, order_index, status, access_token,
-                  ip_address, user_agent, viewed_at, signed_at, declined_at, decline_reason,
-                  email_sent_at, created_at, updated_at
+        SET access_token = $2, verified_at = NULL
+        WHERE id = $1 AND status NOT IN ('signed', 'approved')
+        RETURNING id, document_id, email, name, order_index, role, status, access_token,
+                  ip_address, user_agent, viewed_at, signed_at, approved_at, verified_at,
+                  verification_method, verification_outcome, declined_at,
+                  consented_at, consent_version, consent_ip_address, consent_user_agent, locale,
+                  decline_reason, email_sent_at, reminders_sent, last_reminded_at, anonymized_at,
+                  created_at, updated_at
         "#,
     )
     .bind(id)
+    .bind(access_token)
+    .fetch_optional(&mut *conn)
+    .await?;
+
+    if signer.is_some() {
+        end_signer_sessions(conn, id).await?;
+    }
+
+    Ok(signer)
+}
+
+pub async fn revoke_access_token(
+    executor: impl PgExecutor<'_>,
+    signer_id: Uuid,
+    token_hash: &str,
+    reason: Option<&str>,
+) -> Result<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO revoked_signer_tokens (token_hash, signer_id, reason)
+        VALUES ($1, $2, $3)
+        ON CONFLICT (token_hash) DO NOTHING
+        "#,
+    )
+    .bind(token_hash)
+    .bind(signer_id)
+    .bind(reason)
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+/// Takes a signer who has not signed or approved off the document, giving
+/// them a new link nobody receives and ending sessions opened with the old
+/// one.
+pub async fn mark_signer_removed(
+    conn: &mut PgConnection,
+    id: Uuid,
+    access_token: &str,
+) -> Result<Option<Signer>> {
+    let signer = sqlx::query_as::<_, Signer>(
+        r#"
+        UPDATE signers
+        SET status = 'removed', access_token = $2, verified_at = NULL, updated_at = NOW()
+        WHERE id = $1 AND status NOT IN ('signed', 'approved', 'removed')
+        RETURNING id, document_id, email, name, order_index, role, status, access_token,
+                  ip_address, user_agent, viewed_at, signed_at, approved_at, verified_at,
+                  verification_method, verification_outcome, declined_at,
+                  consented_at, consent_version, consent_ip_address, consent_user_agent, locale,
+                  decline_reason, email_sent_at, reminders_sent, last_reminded_at, anonymized_at,
+                  created_at, updated_at
+        "#,
+    )
+    .bind(id)
+    .bind(access_token)
+    .fetch_optional(&mut *conn)
+    .await?;
+
+    if signer.is_some() {
+        end_signer_sessions(conn, id).await?;
+    }
+
+    Ok(signer)
+}
+
+pub async fn is_access_token_revoked(pool: &PgPool, token_hash: &str) -> Result<bool> {
+    let revoked = sqlx::query_scalar::<_, bool>(
+        "SELECT EXISTS (SELECT 1 FROM revoked_signer_tokens WHERE token_hash = $1)",
+    )
+    .bind(token_hash)
     .fetch_one(pool)
     .await?;
 
+    Ok(revoked)
+}
+
+/// Ends in-person sessions and access code unlocks tied to the signer's
+/// previous link.
+async fn end_signer_sessions(conn: &mut PgConnection, id: Uuid) -> Result<()> {
+    sqlx::query("DELETE FROM in_person_sessions WHERE signer_id = $1")
+        .bind(id)
+        .execute(&mut *conn)
+        .await?;
+
+    sqlx::query(
+        r#"
+        UPDATE signer_access_codes
+        SET unlocked_at = NULL, failed_attempts = 0, locked_until = NULL, updated_at = NOW()
+        WHERE signer_id = $1
+        "#,
+    )
+    .bind(id)
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Claims up to `limit` signers who have not finished a pending document
+/// that expires within `within_days`, marking them warned so no other
+/// instance warns them too.
+pub async fn claim_expiry_warnings(
+    pool: &PgPool,
+    within_days: i64,
+    limit: i64,
+) -> Result<Vec<Signer>> {
+    let signers = sqlx::query_as::<_, Signer>(
+        r#"
+        UPDATE signers
+        SET expiry_warning_sent_at = NOW()
+        WHERE id IN (
+            SELECT s.id
+            FROM signers s
+            JOIN documents d ON d.id = s.document_id
+            WHERE d.status = 'pending' AND d.deleted_at IS NULL
+              AND d.expires_at > NOW() AND d.expires_at <= NOW() + make_interval(days => $1::INT)
+              AND s.status IN ('pending', 'sent', 'viewed') AND s.expiry_warning_sent_at IS NULL
+            ORDER BY d.expires_at
+            LIMIT $2
+            FOR UPDATE OF s SKIP LOCKED
+        )
+        RETURNING id, document_id, email, name, order_index, role, status, access_token,
+                  ip_address, user_agent, viewed_at, signed_at, approved_at, verified_at,
+                  verification_method, verification_outcome, declined_at,
+                  consented_at, consent_version, consent_ip_address, consent_user_agent, locale,
+                  decline_reason, email_sent_at, reminders_sent, last_reminded_at, anonymized_at,
+                  created_at, updated_at
+        "#,
+    )
+    .bind(within_days)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(signers)
+}
+
+/// Undoes a claim whose warning could not be sent, so it is tried again.
+pub async fn release_expiry_warning(pool: &PgPool, id: Uuid) -> Result<()> {
+    sqlx::query("UPDATE signers SET expiry_warning_sent_at = NULL WHERE id = $1")
+        .bind(id)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+/// Claims up to `limit` signers due an automatic reminder, counting the
+/// reminder so no other instance sends it too. A signer is due once the
+/// document's reminder interval has passed since they were last emailed,
+/// until its `max_reminders` is reached.
+pub async fn claim_due_reminders(executor: impl PgExecutor<'_>, limit: i64) -> Result<Vec<Signer>> {
+    let signers = sqlx::query_as::<_, Signer>(
+        r#"
+        UPDATE signers
+        SET reminders_sent = reminders_sent + 1, last_reminded_at = NOW()
+        WHERE id IN (
+            SELECT s.id
+            FROM signers s
+            JOIN documents d ON d.id = s.document_id
+            WHERE d.status = 'pending' AND d.deleted_at IS NULL
+              AND (d.expires_at IS NULL OR d.expires_at > NOW())
+              AND d.reminder_interval_days IS NOT NULL AND d.max_reminders IS NOT NULL
+              AND s.status IN ('sent', 'viewed') AND s.reminders_sent < d.max_reminders
+              AND COALESCE(s.last_reminded_at, s.email_sent_at)
+                  <= NOW() - make_interval(days => d.reminder_interval_days)
+            ORDER BY COALESCE(s.last_reminded_at, s.email_sent_at)
+            LIMIT $1
+            FOR UPDATE OF s SKIP LOCKED
+        )
+        RETURNING id, document_id, email, name, order_index, role, status, access_token,
+                  ip_address, user_agent, viewed_at, signed_at, approved_at, verified_at,
+                  verification_method, verification_outcome, declined_at,
+                  consented_at, consent_version, consent_ip_address, consent_user_agent, locale,
+                  decline_reason, email_sent_at, reminders_sent, last_reminded_at, anonymized_at,
+                  created_at, updated_at
+        "#,
+    )
+    .bind(limit)
+    .fetch_all(executor)
+    .await?;
+
+    Ok(signers)
+}
+
+/// Counts a reminder sent by the document owner.
+pub async fn record_reminder(executor: impl PgExecutor<'_>, id: Uuid) -> Result<Signer> {
+    let signer = sqlx::query_as::<_, Signer>(
+        r#"
+        UPDATE signers
+        SET reminders_sent = reminders_sent + 1, last_reminded_at = NOW()
+        WHERE id = $1
+        RETURNING id, document_id, email, name, order_index, role, status, access_token,
+                  ip_address, user_agent, viewed_at, signed_at, approved_at, verified_at,
+                  verification_method, verification_outcome, declined_at,
+                  consented_at, consent_version, consent_ip_address, consent_user_agent, locale,
+                  decline_reason, email_sent_at, reminders_sent, last_reminded_at, anonymized_at,
+                  created_at, updated_at
+        "#,
+    )
+    .bind(id)
+    .fetch_one(executor)
+    .await?;
+
     Ok(signer)
 }
 
@@ -217,3 +652,577 @@ pub async fn count_signed_by_document(pool: &PgPool, document_id: Uuid) -> Resul
 
     Ok(count.0)
 }
+
+pub async fn create_in_person_session(
+    pool: &PgPool,
+    signer_id: Uuid,
+    host_user_id: Uuid,
+    token: &str,
+    expires_at: DateTime<Utc>,
+) -> Result<InPersonSession> {
+    let session = sqlx::query_as::<_, InPersonSession>(
+        r#"
+        INSERT INTO in_person_sessions (signer_id, host_user_id, token, expires_at)
+        VALUES ($1, $2, $3, $4)
+        RETURNING id, signer_id, host_user_id, token, expires_at, created_at
+        "#,
+    )
+    .bind(signer_id)
+    .bind(host_user_id)
+    .bind(token)
+    .bind(expires_at)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(session)
+}
+
+pub async fn get_in_person_session_by_id(
+    pool: &PgPool,
+    id: Uuid,
+) -> Result<Option<InPersonSession>> {
+    let session = sqlx::query_as::<_, InPersonSession>(
+        r#"
+        SELECT id, signer_id, host_user_id, token, expires_at, created_at
+        FROM in_person_sessions
+        WHERE id = $1
+        "#,
+    )
+    .bind(id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(session)
+}
+
+pub async fn get_in_person_session_by_token(
+    pool: &PgPool,
+    token: &str,
+) -> Result<Option<InPersonSession>> {
+    let session = sqlx::query_as::<_, InPersonSession>(
+        r#"
+        SELECT id, signer_id, host_user_id, token, expires_at, created_at
+        FROM in_person_sessions
+        WHERE token = $1
+        "#,
+    )
+    .bind(token)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(session)
+}
+
+/// Records the outcome of an identity verification attempt. Only a successful
+/// outcome sets `verified_at`; anything else clears it.
+pub async fn record_verification_outcome(
+    pool: &PgPool,
+    id: Uuid,
+    method: &str,
+    outcome: &str,
+) -> Result<Signer> {
+    let signer = sqlx::query_as::<_, Signer>(
+        r#"
+        UPDATE signers
+        SET verification_method = $2,
+            verification_outcome = $3,
+            verified_at = CASE WHEN $3 = 'verified' THEN NOW() ELSE NULL END
+        WHERE id = $1
+        RETURNING id, document_id, email, name, order_index, role, status, access_token,
+                  ip_address, user_agent, viewed_at, signed_at, approved_at, verified_at,
+                  verification_method, verification_outcome, declined_at,
+                  consented_at, consent_version, consent_ip_address, consent_user_agent, locale,
+                  decline_reason, email_sent_at, reminders_sent, last_reminded_at, anonymized_at,
+                  created_at, updated_at
+        "#,
+    )
+    .bind(id)
+    .bind(method)
+    .bind(outcome)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(signer)
+}
+
+/// Records the signer's consent to do business electronically under the
+/// consent text `version`, replacing any earlier consent.
+pub async fn record_consent(
+    pool: &PgPool,
+    id: Uuid,
+    version: &str,
+    ip_address: &str,
+    user_agent: &str,
+) -> Result<Signer> {
+    let signer = sqlx::query_as::<_, Signer>(
+        r#"
+        UPDATE signers
+        SET consented_at = NOW(),
+            consent_version = $2,
+            consent_ip_address = $3,
+            consent_user_agent = $4,
+            updated_at = NOW()
+        WHERE id = $1
+        RETURNING id, document_id, email, name, order_index, role, status, access_token,
+                  ip_address, user_agent, viewed_at, signed_at, approved_at, verified_at,
+                  verification_method, verification_outcome, declined_at,
+                  consented_at, consent_version, consent_ip_address, consent_user_agent, locale,
+                  decline_reason, email_sent_at, reminders_sent, last_reminded_at, anonymized_at,
+                  created_at, updated_at
+        "#,
+    )
+    .bind(id)
+    .bind(version)
+    .bind(ip_address)
+    .bind(user_agent)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(signer)
+}
+
+pub async fn get_signing_progress(
+    pool: &PgPool,
+    signer_id: Uuid,
+) -> Result<Option<SigningProgress>> {
+    let progress = sqlx::query_as::<_, SigningProgress>(
+        r#"
+        SELECT signing_progress AS "values", signing_progress_saved_at AS saved_at
+        FROM signers
+        WHERE id = $1 AND signing_progress IS NOT NULL
+        "#,
+    )
+    .bind(signer_id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(progress)
+}
+
+/// Replaces the signer's saved progress. Returns when it was saved, or `None`
+/// if they have already signed, approved or declined.
+pub async fn save_signing_progress(
+    pool: &PgPool,
+    signer_id: Uuid,
+    values: &CompleteSigningRequest,
+) -> Result<Option<DateTime<Utc>>> {
+    let saved_at = sqlx::query_scalar::<_, DateTime<Utc>>(
+        r#"
+        UPDATE signers
+        SET signing_progress = $2, signing_progress_saved_at = NOW()
+        WHERE id = $1 AND status IN ('pending', 'sent', 'viewed')
+        RETURNING signing_progress_saved_at
+        "#,
+    )
+    .bind(signer_id)
+    .bind(Json(values))
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(saved_at)
+}
+
+pub async fn create_verification_code(
+    pool: &PgPool,
+    signer_id: Uuid,
+    code_hash: &str,
+    expires_at: DateTime<Utc>,
+) -> Result<VerificationCode> {
+    let code = sqlx::query_as::<_, VerificationCode>(
+        r#"
+        INSERT INTO signer_verification_codes (signer_id, code_hash, expires_at)
+        VALUES ($1, $2, $3)
+        RETURNING id, signer_id, code_hash, expires_at, failed_attempts, consumed_at, created_at
+        "#,
+    )
+    .bind(signer_id)
+    .bind(code_hash)
+    .bind(expires_at)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(code)
+}
+
+/// Returns the most recently issued verification code for a signer.
+pub async fn get_latest_verification_code(
+    pool: &PgPool,
+    signer_id: Uuid,
+) -> Result<Option<VerificationCode>> {
+    let code = sqlx::query_as::<_, VerificationCode>(
+        r#"
+        SELECT id, signer_id, code_hash, expires_at, failed_attempts, consumed_at, created_at
+        FROM signer_verification_codes
+        WHERE signer_id = $1
+        ORDER BY created_at DESC
+        LIMIT 1
+        "#,
+    )
+    .bind(signer_id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(code)
+}
+
+pub async fn count_verification_codes_since(
+    pool: &PgPool,
+    signer_id: Uuid,
+    since: DateTime<Utc>,
+) -> Result<i64> {
+    let count: (i64,) = sqlx::query_as(
+        "SELECT COUNT(*) FROM signer_verification_codes WHERE signer_id = $1 AND created_at > $2",
+    )
+    .bind(signer_id)
+    .bind(since)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(count.0)
+}
+
+pub async fn count_failed_verifications_since(
+    pool: &PgPool,
+    signer_id: Uuid,
+    since: DateTime<Utc>,
+) -> Result<i64> {
+    let count: (i64,) = sqlx::query_as(
+        r#"
+        SELECT COALESCE(SUM(failed_attempts), 0)::BIGINT
+        FROM signer_verification_codes
+        WHERE signer_id = $1 AND created_at > $2
+        "#,
+    )
+    .bind(signer_id)
+    .bind(since)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(count.0)
+}
+
+pub async fn record_failed_verification(pool: &PgPool, code_id: Uuid) -> Result<()> {
+    sqlx::query(
+        "UPDATE signer_verification_codes SET failed_attempts = failed_attempts + 1 WHERE id = $1",
+    )
+    .bind(code_id)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn consume_verification_code(pool: &PgPool, code_id: Uuid) -> Result<()> {
+    sqlx::query("UPDATE signer_verification_codes SET consumed_at = NOW() WHERE id = $1")
+        .bind(code_id)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+/// Returns `None` if `expected_updated_at` is given and no longer matches.
+pub async fn update_signer(
+    executor: impl PgExecutor<'_>,
+    id: Uuid,
+    name: Option<&str>,
+    email: Option<&str>,
+    order_index: Option<i32>,
+    locale: Option<&str>,
+    expected_updated_at: Option<DateTime<Utc>>,
+) -> Result<Option<Signer>> {
+    // An empty locale clears it.
+    let signer = sqlx::query_as::<_, Signer>(
+        r#"
+        UPDATE signers
+        SET name = COALESCE($1, name), email = COALESCE($2, email),
+            order_index = COALESCE($3, order_index),
+            locale = CASE WHEN $6::TEXT IS NULL THEN locale ELSE NULLIF($6, '') END,
+            updated_at = NOW()
+        WHERE id = $4 AND ($5::TIMESTAMPTZ IS NULL OR updated_at = $5)
+        RETURNING id, document_id, email, name, order_index, role, status, access_token,
+                  ip_address, user_agent, viewed_at, signed_at, approved_at, verified_at,
+                  verification_method, verification_outcome, declined_at,
+                  consented_at, consent_version, consent_ip_address, consent_user_agent, locale,
+                  decline_reason, email_sent_at, reminders_sent, last_reminded_at, anonymized_at,
+                  created_at, updated_at
+        "#,
+    )
+    .bind(name)
+    .bind(email)
+    .bind(order_index)
+    .bind(id)
+    .bind(expected_updated_at)
+    .bind(locale)
+    .fetch_optional(executor)
+    .await?;
+
+    Ok(signer)
+}
+
+/// Sets or replaces a signer's access code, resetting any unlock or lockout state.
+pub async fn set_access_code(
+    executor: impl PgExecutor<'_>,
+    signer_id: Uuid,
+    code_hash: &str,
+) -> Result<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO signer_access_codes (signer_id, code_hash)
+        VALUES ($1, $2)
+        ON CONFLICT (signer_id) DO UPDATE
+        SET code_hash = EXCLUDED.code_hash, failed_attempts = 0, locked_until = NULL,
+            unlocked_at = NULL, updated_at = NOW()
+        "#,
+    )
+    .bind(signer_id)
+    .bind(code_hash)
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn delete_access_code(executor: impl PgExecutor<'_>, signer_id: Uuid) -> Result<()> {
+    sqlx::query("DELETE FROM signer_access_codes WHERE signer_id = $1")
+        .bind(signer_id)
+        .execute(executor)
+        .await?;
+
+    Ok(())
+}
+
+pub async fn get_access_code(pool: &PgPool, signer_id: Uuid) -> Result<Option<SignerAccessCode>> {
+    let code = sqlx::query_as::<_, SignerAccessCode>(
+        r#"
+        SELECT signer_id, code_hash, failed_attempts, locked_until, unlocked_at, created_at, updated_at
+        FROM signer_access_codes
+        WHERE signer_id = $1
+        "#,
+    )
+    .bind(signer_id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(code)
+}
+
+/// Counts a wrong access code, locking the signer out once `max_attempts` is reached.
+pub async fn record_access_code_failure(
+    pool: &PgPool,
+    signer_id: Uuid,
+    max_attempts: i64,
+    locked_until: DateTime<Utc>,
+) -> Result<SignerAccessCode> {
+    let code = sqlx::query_as::<_, SignerAccessCode>(
+        r#"
+        UPDATE signer_access_codes
+        SET failed_attempts = CASE WHEN failed_attempts + 1 >= $2 THEN 0 ELSE failed_attempts + 1 END,
+            locked_until = CASE WHEN failed_attempts + 1 >= $2 THEN $3 ELSE locked_until END,
+            updated_at = NOW()
+        WHERE signer_id = $1
+        RETURNING signer_id, code_hash, failed_attempts, locked_until, unlocked_at, created_at, updated_at
+        "#,
+    )
+    .bind(signer_id)
+    .bind(max_attempts)
+    .bind(locked_until)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(code)
+}
+
+pub async fn mark_access_code_unlocked(pool: &PgPool, signer_id: Uuid) -> Result<()> {
+    sqlx::query(
+        r#"
+        UPDATE signer_access_codes
+        SET unlocked_at = NOW(), failed_attempts = 0, locked_until = NULL, updated_at = NOW()
+        WHERE signer_id = $1
+        "#,
+    )
+    .bind(signer_id)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn create_open_link(
+    executor: impl PgExecutor<'_>,
+    signer_id: Uuid,
+    token: &str,
+    single_use: bool,
+) -> Result<SignerOpenLink> {
+    let link = sqlx::query_as::<_, SignerOpenLink>(
+        r#"
+        INSERT INTO signer_open_links (signer_id, token, single_use)
+        VALUES ($1, $2, $3)
+        RETURNING signer_id, token, single_use, claimed_at, claimed_ip, claimed_user_agent, created_at
+        "#,
+    )
+    .bind(signer_id)
+    .bind(token)
+    .bind(single_use)
+    .fetch_one(executor)
+    .await?;
+
+    Ok(link)
+}
+
+pub async fn get_open_link(
+    executor: impl PgExecutor<'_>,
+    signer_id: Uuid,
+) -> Result<Option<SignerOpenLink>> {
+    let link = sqlx::query_as::<_, SignerOpenLink>(
+        r#"
+        SELECT signer_id, token, single_use, claimed_at, claimed_ip, claimed_user_agent, created_at
+        FROM signer_open_links
+        WHERE signer_id = $1
+        "#,
+    )
+    .bind(signer_id)
+    .fetch_optional(executor)
+    .await?;
+
+    Ok(link)
+}
+
+pub async fn get_open_link_by_token(pool: &PgPool, token: &str) -> Result<Option<SignerOpenLink>> {
+    let link = sqlx::query_as::<_, SignerOpenLink>(
+        r#"
+        SELECT signer_id, token, single_use, claimed_at, claimed_ip, claimed_user_agent, created_at
+        FROM signer_open_links
+        WHERE token = $1
+        "#,
+    )
+    .bind(token)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(link)
+}
+
+pub async fn get_open_links_by_document(
+    executor: impl PgExecutor<'_>,
+    document_id: Uuid,
+) -> Result<Vec<SignerOpenLink>> {
+    let links = sqlx::query_as::<_, SignerOpenLink>(
+        r#"
+        SELECT l.signer_id, l.token, l.single_use, l.claimed_at, l.claimed_ip,
+               l.claimed_user_agent, l.created_at
+        FROM signer_open_links l
+        JOIN signers s ON s.id = l.signer_id
+        WHERE s.document_id = $1 AND s.status <> 'removed'
+        "#,
+    )
+    .bind(document_id)
+    .fetch_all(executor)
+    .await?;
+
+    Ok(links)
+}
+
+/// Claims an open link for the caller, unless someone already has. Of
+/// concurrent claims exactly one gets the link back.
+pub async fn claim_open_link(
+    conn: &mut PgConnection,
+    token: &str,
+    ip_address: &str,
+    user_agent: &str,
+) -> Result<Option<SignerOpenLink>> {
+    let link = sqlx::query_as::<_, SignerOpenLink>(
+        r#"
+        UPDATE signer_open_links
+        SET claimed_at = NOW(), claimed_ip = $2, claimed_user_agent = $3
+        WHERE token = $1 AND claimed_at IS NULL
+        RETURNING signer_id, token, single_use, claimed_at, claimed_ip, claimed_user_agent, created_at
+        "#,
+    )
+    .bind(token)
+    .bind(ip_address)
+    .bind(user_agent)
+    .fetch_optional(&mut *conn)
+    .await?;
+
+    Ok(link)
+}
+
+pub async fn set_signer_identity(
+    conn: &mut PgConnection,
+    id: Uuid,
+    name: &str,
+    email: &str,
+) -> Result<Signer> {
+    let signer = sqlx::query_as::<_, Signer>(
+        r#"
+        UPDATE signers
+        SET name = $2, email = $3, updated_at = NOW()
+        WHERE id = $1
+        RETURNING id, document_id, email, name, order_index, role, status, access_token,
+                  ip_address, user_agent, viewed_at, signed_at, approved_at, verified_at,
+                  verification_method, verification_outcome, declined_at,
+                  consented_at, consent_version, consent_ip_address, consent_user_agent, locale,
+                  decline_reason, email_sent_at, reminders_sent, last_reminded_at, anonymized_at,
+                  created_at, updated_at
+        "#,
+    )
+    .bind(id)
+    .bind(name)
+    .bind(email)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    Ok(signer)
+}
+
+/// Replaces the signer's personal data with placeholders, including what was
+/// recorded when they consented and when they claimed an open link. The IP address and user agent are
+/// only replaced where one was recorded.
+pub async fn anonymize_signer(
+    pool: &PgPool,
+    id: Uuid,
+    name: &str,
+    email: &str,
+    ip_address: &str,
+    user_agent: &str,
+) -> Result<()> {
+    sqlx::query(
+        r#"
+        UPDATE signers
+        SET name = $1,
+            email = $2,
+            ip_address = CASE WHEN ip_address IS NULL THEN NULL ELSE $3 END,
+            user_agent = CASE WHEN user_agent IS NULL THEN NULL ELSE $4 END,
+            consent_ip_address = CASE WHEN consent_ip_address IS NULL THEN NULL ELSE $3 END,
+            consent_user_agent = CASE WHEN consent_user_agent IS NULL THEN NULL ELSE $4 END,
+            signing_progress = NULL, signing_progress_saved_at = NULL,
+            anonymized_at = NOW(),
+            updated_at = NOW()
+        WHERE id = $5
+        "#,
+    )
+    .bind(name)
+    .bind(email)
+    .bind(ip_address)
+    .bind(user_agent)
+    .bind(id)
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        r#"
+        UPDATE signer_open_links
+        SET claimed_ip = CASE WHEN claimed_ip IS NULL THEN NULL ELSE $1 END,
+            claimed_user_agent = CASE WHEN claimed_user_agent IS NULL THEN NULL ELSE $2 END
+        WHERE signer_id = $3
+        "#,
+    )
+    .bind(ip_address)
+    .bind(user_agent)
+    .bind(id)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
@@ -0,0 +1,58 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use sqlx::types::Json;
+use sqlx::PgPool;
+
+use crate::models::storage_scan::{HashMismatch, MissingFile, OrphanedObject, StorageScanReport};
+
+#[allow(clippy::too_many_arguments)]
+pub async fn create_report(
+    pool: &PgPool,
+    started_at: DateTime<Utc>,
+    objects_scanned: i32,
+    documents_checked: i32,
+    orphans: &[OrphanedObject],
+    orphans_deleted: i32,
+    missing_files: &[MissingFile],
+    hash_mismatches: &[HashMismatch],
+    errors: &[String],
+) -> Result<StorageScanReport> {
+    let report = sqlx::query_as::<_, StorageScanReport>(
+        r#"
+        INSERT INTO storage_scan_reports (started_at, objects_scanned, documents_checked, orphans, orphans_deleted,
+                                          missing_files, hash_mismatches, errors)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+        RETURNING id, started_at, completed_at, objects_scanned, documents_checked, orphans, orphans_deleted,
+                  missing_files, hash_mismatches, errors
+        "#,
+    )
+    .bind(started_at)
+    .bind(objects_scanned)
+    .bind(documents_checked)
+    .bind(Json(orphans))
+    .bind(orphans_deleted)
+    .bind(Json(missing_files))
+    .bind(Json(hash_mismatches))
+    .bind(Json(errors))
+    .fetch_one(pool)
+    .await?;
+
+    Ok(report)
+}
+
+pub async fn list_reports(pool: &PgPool, limit: i64) -> Result<Vec<StorageScanReport>> {
+    let reports = sqlx::query_as::<_, StorageScanReport>(
+        r#"
+        SELECT id, started_at, completed_at, objects_scanned, documents_checked, orphans, orphans_deleted,
+               missing_files, hash_mismatches, errors
+        FROM storage_scan_reports
+        ORDER BY started_at DESC
+        LIMIT $1
+        "#,
+    )
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(reports)
+}
@@ -0,0 +1,83 @@
+use anyhow::Result;
+use sqlx::{PgExecutor, PgPool};
+use uuid::Uuid;
+
+use crate::models::tag::TagUsage;
+
+/// Tags a document. Tags it already has, in any case, are left as they are.
+pub async fn add_tags(
+    executor: impl PgExecutor<'_>,
+    document_id: Uuid,
+    tags: &[String],
+) -> Result<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO document_tags (document_id, name)
+        SELECT $1, tag FROM UNNEST($2::TEXT[]) AS tag
+        ON CONFLICT DO NOTHING
+        "#,
+    )
+    .bind(document_id)
+    .bind(tags)
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+/// Returns whether the document had the tag.
+pub async fn remove_tag(
+    executor: impl PgExecutor<'_>,
+    document_id: Uuid,
+    name: &str,
+) -> Result<bool> {
+    let result = sqlx::query(
+        r#"
+        DELETE FROM document_tags
+        WHERE document_id = $1 AND LOWER(name) = LOWER($2)
+        "#,
+    )
+    .bind(document_id)
+    .bind(name)
+    .execute(executor)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+pub async fn get_tags(executor: impl PgExecutor<'_>, document_id: Uuid) -> Result<Vec<String>> {
+    let tags = sqlx::query_scalar::<_, String>(
+        r#"
+        SELECT name
+        FROM document_tags
+        WHERE document_id = $1
+        ORDER BY LOWER(name)
+        "#,
+    )
+    .bind(document_id)
+    .fetch_all(executor)
+    .await?;
+
+    Ok(tags)
+}
+
+/// The distinct tags on a user's documents outside the trash. Tags differing
+/// only in case are counted together, under the spelling used first.
+pub async fn get_tag_usage(pool: &PgPool, owner_id: Uuid) -> Result<Vec<TagUsage>> {
+    let usage = sqlx::query_as::<_, TagUsage>(
+        r#"
+        SELECT (ARRAY_AGG(t.name ORDER BY t.created_at, t.name))[1] AS name,
+               COUNT(*) AS document_count
+        FROM document_tags t
+        JOIN documents d ON d.id = t.document_id
+        WHERE d.owner_id = $1 AND d.deleted_at IS NULL
+        GROUP BY LOWER(t.name)
+        ORDER BY LOWER(t.name)
+        "#,
+    )
+    .bind(owner_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(usage)
+}
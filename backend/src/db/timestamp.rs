@@ -0,0 +1,163 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::models::timestamp::{DocumentTimestamp, TimestampSubject};
+
+pub async fn create_timestamp(
+    pool: &PgPool,
+    document_id: Uuid,
+    subject: TimestampSubject,
+    hashed_value: &str,
+    tsa_url: &str,
+) -> Result<DocumentTimestamp> {
+    let timestamp = sqlx::query_as::<_, DocumentTimestamp>(
+        r#"
+        INSERT INTO document_timestamps (document_id, subject, hashed_value, tsa_url, next_attempt_at)
+        VALUES ($1, $2, $3, $4, NOW())
+        RETURNING id, document_id, subject, hashed_value, tsa_url, token, gen_time, serial_number,
+                  attempts, last_error, next_attempt_at, created_at
+        "#,
+    )
+    .bind(document_id)
+    .bind(subject)
+    .bind(hashed_value)
+    .bind(tsa_url)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(timestamp)
+}
+
+pub async fn get_timestamp_by_id(pool: &PgPool, id: Uuid) -> Result<Option<DocumentTimestamp>> {
+    let timestamp = sqlx::query_as::<_, DocumentTimestamp>(
+        r#"
+        SELECT id, document_id, subject, hashed_value, tsa_url, token, gen_time, serial_number,
+               attempts, last_error, next_attempt_at, created_at
+        FROM document_timestamps
+        WHERE id = $1
+        "#,
+    )
+    .bind(id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(timestamp)
+}
+
+pub async fn get_timestamps_by_document(
+    pool: &PgPool,
+    document_id: Uuid,
+) -> Result<Vec<DocumentTimestamp>> {
+    let timestamps = sqlx::query_as::<_, DocumentTimestamp>(
+        r#"
+        SELECT id, document_id, subject, hashed_value, tsa_url, token, gen_time, serial_number,
+               attempts, last_error, next_attempt_at, created_at
+        FROM document_timestamps
+        WHERE document_id = $1
+        ORDER BY created_at
+        "#,
+    )
+    .bind(document_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(timestamps)
+}
+
+/// The most recent granted timestamp of `hashed_value`.
+pub async fn get_granted_timestamp(
+    pool: &PgPool,
+    document_id: Uuid,
+    subject: TimestampSubject,
+    hashed_value: &str,
+) -> Result<Option<DocumentTimestamp>> {
+    let timestamp = sqlx::query_as::<_, DocumentTimestamp>(
+        r#"
+        SELECT id, document_id, subject, hashed_value, tsa_url, token, gen_time, serial_number,
+               attempts, last_error, next_attempt_at, created_at
+        FROM document_timestamps
+        WHERE document_id = $1 AND subject = $2 AND hashed_value = $3 AND token IS NOT NULL
+        ORDER BY gen_time DESC
+        LIMIT 1
+        "#,
+    )
+    .bind(document_id)
+    .bind(subject)
+    .bind(hashed_value)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(timestamp)
+}
+
+pub async fn get_due_timestamps(pool: &PgPool, limit: i64) -> Result<Vec<DocumentTimestamp>> {
+    let timestamps = sqlx::query_as::<_, DocumentTimestamp>(
+        r#"
+        SELECT id, document_id, subject, hashed_value, tsa_url, token, gen_time, serial_number,
+               attempts, last_error, next_attempt_at, created_at
+        FROM document_timestamps
+        WHERE token IS NULL AND next_attempt_at IS NOT NULL AND next_attempt_at <= NOW()
+        ORDER BY next_attempt_at
+        LIMIT $1
+        "#,
+    )
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(timestamps)
+}
+
+pub async fn set_token(
+    pool: &PgPool,
+    id: Uuid,
+    token: &[u8],
+    gen_time: DateTime<Utc>,
+    serial_number: &str,
+) -> Result<DocumentTimestamp> {
+    let timestamp = sqlx::query_as::<_, DocumentTimestamp>(
+        r#"
+        UPDATE document_timestamps
+        SET token = $1, gen_time = $2, serial_number = $3, attempts = attempts + 1,
+            last_error = NULL, next_attempt_at = NULL
+        WHERE id = $4
+        RETURNING id, document_id, subject, hashed_value, tsa_url, token, gen_time, serial_number,
+                  attempts, last_error, next_attempt_at, created_at
+        "#,
+    )
+    .bind(token)
+    .bind(gen_time)
+    .bind(serial_number)
+    .bind(id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(timestamp)
+}
+
+/// Records a failed attempt. A `next_attempt_at` of `None` gives up.
+pub async fn record_failure(
+    pool: &PgPool,
+    id: Uuid,
+    error: &str,
+    next_attempt_at: Option<DateTime<Utc>>,
+) -> Result<DocumentTimestamp> {
+    let timestamp = sqlx::query_as::<_, DocumentTimestamp>(
+        r#"
+        UPDATE document_timestamps
+        SET attempts = attempts + 1, last_error = $1, next_attempt_at = $2
+        WHERE id = $3
+        RETURNING id, document_id, subject, hashed_value, tsa_url, token, gen_time, serial_number,
+                  attempts, last_error, next_attempt_at, created_at
+        "#,
+    )
+    .bind(error)
+    .bind(next_attempt_at)
+    .bind(id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(timestamp)
+}
@@ -0,0 +1,179 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use sqlx::{PgExecutor, PgPool};
+use uuid::Uuid;
+
+use crate::models::two_factor::UserTwoFactor;
+
+pub async fn get_two_factor(
+    executor: impl PgExecutor<'_>,
+    user_id: Uuid,
+) -> Result<Option<UserTwoFactor>> {
+    let two_factor = sqlx::query_as::<_, UserTwoFactor>(
+        r#"
+        SELECT user_id, secret_encrypted, pending_secret_encrypted, enabled_at, last_used_step,
+               failed_attempts, locked_until, created_at
+        FROM user_two_factor
+        WHERE user_id = $1
+        "#,
+    )
+    .bind(user_id)
+    .fetch_optional(executor)
+    .await?;
+
+    Ok(two_factor)
+}
+
+/// Stores a secret being set up, replacing any earlier one that was never
+/// confirmed.
+pub async fn set_pending_secret(pool: &PgPool, user_id: Uuid, encrypted: &str) -> Result<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO user_two_factor (user_id, pending_secret_encrypted)
+        VALUES ($1, $2)
+        ON CONFLICT (user_id) DO UPDATE SET pending_secret_encrypted = EXCLUDED.pending_secret_encrypted
+        "#,
+    )
+    .bind(user_id)
+    .bind(encrypted)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Makes the pending secret the active one. `step` is that of the code that
+/// confirmed it, so the code cannot be used again. Returns `false` if there
+/// was no pending secret or it was enabled concurrently.
+pub async fn enable_two_factor(
+    executor: impl PgExecutor<'_>,
+    user_id: Uuid,
+    step: i64,
+) -> Result<bool> {
+    let result = sqlx::query(
+        r#"
+        UPDATE user_two_factor
+        SET secret_encrypted = pending_secret_encrypted, pending_secret_encrypted = NULL,
+            enabled_at = NOW(), last_used_step = $2, failed_attempts = 0, locked_until = NULL
+        WHERE user_id = $1 AND enabled_at IS NULL AND pending_secret_encrypted IS NOT NULL
+        "#,
+    )
+    .bind(user_id)
+    .bind(step)
+    .execute(executor)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+pub async fn disable_two_factor(pool: &PgPool, user_id: Uuid) -> Result<()> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query("DELETE FROM recovery_codes WHERE user_id = $1")
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query("DELETE FROM user_two_factor WHERE user_id = $1")
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(())
+}
+
+/// Records an accepted second factor. With a `step` the code is only accepted
+/// if no code of that or a later step was, so each code works once; returns
+/// `false` if one was.
+pub async fn record_success(pool: &PgPool, user_id: Uuid, step: Option<i64>) -> Result<bool> {
+    let result = sqlx::query(
+        r#"
+        UPDATE user_two_factor
+        SET last_used_step = COALESCE($2, last_used_step), failed_attempts = 0
+        WHERE user_id = $1
+          AND ($2::BIGINT IS NULL OR last_used_step IS NULL OR last_used_step < $2)
+        "#,
+    )
+    .bind(user_id)
+    .bind(step)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Counts a wrong code, locking the second factor once `max_attempts` is
+/// reached.
+pub async fn record_failure(
+    pool: &PgPool,
+    user_id: Uuid,
+    max_attempts: i64,
+    locked_until: DateTime<Utc>,
+) -> Result<()> {
+    sqlx::query(
+        r#"
+        UPDATE user_two_factor
+        SET failed_attempts = CASE WHEN failed_attempts + 1 >= $2 THEN 0 ELSE failed_attempts + 1 END,
+            locked_until = CASE WHEN failed_attempts + 1 >= $2 THEN $3 ELSE locked_until END
+        WHERE user_id = $1
+        "#,
+    )
+    .bind(user_id)
+    .bind(max_attempts)
+    .bind(locked_until)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn replace_recovery_codes(
+    executor: impl PgExecutor<'_>,
+    user_id: Uuid,
+    code_hashes: &[String],
+) -> Result<()> {
+    sqlx::query(
+        r#"
+        WITH removed AS (DELETE FROM recovery_codes WHERE user_id = $1)
+        INSERT INTO recovery_codes (user_id, code_hash)
+        SELECT $1, UNNEST($2::VARCHAR[])
+        "#,
+    )
+    .bind(user_id)
+    .bind(code_hashes)
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+/// Marks a recovery code used. Returns `false` if the user has no such unused
+/// code.
+pub async fn use_recovery_code(pool: &PgPool, user_id: Uuid, code_hash: &str) -> Result<bool> {
+    let result = sqlx::query(
+        r#"
+        UPDATE recovery_codes
+        SET used_at = NOW()
+        WHERE user_id = $1 AND code_hash = $2 AND used_at IS NULL
+        "#,
+    )
+    .bind(user_id)
+    .bind(code_hash)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+pub async fn count_unused_recovery_codes(pool: &PgPool, user_id: Uuid) -> Result<i64> {
+    let count: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM recovery_codes WHERE user_id = $1 AND used_at IS NULL",
+    )
+    .bind(user_id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(count)
+}
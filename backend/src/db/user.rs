@@ -1,27 +1,28 @@
 use anyhow::Result;
-use sqlx::PgPool;
+use sqlx::{PgExecutor, PgPool};
 use uuid::Uuid;
 
-use crate::models::user::User;
+use crate::models::user::{Role, User};
 
 pub async fn create_user(
     pool: &PgPool,
     email: &str,
     password_hash: &str,
     name: &str,
-    is_admin: bool,
+    role: Role,
 ) -> Result<User> {
     let user = sqlx::query_as::<_, User>(
         r#"
-        INSERT INTO users (email, password_hash, name, is_admin)
+        INSERT INTO users (email, password_hash, name, role)
         VALUES ($1, $2, $3, $4)
-        RETURNING id, email, password_hash, name, is_admin, created_at, updated_at
+        RETURNING id, email, password_hash, name, role, display_timezone, reply_to_enabled,
+                  created_at, updated_at
         "#,
     )
     .bind(email)
     .bind(password_hash)
     .bind(name)
-    .bind(is_admin)
+    .bind(role)
     .fetch_one(pool)
     .await?;
 
@@ -31,7 +32,8 @@ pub async fn create_user(
 pub async fn get_user_by_email(pool: &PgPool, email: &str) -> Result<Option<User>> {
     let user = sqlx::query_as::<_, User>(
         r#"
-        SELECT id, email, password_hash, name, is_admin, created_at, updated_at
+        SELECT id, email, password_hash, name, role, display_timezone, reply_to_enabled,
+               created_at, updated_at
         FROM users
         WHERE email = $1
         "#,
@@ -43,16 +45,17 @@ pub async fn get_user_by_email(pool: &PgPool, email: &str) -> Result<Option<User
     Ok(user)
 }
 
-pub async fn get_user_by_id(pool: &PgPool, id: Uuid) -> Result<Option<User>> {
+pub async fn get_user_by_id(executor: impl PgExecutor<'_>, id: Uuid) -> Result<Option<User>> {
     let user = sqlx::query_as::<_, User>(
         r#"
-        SELECT id, email, password_hash, name, is_admin, created_at, updated_at
+        SELECT id, email, password_hash, name, role, display_timezone, reply_to_enabled,
+               created_at, updated_at
         FROM users
         WHERE id = $1
         "#,
     )
     .bind(id)
-    .fetch_optional(pool)
+    .fetch_optional(executor)
     .await?;
 
     Ok(user)
@@ -74,10 +77,193 @@ pub async fn update_user_password(pool: &PgPool, id: Uuid, password_hash: &str)
     Ok(())
 }
 
+/// Returns `false` if another account already uses `email`.
+pub async fn update_user_email(
+    executor: impl PgExecutor<'_>,
+    id: Uuid,
+    email: &str,
+) -> Result<bool> {
+    let result = sqlx::query(
+        r#"
+        UPDATE users
+        SET email = $1
+        WHERE id = $2
+        "#,
+    )
+    .bind(email)
+    .bind(id)
+    .execute(executor)
+    .await;
+
+    match result {
+        Ok(_) => Ok(true),
+        Err(sqlx::Error::Database(e)) if e.is_unique_violation() => Ok(false),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Sets the name and password of an invited user accepting their invitation.
+pub async fn set_user_credentials(
+    executor: impl PgExecutor<'_>,
+    id: Uuid,
+    name: &str,
+    password_hash: &str,
+) -> Result<()> {
+    sqlx::query(
+        r#"
+        UPDATE users
+        SET name = $1, password_hash = $2, updated_at = NOW()
+        WHERE id = $3
+        "#,
+    )
+    .bind(name)
+    .bind(password_hash)
+    .bind(id)
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
 pub async fn count_admin_users(pool: &PgPool) -> Result<i64> {
-    let count: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM users WHERE is_admin = true")
+    let count: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM users WHERE role = 'admin'")
         .fetch_one(pool)
         .await?;
 
     Ok(count.0)
 }
+
+/// The ids of all admins, locked until the transaction ends so that
+/// concurrent role changes cannot leave no admin.
+pub async fn lock_admin_ids(executor: impl PgExecutor<'_>) -> Result<Vec<Uuid>> {
+    let ids = sqlx::query_scalar("SELECT id FROM users WHERE role = 'admin' FOR UPDATE")
+        .fetch_all(executor)
+        .await?;
+
+    Ok(ids)
+}
+
+pub async fn set_role(executor: impl PgExecutor<'_>, id: Uuid, role: Role) -> Result<Option<User>> {
+    let user = sqlx::query_as::<_, User>(
+        r#"
+        UPDATE users
+        SET role = $1, updated_at = NOW()
+        WHERE id = $2
+        RETURNING id, email, password_hash, name, role, display_timezone, reply_to_enabled,
+                  created_at, updated_at
+        "#,
+    )
+    .bind(role)
+    .bind(id)
+    .fetch_optional(executor)
+    .await?;
+
+    Ok(user)
+}
+
+/// Sets the timezone the user reads times in; `None` uses the server default.
+pub async fn set_display_timezone(
+    pool: &PgPool,
+    id: Uuid,
+    display_timezone: Option<&str>,
+) -> Result<Option<User>> {
+    let user = sqlx::query_as::<_, User>(
+        r#"
+        UPDATE users
+        SET display_timezone = $1, updated_at = NOW()
+        WHERE id = $2
+        RETURNING id, email, password_hash, name, role, display_timezone, reply_to_enabled,
+                  created_at, updated_at
+        "#,
+    )
+    .bind(display_timezone)
+    .bind(id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(user)
+}
+
+/// Sets whether emails to the user's signers set Reply-To to them.
+pub async fn set_reply_to_enabled(
+    pool: &PgPool,
+    id: Uuid,
+    reply_to_enabled: bool,
+) -> Result<Option<User>> {
+    let user = sqlx::query_as::<_, User>(
+        r#"
+        UPDATE users
+        SET reply_to_enabled = $1, updated_at = NOW()
+        WHERE id = $2
+        RETURNING id, email, password_hash, name, role, display_timezone, reply_to_enabled,
+                  created_at, updated_at
+        "#,
+    )
+    .bind(reply_to_enabled)
+    .bind(id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(user)
+}
+
+pub async fn get_admin_users(pool: &PgPool) -> Result<Vec<User>> {
+    let users = sqlx::query_as::<_, User>(
+        r#"
+        SELECT id, email, password_hash, name, role, display_timezone, reply_to_enabled,
+               created_at, updated_at
+        FROM users
+        WHERE role = 'admin'
+        ORDER BY created_at
+        "#,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(users)
+}
+
+/// The user's quota override in MB, and the bytes stored for and number of
+/// their documents outside the trash. `None` if the user does not exist.
+pub async fn get_storage_usage(
+    pool: &PgPool,
+    user_id: Uuid,
+) -> Result<Option<(Option<i64>, i64, i64)>> {
+    let usage = sqlx::query_as::<_, (Option<i64>, i64, i64)>(
+        r#"
+        SELECT u.storage_quota_mb,
+               COALESCE(SUM(COALESCE(d.file_size, 0) + COALESCE(d.sealed_file_size, 0)), 0)::BIGINT,
+               COUNT(d.id)
+        FROM users u
+        LEFT JOIN documents d ON d.owner_id = u.id AND d.deleted_at IS NULL
+        WHERE u.id = $1
+        GROUP BY u.id
+        "#,
+    )
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(usage)
+}
+
+/// Sets the user's quota override in MB; `None` goes back to the default.
+pub async fn set_storage_quota(
+    pool: &PgPool,
+    user_id: Uuid,
+    quota_mb: Option<i64>,
+) -> Result<bool> {
+    let result = sqlx::query(
+        r#"
+        UPDATE users
+        SET storage_quota_mb = $1
+        WHERE id = $2
+        "#,
+    )
+    .bind(quota_mb)
+    .bind(user_id)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
@@ -1,20 +1,26 @@
 use anyhow::Result;
 use axum::{
-    http::{header, Method},
-    Router,
+    http::{header, HeaderName, Method},
+    middleware, Router,
 };
 use sqlx::postgres::PgPoolOptions;
+use std::future::IntoFuture;
 use std::net::SocketAddr;
+use std::time::Duration;
 use tower_http::{
     cors::{Any, CorsLayer},
     trace::TraceLayer,
 };
-use tracing::info;
+use tracing::{info, warn};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 use signvault::api;
+use signvault::api::idempotency::{IDEMPOTENCY_KEY_HEADER, IDEMPOTENT_REPLAYED_HEADER};
+use signvault::api::middleware::request_id_middleware;
 use signvault::services;
 use signvault::services::config::Config;
+use signvault::services::server_key;
+use signvault::utils::request_id::REQUEST_ID_HEADER;
 
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -32,7 +38,8 @@ async fn main() -> Result<()> {
     info!("Starting SignVault server");
 
     let pool = PgPoolOptions::new()
-        .max_connections(10)
+        .max_connections(config.database_max_connections)
+        .acquire_timeout(Duration::from_secs(config.database_acquire_timeout_secs))
         .connect(&config.database_url)
         .await?;
 
@@ -44,10 +51,18 @@ async fn main() -> Result<()> {
     services::admin::ensure_admin_exists(&pool, &config).await?;
     info!("Admin account verified");
 
-    std::fs::create_dir_all(&config.storage_path)?;
-    info!("Storage directory initialized: {}", config.storage_path);
+    let app_state = api::state::AppState::new(pool.clone(), config.clone())?;
+    info!(
+        "Storage initialized: {} ({})",
+        app_state.storage.location(),
+        config.storage_backend
+    );
+    let shutdown = app_state.shutdown.clone();
 
-    let app_state = api::state::AppState::new(pool, config.clone());
+    app_state.scheduler.clone().spawn(app_state.clone());
+
+    services::quota::spawn_size_backfill(pool.clone(), app_state.storage.clone());
+    services::signature_store::spawn_image_migration(pool.clone(), app_state.storage.clone());
 
     let cors = CorsLayer::new()
         .allow_origin(Any)
@@ -59,11 +74,30 @@ async fn main() -> Result<()> {
             Method::DELETE,
             Method::OPTIONS,
         ])
-        .allow_headers([header::CONTENT_TYPE, header::AUTHORIZATION, header::ACCEPT]);
-
-    let app = Router::new()
-        .nest("/api", api::routes::create_routes(app_state))
+        .allow_headers([
+            header::CONTENT_TYPE,
+            header::AUTHORIZATION,
+            header::ACCEPT,
+            HeaderName::from_static(REQUEST_ID_HEADER),
+            HeaderName::from_static(IDEMPOTENCY_KEY_HEADER),
+            HeaderName::from_static(api::shared::PASSCODE_HEADER),
+        ])
+        .expose_headers([
+            HeaderName::from_static(REQUEST_ID_HEADER),
+            HeaderName::from_static(IDEMPOTENT_REPLAYED_HEADER),
+            HeaderName::from_static(server_key::KEY_ID_HEADER),
+            HeaderName::from_static(server_key::SIGNATURE_HEADER),
+        ]);
+
+    let mut app = Router::new()
+        .merge(api::routes::create_well_known_routes(app_state.clone()))
+        .nest("/api", api::routes::create_routes(app_state));
+    if config.api_docs_enabled {
+        app = app.merge(api::routes::create_docs_routes());
+    }
+    let app = app
         .layer(TraceLayer::new_for_http())
+        .layer(middleware::from_fn(request_id_middleware))
         .layer(cors);
 
     let addr: SocketAddr = format!("{}:{}", config.backend_host, config.backend_port)
@@ -73,7 +107,66 @@ async fn main() -> Result<()> {
     info!("Server listening on {}", addr);
 
     let listener = tokio::net::TcpListener::bind(addr).await?;
-    axum::serve(listener, app).await?;
+
+    tokio::spawn({
+        let shutdown = shutdown.clone();
+        async move {
+            shutdown_signal().await;
+            info!("Shutdown signal received, draining in-flight requests");
+            shutdown.cancel();
+        }
+    });
+
+    let server = axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .with_graceful_shutdown({
+        let shutdown = shutdown.clone();
+        async move { shutdown.cancelled().await }
+    });
+
+    let drain_timeout = Duration::from_secs(config.shutdown_drain_timeout_secs);
+
+    tokio::select! {
+        result = server.into_future() => result?,
+        _ = async {
+            shutdown.cancelled().await;
+            tokio::time::sleep(drain_timeout).await;
+        } => {
+            warn!(
+                "In-flight requests did not finish within {}s, shutting down anyway",
+                drain_timeout.as_secs()
+            );
+        }
+    }
+
+    pool.close().await;
+    info!("Server stopped");
 
     Ok(())
 }
+
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("Failed to install Ctrl+C handler");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("Failed to install SIGTERM handler")
+            .recv()
+            .await;
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+}
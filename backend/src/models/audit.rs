@@ -3,7 +3,12 @@ use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use uuid::Uuid;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+use crate::models::server_key::{ServerSignature, ServerSignatureVerification};
+use crate::models::signature::SignatureType;
+use crate::models::timestamp::DocumentTimestamp;
+use utoipa::ToSchema;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type, ToSchema)]
 #[sqlx(type_name = "audit_action", rename_all = "snake_case")]
 #[serde(rename_all = "snake_case")]
 pub enum AuditAction {
@@ -23,14 +28,50 @@ pub enum AuditAction {
     SignerViewed,
     SignerSigned,
     SignerDeclined,
+    SignerReset,
+    SignerReminded,
     SignatureApplied,
     CertificateGenerated,
+    SignerApproved,
+    InPersonSessionStarted,
+    VerificationCodeSent,
+    VerificationFailed,
+    SignerVerified,
+    VerificationStarted,
+    AccessCodeFailed,
+    SignerUnlocked,
+    SignerUpdated,
+    FileIntegrityViolation,
+    DocumentDeleted,
+    DocumentRestored,
+    LegalHoldChanged,
+    SignerAnonymized,
+    FormFieldsImported,
+    DocumentSealed,
+    DocumentTimestamped,
+    FileVerified,
+    DocumentUpdated,
+    DocumentRecalled,
+    DocumentAmended,
+    DocumentShared,
+    DocumentUnshared,
+    DocumentPackageDownloaded,
+    ShareLinkCreated,
+    ShareLinkRevoked,
+    ShareLinkAccessed,
+    ShareLinkPasscodeFailed,
+    OpenLinkCreated,
+    SignerClaimed,
+    SignerTokenRotated,
+    ConsentGiven,
 }
 
-#[derive(Debug, Clone, FromRow, Serialize)]
+#[derive(Debug, Clone, FromRow, Serialize, ToSchema)]
 pub struct AuditLog {
     pub id: Uuid,
     pub document_id: Uuid,
+    /// Position in the document's audit chain, from 1.
+    pub sequence: i64,
     pub signer_id: Option<Uuid>,
     pub user_id: Option<Uuid>,
     pub action: AuditAction,
@@ -40,9 +81,37 @@ pub struct AuditLog {
     pub entry_hash: String,
     pub previous_hash: Option<String>,
     pub created_at: DateTime<Utc>,
+    /// Set when personal data in the entry was erased after it was hashed.
+    pub anonymized_at: Option<DateTime<Utc>>,
+    #[sqlx(skip)]
+    pub device: Option<String>,
+}
+
+/// Result of checking a document's audit hash chain.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct AuditChainStatus {
+    pub intact: bool,
+    /// Entries whose contents no longer match what was hashed because
+    /// personal data was erased from them.
+    pub anonymized_entries: usize,
+    /// Sequence of the first entry that does not link to the one before it,
+    /// or that follows a missing entry.
+    pub first_broken_sequence: Option<i64>,
+}
+
+/// A document's audit trail as downloaded from `/documents/:id/audit/export`,
+/// signed with the server key.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AuditExport {
+    pub document_id: Uuid,
+    pub document_title: String,
+    pub document_hash: String,
+    pub exported_at: DateTime<Utc>,
+    pub chain: AuditChainStatus,
+    pub entries: Vec<AuditLog>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct AuditLogPublic {
     pub id: Uuid,
     pub action: AuditAction,
@@ -53,7 +122,7 @@ pub struct AuditLogPublic {
     pub created_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct Certificate {
     pub document_id: Uuid,
     pub document_title: String,
@@ -61,25 +130,164 @@ pub struct Certificate {
     pub created_at: DateTime<Utc>,
     pub completed_at: DateTime<Utc>,
     pub signers: Vec<CertificateSigner>,
+    pub approvals: Vec<CertificateApproval>,
     pub audit_trail: Vec<CertificateAuditEntry>,
+    /// Set once the document has been sealed with the server certificate.
+    pub seal: Option<CertificateSeal>,
+    /// Time a timestamp authority attested for `document_hash`.
+    pub document_timestamp: Option<CertificateTimestamp>,
     pub certificate_hash: String,
+    /// Server key signature over the `certificate_hash` text.
+    pub server_signature: ServerSignature,
     pub generated_at: DateTime<Utc>,
+    /// Time a timestamp authority attested for `certificate_hash`. Obtained
+    /// after the hash is computed, so it is not part of it.
+    pub certificate_timestamp: Option<CertificateTimestamp>,
+    /// The times above in the reader's display timezone. Not part of
+    /// `certificate_hash`: the UTC instants are the record.
+    pub local_times: CertificateLocalTimes,
+}
+
+/// A certificate's times formatted in one timezone with its abbreviation and
+/// UTC offset, e.g. `2024-11-03 01:30:00 EST (UTC-05:00)`.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct CertificateLocalTimes {
+    /// IANA name of the timezone, e.g. `America/New_York`.
+    pub timezone: String,
+    pub created_at: String,
+    pub completed_at: String,
+    pub generated_at: String,
+    pub sealed_at: Option<String>,
+    /// `signed_at` of each signer, in the order of `signers`.
+    pub signers: Vec<String>,
+    /// `approved_at` of each approval, in the order of `approvals`.
+    pub approvals: Vec<String>,
+    /// `timestamp` of each entry, in the order of `audit_trail`.
+    pub audit_trail: Vec<String>,
+}
+
+/// Approximate location of an IP address, resolved when the action happened.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct GeoLocation {
+    pub city: Option<String>,
+    pub country: Option<String>,
+    pub country_code: Option<String>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct CertificateSigner {
     pub name: String,
     pub email: String,
     pub signed_at: DateTime<Utc>,
     pub ip_address: String,
     pub signature_hash: String,
+    pub signature_type: SignatureType,
+    pub typed_text: Option<String>,
+    pub location: Option<GeoLocation>,
+    pub device: Option<String>,
+    pub verified_at: Option<DateTime<Utc>>,
+    pub verification_method: Option<String>,
+    pub verification_outcome: Option<String>,
+    /// When they agreed to do business electronically, and the version of
+    /// the consent text they agreed to.
+    pub consented_at: Option<DateTime<Utc>>,
+    pub consent_version: Option<String>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct CertificateApproval {
+    pub name: String,
+    pub email: String,
+    pub approved_at: DateTime<Utc>,
+    pub ip_address: String,
+    pub verified_at: Option<DateTime<Utc>>,
+    pub verification_method: Option<String>,
+    pub verification_outcome: Option<String>,
+    /// When they agreed to do business electronically, and the version of
+    /// the consent text they agreed to.
+    pub consented_at: Option<DateTime<Utc>>,
+    pub consent_version: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct CertificateSeal {
+    pub sealed_at: DateTime<Utc>,
+    pub sealed_file_hash: String,
+    pub signer: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct CertificateTimestamp {
+    pub timestamp_id: Uuid,
+    pub tsa_url: String,
+    pub gen_time: DateTime<Utc>,
+    pub serial_number: Option<String>,
+}
+
+impl CertificateTimestamp {
+    /// `None` until the authority has granted the timestamp.
+    pub fn from_granted(timestamp: &DocumentTimestamp) -> Option<Self> {
+        timestamp.token.as_ref()?;
+        Some(Self {
+            timestamp_id: timestamp.id,
+            tsa_url: timestamp.tsa_url.clone(),
+            gen_time: timestamp.gen_time?,
+            serial_number: timestamp.serial_number.clone(),
+        })
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct CertificateAuditEntry {
+    pub action: String,
+    pub actor: Option<String>,
+    pub timestamp: DateTime<Utc>,
+    pub ip_address: Option<String>,
+    pub device: Option<String>,
+    pub details: Option<String>,
+    /// Readable before and after values of the fields an edit changed.
+    pub changes: Option<String>,
+}
+
+/// A certificate as issued before certificate hashes were versioned. Its
+/// hash covers the serde_json output of `signers` and `audit_trail`, so these
+/// structs keep the fields, and their order, of the time.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LegacyCertificate {
+    pub document_id: Uuid,
+    pub document_title: String,
+    pub document_hash: String,
+    pub created_at: DateTime<Utc>,
+    pub completed_at: DateTime<Utc>,
+    pub signers: Vec<LegacyCertificateSigner>,
+    pub audit_trail: Vec<LegacyCertificateAuditEntry>,
+    pub certificate_hash: String,
+    pub generated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LegacyCertificateSigner {
+    pub name: String,
+    pub email: String,
+    pub signed_at: DateTime<Utc>,
+    pub ip_address: String,
+    pub signature_hash: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LegacyCertificateAuditEntry {
     pub action: String,
     pub actor: Option<String>,
     pub timestamp: DateTime<Utc>,
     pub ip_address: Option<String>,
     pub details: Option<String>,
 }
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CertificateVerification {
+    /// Whether `certificate_hash` matches the certificate's contents.
+    pub hash_valid: bool,
+    /// The server signature over `certificate_hash`. Certificates issued
+    /// before they were signed have none.
+    pub signature: Option<ServerSignatureVerification>,
+}
@@ -0,0 +1,47 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::FromRow;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+pub const FAILURE_UNKNOWN_EMAIL: &str = "unknown_email";
+pub const FAILURE_WRONG_PASSWORD: &str = "wrong_password";
+pub const FAILURE_WRONG_TWO_FACTOR_CODE: &str = "wrong_two_factor_code";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, sqlx::Type, ToSchema)]
+#[sqlx(type_name = "auth_event_type", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum AuthEventType {
+    /// A login that issued a bearer token.
+    LoginSucceeded,
+    LoginFailed,
+    /// A confirmation link was sent to a new address; `email` is that
+    /// address.
+    EmailChangeRequested,
+    /// The new address was confirmed and is now the login email.
+    EmailChanged,
+    /// The password was right and a second factor was asked for; a
+    /// `login_succeeded` event follows once it is given.
+    TwoFactorRequired,
+    TwoFactorEnabled,
+    TwoFactorDisabled,
+    /// A recovery code was used in place of an authenticator code.
+    RecoveryCodeUsed,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, ToSchema)]
+pub struct AuthEvent {
+    pub id: Uuid,
+    pub user_id: Option<Uuid>,
+    pub event_type: AuthEventType,
+    /// The email that was given.
+    pub email: String,
+    /// Why a login failed; only shown to admins, as it tells whether the
+    /// email belongs to an account.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub failure_reason: Option<String>,
+    pub token_expires_at: Option<DateTime<Utc>>,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
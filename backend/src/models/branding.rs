@@ -0,0 +1,64 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::ToSchema;
+use uuid::Uuid;
+use validator::Validate;
+
+/// An owner's branding as stored. Any part left unset falls back to the
+/// default SignVault styling.
+#[derive(Debug, Clone, FromRow)]
+pub struct UserBranding {
+    pub user_id: Uuid,
+    pub company_name: Option<String>,
+    /// `#rrggbb`.
+    pub primary_color: Option<String>,
+    pub logo_storage_key: Option<String>,
+    pub logo_content_type: Option<String>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl UserBranding {
+    /// Where the logo is served from, changing whenever the branding is
+    /// saved so clients never show a replaced logo from their cache.
+    pub fn logo_url(&self, public_url: &str) -> Option<String> {
+        self.logo_storage_key.as_ref().map(|_| {
+            format!(
+                "{}/api/branding/{}/logo?v={}",
+                public_url,
+                self.user_id,
+                self.updated_at.timestamp()
+            )
+        })
+    }
+
+    pub fn public(&self, public_url: &str) -> Branding {
+        Branding {
+            company_name: self.company_name.clone(),
+            primary_color: self.primary_color.clone(),
+            logo_url: self.logo_url(public_url),
+        }
+    }
+}
+
+/// Branding as shown to owners and signers.
+#[derive(Debug, Clone, Default, Serialize, ToSchema)]
+pub struct Branding {
+    pub company_name: Option<String>,
+    pub primary_color: Option<String>,
+    /// Public URL of the PNG or JPEG logo.
+    pub logo_url: Option<String>,
+}
+
+/// Replaces the current user's branding. Unset `company_name` and
+/// `primary_color` are cleared; the logo is kept unless `logo` is given.
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct UpdateBrandingRequest {
+    #[validate(length(max = 100, message = "Company name must be at most 100 characters"))]
+    pub company_name: Option<String>,
+    /// `#rrggbb`, e.g. `#0f766e`.
+    pub primary_color: Option<String>,
+    /// Base64 PNG or JPEG data URL of a new logo, or an empty string to
+    /// remove it.
+    pub logo: Option<String>,
+}
@@ -1,10 +1,15 @@
 use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
+use sqlx::types::Json;
 use sqlx::FromRow;
+use utoipa::ToSchema;
 use uuid::Uuid;
 use validator::Validate;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+use crate::models::encryption::{FileEncryption, WrappedDataKey};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type, ToSchema)]
 #[sqlx(type_name = "document_status", rename_all = "snake_case")]
 #[serde(rename_all = "snake_case")]
 pub enum DocumentStatus {
@@ -15,7 +20,7 @@ pub enum DocumentStatus {
     Expired,
 }
 
-#[derive(Debug, Clone, FromRow, Serialize)]
+#[derive(Debug, Clone, FromRow, Serialize, ToSchema)]
 pub struct Document {
     pub id: Uuid,
     pub owner_id: Uuid,
@@ -25,15 +30,106 @@ pub struct Document {
     pub file_hash: String,
     pub status: DocumentStatus,
     pub self_sign_only: bool,
+    pub verification_method: Option<String>,
     pub total_signers: i32,
     pub completed_signers: i32,
     pub expires_at: Option<DateTime<Utc>>,
     pub completed_at: Option<DateTime<Utc>>,
+    pub deleted_at: Option<DateTime<Utc>>,
+    pub legal_hold: bool,
+    /// Cached at upload; `None` for documents uploaded before it was.
+    pub page_count: Option<i32>,
+    #[serde(skip_serializing)]
+    pub page_sizes: Option<Json<Vec<DocumentPage>>>,
+    /// Storage key of the copy sealed with the server certificate, if any.
+    #[serde(skip_serializing)]
+    pub sealed_file_path: Option<String>,
+    pub sealed_file_hash: Option<String>,
+    pub sealed_at: Option<DateTime<Utc>>,
+    /// Subject of the certificate the document was sealed with.
+    pub seal_signer: Option<String>,
+    /// Why the owner voided the document.
+    pub void_reason: Option<String>,
+    /// Days between automatic reminders to signers who have not finished;
+    /// reminders are off unless both this and `max_reminders` are set.
+    pub reminder_interval_days: Option<i32>,
+    pub max_reminders: Option<i32>,
+    /// Whether signers can download the completed document from their
+    /// status page.
+    pub signer_download_enabled: bool,
+    /// Milestones at which the owner is emailed about signing progress.
+    #[schema(value_type = Vec<ProgressNotification>)]
+    pub progress_notifications: Json<Vec<ProgressNotification>>,
+    /// Language of emails to signers who have none of their own.
+    pub locale: Option<String>,
+    /// Master key the document's data key is wrapped with; `None` for
+    /// documents whose files are stored in plaintext.
+    #[serde(skip_serializing)]
+    pub encryption_key_id: Option<String>,
+    #[serde(skip_serializing)]
+    pub wrapped_data_key: Option<Vec<u8>>,
+    /// Nonces the original and sealed files were encrypted with; `None` for
+    /// files stored in plaintext.
+    #[serde(skip_serializing)]
+    pub file_nonce: Option<Vec<u8>>,
+    #[serde(skip_serializing)]
+    pub sealed_file_nonce: Option<Vec<u8>>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Deserialize, Validate)]
+impl Document {
+    /// The key the document's files are encrypted with, if they are.
+    pub fn data_key(&self) -> Option<WrappedDataKey> {
+        match (&self.encryption_key_id, &self.wrapped_data_key) {
+            (Some(key_id), Some(wrapped_key)) => Some(WrappedDataKey {
+                key_id: key_id.clone(),
+                wrapped_key: wrapped_key.clone(),
+            }),
+            _ => None,
+        }
+    }
+
+    /// How a file of this document encrypted with `nonce` is decrypted;
+    /// `None` when it is stored in plaintext.
+    pub fn file_encryption(&self, nonce: Option<&[u8]>) -> Option<FileEncryption> {
+        Some(FileEncryption {
+            data_key: self.data_key()?,
+            nonce: nonce?.to_vec(),
+        })
+    }
+
+    pub fn original_encryption(&self) -> Option<FileEncryption> {
+        self.file_encryption(self.file_nonce.as_deref())
+    }
+
+    pub fn sealed_encryption(&self) -> Option<FileEncryption> {
+        self.file_encryption(self.sealed_file_nonce.as_deref())
+    }
+}
+
+/// When the owner is emailed about signing progress. Completion always sends
+/// its own notice, so these only fire while someone is still outstanding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ProgressNotification {
+    /// After every signature or approval.
+    EverySignature,
+    /// When only one signer is left.
+    OneRemaining,
+}
+
+/// A newly uploaded document.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct UploadedDocument {
+    #[serde(flatten)]
+    pub document: Document,
+    /// One of the owner's documents with an identical file, if there is one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duplicate_of: Option<Uuid>,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
 pub struct CreateDocumentRequest {
     #[validate(length(
         min = 1,
@@ -45,7 +141,7 @@ pub struct CreateDocumentRequest {
     pub expires_at: Option<DateTime<Utc>>,
 }
 
-#[derive(Debug, Deserialize, Validate)]
+#[derive(Debug, Deserialize, Validate, ToSchema)]
 pub struct UpdateDocumentRequest {
     #[validate(length(
         min = 1,
@@ -54,9 +150,22 @@ pub struct UpdateDocumentRequest {
     ))]
     pub title: Option<String>,
     pub expires_at: Option<DateTime<Utc>>,
+    /// 0 turns automatic reminders off.
+    pub reminder_interval_days: Option<i32>,
+    pub max_reminders: Option<i32>,
+    pub signer_download_enabled: Option<bool>,
+    /// Replaces the progress milestones; an empty list turns them off.
+    pub progress_notifications: Option<Vec<ProgressNotification>>,
+    /// Language of emails to signers who have none of their own, e.g. `fr`;
+    /// an empty string falls back to the server default.
+    #[validate(length(max = 35, message = "Locale must be at most 35 characters"))]
+    pub locale: Option<String>,
+    /// Only apply the update if the document is unchanged since it was read,
+    /// like `If-Match`.
+    pub expected_updated_at: Option<DateTime<Utc>>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct DocumentField {
     pub id: Uuid,
     pub field_type: FieldType,
@@ -72,7 +181,7 @@ pub struct DocumentField {
     pub date_format: Option<String>,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type, ToSchema)]
 #[sqlx(type_name = "field_type", rename_all = "snake_case")]
 #[serde(rename_all = "snake_case")]
 pub enum FieldType {
@@ -80,9 +189,12 @@ pub enum FieldType {
     Date,
     Text,
     Initial,
+    Checkbox,
+    Number,
+    Radio,
 }
 
-#[derive(Debug, FromRow, Serialize)]
+#[derive(Debug, Clone, FromRow, Serialize, ToSchema)]
 pub struct DocumentFieldRow {
     pub id: Uuid,
     pub document_id: Uuid,
@@ -97,11 +209,229 @@ pub struct DocumentFieldRow {
     pub font_size: Option<i32>,
     pub font_family: Option<String>,
     pub date_format: Option<String>,
+    pub norm_x: Option<f64>,
+    pub norm_y: Option<f64>,
+    pub norm_width: Option<f64>,
+    pub norm_height: Option<f64>,
+    /// The signer must fill the field in, unless its condition hides it.
+    pub required: bool,
+    /// The field is only shown while this field holds `condition_value`.
+    pub condition_field_id: Option<Uuid>,
+    pub condition_value: Option<String>,
+    /// Names the field in messages to the signer.
+    pub label: Option<String>,
+    /// Bounds and precision of a number field.
+    pub min_value: Option<Decimal>,
+    pub max_value: Option<Decimal>,
+    pub decimal_places: Option<i32>,
+    /// Holds a value set by the owner that signers cannot change.
+    pub read_only: bool,
+    /// The choice a radio field is an option of.
+    pub group_key: Option<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Deserialize, Validate)]
+/// How `x`, `y`, `width` and `height` are expressed in field requests. Both
+/// are measured from the top-left corner of the page as displayed, i.e. with
+/// its rotation applied.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CoordinateSpace {
+    /// Fractions (0-1) of the page width and height.
+    Normalized,
+    /// PDF points. Deprecated, assumed when a request does not say.
+    #[default]
+    Points,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FieldRect {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+/// A field's position in both coordinate spaces, as stored.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FieldGeometry {
+    pub points: FieldRect,
+    pub normalized: FieldRect,
+}
+
+impl FieldGeometry {
+    /// Positions `rect`, given in `space`, on `page`. Normalized rectangles
+    /// must lie within the page.
+    pub fn new(
+        rect: FieldRect,
+        space: CoordinateSpace,
+        page: &DocumentPage,
+    ) -> Result<Self, String> {
+        let FieldRect {
+            x,
+            y,
+            width,
+            height,
+        } = rect;
+
+        if ![x, y, width, height].iter().all(|v| v.is_finite()) || width <= 0.0 || height <= 0.0 {
+            return Err("Field width and height must be positive numbers".to_string());
+        }
+
+        if page.width <= 0.0 || page.height <= 0.0 {
+            return Err(format!("Page {} has no area", page.number));
+        }
+
+        // Slack for rounding in clients that derive fractions from pixels
+        const EPSILON: f64 = 1e-6;
+
+        match space {
+            CoordinateSpace::Normalized => {
+                if x < 0.0 || y < 0.0 || x + width > 1.0 + EPSILON || y + height > 1.0 + EPSILON {
+                    return Err(
+                        "Normalized coordinates must lie within the page (0 to 1)".to_string()
+                    );
+                }
+
+                Ok(Self {
+                    points: FieldRect {
+                        x: x * page.width,
+                        y: y * page.height,
+                        width: width * page.width,
+                        height: height * page.height,
+                    },
+                    normalized: rect,
+                })
+            }
+            CoordinateSpace::Points => Ok(Self {
+                points: rect,
+                normalized: FieldRect {
+                    x: x / page.width,
+                    y: y / page.height,
+                    width: width / page.width,
+                    height: height / page.height,
+                },
+            }),
+        }
+    }
+}
+
+impl DocumentFieldRow {
+    /// How messages to the signer refer to the field.
+    pub fn display_name(&self) -> String {
+        match self.label.as_deref().map(str::trim) {
+            Some(label) if !label.is_empty() => label.to_string(),
+            _ => format!("Field {}", self.id),
+        }
+    }
+
+    /// The field's current position in `space`.
+    pub fn rect(&self, space: CoordinateSpace, page: &DocumentPage) -> FieldRect {
+        let points = FieldRect {
+            x: self.x,
+            y: self.y,
+            width: self.width,
+            height: self.height,
+        };
+
+        match (
+            space,
+            self.norm_x,
+            self.norm_y,
+            self.norm_width,
+            self.norm_height,
+        ) {
+            (CoordinateSpace::Points, ..) => points,
+            (CoordinateSpace::Normalized, Some(x), Some(y), Some(width), Some(height)) => {
+                FieldRect {
+                    x,
+                    y,
+                    width,
+                    height,
+                }
+            }
+            (CoordinateSpace::Normalized, ..) => FieldRect {
+                x: points.x / page.width,
+                y: points.y / page.height,
+                width: points.width / page.width,
+                height: points.height / page.height,
+            },
+        }
+    }
+}
+
+/// An AcroForm field that could not be turned into a document field.
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct SkippedFormField {
+    pub name: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AcroFormImportResponse {
+    pub imported: Vec<DocumentFieldRow>,
+    /// Form fields imported by an earlier run, left as they are.
+    pub already_imported: Vec<String>,
+    pub skipped: Vec<SkippedFormField>,
+}
+
+#[derive(Debug, Default, Deserialize, Validate, ToSchema)]
+pub struct VoidDocumentRequest {
+    #[validate(length(max = 1000, message = "Reason must be at most 1000 characters"))]
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BulkDocumentAction {
+    Void,
+    Delete,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct BulkDocumentRequest {
+    pub action: BulkDocumentAction,
+    #[validate(length(min = 1, max = 100, message = "Between 1 and 100 ids can be given"))]
+    pub ids: Vec<Uuid>,
+    /// Why the documents are voided; only used with `void`.
+    #[validate(length(max = 1000, message = "Reason must be at most 1000 characters"))]
+    pub reason: Option<String>,
+}
+
+/// The outcome for one document of a bulk operation. Failures carry the
+/// error the single-document endpoint would have returned.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct BulkDocumentResult {
+    pub id: Uuid,
+    pub success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct BulkDocumentResponse {
+    pub succeeded: usize,
+    pub failed: usize,
+    /// In the order the ids were given.
+    pub results: Vec<BulkDocumentResult>,
+}
+
+#[derive(Debug, Default, Deserialize, ToSchema)]
+pub struct RecallDocumentRequest {
+    /// Email signers who already received the request that it was withdrawn.
+    #[serde(default)]
+    pub notify_signers: bool,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct SetLegalHoldRequest {
+    pub legal_hold: bool,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
 pub struct AddFieldRequest {
     pub field_type: FieldType,
     pub page: i32,
@@ -114,10 +444,34 @@ pub struct AddFieldRequest {
     pub font_size: Option<i32>,
     pub font_family: Option<String>,
     pub date_format: Option<String>,
+    #[serde(default)]
+    pub coordinate_space: CoordinateSpace,
+    #[serde(default)]
+    pub required: bool,
+    /// Show the field only while this text, date, number or checkbox field
+    /// holds `condition_value`; a checked checkbox holds `true`.
+    pub condition_field_id: Option<Uuid>,
+    pub condition_value: Option<String>,
+    pub label: Option<String>,
+    /// Inclusive bounds of a number field.
+    pub min_value: Option<Decimal>,
+    pub max_value: Option<Decimal>,
+    /// Most decimal places a number field accepts, and how many its value is
+    /// stored with.
+    pub decimal_places: Option<i32>,
+    /// Signers see `value` but cannot change it; requires a `value`.
+    #[serde(default)]
+    pub read_only: bool,
+    /// Required for radio fields: the radio fields sharing it are the options
+    /// of one choice, selected with `true`. The choice is required when any
+    /// of its options is.
+    pub group_key: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct UpdateFieldRequest {
+    #[serde(default)]
+    pub coordinate_space: CoordinateSpace,
     pub x: Option<f64>,
     pub y: Option<f64>,
     pub width: Option<f64>,
@@ -126,24 +480,98 @@ pub struct UpdateFieldRequest {
     pub font_size: Option<i32>,
     pub font_family: Option<String>,
     pub date_format: Option<String>,
+    pub required: Option<bool>,
+    /// Replaces the condition; given together with `condition_value`.
+    pub condition_field_id: Option<Uuid>,
+    /// An empty string removes the condition.
+    pub condition_value: Option<String>,
+    pub label: Option<String>,
+    pub min_value: Option<Decimal>,
+    pub max_value: Option<Decimal>,
+    pub decimal_places: Option<i32>,
+    /// Requires the field to have a `value`.
+    pub read_only: Option<bool>,
+    /// Moves a radio field to another choice.
+    pub group_key: Option<String>,
+    /// Only apply the update if the field is unchanged since it was read,
+    /// like `If-Match`.
+    pub expected_updated_at: Option<DateTime<Utc>>,
+}
+
+/// Displayed size of a page in PDF points, with its rotation applied.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DocumentPage {
+    pub number: i32,
+    pub width: f64,
+    pub height: f64,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct DocumentWithFields {
     #[serde(flatten)]
     pub document: Document,
     pub fields: Vec<DocumentFieldRow>,
     pub signers: Vec<super::signer::Signer>,
+    pub pages: Vec<DocumentPage>,
 }
 
-#[derive(Debug, Serialize)]
+/// A document in its owner's list, with what it is waiting for.
+#[derive(Debug, FromRow, Serialize, ToSchema)]
 pub struct DocumentListItem {
-    pub id: Uuid,
-    pub title: String,
-    pub status: DocumentStatus,
-    pub self_sign_only: bool,
-    pub total_signers: i32,
-    pub completed_signers: i32,
-    pub created_at: DateTime<Utc>,
-    pub updated_at: DateTime<Utc>,
+    #[serde(flatten)]
+    #[sqlx(flatten)]
+    pub document: Document,
+    /// The first signer, in signing order, who has not responded yet. Only
+    /// set while the document is pending.
+    pub waiting_on_name: Option<String>,
+    pub waiting_on_email: Option<String>,
+    /// When the latest audit entry was recorded.
+    pub last_activity_at: Option<DateTime<Utc>>,
+    pub viewed_signers: i64,
+    pub unviewed_signers: i64,
+    pub tags: Vec<String>,
+}
+
+/// A document found by a search, with the signers whose name or email
+/// matched.
+#[derive(Debug, FromRow)]
+pub struct DocumentSearchRow {
+    #[sqlx(flatten)]
+    pub document: Document,
+    pub rank: f32,
+    pub matched_signer_names: Vec<String>,
+    pub matched_signer_emails: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchField {
+    Title,
+    Filename,
+    SignerName,
+    SignerEmail,
+}
+
+/// A piece of a matched value; the pieces with `matched` set are the
+/// occurrences of the search term.
+#[derive(Debug, PartialEq, Eq, Serialize, ToSchema)]
+pub struct HighlightFragment {
+    pub text: String,
+    pub matched: bool,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SearchMatch {
+    pub field: SearchField,
+    pub value: String,
+    pub fragments: Vec<HighlightFragment>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct DocumentSearchResult {
+    #[serde(flatten)]
+    pub document: Document,
+    /// Higher is a better match; results come best first.
+    pub rank: f32,
+    pub matches: Vec<SearchMatch>,
 }
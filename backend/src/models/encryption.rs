@@ -0,0 +1,14 @@
+/// A document's data key, wrapped with the master key named `key_id`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WrappedDataKey {
+    pub key_id: String,
+    pub wrapped_key: Vec<u8>,
+}
+
+/// How a stored file was encrypted: with its document's data key and a nonce
+/// of its own.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileEncryption {
+    pub data_key: WrappedDataKey,
+    pub nonce: Vec<u8>,
+}
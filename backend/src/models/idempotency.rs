@@ -0,0 +1,30 @@
+use chrono::{DateTime, Utc};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+/// A request made with an `Idempotency-Key` header, and the response it got.
+#[derive(Debug, Clone, FromRow)]
+pub struct IdempotencyKey {
+    pub user_id: Uuid,
+    pub key: String,
+    /// Hex SHA-256 of the method, path and body of the request.
+    pub request_hash: String,
+    /// `None` while the request is being executed.
+    pub response_status: Option<i16>,
+    pub response_content_type: Option<String>,
+    pub response_body: Option<Vec<u8>>,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// The outcome of claiming an idempotency key.
+#[derive(Debug)]
+pub enum KeyClaim {
+    /// The key was free and is now held by the caller. `claimed_at` names
+    /// this claim, so that a request whose claim lapsed and was taken over
+    /// cannot store or release the claim of the request that took it.
+    Claimed { claimed_at: DateTime<Utc> },
+    /// The request that holds or held the key, with its response once it
+    /// finished.
+    Taken(IdempotencyKey),
+}
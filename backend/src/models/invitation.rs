@@ -0,0 +1,44 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::ToSchema;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::models::user::Role;
+
+/// An invitation to an account created by an admin. The account has no
+/// usable password until the invitation is accepted.
+#[derive(Debug, Clone, FromRow, Serialize, ToSchema)]
+pub struct Invitation {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub email: String,
+    #[serde(skip_serializing)]
+    pub token_hash: String,
+    pub invited_by: Option<Uuid>,
+    pub expires_at: DateTime<Utc>,
+    /// When the invitation was last emailed.
+    pub sent_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+    pub accepted_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct CreateInvitationRequest {
+    #[validate(email(message = "Invalid email address"))]
+    pub email: String,
+    /// Used to greet the invitee; they choose their own name when accepting.
+    pub name: Option<String>,
+    /// Defaults to `sender`.
+    #[serde(default)]
+    pub role: Role,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct AcceptInvitationRequest {
+    #[validate(length(min = 1, message = "Name is required"))]
+    pub name: String,
+    #[validate(length(min = 8, message = "Password must be at least 8 characters"))]
+    pub password: String,
+}
@@ -0,0 +1,34 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::FromRow;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, sqlx::Type, ToSchema)]
+#[sqlx(type_name = "job_run_status", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum JobRunStatus {
+    Running,
+    Succeeded,
+    Failed,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, ToSchema)]
+pub struct JobRun {
+    pub id: Uuid,
+    pub job_name: String,
+    pub status: JobRunStatus,
+    pub error: Option<String>,
+    /// The admin who ran the job by hand; `None` for scheduled runs.
+    pub triggered_by: Option<Uuid>,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+/// A registered background job and how its latest run went.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct JobSummary {
+    pub name: String,
+    pub interval_secs: u64,
+    pub last_run: Option<JobRun>,
+}
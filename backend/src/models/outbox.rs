@@ -0,0 +1,72 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type, ToSchema)]
+#[sqlx(type_name = "outbox_email_kind", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum OutboxEmailKind {
+    /// Invites a signer to sign a document that was just sent.
+    SigningRequest,
+    /// Tells a signer their link was withdrawn when a document is recalled.
+    RequestWithdrawn,
+    /// Tells a signer the document was voided, and why.
+    RequestCancelled,
+    /// Tells a signer they were taken off a document that is still pending.
+    SignerRemoved,
+    /// Tells a signer who has not finished that the deadline changed.
+    DeadlineChanged,
+    /// Reminds a signer who has not finished to sign.
+    SigningReminder,
+    /// Tells the owner, or a signer who signed, that the document is complete.
+    CompletionNotice,
+    /// Tells the owner who has signed and who is outstanding, when one of the
+    /// document's progress milestones is reached.
+    ProgressUpdate,
+}
+
+#[derive(Debug, Clone, FromRow)]
+pub struct OutboxEmail {
+    pub id: Uuid,
+    pub kind: OutboxEmailKind,
+    pub document_id: Uuid,
+    /// `None` for emails to the document owner.
+    pub signer_id: Option<Uuid>,
+    pub attempts: i32,
+    pub last_error: Option<String>,
+    /// When the next attempt is due; `None` once sent or given up on.
+    pub next_attempt_at: Option<DateTime<Utc>>,
+    pub sent_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type, ToSchema)]
+#[sqlx(type_name = "outbox_event_type", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum OutboxEventType {
+    /// The document left draft; the payload lists the `signer_ids` to invite.
+    DocumentSent,
+    /// A signer signed or an approver approved.
+    SignerSigned,
+    /// A signer declined or an approver rejected.
+    SignerDeclined,
+    /// The last participant finished.
+    DocumentCompleted,
+}
+
+#[derive(Debug, Clone, FromRow)]
+pub struct OutboxEvent {
+    pub id: Uuid,
+    pub event_type: OutboxEventType,
+    pub document_id: Uuid,
+    pub signer_id: Option<Uuid>,
+    pub payload: serde_json::Value,
+    pub attempts: i32,
+    pub last_error: Option<String>,
+    /// When the next attempt is due; `None` once processed or given up on.
+    pub next_attempt_at: Option<DateTime<Utc>>,
+    pub processed_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
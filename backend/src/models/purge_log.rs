@@ -0,0 +1,23 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::FromRow;
+use uuid::Uuid;
+
+use crate::models::document::DocumentStatus;
+use utoipa::ToSchema;
+
+pub const PURGE_REASON_RETENTION: &str = "retention";
+pub const PURGE_REASON_TRASH: &str = "trash";
+
+#[derive(Debug, Clone, FromRow, Serialize, ToSchema)]
+pub struct PurgeLogEntry {
+    pub id: Uuid,
+    pub document_id: Uuid,
+    pub owner_id: Uuid,
+    pub title: String,
+    pub status: DocumentStatus,
+    pub file_hash: String,
+    pub reason: String,
+    pub document_created_at: DateTime<Utc>,
+    pub purged_at: DateTime<Utc>,
+}
@@ -0,0 +1,67 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type, ToSchema)]
+#[sqlx(type_name = "security_event_type", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum SecurityEventType {
+    /// The stored file no longer matches the hash recorded at upload.
+    FileIntegrityViolation,
+    /// The audit trail's hash chain failed verification.
+    AuditChainBroken,
+}
+
+impl SecurityEventType {
+    /// What went wrong, as a sentence for alert emails.
+    pub fn description(self) -> &'static str {
+        match self {
+            Self::FileIntegrityViolation => {
+                "The stored file no longer matches the hash recorded when it was uploaded. It will not be served until this is resolved."
+            }
+            Self::AuditChainBroken => {
+                "The document's audit trail failed verification: an entry was altered, removed or inserted after it was written."
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type, ToSchema)]
+#[sqlx(type_name = "security_event_severity", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum SecurityEventSeverity {
+    Low,
+    Medium,
+    High,
+}
+
+/// What ran the check that failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type, ToSchema)]
+#[sqlx(type_name = "security_event_source", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum SecurityEventSource {
+    /// An API request: a download, an export or an admin check.
+    Request,
+    StorageScan,
+    /// Generating a certificate of completion.
+    Certificate,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, ToSchema)]
+pub struct SecurityEvent {
+    pub id: Uuid,
+    pub document_id: Uuid,
+    pub event_type: SecurityEventType,
+    pub severity: SecurityEventSeverity,
+    pub source: SecurityEventSource,
+    /// The hashes that differed, or the first broken sequence of the chain.
+    pub details: Option<serde_json::Value>,
+    /// How many times the failure was seen while the event was open.
+    pub occurrences: i32,
+    pub created_at: DateTime<Utc>,
+    pub last_seen_at: DateTime<Utc>,
+    pub acknowledged_at: Option<DateTime<Utc>>,
+    pub acknowledged_by: Option<Uuid>,
+}
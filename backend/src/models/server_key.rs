@@ -0,0 +1,48 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+pub const ED25519: &str = "Ed25519";
+
+/// An Ed25519 signature made with the server key, e.g. over a certificate
+/// hash. `key_id` names the key in `/.well-known/signvault-public-key`, so
+/// signatures made before a key rotation stay verifiable.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ServerSignature {
+    pub algorithm: String,
+    pub key_id: String,
+    pub key_fingerprint: String,
+    /// Base64 encoded.
+    pub signature: String,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ServerPublicKey {
+    pub key_id: String,
+    pub algorithm: &'static str,
+    pub fingerprint: String,
+    /// SubjectPublicKeyInfo PEM.
+    pub public_key: String,
+    /// False for retired keys, which only verify older signatures.
+    pub current: bool,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ServerPublicKeySet {
+    pub current_key_id: String,
+    pub keys: Vec<ServerPublicKey>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct VerifyServerSignatureRequest {
+    /// The signed text, e.g. a certificate hash.
+    pub message: String,
+    pub key_id: String,
+    pub signature: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ServerSignatureVerification {
+    pub valid: bool,
+    /// Set when `key_id` names a known key.
+    pub key: Option<ServerPublicKey>,
+}
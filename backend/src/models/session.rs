@@ -0,0 +1,23 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::FromRow;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// A login, identified in its bearer token. It stops working once revoked or
+/// expired.
+#[derive(Debug, Clone, FromRow, Serialize, ToSchema)]
+pub struct UserSession {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    #[serde(skip_serializing)]
+    pub revoked_at: Option<DateTime<Utc>>,
+    /// Whether this is the session of the request listing it.
+    #[sqlx(skip)]
+    pub current: bool,
+}
@@ -0,0 +1,45 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::models::document::Document;
+use utoipa::ToSchema;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type, ToSchema)]
+#[sqlx(type_name = "document_share_role", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum ShareRole {
+    /// Can read the document, its signers and its audit trail, and download it.
+    Viewer,
+    /// Can also change the fields and signers of a draft.
+    Editor,
+}
+
+/// A user other than the owner a document was shared with.
+#[derive(Debug, Clone, FromRow, Serialize, ToSchema)]
+pub struct DocumentShare {
+    pub document_id: Uuid,
+    pub user_id: Uuid,
+    pub email: String,
+    pub name: String,
+    pub role: ShareRole,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct ShareDocumentRequest {
+    #[validate(email)]
+    pub email: String,
+    pub role: ShareRole,
+}
+
+/// A document another user shared with the authenticated user.
+#[derive(Debug, Clone, FromRow, Serialize, ToSchema)]
+pub struct SharedDocument {
+    #[serde(flatten)]
+    #[sqlx(flatten)]
+    pub document: Document,
+    pub share_role: ShareRole,
+}
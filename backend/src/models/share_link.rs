@@ -0,0 +1,68 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::ToSchema;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::models::document::DocumentStatus;
+
+/// Longest a share link can stay valid: one year.
+pub const MAX_SHARE_LINK_HOURS: i64 = 24 * 365;
+
+#[derive(Debug, Clone, FromRow, Serialize, ToSchema)]
+pub struct ShareLink {
+    pub id: Uuid,
+    pub document_id: Uuid,
+    #[serde(skip_serializing)]
+    pub passcode_hash: Option<String>,
+    #[serde(skip_serializing)]
+    pub failed_passcode_attempts: i32,
+    #[serde(skip_serializing)]
+    pub locked_until: Option<DateTime<Utc>>,
+    /// Whether the link asks for a passcode.
+    pub has_passcode: bool,
+    pub expires_at: DateTime<Utc>,
+    pub created_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub revoked_at: Option<DateTime<Utc>>,
+    pub access_count: i32,
+    pub last_accessed_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct CreateShareLinkRequest {
+    /// Hours until the link expires; `SHARE_LINK_EXPIRY_HOURS` by default.
+    #[validate(range(
+        min = 1,
+        max = 8760,
+        message = "Expiry must be between 1 and 8760 hours"
+    ))]
+    pub expires_in_hours: Option<i64>,
+    /// Asked for on every access through the link.
+    #[validate(length(min = 4, max = 64, message = "Passcode must be 4 to 64 characters"))]
+    pub passcode: Option<String>,
+}
+
+/// A new share link. The token is only ever returned here.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CreatedShareLink {
+    #[serde(flatten)]
+    pub link: ShareLink,
+    pub token: String,
+    /// The page to hand out, on the frontend.
+    pub url: String,
+}
+
+/// What a share link shows of its document.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ShareLinkDocument {
+    pub title: String,
+    pub status: DocumentStatus,
+    pub completed_at: Option<DateTime<Utc>>,
+    /// Whether the PDF served is the sealed copy.
+    pub sealed: bool,
+    pub expires_at: DateTime<Utc>,
+    pub pdf_url: String,
+    pub certificate_url: String,
+}
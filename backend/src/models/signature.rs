@@ -1,35 +1,105 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use sqlx::types::Json;
 use sqlx::FromRow;
+use utoipa::ToSchema;
 use uuid::Uuid;
+use validator::Validate;
 
-#[derive(Debug, Clone, FromRow, Serialize)]
+#[derive(Debug, Clone, FromRow, Serialize, ToSchema)]
 pub struct Signature {
     pub id: Uuid,
     pub signer_id: Uuid,
     pub document_id: Uuid,
     pub field_id: Uuid,
-    pub signature_data: String,
+    /// Where the image is stored; `None` for signatures written before
+    /// images were moved to storage, until they are.
+    #[serde(skip_serializing)]
+    pub storage_key: Option<String>,
+    pub content_type: Option<String>,
+    pub byte_length: Option<i32>,
+    /// Nonce the image was encrypted with; `None` when stored in plaintext.
+    #[serde(skip_serializing)]
+    pub image_nonce: Option<Vec<u8>>,
     pub signature_hash: String,
+    pub signature_type: SignatureType,
+    pub typed_text: Option<String>,
+    pub typed_font: Option<String>,
     pub ip_address: String,
     pub user_agent: String,
     pub created_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Deserialize)]
+/// A signature image written to storage, as recorded on its row.
+#[derive(Debug, Clone)]
+pub struct StoredSignatureImage {
+    pub storage_key: String,
+    pub content_type: String,
+    pub byte_length: i32,
+    pub nonce: Option<Vec<u8>>,
+}
+
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, sqlx::Type, ToSchema,
+)]
+#[sqlx(type_name = "signature_type", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum SignatureType {
+    #[default]
+    Drawn,
+    Typed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct SubmitSignatureRequest {
     pub field_id: Uuid,
-    pub signature_data: String,
+    #[serde(default)]
+    pub signature_type: SignatureType,
+    pub signature_data: Option<String>,
+    pub saved_signature_id: Option<Uuid>,
+    pub text: Option<String>,
+    pub font: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct SubmitFieldValueRequest {
     pub field_id: Uuid,
     pub value: String,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct CompleteSigningRequest {
     pub signatures: Vec<SubmitSignatureRequest>,
     pub field_values: Vec<SubmitFieldValueRequest>,
+    pub apply_initials_to_all: Option<String>,
+}
+
+/// What a signer has filled in so far, saved so they can resume later.
+/// Nothing in it is applied until they submit.
+#[derive(Debug, FromRow, Serialize, ToSchema)]
+pub struct SigningProgress {
+    #[schema(value_type = CompleteSigningRequest)]
+    pub values: Json<CompleteSigningRequest>,
+    pub saved_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, ToSchema)]
+pub struct UserSignature {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub name: String,
+    pub signature_data: String,
+    pub signature_hash: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct CreateUserSignatureRequest {
+    #[validate(length(
+        min = 1,
+        max = 100,
+        message = "Name must be between 1 and 100 characters"
+    ))]
+    pub name: String,
+    pub signature_data: String,
 }
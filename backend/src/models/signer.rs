@@ -1,10 +1,11 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
+use utoipa::ToSchema;
 use uuid::Uuid;
 use validator::Validate;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type, ToSchema)]
 #[sqlx(type_name = "signer_status", rename_all = "snake_case")]
 #[serde(rename_all = "snake_case")]
 pub enum SignerStatus {
@@ -13,56 +14,164 @@ pub enum SignerStatus {
     Viewed,
     Signed,
     Declined,
+    Approved,
+    /// Taken off the document after it was sent.
+    Removed,
 }
 
-#[derive(Debug, Clone, FromRow, Serialize)]
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, sqlx::Type, ToSchema,
+)]
+#[sqlx(type_name = "signer_role", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum SignerRole {
+    #[default]
+    Signer,
+    Approver,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, ToSchema)]
 pub struct Signer {
     pub id: Uuid,
     pub document_id: Uuid,
     pub email: String,
     pub name: String,
     pub order_index: i32,
+    pub role: SignerRole,
     pub status: SignerStatus,
     pub access_token: String,
     pub ip_address: Option<String>,
     pub user_agent: Option<String>,
     pub viewed_at: Option<DateTime<Utc>>,
     pub signed_at: Option<DateTime<Utc>>,
+    pub approved_at: Option<DateTime<Utc>>,
+    pub verified_at: Option<DateTime<Utc>>,
+    pub verification_method: Option<String>,
+    pub verification_outcome: Option<String>,
+    /// When the signer agreed to do business electronically, and the
+    /// version of the consent text they agreed to.
+    pub consented_at: Option<DateTime<Utc>>,
+    pub consent_version: Option<String>,
+    pub consent_ip_address: Option<String>,
+    pub consent_user_agent: Option<String>,
+    /// Language of the emails sent to the signer, e.g. `fr`; the document's
+    /// when `None`.
+    pub locale: Option<String>,
     pub declined_at: Option<DateTime<Utc>>,
     pub decline_reason: Option<String>,
     pub email_sent_at: Option<DateTime<Utc>>,
+    /// Manual and automatic reminders sent since the signer was last sent
+    /// the document.
+    pub reminders_sent: i32,
+    pub last_reminded_at: Option<DateTime<Utc>>,
+    pub anonymized_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Deserialize, Validate)]
+#[derive(Debug, Deserialize, Validate, ToSchema)]
 pub struct AddSignerRequest {
     #[validate(email(message = "Invalid email address"))]
     pub email: String,
     #[validate(length(min = 1, message = "Name is required"))]
     pub name: String,
     pub order_index: Option<i32>,
+    #[serde(default)]
+    pub role: SignerRole,
+    #[validate(length(
+        min = 4,
+        max = 64,
+        message = "Access code must be between 4 and 64 characters"
+    ))]
+    pub access_code: Option<String>,
+    /// Language of the emails sent to the signer, e.g. `fr`. Defaults to the
+    /// document's; unsupported languages fall back the same way.
+    #[validate(length(max = 35, message = "Locale must be at most 35 characters"))]
+    pub locale: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Validate, ToSchema)]
 pub struct UpdateSignerRequest {
+    #[validate(length(min = 1, message = "Name is required"))]
     pub name: Option<String>,
+    #[validate(email(message = "Invalid email address"))]
     pub email: Option<String>,
     pub order_index: Option<i32>,
+    /// New access code of 4 to 64 characters; an empty string removes the
+    /// existing code.
+    #[validate(length(max = 64, message = "Access code must be between 4 and 64 characters"))]
+    pub access_code: Option<String>,
+    /// An empty string makes the signer use the document's language.
+    #[validate(length(max = 35, message = "Locale must be at most 35 characters"))]
+    pub locale: Option<String>,
+    /// Only apply the update if the signer is unchanged since it was read,
+    /// like `If-Match`.
+    pub expected_updated_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Default, Deserialize, Validate, ToSchema)]
+pub struct RotateSignerTokenRequest {
+    /// Why the link was replaced, recorded in the audit trail.
+    #[validate(length(max = 1000, message = "Reason must be at most 1000 characters"))]
+    pub reason: Option<String>,
+    /// Email the signer their new link.
+    #[serde(default)]
+    pub resend: bool,
+}
+
+#[derive(Debug, Default, Deserialize, Validate, ToSchema)]
+pub struct RemoveSignerRequest {
+    /// Why the signer was removed, recorded in the audit trail.
+    #[validate(length(max = 1000, message = "Reason must be at most 1000 characters"))]
+    pub reason: Option<String>,
+}
+
+/// The person taking over a signer's place, fields and role.
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct ReplaceSignerRequest {
+    #[validate(email(message = "Invalid email address"))]
+    pub email: String,
+    #[validate(length(min = 1, message = "Name is required"))]
+    pub name: String,
+    #[validate(length(
+        min = 4,
+        max = 64,
+        message = "Access code must be between 4 and 64 characters"
+    ))]
+    pub access_code: Option<String>,
+    /// Language of the emails sent to the new signer. Defaults to the
+    /// replaced signer's.
+    #[validate(length(max = 35, message = "Locale must be at most 35 characters"))]
+    pub locale: Option<String>,
+    /// Why the signer was replaced, recorded in the audit trail.
+    #[validate(length(max = 1000, message = "Reason must be at most 1000 characters"))]
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct AnonymizeSignerRequest {
+    #[validate(email(message = "Invalid email address"))]
+    pub email: String,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct SignerPublic {
     pub id: Uuid,
     pub document_id: Uuid,
     pub email: String,
     pub name: String,
     pub order_index: i32,
+    pub role: SignerRole,
     pub status: SignerStatus,
     pub viewed_at: Option<DateTime<Utc>>,
     pub signed_at: Option<DateTime<Utc>>,
+    pub approved_at: Option<DateTime<Utc>>,
+    pub verified_at: Option<DateTime<Utc>>,
+    pub verification_method: Option<String>,
+    pub verification_outcome: Option<String>,
     pub declined_at: Option<DateTime<Utc>>,
     pub email_sent_at: Option<DateTime<Utc>>,
+    pub anonymized_at: Option<DateTime<Utc>>,
 }
 
 impl From<Signer> for SignerPublic {
@@ -73,16 +182,169 @@ impl From<Signer> for SignerPublic {
             email: s.email,
             name: s.name,
             order_index: s.order_index,
+            role: s.role,
             status: s.status,
             viewed_at: s.viewed_at,
             signed_at: s.signed_at,
+            approved_at: s.approved_at,
+            verified_at: s.verified_at,
+            verification_method: s.verification_method,
+            verification_outcome: s.verification_outcome,
             declined_at: s.declined_at,
             email_sent_at: s.email_sent_at,
+            anonymized_at: s.anonymized_at,
         }
     }
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, FromRow, Serialize, ToSchema)]
+pub struct InPersonSession {
+    pub id: Uuid,
+    pub signer_id: Uuid,
+    pub host_user_id: Uuid,
+    #[serde(skip_serializing)]
+    pub token: String,
+    pub expires_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct InPersonSessionResponse {
+    pub signer_id: Uuid,
+    pub signing_url: String,
+    pub token: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Claims of the short-lived token a signer exchanges their link for.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SigningSessionClaims {
+    pub sub: String,
+    pub signer_id: Uuid,
+    /// Hash of the token the session was exchanged for, so replacing the
+    /// signer's link ends their sessions too.
+    pub token_hash: String,
+    /// Set when exchanged for an in-person session token.
+    pub in_person_session_id: Option<Uuid>,
+    pub exp: i64,
+    pub iat: i64,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SigningSessionToken {
+    /// Sent as `Authorization: Bearer` to the signing endpoints.
+    pub session_token: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct DeclineRequest {
     pub reason: Option<String>,
 }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ApprovalDecision {
+    Approve,
+    Reject,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ApprovalRequest {
+    pub decision: ApprovalDecision,
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Clone, FromRow)]
+pub struct VerificationCode {
+    pub id: Uuid,
+    pub signer_id: Uuid,
+    pub code_hash: String,
+    pub expires_at: DateTime<Utc>,
+    pub failed_attempts: i32,
+    pub consumed_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, FromRow)]
+pub struct SignerAccessCode {
+    pub signer_id: Uuid,
+    pub code_hash: String,
+    pub failed_attempts: i32,
+    pub locked_until: Option<DateTime<Utc>>,
+    pub unlocked_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, FromRow)]
+pub struct SignerOpenLink {
+    pub signer_id: Uuid,
+    pub token: String,
+    pub single_use: bool,
+    pub claimed_at: Option<DateTime<Utc>>,
+    pub claimed_ip: Option<String>,
+    pub claimed_user_agent: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl SignerOpenLink {
+    /// Whether the signer behind the link can be emailed: only once claimed,
+    /// and never for single-use links.
+    pub fn emails_signer(&self) -> bool {
+        self.claimed_at.is_some() && !self.single_use
+    }
+}
+
+#[derive(Debug, Default, Deserialize, Validate, ToSchema)]
+pub struct CreateOpenLinkRequest {
+    /// Shown in place of a name until the slot is claimed.
+    #[validate(length(
+        min = 1,
+        max = 255,
+        message = "Label must be between 1 and 255 characters"
+    ))]
+    pub label: Option<String>,
+    pub order_index: Option<i32>,
+    #[serde(default)]
+    pub role: SignerRole,
+    /// Return the claimant's signing link once, in the claim response,
+    /// instead of also emailing it to them.
+    #[serde(default)]
+    pub single_use: bool,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct OpenLinkResponse {
+    pub signer: Signer,
+    pub signing_url: String,
+    pub token: String,
+    pub single_use: bool,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct ClaimSigningLinkRequest {
+    #[validate(length(min = 1, max = 255, message = "Name is required"))]
+    pub name: String,
+    #[validate(email(message = "Invalid email address"))]
+    pub email: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ClaimSigningLinkResponse {
+    pub signer_id: Uuid,
+    /// The claimant's own signing token, which opens the session from now on.
+    pub token: String,
+    pub signing_url: String,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UnlockRequest {
+    pub access_code: String,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ConsentRequest {
+    /// `consent_version` of the signing session the text was read from.
+    pub version: String,
+}
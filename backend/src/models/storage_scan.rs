@@ -0,0 +1,46 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::types::Json;
+use sqlx::FromRow;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, FromRow, Serialize, ToSchema)]
+pub struct StorageScanReport {
+    pub id: Uuid,
+    pub started_at: DateTime<Utc>,
+    pub completed_at: DateTime<Utc>,
+    pub objects_scanned: i32,
+    pub documents_checked: i32,
+    #[schema(value_type = Vec<OrphanedObject>)]
+    pub orphans: Json<Vec<OrphanedObject>>,
+    pub orphans_deleted: i32,
+    #[schema(value_type = Vec<MissingFile>)]
+    pub missing_files: Json<Vec<MissingFile>>,
+    #[schema(value_type = Vec<HashMismatch>)]
+    pub hash_mismatches: Json<Vec<HashMismatch>>,
+    #[schema(value_type = Vec<String>)]
+    pub errors: Json<Vec<String>>,
+}
+
+/// A stored object no document refers to.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct OrphanedObject {
+    pub key: String,
+    pub size: u64,
+    pub last_modified: DateTime<Utc>,
+    pub deleted: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct MissingFile {
+    pub document_id: Uuid,
+    pub file_path: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct HashMismatch {
+    pub document_id: Uuid,
+    pub expected_hash: String,
+    pub actual_hash: String,
+}
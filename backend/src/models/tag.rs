@@ -0,0 +1,75 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::ToSchema;
+use validator::Validate;
+
+/// Tags a single document can carry.
+pub const MAX_TAGS_PER_DOCUMENT: usize = 20;
+pub const MAX_TAG_LENGTH: usize = 50;
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct AddTagsRequest {
+    #[validate(length(min = 1, max = 20, message = "Between 1 and 20 tags can be given"))]
+    pub tags: Vec<String>,
+}
+
+/// A tag used on the user's documents, and on how many of them.
+#[derive(Debug, FromRow, Serialize, ToSchema)]
+pub struct TagUsage {
+    pub name: String,
+    pub document_count: i64,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TagMode {
+    /// Documents carrying every one of the tags.
+    #[default]
+    All,
+    /// Documents carrying at least one of the tags.
+    Any,
+}
+
+/// Restricts a document list to documents with the given tags, compared
+/// case-insensitively.
+#[derive(Debug, Clone)]
+pub struct TagFilter {
+    pub tags: Vec<String>,
+    pub mode: TagMode,
+}
+
+impl TagFilter {
+    /// Parses a comma separated `tags` query parameter; `None` when it names
+    /// no tag.
+    pub fn parse(tags: &str, mode: TagMode) -> Option<Self> {
+        let tags: Vec<String> = tags
+            .split(',')
+            .map(str::trim)
+            .filter(|tag| !tag.is_empty())
+            .map(str::to_string)
+            .collect();
+
+        (!tags.is_empty()).then_some(Self { tags, mode })
+    }
+}
+
+/// Trims a tag name and checks it can be stored. Commas are rejected as they
+/// separate tags when filtering.
+pub fn normalize_tag(name: &str) -> Result<String, String> {
+    let name = name.trim();
+
+    if name.is_empty() {
+        return Err("Tags cannot be empty".to_string());
+    }
+    if name.chars().count() > MAX_TAG_LENGTH {
+        return Err(format!(
+            "Tags must be at most {} characters",
+            MAX_TAG_LENGTH
+        ));
+    }
+    if name.contains(',') || name.chars().any(char::is_control) {
+        return Err("Tags cannot contain commas or control characters".to_string());
+    }
+
+    Ok(name.to_string())
+}
@@ -0,0 +1,114 @@
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// What a timestamp attests: the document's uploaded file, or a completion
+/// certificate generated for it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type, ToSchema)]
+#[sqlx(type_name = "timestamp_subject", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum TimestampSubject {
+    Document,
+    Certificate,
+}
+
+#[derive(Debug, Clone, FromRow)]
+pub struct DocumentTimestamp {
+    pub id: Uuid,
+    pub document_id: Uuid,
+    pub subject: TimestampSubject,
+    /// Hex SHA-256 sent to the timestamp authority.
+    pub hashed_value: String,
+    pub tsa_url: String,
+    /// DER `TimeStampToken`, once obtained.
+    pub token: Option<Vec<u8>>,
+    /// Time attested by the authority.
+    pub gen_time: Option<DateTime<Utc>>,
+    pub serial_number: Option<String>,
+    pub attempts: i32,
+    pub last_error: Option<String>,
+    /// When the next attempt is due; `None` once granted or given up on.
+    pub next_attempt_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TimestampStatus {
+    Granted,
+    Pending,
+    Failed,
+}
+
+impl DocumentTimestamp {
+    pub fn status(&self) -> TimestampStatus {
+        if self.token.is_some() {
+            TimestampStatus::Granted
+        } else if self.next_attempt_at.is_some() {
+            TimestampStatus::Pending
+        } else {
+            TimestampStatus::Failed
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct DocumentTimestampResponse {
+    pub id: Uuid,
+    pub document_id: Uuid,
+    pub subject: TimestampSubject,
+    pub hashed_value: String,
+    pub tsa_url: String,
+    pub status: TimestampStatus,
+    /// Base64 DER `TimeStampToken`.
+    pub token: Option<String>,
+    pub gen_time: Option<DateTime<Utc>>,
+    pub serial_number: Option<String>,
+    pub attempts: i32,
+    pub last_error: Option<String>,
+    pub next_attempt_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<DocumentTimestamp> for DocumentTimestampResponse {
+    fn from(timestamp: DocumentTimestamp) -> Self {
+        Self {
+            status: timestamp.status(),
+            token: timestamp
+                .token
+                .as_deref()
+                .map(|token| base64::engine::general_purpose::STANDARD.encode(token)),
+            id: timestamp.id,
+            document_id: timestamp.document_id,
+            subject: timestamp.subject,
+            hashed_value: timestamp.hashed_value,
+            tsa_url: timestamp.tsa_url,
+            gen_time: timestamp.gen_time,
+            serial_number: timestamp.serial_number,
+            attempts: timestamp.attempts,
+            last_error: timestamp.last_error,
+            next_attempt_at: timestamp.next_attempt_at,
+            created_at: timestamp.created_at,
+        }
+    }
+}
+
+/// Outcome of checking a stored timestamp token.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TimestampVerification {
+    pub timestamp_id: Uuid,
+    pub valid: bool,
+    /// Whether the token attests the hash it was requested for.
+    pub imprint_matches: bool,
+    /// Whether the authority's signature over the token checks out; `None`
+    /// when built without OpenSSL.
+    pub signature_valid: Option<bool>,
+    /// Whether the signing certificate chains to `TSA_CA_PATH`; `None` when
+    /// no trust anchors are configured.
+    pub chain_trusted: Option<bool>,
+    pub gen_time: Option<DateTime<Utc>>,
+    pub errors: Vec<String>,
+}
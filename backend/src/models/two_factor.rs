@@ -0,0 +1,94 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::ToSchema;
+use uuid::Uuid;
+use validator::Validate;
+
+/// Purpose of a login challenge token, so it is never mistaken for a bearer
+/// token or the other way round.
+pub const CHALLENGE_PURPOSE: &str = "two_factor";
+
+/// A user's TOTP settings. Two-factor authentication is on once `enabled_at`
+/// is set; until then a secret being set up is only `pending_secret_encrypted`.
+#[derive(Debug, Clone, FromRow)]
+pub struct UserTwoFactor {
+    pub user_id: Uuid,
+    pub secret_encrypted: Option<String>,
+    pub pending_secret_encrypted: Option<String>,
+    pub enabled_at: Option<DateTime<Utc>>,
+    pub last_used_step: Option<i64>,
+    pub failed_attempts: i32,
+    pub locked_until: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TwoFactorStatus {
+    pub enabled: bool,
+    pub enabled_at: Option<DateTime<Utc>>,
+    pub recovery_codes_remaining: i64,
+}
+
+/// A new secret to add to an authenticator app. It only takes effect once a
+/// code from it is given to `POST /auth/2fa/enable`.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TwoFactorSetup {
+    /// Base32 secret, for entering by hand.
+    pub secret: String,
+    /// `otpauth://` URI, usually shown as a QR code.
+    pub provisioning_uri: String,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct EnableTwoFactorRequest {
+    /// A current code from the authenticator app.
+    #[validate(length(min = 1, message = "Code is required"))]
+    pub code: String,
+}
+
+/// Single-use codes to sign in with when the authenticator is unavailable.
+/// They are only shown once.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct RecoveryCodes {
+    pub recovery_codes: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct DisableTwoFactorRequest {
+    /// The account's current password.
+    #[validate(length(min = 1, message = "Password is required"))]
+    pub password: String,
+    /// A current code from the authenticator app, or a recovery code.
+    #[validate(length(min = 1, message = "Code is required"))]
+    pub code: String,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct VerifyTwoFactorRequest {
+    /// Token from the login response.
+    #[validate(length(min = 1, message = "Challenge token is required"))]
+    pub challenge_token: String,
+    /// A current code from the authenticator app, or a recovery code.
+    #[validate(length(min = 1, message = "Code is required"))]
+    pub code: String,
+}
+
+/// Returned by login instead of a bearer token when the account has
+/// two-factor authentication on.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TwoFactorChallenge {
+    pub two_factor_required: bool,
+    /// Exchanged with a code at `POST /auth/2fa/verify`.
+    pub challenge_token: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TwoFactorClaims {
+    pub sub: String,
+    pub user_id: Uuid,
+    pub purpose: String,
+    pub exp: i64,
+    pub iat: i64,
+}
@@ -1,22 +1,56 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
+use utoipa::ToSchema;
 use uuid::Uuid;
 use validator::Validate;
 
-#[derive(Debug, Clone, FromRow, Serialize)]
+use crate::models::two_factor::TwoFactorChallenge;
+
+/// What a user may do. Admins can do everything; senders upload, send and
+/// share their documents; viewers can only see documents shared with them.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, sqlx::Type, ToSchema,
+)]
+#[sqlx(type_name = "user_role", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    Admin,
+    #[default]
+    Sender,
+    Viewer,
+}
+
+impl Role {
+    pub fn is_admin(self) -> bool {
+        self == Self::Admin
+    }
+
+    /// Whether the user may create, change and share documents.
+    pub fn can_send(self) -> bool {
+        matches!(self, Self::Admin | Self::Sender)
+    }
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, ToSchema)]
 pub struct User {
     pub id: Uuid,
     pub email: String,
     #[serde(skip_serializing)]
     pub password_hash: String,
     pub name: String,
-    pub is_admin: bool,
+    pub role: Role,
+    /// IANA timezone certificates and audit exports show times in for the
+    /// user, e.g. `America/New_York`; the server default when `None`.
+    pub display_timezone: Option<String>,
+    /// Whether signing requests and reminders to the user's signers set
+    /// Reply-To to the user.
+    pub reply_to_enabled: bool,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Deserialize, Validate)]
+#[derive(Debug, Deserialize, Validate, ToSchema)]
 pub struct CreateUserRequest {
     #[validate(email(message = "Invalid email address"))]
     pub email: String,
@@ -26,7 +60,7 @@ pub struct CreateUserRequest {
     pub name: String,
 }
 
-#[derive(Debug, Deserialize, Validate)]
+#[derive(Debug, Deserialize, Validate, ToSchema)]
 pub struct LoginRequest {
     #[validate(email(message = "Invalid email address"))]
     pub email: String,
@@ -34,18 +68,29 @@ pub struct LoginRequest {
     pub password: String,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct LoginResponse {
     pub token: String,
     pub user: UserPublic,
 }
 
-#[derive(Debug, Serialize)]
+/// A bearer token, or a challenge when the account has two-factor
+/// authentication on.
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(untagged)]
+pub enum LoginOutcome {
+    Authenticated(LoginResponse),
+    TwoFactorRequired(TwoFactorChallenge),
+}
+
+#[derive(Debug, Serialize, ToSchema)]
 pub struct UserPublic {
     pub id: Uuid,
     pub email: String,
     pub name: String,
-    pub is_admin: bool,
+    pub role: Role,
+    pub display_timezone: Option<String>,
+    pub reply_to_enabled: bool,
 }
 
 impl From<User> for UserPublic {
@@ -54,17 +99,72 @@ impl From<User> for UserPublic {
             id: user.id,
             email: user.email,
             name: user.name,
-            is_admin: user.is_admin,
+            role: user.role,
+            display_timezone: user.display_timezone,
+            reply_to_enabled: user.reply_to_enabled,
         }
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct UpdateProfileRequest {
+    /// IANA timezone to show certificate and audit export times in, e.g.
+    /// `America/New_York`; an empty string uses the server default.
+    pub display_timezone: Option<String>,
+    /// Whether signers' replies to signing requests and reminders go to you.
+    pub reply_to_enabled: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct Claims {
     pub sub: String,
     pub user_id: Uuid,
+    /// The email at login; it is not updated if the user changes it.
     pub email: String,
-    pub is_admin: bool,
+    /// The role when the token was issued; a changed role applies to tokens
+    /// issued afterwards.
+    pub role: Role,
+    /// The login session; the token stops working once it is revoked.
+    pub sid: Uuid,
     pub exp: i64,
     pub iat: i64,
 }
+
+/// Bytes a user's documents take up in storage, against their quota. Counts
+/// the original and sealed files of documents outside the trash.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct StorageUsage {
+    pub user_id: Uuid,
+    pub used_bytes: i64,
+    pub document_count: i64,
+    /// `None` when the user has no limit.
+    pub quota_bytes: Option<i64>,
+    /// False when the quota is `DEFAULT_STORAGE_QUOTA_MB`.
+    pub custom_quota: bool,
+}
+
+impl StorageUsage {
+    /// Whether `additional` more bytes still fit in the quota.
+    pub fn allows(&self, additional: i64) -> bool {
+        match self.quota_bytes {
+            Some(quota) => self.used_bytes + additional <= quota,
+            None => true,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct SetUserRoleRequest {
+    pub role: Role,
+    /// Also sign the user out everywhere, so the new role applies at once
+    /// rather than when their current tokens expire.
+    #[serde(default)]
+    pub revoke_sessions: bool,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct SetStorageQuotaRequest {
+    /// `None` goes back to the default quota; 0 means unlimited.
+    #[validate(range(min = 0, message = "Quota cannot be negative"))]
+    pub quota_mb: Option<i64>,
+}
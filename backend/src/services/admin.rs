@@ -3,6 +3,7 @@ use sqlx::PgPool;
 use tracing::info;
 
 use crate::db;
+use crate::models::user::Role;
 use crate::services::config::Config;
 
 pub async fn ensure_admin_exists(pool: &PgPool, config: &Config) -> Result<()> {
@@ -21,7 +22,7 @@ pub async fn ensure_admin_exists(pool: &PgPool, config: &Config) -> Result<()> {
             &config.admin_email,
             &password_hash,
             "Administrator",
-            true,
+            Role::Admin,
         )
         .await?;
 
@@ -0,0 +1,400 @@
+use anyhow::Result;
+use serde::Serialize;
+use sqlx::PgPool;
+use std::collections::BTreeMap;
+use tracing::{info, warn};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::db;
+use crate::models::audit::{AuditAction, AuditLog};
+use crate::models::document::{Document, DocumentStatus};
+use crate::models::signer::Signer;
+use crate::services::audit;
+use crate::services::crypto::HashAlgorithm;
+use crate::services::email::EmailService;
+
+pub const ANONYMIZED_NAME: &str = "Anonymized signer";
+pub const ANONYMIZED_IP_ADDRESS: &str = "0.0.0.0";
+pub const ANONYMIZED_USER_AGENT: &str = "anonymized";
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct AffectedDocument {
+    pub document_id: Uuid,
+    pub title: String,
+    pub owner_id: Uuid,
+}
+
+impl From<&Document> for AffectedDocument {
+    fn from(document: &Document) -> Self {
+        Self {
+            document_id: document.id,
+            title: document.title.clone(),
+            owner_id: document.owner_id,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AnonymizationReport {
+    pub signers_anonymized: usize,
+    pub audit_entries_anonymized: usize,
+    pub documents: Vec<AffectedDocument>,
+    /// Envelopes still out for signature, left untouched so they can complete.
+    pub skipped_pending_documents: Vec<AffectedDocument>,
+    pub owners_notified: usize,
+}
+
+/// The admin performing the erasure, for the audit entries it leaves behind.
+pub struct AnonymizationActor<'a> {
+    pub user_id: Uuid,
+    pub ip_address: &'a str,
+    pub user_agent: &'a str,
+}
+
+/// Personal data of the signer row being erased, and what replaces its email.
+struct ErasedSigner<'a> {
+    signer: &'a Signer,
+    placeholder_email: String,
+}
+
+/// Replacement columns for an audit entry that mentions the signer.
+#[derive(Debug, PartialEq)]
+struct AnonymizedEntry {
+    ip_address: Option<String>,
+    user_agent: Option<String>,
+    details: Option<serde_json::Value>,
+}
+
+/// Erases a signer's personal data from every envelope they are part of that
+/// is no longer pending. Names, emails, IP addresses and user agents are
+/// replaced with placeholders in signer rows, signatures and audit entries;
+/// signature data and hashes, and audit hashes, are left as they were. Owners
+/// of the affected documents are notified.
+pub async fn anonymize_signer(
+    pool: &PgPool,
+    hash_algorithm: HashAlgorithm,
+    email_service: Option<&EmailService>,
+    email: &str,
+    actor: &AnonymizationActor<'_>,
+) -> Result<AnonymizationReport> {
+    let signers = db::signer::get_signers_by_email(pool, email).await?;
+
+    let mut report = AnonymizationReport {
+        signers_anonymized: 0,
+        audit_entries_anonymized: 0,
+        documents: Vec::new(),
+        skipped_pending_documents: Vec::new(),
+        owners_notified: 0,
+    };
+
+    for signer in &signers {
+        let Some(document) =
+            db::document::get_document_by_id_including_deleted(pool, signer.document_id).await?
+        else {
+            continue;
+        };
+
+        if document.status == DocumentStatus::Pending {
+            push_unique(&mut report.skipped_pending_documents, &document);
+            continue;
+        }
+
+        let erased = ErasedSigner {
+            signer,
+            placeholder_email: format!("anonymized-{}@anonymized.invalid", Uuid::new_v4().simple()),
+        };
+
+        // The signer row goes last: it is what identifies the signer, so an
+        // interrupted run can simply be repeated.
+        for log in db::audit::get_audit_logs_by_document(pool, document.id).await? {
+            if let Some(entry) = anonymize_entry(&log, &erased) {
+                db::audit::anonymize_audit_log(
+                    pool,
+                    log.id,
+                    entry.ip_address.as_deref(),
+                    entry.user_agent.as_deref(),
+                    entry.details,
+                )
+                .await?;
+                report.audit_entries_anonymized += 1;
+            }
+        }
+
+        db::signature::anonymize_signatures_by_signer(
+            pool,
+            signer.id,
+            ANONYMIZED_IP_ADDRESS,
+            ANONYMIZED_USER_AGENT,
+        )
+        .await?;
+
+        db::signer::anonymize_signer(
+            pool,
+            signer.id,
+            ANONYMIZED_NAME,
+            &erased.placeholder_email,
+            ANONYMIZED_IP_ADDRESS,
+            ANONYMIZED_USER_AGENT,
+        )
+        .await?;
+
+        audit::log_action(
+            pool,
+            hash_algorithm,
+            document.id,
+            None,
+            Some(actor.user_id),
+            AuditAction::SignerAnonymized,
+            Some(actor.ip_address),
+            Some(actor.user_agent),
+            Some(serde_json::json!({ "signer_id": signer.id })),
+        )
+        .await?;
+
+        report.signers_anonymized += 1;
+        push_unique(&mut report.documents, &document);
+    }
+
+    if let Some(email_service) = email_service {
+        report.owners_notified = notify_owners(pool, email_service, &report.documents).await?;
+    }
+
+    info!(
+        "Anonymized {} signer row(s) and {} audit entries across {} document(s)",
+        report.signers_anonymized,
+        report.audit_entries_anonymized,
+        report.documents.len()
+    );
+
+    Ok(report)
+}
+
+fn push_unique(documents: &mut Vec<AffectedDocument>, document: &Document) {
+    if !documents.iter().any(|d| d.document_id == document.id) {
+        documents.push(document.into());
+    }
+}
+
+async fn notify_owners(
+    pool: &PgPool,
+    email_service: &EmailService,
+    documents: &[AffectedDocument],
+) -> Result<usize> {
+    let mut titles_by_owner: BTreeMap<Uuid, Vec<String>> = BTreeMap::new();
+    for document in documents {
+        titles_by_owner
+            .entry(document.owner_id)
+            .or_default()
+            .push(document.title.clone());
+    }
+
+    let mut notified = 0;
+    for (owner_id, titles) in titles_by_owner {
+        let Some(owner) = db::user::get_user_by_id(pool, owner_id).await? else {
+            continue;
+        };
+
+        match email_service
+            .send_signer_anonymized_notice(&owner.email, &owner.name, &titles)
+            .await
+        {
+            Ok(()) => notified += 1,
+            Err(e) => warn!(
+                "Failed to notify owner {} about signer anonymization: {}",
+                owner_id, e
+            ),
+        }
+    }
+
+    Ok(notified)
+}
+
+/// The anonymized columns of `log`, or `None` if it holds nothing about the
+/// signer. Network and device data is only replaced in the signer's own
+/// entries; their name and email are replaced wherever they appear in details.
+fn anonymize_entry(log: &AuditLog, erased: &ErasedSigner<'_>) -> Option<AnonymizedEntry> {
+    let own = log.signer_id == Some(erased.signer.id);
+
+    let ip_address = match &log.ip_address {
+        Some(_) if own => Some(ANONYMIZED_IP_ADDRESS.to_string()),
+        other => other.clone(),
+    };
+    let user_agent = match &log.user_agent {
+        Some(_) if own => Some(ANONYMIZED_USER_AGENT.to_string()),
+        other => other.clone(),
+    };
+
+    let details = log.details.clone().map(|mut details| {
+        if own {
+            if let Some(map) = details.as_object_mut() {
+                map.remove("location");
+                if map.contains_key("device") {
+                    map.insert("device".to_string(), ANONYMIZED_USER_AGENT.into());
+                }
+            }
+        }
+        replace_strings(&mut details, erased);
+        details
+    });
+
+    let entry = AnonymizedEntry {
+        ip_address,
+        user_agent,
+        details,
+    };
+
+    let unchanged = entry.ip_address == log.ip_address
+        && entry.user_agent == log.user_agent
+        && entry.details == log.details;
+
+    (!unchanged).then_some(entry)
+}
+
+fn replace_strings(value: &mut serde_json::Value, erased: &ErasedSigner<'_>) {
+    match value {
+        serde_json::Value::String(s) => {
+            if s.eq_ignore_ascii_case(&erased.signer.email) {
+                *s = erased.placeholder_email.clone();
+            } else if *s == erased.signer.name {
+                *s = ANONYMIZED_NAME.to_string();
+            } else if erased.signer.ip_address.as_deref() == Some(s.as_str()) {
+                *s = ANONYMIZED_IP_ADDRESS.to_string();
+            } else if erased.signer.user_agent.as_deref() == Some(s.as_str()) {
+                *s = ANONYMIZED_USER_AGENT.to_string();
+            }
+        }
+        serde_json::Value::Array(values) => {
+            for value in values {
+                replace_strings(value, erased);
+            }
+        }
+        serde_json::Value::Object(map) => {
+            for value in map.values_mut() {
+                replace_strings(value, erased);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use serde_json::json;
+
+    use crate::models::signer::{SignerRole, SignerStatus};
+
+    fn signer() -> Signer {
+        Signer {
+            id: Uuid::new_v4(),
+            document_id: Uuid::new_v4(),
+            email: "Jane@Example.com".to_string(),
+            name: "Jane Doe".to_string(),
+            order_index: 0,
+            role: SignerRole::Signer,
+            status: SignerStatus::Signed,
+            access_token: "token".to_string(),
+            ip_address: Some("203.0.113.7".to_string()),
+            user_agent: Some("Mozilla/5.0".to_string()),
+            viewed_at: None,
+            signed_at: None,
+            approved_at: None,
+            verified_at: None,
+            verification_method: None,
+            verification_outcome: None,
+            consented_at: None,
+            consent_version: None,
+            consent_ip_address: None,
+            consent_user_agent: None,
+            locale: None,
+            declined_at: None,
+            decline_reason: None,
+            email_sent_at: None,
+            reminders_sent: 0,
+            last_reminded_at: None,
+            anonymized_at: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn log(signer_id: Option<Uuid>, details: serde_json::Value) -> AuditLog {
+        AuditLog {
+            id: Uuid::new_v4(),
+            document_id: Uuid::new_v4(),
+            sequence: 1,
+            signer_id,
+            user_id: None,
+            action: AuditAction::SignerSigned,
+            ip_address: Some("198.51.100.1".to_string()),
+            user_agent: Some("Owner Agent".to_string()),
+            details: Some(details),
+            entry_hash: "hash".to_string(),
+            previous_hash: None,
+            created_at: Utc::now(),
+            anonymized_at: None,
+            device: None,
+        }
+    }
+
+    #[test]
+    fn test_anonymize_own_entry() {
+        let signer = signer();
+        let erased = ErasedSigner {
+            signer: &signer,
+            placeholder_email: "anonymized-1@anonymized.invalid".to_string(),
+        };
+
+        let entry = anonymize_entry(
+            &log(
+                Some(signer.id),
+                json!({
+                    "signer_email": "jane@example.com",
+                    "device": "Safari on iOS",
+                    "location": { "city": "Paris" },
+                }),
+            ),
+            &erased,
+        )
+        .unwrap();
+
+        assert_eq!(entry.ip_address.as_deref(), Some(ANONYMIZED_IP_ADDRESS));
+        assert_eq!(entry.user_agent.as_deref(), Some(ANONYMIZED_USER_AGENT));
+        assert_eq!(
+            entry.details,
+            Some(json!({
+                "signer_email": "anonymized-1@anonymized.invalid",
+                "device": ANONYMIZED_USER_AGENT,
+            }))
+        );
+    }
+
+    #[test]
+    fn test_anonymize_other_entries_only_touches_mentions() {
+        let signer = signer();
+        let erased = ErasedSigner {
+            signer: &signer,
+            placeholder_email: "anonymized-1@anonymized.invalid".to_string(),
+        };
+
+        let entry = anonymize_entry(
+            &log(
+                None,
+                json!({ "signer_name": "Jane Doe", "device": "Chrome on Windows" }),
+            ),
+            &erased,
+        )
+        .unwrap();
+        assert_eq!(entry.ip_address.as_deref(), Some("198.51.100.1"));
+        assert_eq!(entry.user_agent.as_deref(), Some("Owner Agent"));
+        assert_eq!(
+            entry.details,
+            Some(json!({ "signer_name": ANONYMIZED_NAME, "device": "Chrome on Windows" }))
+        );
+
+        let unrelated = log(None, json!({ "signer_email": "someone@example.com" }));
+        assert_eq!(anonymize_entry(&unrelated, &erased), None);
+    }
+}
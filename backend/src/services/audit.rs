@@ -1,17 +1,31 @@
 use anyhow::Result;
-use chrono::Utc;
-use sqlx::PgPool;
+use chrono::{SecondsFormat, SubsecRound, Utc};
+use chrono_tz::Tz;
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+use sqlx::{PgConnection, PgPool};
 use uuid::Uuid;
 
 use crate::db;
 use crate::models::audit::{
-    AuditAction, AuditLog, Certificate, CertificateAuditEntry, CertificateSigner,
+    AuditAction, AuditChainStatus, AuditLog, Certificate, CertificateApproval,
+    CertificateAuditEntry, CertificateLocalTimes, CertificateSeal, CertificateSigner,
+    CertificateTimestamp, GeoLocation, LegacyCertificate,
 };
-use crate::services::crypto;
+use crate::models::security_event::SecurityEventSource;
+use crate::models::signer::SignerRole;
+use crate::models::timestamp::TimestampSubject;
+use crate::services::crypto::{self, HashAlgorithm};
+use crate::services::display_time;
+use crate::services::security_alerts::SecurityAlerts;
+use crate::services::server_key::ServerKey;
+use crate::services::user_agent;
+use crate::utils::request_id;
 
 #[allow(clippy::too_many_arguments)]
 pub async fn log_action(
     pool: &PgPool,
+    hash_algorithm: HashAlgorithm,
     document_id: Uuid,
     signer_id: Option<Uuid>,
     user_id: Option<Uuid>,
@@ -20,23 +34,81 @@ pub async fn log_action(
     user_agent: Option<&str>,
     details: Option<serde_json::Value>,
 ) -> Result<AuditLog> {
-    let previous = db::audit::get_latest_audit_log(pool, document_id).await?;
+    let mut tx = pool.begin().await?;
+
+    let log = log_action_in(
+        &mut tx,
+        hash_algorithm,
+        document_id,
+        signer_id,
+        user_id,
+        action,
+        ip_address,
+        user_agent,
+        details,
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(log)
+}
+
+/// Like `log_action`, inside a transaction, so the entry commits or rolls
+/// back with the change it records. Other entries of the document wait for
+/// the transaction to end.
+#[allow(clippy::too_many_arguments)]
+pub async fn log_action_in(
+    conn: &mut PgConnection,
+    hash_algorithm: HashAlgorithm,
+    document_id: Uuid,
+    signer_id: Option<Uuid>,
+    user_id: Option<Uuid>,
+    action: AuditAction,
+    ip_address: Option<&str>,
+    user_agent: Option<&str>,
+    details: Option<serde_json::Value>,
+) -> Result<AuditLog> {
+    db::audit::lock_audit_chain(&mut *conn, document_id).await?;
+
+    let previous = db::audit::get_latest_audit_log(&mut *conn, document_id).await?;
     let previous_hash = previous.as_ref().map(|p| p.entry_hash.as_str());
+    let sequence = previous.as_ref().map_or(1, |p| p.sequence + 1);
 
-    let timestamp = Utc::now().to_rfc3339();
-    let details_str = details.as_ref().map(|d| d.to_string());
+    let context = [
+        ("device", user_agent.map(user_agent::describe)),
+        ("request_id", request_id::current()),
+    ];
+
+    let mut details = details;
+    for (key, value) in context {
+        if let Some(value) = value {
+            if let Some(map) = details
+                .get_or_insert_with(|| serde_json::json!({}))
+                .as_object_mut()
+            {
+                map.insert(key.to_string(), serde_json::json!(value));
+            }
+        }
+    }
+
+    // Stored at the precision it is hashed at, so the hash can be recomputed.
+    let created_at = Utc::now().trunc_subsecs(6);
 
     let entry_hash = crypto::compute_audit_hash(
-        &document_id,
-        &format!("{:?}", action),
-        &timestamp,
+        hash_algorithm,
+        document_id,
+        sequence,
+        action,
+        created_at,
         previous_hash,
-        details_str.as_deref(),
+        details.as_ref(),
     );
 
     let log = db::audit::create_audit_log(
-        pool,
+        &mut *conn,
         document_id,
+        sequence,
         signer_id,
         user_id,
         action,
@@ -45,13 +117,93 @@ pub async fn log_action(
         details,
         &entry_hash,
         previous_hash,
+        created_at,
     )
     .await?;
 
     Ok(log)
 }
 
-pub async fn generate_certificate(pool: &PgPool, document_id: Uuid) -> Result<Certificate> {
+/// Readable device description of an audit entry, preferring the one recorded
+/// when the entry was written.
+pub fn device_description(log: &AuditLog) -> Option<String> {
+    log.details
+        .as_ref()
+        .and_then(|d| d.get("device"))
+        .and_then(|d| d.as_str())
+        .map(|d| d.to_string())
+        .or_else(|| log.user_agent.as_deref().map(user_agent::describe))
+}
+
+/// The fields whose values differ between two versions of a record, as
+/// `{"field": {"from": ..., "to": ...}}`, for the `changed` entry of audit
+/// details. Fields in `ignored` are left out.
+pub fn changes<T: Serialize>(before: &T, after: &T, ignored: &[&str]) -> serde_json::Value {
+    let (Ok(Value::Object(before)), Ok(Value::Object(after))) =
+        (serde_json::to_value(before), serde_json::to_value(after))
+    else {
+        return Value::Object(Map::new());
+    };
+
+    let changed = after
+        .into_iter()
+        .filter(|(name, _)| !ignored.contains(&name.as_str()))
+        .filter_map(|(name, to)| {
+            let from = before.get(&name).cloned().unwrap_or(Value::Null);
+            (from != to).then(|| (name, json!({ "from": from, "to": to })))
+        })
+        .collect();
+
+    Value::Object(changed)
+}
+
+/// Renders the `changed` entry of audit details, e.g. `x: 10 -> 20; y: 5 -> 8`.
+fn describe_changes(details: &Value) -> Option<String> {
+    let changed = details.get("changed")?.as_object()?;
+    if changed.is_empty() {
+        return None;
+    }
+
+    let render = |value: Option<&Value>| match value {
+        None | Some(Value::Null) => "none".to_string(),
+        Some(Value::String(text)) => text.clone(),
+        Some(value) => value.to_string(),
+    };
+
+    Some(
+        changed
+            .iter()
+            .map(|(name, change)| {
+                format!(
+                    "{}: {} -> {}",
+                    name,
+                    render(change.get("from")),
+                    render(change.get("to"))
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("; "),
+    )
+}
+
+/// The location recorded in the signer's `SignerSigned` audit entry, if any.
+fn signed_location(audit_logs: &[AuditLog], signer_id: Uuid) -> Option<GeoLocation> {
+    audit_logs
+        .iter()
+        .rev()
+        .find(|log| log.signer_id == Some(signer_id) && log.action == AuditAction::SignerSigned)
+        .and_then(|log| log.details.as_ref()?.get("location").cloned())
+        .and_then(|location| serde_json::from_value(location).ok())
+}
+
+pub async fn generate_certificate(
+    pool: &PgPool,
+    hash_algorithm: HashAlgorithm,
+    server_key: &ServerKey,
+    alerts: &SecurityAlerts,
+    document_id: Uuid,
+    timezone: Tz,
+) -> Result<Certificate> {
     let document = db::document::get_document_by_id(pool, document_id)
         .await?
         .ok_or_else(|| anyhow::anyhow!("Document not found"))?;
@@ -59,6 +211,13 @@ pub async fn generate_certificate(pool: &PgPool, document_id: Uuid) -> Result<Ce
     let signers = db::signer::get_signers_by_document(pool, document_id).await?;
     let signatures = db::signature::get_signatures_by_document(pool, document_id).await?;
     let audit_logs = db::audit::get_audit_logs_by_document(pool, document_id).await?;
+    alerts
+        .check_chain(
+            &document,
+            &chain_status(&audit_logs),
+            SecurityEventSource::Certificate,
+        )
+        .await?;
 
     let completed_at = document
         .completed_at
@@ -66,13 +225,9 @@ pub async fn generate_certificate(pool: &PgPool, document_id: Uuid) -> Result<Ce
 
     let cert_signers: Vec<CertificateSigner> = signers
         .iter()
-        .filter(|s| s.signed_at.is_some())
+        .filter(|s| s.role == SignerRole::Signer && s.signed_at.is_some())
         .map(|s| {
-            let sig_hash = signatures
-                .iter()
-                .find(|sig| sig.signer_id == s.id)
-                .map(|sig| sig.signature_hash.clone())
-                .unwrap_or_default();
+            let signature = signatures.iter().find(|sig| sig.signer_id == s.id);
 
             CertificateSigner {
                 name: s.name.clone(),
@@ -82,11 +237,43 @@ pub async fn generate_certificate(pool: &PgPool, document_id: Uuid) -> Result<Ce
                     .ip_address
                     .clone()
                     .unwrap_or_else(|| "Unknown".to_string()),
-                signature_hash: sig_hash,
+                signature_hash: signature
+                    .map(|sig| sig.signature_hash.clone())
+                    .unwrap_or_default(),
+                signature_type: signature.map(|sig| sig.signature_type).unwrap_or_default(),
+                typed_text: signature.and_then(|sig| sig.typed_text.clone()),
+                location: signed_location(&audit_logs, s.id),
+                device: s.user_agent.as_deref().map(user_agent::describe),
+                verified_at: s.verified_at,
+                verification_method: s.verification_method.clone(),
+                verification_outcome: s.verification_outcome.clone(),
+                consented_at: s.consented_at,
+                consent_version: s.consent_version.clone(),
             }
         })
         .collect();
 
+    let cert_approvals: Vec<CertificateApproval> = signers
+        .iter()
+        .filter(|s| s.role == SignerRole::Approver)
+        .filter_map(|s| {
+            s.approved_at.map(|approved_at| CertificateApproval {
+                name: s.name.clone(),
+                email: s.email.clone(),
+                approved_at,
+                ip_address: s
+                    .ip_address
+                    .clone()
+                    .unwrap_or_else(|| "Unknown".to_string()),
+                verified_at: s.verified_at,
+                verification_method: s.verification_method.clone(),
+                verification_outcome: s.verification_outcome.clone(),
+                consented_at: s.consented_at,
+                consent_version: s.consent_version.clone(),
+            })
+        })
+        .collect();
+
     let audit_trail: Vec<CertificateAuditEntry> = audit_logs
         .iter()
         .map(|log| {
@@ -104,24 +291,70 @@ pub async fn generate_certificate(pool: &PgPool, document_id: Uuid) -> Result<Ce
                 actor,
                 timestamp: log.created_at,
                 ip_address: log.ip_address.clone(),
+                device: device_description(log),
                 details: log.details.as_ref().map(|d| d.to_string()),
+                changes: log.details.as_ref().and_then(describe_changes),
             }
         })
         .collect();
 
-    let generated_at = Utc::now();
-
-    let signers_data = serde_json::to_string(&cert_signers)?;
-    let audit_data = serde_json::to_string(&audit_trail)?;
+    let generated_at = Utc::now().trunc_subsecs(6);
 
     let certificate_hash = crypto::compute_certificate_hash(
-        &document_id,
+        hash_algorithm,
+        document_id,
         &document.file_hash,
-        &signers_data,
-        &audit_data,
-        &generated_at.to_rfc3339(),
+        &cert_signers,
+        &cert_approvals,
+        &audit_trail,
+        generated_at,
     );
 
+    let seal = match (
+        document.sealed_at,
+        document.sealed_file_hash,
+        document.seal_signer,
+    ) {
+        (Some(sealed_at), Some(sealed_file_hash), Some(signer)) => Some(CertificateSeal {
+            sealed_at,
+            sealed_file_hash,
+            signer,
+        }),
+        _ => None,
+    };
+
+    let document_timestamp = db::timestamp::get_granted_timestamp(
+        pool,
+        document_id,
+        TimestampSubject::Document,
+        &document.file_hash,
+    )
+    .await?
+    .as_ref()
+    .and_then(CertificateTimestamp::from_granted);
+
+    let local_times = CertificateLocalTimes {
+        timezone: timezone.name().to_string(),
+        created_at: display_time::format(document.created_at, timezone),
+        completed_at: display_time::format(completed_at, timezone),
+        generated_at: display_time::format(generated_at, timezone),
+        sealed_at: seal
+            .as_ref()
+            .map(|seal| display_time::format(seal.sealed_at, timezone)),
+        signers: cert_signers
+            .iter()
+            .map(|s| display_time::format(s.signed_at, timezone))
+            .collect(),
+        approvals: cert_approvals
+            .iter()
+            .map(|a| display_time::format(a.approved_at, timezone))
+            .collect(),
+        audit_trail: audit_trail
+            .iter()
+            .map(|entry| display_time::format(entry.timestamp, timezone))
+            .collect(),
+    };
+
     let cert = Certificate {
         document_id,
         document_title: document.title,
@@ -129,13 +362,20 @@ pub async fn generate_certificate(pool: &PgPool, document_id: Uuid) -> Result<Ce
         created_at: document.created_at,
         completed_at,
         signers: cert_signers,
+        approvals: cert_approvals,
         audit_trail,
+        seal,
+        document_timestamp,
+        server_signature: server_key.sign(certificate_hash.as_bytes()),
         certificate_hash,
         generated_at,
+        certificate_timestamp: None,
+        local_times,
     };
 
     log_action(
         pool,
+        hash_algorithm,
         document_id,
         None,
         None,
@@ -143,7 +383,8 @@ pub async fn generate_certificate(pool: &PgPool, document_id: Uuid) -> Result<Ce
         None,
         None,
         Some(serde_json::json!({
-            "certificate_hash": cert.certificate_hash
+            "certificate_hash": cert.certificate_hash,
+            "key_id": cert.server_signature.key_id
         })),
     )
     .await?;
@@ -151,6 +392,430 @@ pub async fn generate_certificate(pool: &PgPool, document_id: Uuid) -> Result<Ce
     Ok(cert)
 }
 
-pub async fn verify_integrity(pool: &PgPool, document_id: Uuid) -> Result<bool> {
-    db::audit::verify_audit_chain(pool, document_id).await
+const CSV_HEADER: &str = "sequence,timestamp,local_time,action,signer_id,user_id,ip_address,\
+device,details,entry_hash,previous_hash";
+
+/// A document's audit trail as CSV, one row per entry, with each time both in
+/// UTC and in `timezone`.
+pub fn audit_csv(entries: &[AuditLog], timezone: Tz) -> String {
+    let mut csv = format!("{}\r\n", CSV_HEADER);
+
+    for log in entries {
+        let action = serde_json::to_value(log.action)
+            .ok()
+            .and_then(|action| action.as_str().map(str::to_string))
+            .unwrap_or_default();
+        let row = [
+            log.sequence.to_string(),
+            log.created_at.to_rfc3339_opts(SecondsFormat::Micros, true),
+            display_time::format(log.created_at, timezone),
+            action,
+            log.signer_id.map(|id| id.to_string()).unwrap_or_default(),
+            log.user_id.map(|id| id.to_string()).unwrap_or_default(),
+            log.ip_address.clone().unwrap_or_default(),
+            log.device.clone().unwrap_or_default(),
+            log.details
+                .as_ref()
+                .map(|d| d.to_string())
+                .unwrap_or_default(),
+            log.entry_hash.clone(),
+            log.previous_hash.clone().unwrap_or_default(),
+        ];
+
+        csv.push_str(
+            &row.iter()
+                .map(|f| csv_field(f))
+                .collect::<Vec<_>>()
+                .join(","),
+        );
+        csv.push_str("\r\n");
+    }
+
+    csv
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// A certificate as it was issued, in the shape of its hash's version.
+#[derive(Debug)]
+pub enum IssuedCertificate {
+    Current(Box<Certificate>),
+    /// Issued before certificate hashes were versioned, and before they were
+    /// signed with the server key.
+    Legacy(LegacyCertificate),
+}
+
+impl IssuedCertificate {
+    /// Reads a certificate, as JSON, in the shape its `certificate_hash`
+    /// belongs to.
+    pub fn from_json(value: Value) -> Result<Self> {
+        let hash = value["certificate_hash"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("Certificate has no certificate_hash"))?;
+
+        Ok(if crypto::is_canonical_hash(hash) {
+            Self::Current(Box::new(serde_json::from_value(value)?))
+        } else {
+            Self::Legacy(serde_json::from_value(value)?)
+        })
+    }
+
+    pub fn certificate_hash(&self) -> &str {
+        match self {
+            Self::Current(certificate) => &certificate.certificate_hash,
+            Self::Legacy(certificate) => &certificate.certificate_hash,
+        }
+    }
+}
+
+/// Whether a certificate's hash matches its contents, under the rules of the
+/// hash's version.
+pub fn verify_certificate_hash(certificate: &IssuedCertificate) -> Result<bool> {
+    let hash = certificate.certificate_hash();
+    let (algorithm, digest) = crypto::parse_hash(hash)?;
+
+    let expected = match certificate {
+        IssuedCertificate::Current(certificate) => crypto::compute_certificate_hash(
+            algorithm,
+            certificate.document_id,
+            &certificate.document_hash,
+            &certificate.signers,
+            &certificate.approvals,
+            &certificate.audit_trail,
+            certificate.generated_at,
+        ),
+        IssuedCertificate::Legacy(certificate) => crypto::compute_legacy_certificate_hash(
+            &certificate.document_id,
+            &certificate.document_hash,
+            &serde_json::to_string(&certificate.signers)?,
+            &serde_json::to_string(&certificate.audit_trail)?,
+            &certificate.generated_at.to_rfc3339(),
+        ),
+    };
+
+    Ok(crypto::parse_hash(&expected)?.1 == digest)
+}
+
+pub async fn verify_integrity(pool: &PgPool, document_id: Uuid) -> Result<AuditChainStatus> {
+    let logs = db::audit::get_audit_logs_by_document(pool, document_id).await?;
+
+    Ok(chain_status(&logs))
+}
+
+/// Checks that entries are numbered from 1 without gaps, that each links to
+/// the one before it and that `v2` hashes match the entry's contents. Older
+/// hashes covered a time that was not stored, so only their links can be
+/// checked. Anonymized entries keep their original `entry_hash` and
+/// `previous_hash`, so erasing personal data leaves the links intact; they
+/// are counted so a verifier knows their contents differ from what was
+/// hashed.
+fn chain_status(logs: &[AuditLog]) -> AuditChainStatus {
+    let mut previous: Option<&AuditLog> = None;
+    let first_broken_sequence = logs
+        .iter()
+        .find(|log| {
+            let expected_sequence = previous.map_or(1, |p| p.sequence + 1);
+            let expected_hash = previous.map(|p| &p.entry_hash);
+            previous = Some(log);
+
+            log.sequence != expected_sequence
+                || log.previous_hash.as_ref() != expected_hash
+                || !entry_hash_matches(log)
+        })
+        .map(|log| log.sequence);
+
+    AuditChainStatus {
+        intact: first_broken_sequence.is_none(),
+        anonymized_entries: logs.iter().filter(|l| l.anonymized_at.is_some()).count(),
+        first_broken_sequence,
+    }
+}
+
+fn entry_hash_matches(log: &AuditLog) -> bool {
+    if !crypto::is_canonical_hash(&log.entry_hash) || log.anonymized_at.is_some() {
+        return true;
+    }
+    let Ok((algorithm, _)) = crypto::parse_hash(&log.entry_hash) else {
+        return false;
+    };
+
+    crypto::compute_audit_hash(
+        algorithm,
+        log.document_id,
+        log.sequence,
+        log.action,
+        log.created_at,
+        log.previous_hash.as_deref(),
+        log.details.as_ref(),
+    ) == log.entry_hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Serialize)]
+    struct Field {
+        x: f64,
+        y: f64,
+        value: Option<String>,
+        updated_at: i64,
+    }
+
+    fn chain(length: i64) -> Vec<AuditLog> {
+        (1..=length)
+            .map(|sequence| AuditLog {
+                id: Uuid::new_v4(),
+                document_id: Uuid::nil(),
+                sequence,
+                signer_id: None,
+                user_id: None,
+                action: AuditAction::FieldAdded,
+                ip_address: None,
+                user_agent: None,
+                details: None,
+                entry_hash: format!("hash-{}", sequence),
+                previous_hash: (sequence > 1).then(|| format!("hash-{}", sequence - 1)),
+                created_at: Utc::now(),
+                anonymized_at: None,
+                device: None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_chain_status_reports_first_broken_link() {
+        let intact = chain_status(&chain(4));
+        assert!(intact.intact);
+        assert_eq!(intact.first_broken_sequence, None);
+        assert!(chain_status(&[]).intact);
+
+        let mut tampered = chain(4);
+        tampered[2].previous_hash = Some("forged".to_string());
+        let status = chain_status(&tampered);
+        assert!(!status.intact);
+        assert_eq!(status.first_broken_sequence, Some(3));
+
+        let mut missing = chain(4);
+        missing.remove(1);
+        missing[1].previous_hash = Some("hash-1".to_string());
+        assert_eq!(chain_status(&missing).first_broken_sequence, Some(3));
+
+        let mut unanchored = chain(2);
+        unanchored[0].previous_hash = Some("hash-0".to_string());
+        assert_eq!(chain_status(&unanchored).first_broken_sequence, Some(1));
+    }
+
+    fn hashed_chain(length: i64) -> Vec<AuditLog> {
+        let mut logs = chain(length);
+        for i in 0..logs.len() {
+            let previous_hash = i.checked_sub(1).map(|p| logs[p].entry_hash.clone());
+            let log = &mut logs[i];
+            log.created_at = log.created_at.trunc_subsecs(6);
+            log.details = Some(json!({ "x": 10.0 }));
+            log.previous_hash = previous_hash;
+            log.entry_hash = crypto::compute_audit_hash(
+                HashAlgorithm::Sha256,
+                log.document_id,
+                log.sequence,
+                log.action,
+                log.created_at,
+                log.previous_hash.as_deref(),
+                log.details.as_ref(),
+            );
+        }
+        logs
+    }
+
+    #[test]
+    fn test_chain_status_recomputes_canonical_hashes() {
+        assert!(chain_status(&hashed_chain(3)).intact);
+
+        let mut edited = hashed_chain(3);
+        edited[1].details = Some(json!({ "x": 20.0 }));
+        assert_eq!(chain_status(&edited).first_broken_sequence, Some(2));
+
+        let mut redated = hashed_chain(3);
+        redated[2].created_at += chrono::Duration::microseconds(1);
+        assert_eq!(chain_status(&redated).first_broken_sequence, Some(3));
+
+        let mut anonymized = hashed_chain(3);
+        anonymized[1].details = Some(json!({}));
+        anonymized[1].anonymized_at = Some(Utc::now());
+        assert!(chain_status(&anonymized).intact);
+    }
+
+    fn certificate(generated_at: chrono::DateTime<Utc>) -> Certificate {
+        Certificate {
+            document_id: Uuid::nil(),
+            document_title: "Lease".to_string(),
+            document_hash: "sha256:abc".to_string(),
+            created_at: generated_at,
+            completed_at: generated_at,
+            signers: vec![],
+            approvals: vec![],
+            audit_trail: vec![CertificateAuditEntry {
+                action: "DocumentCreated".to_string(),
+                actor: Some("System".to_string()),
+                timestamp: generated_at,
+                ip_address: None,
+                device: None,
+                details: Some(r#"{"x":1.5}"#.to_string()),
+                changes: None,
+            }],
+            seal: None,
+            document_timestamp: None,
+            certificate_hash: String::new(),
+            server_signature: crate::models::server_key::ServerSignature {
+                algorithm: String::new(),
+                key_id: String::new(),
+                key_fingerprint: String::new(),
+                signature: String::new(),
+            },
+            generated_at,
+            certificate_timestamp: None,
+            local_times: CertificateLocalTimes {
+                timezone: "UTC".to_string(),
+                created_at: String::new(),
+                completed_at: String::new(),
+                generated_at: String::new(),
+                sealed_at: None,
+                signers: vec![],
+                approvals: vec![],
+                audit_trail: vec![],
+            },
+        }
+    }
+
+    #[test]
+    fn test_verify_certificate_hash() {
+        let mut cert = certificate(Utc::now().trunc_subsecs(6));
+        cert.certificate_hash = crypto::compute_certificate_hash(
+            HashAlgorithm::Sha512,
+            cert.document_id,
+            &cert.document_hash,
+            &cert.signers,
+            &cert.approvals,
+            &cert.audit_trail,
+            cert.generated_at,
+        );
+        let issued = serde_json::to_value(&cert).unwrap();
+        let verify = |value: Value| {
+            verify_certificate_hash(&IssuedCertificate::from_json(value).unwrap()).unwrap()
+        };
+        assert!(verify(issued.clone()));
+
+        let mut edited = issued;
+        edited["audit_trail"][0]["actor"] = Value::Null;
+        assert!(!verify(edited));
+    }
+
+    /// A certificate as served before hashes were versioned, with the hash
+    /// that code computed for it.
+    fn legacy_certificate() -> Value {
+        json!({
+            "document_id": "6f1c2b9e-8a4d-4c3b-9f0e-2d7a5b1c3e4f",
+            "document_title": "Lease",
+            "document_hash": "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855",
+            "created_at": "2024-01-01T11:00:00Z",
+            "completed_at": "2024-01-01T12:00:00.123456789Z",
+            "signers": [{
+                "name": "Ada Lovelace",
+                "email": "ada@example.com",
+                "signed_at": "2024-01-01T12:00:00.123456789Z",
+                "ip_address": "203.0.113.7",
+                "signature_hash": "9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08"
+            }],
+            "audit_trail": [
+                {
+                    "action": "DocumentCreated",
+                    "actor": "System",
+                    "timestamp": "2024-01-01T11:00:00Z",
+                    "ip_address": null,
+                    "details": "{\"title\":\"Lease\"}"
+                },
+                {
+                    "action": "SignatureApplied",
+                    "actor": "Ada Lovelace (ada@example.com)",
+                    "timestamp": "2024-01-01T12:00:00.123456789Z",
+                    "ip_address": "203.0.113.7",
+                    "details": null
+                }
+            ],
+            "certificate_hash": "638f250b4eae06dcabb274f052fe6e88834ad0874cc44c16a4891752e02f8e8c",
+            "generated_at": "2024-01-01T12:30:45.987654321Z"
+        })
+    }
+
+    #[test]
+    fn test_verify_legacy_certificate_hash() {
+        let issued = IssuedCertificate::from_json(legacy_certificate()).unwrap();
+        assert!(matches!(issued, IssuedCertificate::Legacy(_)));
+        assert!(verify_certificate_hash(&issued).unwrap());
+
+        let mut edited = legacy_certificate();
+        edited["signers"][0]["ip_address"] = json!("198.51.100.1");
+        let edited = IssuedCertificate::from_json(edited).unwrap();
+        assert!(!verify_certificate_hash(&edited).unwrap());
+    }
+
+    #[test]
+    fn test_changes_lists_only_changed_fields() {
+        let before = Field {
+            x: 10.0,
+            y: 5.0,
+            value: None,
+            updated_at: 1,
+        };
+        let after = Field {
+            x: 20.0,
+            y: 5.0,
+            value: Some("Approved".to_string()),
+            updated_at: 2,
+        };
+
+        let changed = changes(&before, &after, &["updated_at"]);
+        assert_eq!(
+            changed,
+            json!({
+                "x": { "from": 10.0, "to": 20.0 },
+                "value": { "from": null, "to": "Approved" }
+            })
+        );
+
+        assert_eq!(
+            describe_changes(&json!({ "field_id": "f", "changed": changed })).as_deref(),
+            Some("value: none -> Approved; x: 10.0 -> 20.0")
+        );
+        assert_eq!(describe_changes(&json!({ "changed": {} })), None);
+        assert_eq!(describe_changes(&json!({ "field_id": "f" })), None);
+    }
+
+    #[test]
+    fn test_audit_csv_shows_local_time_and_quotes_fields() {
+        use chrono::TimeZone;
+
+        let mut entries = chain(1);
+        // 01:30 EST on the day New York's clocks went back
+        entries[0].created_at = Utc.with_ymd_and_hms(2024, 11, 3, 6, 30, 0).unwrap();
+        entries[0].details = Some(json!({ "title": "Lease, \"final\"" }));
+
+        let csv = audit_csv(&entries, chrono_tz::America::New_York);
+        let mut lines = csv.split("\r\n");
+
+        assert_eq!(lines.next(), Some(CSV_HEADER));
+        assert_eq!(
+            lines.next(),
+            Some(
+                "1,2024-11-03T06:30:00.000000Z,2024-11-03 01:30:00 EST (UTC-05:00),field_added,,,,,\
+                 \"{\"\"title\"\":\"\"Lease, \\\"\"final\\\"\"\"\"}\",hash-1,"
+            )
+        );
+    }
 }
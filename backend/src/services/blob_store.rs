@@ -0,0 +1,82 @@
+use anyhow::Result;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::db;
+use crate::models::encryption::FileEncryption;
+use crate::services::storage::{self, Storage};
+
+/// An uploaded original PDF as stored for a new document.
+pub struct StoredOriginal {
+    pub storage_key: String,
+    /// How the file is encrypted. Documents sharing a file share the data key
+    /// it is encrypted with.
+    pub encryption: Option<FileEncryption>,
+}
+
+/// Stores an uploaded original PDF for a new document, encrypted with a new
+/// data key when storage encryption is on. With `deduplicate`, a file the
+/// owner already stored with the same hash is reused instead of writing
+/// another copy.
+pub async fn store_original(
+    pool: &PgPool,
+    storage: &dyn Storage,
+    deduplicate: bool,
+    owner_id: Uuid,
+    document_id: Uuid,
+    file_hash: &str,
+    data: Vec<u8>,
+) -> Result<StoredOriginal> {
+    let data_key = storage.new_data_key().await?;
+
+    if deduplicate {
+        if let Some((storage_key, encryption)) =
+            db::blob::acquire_blob(pool, owner_id, file_hash, data_key.is_some()).await?
+        {
+            return Ok(StoredOriginal {
+                storage_key,
+                encryption,
+            });
+        }
+    }
+
+    let key = storage::original_pdf_key(owner_id, document_id);
+    let nonce = storage.put_file(&key, data, data_key.as_ref()).await?;
+
+    if let Err(e) = db::blob::create_blob(pool, &key, owner_id, file_hash).await {
+        let _ = storage.delete(&key).await;
+        return Err(e);
+    }
+
+    Ok(StoredOriginal {
+        storage_key: key,
+        encryption: data_key
+            .zip(nonce)
+            .map(|(data_key, nonce)| FileEncryption { data_key, nonce }),
+    })
+}
+
+/// Drops a document's reference to the file stored at `storage_key`, deleting
+/// the file once no document uses it. If the file cannot be deleted, nothing
+/// changes and the error is returned.
+pub async fn release(pool: &PgPool, storage: &dyn Storage, storage_key: &str) -> Result<()> {
+    let mut tx = pool.begin().await?;
+
+    match db::blob::lock_blob(&mut tx, storage_key).await? {
+        Some(ref_count) if ref_count > 1 => {
+            db::blob::decrement_blob(&mut tx, storage_key).await?;
+        }
+        counted => {
+            // Deleted while the row is locked, so nobody can take a new
+            // reference to the file in the meantime.
+            storage.delete(storage_key).await?;
+            if counted.is_some() {
+                db::blob::delete_blob(&mut tx, storage_key).await?;
+            }
+        }
+    }
+
+    tx.commit().await?;
+
+    Ok(())
+}
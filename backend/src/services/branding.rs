@@ -0,0 +1,154 @@
+use anyhow::Result;
+use base64::Engine;
+use std::io::Cursor;
+
+pub const MAX_LOGO_DIMENSION_PX: u32 = 2048;
+
+const PNG_PREFIX: &str = "data:image/png;base64,";
+const JPEG_PREFIXES: &[&str] = &["data:image/jpeg;base64,", "data:image/jpg;base64,"];
+
+/// Checks a `#rrggbb` color and returns it in lowercase. Only this form is
+/// accepted, since the color is written into email styles as is.
+pub fn parse_color(value: &str) -> Result<String> {
+    let value = value.trim();
+
+    match value.strip_prefix('#') {
+        Some(hex) if hex.len() == 6 && hex.chars().all(|c| c.is_ascii_hexdigit()) => {
+            Ok(value.to_ascii_lowercase())
+        }
+        _ => Err(anyhow::anyhow!("Color must be a hex color such as #2563eb")),
+    }
+}
+
+/// Decodes a logo data URL and checks it is a well-formed PNG or JPEG within
+/// `max_bytes` and `MAX_LOGO_DIMENSION_PX`. Returns its content type and
+/// bytes.
+pub fn parse_logo(data_url: &str, max_bytes: usize) -> Result<(&'static str, Vec<u8>)> {
+    let data_url = data_url.trim();
+
+    let (content_type, encoded) = if let Some(encoded) = data_url.strip_prefix(PNG_PREFIX) {
+        ("image/png", encoded)
+    } else if let Some(encoded) = JPEG_PREFIXES.iter().find_map(|p| data_url.strip_prefix(p)) {
+        ("image/jpeg", encoded)
+    } else {
+        return Err(anyhow::anyhow!(
+            "Logo must be a base64 PNG or JPEG data URL"
+        ));
+    };
+
+    if encoded.len() > max_bytes.div_ceil(3) * 4 {
+        return Err(size_error(max_bytes));
+    }
+
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(encoded)
+        .map_err(|_| anyhow::anyhow!("Logo is not valid base64"))?;
+
+    if bytes.len() > max_bytes {
+        return Err(size_error(max_bytes));
+    }
+
+    match content_type {
+        "image/png" => validate_png(&bytes)?,
+        _ => validate_jpeg(&bytes)?,
+    }
+
+    Ok((content_type, bytes))
+}
+
+fn size_error(max_bytes: usize) -> anyhow::Error {
+    anyhow::anyhow!("Logo exceeds the maximum size of {} KB", max_bytes / 1024)
+}
+
+fn check_dimensions(width: u32, height: u32) -> Result<()> {
+    if width == 0 || height == 0 {
+        return Err(anyhow::anyhow!("Logo is empty"));
+    }
+
+    if width > MAX_LOGO_DIMENSION_PX || height > MAX_LOGO_DIMENSION_PX {
+        return Err(anyhow::anyhow!(
+            "Logo dimensions {}x{} exceed the maximum of {}x{}",
+            width,
+            height,
+            MAX_LOGO_DIMENSION_PX,
+            MAX_LOGO_DIMENSION_PX
+        ));
+    }
+
+    Ok(())
+}
+
+fn validate_png(bytes: &[u8]) -> Result<()> {
+    let invalid = || anyhow::anyhow!("Logo is not a valid PNG");
+
+    let mut reader = png::Decoder::new(Cursor::new(bytes))
+        .read_info()
+        .map_err(|_| invalid())?;
+    check_dimensions(reader.info().width, reader.info().height)?;
+
+    let mut buf = vec![0; reader.output_buffer_size()];
+    reader.next_frame(&mut buf).map_err(|_| invalid())?;
+
+    Ok(())
+}
+
+fn validate_jpeg(bytes: &[u8]) -> Result<()> {
+    let invalid = || anyhow::anyhow!("Logo is not a valid JPEG");
+
+    let mut decoder = jpeg_decoder::Decoder::new(Cursor::new(bytes));
+    decoder.read_info().map_err(|_| invalid())?;
+    let info = decoder.info().ok_or_else(invalid)?;
+    check_dimensions(info.width as u32, info.height as u32)?;
+    decoder.decode().map_err(|_| invalid())?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::signature_image::encode_rgba_png;
+
+    fn png_data_url(width: u32, height: u32) -> String {
+        let rgba = vec![200; (width * height * 4) as usize];
+        let png = encode_rgba_png(width, height, &rgba).unwrap();
+        format!(
+            "{}{}",
+            PNG_PREFIX,
+            base64::engine::general_purpose::STANDARD.encode(png)
+        )
+    }
+
+    #[test]
+    fn test_parse_color() {
+        assert_eq!(parse_color(" #0F766E ").unwrap(), "#0f766e");
+        assert!(parse_color("0f766e").is_err());
+        assert!(parse_color("#0f766").is_err());
+        assert!(parse_color("#0f766g").is_err());
+        assert!(parse_color("red; background: url(x)").is_err());
+    }
+
+    #[test]
+    fn test_parse_logo_accepts_png() {
+        let (content_type, bytes) = parse_logo(&png_data_url(40, 20), 64 * 1024).unwrap();
+        assert_eq!(content_type, "image/png");
+        assert!(bytes.starts_with(b"\x89PNG"));
+    }
+
+    #[test]
+    fn test_parse_logo_rejects_other_images() {
+        assert!(parse_logo("data:image/gif;base64,R0lGODlhAQABAAAAACw=", 64 * 1024).is_err());
+        assert!(parse_logo("data:image/png;base64,bm90IGEgcG5n", 64 * 1024).is_err());
+        assert!(parse_logo("data:image/jpeg;base64,bm90IGEganBlZw==", 64 * 1024).is_err());
+    }
+
+    #[test]
+    fn test_parse_logo_enforces_limits() {
+        let error = parse_logo(&png_data_url(40, 20), 16).unwrap_err();
+        assert!(error.to_string().contains("maximum size"));
+
+        let error =
+            parse_logo(&png_data_url(MAX_LOGO_DIMENSION_PX + 1, 1), 1024 * 1024).unwrap_err();
+        assert!(error.to_string().contains("dimensions"));
+    }
+}
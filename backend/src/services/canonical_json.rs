@@ -0,0 +1,110 @@
+use serde::Serialize;
+use serde_json::{Number, Value};
+
+/// Largest integer an `f64` holds exactly.
+const MAX_SAFE_INTEGER: f64 = 9_007_199_254_740_992.0;
+
+/// Serializes `value` as canonical JSON, the form hashed for audit entries
+/// and certificates: no whitespace, object keys sorted by their UTF-8 bytes
+/// whatever order the map keeps them in, and floats without a fractional
+/// part written as integers, so `150.0` and `150` serialize alike (JSONB does
+/// not keep the difference). Other floats use the shortest form that reads
+/// back to the same value, and strings serde_json's escaping.
+pub fn to_string<T: Serialize>(value: &T) -> serde_json::Result<String> {
+    let value = serde_json::to_value(value)?;
+    let mut out = String::new();
+    write_value(&value, &mut out);
+    Ok(out)
+}
+
+fn write_value(value: &Value, out: &mut String) {
+    match value {
+        Value::Null => out.push_str("null"),
+        Value::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+        Value::Number(n) => write_number(n, out),
+        Value::String(s) => write_string(s, out),
+        Value::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_value(item, out);
+            }
+            out.push(']');
+        }
+        Value::Object(map) => {
+            let mut entries: Vec<_> = map.iter().collect();
+            entries.sort_by(|(a, _), (b, _)| a.as_bytes().cmp(b.as_bytes()));
+
+            out.push('{');
+            for (i, (key, item)) in entries.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_string(key, out);
+                out.push(':');
+                write_value(item, out);
+            }
+            out.push('}');
+        }
+    }
+}
+
+fn write_number(n: &Number, out: &mut String) {
+    match n.as_f64() {
+        Some(f) if n.is_f64() && f.fract() == 0.0 && f.abs() < MAX_SAFE_INTEGER => {
+            // Also turns -0.0 into 0.
+            out.push_str(&(f as i64).to_string());
+        }
+        _ => out.push_str(&n.to_string()),
+    }
+}
+
+fn write_string(s: &str, out: &mut String) {
+    out.push_str(&serde_json::to_string(s).expect("strings always serialize"));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_keys_are_sorted_at_every_level() {
+        let value = json!({
+            "b": 1,
+            "a": { "z": [3, { "y": true, "x": null }], "é": "v", "A": "w" }
+        });
+        assert_eq!(
+            to_string(&value).unwrap(),
+            r#"{"a":{"A":"w","z":[3,{"x":null,"y":true}],"é":"v"},"b":1}"#
+        );
+    }
+
+    #[test]
+    fn test_numbers_have_one_form() {
+        assert_eq!(
+            to_string(&json!([
+                150.0,
+                150,
+                -0.0,
+                0.5,
+                1e21,
+                -7,
+                18446744073709551615u64
+            ]))
+            .unwrap(),
+            "[150,150,0,0.5,1e+21,-7,18446744073709551615]"
+        );
+        assert_eq!(to_string(&json!(0.1 + 0.2)).unwrap(), "0.30000000000000004");
+    }
+
+    #[test]
+    fn test_strings_are_escaped() {
+        assert_eq!(
+            to_string(&json!("say \"hi\"\n\\ café")).unwrap(),
+            r#""say \"hi\"\n\\ café""#
+        );
+    }
+}
@@ -1,13 +1,42 @@
 use anyhow::{Context, Result};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use chrono_tz::Tz;
+use ipnetwork::IpNetwork;
+use sha2::{Digest, Sha256};
 use std::env;
 
+use crate::models::document::DocumentStatus;
+use crate::services::crypto::HashAlgorithm;
+use crate::services::i18n::Locale;
+use crate::services::ip_privacy::IpAddressMode;
+use crate::services::pdf::PdfLimits;
+
+const DEFAULT_ESIGN_CONSENT_TEXT: &str = "I agree to use electronic records and signatures \
+    for this document, and that my electronic signature is as legally binding as a handwritten \
+    one. I can ask the sender for a paper copy, or withdraw this consent before signing by \
+    declining the document.";
+
 #[derive(Clone, Debug)]
 pub struct Config {
     pub database_url: String,
+    pub database_max_connections: u32,
+    /// How long a request waits for a free database connection before
+    /// failing.
+    pub database_acquire_timeout_secs: u64,
     pub backend_host: String,
     pub backend_port: u16,
     pub jwt_secret: String,
+    /// The secret before the last rotation; tokens signed with it are still
+    /// accepted until it is removed.
+    pub jwt_previous_secret: Option<String>,
+    pub jwt_issuer: String,
+    pub jwt_audience: String,
     pub jwt_expiration_hours: i64,
+    /// Base64 AES-256 key encrypting TOTP secrets; derived from `jwt_secret`
+    /// when unset.
+    pub totp_encryption_key: Option<String>,
+    /// Name authenticator apps show next to the account.
+    pub totp_issuer: String,
     pub bcrypt_cost: u32,
     pub admin_email: String,
     pub admin_password: String,
@@ -18,27 +47,140 @@ pub struct Config {
     pub smtp_from_email: String,
     pub smtp_from_name: String,
     pub smtp_tls: bool,
+    /// Language of emails to signers whose document has none.
+    pub default_locale: Locale,
+    pub email_retry_interval_secs: u64,
+    pub storage_backend: String,
     pub storage_path: String,
+    pub s3_bucket: Option<String>,
+    pub s3_region: String,
+    pub s3_endpoint: Option<String>,
+    pub s3_access_key_id: Option<String>,
+    pub s3_secret_access_key: Option<String>,
+    pub s3_prefix: Option<String>,
+    pub s3_allow_http: bool,
+    /// Base64 AES-256 master key wrapping the data keys stored files are
+    /// encrypted with; files are stored in plaintext when unset.
+    pub storage_encryption_key: Option<String>,
+    /// The master key before the last rotation, still used to unwrap data
+    /// keys it wrapped.
+    pub storage_encryption_previous_key: Option<String>,
+    pub storage_scan_interval_hours: u64,
+    pub storage_orphan_grace_hours: i64,
+    pub storage_scan_delete_orphans: bool,
+    pub deleted_document_retention_days: i64,
+    /// Days login history is kept; `None` keeps it forever.
+    pub auth_event_retention_days: Option<i64>,
+    pub soft_delete_completed_documents: bool,
+    pub void_reason_required: bool,
+    pub expiry_warning_days: i64,
+    pub retention: RetentionPolicy,
     pub max_file_size_mb: u64,
-    pub hash_algorithm: String,
+    pub default_storage_quota_mb: u64,
+    pub max_pdf_pages: usize,
+    pub max_pdf_page_dimension_pt: f64,
+    pub strip_pdf_active_content: bool,
+    /// Whether an owner's upload of a file they already stored reuses the
+    /// stored copy.
+    pub upload_deduplication: bool,
+    pub pdf_max_concurrent_jobs: usize,
+    pub pdftoppm_path: String,
+    pub preview_max_width: u32,
+    pub hash_algorithm: HashAlgorithm,
     pub public_url: String,
+    pub api_docs_enabled: bool,
     pub rate_limit_rpm: u32,
+    pub in_person_session_minutes: i64,
+    /// Lifetime of the session tokens signers exchange their link for.
+    pub signing_session_minutes: i64,
+    /// Whether signing endpoints still accept the signer's link token in the
+    /// path instead of a session token; kept while clients migrate.
+    pub signing_path_tokens: bool,
+    /// Whether signers must consent to do business electronically before
+    /// their signature or approval is accepted.
+    pub esign_consent_required: bool,
+    /// The consent text signers are asked to accept.
+    pub esign_consent_text: String,
+    pub esign_consent_version: Option<String>,
+    /// How long share links stay valid unless their creator says otherwise.
+    pub share_link_expiry_hours: i64,
+    /// How long the link confirming a new account email stays valid.
+    pub email_change_expiry_hours: i64,
+    /// How long an admin's invitation to create an account stays valid.
+    pub invitation_expiry_hours: i64,
+    pub max_signature_size_kb: usize,
+    pub max_branding_logo_kb: usize,
+    /// Largest progress a signer can save before submitting.
+    pub max_signing_progress_kb: usize,
+    /// Most signers, approvers and open slots included, a document can have.
+    pub max_signers_per_document: usize,
+    pub signature_fonts_dir: String,
+    /// Timezone certificates and audit exports show times in for users who
+    /// have not chosen one.
+    pub display_timezone: Tz,
+    pub verification_code_minutes: i64,
+    pub verification_max_attempts: i64,
+    pub verification_lockout_minutes: i64,
+    pub verified_session_minutes: i64,
+    pub verification_providers: Vec<String>,
+    pub geoip_database_path: Option<String>,
+    pub seal_certificate_path: Option<String>,
+    pub seal_private_key_path: Option<String>,
+    pub seal_certificate_password: Option<String>,
+    pub tsa_url: Option<String>,
+    pub tsa_timeout_secs: u64,
+    pub tsa_ca_path: Option<String>,
+    pub tsa_retry_interval_secs: u64,
+    pub signing_key_path: String,
+    pub signing_retired_key_paths: Vec<String>,
+    pub trusted_proxies: Vec<IpNetwork>,
+    /// How much of client IP addresses is recorded.
+    pub ip_address_mode: IpAddressMode,
+    /// Key of the HMAC recording IP addresses in `hashed` mode.
+    pub ip_address_hash_key: Option<String>,
+    pub shutdown_drain_timeout_secs: u64,
 }
 
 impl Config {
     pub fn from_env() -> Result<Self> {
         Ok(Self {
             database_url: env::var("DATABASE_URL").context("DATABASE_URL must be set")?,
+            database_max_connections: env::var("DATABASE_MAX_CONNECTIONS")
+                .unwrap_or_else(|_| "10".to_string())
+                .parse()
+                .context("DATABASE_MAX_CONNECTIONS must be a number")?,
+            database_acquire_timeout_secs: env::var("DATABASE_ACQUIRE_TIMEOUT_SECS")
+                .unwrap_or_else(|_| "30".to_string())
+                .parse()
+                .context("DATABASE_ACQUIRE_TIMEOUT_SECS must be a number")?,
             backend_host: env::var("BACKEND_HOST").unwrap_or_else(|_| "0.0.0.0".to_string()),
             backend_port: env::var("BACKEND_PORT")
                 .unwrap_or_else(|_| "8080".to_string())
                 .parse()
                 .context("BACKEND_PORT must be a valid port number")?,
             jwt_secret: env::var("JWT_SECRET").context("JWT_SECRET must be set")?,
+            jwt_previous_secret: env::var("JWT_PREVIOUS_SECRET")
+                .ok()
+                .filter(|s| !s.is_empty()),
+            jwt_issuer: env::var("JWT_ISSUER")
+                .ok()
+                .filter(|i| !i.is_empty())
+                .unwrap_or_else(|| "signvault".to_string()),
+            jwt_audience: env::var("JWT_AUDIENCE")
+                .ok()
+                .filter(|a| !a.is_empty())
+                .unwrap_or_else(|| "signvault-api".to_string()),
             jwt_expiration_hours: env::var("JWT_EXPIRATION_HOURS")
                 .unwrap_or_else(|_| "24".to_string())
                 .parse()
                 .context("JWT_EXPIRATION_HOURS must be a number")?,
+            totp_encryption_key: env::var("TOTP_ENCRYPTION_KEY")
+                .ok()
+                .filter(|k| !k.is_empty()),
+            totp_issuer: env::var("TOTP_ISSUER")
+                .ok()
+                .filter(|i| !i.is_empty())
+                .unwrap_or_else(|| "SignVault".to_string()),
             bcrypt_cost: env::var("BCRYPT_COST")
                 .unwrap_or_else(|_| "12".to_string())
                 .parse()
@@ -59,22 +201,373 @@ impl Config {
                 .unwrap_or_else(|_| "true".to_string())
                 .parse()
                 .unwrap_or(true),
+            default_locale: env::var("DEFAULT_LOCALE")
+                .unwrap_or_else(|_| "en".to_string())
+                .parse()
+                .context("DEFAULT_LOCALE must be a supported language: en or fr")?,
+            email_retry_interval_secs: env::var("EMAIL_RETRY_INTERVAL_SECS")
+                .unwrap_or_else(|_| "60".to_string())
+                .parse()
+                .context("EMAIL_RETRY_INTERVAL_SECS must be a number")?,
+            storage_backend: env::var("STORAGE_BACKEND")
+                .unwrap_or_else(|_| "local".to_string())
+                .trim()
+                .to_lowercase(),
             storage_path: env::var("STORAGE_PATH").unwrap_or_else(|_| "./data/storage".to_string()),
+            s3_bucket: env::var("S3_BUCKET").ok().filter(|v| !v.is_empty()),
+            s3_region: env::var("S3_REGION").unwrap_or_else(|_| "us-east-1".to_string()),
+            s3_endpoint: env::var("S3_ENDPOINT").ok().filter(|v| !v.is_empty()),
+            s3_access_key_id: env::var("S3_ACCESS_KEY_ID").ok().filter(|v| !v.is_empty()),
+            s3_secret_access_key: env::var("S3_SECRET_ACCESS_KEY")
+                .ok()
+                .filter(|v| !v.is_empty()),
+            s3_prefix: env::var("S3_PREFIX").ok().filter(|v| !v.is_empty()),
+            s3_allow_http: env::var("S3_ALLOW_HTTP")
+                .unwrap_or_else(|_| "false".to_string())
+                .parse()
+                .unwrap_or(false),
+            storage_encryption_key: env::var("STORAGE_ENCRYPTION_KEY")
+                .ok()
+                .filter(|k| !k.is_empty()),
+            storage_encryption_previous_key: env::var("STORAGE_ENCRYPTION_PREVIOUS_KEY")
+                .ok()
+                .filter(|k| !k.is_empty()),
+            storage_scan_interval_hours: env::var("STORAGE_SCAN_INTERVAL_HOURS")
+                .unwrap_or_else(|_| "24".to_string())
+                .parse()
+                .context("STORAGE_SCAN_INTERVAL_HOURS must be a number")?,
+            storage_orphan_grace_hours: env::var("STORAGE_ORPHAN_GRACE_HOURS")
+                .unwrap_or_else(|_| "24".to_string())
+                .parse()
+                .context("STORAGE_ORPHAN_GRACE_HOURS must be a number")?,
+            storage_scan_delete_orphans: env::var("STORAGE_SCAN_DELETE_ORPHANS")
+                .unwrap_or_else(|_| "false".to_string())
+                .parse()
+                .unwrap_or(false),
+            deleted_document_retention_days: env::var("DELETED_DOCUMENT_RETENTION_DAYS")
+                .unwrap_or_else(|_| "30".to_string())
+                .parse()
+                .context("DELETED_DOCUMENT_RETENTION_DAYS must be a number")?,
+            auth_event_retention_days: retention_days("AUTH_EVENT_RETENTION_DAYS", "365")?,
+            soft_delete_completed_documents: env::var("SOFT_DELETE_COMPLETED_DOCUMENTS")
+                .unwrap_or_else(|_| "false".to_string())
+                .parse()
+                .unwrap_or(false),
+            void_reason_required: env::var("VOID_REASON_REQUIRED")
+                .unwrap_or_else(|_| "true".to_string())
+                .parse()
+                .unwrap_or(true),
+            expiry_warning_days: env::var("EXPIRY_WARNING_DAYS")
+                .unwrap_or_else(|_| "3".to_string())
+                .parse()
+                .context("EXPIRY_WARNING_DAYS must be a number")?,
+            retention: RetentionPolicy {
+                draft_days: retention_days("RETENTION_DRAFT_DAYS", "90")?,
+                pending_days: retention_days("RETENTION_PENDING_DAYS", "")?,
+                completed_days: retention_days("RETENTION_COMPLETED_DAYS", "2555")?,
+                voided_days: retention_days("RETENTION_VOIDED_DAYS", "90")?,
+                expired_days: retention_days("RETENTION_EXPIRED_DAYS", "90")?,
+            },
             max_file_size_mb: env::var("MAX_FILE_SIZE_MB")
                 .unwrap_or_else(|_| "50".to_string())
                 .parse()
                 .context("MAX_FILE_SIZE_MB must be a number")?,
-            hash_algorithm: env::var("HASH_ALGORITHM").unwrap_or_else(|_| "SHA256".to_string()),
+            default_storage_quota_mb: env::var("DEFAULT_STORAGE_QUOTA_MB")
+                .unwrap_or_else(|_| "1024".to_string())
+                .parse()
+                .context("DEFAULT_STORAGE_QUOTA_MB must be a number")?,
+            max_pdf_pages: env::var("MAX_PDF_PAGES")
+                .unwrap_or_else(|_| "500".to_string())
+                .parse()
+                .context("MAX_PDF_PAGES must be a number")?,
+            max_pdf_page_dimension_pt: env::var("MAX_PDF_PAGE_DIMENSION_PT")
+                .unwrap_or_else(|_| "14400".to_string())
+                .parse()
+                .context("MAX_PDF_PAGE_DIMENSION_PT must be a number")?,
+            strip_pdf_active_content: env::var("STRIP_PDF_ACTIVE_CONTENT")
+                .unwrap_or_else(|_| "false".to_string())
+                .parse()
+                .unwrap_or(false),
+            upload_deduplication: env::var("UPLOAD_DEDUPLICATION")
+                .unwrap_or_else(|_| "true".to_string())
+                .parse()
+                .unwrap_or(true),
+            pdf_max_concurrent_jobs: match env::var("PDF_MAX_CONCURRENT_JOBS") {
+                Ok(value) => value
+                    .parse()
+                    .context("PDF_MAX_CONCURRENT_JOBS must be a number")?,
+                Err(_) => std::thread::available_parallelism().map_or(4, |n| n.get()),
+            },
+            pdftoppm_path: env::var("PDFTOPPM_PATH").unwrap_or_else(|_| "pdftoppm".to_string()),
+            preview_max_width: env::var("PREVIEW_MAX_WIDTH")
+                .unwrap_or_else(|_| "2000".to_string())
+                .parse()
+                .context("PREVIEW_MAX_WIDTH must be a number")?,
+            hash_algorithm: env::var("HASH_ALGORITHM")
+                .unwrap_or_else(|_| "SHA256".to_string())
+                .parse()
+                .context("HASH_ALGORITHM must be SHA256, SHA512 or BLAKE3")?,
             public_url: env::var("PUBLIC_URL")
                 .unwrap_or_else(|_| "http://localhost:5173".to_string()),
+            api_docs_enabled: env::var("API_DOCS_ENABLED")
+                .unwrap_or_else(|_| "false".to_string())
+                .parse()
+                .unwrap_or(false),
             rate_limit_rpm: env::var("RATE_LIMIT_RPM")
                 .unwrap_or_else(|_| "60".to_string())
                 .parse()
                 .context("RATE_LIMIT_RPM must be a number")?,
+            in_person_session_minutes: env::var("IN_PERSON_SESSION_MINUTES")
+                .unwrap_or_else(|_| "15".to_string())
+                .parse()
+                .context("IN_PERSON_SESSION_MINUTES must be a number")?,
+            signing_session_minutes: env::var("SIGNING_SESSION_MINUTES")
+                .unwrap_or_else(|_| "20".to_string())
+                .parse()
+                .context("SIGNING_SESSION_MINUTES must be a number")?,
+            signing_path_tokens: env::var("SIGNING_PATH_TOKENS")
+                .unwrap_or_else(|_| "true".to_string())
+                .parse()
+                .unwrap_or(true),
+            esign_consent_required: env::var("ESIGN_CONSENT_REQUIRED")
+                .unwrap_or_else(|_| "false".to_string())
+                .parse()
+                .unwrap_or(false),
+            esign_consent_text: env::var("ESIGN_CONSENT_TEXT")
+                .ok()
+                .filter(|t| !t.trim().is_empty())
+                .unwrap_or_else(|| DEFAULT_ESIGN_CONSENT_TEXT.to_string()),
+            esign_consent_version: env::var("ESIGN_CONSENT_VERSION")
+                .ok()
+                .filter(|v| !v.is_empty()),
+            share_link_expiry_hours: env::var("SHARE_LINK_EXPIRY_HOURS")
+                .unwrap_or_else(|_| "168".to_string())
+                .parse()
+                .context("SHARE_LINK_EXPIRY_HOURS must be a number")?,
+            email_change_expiry_hours: env::var("EMAIL_CHANGE_EXPIRY_HOURS")
+                .unwrap_or_else(|_| "24".to_string())
+                .parse()
+                .context("EMAIL_CHANGE_EXPIRY_HOURS must be a number")?,
+            invitation_expiry_hours: env::var("INVITATION_EXPIRY_HOURS")
+                .unwrap_or_else(|_| "72".to_string())
+                .parse()
+                .context("INVITATION_EXPIRY_HOURS must be a number")?,
+            max_signature_size_kb: env::var("MAX_SIGNATURE_SIZE_KB")
+                .unwrap_or_else(|_| "512".to_string())
+                .parse()
+                .context("MAX_SIGNATURE_SIZE_KB must be a number")?,
+            max_branding_logo_kb: env::var("MAX_BRANDING_LOGO_KB")
+                .unwrap_or_else(|_| "256".to_string())
+                .parse()
+                .context("MAX_BRANDING_LOGO_KB must be a number")?,
+            max_signing_progress_kb: env::var("MAX_SIGNING_PROGRESS_KB")
+                .unwrap_or_else(|_| "1024".to_string())
+                .parse()
+                .context("MAX_SIGNING_PROGRESS_KB must be a number")?,
+            max_signers_per_document: env::var("MAX_SIGNERS_PER_DOCUMENT")
+                .unwrap_or_else(|_| "50".to_string())
+                .parse()
+                .context("MAX_SIGNERS_PER_DOCUMENT must be a number")?,
+            signature_fonts_dir: env::var("SIGNATURE_FONTS_DIR")
+                .unwrap_or_else(|_| "./assets/fonts".to_string()),
+            display_timezone: env::var("DISPLAY_TIMEZONE")
+                .unwrap_or_else(|_| "UTC".to_string())
+                .parse()
+                .map_err(|_| anyhow::anyhow!("DISPLAY_TIMEZONE must be an IANA timezone"))?,
+            verification_code_minutes: env::var("VERIFICATION_CODE_MINUTES")
+                .unwrap_or_else(|_| "10".to_string())
+                .parse()
+                .context("VERIFICATION_CODE_MINUTES must be a number")?,
+            verification_max_attempts: env::var("VERIFICATION_MAX_ATTEMPTS")
+                .unwrap_or_else(|_| "5".to_string())
+                .parse()
+                .context("VERIFICATION_MAX_ATTEMPTS must be a number")?,
+            verification_lockout_minutes: env::var("VERIFICATION_LOCKOUT_MINUTES")
+                .unwrap_or_else(|_| "15".to_string())
+                .parse()
+                .context("VERIFICATION_LOCKOUT_MINUTES must be a number")?,
+            verified_session_minutes: env::var("VERIFIED_SESSION_MINUTES")
+                .unwrap_or_else(|_| "30".to_string())
+                .parse()
+                .context("VERIFIED_SESSION_MINUTES must be a number")?,
+            verification_providers: env::var("VERIFICATION_PROVIDERS")
+                .unwrap_or_else(|_| "email_otp".to_string())
+                .split(',')
+                .map(|p| p.trim().to_string())
+                .filter(|p| !p.is_empty())
+                .collect(),
+            geoip_database_path: env::var("GEOIP_DATABASE_PATH")
+                .ok()
+                .filter(|p| !p.is_empty()),
+            seal_certificate_path: env::var("SEAL_CERTIFICATE_PATH")
+                .ok()
+                .filter(|p| !p.is_empty()),
+            seal_private_key_path: env::var("SEAL_PRIVATE_KEY_PATH")
+                .ok()
+                .filter(|p| !p.is_empty()),
+            seal_certificate_password: env::var("SEAL_CERTIFICATE_PASSWORD").ok(),
+            tsa_url: env::var("TSA_URL").ok().filter(|u| !u.is_empty()),
+            tsa_timeout_secs: env::var("TSA_TIMEOUT_SECS")
+                .unwrap_or_else(|_| "10".to_string())
+                .parse()
+                .context("TSA_TIMEOUT_SECS must be a number")?,
+            tsa_ca_path: env::var("TSA_CA_PATH").ok().filter(|p| !p.is_empty()),
+            tsa_retry_interval_secs: env::var("TSA_RETRY_INTERVAL_SECS")
+                .unwrap_or_else(|_| "300".to_string())
+                .parse()
+                .context("TSA_RETRY_INTERVAL_SECS must be a number")?,
+            signing_key_path: env::var("SIGNING_KEY_PATH")
+                .ok()
+                .filter(|p| !p.is_empty())
+                .unwrap_or_else(|| "./data/keys/signing-key.pem".to_string()),
+            signing_retired_key_paths: env::var("SIGNING_RETIRED_KEY_PATHS")
+                .unwrap_or_default()
+                .split(',')
+                .map(|p| p.trim().to_string())
+                .filter(|p| !p.is_empty())
+                .collect(),
+            trusted_proxies: env::var("TRUSTED_PROXIES")
+                .unwrap_or_default()
+                .split(',')
+                .map(str::trim)
+                .filter(|p| !p.is_empty())
+                .map(|p| p.parse())
+                .collect::<Result<_, _>>()
+                .context("TRUSTED_PROXIES must be a comma-separated list of IPs or CIDR ranges")?,
+            ip_address_mode: env::var("IP_ADDRESS_MODE")
+                .unwrap_or_else(|_| "full".to_string())
+                .parse()
+                .context("IP_ADDRESS_MODE must be full, truncated or hashed")?,
+            ip_address_hash_key: env::var("IP_ADDRESS_HASH_KEY")
+                .ok()
+                .filter(|k| !k.is_empty()),
+            shutdown_drain_timeout_secs: env::var("SHUTDOWN_DRAIN_TIMEOUT_SECS")
+                .unwrap_or_else(|_| "30".to_string())
+                .parse()
+                .context("SHUTDOWN_DRAIN_TIMEOUT_SECS must be a number")?,
         })
     }
 
     pub fn max_file_size_bytes(&self) -> u64 {
         self.max_file_size_mb * 1024 * 1024
     }
+
+    pub fn pdf_limits(&self) -> PdfLimits {
+        PdfLimits {
+            max_pages: self.max_pdf_pages,
+            max_page_dimension: self.max_pdf_page_dimension_pt,
+        }
+    }
+
+    pub fn max_signature_size_bytes(&self) -> usize {
+        self.max_signature_size_kb * 1024
+    }
+
+    pub fn max_branding_logo_bytes(&self) -> usize {
+        self.max_branding_logo_kb * 1024
+    }
+
+    pub fn max_signing_progress_bytes(&self) -> usize {
+        self.max_signing_progress_kb * 1024
+    }
+
+    pub fn totp_key(&self) -> Result<[u8; 32]> {
+        match &self.totp_encryption_key {
+            Some(key) => BASE64
+                .decode(key)
+                .ok()
+                .and_then(|key| key.try_into().ok())
+                .context("TOTP_ENCRYPTION_KEY must be 32 bytes, base64-encoded"),
+            None => Ok(Sha256::digest(format!("signvault-totp:{}", self.jwt_secret)).into()),
+        }
+    }
+
+    /// Version of the consent text signers accept: `ESIGN_CONSENT_VERSION`,
+    /// or one derived from the text, so editing it asks signers again.
+    pub fn consent_version(&self) -> String {
+        match &self.esign_consent_version {
+            Some(version) => version.clone(),
+            None => {
+                let mut version = hex::encode(Sha256::digest(&self.esign_consent_text));
+                version.truncate(16);
+                version
+            }
+        }
+    }
+
+    /// Key of the HMAC recording IP addresses in `hashed` mode. Derived from
+    /// `JWT_SECRET` when unset.
+    pub fn ip_hash_key(&self) -> [u8; 32] {
+        match &self.ip_address_hash_key {
+            Some(key) => Sha256::digest(key).into(),
+            None => Sha256::digest(format!("signvault-ip:{}", self.jwt_secret)).into(),
+        }
+    }
+
+    /// Master keys for encrypting stored files, the current one first. Empty
+    /// when storage encryption is off.
+    pub fn storage_encryption_keys(&self) -> Result<Vec<[u8; 32]>> {
+        let decode = |name: &str, key: &str| -> Result<[u8; 32]> {
+            BASE64
+                .decode(key)
+                .ok()
+                .and_then(|key| key.try_into().ok())
+                .with_context(|| format!("{} must be 32 bytes, base64-encoded", name))
+        };
+
+        match (
+            &self.storage_encryption_key,
+            &self.storage_encryption_previous_key,
+        ) {
+            (None, None) => Ok(Vec::new()),
+            (None, Some(_)) => Err(anyhow::anyhow!(
+                "STORAGE_ENCRYPTION_PREVIOUS_KEY requires STORAGE_ENCRYPTION_KEY"
+            )),
+            (Some(key), previous) => {
+                let mut keys = vec![decode("STORAGE_ENCRYPTION_KEY", key)?];
+                if let Some(previous) = previous {
+                    keys.push(decode("STORAGE_ENCRYPTION_PREVIOUS_KEY", previous)?);
+                }
+                Ok(keys)
+            }
+        }
+    }
+}
+
+/// Days after which documents in each status are purged. `None` keeps them
+/// forever.
+#[derive(Clone, Debug)]
+pub struct RetentionPolicy {
+    pub draft_days: Option<i64>,
+    pub pending_days: Option<i64>,
+    pub completed_days: Option<i64>,
+    pub voided_days: Option<i64>,
+    pub expired_days: Option<i64>,
+}
+
+impl RetentionPolicy {
+    pub fn days_for(&self, status: DocumentStatus) -> Option<i64> {
+        match status {
+            DocumentStatus::Draft => self.draft_days,
+            DocumentStatus::Pending => self.pending_days,
+            DocumentStatus::Completed => self.completed_days,
+            DocumentStatus::Voided => self.voided_days,
+            DocumentStatus::Expired => self.expired_days,
+        }
+    }
+}
+
+/// Reads a retention period in days; empty means keep forever.
+fn retention_days(name: &str, default: &str) -> Result<Option<i64>> {
+    let value = env::var(name).unwrap_or_else(|_| default.to_string());
+    let value = value.trim();
+
+    if value.is_empty() {
+        return Ok(None);
+    }
+
+    value
+        .parse()
+        .map(Some)
+        .with_context(|| format!("{} must be a number of days or empty", name))
 }
@@ -1,19 +1,134 @@
-use sha2::{Digest, Sha256};
+use chrono::{DateTime, SecondsFormat, Utc};
+use futures::{Stream, TryStreamExt};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256, Sha512};
+use std::fmt;
 use std::io::Read;
+use std::str::FromStr;
+use thiserror::Error;
 use uuid::Uuid;
 
-pub fn hash_data(data: &[u8]) -> String {
-    let mut hasher = Sha256::new();
+use crate::models::audit::{
+    AuditAction, CertificateApproval, CertificateAuditEntry, CertificateSigner,
+};
+use crate::services::canonical_json;
+
+/// Marks hashes computed over canonical JSON (`v2:sha256:...`). Audit entry
+/// and certificate hashes without it were computed over colon separated
+/// strings.
+pub const CANONICAL_HASH_PREFIX: &str = "v2:";
+
+/// Algorithm used for new hashes, from `HASH_ALGORITHM`. Stored hashes are
+/// prefixed with the algorithm that produced them (`sha512:...`), so they
+/// keep verifying after it changes; unprefixed hashes predate the prefix and
+/// are SHA-256.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HashAlgorithm {
+    Sha256,
+    Sha512,
+    Blake3,
+}
+
+#[derive(Debug, Error)]
+#[error("unknown hash algorithm {0:?}, expected SHA256, SHA512 or BLAKE3")]
+pub struct UnknownHashAlgorithm(String);
+
+impl HashAlgorithm {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Sha256 => "sha256",
+            Self::Sha512 => "sha512",
+            Self::Blake3 => "blake3",
+        }
+    }
+
+    fn hasher(self) -> Hasher {
+        match self {
+            Self::Sha256 => Hasher::Sha256(Sha256::new()),
+            Self::Sha512 => Hasher::Sha512(Sha512::new()),
+            Self::Blake3 => Hasher::Blake3(Box::new(blake3::Hasher::new())),
+        }
+    }
+}
+
+impl fmt::Display for HashAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for HashAlgorithm {
+    type Err = UnknownHashAlgorithm;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().replace('-', "").as_str() {
+            "sha256" => Ok(Self::Sha256),
+            "sha512" => Ok(Self::Sha512),
+            "blake3" => Ok(Self::Blake3),
+            _ => Err(UnknownHashAlgorithm(s.to_string())),
+        }
+    }
+}
+
+enum Hasher {
+    Sha256(Sha256),
+    Sha512(Sha512),
+    Blake3(Box<blake3::Hasher>),
+}
+
+impl Hasher {
+    fn update(&mut self, data: &[u8]) {
+        match self {
+            Self::Sha256(hasher) => hasher.update(data),
+            Self::Sha512(hasher) => hasher.update(data),
+            Self::Blake3(hasher) => {
+                hasher.update(data);
+            }
+        }
+    }
+
+    /// The prefixed hex digest.
+    fn finalize(self) -> String {
+        let (algorithm, digest) = match self {
+            Self::Sha256(hasher) => (HashAlgorithm::Sha256, hasher.finalize().to_vec()),
+            Self::Sha512(hasher) => (HashAlgorithm::Sha512, hasher.finalize().to_vec()),
+            Self::Blake3(hasher) => (HashAlgorithm::Blake3, hasher.finalize().as_bytes().to_vec()),
+        };
+        format!("{}:{}", algorithm, hex::encode(digest))
+    }
+}
+
+/// Splits a stored hash into its algorithm and hex digest.
+pub fn parse_hash(hash: &str) -> Result<(HashAlgorithm, &str), UnknownHashAlgorithm> {
+    let hash = hash.strip_prefix(CANONICAL_HASH_PREFIX).unwrap_or(hash);
+    match hash.split_once(':') {
+        Some((algorithm, digest)) => Ok((algorithm.parse()?, digest)),
+        None => Ok((HashAlgorithm::Sha256, hash)),
+    }
+}
+
+/// Whether `data` hashes to `hash` with the algorithm recorded in `hash`.
+pub fn verify_data(hash: &str, data: &[u8]) -> Result<bool, UnknownHashAlgorithm> {
+    let (algorithm, digest) = parse_hash(hash)?;
+    Ok(parse_hash(&hash_data(algorithm, data))?.1 == digest)
+}
+
+pub fn verify_string(hash: &str, data: &str) -> Result<bool, UnknownHashAlgorithm> {
+    verify_data(hash, data.as_bytes())
+}
+
+pub fn hash_data(algorithm: HashAlgorithm, data: &[u8]) -> String {
+    let mut hasher = algorithm.hasher();
     hasher.update(data);
-    hex::encode(hasher.finalize())
+    hasher.finalize()
 }
 
-pub fn hash_string(data: &str) -> String {
-    hash_data(data.as_bytes())
+pub fn hash_string(algorithm: HashAlgorithm, data: &str) -> String {
+    hash_data(algorithm, data.as_bytes())
 }
 
-pub fn hash_file<R: Read>(mut reader: R) -> anyhow::Result<String> {
-    let mut hasher = Sha256::new();
+pub fn hash_file<R: Read>(algorithm: HashAlgorithm, mut reader: R) -> anyhow::Result<String> {
+    let mut hasher = algorithm.hasher();
     let mut buffer = [0u8; 8192];
 
     loop {
@@ -24,7 +139,36 @@ pub fn hash_file<R: Read>(mut reader: R) -> anyhow::Result<String> {
         hasher.update(&buffer[..bytes_read]);
     }
 
-    Ok(hex::encode(hasher.finalize()))
+    Ok(hasher.finalize())
+}
+
+/// Hashes a byte stream chunk by chunk without buffering it.
+pub async fn hash_stream<S, B>(algorithm: HashAlgorithm, stream: S) -> anyhow::Result<String>
+where
+    S: Stream<Item = std::io::Result<B>> + Unpin,
+    B: AsRef<[u8]>,
+{
+    Ok(hash_stream_each(&[algorithm], stream).await?.remove(0))
+}
+
+/// Hashes a byte stream once with each of `algorithms`, in a single pass.
+pub async fn hash_stream_each<S, B>(
+    algorithms: &[HashAlgorithm],
+    mut stream: S,
+) -> anyhow::Result<Vec<String>>
+where
+    S: Stream<Item = std::io::Result<B>> + Unpin,
+    B: AsRef<[u8]>,
+{
+    let mut hashers: Vec<Hasher> = algorithms.iter().map(|a| a.hasher()).collect();
+
+    while let Some(chunk) = stream.try_next().await? {
+        for hasher in &mut hashers {
+            hasher.update(chunk.as_ref());
+        }
+    }
+
+    Ok(hashers.into_iter().map(Hasher::finalize).collect())
 }
 
 pub fn generate_access_token() -> String {
@@ -33,27 +177,80 @@ pub fn generate_access_token() -> String {
     format!("{}{}", uuid1.simple(), uuid2.simple())
 }
 
-pub fn compute_audit_hash(
-    document_id: &Uuid,
-    action: &str,
-    timestamp: &str,
-    previous_hash: Option<&str>,
-    details: Option<&str>,
-) -> String {
-    let mut data = format!("{}:{}:{}", document_id, action, timestamp);
+/// Lookup key for a token that is stored hashed. Always SHA-256, so links keep
+/// resolving when `HASH_ALGORITHM` changes.
+pub fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
 
-    if let Some(prev) = previous_hash {
-        data.push_str(&format!(":{}", prev));
-    }
+/// Generates a random 6-digit one-time code.
+pub fn generate_verification_code() -> String {
+    let value = u128::from_le_bytes(*Uuid::new_v4().as_bytes());
+    format!("{:06}", value % 1_000_000)
+}
 
-    if let Some(det) = details {
-        data.push_str(&format!(":{}", det));
-    }
+pub fn is_canonical_hash(hash: &str) -> bool {
+    hash.starts_with(CANONICAL_HASH_PREFIX)
+}
 
-    hash_string(&data)
+fn hash_canonical(algorithm: HashAlgorithm, value: &Value) -> String {
+    let data = canonical_json::to_string(value).expect("JSON values always serialize");
+    format!("{}{}", CANONICAL_HASH_PREFIX, hash_string(algorithm, &data))
 }
 
+/// Hash of an audit entry, over the canonical JSON of exactly these fields.
+/// `timestamp` is hashed to the microsecond, the precision it is stored at.
+pub fn compute_audit_hash(
+    algorithm: HashAlgorithm,
+    document_id: Uuid,
+    sequence: i64,
+    action: AuditAction,
+    timestamp: DateTime<Utc>,
+    previous_hash: Option<&str>,
+    details: Option<&Value>,
+) -> String {
+    hash_canonical(
+        algorithm,
+        &json!({
+            "document_id": document_id,
+            "sequence": sequence,
+            "action": action,
+            "timestamp": timestamp.to_rfc3339_opts(SecondsFormat::Micros, true),
+            "previous_hash": previous_hash,
+            "details": details,
+        }),
+    )
+}
+
+/// Hash of a certificate, over the canonical JSON of exactly these fields,
+/// so it can be recomputed from the certificate itself.
 pub fn compute_certificate_hash(
+    algorithm: HashAlgorithm,
+    document_id: Uuid,
+    document_hash: &str,
+    signers: &[CertificateSigner],
+    approvals: &[CertificateApproval],
+    audit_trail: &[CertificateAuditEntry],
+    generated_at: DateTime<Utc>,
+) -> String {
+    hash_canonical(
+        algorithm,
+        &json!({
+            "document_id": document_id,
+            "document_hash": document_hash,
+            "signers": signers,
+            "approvals": approvals,
+            "audit_trail": audit_trail,
+            "generated_at": generated_at.to_rfc3339_opts(SecondsFormat::Micros, true),
+        }),
+    )
+}
+
+/// The certificate hash as computed before `v2`, kept to verify older
+/// certificates: the SHA-256 of the parts joined with colons. The signers and
+/// audit trail are serde_json output of the structs of the time, kept as
+/// `LegacyCertificateSigner` and `LegacyCertificateAuditEntry`.
+pub fn compute_legacy_certificate_hash(
     document_id: &Uuid,
     document_hash: &str,
     signers_data: &str,
@@ -64,7 +261,7 @@ pub fn compute_certificate_hash(
         "CERT:{}:{}:{}:{}:{}",
         document_id, document_hash, signers_data, audit_data, generated_at
     );
-    hash_string(&data)
+    hash_string(HashAlgorithm::Sha256, &data)
 }
 
 #[cfg(test)]
@@ -73,12 +270,77 @@ mod tests {
 
     #[test]
     fn test_hash_string() {
-        let hash = hash_string("hello world");
-        assert_eq!(hash.len(), 64);
+        let hash = hash_string(HashAlgorithm::Sha256, "hello world");
         assert_eq!(
             hash,
-            "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9"
+            "sha256:b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9"
+        );
+
+        let hash = hash_string(HashAlgorithm::Sha512, "hello world");
+        assert!(hash.starts_with("sha512:309ecc489c12d6eb4cc40f50c902f2b4"));
+        assert_eq!(hash.len(), 7 + 128);
+
+        assert_eq!(
+            hash_string(HashAlgorithm::Blake3, "hello world"),
+            "blake3:d74981efa70a0c880b8d8c1985d075dbcbf679b99a5f9914e5aaf96b831a9e24"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_hash_stream_matches_hash_data() {
+        for algorithm in [
+            HashAlgorithm::Sha256,
+            HashAlgorithm::Sha512,
+            HashAlgorithm::Blake3,
+        ] {
+            let chunks: Vec<std::io::Result<&[u8]>> = vec![Ok(b"hello "), Ok(b"world")];
+            let hash = hash_stream(algorithm, futures::stream::iter(chunks))
+                .await
+                .unwrap();
+            assert_eq!(hash, hash_string(algorithm, "hello world"));
+            assert_eq!(hash_file(algorithm, &b"hello world"[..]).unwrap(), hash);
+        }
+
+        let algorithms = [HashAlgorithm::Blake3, HashAlgorithm::Sha256];
+        let chunks: Vec<std::io::Result<&[u8]>> = vec![Ok(b"hello "), Ok(b"world")];
+        let hashes = hash_stream_each(&algorithms, futures::stream::iter(chunks))
+            .await
+            .unwrap();
+        assert_eq!(
+            hashes,
+            algorithms.map(|algorithm| hash_string(algorithm, "hello world"))
+        );
+    }
+
+    #[test]
+    fn test_verify_historical_and_prefixed_hashes() {
+        let legacy = "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9";
+        assert!(verify_string(legacy, "hello world").unwrap());
+        assert!(!verify_string(legacy, "hello world!").unwrap());
+
+        let hash = hash_string(HashAlgorithm::Blake3, "hello world");
+        assert!(verify_string(&hash, "hello world").unwrap());
+        assert!(!verify_string(&hash, "hello").unwrap());
+
+        assert!(verify_string("md5:5eb63bbbe01eeed093cb22bb8f5acdc3", "hello world").is_err());
+    }
+
+    #[test]
+    fn test_parse_hash_algorithm() {
+        assert_eq!(
+            "SHA256".parse::<HashAlgorithm>().unwrap(),
+            HashAlgorithm::Sha256
         );
+        assert_eq!(
+            "sha-512".parse::<HashAlgorithm>().unwrap(),
+            HashAlgorithm::Sha512
+        );
+        assert_eq!(
+            "BLAKE3".parse::<HashAlgorithm>().unwrap(),
+            HashAlgorithm::Blake3
+        );
+        assert!("SHA384".parse::<HashAlgorithm>().is_err());
+        assert!("".parse::<HashAlgorithm>().is_err());
     }
 
     #[test]
@@ -88,10 +350,120 @@ mod tests {
     }
 
     #[test]
-    fn test_audit_hash_consistency() {
-        let doc_id = Uuid::new_v4();
-        let hash1 = compute_audit_hash(&doc_id, "created", "2024-01-01T00:00:00Z", None, None);
-        let hash2 = compute_audit_hash(&doc_id, "created", "2024-01-01T00:00:00Z", None, None);
-        assert_eq!(hash1, hash2);
+    fn test_hash_token() {
+        assert_eq!(
+            hash_token("hello world"),
+            "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9"
+        );
+    }
+
+    #[test]
+    fn test_generate_verification_code() {
+        let code = generate_verification_code();
+        assert_eq!(code.len(), 6);
+        assert!(code.chars().all(|c| c.is_ascii_digit()));
+    }
+
+    fn fixed_time() -> DateTime<Utc> {
+        "2024-01-01T12:30:45.123456Z".parse().unwrap()
+    }
+
+    #[test]
+    fn test_audit_hash_is_pinned() {
+        let document_id: Uuid = "6f1c2b9e-8a4d-4c3b-9f0e-2d7a5b1c3e4f".parse().unwrap();
+        let details = json!({ "request_id": "req-1", "field_id": "f-1", "x": 150.0 });
+
+        let first = compute_audit_hash(
+            HashAlgorithm::Sha256,
+            document_id,
+            1,
+            AuditAction::DocumentCreated,
+            fixed_time(),
+            None,
+            None,
+        );
+        assert_eq!(
+            first,
+            "v2:sha256:bdd032b860a7a4c47553e2cb5675f495907a61d44fb7da554325185f252b5876"
+        );
+
+        let second = compute_audit_hash(
+            HashAlgorithm::Sha256,
+            document_id,
+            2,
+            AuditAction::FieldAdded,
+            fixed_time(),
+            Some(&first),
+            Some(&details),
+        );
+        assert_eq!(
+            second,
+            "v2:sha256:c578e6c55ce434cce51905c4f319407f0a7df6a0246c839a4f2784d9938d7f0d"
+        );
+
+        // Key order and float spelling do not change the hash.
+        let reordered = json!({ "x": 150, "field_id": "f-1", "request_id": "req-1" });
+        assert_eq!(
+            compute_audit_hash(
+                HashAlgorithm::Sha256,
+                document_id,
+                2,
+                AuditAction::FieldAdded,
+                fixed_time(),
+                Some(&first),
+                Some(&reordered),
+            ),
+            second
+        );
+
+        let blake3 = compute_audit_hash(
+            HashAlgorithm::Blake3,
+            document_id,
+            1,
+            AuditAction::DocumentCreated,
+            fixed_time(),
+            None,
+            None,
+        );
+        assert!(blake3.starts_with("v2:blake3:"));
+        assert_eq!(parse_hash(&blake3).unwrap().0, HashAlgorithm::Blake3);
+    }
+
+    #[test]
+    fn test_certificate_hashes_are_pinned() {
+        let document_id: Uuid = "6f1c2b9e-8a4d-4c3b-9f0e-2d7a5b1c3e4f".parse().unwrap();
+        let audit_trail = [CertificateAuditEntry {
+            action: "DocumentCreated".to_string(),
+            actor: Some("System".to_string()),
+            timestamp: fixed_time(),
+            ip_address: None,
+            device: None,
+            details: None,
+            changes: None,
+        }];
+
+        assert_eq!(
+            compute_certificate_hash(
+                HashAlgorithm::Sha256,
+                document_id,
+                "sha256:abc",
+                &[],
+                &[],
+                &audit_trail,
+                fixed_time(),
+            ),
+            "v2:sha256:13047a0544a55fbba126a87747c448fd49a1ea68b9db3f18d7b9fadeb8f86cb8"
+        );
+
+        assert_eq!(
+            compute_legacy_certificate_hash(
+                &document_id,
+                "sha256:abc",
+                "[]",
+                "[]",
+                "2024-01-01T12:30:45.123456+00:00",
+            ),
+            "sha256:188beb455fbc9759d14d56e607f437fec597a8785e239ad7896c14cd72a38839"
+        );
     }
 }
@@ -0,0 +1,71 @@
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+
+/// Times shown to people carry the zone's abbreviation and UTC offset, so the
+/// hour repeated when clocks go back reads unambiguously, e.g.
+/// `2024-11-03 01:30:00 EST (UTC-05:00)`.
+const FORMAT: &str = "%Y-%m-%d %H:%M:%S %Z (UTC%:z)";
+
+/// Formats an instant in `timezone` for people to read. Stored times stay in
+/// UTC; this only presents them.
+pub fn format(at: DateTime<Utc>, timezone: Tz) -> String {
+    at.with_timezone(&timezone).format(FORMAT).to_string()
+}
+
+/// The timezone named by a user's setting, or `default` when they have none
+/// or it is no longer a known IANA name.
+pub fn resolve(name: Option<&str>, default: Tz) -> Tz {
+    name.and_then(|name| name.parse().ok()).unwrap_or(default)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use chrono_tz::America::New_York;
+
+    fn utc(y: i32, m: u32, d: u32, h: u32, min: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(y, m, d, h, min, 0).unwrap()
+    }
+
+    #[test]
+    fn test_repeated_hour_on_fall_back_day_is_unambiguous() {
+        // Clocks in New York went back from 02:00 EDT to 01:00 EST on
+        // 2024-11-03, so 01:30 happened twice
+        assert_eq!(
+            format(utc(2024, 11, 3, 5, 30), New_York),
+            "2024-11-03 01:30:00 EDT (UTC-04:00)"
+        );
+        assert_eq!(
+            format(utc(2024, 11, 3, 6, 30), New_York),
+            "2024-11-03 01:30:00 EST (UTC-05:00)"
+        );
+    }
+
+    #[test]
+    fn test_spring_forward_skips_the_missing_hour() {
+        assert_eq!(
+            format(utc(2024, 3, 10, 6, 59), New_York),
+            "2024-03-10 01:59:00 EST (UTC-05:00)"
+        );
+        assert_eq!(
+            format(utc(2024, 3, 10, 7, 0), New_York),
+            "2024-03-10 03:00:00 EDT (UTC-04:00)"
+        );
+    }
+
+    #[test]
+    fn test_utc() {
+        assert_eq!(
+            format(utc(2024, 11, 3, 6, 30), Tz::UTC),
+            "2024-11-03 06:30:00 UTC (UTC+00:00)"
+        );
+    }
+
+    #[test]
+    fn test_resolve_falls_back_to_default() {
+        assert_eq!(resolve(Some("America/New_York"), Tz::UTC), New_York);
+        assert_eq!(resolve(Some("Mars/Olympus_Mons"), Tz::UTC), Tz::UTC);
+        assert_eq!(resolve(None, New_York), New_York);
+    }
+}
@@ -1,18 +1,56 @@
 use anyhow::Result;
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
 use lettre::{
-    message::{header::ContentType, Mailbox},
+    message::{header::ContentType, Attachment, Mailbox, MultiPart, SinglePart},
     transport::smtp::authentication::Credentials,
     AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor,
 };
-use tracing::{error, info};
+use tracing::{error, info, warn};
+use uuid::Uuid;
 
+use crate::models::branding::UserBranding;
+use crate::models::document::Document;
+use crate::models::signer::{Signer, SignerStatus};
 use crate::services::config::Config;
+use crate::services::i18n::{self, EmailText, Locale, Messages};
+use crate::services::{display_time, ics};
+
+/// A signer's deadline, attached to the emails they get as a calendar event.
+/// Each signer of a document has one event, which later emails update or
+/// cancel.
+pub struct SignerDeadline {
+    pub document_id: Uuid,
+    pub signer_id: Uuid,
+    pub due_at: DateTime<Utc>,
+    pub sequence: i64,
+}
+
+impl SignerDeadline {
+    /// `None` when the document has no deadline. Calendars only apply an
+    /// update whose `sequence` is higher than the last they saw.
+    pub fn of(document: &Document, signer: &Signer, sequence: i64) -> Option<Self> {
+        document.expires_at.map(|due_at| Self {
+            document_id: document.id,
+            signer_id: signer.id,
+            due_at,
+            sequence,
+        })
+    }
+}
+
+/// An iCalendar file attached to an email.
+struct Calendar {
+    method: ics::Method,
+    content: String,
+}
 
 pub struct EmailService {
     transport: AsyncSmtpTransport<Tokio1Executor>,
     from_email: String,
     from_name: String,
     public_url: String,
+    default_locale: Locale,
 }
 
 impl EmailService {
@@ -36,207 +74,1804 @@ impl EmailService {
             from_email: config.smtp_from_email.clone(),
             from_name: config.smtp_from_name.clone(),
             public_url: config.public_url.clone(),
+            default_locale: config.default_locale,
         })
     }
 
+    /// Language of emails to owners, and to signers whose document has none.
+    pub fn default_locale(&self) -> Locale {
+        self.default_locale
+    }
+
+    #[allow(clippy::too_many_arguments)]
     pub async fn send_signing_request(
         &self,
         to_email: &str,
         to_name: &str,
+        locale: Locale,
         document_title: &str,
         sender_name: &str,
         access_token: &str,
+        branding: Option<&UserBranding>,
+        reply_to: Option<&Mailbox>,
+        deadline: Option<&SignerDeadline>,
     ) -> Result<()> {
         let signing_url = format!("{}/sign/{}", self.public_url, access_token);
+        let m = locale.messages();
+        let text = &m.signing_request;
 
-        let subject = format!(
-            "{} has requested your signature on \"{}\"",
-            sender_name, document_title
+        let subject = i18n::fill(
+            text.subject,
+            &[
+                ("sender_name", sender_name),
+                ("document_title", document_title),
+            ],
         );
+        let greeting = i18n::fill(m.greeting, &[("to_name", to_name)]);
+        let footer = self.reply_footer(m, reply_to);
 
         let html_body = format!(
             r#"<!DOCTYPE html>
-<html>
+<html lang="{lang}">
 <head>
     <meta charset="utf-8">
     <meta name="viewport" content="width=device-width, initial-scale=1.0">
-    <title>Signature Request</title>
+    <title>{heading}</title>
 </head>
 <body style="font-family: Arial, sans-serif; line-height: 1.6; color: #333; max-width: 600px; margin: 0 auto; padding: 20px;">
-    <div style="background-color: #f8f9fa; padding: 20px; border-radius: 8px; margin-bottom: 20px;">
-        <h1 style="color: #2563eb; margin: 0 0 10px 0; font-size: 24px;">Signature Request</h1>
-        <p style="margin: 0; color: #666;">You have received a document to sign</p>
+    {brand_header}<div style="background-color: #f8f9fa; padding: 20px; border-radius: 8px; margin-bottom: 20px;">
+        <h1 style="color: {color}; margin: 0 0 10px 0; font-size: 24px;">{heading}</h1>
+        <p style="margin: 0; color: #666;">{tagline}</p>
     </div>
 
-    <p>Hello {to_name},</p>
+    <p>{greeting}</p>
 
-    <p><strong>{sender_name}</strong> has requested your electronic signature on the following document:</p>
+    <p>{body}</p>
 
     <div style="background-color: #e8f4fd; padding: 15px; border-radius: 8px; margin: 20px 0;">
         <p style="margin: 0; font-weight: bold; color: #1e40af;">{document_title}</p>
     </div>
 
-    <p>Please click the button below to review and sign the document:</p>
+    <p>{sign_prompt}</p>
 
     <div style="text-align: center; margin: 30px 0;">
-        <a href="{signing_url}" style="background-color: #2563eb; color: white; padding: 14px 28px; text-decoration: none; border-radius: 6px; font-weight: bold; display: inline-block;">Review & Sign Document</a>
+        <a href="{signing_url}" style="background-color: {color}; color: white; padding: 14px 28px; text-decoration: none; border-radius: 6px; font-weight: bold; display: inline-block;">{sign_button}</a>
     </div>
 
-    <p style="font-size: 14px; color: #666;">If the button doesn't work, copy and paste this link into your browser:</p>
+    <p style="font-size: 14px; color: #666;">{button_fallback}</p>
     <p style="font-size: 12px; color: #888; word-break: break-all;">{signing_url}</p>
 
     <hr style="border: none; border-top: 1px solid #eee; margin: 30px 0;">
 
     <p style="font-size: 12px; color: #888;">
-        This is an automated message from {from_name}. Please do not reply to this email.
+        {footer}
         <br><br>
-        Your electronic signature is legally binding under the ESIGN Act (USA) and eIDAS Regulation (EU).
+        {legally_binding}
     </p>
 </body>
 </html>"#,
-            to_name = to_name,
-            sender_name = sender_name,
+            lang = locale.code(),
+            brand_header = self.brand_header(branding),
+            color = brand_color(branding, "#2563eb"),
+            heading = text.heading,
+            tagline = text.tagline,
+            greeting = greeting,
+            body = i18n::fill(text.body, &[("sender_name", &strong(sender_name))]),
             document_title = document_title,
+            sign_prompt = m.sign_prompt_html,
             signing_url = signing_url,
-            from_name = self.from_name
+            sign_button = m.sign_button,
+            button_fallback = m.button_fallback,
+            footer = footer,
+            legally_binding = m.legally_binding
         );
 
         let plain_body = format!(
-            r#"Signature Request
+            r#"{heading}
 
-Hello {to_name},
+{greeting}
 
-{sender_name} has requested your electronic signature on the following document:
+{body}
 
 {document_title}
 
-Please visit the following link to review and sign the document:
+{sign_prompt}
 {signing_url}
 
-Your electronic signature is legally binding under the ESIGN Act (USA) and eIDAS Regulation (EU).
+{legally_binding}
 
 ---
-This is an automated message from {from_name}. Please do not reply to this email."#,
-            to_name = to_name,
-            sender_name = sender_name,
+{footer}"#,
+            heading = text.heading,
+            greeting = greeting,
+            body = i18n::fill(text.body, &[("sender_name", sender_name)]),
             document_title = document_title,
+            sign_prompt = m.sign_prompt_plain,
             signing_url = signing_url,
-            from_name = self.from_name
+            legally_binding = m.legally_binding,
+            footer = footer
         );
 
-        self.send_email(to_email, to_name, &subject, &html_body, &plain_body)
-            .await
+        let calendar = deadline.map(|deadline| {
+            self.deadline_calendar(
+                m,
+                document_title,
+                deadline,
+                Some(&signing_url),
+                ics::Method::Publish,
+            )
+        });
+
+        self.send_email_from(
+            &self.sender_name(branding),
+            reply_to,
+            to_email,
+            to_name,
+            &subject,
+            &html_body,
+            &plain_body,
+            calendar,
+        )
+        .await
     }
 
-    pub async fn send_completion_notification(
+    #[allow(clippy::too_many_arguments)]
+    pub async fn send_signing_reminder(
         &self,
         to_email: &str,
         to_name: &str,
+        locale: Locale,
         document_title: &str,
+        sender_name: &str,
+        access_token: &str,
+        reply_to: Option<&Mailbox>,
     ) -> Result<()> {
-        let subject = format!("Document \"{}\" has been fully signed", document_title);
+        let signing_url = format!("{}/sign/{}", self.public_url, access_token);
+        let m = locale.messages();
+        let text = &m.signing_reminder;
+
+        let subject = i18n::fill(
+            text.subject,
+            &[
+                ("sender_name", sender_name),
+                ("document_title", document_title),
+            ],
+        );
+        let greeting = i18n::fill(m.greeting, &[("to_name", to_name)]);
+        let footer = self.reply_footer(m, reply_to);
 
         let html_body = format!(
             r#"<!DOCTYPE html>
-<html>
+<html lang="{lang}">
 <head>
     <meta charset="utf-8">
     <meta name="viewport" content="width=device-width, initial-scale=1.0">
-    <title>Document Completed</title>
+    <title>{heading}</title>
 </head>
 <body style="font-family: Arial, sans-serif; line-height: 1.6; color: #333; max-width: 600px; margin: 0 auto; padding: 20px;">
-    <div style="background-color: #d4edda; padding: 20px; border-radius: 8px; margin-bottom: 20px;">
-        <h1 style="color: #155724; margin: 0 0 10px 0; font-size: 24px;">Document Completed</h1>
-        <p style="margin: 0; color: #155724;">All parties have signed the document</p>
+    <div style="background-color: #f8f9fa; padding: 20px; border-radius: 8px; margin-bottom: 20px;">
+        <h1 style="color: #2563eb; margin: 0 0 10px 0; font-size: 24px;">{heading}</h1>
+        <p style="margin: 0; color: #666;">{tagline}</p>
     </div>
 
-    <p>Hello {to_name},</p>
+    <p>{greeting}</p>
 
-    <p>Great news! The following document has been signed by all parties:</p>
+    <p>{body}</p>
 
     <div style="background-color: #e8f4fd; padding: 15px; border-radius: 8px; margin: 20px 0;">
         <p style="margin: 0; font-weight: bold; color: #1e40af;">{document_title}</p>
     </div>
 
-    <p>You can download the signed document and certificate of completion from your dashboard.</p>
+    <p>{sign_prompt}</p>
 
     <div style="text-align: center; margin: 30px 0;">
-        <a href="{dashboard_url}" style="background-color: #28a745; color: white; padding: 14px 28px; text-decoration: none; border-radius: 6px; font-weight: bold; display: inline-block;">View Dashboard</a>
+        <a href="{signing_url}" style="background-color: #2563eb; color: white; padding: 14px 28px; text-decoration: none; border-radius: 6px; font-weight: bold; display: inline-block;">{sign_button}</a>
     </div>
 
+    <p style="font-size: 14px; color: #666;">{button_fallback}</p>
+    <p style="font-size: 12px; color: #888; word-break: break-all;">{signing_url}</p>
+
     <hr style="border: none; border-top: 1px solid #eee; margin: 30px 0;">
 
     <p style="font-size: 12px; color: #888;">
-        This is an automated message from {from_name}. Please do not reply to this email.
+        {footer}
     </p>
 </body>
 </html>"#,
-            to_name = to_name,
+            lang = locale.code(),
+            heading = text.heading,
+            tagline = text.tagline,
+            greeting = greeting,
+            body = i18n::fill(text.body, &[("sender_name", &strong(sender_name))]),
             document_title = document_title,
-            dashboard_url = self.public_url,
-            from_name = self.from_name
+            sign_prompt = m.sign_prompt_html,
+            signing_url = signing_url,
+            sign_button = m.sign_button,
+            button_fallback = m.button_fallback,
+            footer = footer
         );
 
         let plain_body = format!(
-            r#"Document Completed
+            r#"{heading}
 
-Hello {to_name},
+{greeting}
 
-Great news! The following document has been signed by all parties:
+{body}
 
 {document_title}
 
-You can download the signed document and certificate of completion from your dashboard at:
-{dashboard_url}
+{sign_prompt}
+{signing_url}
 
 ---
-This is an automated message from {from_name}. Please do not reply to this email."#,
-            to_name = to_name,
+{footer}"#,
+            heading = text.heading,
+            greeting = greeting,
+            body = i18n::fill(text.body, &[("sender_name", sender_name)]),
             document_title = document_title,
-            dashboard_url = self.public_url,
-            from_name = self.from_name
+            sign_prompt = m.sign_prompt_plain,
+            signing_url = signing_url,
+            footer = footer
         );
 
-        self.send_email(to_email, to_name, &subject, &html_body, &plain_body)
-            .await
+        self.send_email_from(
+            &self.from_name,
+            reply_to,
+            to_email,
+            to_name,
+            &subject,
+            &html_body,
+            &plain_body,
+            None,
+        )
+        .await
     }
 
-    async fn send_email(
+    pub async fn send_request_withdrawn(
         &self,
         to_email: &str,
         to_name: &str,
-        subject: &str,
-        html_body: &str,
-        _plain_body: &str,
+        locale: Locale,
+        document_title: &str,
+        sender_name: &str,
+        deadline: Option<&SignerDeadline>,
     ) -> Result<()> {
-        let from: Mailbox = format!("{} <{}>", self.from_name, self.from_email).parse()?;
-        let to: Mailbox = format!("{} <{}>", to_name, to_email).parse()?;
+        let m = locale.messages();
+        let text = &m.request_withdrawn;
 
-        let email = Message::builder()
-            .from(from)
-            .to(to)
-            .subject(subject)
-            .header(ContentType::TEXT_HTML)
-            .body(html_body.to_string())?;
+        let subject = i18n::fill(
+            text.subject,
+            &[
+                ("sender_name", sender_name),
+                ("document_title", document_title),
+            ],
+        );
+        let greeting = i18n::fill(m.greeting, &[("to_name", to_name)]);
+        let footer = self.footer(m);
 
-        match self.transport.send(email).await {
-            Ok(_) => {
-                info!("Email sent successfully to {}", to_email);
-                Ok(())
-            }
-            Err(e) => {
-                error!("Failed to send email to {}: {}", to_email, e);
-                Err(anyhow::anyhow!("Failed to send email: {}", e))
-            }
-        }
+        let html_body = format!(
+            r#"<!DOCTYPE html>
+<html lang="{lang}">
+<head>
+    <meta charset="utf-8">
+    <meta name="viewport" content="width=device-width, initial-scale=1.0">
+    <title>{heading}</title>
+</head>
+<body style="font-family: Arial, sans-serif; line-height: 1.6; color: #333; max-width: 600px; margin: 0 auto; padding: 20px;">
+    <div style="background-color: #f8f9fa; padding: 20px; border-radius: 8px; margin-bottom: 20px;">
+        <h1 style="color: #2563eb; margin: 0 0 10px 0; font-size: 24px;">{heading}</h1>
+        <p style="margin: 0; color: #666;">{tagline}</p>
+    </div>
+
+    <p>{greeting}</p>
+
+    <p>{body}</p>
+
+    <div style="background-color: #e8f4fd; padding: 15px; border-radius: 8px; margin: 20px 0;">
+        <p style="margin: 0; font-weight: bold; color: #1e40af;">{document_title}</p>
+    </div>
+
+    <p>{withdrawn_resend}</p>
+
+    <hr style="border: none; border-top: 1px solid #eee; margin: 30px 0;">
+
+    <p style="font-size: 12px; color: #888;">
+        {footer}
+    </p>
+</body>
+</html>"#,
+            lang = locale.code(),
+            heading = text.heading,
+            tagline = text.tagline,
+            greeting = greeting,
+            body = i18n::fill(text.body, &[("sender_name", &strong(sender_name))]),
+            document_title = document_title,
+            withdrawn_resend = m.withdrawn_resend,
+            footer = footer
+        );
+
+        let plain_body = format!(
+            r#"{heading}
+
+{greeting}
+
+{body}
+
+{document_title}
+
+{withdrawn_resend}
+
+---
+{footer}"#,
+            heading = text.heading,
+            greeting = greeting,
+            body = i18n::fill(text.body, &[("sender_name", sender_name)]),
+            document_title = document_title,
+            withdrawn_resend = m.withdrawn_resend,
+            footer = footer
+        );
+
+        let calendar = deadline.map(|deadline| {
+            self.deadline_calendar(m, document_title, deadline, None, ics::Method::Cancel)
+        });
+
+        self.send_email_from(
+            &self.from_name,
+            None,
+            to_email,
+            to_name,
+            &subject,
+            &html_body,
+            &plain_body,
+            calendar,
+        )
+        .await
     }
-}
 
-pub fn create_email_service(config: &Config) -> Result<Option<EmailService>> {
-    if config.smtp_host.is_empty() || config.smtp_host == "localhost" {
-        info!("Email service not configured, emails will be logged but not sent");
-        return Ok(None);
+    #[allow(clippy::too_many_arguments)]
+    pub async fn send_request_cancelled(
+        &self,
+        to_email: &str,
+        to_name: &str,
+        locale: Locale,
+        document_title: &str,
+        sender_name: &str,
+        reason: Option<&str>,
+        deadline: Option<&SignerDeadline>,
+    ) -> Result<()> {
+        self.send_cancellation(
+            &locale.messages().request_cancelled,
+            to_email,
+            to_name,
+            locale,
+            document_title,
+            sender_name,
+            reason,
+            deadline,
+        )
+        .await
     }
 
-    Ok(Some(EmailService::new(config)?))
+    /// Tells a signer they were taken off a document that others still sign.
+    pub async fn send_signer_removed(
+        &self,
+        to_email: &str,
+        to_name: &str,
+        locale: Locale,
+        document_title: &str,
+        sender_name: &str,
+        deadline: Option<&SignerDeadline>,
+    ) -> Result<()> {
+        self.send_cancellation(
+            &locale.messages().signer_removed,
+            to_email,
+            to_name,
+            locale,
+            document_title,
+            sender_name,
+            None,
+            deadline,
+        )
+        .await
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn send_cancellation(
+        &self,
+        text: &EmailText,
+        to_email: &str,
+        to_name: &str,
+        locale: Locale,
+        document_title: &str,
+        sender_name: &str,
+        reason: Option<&str>,
+        deadline: Option<&SignerDeadline>,
+    ) -> Result<()> {
+        let m = locale.messages();
+
+        let subject = i18n::fill(
+            text.subject,
+            &[
+                ("sender_name", sender_name),
+                ("document_title", document_title),
+            ],
+        );
+        let greeting = i18n::fill(m.greeting, &[("to_name", to_name)]);
+        let footer = self.footer(m);
+
+        let reason_html = reason
+            .map(|reason| {
+                format!(
+                    r#"<p>{}</p>
+
+    <blockquote style="margin: 20px 0; padding: 10px 15px; border-left: 4px solid #ddd; color: #555;">{}</blockquote>
+
+"#,
+                    m.cancel_reason, reason
+                )
+            })
+            .unwrap_or_default();
+
+        let html_body = format!(
+            r#"<!DOCTYPE html>
+<html lang="{lang}">
+<head>
+    <meta charset="utf-8">
+    <meta name="viewport" content="width=device-width, initial-scale=1.0">
+    <title>{heading}</title>
+</head>
+<body style="font-family: Arial, sans-serif; line-height: 1.6; color: #333; max-width: 600px; margin: 0 auto; padding: 20px;">
+    <div style="background-color: #f8f9fa; padding: 20px; border-radius: 8px; margin-bottom: 20px;">
+        <h1 style="color: #dc2626; margin: 0 0 10px 0; font-size: 24px;">{heading}</h1>
+        <p style="margin: 0; color: #666;">{tagline}</p>
+    </div>
+
+    <p>{greeting}</p>
+
+    <p>{body}</p>
+
+    <div style="background-color: #e8f4fd; padding: 15px; border-radius: 8px; margin: 20px 0;">
+        <p style="margin: 0; font-weight: bold; color: #1e40af;">{document_title}</p>
+    </div>
+
+    {reason_html}<p>{link_disabled}</p>
+
+    <hr style="border: none; border-top: 1px solid #eee; margin: 30px 0;">
+
+    <p style="font-size: 12px; color: #888;">
+        {footer}
+    </p>
+</body>
+</html>"#,
+            lang = locale.code(),
+            heading = text.heading,
+            tagline = text.tagline,
+            greeting = greeting,
+            body = i18n::fill(text.body, &[("sender_name", &strong(sender_name))]),
+            document_title = document_title,
+            reason_html = reason_html,
+            link_disabled = m.link_disabled,
+            footer = footer
+        );
+
+        let reason_plain = reason
+            .map(|reason| format!("{}\n\n{}\n\n", m.cancel_reason, reason))
+            .unwrap_or_default();
+
+        let plain_body = format!(
+            r#"{heading}
+
+{greeting}
+
+{body}
+
+{document_title}
+
+{reason_plain}{link_disabled}
+
+---
+{footer}"#,
+            heading = text.heading,
+            greeting = greeting,
+            body = i18n::fill(text.body, &[("sender_name", sender_name)]),
+            document_title = document_title,
+            reason_plain = reason_plain,
+            link_disabled = m.link_disabled,
+            footer = footer
+        );
+
+        let calendar = deadline.map(|deadline| {
+            self.deadline_calendar(m, document_title, deadline, None, ics::Method::Cancel)
+        });
+
+        self.send_email_from(
+            &self.from_name,
+            None,
+            to_email,
+            to_name,
+            &subject,
+            &html_body,
+            &plain_body,
+            calendar,
+        )
+        .await
+    }
+
+    /// Tells a signer the deadline changed, with the updated calendar event.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn send_deadline_changed(
+        &self,
+        to_email: &str,
+        to_name: &str,
+        locale: Locale,
+        document_title: &str,
+        sender_name: &str,
+        access_token: &str,
+        reply_to: Option<&Mailbox>,
+        deadline: &SignerDeadline,
+    ) -> Result<()> {
+        let signing_url = format!("{}/sign/{}", self.public_url, access_token);
+        let m = locale.messages();
+        let text = &m.deadline_changed;
+        let expires_at = display_time::format(deadline.due_at, Tz::UTC);
+
+        let subject = i18n::fill(
+            text.subject,
+            &[
+                ("sender_name", sender_name),
+                ("document_title", document_title),
+            ],
+        );
+        let greeting = i18n::fill(m.greeting, &[("to_name", to_name)]);
+        let footer = self.reply_footer(m, reply_to);
+
+        let html_body = format!(
+            r#"<!DOCTYPE html>
+<html lang="{lang}">
+<head>
+    <meta charset="utf-8">
+    <meta name="viewport" content="width=device-width, initial-scale=1.0">
+    <title>{heading}</title>
+</head>
+<body style="font-family: Arial, sans-serif; line-height: 1.6; color: #333; max-width: 600px; margin: 0 auto; padding: 20px;">
+    <div style="background-color: #f8f9fa; padding: 20px; border-radius: 8px; margin-bottom: 20px;">
+        <h1 style="color: #2563eb; margin: 0 0 10px 0; font-size: 24px;">{heading}</h1>
+        <p style="margin: 0; color: #666;">{tagline}</p>
+    </div>
+
+    <p>{greeting}</p>
+
+    <p>{body}</p>
+
+    <div style="background-color: #e8f4fd; padding: 15px; border-radius: 8px; margin: 20px 0;">
+        <p style="margin: 0; font-weight: bold; color: #1e40af;">{document_title}</p>
+    </div>
+
+    <p>{sign_prompt}</p>
+
+    <div style="text-align: center; margin: 30px 0;">
+        <a href="{signing_url}" style="background-color: #2563eb; color: white; padding: 14px 28px; text-decoration: none; border-radius: 6px; font-weight: bold; display: inline-block;">{sign_button}</a>
+    </div>
+
+    <p style="font-size: 14px; color: #666;">{button_fallback}</p>
+    <p style="font-size: 12px; color: #888; word-break: break-all;">{signing_url}</p>
+
+    <hr style="border: none; border-top: 1px solid #eee; margin: 30px 0;">
+
+    <p style="font-size: 12px; color: #888;">
+        {footer}
+    </p>
+</body>
+</html>"#,
+            lang = locale.code(),
+            heading = text.heading,
+            tagline = text.tagline,
+            greeting = greeting,
+            body = i18n::fill(
+                text.body,
+                &[
+                    ("sender_name", &strong(sender_name)),
+                    ("expires_at", &strong(&expires_at)),
+                ],
+            ),
+            document_title = document_title,
+            sign_prompt = m.sign_prompt_html,
+            signing_url = signing_url,
+            sign_button = m.sign_button,
+            button_fallback = m.button_fallback,
+            footer = footer
+        );
+
+        let plain_body = format!(
+            r#"{heading}
+
+{greeting}
+
+{body}
+
+{document_title}
+
+{sign_prompt}
+{signing_url}
+
+---
+{footer}"#,
+            heading = text.heading,
+            greeting = greeting,
+            body = i18n::fill(
+                text.body,
+                &[("sender_name", sender_name), ("expires_at", &expires_at)],
+            ),
+            document_title = document_title,
+            sign_prompt = m.sign_prompt_plain,
+            signing_url = signing_url,
+            footer = footer
+        );
+
+        let calendar = self.deadline_calendar(
+            m,
+            document_title,
+            deadline,
+            Some(&signing_url),
+            ics::Method::Publish,
+        );
+
+        self.send_email_from(
+            &self.from_name,
+            reply_to,
+            to_email,
+            to_name,
+            &subject,
+            &html_body,
+            &plain_body,
+            Some(calendar),
+        )
+        .await
+    }
+
+    pub async fn send_expiry_warning(
+        &self,
+        to_email: &str,
+        to_name: &str,
+        document_title: &str,
+        sender_name: &str,
+        expires_at: DateTime<Utc>,
+        access_token: &str,
+    ) -> Result<()> {
+        let signing_url = format!("{}/sign/{}", self.public_url, access_token);
+        let expires_at = format_expiry(expires_at);
+
+        let subject = format!("Reminder: \"{}\" expires soon", document_title);
+
+        let html_body = format!(
+            r#"<!DOCTYPE html>
+<html>
+<head>
+    <meta charset="utf-8">
+    <meta name="viewport" content="width=device-width, initial-scale=1.0">
+    <title>Document Expires Soon</title>
+</head>
+<body style="font-family: Arial, sans-serif; line-height: 1.6; color: #333; max-width: 600px; margin: 0 auto; padding: 20px;">
+    <div style="background-color: #fff3cd; padding: 20px; border-radius: 8px; margin-bottom: 20px;">
+        <h1 style="color: #856404; margin: 0 0 10px 0; font-size: 24px;">Document Expires Soon</h1>
+        <p style="margin: 0; color: #856404;">Your signature is still needed</p>
+    </div>
+
+    <p>Hello {to_name},</p>
+
+    <p><strong>{sender_name}</strong> is still waiting for your signature on the following document, which expires on <strong>{expires_at}</strong>:</p>
+
+    <div style="background-color: #e8f4fd; padding: 15px; border-radius: 8px; margin: 20px 0;">
+        <p style="margin: 0; font-weight: bold; color: #1e40af;">{document_title}</p>
+    </div>
+
+    <p>Please click the button below to review and sign the document before then:</p>
+
+    <div style="text-align: center; margin: 30px 0;">
+        <a href="{signing_url}" style="background-color: #2563eb; color: white; padding: 14px 28px; text-decoration: none; border-radius: 6px; font-weight: bold; display: inline-block;">Review & Sign Document</a>
+    </div>
+
+    <p style="font-size: 14px; color: #666;">If the button doesn't work, copy and paste this link into your browser:</p>
+    <p style="font-size: 12px; color: #888; word-break: break-all;">{signing_url}</p>
+
+    <hr style="border: none; border-top: 1px solid #eee; margin: 30px 0;">
+
+    <p style="font-size: 12px; color: #888;">
+        This is an automated message from {from_name}. Please do not reply to this email.
+    </p>
+</body>
+</html>"#,
+            to_name = to_name,
+            sender_name = sender_name,
+            document_title = document_title,
+            expires_at = expires_at,
+            signing_url = signing_url,
+            from_name = self.from_name
+        );
+
+        let plain_body = format!(
+            r#"Document Expires Soon
+
+Hello {to_name},
+
+{sender_name} is still waiting for your signature on the following document, which expires on {expires_at}:
+
+{document_title}
+
+Please visit the following link to review and sign the document before then:
+{signing_url}
+
+---
+This is an automated message from {from_name}. Please do not reply to this email."#,
+            to_name = to_name,
+            sender_name = sender_name,
+            document_title = document_title,
+            expires_at = expires_at,
+            signing_url = signing_url,
+            from_name = self.from_name
+        );
+
+        self.send_email(to_email, to_name, &subject, &html_body, &plain_body)
+            .await
+    }
+
+    /// Lists an owner's documents that expire soon and are not fully signed.
+    pub async fn send_expiry_digest(
+        &self,
+        to_email: &str,
+        to_name: &str,
+        documents: &[Document],
+    ) -> Result<()> {
+        let subject = match documents {
+            [document] => format!("\"{}\" expires soon", document.title),
+            _ => format!("{} of your documents expire soon", documents.len()),
+        };
+
+        let rows_html: String = documents
+            .iter()
+            .map(|document| {
+                format!(
+                    r#"
+        <tr>
+            <td style="padding: 8px; border-bottom: 1px solid #eee;"><a href="{public_url}/documents/{id}" style="color: #1e40af;">{title}</a></td>
+            <td style="padding: 8px; border-bottom: 1px solid #eee;">{expires_at}</td>
+            <td style="padding: 8px; border-bottom: 1px solid #eee;">{completed} of {total}</td>
+        </tr>"#,
+                    public_url = self.public_url,
+                    id = document.id,
+                    title = document.title,
+                    expires_at = document.expires_at.map(format_expiry).unwrap_or_default(),
+                    completed = document.completed_signers,
+                    total = document.total_signers
+                )
+            })
+            .collect();
+
+        let rows_plain: String = documents
+            .iter()
+            .map(|document| {
+                format!(
+                    "- {} (expires {}, {} of {} signed)\n  {}/documents/{}\n",
+                    document.title,
+                    document.expires_at.map(format_expiry).unwrap_or_default(),
+                    document.completed_signers,
+                    document.total_signers,
+                    self.public_url,
+                    document.id
+                )
+            })
+            .collect();
+
+        let html_body = format!(
+            r#"<!DOCTYPE html>
+<html>
+<head>
+    <meta charset="utf-8">
+    <meta name="viewport" content="width=device-width, initial-scale=1.0">
+    <title>Documents Expiring Soon</title>
+</head>
+<body style="font-family: Arial, sans-serif; line-height: 1.6; color: #333; max-width: 600px; margin: 0 auto; padding: 20px;">
+    <div style="background-color: #fff3cd; padding: 20px; border-radius: 8px; margin-bottom: 20px;">
+        <h1 style="color: #856404; margin: 0 0 10px 0; font-size: 24px;">Documents Expiring Soon</h1>
+        <p style="margin: 0; color: #856404;">Some signers have not signed yet</p>
+    </div>
+
+    <p>Hello {to_name},</p>
+
+    <p>The following documents you sent expire soon and are still waiting for signatures. Their signers have been sent a reminder.</p>
+
+    <table style="width: 100%; border-collapse: collapse; margin: 20px 0; font-size: 14px;">
+        <tr>
+            <th style="padding: 8px; text-align: left; border-bottom: 2px solid #ddd;">Document</th>
+            <th style="padding: 8px; text-align: left; border-bottom: 2px solid #ddd;">Expires</th>
+            <th style="padding: 8px; text-align: left; border-bottom: 2px solid #ddd;">Signed</th>
+        </tr>{rows_html}
+    </table>
+
+    <hr style="border: none; border-top: 1px solid #eee; margin: 30px 0;">
+
+    <p style="font-size: 12px; color: #888;">
+        This is an automated message from {from_name}. Please do not reply to this email.
+    </p>
+</body>
+</html>"#,
+            to_name = to_name,
+            rows_html = rows_html,
+            from_name = self.from_name
+        );
+
+        let plain_body = format!(
+            r#"Documents Expiring Soon
+
+Hello {to_name},
+
+The following documents you sent expire soon and are still waiting for signatures. Their signers have been sent a reminder.
+
+{rows_plain}
+---
+This is an automated message from {from_name}. Please do not reply to this email."#,
+            to_name = to_name,
+            rows_plain = rows_plain,
+            from_name = self.from_name
+        );
+
+        self.send_email(to_email, to_name, &subject, &html_body, &plain_body)
+            .await
+    }
+
+    pub async fn send_completion_notification(
+        &self,
+        to_email: &str,
+        to_name: &str,
+        locale: Locale,
+        document_title: &str,
+        branding: Option<&UserBranding>,
+    ) -> Result<()> {
+        let m = locale.messages();
+        let text = &m.completion;
+
+        let subject = i18n::fill(text.subject, &[("document_title", document_title)]);
+        let greeting = i18n::fill(m.greeting, &[("to_name", to_name)]);
+        let footer = self.footer(m);
+
+        let html_body = format!(
+            r#"<!DOCTYPE html>
+<html lang="{lang}">
+<head>
+    <meta charset="utf-8">
+    <meta name="viewport" content="width=device-width, initial-scale=1.0">
+    <title>{heading}</title>
+</head>
+<body style="font-family: Arial, sans-serif; line-height: 1.6; color: #333; max-width: 600px; margin: 0 auto; padding: 20px;">
+    {brand_header}<div style="background-color: #d4edda; padding: 20px; border-radius: 8px; margin-bottom: 20px;">
+        <h1 style="color: #155724; margin: 0 0 10px 0; font-size: 24px;">{heading}</h1>
+        <p style="margin: 0; color: #155724;">{tagline}</p>
+    </div>
+
+    <p>{greeting}</p>
+
+    <p>{body}</p>
+
+    <div style="background-color: #e8f4fd; padding: 15px; border-radius: 8px; margin: 20px 0;">
+        <p style="margin: 0; font-weight: bold; color: #1e40af;">{document_title}</p>
+    </div>
+
+    <p>{download}</p>
+
+    <div style="text-align: center; margin: 30px 0;">
+        <a href="{dashboard_url}" style="background-color: {color}; color: white; padding: 14px 28px; text-decoration: none; border-radius: 6px; font-weight: bold; display: inline-block;">{dashboard_button}</a>
+    </div>
+
+    <hr style="border: none; border-top: 1px solid #eee; margin: 30px 0;">
+
+    <p style="font-size: 12px; color: #888;">
+        {footer}
+    </p>
+</body>
+</html>"#,
+            lang = locale.code(),
+            brand_header = self.brand_header(branding),
+            color = brand_color(branding, "#28a745"),
+            heading = text.heading,
+            tagline = text.tagline,
+            greeting = greeting,
+            body = text.body,
+            document_title = document_title,
+            download = m.completion_download_html,
+            dashboard_url = self.public_url,
+            dashboard_button = m.dashboard_button,
+            footer = footer
+        );
+
+        let plain_body = format!(
+            r#"{heading}
+
+{greeting}
+
+{body}
+
+{document_title}
+
+{download}
+{dashboard_url}
+
+---
+{footer}"#,
+            heading = text.heading,
+            greeting = greeting,
+            body = text.body,
+            document_title = document_title,
+            download = m.completion_download_plain,
+            dashboard_url = self.public_url,
+            footer = footer
+        );
+
+        self.send_email_from(
+            &self.sender_name(branding),
+            None,
+            to_email,
+            to_name,
+            &subject,
+            &html_body,
+            &plain_body,
+            None,
+        )
+        .await
+    }
+
+    /// Tells the owner how far a document has got: who has signed and who is
+    /// still outstanding.
+    pub async fn send_progress_update(
+        &self,
+        to_email: &str,
+        to_name: &str,
+        document: &Document,
+        signers: &[Signer],
+    ) -> Result<()> {
+        let (finished, outstanding): (Vec<&Signer>, Vec<&Signer>) = signers
+            .iter()
+            .partition(|s| matches!(s.status, SignerStatus::Signed | SignerStatus::Approved));
+
+        let subject = format!(
+            "\"{}\": {} of {} signed",
+            document.title,
+            finished.len(),
+            signers.len()
+        );
+
+        let describe = |signer: &Signer| {
+            if signer.email.is_empty() {
+                signer.name.clone()
+            } else {
+                format!("{} ({})", signer.name, signer.email)
+            }
+        };
+        let list_html = |signers: &[&Signer]| -> String {
+            signers
+                .iter()
+                .map(|s| format!("\n        <li>{}</li>", describe(s)))
+                .collect()
+        };
+        let list_plain = |signers: &[&Signer]| -> String {
+            signers
+                .iter()
+                .map(|s| format!("- {}\n", describe(s)))
+                .collect()
+        };
+
+        let html_body = format!(
+            r#"<!DOCTYPE html>
+<html>
+<head>
+    <meta charset="utf-8">
+    <meta name="viewport" content="width=device-width, initial-scale=1.0">
+    <title>Signing Progress</title>
+</head>
+<body style="font-family: Arial, sans-serif; line-height: 1.6; color: #333; max-width: 600px; margin: 0 auto; padding: 20px;">
+    <div style="background-color: #e8f4fd; padding: 20px; border-radius: 8px; margin-bottom: 20px;">
+        <h1 style="color: #1e40af; margin: 0 0 10px 0; font-size: 24px;">Signing Progress</h1>
+        <p style="margin: 0; color: #1e40af;">{finished_count} of {total} signers have signed</p>
+    </div>
+
+    <p>Hello {to_name},</p>
+
+    <p>There is progress on <strong>{document_title}</strong>.</p>
+
+    <p style="font-weight: bold; margin-bottom: 0;">Signed</p>
+    <ul style="margin-top: 5px;">{finished_html}
+    </ul>
+
+    <p style="font-weight: bold; margin-bottom: 0;">Still outstanding</p>
+    <ul style="margin-top: 5px;">{outstanding_html}
+    </ul>
+
+    <div style="text-align: center; margin: 30px 0;">
+        <a href="{public_url}/documents/{document_id}" style="background-color: #2563eb; color: white; padding: 14px 28px; text-decoration: none; border-radius: 6px; font-weight: bold; display: inline-block;">View Document</a>
+    </div>
+
+    <hr style="border: none; border-top: 1px solid #eee; margin: 30px 0;">
+
+    <p style="font-size: 12px; color: #888;">
+        This is an automated message from {from_name}. Please do not reply to this email.
+    </p>
+</body>
+</html>"#,
+            finished_count = finished.len(),
+            total = signers.len(),
+            to_name = to_name,
+            document_title = document.title,
+            finished_html = list_html(&finished),
+            outstanding_html = list_html(&outstanding),
+            public_url = self.public_url,
+            document_id = document.id,
+            from_name = self.from_name
+        );
+
+        let plain_body = format!(
+            r#"Signing Progress
+
+Hello {to_name},
+
+There is progress on "{document_title}": {finished_count} of {total} signers have signed.
+
+Signed:
+{finished_plain}
+Still outstanding:
+{outstanding_plain}
+View the document at:
+{public_url}/documents/{document_id}
+
+---
+This is an automated message from {from_name}. Please do not reply to this email."#,
+            to_name = to_name,
+            document_title = document.title,
+            finished_count = finished.len(),
+            total = signers.len(),
+            finished_plain = list_plain(&finished),
+            outstanding_plain = list_plain(&outstanding),
+            public_url = self.public_url,
+            document_id = document.id,
+            from_name = self.from_name
+        );
+
+        self.send_email(to_email, to_name, &subject, &html_body, &plain_body)
+            .await
+    }
+
+    pub async fn send_verification_code(
+        &self,
+        to_email: &str,
+        to_name: &str,
+        locale: Locale,
+        document_title: &str,
+        code: &str,
+        expires_minutes: i64,
+    ) -> Result<()> {
+        let m = locale.messages();
+        let text = &m.verification_code;
+
+        let subject = i18n::fill(text.subject, &[("document_title", document_title)]);
+        let greeting = i18n::fill(m.greeting, &[("to_name", to_name)]);
+        let expiry = i18n::fill(
+            m.verification_expiry,
+            &[("expires_minutes", &expires_minutes.to_string())],
+        );
+        let footer = self.footer(m);
+
+        let html_body = format!(
+            r#"<!DOCTYPE html>
+<html lang="{lang}">
+<head>
+    <meta charset="utf-8">
+    <meta name="viewport" content="width=device-width, initial-scale=1.0">
+    <title>{heading}</title>
+</head>
+<body style="font-family: Arial, sans-serif; line-height: 1.6; color: #333; max-width: 600px; margin: 0 auto; padding: 20px;">
+    <div style="background-color: #f8f9fa; padding: 20px; border-radius: 8px; margin-bottom: 20px;">
+        <h1 style="color: #2563eb; margin: 0 0 10px 0; font-size: 24px;">{heading}</h1>
+        <p style="margin: 0; color: #666;">{tagline}</p>
+    </div>
+
+    <p>{greeting}</p>
+
+    <p>{body}</p>
+
+    <div style="background-color: #e8f4fd; padding: 15px; border-radius: 8px; margin: 20px 0; text-align: center;">
+        <p style="margin: 0; font-size: 32px; font-weight: bold; letter-spacing: 8px; color: #1e40af;">{code}</p>
+    </div>
+
+    <p>{expiry}</p>
+
+    <hr style="border: none; border-top: 1px solid #eee; margin: 30px 0;">
+
+    <p style="font-size: 12px; color: #888;">
+        {footer}
+    </p>
+</body>
+</html>"#,
+            lang = locale.code(),
+            heading = text.heading,
+            tagline = text.tagline,
+            greeting = greeting,
+            body = i18n::fill(text.body, &[("document_title", &strong(document_title))]),
+            code = code,
+            expiry = expiry,
+            footer = footer
+        );
+
+        let plain_body = format!(
+            r#"{greeting}
+
+{body}
+
+{code}
+
+{expiry}
+
+---
+{footer}"#,
+            greeting = greeting,
+            body = i18n::fill(
+                text.body,
+                &[("document_title", &format!("\"{}\"", document_title))]
+            ),
+            code = code,
+            expiry = expiry,
+            footer = footer
+        );
+
+        self.send_email(to_email, to_name, &subject, &html_body, &plain_body)
+            .await
+    }
+
+    /// Alerts the owner or an admin that a document failed verification;
+    /// `description` says how.
+    pub async fn send_tamper_alert(
+        &self,
+        to_email: &str,
+        to_name: &str,
+        document_title: &str,
+        description: &str,
+    ) -> Result<()> {
+        let subject = format!("Integrity check failed for \"{}\"", document_title);
+
+        let html_body = format!(
+            r#"<!DOCTYPE html>
+<html>
+<head>
+    <meta charset="utf-8">
+    <meta name="viewport" content="width=device-width, initial-scale=1.0">
+    <title>Integrity Check Failed</title>
+</head>
+<body style="font-family: Arial, sans-serif; line-height: 1.6; color: #333; max-width: 600px; margin: 0 auto; padding: 20px;">
+    <div style="background-color: #f8d7da; padding: 20px; border-radius: 8px; margin-bottom: 20px;">
+        <h1 style="color: #721c24; margin: 0 0 10px 0; font-size: 24px;">Integrity Check Failed</h1>
+        <p style="margin: 0; color: #721c24;">A document may have been tampered with</p>
+    </div>
+
+    <p>Hello {to_name},</p>
+
+    <p>The following document failed an integrity check:</p>
+
+    <div style="background-color: #e8f4fd; padding: 15px; border-radius: 8px; margin: 20px 0;">
+        <p style="margin: 0; font-weight: bold; color: #1e40af;">{document_title}</p>
+    </div>
+
+    <p>{description}</p>
+
+    <p>The event has been recorded as a security event. You will not be alerted again about this document until an administrator acknowledges it.</p>
+
+    <hr style="border: none; border-top: 1px solid #eee; margin: 30px 0;">
+
+    <p style="font-size: 12px; color: #888;">
+        This is an automated message from {from_name}. Please do not reply to this email.
+    </p>
+</body>
+</html>"#,
+            to_name = to_name,
+            document_title = document_title,
+            description = description,
+            from_name = self.from_name
+        );
+
+        let plain_body = format!(
+            r#"Integrity Check Failed
+
+Hello {to_name},
+
+The following document failed an integrity check:
+
+{document_title}
+
+{description}
+
+The event has been recorded as a security event. You will not be alerted again about this document until an administrator acknowledges it.
+
+---
+This is an automated message from {from_name}. Please do not reply to this email."#,
+            to_name = to_name,
+            document_title = document_title,
+            description = description,
+            from_name = self.from_name
+        );
+
+        self.send_email(to_email, to_name, &subject, &html_body, &plain_body)
+            .await
+    }
+
+    pub async fn send_signer_anonymized_notice(
+        &self,
+        to_email: &str,
+        to_name: &str,
+        document_titles: &[String],
+    ) -> Result<()> {
+        let subject = "Signer personal data erased from your documents";
+
+        let html_titles: String = document_titles
+            .iter()
+            .map(|title| format!("<li>{}</li>", title))
+            .collect();
+        let plain_titles: String = document_titles
+            .iter()
+            .map(|title| format!("- {}\n", title))
+            .collect();
+
+        let html_body = format!(
+            r#"<!DOCTYPE html>
+<html>
+<head>
+    <meta charset="utf-8">
+    <meta name="viewport" content="width=device-width, initial-scale=1.0">
+    <title>Signer Data Erased</title>
+</head>
+<body style="font-family: Arial, sans-serif; line-height: 1.6; color: #333; max-width: 600px; margin: 0 auto; padding: 20px;">
+    <div style="background-color: #f8f9fa; padding: 20px; border-radius: 8px; margin-bottom: 20px;">
+        <h1 style="color: #2563eb; margin: 0 0 10px 0; font-size: 24px;">Signer Data Erased</h1>
+        <p style="margin: 0; color: #666;">A signer exercised their right to erasure</p>
+    </div>
+
+    <p>Hello {to_name},</p>
+
+    <p>Following an erasure request, the name, email address, IP address and device details of a signer have been replaced with placeholders in the following documents:</p>
+
+    <ul style="background-color: #e8f4fd; padding: 15px 15px 15px 35px; border-radius: 8px; margin: 20px 0;">
+        {html_titles}
+    </ul>
+
+    <p>The signed files, signature hashes and audit trail links are unchanged. Audit entries that were rewritten are marked as anonymized.</p>
+
+    <hr style="border: none; border-top: 1px solid #eee; margin: 30px 0;">
+
+    <p style="font-size: 12px; color: #888;">
+        This is an automated message from {from_name}. Please do not reply to this email.
+    </p>
+</body>
+</html>"#,
+            to_name = to_name,
+            html_titles = html_titles,
+            from_name = self.from_name
+        );
+
+        let plain_body = format!(
+            r#"Signer Data Erased
+
+Hello {to_name},
+
+Following an erasure request, the name, email address, IP address and device details of a signer have been replaced with placeholders in the following documents:
+
+{plain_titles}
+The signed files, signature hashes and audit trail links are unchanged. Audit entries that were rewritten are marked as anonymized.
+
+---
+This is an automated message from {from_name}. Please do not reply to this email."#,
+            to_name = to_name,
+            plain_titles = plain_titles,
+            from_name = self.from_name
+        );
+
+        self.send_email(to_email, to_name, subject, &html_body, &plain_body)
+            .await
+    }
+
+    pub async fn send_email_change_confirmation(
+        &self,
+        to_email: &str,
+        to_name: &str,
+        token: &str,
+        expires_at: DateTime<Utc>,
+    ) -> Result<()> {
+        let confirm_url = format!("{}/confirm-email/{}", self.public_url, token);
+        let expires = format_expiry(expires_at);
+
+        let subject = "Confirm your new email address";
+
+        let html_body = format!(
+            r#"<!DOCTYPE html>
+<html>
+<head>
+    <meta charset="utf-8">
+    <meta name="viewport" content="width=device-width, initial-scale=1.0">
+    <title>Confirm Your Email</title>
+</head>
+<body style="font-family: Arial, sans-serif; line-height: 1.6; color: #333; max-width: 600px; margin: 0 auto; padding: 20px;">
+    <div style="background-color: #f8f9fa; padding: 20px; border-radius: 8px; margin-bottom: 20px;">
+        <h1 style="color: #2563eb; margin: 0 0 10px 0; font-size: 24px;">Confirm Your Email</h1>
+        <p style="margin: 0; color: #666;">You asked to sign in with this address</p>
+    </div>
+
+    <p>Hello {to_name},</p>
+
+    <p>Click the button below to make <strong>{to_email}</strong> the email address of your account. Until you do, you keep signing in with your current address.</p>
+
+    <div style="text-align: center; margin: 30px 0;">
+        <a href="{confirm_url}" style="background-color: #2563eb; color: white; padding: 14px 28px; text-decoration: none; border-radius: 6px; font-weight: bold; display: inline-block;">Confirm Email Address</a>
+    </div>
+
+    <p style="font-size: 14px; color: #666;">If the button doesn't work, copy and paste this link into your browser:</p>
+    <p style="font-size: 12px; color: #888; word-break: break-all;">{confirm_url}</p>
+
+    <p>This link expires on {expires}. If you did not ask for this change, you can ignore this email.</p>
+
+    <hr style="border: none; border-top: 1px solid #eee; margin: 30px 0;">
+
+    <p style="font-size: 12px; color: #888;">
+        This is an automated message from {from_name}. Please do not reply to this email.
+    </p>
+</body>
+</html>"#,
+            to_name = to_name,
+            to_email = to_email,
+            confirm_url = confirm_url,
+            expires = expires,
+            from_name = self.from_name
+        );
+
+        let plain_body = format!(
+            r#"Confirm Your Email
+
+Hello {to_name},
+
+Visit the following link to make {to_email} the email address of your account. Until you do, you keep signing in with your current address.
+{confirm_url}
+
+This link expires on {expires}. If you did not ask for this change, you can ignore this email.
+
+---
+This is an automated message from {from_name}. Please do not reply to this email."#,
+            to_name = to_name,
+            to_email = to_email,
+            confirm_url = confirm_url,
+            expires = expires,
+            from_name = self.from_name
+        );
+
+        self.send_email(to_email, to_name, subject, &html_body, &plain_body)
+            .await
+    }
+
+    /// Invites someone to the account an admin created for them; they choose
+    /// their name and password from the link.
+    pub async fn send_invitation(
+        &self,
+        to_email: &str,
+        to_name: &str,
+        inviter_name: &str,
+        token: &str,
+        expires_at: DateTime<Utc>,
+    ) -> Result<()> {
+        let accept_url = format!("{}/invitations/{}", self.public_url, token);
+        let expires = format_expiry(expires_at);
+        let greeting = if to_name.is_empty() {
+            "Hello".to_string()
+        } else {
+            format!("Hello {}", to_name)
+        };
+
+        let subject = format!("{} invited you to {}", inviter_name, self.from_name);
+
+        let html_body = format!(
+            r#"<!DOCTYPE html>
+<html>
+<head>
+    <meta charset="utf-8">
+    <meta name="viewport" content="width=device-width, initial-scale=1.0">
+    <title>You're Invited</title>
+</head>
+<body style="font-family: Arial, sans-serif; line-height: 1.6; color: #333; max-width: 600px; margin: 0 auto; padding: 20px;">
+    <div style="background-color: #f8f9fa; padding: 20px; border-radius: 8px; margin-bottom: 20px;">
+        <h1 style="color: #2563eb; margin: 0 0 10px 0; font-size: 24px;">You're Invited</h1>
+        <p style="margin: 0; color: #666;">An account was created for you</p>
+    </div>
+
+    <p>{greeting},</p>
+
+    <p><strong>{inviter_name}</strong> invited you to {from_name}. Click the button below to choose your name and password; you will sign in with <strong>{to_email}</strong>.</p>
+
+    <div style="text-align: center; margin: 30px 0;">
+        <a href="{accept_url}" style="background-color: #2563eb; color: white; padding: 14px 28px; text-decoration: none; border-radius: 6px; font-weight: bold; display: inline-block;">Accept Invitation</a>
+    </div>
+
+    <p style="font-size: 14px; color: #666;">If the button doesn't work, copy and paste this link into your browser:</p>
+    <p style="font-size: 12px; color: #888; word-break: break-all;">{accept_url}</p>
+
+    <p>This invitation expires on {expires}. If you were not expecting it, you can ignore this email.</p>
+
+    <hr style="border: none; border-top: 1px solid #eee; margin: 30px 0;">
+
+    <p style="font-size: 12px; color: #888;">
+        This is an automated message from {from_name}. Please do not reply to this email.
+    </p>
+</body>
+</html>"#,
+            greeting = greeting,
+            inviter_name = inviter_name,
+            to_email = to_email,
+            accept_url = accept_url,
+            expires = expires,
+            from_name = self.from_name
+        );
+
+        let plain_body = format!(
+            r#"You're Invited
+
+{greeting},
+
+{inviter_name} invited you to {from_name}. Visit the following link to choose your name and password; you will sign in with {to_email}.
+{accept_url}
+
+This invitation expires on {expires}. If you were not expecting it, you can ignore this email.
+
+---
+This is an automated message from {from_name}. Please do not reply to this email."#,
+            greeting = greeting,
+            inviter_name = inviter_name,
+            to_email = to_email,
+            accept_url = accept_url,
+            expires = expires,
+            from_name = self.from_name
+        );
+
+        self.send_email(to_email, to_name, &subject, &html_body, &plain_body)
+            .await
+    }
+
+    /// Tells the current address that a change to `new_email` was requested.
+    pub async fn send_email_change_notice(
+        &self,
+        to_email: &str,
+        to_name: &str,
+        new_email: &str,
+    ) -> Result<()> {
+        let subject = "Your account email is being changed";
+
+        let html_body = format!(
+            r#"<!DOCTYPE html>
+<html>
+<head>
+    <meta charset="utf-8">
+    <meta name="viewport" content="width=device-width, initial-scale=1.0">
+    <title>Email Change Requested</title>
+</head>
+<body style="font-family: Arial, sans-serif; line-height: 1.6; color: #333; max-width: 600px; margin: 0 auto; padding: 20px;">
+    <div style="background-color: #f8f9fa; padding: 20px; border-radius: 8px; margin-bottom: 20px;">
+        <h1 style="color: #2563eb; margin: 0 0 10px 0; font-size: 24px;">Email Change Requested</h1>
+        <p style="margin: 0; color: #666;">Your account's sign-in address</p>
+    </div>
+
+    <p>Hello {to_name},</p>
+
+    <p>A change of your account's email address to <strong>{new_email}</strong> was requested. It takes effect once the link sent to that address is followed, after which you will sign in with it instead of this one.</p>
+
+    <p>If you did not ask for this change, change your password and contact your administrator.</p>
+
+    <hr style="border: none; border-top: 1px solid #eee; margin: 30px 0;">
+
+    <p style="font-size: 12px; color: #888;">
+        This is an automated message from {from_name}. Please do not reply to this email.
+    </p>
+</body>
+</html>"#,
+            to_name = to_name,
+            new_email = new_email,
+            from_name = self.from_name
+        );
+
+        let plain_body = format!(
+            r#"Email Change Requested
+
+Hello {to_name},
+
+A change of your account's email address to {new_email} was requested. It takes effect once the link sent to that address is followed, after which you will sign in with it instead of this one.
+
+If you did not ask for this change, change your password and contact your administrator.
+
+---
+This is an automated message from {from_name}. Please do not reply to this email."#,
+            to_name = to_name,
+            new_email = new_email,
+            from_name = self.from_name
+        );
+
+        self.send_email(to_email, to_name, subject, &html_body, &plain_body)
+            .await
+    }
+
+    fn footer(&self, messages: &Messages) -> String {
+        i18n::fill(messages.automated_footer, &[("from_name", &self.from_name)])
+    }
+
+    /// The footer of an email whose replies go to `reply_to`, if set.
+    fn reply_footer(&self, messages: &Messages, reply_to: Option<&Mailbox>) -> String {
+        let Some(reply_to) = reply_to else {
+            return self.footer(messages);
+        };
+
+        i18n::fill(
+            messages.reply_to_footer,
+            &[
+                ("from_name", &self.from_name),
+                ("owner_name", reply_to.name.as_deref().unwrap_or_default()),
+                ("owner_email", reply_to.email.as_ref()),
+            ],
+        )
+    }
+
+    /// The owner's logo, else their company name, above the email's heading.
+    fn brand_header(&self, branding: Option<&UserBranding>) -> String {
+        let Some(branding) = branding else {
+            return String::new();
+        };
+        let name = branding.company_name.as_deref().map(escape_html);
+
+        match (branding.logo_url(&self.public_url), name) {
+            (Some(logo_url), name) => format!(
+                r#"<div style="text-align: center; margin-bottom: 20px;"><img src="{}" alt="{}" style="max-height: 60px; max-width: 240px;"></div>
+    "#,
+                logo_url,
+                name.unwrap_or_default()
+            ),
+            (None, Some(name)) => format!(
+                r#"<p style="text-align: center; margin: 0 0 20px 0; font-size: 20px; font-weight: bold; color: {};">{}</p>
+    "#,
+                brand_color(Some(branding), "#333"),
+                name
+            ),
+            (None, None) => String::new(),
+        }
+    }
+
+    /// The From name of branded emails, e.g. `Acme via SignVault`, so they
+    /// are recognizable while still coming from this server.
+    fn sender_name(&self, branding: Option<&UserBranding>) -> String {
+        match branding.and_then(|b| b.company_name.as_deref()) {
+            Some(company_name) => format!("{} via {}", company_name, self.from_name),
+            None => self.from_name.clone(),
+        }
+    }
+
+    /// The calendar event of a signer's deadline. It links to the document
+    /// unless it cancels the event, when the link no longer works.
+    fn deadline_calendar(
+        &self,
+        messages: &Messages,
+        document_title: &str,
+        deadline: &SignerDeadline,
+        signing_url: Option<&str>,
+        method: ics::Method,
+    ) -> Calendar {
+        let domain = self
+            .from_email
+            .rsplit_once('@')
+            .map_or("signvault", |(_, domain)| domain);
+        let uid = format!(
+            "deadline-{}-{}@{}",
+            deadline.document_id, deadline.signer_id, domain
+        );
+        let summary = i18n::fill(
+            messages.calendar_summary,
+            &[("document_title", document_title)],
+        );
+        let description = match signing_url {
+            Some(signing_url) => i18n::fill(
+                messages.calendar_description,
+                &[
+                    ("document_title", document_title),
+                    ("signing_url", signing_url),
+                ],
+            ),
+            None => summary.clone(),
+        };
+
+        let event = ics::DeadlineEvent {
+            uid: &uid,
+            sequence: deadline.sequence,
+            summary: &summary,
+            description: &description,
+            url: signing_url,
+            organizer_email: &self.from_email,
+            due_at: deadline.due_at,
+            stamp: Utc::now(),
+        };
+
+        Calendar {
+            method,
+            content: ics::deadline_calendar(&event, method),
+        }
+    }
+
+    async fn send_email(
+        &self,
+        to_email: &str,
+        to_name: &str,
+        subject: &str,
+        html_body: &str,
+        plain_body: &str,
+    ) -> Result<()> {
+        self.send_email_from(
+            &self.from_name,
+            None,
+            to_email,
+            to_name,
+            subject,
+            html_body,
+            plain_body,
+            None,
+        )
+        .await
+    }
+
+    /// Sends from the configured address under `from_name`, with replies
+    /// going to `reply_to` when set and `calendar` attached, if any.
+    #[allow(clippy::too_many_arguments)]
+    async fn send_email_from(
+        &self,
+        from_name: &str,
+        reply_to: Option<&Mailbox>,
+        to_email: &str,
+        to_name: &str,
+        subject: &str,
+        html_body: &str,
+        _plain_body: &str,
+        calendar: Option<Calendar>,
+    ) -> Result<()> {
+        let from = Mailbox::new(Some(from_name.to_string()), self.from_email.parse()?);
+        let to: Mailbox = format!("{} <{}>", to_name, to_email).parse()?;
+
+        let mut builder = Message::builder().from(from).to(to);
+        if let Some(reply_to) = reply_to {
+            builder = builder.reply_to(reply_to.clone());
+        }
+
+        let builder = builder.subject(subject);
+        let email = match calendar {
+            Some(calendar) => builder.multipart(with_calendar(html_body, calendar)?)?,
+            None => builder
+                .header(ContentType::TEXT_HTML)
+                .body(html_body.to_string())?,
+        };
+
+        match self.transport.send(email).await {
+            Ok(_) => {
+                info!("Email sent successfully to {}", to_email);
+                Ok(())
+            }
+            Err(e) => {
+                error!("Failed to send email to {}: {}", to_email, e);
+                Err(anyhow::anyhow!("Failed to send email: {}", e))
+            }
+        }
+    }
+}
+
+/// A multipart/mixed body of the HTML email followed by `calendar` as
+/// `deadline.ics`.
+fn with_calendar(html_body: &str, calendar: Calendar) -> Result<MultiPart> {
+    let content_type = ContentType::parse(&format!(
+        "text/calendar; charset=utf-8; method={}",
+        calendar.method.as_str()
+    ))?;
+
+    Ok(MultiPart::mixed()
+        .singlepart(SinglePart::html(html_body.to_string()))
+        .singlepart(
+            Attachment::new("deadline.ics".to_string()).body(calendar.content, content_type),
+        ))
+}
+
+fn brand_color<'a>(branding: Option<&'a UserBranding>, default: &'a str) -> &'a str {
+    branding
+        .and_then(|b| b.primary_color.as_deref())
+        .unwrap_or(default)
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn strong(text: &str) -> String {
+    format!("<strong>{}</strong>", text)
+}
+
+fn format_expiry(expires_at: DateTime<Utc>) -> String {
+    expires_at.format("%B %-d, %Y at %H:%M UTC").to_string()
+}
+
+/// The document owner as the Reply-To of emails to their signers. `None`,
+/// with a warning, when their address cannot be put in a header; the name is
+/// encoded by lettre, so it cannot break the message.
+pub fn owner_mailbox(name: &str, email: &str) -> Option<Mailbox> {
+    match email.parse() {
+        Ok(address) => Some(Mailbox::new(Some(name.to_string()), address)),
+        Err(e) => {
+            warn!("Not setting Reply-To to invalid address {:?}: {}", email, e);
+            None
+        }
+    }
+}
+
+pub fn create_email_service(config: &Config) -> Result<Option<EmailService>> {
+    if config.smtp_host.is_empty() || config.smtp_host == "localhost" {
+        info!("Email service not configured, emails will be logged but not sent");
+        return Ok(None);
+    }
+
+    Ok(Some(EmailService::new(config)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_owner_mailbox_survives_awkward_names() {
+        let reply_to = owner_mailbox("Doe, Jane \"JD\" <ceo>", "jane@example.com").unwrap();
+
+        let message = Message::builder()
+            .from("SignVault <noreply@example.com>".parse().unwrap())
+            .to("signer@example.com".parse().unwrap())
+            .reply_to(reply_to)
+            .subject("Test")
+            .body(String::new())
+            .unwrap();
+        let headers = String::from_utf8(message.formatted()).unwrap();
+
+        let reply_to = headers
+            .lines()
+            .find(|line| line.starts_with("Reply-To:"))
+            .unwrap();
+        assert!(reply_to.ends_with("<jane@example.com>"));
+    }
+
+    #[test]
+    fn test_calendar_is_attached_after_the_html() {
+        let calendar = Calendar {
+            method: ics::Method::Cancel,
+            content: "BEGIN:VCALENDAR\r\nMETHOD:CANCEL\r\nEND:VCALENDAR\r\n".to_string(),
+        };
+
+        let message = Message::builder()
+            .from("SignVault <noreply@example.com>".parse().unwrap())
+            .to("signer@example.com".parse().unwrap())
+            .subject("Test")
+            .multipart(with_calendar("<p>Hello</p>", calendar).unwrap())
+            .unwrap();
+        let formatted = String::from_utf8(message.formatted()).unwrap();
+
+        assert!(formatted.contains("Content-Type: multipart/mixed"));
+        let html = formatted.find("Content-Type: text/html").unwrap();
+        let ics = formatted
+            .find("Content-Type: text/calendar; charset=utf-8; method=CANCEL")
+            .unwrap();
+        assert!(html < ics);
+        assert!(formatted.contains("attachment; filename=\"deadline.ics\""));
+    }
+
+    #[test]
+    fn test_owner_mailbox_rejects_invalid_addresses() {
+        assert!(owner_mailbox("Jane", "not an address").is_none());
+        assert!(owner_mailbox("Jane", "jane@example.com\r\nBcc: x@example.com").is_none());
+    }
 }
@@ -0,0 +1,395 @@
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Nonce};
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use bytes::Bytes;
+use futures::stream::{self, StreamExt};
+use sha2::{Digest, Sha256};
+use std::ops::Range;
+use std::sync::Arc;
+use thiserror::Error;
+
+use crate::models::encryption::{FileEncryption, WrappedDataKey};
+use crate::services::config::Config;
+use crate::services::storage::{ByteStream, Storage, StoredObject};
+
+pub const DATA_KEY_BYTES: usize = 32;
+pub const NONCE_BYTES: usize = 12;
+/// Bytes AES-GCM adds to every encrypted file.
+pub const TAG_BYTES: u64 = 16;
+
+const KEY_ID_LEN: usize = 16;
+
+/// A stored file that no longer authenticates under its data key, which means
+/// it was changed after it was written.
+#[derive(Debug, Error)]
+#[error("{0} fails authentication; it was changed after it was stored")]
+pub struct TamperedFile(String);
+
+/// Wraps and unwraps document data keys with a master key. Master keys from
+/// the config are the built-in provider; a KMS plugs in by implementing this.
+#[async_trait]
+pub trait KeyProvider: Send + Sync {
+    /// Id of the master key new data keys are wrapped with.
+    fn key_id(&self) -> &str;
+
+    async fn wrap(&self, data_key: &[u8; DATA_KEY_BYTES]) -> Result<Vec<u8>>;
+
+    /// Unwraps a data key wrapped with the master key `key_id`.
+    async fn unwrap(&self, key_id: &str, wrapped_key: &[u8]) -> Result<[u8; DATA_KEY_BYTES]>;
+}
+
+/// `STORAGE_ENCRYPTION_KEY` and `STORAGE_ENCRYPTION_PREVIOUS_KEY`. Key ids are
+/// derived from the keys, so rotating needs no other setting.
+pub struct LocalKeyProvider {
+    keys: Vec<(String, Aes256Gcm)>,
+}
+
+impl LocalKeyProvider {
+    /// `keys` holds the current master key first.
+    pub fn new(keys: &[[u8; 32]]) -> Result<Self> {
+        if keys.is_empty() {
+            return Err(anyhow!("No storage encryption key configured"));
+        }
+
+        Ok(Self {
+            keys: keys
+                .iter()
+                .map(|key| (key_id(key), Aes256Gcm::new(key.into())))
+                .collect(),
+        })
+    }
+}
+
+#[async_trait]
+impl KeyProvider for LocalKeyProvider {
+    fn key_id(&self) -> &str {
+        &self.keys[0].0
+    }
+
+    async fn wrap(&self, data_key: &[u8; DATA_KEY_BYTES]) -> Result<Vec<u8>> {
+        let (nonce, ciphertext) = encrypt(&self.keys[0].1, data_key)?;
+        Ok([nonce, ciphertext].concat())
+    }
+
+    async fn unwrap(&self, key_id: &str, wrapped_key: &[u8]) -> Result<[u8; DATA_KEY_BYTES]> {
+        let (_, cipher) = self
+            .keys
+            .iter()
+            .find(|(id, _)| id == key_id)
+            .ok_or_else(|| {
+                anyhow!(
+                    "Unknown storage encryption key {}; was STORAGE_ENCRYPTION_PREVIOUS_KEY removed too early?",
+                    key_id
+                )
+            })?;
+        if wrapped_key.len() <= NONCE_BYTES {
+            return Err(anyhow!("Wrapped data key is too short"));
+        }
+
+        let (nonce, ciphertext) = wrapped_key.split_at(NONCE_BYTES);
+        decrypt(cipher, nonce, ciphertext)?
+            .try_into()
+            .map_err(|_| anyhow!("Wrapped data key has the wrong length"))
+    }
+}
+
+fn key_id(key: &[u8; 32]) -> String {
+    let mut id = hex::encode(
+        Sha256::new()
+            .chain_update(b"signvault-storage:")
+            .chain_update(key)
+            .finalize(),
+    );
+    id.truncate(KEY_ID_LEN);
+    id
+}
+
+/// The configured key provider, or `None` when storage encryption is off.
+pub fn key_provider(config: &Config) -> Result<Option<Arc<dyn KeyProvider>>> {
+    let keys = config.storage_encryption_keys()?;
+    if keys.is_empty() {
+        return Ok(None);
+    }
+
+    Ok(Some(Arc::new(LocalKeyProvider::new(&keys)?)))
+}
+
+fn encrypt(cipher: &Aes256Gcm, plaintext: &[u8]) -> Result<(Vec<u8>, Vec<u8>)> {
+    let mut nonce = [0u8; NONCE_BYTES];
+    getrandom::getrandom(&mut nonce).context("Failed to generate nonce")?;
+
+    let ciphertext = cipher
+        .encrypt(Nonce::from_slice(&nonce), plaintext)
+        .map_err(|_| anyhow!("Encryption failed"))?;
+
+    Ok((nonce.to_vec(), ciphertext))
+}
+
+fn decrypt(cipher: &Aes256Gcm, nonce: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>> {
+    if nonce.len() != NONCE_BYTES {
+        return Err(anyhow!("Nonce has the wrong length"));
+    }
+
+    cipher
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| anyhow!("Decryption failed; the file or its key does not match"))
+}
+
+/// Storage that encrypts document files with their document's data key.
+/// Objects written with the plain `Storage` methods, and document files
+/// without a data key, are passed through unchanged.
+pub struct EncryptedStorage {
+    inner: Arc<dyn Storage>,
+    keys: Arc<dyn KeyProvider>,
+}
+
+impl EncryptedStorage {
+    pub fn new(inner: Arc<dyn Storage>, keys: Arc<dyn KeyProvider>) -> Self {
+        Self { inner, keys }
+    }
+
+    pub fn keys(&self) -> &dyn KeyProvider {
+        self.keys.as_ref()
+    }
+
+    async fn cipher(&self, data_key: &WrappedDataKey) -> Result<Aes256Gcm> {
+        let key = self
+            .keys
+            .unwrap(&data_key.key_id, &data_key.wrapped_key)
+            .await?;
+        Ok(Aes256Gcm::new(&key.into()))
+    }
+
+    /// Encrypts `data` with `data_key` without storing it, returning the
+    /// nonce and ciphertext.
+    pub async fn encrypt(
+        &self,
+        data_key: &WrappedDataKey,
+        data: &[u8],
+    ) -> Result<(Vec<u8>, Vec<u8>)> {
+        encrypt(&self.cipher(data_key).await?, data)
+    }
+
+    /// `data_key` wrapped with the current master key, or `None` if it
+    /// already is.
+    pub async fn rewrap(&self, data_key: &WrappedDataKey) -> Result<Option<WrappedDataKey>> {
+        if data_key.key_id == self.keys.key_id() {
+            return Ok(None);
+        }
+
+        let key = self
+            .keys
+            .unwrap(&data_key.key_id, &data_key.wrapped_key)
+            .await?;
+        Ok(Some(WrappedDataKey {
+            key_id: self.keys.key_id().to_string(),
+            wrapped_key: self.keys.wrap(&key).await?,
+        }))
+    }
+}
+
+#[async_trait]
+impl Storage for EncryptedStorage {
+    fn location(&self) -> String {
+        self.inner.location()
+    }
+
+    async fn put(&self, key: &str, data: Vec<u8>) -> Result<()> {
+        self.inner.put(key, data).await
+    }
+
+    async fn get(&self, key: &str) -> Result<Vec<u8>> {
+        self.inner.get(key).await
+    }
+
+    async fn size(&self, key: &str) -> Result<u64> {
+        self.inner.size(key).await
+    }
+
+    async fn stream(&self, key: &str, range: Option<Range<u64>>) -> Result<ByteStream> {
+        self.inner.stream(key, range).await
+    }
+
+    async fn delete(&self, key: &str) -> Result<()> {
+        self.inner.delete(key).await
+    }
+
+    async fn delete_prefix(&self, prefix: &str) -> Result<()> {
+        self.inner.delete_prefix(prefix).await
+    }
+
+    async fn exists(&self, key: &str) -> Result<bool> {
+        self.inner.exists(key).await
+    }
+
+    async fn list(&self) -> Result<Vec<StoredObject>> {
+        self.inner.list().await
+    }
+
+    async fn new_data_key(&self) -> Result<Option<WrappedDataKey>> {
+        let mut key = [0u8; DATA_KEY_BYTES];
+        getrandom::getrandom(&mut key).context("Failed to generate data key")?;
+
+        Ok(Some(WrappedDataKey {
+            key_id: self.keys.key_id().to_string(),
+            wrapped_key: self.keys.wrap(&key).await?,
+        }))
+    }
+
+    async fn put_file(
+        &self,
+        key: &str,
+        data: Vec<u8>,
+        data_key: Option<&WrappedDataKey>,
+    ) -> Result<Option<Vec<u8>>> {
+        let Some(data_key) = data_key else {
+            self.inner.put(key, data).await?;
+            return Ok(None);
+        };
+
+        let (nonce, ciphertext) = self.encrypt(data_key, &data).await?;
+        self.inner.put(key, ciphertext).await?;
+        Ok(Some(nonce))
+    }
+
+    async fn get_file(&self, key: &str, encryption: Option<&FileEncryption>) -> Result<Vec<u8>> {
+        let data = self.inner.get(key).await?;
+        let Some(encryption) = encryption else {
+            return Ok(data);
+        };
+
+        let cipher = self.cipher(&encryption.data_key).await?;
+        decrypt(&cipher, &encryption.nonce, &data).map_err(|_| TamperedFile(key.to_string()).into())
+    }
+
+    async fn stream_file(
+        &self,
+        key: &str,
+        range: Option<Range<u64>>,
+        encryption: Option<&FileEncryption>,
+    ) -> Result<ByteStream> {
+        if encryption.is_none() {
+            return self.inner.stream(key, range).await;
+        }
+
+        // The whole file is authenticated at once, so it is decrypted in full
+        // even when only a range is wanted
+        let data = self.get_file(key, encryption).await?;
+        let data = match range {
+            Some(range) => data
+                .get(range.start as usize..range.end as usize)
+                .context("Range is outside the file")?
+                .to_vec(),
+            None => data,
+        };
+
+        Ok(stream::once(async move { Ok(Bytes::from(data)) }).boxed())
+    }
+
+    async fn file_size(&self, key: &str, encryption: Option<&FileEncryption>) -> Result<u64> {
+        let size = self.inner.size(key).await?;
+        match encryption {
+            Some(_) => Ok(size.saturating_sub(TAG_BYTES)),
+            None => Ok(size),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::storage::LocalStorage;
+    use futures::TryStreamExt;
+
+    fn encrypted_storage(dir: &std::path::Path, keys: &[[u8; 32]]) -> EncryptedStorage {
+        EncryptedStorage::new(
+            Arc::new(LocalStorage::new(dir).unwrap()),
+            Arc::new(LocalKeyProvider::new(keys).unwrap()),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_files_are_encrypted_at_rest() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = encrypted_storage(dir.path(), &[[1; 32]]);
+        let key = "owner/doc/original.pdf";
+        let plaintext = b"%PDF-1.7 confidential".to_vec();
+
+        let data_key = storage.new_data_key().await.unwrap().unwrap();
+        let nonce = storage
+            .put_file(key, plaintext.clone(), Some(&data_key))
+            .await
+            .unwrap()
+            .unwrap();
+        let encryption = FileEncryption {
+            data_key: data_key.clone(),
+            nonce,
+        };
+
+        let stored = std::fs::read(dir.path().join(key)).unwrap();
+        assert_ne!(stored, plaintext);
+        assert!(!stored
+            .windows(b"confidential".len())
+            .any(|w| w == b"confidential"));
+
+        assert_eq!(
+            storage.get_file(key, Some(&encryption)).await.unwrap(),
+            plaintext
+        );
+        assert_eq!(
+            storage.file_size(key, Some(&encryption)).await.unwrap(),
+            plaintext.len() as u64
+        );
+        let chunks: Vec<Bytes> = storage
+            .stream_file(key, Some(9..21), Some(&encryption))
+            .await
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(chunks.concat(), b"confidential");
+
+        // Another document's key cannot read it
+        let other = FileEncryption {
+            data_key: storage.new_data_key().await.unwrap().unwrap(),
+            nonce: encryption.nonce.clone(),
+        };
+        assert!(storage.get_file(key, Some(&other)).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_previous_master_key_still_unwraps() {
+        let dir = tempfile::tempdir().unwrap();
+        let old = encrypted_storage(dir.path(), &[[1; 32]]);
+        let key = "owner/doc/sealed.pdf";
+
+        let data_key = old.new_data_key().await.unwrap().unwrap();
+        let nonce = old
+            .put_file(key, b"sealed".to_vec(), Some(&data_key))
+            .await
+            .unwrap()
+            .unwrap();
+
+        let rotated = encrypted_storage(dir.path(), &[[2; 32], [1; 32]]);
+        let rewrapped = rotated.rewrap(&data_key).await.unwrap().unwrap();
+        assert_ne!(rewrapped.key_id, data_key.key_id);
+        assert!(rotated.rewrap(&rewrapped).await.unwrap().is_none());
+
+        for data_key in [data_key.clone(), rewrapped.clone()] {
+            let encryption = FileEncryption {
+                data_key,
+                nonce: nonce.clone(),
+            };
+            assert_eq!(
+                rotated.get_file(key, Some(&encryption)).await.unwrap(),
+                b"sealed"
+            );
+        }
+
+        // Without the previous key, data keys it wrapped are lost
+        let forgotten = encrypted_storage(dir.path(), &[[2; 32]]);
+        let encryption = FileEncryption { data_key, nonce };
+        assert!(forgotten.get_file(key, Some(&encryption)).await.is_err());
+    }
+}
@@ -0,0 +1,250 @@
+use anyhow::{anyhow, Result};
+use sqlx::PgPool;
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::db;
+use crate::models::encryption::WrappedDataKey;
+use crate::services::encryption::{EncryptedStorage, NONCE_BYTES};
+use crate::services::signature_store;
+use crate::services::storage::{self, Storage};
+
+const BATCH_SIZE: i64 = 100;
+
+/// Suffix of the copy of an object's ciphertext kept while it is replaced, so
+/// that a run interrupted halfway is finished by the next.
+const JOURNAL_SUFFIX: &str = ".encrypting";
+
+/// What a run of `encrypt_existing_files` is working through, for progress
+/// reports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    DataKeys,
+    Originals,
+    SealedFiles,
+    SignatureImages,
+}
+
+impl Phase {
+    pub fn label(self) -> &'static str {
+        match self {
+            Phase::DataKeys => "data keys rewrapped",
+            Phase::Originals => "original files encrypted",
+            Phase::SealedFiles => "sealed copies encrypted",
+            Phase::SignatureImages => "signature images encrypted",
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct EncryptionSummary {
+    pub data_keys_rewrapped: usize,
+    pub originals: usize,
+    pub sealed_files: usize,
+    pub signature_images: usize,
+    /// Files that could not be encrypted and are left for the next run.
+    pub failed: usize,
+}
+
+/// Encrypts the files stored in plaintext before storage encryption was
+/// turned on, in place, and rewraps data keys still wrapped with a previous
+/// master key. Each file is journaled before it is replaced, so the run can be
+/// interrupted and started again. `progress` is told how far each phase got
+/// after every item.
+pub async fn encrypt_existing_files(
+    pool: &PgPool,
+    storage: &EncryptedStorage,
+    mut progress: impl FnMut(Phase, usize, usize),
+) -> Result<EncryptionSummary> {
+    let mut summary = EncryptionSummary::default();
+
+    summary.failed += finish_interrupted(pool, storage).await?;
+
+    // Data keys
+    let key_id = storage.keys().key_id().to_string();
+    let total = db::encryption::count_documents_with_other_keys(pool, &key_id).await? as usize;
+    let mut done = 0;
+    let mut after = Uuid::nil();
+    loop {
+        let batch =
+            db::encryption::get_documents_with_other_keys(pool, &key_id, after, BATCH_SIZE).await?;
+        let Some((last, _)) = batch.last() else {
+            break;
+        };
+        after = *last;
+
+        for (id, data_key) in batch {
+            match storage.rewrap(&data_key).await {
+                Ok(Some(rewrapped)) => {
+                    db::encryption::set_data_key(pool, id, &rewrapped).await?;
+                    summary.data_keys_rewrapped += 1;
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    warn!("Could not rewrap the data key of document {}: {:?}", id, e);
+                    summary.failed += 1;
+                }
+            }
+            done += 1;
+            progress(Phase::DataKeys, done, total);
+        }
+    }
+
+    // Originals, shared by every document using the same file
+    let total = db::encryption::count_plaintext_originals(pool).await? as usize;
+    let mut done = 0;
+    let mut after = String::new();
+    loop {
+        let batch = db::encryption::get_plaintext_originals(pool, &after, BATCH_SIZE).await?;
+        let Some(last) = batch.last() else {
+            break;
+        };
+        after = last.clone();
+
+        for key in batch {
+            match encrypt_original(pool, storage, &key).await {
+                Ok(()) => summary.originals += 1,
+                Err(e) => {
+                    warn!("Could not encrypt {}: {:?}", key, e);
+                    summary.failed += 1;
+                }
+            }
+            done += 1;
+            progress(Phase::Originals, done, total);
+        }
+    }
+
+    // Sealed copies
+    let total = db::encryption::count_plaintext_sealed_files(pool).await? as usize;
+    let mut done = 0;
+    let mut after = Uuid::nil();
+    loop {
+        let batch = db::encryption::get_plaintext_sealed_files(pool, after, BATCH_SIZE).await?;
+        let Some((last, _, _)) = batch.last() else {
+            break;
+        };
+        after = *last;
+
+        for (_, key, data_key) in batch {
+            match encrypt_object(pool, storage, &key, &data_key).await {
+                Ok(()) => summary.sealed_files += 1,
+                Err(e) => {
+                    warn!("Could not encrypt {}: {:?}", key, e);
+                    summary.failed += 1;
+                }
+            }
+            done += 1;
+            progress(Phase::SealedFiles, done, total);
+        }
+    }
+
+    // Signature images, once any still kept in the table are in storage
+    signature_store::migrate_inline_images(pool, storage).await?;
+    let total = db::encryption::count_plaintext_signature_images(pool).await? as usize;
+    let mut done = 0;
+    let mut after = Uuid::nil();
+    loop {
+        let batch = db::encryption::get_plaintext_signature_images(pool, after, BATCH_SIZE).await?;
+        let Some((last, _, _)) = batch.last() else {
+            break;
+        };
+        after = *last;
+
+        for (_, key, data_key) in batch {
+            match encrypt_object(pool, storage, &key, &data_key).await {
+                Ok(()) => summary.signature_images += 1,
+                Err(e) => {
+                    warn!("Could not encrypt {}: {:?}", key, e);
+                    summary.failed += 1;
+                }
+            }
+            done += 1;
+            progress(Phase::SignatureImages, done, total);
+        }
+    }
+
+    Ok(summary)
+}
+
+async fn encrypt_original(pool: &PgPool, storage: &EncryptedStorage, key: &str) -> Result<()> {
+    let new_key = storage
+        .new_data_key()
+        .await?
+        .ok_or_else(|| anyhow!("Storage encryption is not configured"))?;
+    let data_key = db::encryption::assign_data_key(pool, key, &new_key).await?;
+
+    encrypt_object(pool, storage, key, &data_key).await?;
+
+    // Previews cached from the plaintext are not kept for encrypted files
+    storage
+        .delete_prefix(&storage::page_preview_prefix(key))
+        .await
+}
+
+/// Replaces the plaintext object at `key` with its ciphertext. The nonce is
+/// recorded before the object is replaced, so readers never mistake the
+/// ciphertext for plaintext.
+async fn encrypt_object(
+    pool: &PgPool,
+    storage: &EncryptedStorage,
+    key: &str,
+    data_key: &WrappedDataKey,
+) -> Result<()> {
+    let data = storage.get(key).await?;
+    let (nonce, ciphertext) = storage.encrypt(data_key, &data).await?;
+
+    let journal = journal_key(key);
+    storage
+        .put(&journal, [nonce.as_slice(), &ciphertext].concat())
+        .await?;
+
+    if db::encryption::set_file_nonce(pool, key, &nonce).await? {
+        storage.put(key, ciphertext).await?;
+    }
+
+    storage.delete(&journal).await
+}
+
+/// Finishes objects whose journal was left behind by an interrupted run.
+/// Returns how many could not be finished.
+async fn finish_interrupted(pool: &PgPool, storage: &EncryptedStorage) -> Result<usize> {
+    let mut failed = 0;
+
+    for object in storage.list().await? {
+        let Some(key) = object.key.strip_suffix(JOURNAL_SUFFIX) else {
+            continue;
+        };
+
+        if let Err(e) = finish_object(pool, storage, key, &object.key).await {
+            warn!("Could not finish encrypting {}: {:?}", key, e);
+            failed += 1;
+        }
+    }
+
+    Ok(failed)
+}
+
+async fn finish_object(
+    pool: &PgPool,
+    storage: &EncryptedStorage,
+    key: &str,
+    journal: &str,
+) -> Result<()> {
+    let data = storage.get(journal).await?;
+    if data.len() <= NONCE_BYTES {
+        return Err(anyhow!("Journal {} is too short", journal));
+    }
+
+    // A journal whose nonce was not, and can no longer be, recorded belongs to
+    // a file encrypted since or no longer stored; the object is left as it is
+    let (nonce, ciphertext) = data.split_at(NONCE_BYTES);
+    if db::encryption::set_file_nonce(pool, key, nonce).await? {
+        storage.put(key, ciphertext.to_vec()).await?;
+    }
+
+    storage.delete(journal).await
+}
+
+fn journal_key(key: &str) -> String {
+    format!("{}{}", key, JOURNAL_SUFFIX)
+}
@@ -0,0 +1,327 @@
+//! Conditional fields: a field with a condition is only shown while the field
+//! it depends on holds the condition's value, and a hidden field is treated as
+//! empty. It is not required, and a signer submitting a value or signature for
+//! it is rejected rather than having it silently dropped.
+
+use std::collections::{HashMap, HashSet};
+
+use uuid::Uuid;
+
+use crate::models::document::{DocumentFieldRow, FieldType};
+
+/// What a checked checkbox or selected radio option holds.
+pub const CHECKED: &str = "true";
+
+/// Checks that the field `field_id` (`None` for a field being added), assigned
+/// to `signer_id`, can be shown depending on `source_id` holding `value`.
+pub fn validate_condition(
+    field_id: Option<Uuid>,
+    signer_id: Option<Uuid>,
+    source_id: Uuid,
+    value: &str,
+    fields: &[DocumentFieldRow],
+) -> Result<(), String> {
+    if value.trim().is_empty() {
+        return Err("condition_value must not be empty".to_string());
+    }
+
+    if field_id == Some(source_id) {
+        return Err("A field cannot depend on itself".to_string());
+    }
+
+    let source = fields
+        .iter()
+        .find(|f| f.id == source_id)
+        .ok_or_else(|| "The condition field does not exist on this document".to_string())?;
+
+    if !matches!(
+        source.field_type,
+        FieldType::Text
+            | FieldType::Date
+            | FieldType::Number
+            | FieldType::Checkbox
+            | FieldType::Radio
+    ) {
+        return Err(
+            "Conditions can only depend on text, date, number, checkbox or radio fields"
+                .to_string(),
+        );
+    }
+
+    // The signer must see the field the condition depends on to fill it in
+    if source.signer_id.is_some() && source.signer_id != signer_id {
+        return Err(
+            "The condition field must be assigned to the same signer or to no one".to_string(),
+        );
+    }
+
+    let by_id: HashMap<Uuid, &DocumentFieldRow> = fields.iter().map(|f| (f.id, f)).collect();
+    let mut next = Some(source);
+    let mut steps = 0;
+    while let Some(field) = next {
+        if Some(field.id) == field_id || steps > fields.len() {
+            return Err("The condition would make fields depend on each other".to_string());
+        }
+        next = field
+            .condition_field_id
+            .and_then(|id| by_id.get(&id).copied());
+        steps += 1;
+    }
+
+    Ok(())
+}
+
+/// The fields whose condition is met, or that have none. `submitted` holds
+/// values being submitted, which take precedence over the stored ones.
+pub fn visible_fields(
+    fields: &[DocumentFieldRow],
+    submitted: &HashMap<Uuid, String>,
+) -> HashSet<Uuid> {
+    let by_id: HashMap<Uuid, &DocumentFieldRow> = fields.iter().map(|f| (f.id, f)).collect();
+
+    fields
+        .iter()
+        .filter(|f| is_shown(f, &by_id, submitted, 0))
+        .map(|f| f.id)
+        .collect()
+}
+
+fn is_shown(
+    field: &DocumentFieldRow,
+    by_id: &HashMap<Uuid, &DocumentFieldRow>,
+    submitted: &HashMap<Uuid, String>,
+    depth: usize,
+) -> bool {
+    let (Some(source_id), Some(expected)) =
+        (field.condition_field_id, field.condition_value.as_deref())
+    else {
+        return true;
+    };
+
+    // Cycles are refused when conditions are set; this only guards against
+    // looping on one
+    if depth > by_id.len() {
+        return false;
+    }
+
+    let Some(source) = by_id.get(&source_id) else {
+        return true;
+    };
+
+    let value = submitted
+        .get(&source_id)
+        .map(String::as_str)
+        .or(source.value.as_deref());
+
+    value.map(str::trim) == Some(expected.trim()) && is_shown(source, by_id, submitted, depth + 1)
+}
+
+/// Whether `value` fills in a required field other than a signature or initial.
+pub fn is_filled(field_type: FieldType, value: Option<&str>) -> bool {
+    let value = value.map(str::trim).unwrap_or_default();
+
+    match field_type {
+        FieldType::Checkbox | FieldType::Radio => value == CHECKED,
+        _ => !value.is_empty(),
+    }
+}
+
+/// A required field the signer `signer_id` leaves empty, if any, among the
+/// `visible` fields. Signature and initial fields count for the signer they
+/// are assigned to and are filled by `signed`; the others count for their
+/// signer, or everyone when unassigned, and are filled by `submitted` or the
+/// stored value.
+pub fn missing_required_field(
+    fields: &[DocumentFieldRow],
+    visible: &HashSet<Uuid>,
+    signer_id: Uuid,
+    submitted: &HashMap<Uuid, String>,
+    signed: &HashSet<Uuid>,
+) -> Option<Uuid> {
+    fields
+        .iter()
+        .filter(|f| f.required && visible.contains(&f.id))
+        .find(|f| match f.field_type {
+            FieldType::Signature | FieldType::Initial => {
+                f.signer_id == Some(signer_id) && !signed.contains(&f.id)
+            }
+            // Required for the choice as a whole, see `radio_group`
+            FieldType::Radio => false,
+            field_type => {
+                (f.signer_id.is_none() || f.signer_id == Some(signer_id))
+                    && !is_filled(
+                        field_type,
+                        submitted
+                            .get(&f.id)
+                            .map(String::as_str)
+                            .or(f.value.as_deref()),
+                    )
+            }
+        })
+        .map(|f| f.id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn field(field_type: FieldType) -> DocumentFieldRow {
+        DocumentFieldRow {
+            id: Uuid::new_v4(),
+            document_id: Uuid::nil(),
+            field_type,
+            page: 1,
+            x: 0.0,
+            y: 0.0,
+            width: 10.0,
+            height: 10.0,
+            signer_id: None,
+            value: None,
+            font_size: None,
+            font_family: None,
+            date_format: None,
+            norm_x: None,
+            norm_y: None,
+            norm_width: None,
+            norm_height: None,
+            required: false,
+            condition_field_id: None,
+            condition_value: None,
+            label: None,
+            min_value: None,
+            max_value: None,
+            decimal_places: None,
+            read_only: false,
+            group_key: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn depending_on(source: &DocumentFieldRow, value: &str) -> DocumentFieldRow {
+        DocumentFieldRow {
+            condition_field_id: Some(source.id),
+            condition_value: Some(value.to_string()),
+            ..field(FieldType::Text)
+        }
+    }
+
+    #[test]
+    fn test_field_is_shown_while_its_condition_is_met() {
+        let checkbox = field(FieldType::Checkbox);
+        let company = depending_on(&checkbox, CHECKED);
+        let fields = vec![checkbox.clone(), company.clone()];
+
+        let visible = visible_fields(&fields, &HashMap::new());
+        assert!(visible.contains(&checkbox.id));
+        assert!(!visible.contains(&company.id));
+
+        let submitted = HashMap::from([(checkbox.id, CHECKED.to_string())]);
+        assert!(visible_fields(&fields, &submitted).contains(&company.id));
+    }
+
+    #[test]
+    fn test_submitted_values_take_precedence_over_stored_ones() {
+        let mut checkbox = field(FieldType::Checkbox);
+        checkbox.value = Some(CHECKED.to_string());
+        let company = depending_on(&checkbox, CHECKED);
+        let fields = vec![checkbox.clone(), company.clone()];
+
+        assert!(visible_fields(&fields, &HashMap::new()).contains(&company.id));
+
+        let submitted = HashMap::from([(checkbox.id, "false".to_string())]);
+        assert!(!visible_fields(&fields, &submitted).contains(&company.id));
+    }
+
+    #[test]
+    fn test_fields_depending_on_a_hidden_field_are_hidden() {
+        let mut checkbox = field(FieldType::Checkbox);
+        checkbox.value = Some("false".to_string());
+        let mut company = depending_on(&checkbox, CHECKED);
+        company.value = Some("Acme".to_string());
+        let vat = depending_on(&company, "Acme");
+        let fields = vec![checkbox, company, vat.clone()];
+
+        assert!(!visible_fields(&fields, &HashMap::new()).contains(&vat.id));
+    }
+
+    #[test]
+    fn test_validate_condition() {
+        let checkbox = field(FieldType::Checkbox);
+        let signature = field(FieldType::Signature);
+        let mut assigned = field(FieldType::Text);
+        assigned.signer_id = Some(Uuid::new_v4());
+        let fields = vec![checkbox.clone(), signature.clone(), assigned.clone()];
+
+        assert!(validate_condition(None, None, checkbox.id, CHECKED, &fields).is_ok());
+        assert!(validate_condition(None, None, checkbox.id, " ", &fields).is_err());
+        assert!(validate_condition(None, None, Uuid::new_v4(), CHECKED, &fields).is_err());
+        assert!(validate_condition(None, None, signature.id, "x", &fields).is_err());
+        assert!(validate_condition(None, None, assigned.id, "x", &fields).is_err());
+        assert!(validate_condition(None, assigned.signer_id, assigned.id, "x", &fields).is_ok());
+        assert!(
+            validate_condition(Some(checkbox.id), None, checkbox.id, CHECKED, &fields).is_err()
+        );
+    }
+
+    #[test]
+    fn test_validate_condition_refuses_cycles() {
+        let first = field(FieldType::Text);
+        let second = depending_on(&first, "a");
+        let third = depending_on(&second, "b");
+        let fields = vec![first.clone(), second, third.clone()];
+
+        let error = validate_condition(Some(first.id), None, third.id, "c", &fields).unwrap_err();
+        assert!(error.contains("depend on each other"));
+    }
+
+    #[test]
+    fn test_required_fields_are_only_required_while_shown() {
+        let signer_id = Uuid::new_v4();
+        let checkbox = field(FieldType::Checkbox);
+        let mut company = depending_on(&checkbox, CHECKED);
+        company.required = true;
+        let fields = vec![checkbox.clone(), company.clone()];
+        let missing = |submitted: &HashMap<Uuid, String>| {
+            let visible = visible_fields(&fields, submitted);
+            missing_required_field(&fields, &visible, signer_id, submitted, &HashSet::new())
+        };
+
+        assert_eq!(missing(&HashMap::new()), None);
+
+        let mut submitted = HashMap::from([(checkbox.id, CHECKED.to_string())]);
+        assert_eq!(missing(&submitted), Some(company.id));
+
+        submitted.insert(company.id, "Acme".to_string());
+        assert_eq!(missing(&submitted), None);
+    }
+
+    #[test]
+    fn test_required_signatures_count_for_their_signer() {
+        let signer_id = Uuid::new_v4();
+        let mut own = field(FieldType::Signature);
+        own.required = true;
+        own.signer_id = Some(signer_id);
+        let mut other = field(FieldType::Signature);
+        other.required = true;
+        other.signer_id = Some(Uuid::new_v4());
+        let fields = vec![own.clone(), other];
+        let visible = visible_fields(&fields, &HashMap::new());
+
+        let missing = |signed: &HashSet<Uuid>| {
+            missing_required_field(&fields, &visible, signer_id, &HashMap::new(), signed)
+        };
+        assert_eq!(missing(&HashSet::new()), Some(own.id));
+        assert_eq!(missing(&HashSet::from([own.id])), None);
+    }
+
+    #[test]
+    fn test_is_filled() {
+        assert!(is_filled(FieldType::Text, Some("Acme")));
+        assert!(!is_filled(FieldType::Text, Some("  ")));
+        assert!(!is_filled(FieldType::Date, None));
+        assert!(is_filled(FieldType::Checkbox, Some("true")));
+        assert!(!is_filled(FieldType::Checkbox, Some("false")));
+    }
+}
@@ -0,0 +1,110 @@
+use std::net::IpAddr;
+use tracing::info;
+#[cfg(feature = "geoip")]
+use tracing::warn;
+
+use crate::models::audit::GeoLocation;
+use crate::services::ip_privacy;
+
+/// Resolves signer IP addresses to an approximate city and country using a
+/// local MaxMind (GeoLite2/GeoIP2 City) database. Without a database, or when
+/// built without the `geoip` feature, every lookup returns `None`.
+pub struct GeoIp {
+    #[cfg(feature = "geoip")]
+    reader: Option<maxminddb::Reader<Vec<u8>>>,
+}
+
+impl GeoIp {
+    pub fn disabled() -> Self {
+        Self {
+            #[cfg(feature = "geoip")]
+            reader: None,
+        }
+    }
+
+    #[cfg(feature = "geoip")]
+    pub fn load(database_path: Option<&str>) -> Self {
+        let Some(path) = database_path else {
+            info!("GeoIP database not configured, signer locations will not be resolved");
+            return Self::disabled();
+        };
+
+        match maxminddb::Reader::open_readfile(path) {
+            Ok(reader) => {
+                info!("Loaded GeoIP database from {}", path);
+                Self {
+                    reader: Some(reader),
+                }
+            }
+            Err(e) => {
+                warn!("Failed to load GeoIP database {}: {}", path, e);
+                Self::disabled()
+            }
+        }
+    }
+
+    #[cfg(not(feature = "geoip"))]
+    pub fn load(database_path: Option<&str>) -> Self {
+        if database_path.is_some() {
+            info!("Built without the geoip feature, ignoring GEOIP_DATABASE_PATH");
+        }
+        Self::disabled()
+    }
+
+    /// Looks up an IP address as recorded by the request middleware; a
+    /// truncated address is located by its network, and a hashed one not at
+    /// all. The database is held in memory, so lookups do not block on I/O.
+    pub fn lookup(&self, ip_address: &str) -> Option<GeoLocation> {
+        self.lookup_ip(ip_privacy::locatable_address(ip_address)?)
+    }
+
+    #[cfg(feature = "geoip")]
+    fn lookup_ip(&self, ip: IpAddr) -> Option<GeoLocation> {
+        let reader = self.reader.as_ref()?;
+        let city: maxminddb::geoip2::City = reader.lookup(ip).ok()?;
+
+        let english_name = |names: Option<std::collections::BTreeMap<&str, &str>>| {
+            names.and_then(|n| n.get("en").map(|name| name.to_string()))
+        };
+
+        let location = GeoLocation {
+            city: city.city.and_then(|c| english_name(c.names)),
+            country: city
+                .country
+                .as_ref()
+                .and_then(|c| english_name(c.names.clone())),
+            country_code: city
+                .country
+                .and_then(|c| c.iso_code.map(|code| code.to_string())),
+        };
+
+        if location.city.is_none() && location.country.is_none() {
+            return None;
+        }
+
+        Some(location)
+    }
+
+    #[cfg(not(feature = "geoip"))]
+    fn lookup_ip(&self, _ip: IpAddr) -> Option<GeoLocation> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_disabled_lookup_returns_none() {
+        let geoip = GeoIp::disabled();
+        assert_eq!(geoip.lookup("8.8.8.8"), None);
+        assert_eq!(geoip.lookup("unknown"), None);
+    }
+
+    #[test]
+    fn test_missing_database_degrades_to_disabled() {
+        let geoip = GeoIp::load(Some("/nonexistent/GeoLite2-City.mmdb"));
+        assert_eq!(geoip.lookup("8.8.8.8"), None);
+    }
+}
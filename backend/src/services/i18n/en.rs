@@ -0,0 +1,73 @@
+use super::{EmailText, Messages};
+
+pub(super) const MESSAGES: Messages = Messages {
+    greeting: "Hello {to_name},",
+    automated_footer:
+        "This is an automated message from {from_name}. Please do not reply to this email.",
+    reply_to_footer:
+        "This is an automated message from {from_name}. Replies to this email go to {owner_name} ({owner_email}).",
+    button_fallback: "If the button doesn't work, copy and paste this link into your browser:",
+    sign_prompt_html: "Please click the button below to review and sign the document:",
+    sign_prompt_plain: "Please visit the following link to review and sign the document:",
+    sign_button: "Review & Sign Document",
+    legally_binding: "Your electronic signature is legally binding under the ESIGN Act (USA) and eIDAS Regulation (EU).",
+    link_disabled: "The link you received no longer works.",
+    signing_request: EmailText {
+        subject: "{sender_name} has requested your signature on \"{document_title}\"",
+        heading: "Signature Request",
+        tagline: "You have received a document to sign",
+        body: "{sender_name} has requested your electronic signature on the following document:",
+    },
+    signing_reminder: EmailText {
+        subject: "Reminder: {sender_name} is waiting for your signature on \"{document_title}\"",
+        heading: "Signature Reminder",
+        tagline: "A document is still waiting for you",
+        body: "{sender_name} is still waiting for your electronic signature on the following document:",
+    },
+    request_withdrawn: EmailText {
+        subject: "{sender_name} has withdrawn the signature request for \"{document_title}\"",
+        heading: "Signature Request Withdrawn",
+        tagline: "There is nothing for you to sign for now",
+        body: "{sender_name} has withdrawn their request for your signature on the following document:",
+    },
+    withdrawn_resend: "The link you received no longer works. If the document is sent again, you will receive a new one.",
+    request_cancelled: EmailText {
+        subject: "{sender_name} has cancelled the signature request for \"{document_title}\"",
+        heading: "Signature Request Cancelled",
+        tagline: "This document will not be signed",
+        body: "{sender_name} has voided the following document, so your signature is no longer needed:",
+    },
+    signer_removed: EmailText {
+        subject: "{sender_name} no longer needs your signature on \"{document_title}\"",
+        heading: "Signature No Longer Needed",
+        tagline: "You have been removed as a signer",
+        body: "{sender_name} has removed you from the signers of the following document, so your signature is no longer needed:",
+    },
+    cancel_reason: "The reason given was:",
+    deadline_changed: EmailText {
+        subject: "New deadline to sign \"{document_title}\"",
+        heading: "Signing Deadline Changed",
+        tagline: "You have a new deadline to sign",
+        body: "{sender_name} has changed the deadline to sign the following document to {expires_at}:",
+    },
+    calendar_summary: "Sign: {document_title}",
+    calendar_description: "Deadline to sign \"{document_title}\". Review and sign it at {signing_url}",
+    completion: EmailText {
+        subject: "Document \"{document_title}\" has been fully signed",
+        heading: "Document Completed",
+        tagline: "All parties have signed the document",
+        body: "Great news! The following document has been signed by all parties:",
+    },
+    completion_download_html:
+        "You can download the signed document and certificate of completion from your dashboard.",
+    completion_download_plain:
+        "You can download the signed document and certificate of completion from your dashboard at:",
+    dashboard_button: "View Dashboard",
+    verification_code: EmailText {
+        subject: "Your verification code for \"{document_title}\"",
+        heading: "Verification Code",
+        tagline: "Confirm it's you before signing",
+        body: "Use the following code to verify your email address before signing {document_title}:",
+    },
+    verification_expiry: "This code expires in {expires_minutes} minutes. If you did not request it, you can ignore this email.",
+};
@@ -0,0 +1,73 @@
+use super::{EmailText, Messages};
+
+pub(super) const MESSAGES: Messages = Messages {
+    greeting: "Bonjour {to_name},",
+    automated_footer:
+        "Ceci est un message automatique de {from_name}. Merci de ne pas répondre à cet e-mail.",
+    reply_to_footer:
+        "Ceci est un message automatique de {from_name}. Les réponses à cet e-mail sont transmises à {owner_name} ({owner_email}).",
+    button_fallback: "Si le bouton ne fonctionne pas, copiez et collez ce lien dans votre navigateur :",
+    sign_prompt_html: "Cliquez sur le bouton ci-dessous pour consulter et signer le document :",
+    sign_prompt_plain: "Rendez-vous sur le lien suivant pour consulter et signer le document :",
+    sign_button: "Consulter et signer le document",
+    legally_binding: "Votre signature électronique a valeur juridique en vertu du règlement eIDAS (UE) et de l'ESIGN Act (États-Unis).",
+    link_disabled: "Le lien que vous avez reçu ne fonctionne plus.",
+    signing_request: EmailText {
+        subject: "{sender_name} vous demande de signer « {document_title} »",
+        heading: "Demande de signature",
+        tagline: "Vous avez reçu un document à signer",
+        body: "{sender_name} vous demande de signer électroniquement le document suivant :",
+    },
+    signing_reminder: EmailText {
+        subject: "Rappel : {sender_name} attend votre signature sur « {document_title} »",
+        heading: "Rappel de signature",
+        tagline: "Un document vous attend toujours",
+        body: "{sender_name} attend toujours votre signature électronique sur le document suivant :",
+    },
+    request_withdrawn: EmailText {
+        subject: "{sender_name} a retiré la demande de signature de « {document_title} »",
+        heading: "Demande de signature retirée",
+        tagline: "Vous n'avez rien à signer pour le moment",
+        body: "{sender_name} a retiré sa demande de signature pour le document suivant :",
+    },
+    withdrawn_resend: "Le lien que vous avez reçu ne fonctionne plus. Si le document vous est renvoyé, vous en recevrez un nouveau.",
+    request_cancelled: EmailText {
+        subject: "{sender_name} a annulé la demande de signature de « {document_title} »",
+        heading: "Demande de signature annulée",
+        tagline: "Ce document ne sera pas signé",
+        body: "{sender_name} a annulé le document suivant, votre signature n'est donc plus nécessaire :",
+    },
+    signer_removed: EmailText {
+        subject: "{sender_name} n'a plus besoin de votre signature sur « {document_title} »",
+        heading: "Signature plus nécessaire",
+        tagline: "Vous avez été retiré des signataires",
+        body: "{sender_name} vous a retiré des signataires du document suivant, votre signature n'est donc plus nécessaire :",
+    },
+    cancel_reason: "Motif indiqué :",
+    deadline_changed: EmailText {
+        subject: "Nouvelle date limite pour signer « {document_title} »",
+        heading: "Date limite de signature modifiée",
+        tagline: "Vous avez une nouvelle date limite pour signer",
+        body: "{sender_name} a modifié la date limite de signature du document suivant, désormais fixée au {expires_at} :",
+    },
+    calendar_summary: "Signer : {document_title}",
+    calendar_description: "Date limite pour signer « {document_title} ». Consultez-le et signez-le sur {signing_url}",
+    completion: EmailText {
+        subject: "Le document « {document_title} » a été signé par toutes les parties",
+        heading: "Document finalisé",
+        tagline: "Toutes les parties ont signé le document",
+        body: "Bonne nouvelle ! Le document suivant a été signé par toutes les parties :",
+    },
+    completion_download_html:
+        "Vous pouvez télécharger le document signé et son certificat de finalisation depuis votre tableau de bord.",
+    completion_download_plain:
+        "Vous pouvez télécharger le document signé et son certificat de finalisation depuis votre tableau de bord :",
+    dashboard_button: "Ouvrir le tableau de bord",
+    verification_code: EmailText {
+        subject: "Votre code de vérification pour « {document_title} »",
+        heading: "Code de vérification",
+        tagline: "Confirmez votre identité avant de signer",
+        body: "Utilisez le code suivant pour vérifier votre adresse e-mail avant de signer {document_title} :",
+    },
+    verification_expiry: "Ce code expire dans {expires_minutes} minutes. Si vous ne l'avez pas demandé, vous pouvez ignorer cet e-mail.",
+};
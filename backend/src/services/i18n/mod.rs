@@ -0,0 +1,216 @@
+//! Translations of the emails sent to signers. Each language has a catalog in
+//! its own module; adding one means adding a module, a `Locale` variant and
+//! its arms below.
+
+mod en;
+mod fr;
+
+use std::str::FromStr;
+use thiserror::Error;
+
+use crate::models::document::Document;
+use crate::models::signer::Signer;
+
+/// A language emails can be written in. Locales are given as BCP 47 tags
+/// (`fr`, `fr-CA`, `en_GB`); only the language is used.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Locale {
+    #[default]
+    En,
+    Fr,
+}
+
+#[derive(Debug, Error)]
+#[error("unsupported locale {0:?}, expected one of en, fr")]
+pub struct UnsupportedLocale(String);
+
+impl Locale {
+    pub const ALL: [Locale; 2] = [Locale::En, Locale::Fr];
+
+    pub fn code(self) -> &'static str {
+        match self {
+            Locale::En => "en",
+            Locale::Fr => "fr",
+        }
+    }
+
+    pub fn messages(self) -> &'static Messages {
+        match self {
+            Locale::En => &en::MESSAGES,
+            Locale::Fr => &fr::MESSAGES,
+        }
+    }
+
+    /// The first of `tags` that names a supported language, or `default`.
+    /// Unsupported and malformed tags are skipped.
+    pub fn resolve<'a>(tags: impl IntoIterator<Item = Option<&'a str>>, default: Locale) -> Self {
+        tags.into_iter()
+            .flatten()
+            .find_map(|tag| tag.parse().ok())
+            .unwrap_or(default)
+    }
+}
+
+impl FromStr for Locale {
+    type Err = UnsupportedLocale;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let language = s.trim().split(['-', '_']).next().unwrap_or_default();
+        Locale::ALL
+            .into_iter()
+            .find(|locale| locale.code().eq_ignore_ascii_case(language))
+            .ok_or_else(|| UnsupportedLocale(s.to_string()))
+    }
+}
+
+/// The text of one email. `{name}` placeholders are replaced with `fill`.
+pub struct EmailText {
+    pub subject: &'static str,
+    pub heading: &'static str,
+    pub tagline: &'static str,
+    pub body: &'static str,
+}
+
+/// Everything signers read in the emails sent to them, in one language.
+pub struct Messages {
+    pub greeting: &'static str,
+    pub automated_footer: &'static str,
+    /// Footer of emails whose replies go to the document owner.
+    pub reply_to_footer: &'static str,
+    pub button_fallback: &'static str,
+    pub sign_prompt_html: &'static str,
+    pub sign_prompt_plain: &'static str,
+    pub sign_button: &'static str,
+    pub legally_binding: &'static str,
+    pub link_disabled: &'static str,
+    pub signing_request: EmailText,
+    pub signing_reminder: EmailText,
+    pub request_withdrawn: EmailText,
+    pub withdrawn_resend: &'static str,
+    pub request_cancelled: EmailText,
+    pub signer_removed: EmailText,
+    pub cancel_reason: &'static str,
+    pub deadline_changed: EmailText,
+    /// Title of the calendar event for a signing deadline.
+    pub calendar_summary: &'static str,
+    pub calendar_description: &'static str,
+    pub completion: EmailText,
+    pub completion_download_html: &'static str,
+    pub completion_download_plain: &'static str,
+    pub dashboard_button: &'static str,
+    pub verification_code: EmailText,
+    pub verification_expiry: &'static str,
+}
+
+/// The language to write to a signer in: their own, else their document's,
+/// else `default`.
+pub fn signer_locale(signer: &Signer, document: &Document, default: Locale) -> Locale {
+    Locale::resolve(
+        [signer.locale.as_deref(), document.locale.as_deref()],
+        default,
+    )
+}
+
+/// Replaces each `{name}` in `template` with its value.
+pub fn fill(template: &str, values: &[(&str, &str)]) -> String {
+    let mut text = template.to_string();
+    for (name, value) in values {
+        text = text.replace(&format!("{{{}}}", name), value);
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_locales_parse_by_language() {
+        assert_eq!("fr".parse::<Locale>().unwrap(), Locale::Fr);
+        assert_eq!("fr-CA".parse::<Locale>().unwrap(), Locale::Fr);
+        assert_eq!("EN_gb".parse::<Locale>().unwrap(), Locale::En);
+        assert!("de".parse::<Locale>().is_err());
+        assert!("".parse::<Locale>().is_err());
+    }
+
+    #[test]
+    fn test_resolve_skips_unsupported_locales() {
+        assert_eq!(
+            Locale::resolve([Some("de"), None, Some("fr-BE")], Locale::En),
+            Locale::Fr
+        );
+        assert_eq!(Locale::resolve([Some("xx"), None], Locale::Fr), Locale::Fr);
+    }
+
+    #[test]
+    fn test_fill() {
+        assert_eq!(
+            fill("{a} and {b}, {a}", &[("a", "x"), ("b", "y")]),
+            "x and y, x"
+        );
+    }
+
+    fn placeholders(template: &str) -> Vec<&str> {
+        let mut names: Vec<&str> = template
+            .split('{')
+            .skip(1)
+            .filter_map(|part| part.split_once('}').map(|(name, _)| name))
+            .collect();
+        names.sort_unstable();
+        names
+    }
+
+    #[test]
+    fn test_translations_use_the_same_placeholders() {
+        let texts = |m: &'static Messages| {
+            let mut texts = vec![
+                m.greeting,
+                m.automated_footer,
+                m.reply_to_footer,
+                m.button_fallback,
+                m.sign_prompt_html,
+                m.sign_prompt_plain,
+                m.sign_button,
+                m.legally_binding,
+                m.link_disabled,
+                m.withdrawn_resend,
+                m.cancel_reason,
+                m.calendar_summary,
+                m.calendar_description,
+                m.completion_download_html,
+                m.completion_download_plain,
+                m.dashboard_button,
+                m.verification_expiry,
+            ];
+            for email in [
+                &m.signing_request,
+                &m.signing_reminder,
+                &m.request_withdrawn,
+                &m.request_cancelled,
+                &m.signer_removed,
+                &m.deadline_changed,
+                &m.completion,
+                &m.verification_code,
+            ] {
+                texts.extend([email.subject, email.heading, email.tagline, email.body]);
+            }
+            texts
+        };
+
+        let english = texts(Locale::En.messages());
+        for locale in Locale::ALL {
+            let translated = texts(locale.messages());
+            for (en, text) in english.iter().zip(&translated) {
+                assert!(!text.is_empty(), "{:?} is missing {:?}", locale, en);
+                assert_eq!(
+                    placeholders(en),
+                    placeholders(text),
+                    "{:?} translates {:?} as {:?}",
+                    locale,
+                    en,
+                    text
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,215 @@
+//! iCalendar (RFC 5545) entries for signing deadlines, attached to emails so
+//! signers can put the deadline in their calendar.
+
+use chrono::{DateTime, Utc};
+
+/// Longest content line in octets, excluding the CRLF.
+const MAX_LINE_OCTETS: usize = 75;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Method {
+    /// Adds the event, or updates the one with the same UID and a lower
+    /// sequence.
+    Publish,
+    /// Removes the event with the same UID.
+    Cancel,
+}
+
+impl Method {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Method::Publish => "PUBLISH",
+            Method::Cancel => "CANCEL",
+        }
+    }
+}
+
+/// A signing deadline as a calendar event ending when the document expires.
+pub struct DeadlineEvent<'a> {
+    /// Stays the same across updates and the cancellation of one event.
+    pub uid: &'a str,
+    /// Must grow with each update for clients to apply it.
+    pub sequence: i64,
+    pub summary: &'a str,
+    pub description: &'a str,
+    pub url: Option<&'a str>,
+    pub organizer_email: &'a str,
+    pub due_at: DateTime<Utc>,
+    pub stamp: DateTime<Utc>,
+}
+
+/// The VCALENDAR holding `event`, with CRLF line endings.
+pub fn deadline_calendar(event: &DeadlineEvent, method: Method) -> String {
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//SignVault//Signing Deadlines//EN".to_string(),
+        format!("METHOD:{}", method.as_str()),
+        "BEGIN:VEVENT".to_string(),
+        format!("UID:{}", escape_text(event.uid)),
+        format!("SEQUENCE:{}", event.sequence),
+        format!("DTSTAMP:{}", format_utc(event.stamp)),
+        format!("DTSTART:{}", format_utc(event.due_at)),
+        format!("DTEND:{}", format_utc(event.due_at)),
+        format!("SUMMARY:{}", escape_text(event.summary)),
+        format!("DESCRIPTION:{}", escape_text(event.description)),
+        format!("ORGANIZER:mailto:{}", event.organizer_email),
+        "TRANSP:TRANSPARENT".to_string(),
+    ];
+
+    if let Some(url) = event.url {
+        lines.push(format!("URL:{}", url));
+    }
+
+    match method {
+        Method::Publish => lines.extend([
+            "STATUS:CONFIRMED".to_string(),
+            "BEGIN:VALARM".to_string(),
+            "ACTION:DISPLAY".to_string(),
+            format!("DESCRIPTION:{}", escape_text(event.summary)),
+            "TRIGGER:-P1D".to_string(),
+            "END:VALARM".to_string(),
+        ]),
+        Method::Cancel => lines.push("STATUS:CANCELLED".to_string()),
+    }
+
+    lines.extend(["END:VEVENT".to_string(), "END:VCALENDAR".to_string()]);
+
+    lines.iter().map(|line| fold(line) + "\r\n").collect()
+}
+
+/// A UTC date-time in the basic format, e.g. `20241103T063000Z`.
+fn format_utc(at: DateTime<Utc>) -> String {
+    at.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+/// Escapes a TEXT value: backslashes, semicolons, commas and line breaks.
+fn escape_text(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            ';' => escaped.push_str("\\;"),
+            ',' => escaped.push_str("\\,"),
+            '\r' => {
+                chars.next_if_eq(&'\n');
+                escaped.push_str("\\n");
+            }
+            '\n' => escaped.push_str("\\n"),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+/// Splits a content line into lines of at most 75 octets, continuation lines
+/// starting with a space, without splitting a UTF-8 character.
+fn fold(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len() + line.len() / MAX_LINE_OCTETS * 3);
+    let mut octets = 0;
+
+    for c in line.chars() {
+        if octets + c.len_utf8() > MAX_LINE_OCTETS {
+            folded.push_str("\r\n ");
+            // The leading space counts towards the continuation line
+            octets = 1;
+        }
+        folded.push(c);
+        octets += c.len_utf8();
+    }
+
+    folded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn event<'a>(summary: &'a str, description: &'a str) -> DeadlineEvent<'a> {
+        DeadlineEvent {
+            uid: "deadline-1@example.com",
+            sequence: 42,
+            summary,
+            description,
+            url: Some("https://sign.example.com/sign/abc"),
+            organizer_email: "noreply@example.com",
+            due_at: Utc.with_ymd_and_hms(2024, 11, 3, 6, 30, 0).unwrap(),
+            stamp: Utc.with_ymd_and_hms(2024, 10, 1, 12, 0, 5).unwrap(),
+        }
+    }
+
+    /// Content lines with folding undone.
+    fn unfolded(calendar: &str) -> Vec<String> {
+        calendar
+            .replace("\r\n ", "")
+            .split("\r\n")
+            .filter(|line| !line.is_empty())
+            .map(str::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn test_publish_calendar() {
+        let calendar = deadline_calendar(&event("Sign: NDA", "Sign it"), Method::Publish);
+
+        assert!(calendar.ends_with("END:VCALENDAR\r\n"));
+        assert!(!calendar.replace("\r\n", "").contains('\n'));
+
+        let lines = unfolded(&calendar);
+        for expected in [
+            "METHOD:PUBLISH",
+            "UID:deadline-1@example.com",
+            "SEQUENCE:42",
+            "DTSTAMP:20241001T120005Z",
+            "DTSTART:20241103T063000Z",
+            "DTEND:20241103T063000Z",
+            "SUMMARY:Sign: NDA",
+            "STATUS:CONFIRMED",
+            "TRIGGER:-P1D",
+        ] {
+            assert!(lines.iter().any(|l| l == expected), "missing {}", expected);
+        }
+    }
+
+    #[test]
+    fn test_cancel_keeps_the_uid() {
+        let lines = unfolded(&deadline_calendar(&event("Sign", "x"), Method::Cancel));
+
+        assert!(lines.iter().any(|l| l == "METHOD:CANCEL"));
+        assert!(lines.iter().any(|l| l == "UID:deadline-1@example.com"));
+        assert!(lines.iter().any(|l| l == "STATUS:CANCELLED"));
+        assert!(!lines.iter().any(|l| l == "BEGIN:VALARM"));
+    }
+
+    #[test]
+    fn test_text_is_escaped() {
+        assert_eq!(
+            escape_text("a\\b; c, d\r\ne\nf"),
+            "a\\\\b\\; c\\, d\\ne\\nf"
+        );
+
+        let lines = unfolded(&deadline_calendar(
+            &event("Sign: Q3, final; v2", "Line 1\nLine 2"),
+            Method::Publish,
+        ));
+        assert!(lines.iter().any(|l| l == "SUMMARY:Sign: Q3\\, final\\; v2"));
+        assert!(lines.iter().any(|l| l == "DESCRIPTION:Line 1\\nLine 2"));
+    }
+
+    #[test]
+    fn test_long_lines_are_folded_on_character_boundaries() {
+        let title = format!("Signer : {}", "é".repeat(100));
+        let calendar = deadline_calendar(&event(&title, "x"), Method::Publish);
+
+        for line in calendar.split("\r\n") {
+            assert!(line.len() <= MAX_LINE_OCTETS, "{:?} is too long", line);
+        }
+        assert!(unfolded(&calendar)
+            .iter()
+            .any(|l| *l == format!("SUMMARY:{}", title)));
+    }
+}
@@ -0,0 +1,330 @@
+use anyhow::Result;
+use bytes::Bytes;
+use chrono::{DateTime, Utc};
+use futures::{Stream, TryStreamExt};
+use serde::Serialize;
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::error;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::db;
+use crate::models::audit::{AuditAction, CertificateTimestamp};
+use crate::models::document::Document;
+use crate::models::security_event::{SecurityEventSource, SecurityEventType};
+use crate::models::timestamp::TimestampSubject;
+use crate::services::audit;
+use crate::services::crypto::{self, HashAlgorithm};
+use crate::services::encryption::TamperedFile;
+use crate::services::security_alerts::SecurityAlerts;
+use crate::services::storage::Storage;
+
+/// How long a verdict may be reused for ranged requests before the file is
+/// hashed again.
+const VERDICT_TTL: Duration = Duration::from_secs(10 * 60);
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct IntegrityReport {
+    pub document_id: Uuid,
+    pub intact: bool,
+    pub expected_hash: String,
+    pub actual_hash: String,
+    pub checked_at: DateTime<Utc>,
+}
+
+/// A stored file an uploaded copy can be identical to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum FileArtifact {
+    Original,
+    Sealed,
+}
+
+/// Whether an uploaded file is byte for byte one of a document's stored files.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct FileVerification {
+    pub document_id: Uuid,
+    pub matched: Option<FileArtifact>,
+    pub matched_hash: Option<String>,
+    /// When the matched file was uploaded or sealed.
+    pub recorded_at: Option<DateTime<Utc>>,
+    /// Time a timestamp authority attested for the matched hash.
+    pub timestamp: Option<CertificateTimestamp>,
+    pub uploaded_hash: String,
+    pub uploaded_size: u64,
+    pub checked_at: DateTime<Utc>,
+}
+
+/// Who triggered an integrity check, for the audit entry and security event
+/// of a violation.
+pub struct IntegrityActor<'a> {
+    pub signer_id: Option<Uuid>,
+    pub user_id: Option<Uuid>,
+    pub ip_address: Option<&'a str>,
+    pub user_agent: Option<&'a str>,
+    pub source: SecurityEventSource,
+}
+
+impl IntegrityActor<'_> {
+    /// Checks run by the storage scan rather than a request.
+    pub fn storage_scan() -> Self {
+        Self {
+            signer_id: None,
+            user_id: None,
+            ip_address: None,
+            user_agent: None,
+            source: SecurityEventSource::StorageScan,
+        }
+    }
+}
+
+struct CachedVerdict {
+    file_hash: String,
+    intact: bool,
+    checked_at: Instant,
+}
+
+/// Checks stored document files against the `file_hash` recorded at upload.
+pub struct FileIntegrity {
+    pool: PgPool,
+    hash_algorithm: HashAlgorithm,
+    storage: Arc<dyn Storage>,
+    alerts: Arc<SecurityAlerts>,
+    verdicts: Mutex<HashMap<Uuid, CachedVerdict>>,
+}
+
+impl FileIntegrity {
+    pub fn new(
+        pool: PgPool,
+        hash_algorithm: HashAlgorithm,
+        storage: Arc<dyn Storage>,
+        alerts: Arc<SecurityAlerts>,
+    ) -> Self {
+        Self {
+            pool,
+            hash_algorithm,
+            storage,
+            alerts,
+            verdicts: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Whether the document's file can be served. With `allow_cached`, a
+    /// recent verdict is reused instead of hashing the file again, which is
+    /// what ranged requests for later chunks of the same file want.
+    pub async fn check(
+        &self,
+        document: &Document,
+        allow_cached: bool,
+        actor: &IntegrityActor<'_>,
+    ) -> Result<bool> {
+        if allow_cached {
+            if let Some(intact) = self.cached(document) {
+                return Ok(intact);
+            }
+        }
+
+        Ok(self.verify(document, actor).await?.intact)
+    }
+
+    /// Hashes the stored file and records a violation the first time a
+    /// mismatch is seen.
+    pub async fn verify(
+        &self,
+        document: &Document,
+        actor: &IntegrityActor<'_>,
+    ) -> Result<IntegrityReport> {
+        // Hashed with the algorithm the file was recorded with, which may not
+        // be the one new uploads use
+        let (algorithm, expected) = crypto::parse_hash(&document.file_hash)?;
+        let stream = match self
+            .storage
+            .stream_file(
+                &document.file_path,
+                None,
+                document.original_encryption().as_ref(),
+            )
+            .await
+        {
+            Ok(stream) => stream,
+            // An encrypted file changed in storage no longer decrypts; the
+            // bytes stored are reported instead
+            Err(e) if e.is::<TamperedFile>() => {
+                self.storage.stream(&document.file_path, None).await?
+            }
+            Err(e) => return Err(e),
+        };
+        let actual_hash = crypto::hash_stream(algorithm, stream).await?;
+        let intact = crypto::parse_hash(&actual_hash)?.1 == expected;
+
+        let previously_violated = self.cached(document) == Some(false);
+        self.remember(document, intact);
+
+        let report = IntegrityReport {
+            document_id: document.id,
+            intact,
+            expected_hash: document.file_hash.clone(),
+            actual_hash,
+            checked_at: Utc::now(),
+        };
+
+        if !intact && !previously_violated {
+            self.record_violation(document, &report, actor).await?;
+        }
+
+        Ok(report)
+    }
+
+    /// Hashes an uploaded copy as it streams in, with every algorithm the
+    /// document's stored hashes were recorded with, and reports which stored
+    /// file it matches. The result is recorded in the audit trail.
+    pub async fn identify<S>(
+        &self,
+        document: &Document,
+        stream: S,
+        actor: &IntegrityActor<'_>,
+    ) -> Result<FileVerification>
+    where
+        S: Stream<Item = std::io::Result<Bytes>> + Unpin,
+    {
+        let mut artifacts = vec![(
+            FileArtifact::Original,
+            &document.file_hash,
+            document.created_at,
+        )];
+        if let (Some(hash), Some(sealed_at)) = (&document.sealed_file_hash, document.sealed_at) {
+            artifacts.push((FileArtifact::Sealed, hash, sealed_at));
+        }
+
+        let mut algorithms = vec![self.hash_algorithm];
+        for (_, hash, _) in &artifacts {
+            let (algorithm, _) = crypto::parse_hash(hash)?;
+            if !algorithms.contains(&algorithm) {
+                algorithms.push(algorithm);
+            }
+        }
+
+        let mut uploaded_size = 0;
+        let stream = stream.inspect_ok(|chunk| uploaded_size += chunk.len() as u64);
+        let hashes = crypto::hash_stream_each(&algorithms, stream).await?;
+
+        let mut matched = None;
+        for (artifact, hash, recorded_at) in artifacts {
+            let (algorithm, expected) = crypto::parse_hash(hash)?;
+            let index = algorithms.iter().position(|a| *a == algorithm).unwrap();
+            if crypto::parse_hash(&hashes[index])?.1 == expected {
+                matched = Some((artifact, hash.clone(), recorded_at));
+                break;
+            }
+        }
+
+        let timestamp = match &matched {
+            Some((_, hash, _)) => db::timestamp::get_granted_timestamp(
+                &self.pool,
+                document.id,
+                TimestampSubject::Document,
+                hash,
+            )
+            .await?
+            .as_ref()
+            .and_then(CertificateTimestamp::from_granted),
+            None => None,
+        };
+
+        let verification = FileVerification {
+            document_id: document.id,
+            matched: matched.as_ref().map(|(artifact, _, _)| *artifact),
+            recorded_at: matched.as_ref().map(|(_, _, recorded_at)| *recorded_at),
+            matched_hash: matched.map(|(_, hash, _)| hash),
+            timestamp,
+            uploaded_hash: hashes[0].clone(),
+            uploaded_size,
+            checked_at: Utc::now(),
+        };
+
+        audit::log_action(
+            &self.pool,
+            self.hash_algorithm,
+            document.id,
+            actor.signer_id,
+            actor.user_id,
+            AuditAction::FileVerified,
+            actor.ip_address,
+            actor.user_agent,
+            Some(serde_json::json!({
+                "matched": verification.matched,
+                "uploaded_hash": verification.uploaded_hash,
+                "uploaded_size": verification.uploaded_size,
+            })),
+        )
+        .await?;
+
+        Ok(verification)
+    }
+
+    fn cached(&self, document: &Document) -> Option<bool> {
+        let verdicts = self.verdicts.lock().unwrap();
+        verdicts
+            .get(&document.id)
+            .filter(|v| v.file_hash == document.file_hash && v.checked_at.elapsed() < VERDICT_TTL)
+            .map(|v| v.intact)
+    }
+
+    fn remember(&self, document: &Document, intact: bool) {
+        let mut verdicts = self.verdicts.lock().unwrap();
+        verdicts.retain(|_, v| v.checked_at.elapsed() < VERDICT_TTL);
+        verdicts.insert(
+            document.id,
+            CachedVerdict {
+                file_hash: document.file_hash.clone(),
+                intact,
+                checked_at: Instant::now(),
+            },
+        );
+    }
+
+    async fn record_violation(
+        &self,
+        document: &Document,
+        report: &IntegrityReport,
+        actor: &IntegrityActor<'_>,
+    ) -> Result<()> {
+        error!(
+            "File integrity violation for document {}: expected {}, found {}",
+            document.id, report.expected_hash, report.actual_hash
+        );
+
+        audit::log_action(
+            &self.pool,
+            self.hash_algorithm,
+            document.id,
+            actor.signer_id,
+            actor.user_id,
+            AuditAction::FileIntegrityViolation,
+            actor.ip_address,
+            actor.user_agent,
+            Some(serde_json::json!({
+                "expected_hash": report.expected_hash,
+                "actual_hash": report.actual_hash,
+            })),
+        )
+        .await?;
+
+        self.alerts
+            .raise(
+                document,
+                SecurityEventType::FileIntegrityViolation,
+                actor.source,
+                serde_json::json!({
+                    "expected_hash": report.expected_hash,
+                    "actual_hash": report.actual_hash,
+                }),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
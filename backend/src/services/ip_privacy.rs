@@ -0,0 +1,162 @@
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::net::IpAddr;
+use std::str::FromStr;
+use thiserror::Error;
+
+/// Prefix of IP addresses recorded as a keyed hash.
+pub const HASHED_PREFIX: &str = "hmac:";
+
+/// Bytes of the HMAC kept, so the recorded form fits the 45 characters stored
+/// for an IP address.
+const HASH_BYTES: usize = 20;
+
+/// How much of a client's IP address is recorded. Each mode has its own
+/// recorded form, so entries written before a mode change stay readable:
+/// `full` keeps the address, `truncated` keeps its network in CIDR notation
+/// (`203.0.113.0/24`, `2001:db8:1::/48`) and `hashed` keeps `hmac:` followed
+/// by a keyed hash.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IpAddressMode {
+    Full,
+    Truncated,
+    Hashed,
+}
+
+#[derive(Debug, Error)]
+#[error("unknown IP address mode {0:?}, expected full, truncated or hashed")]
+pub struct UnknownIpAddressMode(String);
+
+impl IpAddressMode {
+    /// The mode an IP address was recorded with, or `None` for values that
+    /// are no address, such as `unknown`.
+    pub fn of(recorded: &str) -> Option<Self> {
+        if recorded.starts_with(HASHED_PREFIX) {
+            Some(Self::Hashed)
+        } else if recorded.contains('/') {
+            Some(Self::Truncated)
+        } else if recorded.parse::<IpAddr>().is_ok() {
+            Some(Self::Full)
+        } else {
+            None
+        }
+    }
+}
+
+impl FromStr for IpAddressMode {
+    type Err = UnknownIpAddressMode;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "full" => Ok(Self::Full),
+            "truncated" => Ok(Self::Truncated),
+            "hashed" => Ok(Self::Hashed),
+            _ => Err(UnknownIpAddressMode(s.to_string())),
+        }
+    }
+}
+
+/// Turns client IP addresses into the form recorded in signer rows,
+/// signatures, audit entries and the login history.
+pub struct IpPrivacy {
+    mode: IpAddressMode,
+    key: [u8; 32],
+}
+
+impl IpPrivacy {
+    pub fn new(mode: IpAddressMode, key: [u8; 32]) -> Self {
+        Self { mode, key }
+    }
+
+    pub fn record(&self, ip: IpAddr) -> String {
+        // IPv4 addresses mapped into IPv6 are recorded as IPv4, so they
+        // truncate and hash the same however they arrived
+        let ip = ip.to_canonical();
+        match self.mode {
+            IpAddressMode::Full => ip.to_string(),
+            IpAddressMode::Truncated => match ip {
+                IpAddr::V4(ip) => {
+                    let [a, b, c, _] = ip.octets();
+                    format!("{}.{}.{}.0/24", a, b, c)
+                }
+                IpAddr::V6(ip) => {
+                    let mut octets = ip.octets();
+                    octets[6..].fill(0);
+                    format!("{}/48", std::net::Ipv6Addr::from(octets))
+                }
+            },
+            IpAddressMode::Hashed => {
+                let mut mac = Hmac::<Sha256>::new_from_slice(&self.key)
+                    .expect("HMAC accepts keys of any length");
+                mac.update(ip.to_string().as_bytes());
+                let hash = mac.finalize().into_bytes();
+                format!("{}{}", HASHED_PREFIX, hex::encode(&hash[..HASH_BYTES]))
+            }
+        }
+    }
+}
+
+/// The address to geolocate for a recorded IP address: the address itself, or
+/// the network of a truncated one. Hashed addresses cannot be located.
+pub fn locatable_address(recorded: &str) -> Option<IpAddr> {
+    match IpAddressMode::of(recorded)? {
+        IpAddressMode::Full => recorded.parse().ok(),
+        IpAddressMode::Truncated => recorded.split('/').next()?.parse().ok(),
+        IpAddressMode::Hashed => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_truncation_zeroes_the_host_part() {
+        let privacy = IpPrivacy::new(IpAddressMode::Truncated, [0; 32]);
+        assert_eq!(privacy.record(ip("203.0.113.77")), "203.0.113.0/24");
+        assert_eq!(privacy.record(ip("::ffff:203.0.113.77")), "203.0.113.0/24");
+        assert_eq!(
+            privacy.record(ip("2001:db8:1234:5678:9abc:def0:1234:5678")),
+            "2001:db8:1234::/48"
+        );
+    }
+
+    #[test]
+    fn test_hashes_are_keyed_and_fit_the_column() {
+        let privacy = IpPrivacy::new(IpAddressMode::Hashed, [1; 32]);
+        let recorded = privacy.record(ip("203.0.113.77"));
+
+        assert!(recorded.starts_with(HASHED_PREFIX));
+        assert!(recorded.len() <= 45);
+        assert_eq!(recorded, privacy.record(ip("::ffff:203.0.113.77")));
+        assert_ne!(
+            recorded,
+            IpPrivacy::new(IpAddressMode::Hashed, [2; 32]).record(ip("203.0.113.77"))
+        );
+    }
+
+    #[test]
+    fn test_recorded_forms_name_their_mode() {
+        for mode in [
+            IpAddressMode::Full,
+            IpAddressMode::Truncated,
+            IpAddressMode::Hashed,
+        ] {
+            let recorded = IpPrivacy::new(mode, [0; 32]).record(ip("2001:db8::1"));
+            assert_eq!(IpAddressMode::of(&recorded), Some(mode));
+        }
+        assert_eq!(IpAddressMode::of("unknown"), None);
+    }
+
+    #[test]
+    fn test_locatable_address() {
+        assert_eq!(locatable_address("203.0.113.7"), Some(ip("203.0.113.7")));
+        assert_eq!(locatable_address("203.0.113.0/24"), Some(ip("203.0.113.0")));
+        assert_eq!(locatable_address("hmac:00ff"), None);
+        assert_eq!(locatable_address("unknown"), None);
+    }
+}
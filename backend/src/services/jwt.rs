@@ -0,0 +1,264 @@
+use anyhow::{anyhow, Context, Result};
+use jsonwebtoken::{DecodingKey, EncodingKey, Header, Validation};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::services::config::Config;
+
+/// Hex digits of the secret's fingerprint used as its key id.
+const KEY_ID_LEN: usize = 16;
+
+/// Who a token is for. Each audience only accepts its own tokens, so a
+/// signing session cannot be used as a login and vice versa.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Audience {
+    /// Signed-in users of the API, `JWT_AUDIENCE`.
+    Api,
+    /// A signer's session, exchanged for their emailed link; `JWT_AUDIENCE`
+    /// with a `-signing` suffix.
+    Signing,
+}
+
+struct JwtKey {
+    kid: String,
+    encoding: EncodingKey,
+    decoding: DecodingKey,
+}
+
+/// Signs and validates the JWTs SignVault issues. Tokens are signed with the
+/// primary secret and carry its key id in the `kid` header; tokens signed
+/// with the previous secret are still accepted, so `JWT_SECRET` can be
+/// rotated without signing everyone out. Every token must carry the
+/// configured `iss` and `aud`.
+pub struct JwtKeys {
+    /// The primary key first.
+    keys: Vec<JwtKey>,
+    issuer: String,
+    audience: String,
+}
+
+impl JwtKeys {
+    pub fn from_config(config: &Config) -> Self {
+        Self::new(
+            &config.jwt_secret,
+            config.jwt_previous_secret.as_deref(),
+            &config.jwt_issuer,
+            &config.jwt_audience,
+        )
+    }
+
+    fn new(secret: &str, previous_secret: Option<&str>, issuer: &str, audience: &str) -> Self {
+        let keys = std::iter::once(secret)
+            .chain(previous_secret)
+            .map(|secret| JwtKey {
+                kid: key_id(secret),
+                encoding: EncodingKey::from_secret(secret.as_bytes()),
+                decoding: DecodingKey::from_secret(secret.as_bytes()),
+            })
+            .collect();
+
+        Self {
+            keys,
+            issuer: issuer.to_string(),
+            audience: audience.to_string(),
+        }
+    }
+
+    fn audience(&self, audience: Audience) -> String {
+        match audience {
+            Audience::Api => self.audience.clone(),
+            Audience::Signing => format!("{}-signing", self.audience),
+        }
+    }
+
+    /// Signs `claims` with the primary key, adding `iss` and `aud`.
+    pub fn encode<T: Serialize>(&self, claims: &T) -> Result<String> {
+        self.encode_for(Audience::Api, claims)
+    }
+
+    pub fn encode_for<T: Serialize>(&self, audience: Audience, claims: &T) -> Result<String> {
+        let mut payload = serde_json::to_value(claims).context("Failed to serialize claims")?;
+        let object = payload
+            .as_object_mut()
+            .ok_or_else(|| anyhow!("Claims must serialize to an object"))?;
+        object.insert("iss".to_string(), self.issuer.clone().into());
+        object.insert("aud".to_string(), self.audience(audience).into());
+
+        let key = &self.keys[0];
+        let header = Header {
+            kid: Some(key.kid.clone()),
+            ..Header::default()
+        };
+
+        jsonwebtoken::encode(&header, &payload, &key.encoding).context("Token encoding failed")
+    }
+
+    /// Validates a token signed with the key its `kid` names, and its `exp`,
+    /// `iss` and `aud` claims.
+    pub fn decode<T: DeserializeOwned>(&self, token: &str) -> Result<T> {
+        self.decode_for(Audience::Api, token)
+    }
+
+    pub fn decode_for<T: DeserializeOwned>(&self, audience: Audience, token: &str) -> Result<T> {
+        let header = jsonwebtoken::decode_header(token).context("Malformed token")?;
+        let kid = header.kid.context("Token has no key id")?;
+        let key = self
+            .keys
+            .iter()
+            .find(|key| key.kid == kid)
+            .ok_or_else(|| anyhow!("Unknown key id {}", kid))?;
+
+        let mut validation = Validation::default();
+        validation.set_issuer(&[&self.issuer]);
+        validation.set_audience(&[self.audience(audience)]);
+        validation.set_required_spec_claims(&["exp", "iss", "aud"]);
+
+        let data = jsonwebtoken::decode::<T>(token, &key.decoding, &validation)
+            .context("Invalid token")?;
+
+        Ok(data.claims)
+    }
+}
+
+fn key_id(secret: &str) -> String {
+    let mut kid = hex::encode(Sha256::digest(format!("signvault-jwt:{}", secret)));
+    kid.truncate(KEY_ID_LEN);
+    kid
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct TestClaims {
+        sub: String,
+        exp: i64,
+    }
+
+    fn claims() -> TestClaims {
+        TestClaims {
+            sub: "user".to_string(),
+            exp: chrono::Utc::now().timestamp() + 3600,
+        }
+    }
+
+    fn keys(secret: &str, previous: Option<&str>) -> JwtKeys {
+        JwtKeys::new(secret, previous, "signvault", "signvault-api")
+    }
+
+    #[test]
+    fn test_round_trip_sets_kid_and_claims() {
+        let keys = keys("primary-secret", None);
+        let claims = claims();
+        let token = keys.encode(&claims).unwrap();
+
+        let header = jsonwebtoken::decode_header(&token).unwrap();
+        assert_eq!(
+            header.kid.as_deref(),
+            Some(key_id("primary-secret").as_str())
+        );
+        assert_eq!(keys.decode::<TestClaims>(&token).unwrap(), claims);
+
+        let payload: serde_json::Value = keys.decode(&token).unwrap();
+        assert_eq!(payload["iss"], "signvault");
+        assert_eq!(payload["aud"], "signvault-api");
+    }
+
+    #[test]
+    fn test_rotation_accepts_previous_key_until_dropped() {
+        let claims = claims();
+        let old_token = keys("old-secret", None).encode(&claims).unwrap();
+
+        let rotated = keys("new-secret", Some("old-secret"));
+        assert_eq!(rotated.decode::<TestClaims>(&old_token).unwrap(), claims);
+
+        // New tokens are signed with the new key
+        let new_token = rotated.encode(&claims).unwrap();
+        assert_eq!(
+            jsonwebtoken::decode_header(&new_token).unwrap().kid,
+            Some(key_id("new-secret"))
+        );
+
+        assert!(keys("new-secret", None)
+            .decode::<TestClaims>(&old_token)
+            .is_err());
+    }
+
+    #[test]
+    fn test_unknown_or_missing_kid_is_rejected() {
+        let keys = keys("primary-secret", None);
+        let encoding = EncodingKey::from_secret(b"primary-secret");
+        let payload = serde_json::json!({
+            "sub": "user",
+            "exp": chrono::Utc::now().timestamp() + 3600,
+            "iss": "signvault",
+            "aud": "signvault-api",
+        });
+
+        let garbage_kid = Header {
+            kid: Some("garbage".to_string()),
+            ..Header::default()
+        };
+        let token = jsonwebtoken::encode(&garbage_kid, &payload, &encoding).unwrap();
+        assert!(keys.decode::<TestClaims>(&token).is_err());
+
+        let token = jsonwebtoken::encode(&Header::default(), &payload, &encoding).unwrap();
+        assert!(keys.decode::<TestClaims>(&token).is_err());
+    }
+
+    #[test]
+    fn test_issuer_and_audience_are_required() {
+        let keys = keys("primary-secret", None);
+        let header = Header {
+            kid: Some(key_id("primary-secret")),
+            ..Header::default()
+        };
+        let encoding = EncodingKey::from_secret(b"primary-secret");
+        let exp = chrono::Utc::now().timestamp() + 3600;
+
+        for payload in [
+            serde_json::json!({ "sub": "user", "exp": exp }),
+            serde_json::json!({ "sub": "user", "exp": exp, "iss": "signvault" }),
+            serde_json::json!({ "sub": "user", "exp": exp, "aud": "signvault-api" }),
+            serde_json::json!({ "sub": "user", "exp": exp, "iss": "other", "aud": "signvault-api" }),
+            serde_json::json!({ "sub": "user", "exp": exp, "iss": "signvault", "aud": "other" }),
+        ] {
+            let token = jsonwebtoken::encode(&header, &payload, &encoding).unwrap();
+            assert!(keys.decode::<TestClaims>(&token).is_err(), "{}", payload);
+        }
+    }
+
+    #[test]
+    fn test_audiences_do_not_accept_each_others_tokens() {
+        let keys = keys("primary-secret", None);
+        let claims = claims();
+
+        let signing = keys.encode_for(Audience::Signing, &claims).unwrap();
+        assert_eq!(
+            keys.decode_for::<TestClaims>(Audience::Signing, &signing)
+                .unwrap(),
+            claims
+        );
+        assert!(keys.decode::<TestClaims>(&signing).is_err());
+
+        let api = keys.encode(&claims).unwrap();
+        assert!(keys
+            .decode_for::<TestClaims>(Audience::Signing, &api)
+            .is_err());
+    }
+
+    #[test]
+    fn test_expired_token_is_rejected() {
+        let keys = keys("primary-secret", None);
+        let token = keys
+            .encode(&TestClaims {
+                sub: "user".to_string(),
+                exp: chrono::Utc::now().timestamp() - 3600,
+            })
+            .unwrap();
+        assert!(keys.decode::<TestClaims>(&token).is_err());
+    }
+}
@@ -1,7 +1,44 @@
 pub mod admin;
+pub mod anonymization;
 pub mod audit;
+pub mod blob_store;
+pub mod branding;
+pub mod canonical_json;
 pub mod config;
 pub mod crypto;
+pub mod display_time;
 pub mod email;
+pub mod encryption;
+pub mod encryption_migration;
+pub mod field_conditions;
+pub mod geoip;
+pub mod i18n;
+pub mod ics;
+pub mod integrity;
+pub mod ip_privacy;
+pub mod jwt;
+pub mod number_field;
+pub mod outbox;
+pub mod package;
 pub mod pdf;
+pub mod preflight;
+pub mod preview;
+pub mod quota;
+pub mod radio_group;
+pub mod reminders;
+pub mod retention;
+pub mod scheduler;
+pub mod seal;
+pub mod search;
+pub mod security_alerts;
+pub mod server_key;
+pub mod signature_image;
+pub mod signature_store;
 pub mod signing;
+pub mod storage;
+pub mod storage_scan;
+pub mod timestamp;
+pub mod totp;
+pub mod typed_signature;
+pub mod user_agent;
+pub mod verification;
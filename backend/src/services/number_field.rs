@@ -0,0 +1,158 @@
+//! Number fields: a signer's value must be a plain decimal number, within the
+//! field's bounds and with at most its decimal places. Only a dot separates the
+//! decimals; a comma is rejected rather than guessed at, as `1,500` reads as
+//! one and a half or fifteen hundred depending on the locale.
+
+use std::str::FromStr;
+
+use rust_decimal::Decimal;
+
+use crate::models::document::DocumentFieldRow;
+
+/// Most decimal places a number field can be configured with.
+pub const MAX_DECIMAL_PLACES: i32 = 10;
+
+/// The bounds and precision a number field's value must respect.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct NumberConstraints {
+    pub min_value: Option<Decimal>,
+    pub max_value: Option<Decimal>,
+    pub decimal_places: Option<i32>,
+}
+
+impl NumberConstraints {
+    pub fn of(field: &DocumentFieldRow) -> Self {
+        Self {
+            min_value: field.min_value,
+            max_value: field.max_value,
+            decimal_places: field.decimal_places,
+        }
+    }
+
+    /// Checks the constraints an owner configures on a field.
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(places) = self.decimal_places {
+            if !(0..=MAX_DECIMAL_PLACES).contains(&places) {
+                return Err(format!(
+                    "decimal_places must be between 0 and {}",
+                    MAX_DECIMAL_PLACES
+                ));
+            }
+        }
+
+        if let (Some(min), Some(max)) = (self.min_value, self.max_value) {
+            if min > max {
+                return Err("min_value must not be greater than max_value".to_string());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Parses `value` and returns it as stored and rendered: with exactly
+    /// `decimal_places` decimals when configured.
+    pub fn normalize(&self, value: &str) -> Result<String, String> {
+        let value = value.trim();
+
+        if value.contains(',') {
+            return Err(
+                "Use a dot as the decimal separator, without thousands separators".to_string(),
+            );
+        }
+
+        let digits = value.strip_prefix('-').unwrap_or(value);
+        let (whole, decimals) = digits.split_once('.').unwrap_or((digits, "0"));
+        let is_plain = |part: &str| !part.is_empty() && part.bytes().all(|b| b.is_ascii_digit());
+        if !is_plain(whole) || !is_plain(decimals) {
+            return Err(format!("\"{}\" is not a number", value));
+        }
+
+        let mut number = Decimal::from_str(value).map_err(|_| "The number is too large")?;
+
+        if let Some(min) = self.min_value.filter(|min| number < *min) {
+            return Err(format!("Must be at least {}", min.normalize()));
+        }
+
+        if let Some(max) = self.max_value.filter(|max| number > *max) {
+            return Err(format!("Must be at most {}", max.normalize()));
+        }
+
+        if let Some(places) = self.decimal_places {
+            let places = places.clamp(0, MAX_DECIMAL_PLACES) as u32;
+            if number.normalize().scale() > places {
+                return Err(match places {
+                    0 => "Must be a whole number".to_string(),
+                    1 => "Must have at most 1 decimal place".to_string(),
+                    _ => format!("Must have at most {} decimal places", places),
+                });
+            }
+            number.rescale(places);
+        }
+
+        Ok(number.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn constraints(min: &str, max: &str, places: i32) -> NumberConstraints {
+        NumberConstraints {
+            min_value: Some(Decimal::from_str(min).unwrap()),
+            max_value: Some(Decimal::from_str(max).unwrap()),
+            decimal_places: Some(places),
+        }
+    }
+
+    #[test]
+    fn test_normalize_pads_to_the_configured_precision() {
+        let amount = constraints("0", "10000", 2);
+
+        assert_eq!(amount.normalize("12").unwrap(), "12.00");
+        assert_eq!(amount.normalize(" 12.5 ").unwrap(), "12.50");
+        assert_eq!(amount.normalize("12.500").unwrap(), "12.50");
+        assert_eq!(
+            NumberConstraints::default().normalize("-3.25").unwrap(),
+            "-3.25"
+        );
+    }
+
+    #[test]
+    fn test_normalize_enforces_bounds_and_precision() {
+        let amount = constraints("0", "10000", 2);
+
+        assert_eq!(amount.normalize("-1").unwrap_err(), "Must be at least 0");
+        assert_eq!(
+            amount.normalize("10000.01").unwrap_err(),
+            "Must be at most 10000"
+        );
+        assert_eq!(
+            amount.normalize("1.005").unwrap_err(),
+            "Must have at most 2 decimal places"
+        );
+        assert_eq!(
+            constraints("0", "5", 0).normalize("1.5").unwrap_err(),
+            "Must be a whole number"
+        );
+    }
+
+    #[test]
+    fn test_normalize_rejects_commas_and_non_numbers() {
+        let any = NumberConstraints::default();
+
+        assert!(any.normalize("12,5").unwrap_err().contains("dot"));
+        assert!(any.normalize("1,500.00").unwrap_err().contains("dot"));
+        for value in ["", "abc", "1e5", "+1", ".5", "5.", "1_000", "1.2.3", "--1"] {
+            assert!(any.normalize(value).is_err(), "{value:?} was accepted");
+        }
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(constraints("0", "10", 2).validate().is_ok());
+        assert!(constraints("10", "0", 2).validate().is_err());
+        assert!(constraints("0", "10", 11).validate().is_err());
+        assert!(constraints("0", "10", -1).validate().is_err());
+    }
+}
@@ -0,0 +1,568 @@
+use anyhow::{bail, Context, Result};
+use chrono::{Duration, Utc};
+use sqlx::{PgConnection, PgPool};
+use std::sync::Arc;
+use tracing::{error, warn};
+use uuid::Uuid;
+
+use crate::db;
+use crate::models::audit::AuditAction;
+use crate::models::branding::UserBranding;
+use crate::models::document::{Document, DocumentStatus, ProgressNotification};
+use crate::models::outbox::{OutboxEmail, OutboxEmailKind, OutboxEvent, OutboxEventType};
+use crate::models::signer::{Signer, SignerStatus};
+use crate::models::user::User;
+use crate::services::audit;
+use crate::services::crypto::HashAlgorithm;
+use crate::services::email::{self, EmailService, SignerDeadline};
+use crate::services::i18n;
+
+/// Attempts per email before giving up on it.
+const MAX_ATTEMPTS: i32 = 10;
+const RETRY_BASE_DELAY_SECS: i64 = 60;
+const RETRY_MAX_DELAY_SECS: i64 = 6 * 3600;
+const DELIVERY_BATCH_SIZE: i64 = 50;
+/// How long a claimed email is hidden from other dispatchers while it is
+/// being delivered.
+const CLAIM_LEASE_SECS: i64 = 300;
+/// How often events left behind by handlers are dispatched.
+pub const EVENT_DISPATCH_INTERVAL: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// What became of the queued emails a delivery run claimed.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct DeliverySummary {
+    pub sent: usize,
+    /// Emails dropped without sending because they were no longer of use,
+    /// e.g. a signing request for a document that was recalled.
+    pub dropped: usize,
+}
+
+enum Delivery {
+    Sent,
+    Dropped,
+}
+
+/// Delivers emails queued in `email_outbox`. Handlers queue emails in the
+/// transaction that makes the change they announce, then ask for them to be
+/// delivered once it commits; anything that fails is retried in the
+/// background with exponential backoff.
+pub struct EmailOutbox {
+    pool: PgPool,
+    hash_algorithm: HashAlgorithm,
+    email_service: Option<Arc<EmailService>>,
+}
+
+impl EmailOutbox {
+    pub fn new(
+        pool: PgPool,
+        hash_algorithm: HashAlgorithm,
+        email_service: Option<Arc<EmailService>>,
+    ) -> Self {
+        Self {
+            pool,
+            hash_algorithm,
+            email_service,
+        }
+    }
+
+    /// False when no mail server is configured, in which case nothing should
+    /// be queued.
+    pub fn enabled(&self) -> bool {
+        self.email_service.is_some()
+    }
+
+    /// Delivers the due emails of one document. Failures are logged rather
+    /// than returned; the emails stay queued and are retried later.
+    pub async fn deliver_document(&self, document_id: Uuid) {
+        if let Err(e) = self.deliver(Some(document_id)).await {
+            error!(
+                "Failed to deliver queued emails of document {}: {:?}",
+                document_id, e
+            );
+        }
+    }
+
+    /// Delivers due emails, optionally only those of one document. Returns
+    /// how many were sent and how many were dropped as stale.
+    pub async fn deliver(&self, document_id: Option<Uuid>) -> Result<DeliverySummary> {
+        let mut summary = DeliverySummary::default();
+
+        let Some(email_service) = &self.email_service else {
+            return Ok(summary);
+        };
+
+        for email in db::outbox::claim_due_emails(
+            &self.pool,
+            document_id,
+            CLAIM_LEASE_SECS,
+            DELIVERY_BATCH_SIZE,
+        )
+        .await?
+        {
+            match self.send(email_service, &email).await {
+                Ok(Delivery::Sent) => summary.sent += 1,
+                Ok(Delivery::Dropped) => summary.dropped += 1,
+                Err(e) => {
+                    let attempts = email.attempts + 1;
+                    let next_attempt_at =
+                        (attempts < MAX_ATTEMPTS).then(|| Utc::now() + retry_delay(attempts));
+
+                    warn!(
+                        "Queued email {} for document {} failed (attempt {}): {:?}",
+                        email.id, email.document_id, attempts, e
+                    );
+
+                    db::outbox::record_failure(
+                        &self.pool,
+                        email.id,
+                        &format!("{:#}", e),
+                        next_attempt_at,
+                    )
+                    .await?;
+                }
+            }
+        }
+
+        Ok(summary)
+    }
+
+    async fn send(&self, email_service: &EmailService, email: &OutboxEmail) -> Result<Delivery> {
+        let document = db::document::get_document_by_id(&self.pool, email.document_id)
+            .await?
+            .context("Document not found")?;
+        let signer = match email.signer_id {
+            Some(signer_id) => Some(
+                db::signer::get_signer_by_id(&self.pool, signer_id)
+                    .await?
+                    .context("Signer not found")?,
+            ),
+            None => None,
+        };
+        let owner = db::user::get_user_by_id(&self.pool, document.owner_id)
+            .await?
+            .context("Owner not found")?;
+        let branding = db::branding::get_branding(&self.pool, owner.id).await?;
+
+        if let Some(reason) = stale_reason(email.kind, &document, signer.as_ref()) {
+            // The link would be of no use now, e.g. the document was recalled.
+            db::outbox::record_failure(&self.pool, email.id, reason, None).await?;
+            return Ok(Delivery::Dropped);
+        }
+
+        match &signer {
+            Some(signer) => {
+                self.send_to_signer(
+                    email_service,
+                    email,
+                    &document,
+                    signer,
+                    &owner,
+                    branding.as_ref(),
+                )
+                .await?
+            }
+            None => match email.kind {
+                OutboxEmailKind::CompletionNotice => {
+                    email_service
+                        .send_completion_notification(
+                            &owner.email,
+                            &owner.name,
+                            email_service.default_locale(),
+                            &document.title,
+                            branding.as_ref(),
+                        )
+                        .await?
+                }
+                OutboxEmailKind::ProgressUpdate => {
+                    let signers =
+                        db::signer::get_signers_by_document(&self.pool, document.id).await?;
+                    email_service
+                        .send_progress_update(&owner.email, &owner.name, &document, &signers)
+                        .await?
+                }
+                _ => bail!("Email has no signer"),
+            },
+        }
+
+        let mut tx = self.pool.begin().await?;
+
+        db::outbox::mark_sent(&mut *tx, email.id).await?;
+
+        if let Some(signer) = &signer {
+            if email.kind == OutboxEmailKind::SigningRequest {
+                db::signer::mark_email_sent(&mut *tx, signer.id).await?;
+            }
+
+            audit::log_action_in(
+                &mut tx,
+                self.hash_algorithm,
+                document.id,
+                Some(signer.id),
+                Some(owner.id),
+                AuditAction::SignerEmailSent,
+                None,
+                None,
+                Some(serde_json::json!({
+                    "signer_email": signer.email,
+                    "email": email.kind
+                })),
+            )
+            .await?;
+        }
+
+        tx.commit().await?;
+
+        Ok(Delivery::Sent)
+    }
+
+    async fn send_to_signer(
+        &self,
+        email_service: &EmailService,
+        email: &OutboxEmail,
+        document: &Document,
+        signer: &Signer,
+        owner: &User,
+        branding: Option<&UserBranding>,
+    ) -> Result<()> {
+        let locale = i18n::signer_locale(signer, document, email_service.default_locale());
+        let owner_name = &owner.name;
+        let reply_to = owner
+            .reply_to_enabled
+            .then(|| email::owner_mailbox(&owner.name, &owner.email))
+            .flatten();
+        // Each email to the signer carries a higher sequence than the ones
+        // before it, and the same one when retried.
+        let deadline = match document.expires_at {
+            Some(_) => {
+                let sequence =
+                    db::outbox::count_earlier_signer_emails(&self.pool, email.id).await?;
+                SignerDeadline::of(document, signer, sequence)
+            }
+            None => None,
+        };
+
+        match email.kind {
+            OutboxEmailKind::SigningRequest => {
+                email_service
+                    .send_signing_request(
+                        &signer.email,
+                        &signer.name,
+                        locale,
+                        &document.title,
+                        owner_name,
+                        &signer.access_token,
+                        branding,
+                        reply_to.as_ref(),
+                        deadline.as_ref(),
+                    )
+                    .await
+            }
+            OutboxEmailKind::SigningReminder => {
+                email_service
+                    .send_signing_reminder(
+                        &signer.email,
+                        &signer.name,
+                        locale,
+                        &document.title,
+                        owner_name,
+                        &signer.access_token,
+                        reply_to.as_ref(),
+                    )
+                    .await
+            }
+            OutboxEmailKind::RequestWithdrawn => {
+                email_service
+                    .send_request_withdrawn(
+                        &signer.email,
+                        &signer.name,
+                        locale,
+                        &document.title,
+                        owner_name,
+                        deadline.as_ref(),
+                    )
+                    .await
+            }
+            OutboxEmailKind::RequestCancelled => {
+                email_service
+                    .send_request_cancelled(
+                        &signer.email,
+                        &signer.name,
+                        locale,
+                        &document.title,
+                        owner_name,
+                        document.void_reason.as_deref(),
+                        deadline.as_ref(),
+                    )
+                    .await
+            }
+            OutboxEmailKind::SignerRemoved => {
+                email_service
+                    .send_signer_removed(
+                        &signer.email,
+                        &signer.name,
+                        locale,
+                        &document.title,
+                        owner_name,
+                        deadline.as_ref(),
+                    )
+                    .await
+            }
+            OutboxEmailKind::DeadlineChanged => {
+                let deadline = deadline.context("Document has no deadline")?;
+                email_service
+                    .send_deadline_changed(
+                        &signer.email,
+                        &signer.name,
+                        locale,
+                        &document.title,
+                        owner_name,
+                        &signer.access_token,
+                        reply_to.as_ref(),
+                        &deadline,
+                    )
+                    .await
+            }
+            OutboxEmailKind::CompletionNotice => {
+                email_service
+                    .send_completion_notification(
+                        &signer.email,
+                        &signer.name,
+                        locale,
+                        &document.title,
+                        branding,
+                    )
+                    .await
+            }
+            OutboxEmailKind::ProgressUpdate => bail!("Progress updates go to the owner"),
+        }
+    }
+}
+
+/// Fans out the domain events in `outbox_events` to their consumers. Handlers
+/// record an event in the transaction that makes the change, then ask for the
+/// document's events to be dispatched once it commits; the
+/// `outbox_events` job picks up anything left behind. Each event is processed
+/// in its own transaction that also marks it processed, so its consumers'
+/// work is committed exactly once.
+///
+/// The only consumer today is email: events become `email_outbox` rows,
+/// which `EmailOutbox` delivers at least once. Further consumers go in
+/// `fan_out`.
+pub struct EventDispatcher {
+    pool: PgPool,
+    email_outbox: Arc<EmailOutbox>,
+}
+
+impl EventDispatcher {
+    pub fn new(pool: PgPool, email_outbox: Arc<EmailOutbox>) -> Self {
+        Self { pool, email_outbox }
+    }
+
+    /// Dispatches the due events of one document and delivers the emails they
+    /// queued. Failures are logged rather than returned; what failed is
+    /// retried later.
+    pub async fn dispatch_document(&self, document_id: Uuid) {
+        if let Err(e) = self.dispatch(Some(document_id)).await {
+            error!(
+                "Failed to dispatch events of document {}: {:?}",
+                document_id, e
+            );
+        }
+
+        self.email_outbox.deliver_document(document_id).await;
+    }
+
+    /// Dispatches due events, optionally only those of one document. Returns
+    /// how many were processed.
+    pub async fn dispatch(&self, document_id: Option<Uuid>) -> Result<usize> {
+        let mut processed = 0;
+
+        for _ in 0..DELIVERY_BATCH_SIZE {
+            let mut tx = self.pool.begin().await?;
+
+            let Some(event) = db::outbox::claim_next_event(&mut tx, document_id).await? else {
+                break;
+            };
+
+            let result = async {
+                self.fan_out(&mut tx, &event).await?;
+                db::outbox::mark_event_processed(&mut *tx, event.id).await?;
+                tx.commit().await?;
+                Ok::<_, anyhow::Error>(())
+            }
+            .await;
+
+            match result {
+                Ok(()) => processed += 1,
+                Err(e) => {
+                    let attempts = event.attempts + 1;
+                    let next_attempt_at =
+                        (attempts < MAX_ATTEMPTS).then(|| Utc::now() + retry_delay(attempts));
+
+                    warn!(
+                        "Event {} ({:?}) for document {} failed (attempt {}): {:?}",
+                        event.id, event.event_type, event.document_id, attempts, e
+                    );
+
+                    db::outbox::record_event_failure(
+                        &self.pool,
+                        event.id,
+                        &format!("{:#}", e),
+                        next_attempt_at,
+                    )
+                    .await?;
+                }
+            }
+        }
+
+        Ok(processed)
+    }
+
+    async fn fan_out(&self, conn: &mut PgConnection, event: &OutboxEvent) -> Result<()> {
+        if self.email_outbox.enabled() {
+            queue_emails(conn, event).await?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Queues the emails announcing `event`.
+async fn queue_emails(conn: &mut PgConnection, event: &OutboxEvent) -> Result<()> {
+    match event.event_type {
+        OutboxEventType::DocumentSent => {
+            let signer_ids: Vec<Uuid> = serde_json::from_value(event.payload["signer_ids"].clone())
+                .context("Event has no signer_ids")?;
+
+            for signer_id in signer_ids {
+                db::outbox::enqueue_email(
+                    &mut *conn,
+                    OutboxEmailKind::SigningRequest,
+                    event.document_id,
+                    Some(signer_id),
+                )
+                .await?;
+            }
+        }
+        OutboxEventType::DocumentCompleted => {
+            db::outbox::enqueue_email(
+                &mut *conn,
+                OutboxEmailKind::CompletionNotice,
+                event.document_id,
+                None,
+            )
+            .await?;
+
+            for signer in db::signer::get_signers_by_document(&mut *conn, event.document_id).await?
+            {
+                if matches!(signer.status, SignerStatus::Signed | SignerStatus::Approved) {
+                    db::outbox::enqueue_email(
+                        &mut *conn,
+                        OutboxEmailKind::CompletionNotice,
+                        event.document_id,
+                        Some(signer.id),
+                    )
+                    .await?;
+                }
+            }
+        }
+        OutboxEventType::SignerSigned => {
+            let completed = event.payload["completed_signers"].as_i64().unwrap_or(0);
+            let total = event.payload["total_signers"].as_i64().unwrap_or(0);
+            let Some(document) =
+                db::document::get_document_by_id(&mut *conn, event.document_id).await?
+            else {
+                return Ok(());
+            };
+
+            if milestone_reached(&document.progress_notifications, completed, total) {
+                db::outbox::enqueue_email(
+                    &mut *conn,
+                    OutboxEmailKind::ProgressUpdate,
+                    event.document_id,
+                    None,
+                )
+                .await?;
+            }
+        }
+        OutboxEventType::SignerDeclined => {}
+    }
+
+    Ok(())
+}
+
+/// Whether a signature that left `completed` of `total` participants done
+/// reaches one of `rules`. The last signature never does; completion sends its
+/// own notice.
+fn milestone_reached(rules: &[ProgressNotification], completed: i64, total: i64) -> bool {
+    let remaining = total - completed;
+
+    remaining > 0
+        && rules.iter().any(|rule| match rule {
+            ProgressNotification::EverySignature => true,
+            ProgressNotification::OneRemaining => remaining == 1,
+        })
+}
+
+/// Why an email asking the signer to sign should no longer be sent, if so.
+fn stale_reason(
+    kind: OutboxEmailKind,
+    document: &Document,
+    signer: Option<&Signer>,
+) -> Option<&'static str> {
+    match kind {
+        OutboxEmailKind::SigningRequest
+        | OutboxEmailKind::SigningReminder
+        | OutboxEmailKind::DeadlineChanged => {
+            if document.status != DocumentStatus::Pending {
+                Some("Document is no longer pending")
+            } else if kind != OutboxEmailKind::SigningRequest
+                && signer.is_some_and(|signer| {
+                    !matches!(signer.status, SignerStatus::Sent | SignerStatus::Viewed)
+                })
+            {
+                Some("Signer has already responded")
+            } else {
+                None
+            }
+        }
+        // Once the document is finished its completion notice says the rest.
+        OutboxEmailKind::ProgressUpdate => {
+            (document.status != DocumentStatus::Pending).then_some("Document is no longer pending")
+        }
+        OutboxEmailKind::RequestWithdrawn
+        | OutboxEmailKind::RequestCancelled
+        | OutboxEmailKind::SignerRemoved
+        | OutboxEmailKind::CompletionNotice => None,
+    }
+}
+
+fn retry_delay(attempts: i32) -> Duration {
+    let delay = RETRY_BASE_DELAY_SECS.saturating_mul(1 << (attempts - 1).clamp(0, 20));
+    Duration::seconds(delay.min(RETRY_MAX_DELAY_SECS))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retry_delay_backs_off_to_a_cap() {
+        assert_eq!(retry_delay(1), Duration::seconds(60));
+        assert_eq!(retry_delay(2), Duration::seconds(120));
+        assert_eq!(retry_delay(5), Duration::seconds(960));
+        assert_eq!(retry_delay(30), Duration::seconds(RETRY_MAX_DELAY_SECS));
+    }
+
+    #[test]
+    fn test_milestone_reached() {
+        use ProgressNotification::*;
+
+        assert!(!milestone_reached(&[], 1, 5));
+        assert!(milestone_reached(&[EverySignature], 1, 5));
+        assert!(!milestone_reached(&[OneRemaining], 3, 5));
+        assert!(milestone_reached(&[OneRemaining], 4, 5));
+        // Completion is announced by its own notice.
+        assert!(!milestone_reached(&[EverySignature, OneRemaining], 5, 5));
+    }
+}
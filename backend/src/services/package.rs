@@ -0,0 +1,268 @@
+use anyhow::Result;
+use async_zip::base::write::ZipFileWriter;
+use async_zip::{Compression, ZipDateTime, ZipEntryBuilder};
+use bytes::Bytes;
+use chrono::{DateTime, Utc};
+use futures::{AsyncWriteExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+use tokio::io::AsyncWrite;
+use tokio_util::io::ReaderStream;
+use tracing::error;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::models::encryption::FileEncryption;
+use crate::models::server_key::ServerSignature;
+use crate::services::storage::{ByteStream, Storage};
+
+pub const ORIGINAL_ENTRY: &str = "original.pdf";
+pub const SEALED_ENTRY: &str = "sealed.pdf";
+pub const CERTIFICATE_ENTRY: &str = "certificate.json";
+pub const AUDIT_TRAIL_ENTRY: &str = "audit-trail.json";
+pub const MANIFEST_ENTRY: &str = "manifest.json";
+
+/// Bytes buffered between the ZIP writer and the response body.
+const PIPE_CAPACITY: usize = 64 * 1024;
+
+/// Lists the other entries of a package with their SHA-256, so the package
+/// can be checked without contacting the server.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct PackageManifest {
+    pub document_id: Uuid,
+    pub title: String,
+    pub generated_at: DateTime<Utc>,
+    pub entries: Vec<PackageManifestEntry>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct PackageManifestEntry {
+    pub name: String,
+    pub size: u64,
+    /// Hex digest of the entry's uncompressed content.
+    pub sha256: String,
+    /// The server key's signature of the entry, for entries that have one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub server_signature: Option<ServerSignature>,
+}
+
+pub enum PackageSource {
+    /// A file in storage, streamed into the package and decrypted if it is
+    /// encrypted.
+    Stored(String, Option<FileEncryption>),
+    Generated(Vec<u8>),
+}
+
+pub struct PackageEntry {
+    pub name: &'static str,
+    pub source: PackageSource,
+    pub server_signature: Option<ServerSignature>,
+}
+
+/// Streams a ZIP of `entries` followed by their manifest. The archive is
+/// written as it is sent, so stored files are never held in memory whole; an
+/// error part way through ends the stream with that error.
+pub fn stream_package(
+    storage: Arc<dyn Storage>,
+    document_id: Uuid,
+    title: String,
+    modified_at: DateTime<Utc>,
+    entries: Vec<PackageEntry>,
+) -> ByteStream {
+    let (writer, reader) = tokio::io::duplex(PIPE_CAPACITY);
+
+    let writing = tokio::spawn(async move {
+        write_package(
+            storage.as_ref(),
+            document_id,
+            title,
+            modified_at,
+            entries,
+            writer,
+        )
+        .await
+        .map_err(|e| {
+            error!(
+                "Failed to build package of document {}: {:?}",
+                document_id, e
+            );
+            std::io::Error::other(format!("{:#}", e))
+        })
+    });
+
+    // The writer closing ends the reader cleanly, so a failure is passed on
+    // as a last item; it aborts the response instead of truncating the ZIP.
+    let outcome = futures::stream::once(async move {
+        match writing.await {
+            Ok(Ok(())) => None,
+            Ok(Err(e)) => Some(Err(e)),
+            Err(e) => Some(Err(std::io::Error::other(e))),
+        }
+    })
+    .filter_map(std::future::ready);
+
+    ReaderStream::new(reader).chain(outcome).boxed()
+}
+
+async fn write_package<W: AsyncWrite + Unpin>(
+    storage: &dyn Storage,
+    document_id: Uuid,
+    title: String,
+    modified_at: DateTime<Utc>,
+    entries: Vec<PackageEntry>,
+    writer: W,
+) -> Result<()> {
+    let modified_at = ZipDateTime::from_chrono(&modified_at);
+    let mut zip = ZipFileWriter::with_tokio(writer);
+    let mut manifest = PackageManifest {
+        document_id,
+        title,
+        generated_at: Utc::now(),
+        entries: Vec::with_capacity(entries.len()),
+    };
+
+    for entry in entries {
+        let builder = ZipEntryBuilder::new(entry.name.into(), Compression::Deflate)
+            .last_modification_date(modified_at);
+        let mut entry_writer = zip.write_entry_stream(builder).await?;
+        let mut hasher = Sha256::new();
+        let mut size = 0u64;
+
+        match entry.source {
+            PackageSource::Stored(key, encryption) => {
+                let mut stream = storage.stream_file(&key, None, encryption.as_ref()).await?;
+                while let Some(chunk) = stream.next().await {
+                    let chunk: Bytes = chunk?;
+                    hasher.update(&chunk);
+                    size += chunk.len() as u64;
+                    entry_writer.write_all(&chunk).await?;
+                }
+            }
+            PackageSource::Generated(data) => {
+                hasher.update(&data);
+                size = data.len() as u64;
+                entry_writer.write_all(&data).await?;
+            }
+        }
+
+        entry_writer.close().await?;
+        manifest.entries.push(PackageManifestEntry {
+            name: entry.name.to_string(),
+            size,
+            sha256: hex::encode(hasher.finalize()),
+            server_signature: entry.server_signature,
+        });
+    }
+
+    let manifest = serde_json::to_vec_pretty(&manifest)?;
+    let builder = ZipEntryBuilder::new(MANIFEST_ENTRY.into(), Compression::Deflate)
+        .last_modification_date(ZipDateTime::from_chrono(&Utc::now()));
+    zip.write_entry_whole(builder, &manifest).await?;
+
+    let mut writer = zip.close().await?.into_inner();
+    tokio::io::AsyncWriteExt::shutdown(&mut writer).await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_zip::base::read::mem::ZipFileReader;
+    use futures::AsyncReadExt;
+
+    #[tokio::test]
+    async fn test_package_lists_entries_in_manifest() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage: Arc<dyn Storage> =
+            Arc::new(crate::services::storage::LocalStorage::new(dir.path()).unwrap());
+        let original = vec![7u8; 300 * 1024];
+        storage
+            .put("owner/doc/original.pdf", original.clone())
+            .await
+            .unwrap();
+
+        let document_id = Uuid::new_v4();
+        let stream = stream_package(
+            storage,
+            document_id,
+            "Lease".to_string(),
+            Utc::now(),
+            vec![
+                PackageEntry {
+                    name: ORIGINAL_ENTRY,
+                    source: PackageSource::Stored("owner/doc/original.pdf".to_string(), None),
+                    server_signature: None,
+                },
+                PackageEntry {
+                    name: CERTIFICATE_ENTRY,
+                    source: PackageSource::Generated(b"{}".to_vec()),
+                    server_signature: None,
+                },
+            ],
+        );
+
+        let chunks: Vec<_> = stream.collect().await;
+        let data: Vec<u8> = chunks
+            .into_iter()
+            .flat_map(|chunk| chunk.unwrap().to_vec())
+            .collect();
+
+        let zip = ZipFileReader::new(data).await.unwrap();
+        let names: Vec<_> = zip
+            .file()
+            .entries()
+            .iter()
+            .map(|entry| entry.filename().as_str().unwrap().to_string())
+            .collect();
+        assert_eq!(names, [ORIGINAL_ENTRY, CERTIFICATE_ENTRY, MANIFEST_ENTRY]);
+
+        let mut manifest = String::new();
+        zip.reader_with_entry(2)
+            .await
+            .unwrap()
+            .read_to_string(&mut manifest)
+            .await
+            .unwrap();
+        let manifest: PackageManifest = serde_json::from_str(&manifest).unwrap();
+        assert_eq!(manifest.document_id, document_id);
+        assert_eq!(manifest.entries.len(), 2);
+        assert_eq!(manifest.entries[0].size, original.len() as u64);
+        assert_eq!(
+            manifest.entries[0].sha256,
+            hex::encode(Sha256::digest(&original))
+        );
+
+        let mut extracted = Vec::new();
+        zip.reader_with_entry(0)
+            .await
+            .unwrap()
+            .read_to_end(&mut extracted)
+            .await
+            .unwrap();
+        assert_eq!(extracted, original);
+    }
+
+    #[tokio::test]
+    async fn test_missing_file_fails_the_stream() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage: Arc<dyn Storage> =
+            Arc::new(crate::services::storage::LocalStorage::new(dir.path()).unwrap());
+
+        let stream = stream_package(
+            storage,
+            Uuid::new_v4(),
+            "Lease".to_string(),
+            Utc::now(),
+            vec![PackageEntry {
+                name: ORIGINAL_ENTRY,
+                source: PackageSource::Stored("owner/doc/original.pdf".to_string(), None),
+                server_signature: None,
+            }],
+        );
+
+        let chunks: Vec<_> = stream.collect().await;
+        assert!(chunks.last().unwrap().is_err());
+    }
+}
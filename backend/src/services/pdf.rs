@@ -1,36 +1,491 @@
-use anyhow::Result;
-use lopdf::{Document, Object};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use bytes::Bytes;
+use lopdf::{Dictionary, Document, Object, ObjectId};
+use serde::Serialize;
+use sqlx::PgPool;
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+use thiserror::Error;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+use tokio::sync::Semaphore;
 
-pub fn validate_pdf(path: &Path) -> Result<()> {
-    let doc = Document::load(path)?;
+use crate::db;
+use crate::models::document::{DocumentPage, FieldType, SkippedFormField};
+use crate::services::storage::Storage;
+
+#[derive(Debug, Error)]
+pub enum PdfValidationError {
+    /// The file has an `/Encrypt` dictionary. `requires_password` is false for
+    /// files that open without a password but are restricted by an owner
+    /// password; those are refused too since they cannot be modified.
+    #[error("Password-protected PDFs are not supported")]
+    PasswordProtected { requires_password: bool },
+
+    /// An Office file, e.g. one renamed to `.pdf`. `kind` is what the user is
+    /// asked to convert, such as `.docx`.
+    #[error("Office documents are not supported — convert your {kind} to PDF before uploading.")]
+    OfficeDocument { kind: &'static str },
+
+    #[error("File is not a PDF")]
+    NotPdf,
+
+    #[error("PDF has {pages} pages, the maximum is {max}")]
+    TooManyPages { pages: usize, max: usize },
+
+    #[error(
+        "Page {page} is {width} x {height} pt, pages must be larger than 0 and at most {max} pt on each side"
+    )]
+    InvalidPageSize {
+        page: u32,
+        width: f64,
+        height: f64,
+        max: f64,
+    },
+
+    #[error("Invalid PDF file: {0}")]
+    Invalid(String),
+
+    #[error(transparent)]
+    Other(#[from] anyhow::Error),
+}
+
+/// Runs lopdf work on the blocking thread pool instead of the async runtime,
+/// with at most a fixed number of jobs at once so a burst of uploads cannot
+/// queue up unbounded blocking tasks.
+pub struct PdfWorkers {
+    permits: Semaphore,
+}
+
+impl PdfWorkers {
+    pub fn new(max_concurrent_jobs: usize) -> Self {
+        Self {
+            permits: Semaphore::new(max_concurrent_jobs.max(1)),
+        }
+    }
+
+    pub(crate) async fn run<T, F>(&self, job: F) -> Result<T>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let _permit = self.permits.acquire().await?;
+        Ok(tokio::task::spawn_blocking(job).await?)
+    }
+
+    pub async fn validate(&self, data: Bytes) -> Result<(), PdfValidationError> {
+        self.run(move || validate_pdf(&data)).await?
+    }
+
+    pub async fn check_limits(
+        &self,
+        data: Bytes,
+        limits: PdfLimits,
+    ) -> Result<(), PdfValidationError> {
+        self.run(move || check_limits(&data, &limits)).await?
+    }
+
+    pub async fn metadata(&self, data: Bytes) -> Result<PdfMetadata> {
+        self.run(move || get_pdf_metadata(&data)).await?
+    }
+
+    pub async fn security_scan(&self, data: Bytes) -> Result<SecurityScan> {
+        self.run(move || security_scan(&data)).await?
+    }
+
+    pub async fn strip_active_content(&self, data: Bytes) -> Result<Vec<u8>> {
+        self.run(move || strip_active_content(&data)).await?
+    }
+
+    pub async fn acroform(&self, data: Bytes) -> Result<AcroForm> {
+        self.run(move || read_acroform(&data)).await?
+    }
+
+    /// Renders a page with `renderer`, counting towards the same limit as
+    /// parsing since rendering is at least as CPU hungry.
+    pub async fn render_page(
+        &self,
+        renderer: &dyn Renderer,
+        data: Vec<u8>,
+        page: u32,
+        width: u32,
+    ) -> Result<Vec<u8>, RenderError> {
+        let _permit = self.permits.acquire().await.map_err(anyhow::Error::from)?;
+        renderer.render_page(data, page, width).await
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum RenderError {
+    /// The rendering backend is not installed or cannot be started.
+    #[error("Page previews are not available: {0}")]
+    Unavailable(String),
+
+    #[error(transparent)]
+    Other(#[from] anyhow::Error),
+}
+
+/// Rasterizes PDF pages for previews.
+#[async_trait]
+pub trait Renderer: Send + Sync {
+    /// Renders page `page` (1-based) of `data` to a PNG `width` pixels wide,
+    /// keeping the page's aspect ratio.
+    async fn render_page(
+        &self,
+        data: Vec<u8>,
+        page: u32,
+        width: u32,
+    ) -> Result<Vec<u8>, RenderError>;
+}
+
+/// Renders with poppler's `pdftoppm`, fed the PDF on stdin.
+pub struct PdftoppmRenderer {
+    command: PathBuf,
+    timeout: Duration,
+}
+
+impl PdftoppmRenderer {
+    pub fn new(command: impl Into<PathBuf>, timeout: Duration) -> Self {
+        Self {
+            command: command.into(),
+            timeout,
+        }
+    }
+}
+
+#[async_trait]
+impl Renderer for PdftoppmRenderer {
+    async fn render_page(
+        &self,
+        data: Vec<u8>,
+        page: u32,
+        width: u32,
+    ) -> Result<Vec<u8>, RenderError> {
+        let page = page.to_string();
+        let mut child = Command::new(&self.command)
+            .args(["-png", "-singlefile", "-f", &page, "-l", &page])
+            .args(["-scale-to-x", &width.to_string(), "-scale-to-y", "-1", "-"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| {
+                RenderError::Unavailable(format!("failed to run {}: {}", self.command.display(), e))
+            })?;
+
+        let mut stdin = child.stdin.take().context("pdftoppm stdin is not piped")?;
+        let write = async move {
+            // pdftoppm may exit before reading everything, e.g. on a bad page
+            let _ = stdin.write_all(&data).await;
+        };
+
+        let (_, output) = tokio::time::timeout(self.timeout, async {
+            tokio::join!(write, child.wait_with_output())
+        })
+        .await
+        .map_err(|_| anyhow::anyhow!("pdftoppm timed out after {:?}", self.timeout))?;
+        let output = output.context("Failed to wait for pdftoppm")?;
+
+        if !output.status.success() || output.stdout.is_empty() {
+            return Err(anyhow::anyhow!(
+                "pdftoppm failed ({}): {}",
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )
+            .into());
+        }
+
+        Ok(output.stdout)
+    }
+}
+
+const PDF_MAGIC: &[u8] = b"%PDF-";
+const EOF_MARKER: &[u8] = b"%%EOF";
+/// How far from the end the `%%EOF` marker may be, as in most readers.
+const EOF_SEARCH_WINDOW: usize = 1024;
+const ZIP_MAGIC: &[u8] = b"PK\x03\x04";
+const OLE_MAGIC: &[u8] = b"\xD0\xCF\x11\xE0\xA1\xB1\x1A\xE1";
+
+/// Checks the file is a PDF from its bytes rather than its name or declared
+/// content type: a `%PDF-` header with a 1.x or 2.x version, and an `%%EOF`
+/// marker near the end.
+pub fn check_file_signature(data: &[u8]) -> Result<(), PdfValidationError> {
+    let Some(version) = data.strip_prefix(PDF_MAGIC) else {
+        return Err(
+            detect_office_document(data).map_or(PdfValidationError::NotPdf, |kind| {
+                PdfValidationError::OfficeDocument { kind }
+            }),
+        );
+    };
+
+    if !matches!(version, [b'1' | b'2', b'.', b'0'..=b'9', ..]) {
+        return Err(PdfValidationError::Invalid(
+            "Unsupported PDF version".to_string(),
+        ));
+    }
+
+    let tail = &data[data.len().saturating_sub(EOF_SEARCH_WINDOW)..];
+    if !tail.windows(EOF_MARKER.len()).any(|w| w == EOF_MARKER) {
+        return Err(PdfValidationError::Invalid(
+            "File is truncated, the end-of-file marker is missing".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+fn detect_office_document(data: &[u8]) -> Option<&'static str> {
+    if data.starts_with(OLE_MAGIC) {
+        return Some("Office document");
+    }
+
+    if !data.starts_with(ZIP_MAGIC) {
+        return None;
+    }
+
+    // OOXML packages are zip files whose entry names reveal the application
+    let contains = |needle: &[u8]| data.windows(needle.len()).any(|w| w == needle);
+    if contains(b"word/") {
+        Some(".docx")
+    } else if contains(b"xl/") {
+        Some(".xlsx")
+    } else if contains(b"ppt/") {
+        Some(".pptx")
+    } else {
+        None
+    }
+}
+
+pub fn validate_pdf(data: &[u8]) -> Result<(), PdfValidationError> {
+    check_file_signature(data)?;
+
+    let mut doc = match Document::load_mem(data) {
+        Ok(doc) => doc,
+        // Encrypted object streams cannot be parsed without the password
+        Err(_) if data.windows(8).any(|w| w == b"/Encrypt") => {
+            return Err(PdfValidationError::PasswordProtected {
+                requires_password: true,
+            })
+        }
+        Err(e) => return Err(PdfValidationError::Invalid(e.to_string())),
+    };
+
+    if doc.trailer.has(b"Encrypt") {
+        return Err(PdfValidationError::PasswordProtected {
+            requires_password: doc.decrypt("").is_err(),
+        });
+    }
 
     if doc.get_pages().is_empty() {
-        return Err(anyhow::anyhow!("PDF has no pages"));
+        return Err(PdfValidationError::Invalid("PDF has no pages".to_string()));
+    }
+
+    Ok(())
+}
+
+/// Upload limits on the shape of a document, beyond its file size.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct PdfLimits {
+    pub max_pages: usize,
+    /// Largest page width or height, in points.
+    pub max_page_dimension: f64,
+}
+
+/// Rejects documents with too many pages, or pages whose MediaBox is empty or
+/// too large for field coordinates to make sense.
+pub fn check_limits(data: &[u8], limits: &PdfLimits) -> Result<(), PdfValidationError> {
+    let doc = Document::load_mem(data).map_err(|e| PdfValidationError::Invalid(e.to_string()))?;
+    let pages = doc.get_pages();
+
+    if pages.len() > limits.max_pages {
+        return Err(PdfValidationError::TooManyPages {
+            pages: pages.len(),
+            max: limits.max_pages,
+        });
+    }
+
+    for (page, page_id) in pages {
+        let Ok(Object::Dictionary(page_dict)) = doc.get_object(page_id) else {
+            continue;
+        };
+
+        let (x1, y1, x2, y2) = get_media_box(&doc, page_dict);
+        let (width, height) = ((x2 - x1).abs(), (y2 - y1).abs());
+        let valid = |side: f64| side > 0.0 && side <= limits.max_page_dimension;
+
+        if !valid(width) || !valid(height) {
+            return Err(PdfValidationError::InvalidPageSize {
+                page,
+                width,
+                height,
+                max: limits.max_page_dimension,
+            });
+        }
     }
 
     Ok(())
 }
 
-pub fn get_page_count(path: &Path) -> Result<usize> {
-    let doc = Document::load(path)?;
+/// Content that makes a viewer run or open something rather than just display
+/// the document.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ActiveContent {
+    JavaScript,
+    EmbeddedFiles,
+    LaunchAction,
+    XfaForm,
+}
+
+impl ActiveContent {
+    pub fn label(self) -> &'static str {
+        match self {
+            ActiveContent::JavaScript => "JavaScript",
+            ActiveContent::EmbeddedFiles => "embedded files",
+            ActiveContent::LaunchAction => "launch actions",
+            ActiveContent::XfaForm => "XFA forms",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SecurityScan {
+    pub findings: Vec<ActiveContent>,
+    /// Whether the findings were stripped from the stored file.
+    pub stripped: bool,
+}
+
+/// Looks for active content anywhere in the object tree.
+pub fn security_scan(data: &[u8]) -> Result<SecurityScan> {
+    let doc = Document::load_mem(data)?;
+
+    let mut found = BTreeSet::new();
+    for object in doc.objects.values() {
+        scan_object(object, &mut found);
+    }
+
+    Ok(SecurityScan {
+        findings: found.into_iter().collect(),
+        stripped: false,
+    })
+}
+
+/// Removes active content and returns the rewritten file. JavaScript and
+/// launch actions are emptied rather than removed so references to them stay
+/// valid; embedded files left unreferenced are dropped.
+pub fn strip_active_content(data: &[u8]) -> Result<Vec<u8>> {
+    let mut doc = Document::load_mem(data)?;
+
+    for object in doc.objects.values_mut() {
+        strip_object(object);
+    }
+    doc.prune_objects();
+
+    let mut stripped = Vec::new();
+    doc.save_to(&mut stripped)?;
+    Ok(stripped)
+}
+
+fn active_key(key: &[u8]) -> Option<ActiveContent> {
+    match key {
+        b"JavaScript" | b"JS" => Some(ActiveContent::JavaScript),
+        b"EmbeddedFiles" | b"EF" => Some(ActiveContent::EmbeddedFiles),
+        b"XFA" => Some(ActiveContent::XfaForm),
+        _ => None,
+    }
+}
+
+fn active_action(dict: &Dictionary) -> Option<ActiveContent> {
+    match dict.get(b"S").and_then(Object::as_name).ok()? {
+        b"JavaScript" => Some(ActiveContent::JavaScript),
+        b"Launch" => Some(ActiveContent::LaunchAction),
+        _ => None,
+    }
+}
+
+fn scan_object(object: &Object, found: &mut BTreeSet<ActiveContent>) {
+    let dict = match object {
+        Object::Dictionary(dict) => dict,
+        Object::Stream(stream) => &stream.dict,
+        Object::Array(items) => {
+            for item in items {
+                scan_object(item, found);
+            }
+            return;
+        }
+        _ => return,
+    };
+
+    found.extend(active_action(dict));
+    for (key, value) in dict.iter() {
+        found.extend(active_key(key));
+        scan_object(value, found);
+    }
+}
+
+fn strip_object(object: &mut Object) {
+    let dict = match object {
+        Object::Dictionary(dict) => dict,
+        Object::Stream(stream) => &mut stream.dict,
+        Object::Array(items) => {
+            items.iter_mut().for_each(strip_object);
+            return;
+        }
+        _ => return,
+    };
+
+    if active_action(dict).is_some() {
+        *dict = Dictionary::new();
+        return;
+    }
+
+    let active_keys: Vec<Vec<u8>> = dict
+        .iter()
+        .filter(|(key, _)| active_key(key).is_some())
+        .map(|(key, _)| key.clone())
+        .collect();
+    for key in active_keys {
+        dict.remove(&key);
+    }
+
+    for (_, value) in dict.iter_mut() {
+        strip_object(value);
+    }
+}
+
+pub fn get_page_count(data: &[u8]) -> Result<usize> {
+    let doc = Document::load_mem(data)?;
     Ok(doc.get_pages().len())
 }
 
-pub fn get_pdf_metadata(path: &Path) -> Result<PdfMetadata> {
-    let doc = Document::load(path)?;
+pub fn get_pdf_metadata(data: &[u8]) -> Result<PdfMetadata> {
+    let doc = Document::load_mem(data)?;
     let pages = doc.get_pages();
     let page_count = pages.len();
 
     let mut page_sizes = Vec::new();
 
-    for (_page_num, page_id) in pages {
+    for (page_num, page_id) in pages {
         if let Ok(Object::Dictionary(page_dict)) = doc.get_object(page_id) {
             let media_box = get_media_box(&doc, page_dict);
+            let (width, height) = (media_box.2 - media_box.0, media_box.3 - media_box.1);
+
+            // Pages turned a quarter are displayed with their sides swapped
+            let (width, height) = if get_rotation(&doc, page_dict).rem_euclid(180) == 90 {
+                (height, width)
+            } else {
+                (width, height)
+            };
+
             page_sizes.push(PageSize {
-                width: media_box.2 - media_box.0,
-                height: media_box.3 - media_box.1,
+                number: page_num,
+                width,
+                height,
             });
         }
     }
@@ -41,6 +496,30 @@ pub fn get_pdf_metadata(path: &Path) -> Result<PdfMetadata> {
     })
 }
 
+/// The page's `/Rotate` in degrees, which may be inherited from its ancestors
+/// in the page tree.
+fn get_rotation(doc: &Document, page_dict: &lopdf::Dictionary) -> i64 {
+    let mut dict = page_dict;
+
+    // Bounded in case of a cyclic page tree
+    for _ in 0..32 {
+        if let Ok(rotate) = dict.get(b"Rotate").and_then(Object::as_i64) {
+            return rotate;
+        }
+
+        match dict
+            .get(b"Parent")
+            .and_then(Object::as_reference)
+            .and_then(|parent| doc.get_dictionary(parent))
+        {
+            Ok(parent) => dict = parent,
+            Err(_) => break,
+        }
+    }
+
+    0
+}
+
 fn get_media_box(doc: &Document, page_dict: &lopdf::Dictionary) -> (f64, f64, f64, f64) {
     if let Ok(Object::Array(arr)) = page_dict.get(b"MediaBox") {
         if arr.len() >= 4 {
@@ -77,6 +556,344 @@ fn get_number(obj: &Object) -> Option<f64> {
     }
 }
 
+const FIELD_FLAG_RADIO: i64 = 1 << 15;
+const FIELD_FLAG_PUSHBUTTON: i64 = 1 << 16;
+
+/// A widget of an AcroForm field, positioned like document fields: in points
+/// from the top-left corner of the page as displayed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FormWidget {
+    /// Fully qualified field name, with `#n` appended when the field has
+    /// several widgets.
+    pub name: String,
+    pub field_type: FieldType,
+    pub page: u32,
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+#[derive(Debug, Default)]
+pub struct AcroForm {
+    pub widgets: Vec<FormWidget>,
+    pub skipped: Vec<SkippedFormField>,
+}
+
+/// Attributes a form field inherits from its ancestors.
+#[derive(Clone, Default)]
+struct InheritedField {
+    name: Option<String>,
+    field_type: Option<Vec<u8>>,
+    flags: i64,
+}
+
+struct PagePlacement {
+    number: u32,
+    media_box: (f64, f64, f64, f64),
+    rotation: i64,
+}
+
+/// Reads the fields of the PDF's AcroForm. Text fields, checkboxes and
+/// signature fields are returned as widgets; other fields are listed as
+/// skipped with the reason.
+pub fn read_acroform(data: &[u8]) -> Result<AcroForm> {
+    let doc = Document::load_mem(data)?;
+    let mut form = AcroForm::default();
+
+    let Some(fields) = doc
+        .catalog()
+        .ok()
+        .and_then(|catalog| catalog.get(b"AcroForm").ok())
+        .and_then(|acroform| resolve_dict(&doc, acroform))
+        .and_then(|acroform| acroform.get(b"Fields").ok())
+        .and_then(|fields| resolve(&doc, fields).as_array().ok())
+    else {
+        return Ok(form);
+    };
+
+    let mut pages_by_id = HashMap::new();
+    let mut pages_by_annot = HashMap::new();
+    for (number, page_id) in doc.get_pages() {
+        let Ok(page_dict) = doc.get_dictionary(page_id) else {
+            continue;
+        };
+        pages_by_id.insert(
+            page_id,
+            PagePlacement {
+                number,
+                media_box: get_media_box(&doc, page_dict),
+                rotation: get_rotation(&doc, page_dict),
+            },
+        );
+
+        if let Some(annots) = page_dict
+            .get(b"Annots")
+            .ok()
+            .and_then(|annots| resolve(&doc, annots).as_array().ok())
+        {
+            for annot in annots {
+                if let Ok(id) = annot.as_reference() {
+                    pages_by_annot.insert(id, page_id);
+                }
+            }
+        }
+    }
+
+    let mut visited = HashSet::new();
+    for field in fields {
+        collect_form_field(
+            &doc,
+            field,
+            &InheritedField::default(),
+            &mut visited,
+            &mut |name, kind, widgets| {
+                let field_type = match kind {
+                    Ok(field_type) => field_type,
+                    Err(reason) => {
+                        form.skipped.push(SkippedFormField {
+                            name,
+                            reason: reason.to_string(),
+                        });
+                        return;
+                    }
+                };
+
+                let numbered = widgets.len() > 1;
+                for (i, (id, widget)) in widgets.into_iter().enumerate() {
+                    let name = if numbered {
+                        format!("{}#{}", name, i + 1)
+                    } else {
+                        name.clone()
+                    };
+
+                    // Pages list their widgets in /Annots; /P is optional
+                    let page = id
+                        .and_then(|id| pages_by_annot.get(&id).copied())
+                        .or_else(|| widget.get(b"P").and_then(Object::as_reference).ok())
+                        .and_then(|page_id| pages_by_id.get(&page_id));
+                    let rect = widget
+                        .get(b"Rect")
+                        .ok()
+                        .and_then(|rect| get_rect(&doc, rect));
+
+                    let (Some(page), Some(rect)) = (page, rect) else {
+                        form.skipped.push(SkippedFormField {
+                            name,
+                            reason: "Not placed on a page".to_string(),
+                        });
+                        continue;
+                    };
+
+                    let (x, y, width, height) = to_page_space(rect, page.media_box, page.rotation);
+                    if width <= 0.0 || height <= 0.0 {
+                        form.skipped.push(SkippedFormField {
+                            name,
+                            reason: "Has no visible area".to_string(),
+                        });
+                        continue;
+                    }
+
+                    form.widgets.push(FormWidget {
+                        name,
+                        field_type,
+                        page: page.number,
+                        x,
+                        y,
+                        width,
+                        height,
+                    });
+                }
+            },
+        );
+    }
+
+    Ok(form)
+}
+
+type FieldKind = std::result::Result<FieldType, &'static str>;
+
+/// A widget annotation and its object id, if it is an indirect object.
+type Widget<'a> = (Option<ObjectId>, &'a Dictionary);
+
+/// Walks a field and its descendants, calling `terminal` for every field that
+/// has widgets with its qualified name, kind and widgets.
+fn collect_form_field<'a>(
+    doc: &'a Document,
+    field: &'a Object,
+    parent: &InheritedField,
+    visited: &mut HashSet<ObjectId>,
+    terminal: &mut dyn FnMut(String, FieldKind, Vec<Widget<'a>>),
+) {
+    let id = field.as_reference().ok();
+    if let Some(id) = id {
+        // Guards against cyclic field trees
+        if !visited.insert(id) || visited.len() > 10_000 {
+            return;
+        }
+    }
+
+    let Some(dict) = resolve_dict(doc, field) else {
+        return;
+    };
+
+    let mut inherited = parent.clone();
+    if let Some(partial) = dict.get(b"T").ok().and_then(|t| t.as_str().ok()) {
+        let partial = decode_text_string(partial);
+        inherited.name = Some(match &parent.name {
+            Some(parent) => format!("{}.{}", parent, partial),
+            None => partial,
+        });
+    }
+    if let Ok(field_type) = dict.get(b"FT").and_then(Object::as_name) {
+        inherited.field_type = Some(field_type.to_vec());
+    }
+    if let Ok(flags) = dict.get(b"Ff").and_then(Object::as_i64) {
+        inherited.flags = flags;
+    }
+
+    let mut widgets = Vec::new();
+    if is_widget(dict) {
+        widgets.push((id, dict));
+    }
+
+    let kids = dict
+        .get(b"Kids")
+        .ok()
+        .and_then(|kids| resolve(doc, kids).as_array().ok());
+    for kid in kids.into_iter().flatten() {
+        match resolve_dict(doc, kid) {
+            Some(kid_dict) if is_widget(kid_dict) && !kid_dict.has(b"T") => {
+                widgets.push((kid.as_reference().ok(), kid_dict));
+            }
+            Some(_) => collect_form_field(doc, kid, &inherited, visited, terminal),
+            None => {}
+        }
+    }
+
+    if !widgets.is_empty() {
+        let name = inherited
+            .name
+            .clone()
+            .unwrap_or_else(|| "(unnamed)".to_string());
+        let kind = form_field_kind(inherited.field_type.as_deref(), inherited.flags);
+        terminal(name, kind, widgets);
+    }
+}
+
+fn form_field_kind(field_type: Option<&[u8]>, flags: i64) -> FieldKind {
+    match field_type {
+        Some(b"Tx") => Ok(FieldType::Text),
+        Some(b"Sig") => Ok(FieldType::Signature),
+        Some(b"Btn") if flags & FIELD_FLAG_PUSHBUTTON != 0 => Err("Push buttons are not supported"),
+        Some(b"Btn") if flags & FIELD_FLAG_RADIO != 0 => Err("Radio buttons are not supported"),
+        Some(b"Btn") => Ok(FieldType::Checkbox),
+        Some(b"Ch") => Err("Choice fields are not supported"),
+        _ => Err("Unknown field type"),
+    }
+}
+
+fn is_widget(dict: &Dictionary) -> bool {
+    dict.get(b"Subtype")
+        .and_then(Object::as_name)
+        .is_ok_and(|subtype| subtype == b"Widget")
+}
+
+fn resolve<'a>(doc: &'a Document, object: &'a Object) -> &'a Object {
+    doc.dereference(object)
+        .map(|(_, object)| object)
+        .unwrap_or(object)
+}
+
+fn resolve_dict<'a>(doc: &'a Document, object: &'a Object) -> Option<&'a Dictionary> {
+    resolve(doc, object).as_dict().ok()
+}
+
+fn get_rect(doc: &Document, rect: &Object) -> Option<(f64, f64, f64, f64)> {
+    let values: Vec<f64> = resolve(doc, rect)
+        .as_array()
+        .ok()?
+        .iter()
+        .filter_map(get_number)
+        .collect();
+    let [x1, y1, x2, y2] = values[..] else {
+        return None;
+    };
+    Some((x1.min(x2), y1.min(y2), x1.max(x2), y1.max(y2)))
+}
+
+/// Converts a rectangle in PDF user space (origin at the bottom-left of the
+/// MediaBox) to `(x, y, width, height)` from the top-left corner of the page
+/// as displayed with its `/Rotate` applied.
+fn to_page_space(
+    rect: (f64, f64, f64, f64),
+    media_box: (f64, f64, f64, f64),
+    rotation: i64,
+) -> (f64, f64, f64, f64) {
+    let (x1, y1, x2, y2) = rect;
+    let (mx1, my1, mx2, my2) = media_box;
+
+    match rotation.rem_euclid(360) {
+        90 => (y1 - my1, x1 - mx1, y2 - y1, x2 - x1),
+        180 => (mx2 - x2, y1 - my1, x2 - x1, y2 - y1),
+        270 => (my2 - y2, mx2 - x2, y2 - y1, x2 - x1),
+        _ => (x1 - mx1, my2 - y2, x2 - x1, y2 - y1),
+    }
+}
+
+/// Decodes a PDF text string, either UTF-16BE with a byte order mark or
+/// PDFDocEncoding, which matches Latin-1 for printable characters.
+fn decode_text_string(bytes: &[u8]) -> String {
+    match bytes.strip_prefix(&[0xFE, 0xFF]) {
+        Some(utf16) => {
+            let units: Vec<u16> = utf16
+                .chunks_exact(2)
+                .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+                .collect();
+            String::from_utf16_lossy(&units)
+        }
+        None => bytes.iter().map(|&b| b as char).collect(),
+    }
+}
+
+impl From<&PageSize> for DocumentPage {
+    fn from(page: &PageSize) -> Self {
+        Self {
+            number: page.number as i32,
+            width: page.width,
+            height: page.height,
+        }
+    }
+}
+
+/// Page sizes of a document, as cached at upload. Documents uploaded before
+/// they were cached have them parsed from the stored file and saved.
+pub async fn load_pages(
+    pool: &PgPool,
+    storage: &dyn Storage,
+    workers: &PdfWorkers,
+    document: &crate::models::document::Document,
+) -> Result<Vec<DocumentPage>> {
+    if let Some(page_sizes) = &document.page_sizes {
+        return Ok(page_sizes.0.clone());
+    }
+
+    let data = storage
+        .get_file(&document.file_path, document.original_encryption().as_ref())
+        .await?;
+    let pages: Vec<DocumentPage> = workers
+        .metadata(Bytes::from(data))
+        .await?
+        .page_sizes
+        .iter()
+        .map(DocumentPage::from)
+        .collect();
+
+    db::document::set_page_metadata(pool, document.id, &pages).await?;
+
+    Ok(pages)
+}
+
 #[derive(Debug, Clone)]
 pub struct PdfMetadata {
     pub page_count: usize,
@@ -85,6 +902,8 @@ pub struct PdfMetadata {
 
 #[derive(Debug, Clone)]
 pub struct PageSize {
+    /// 1-based page number.
+    pub number: u32,
     pub width: f64,
     pub height: f64,
 }
@@ -103,3 +922,243 @@ pub fn delete_file(path: &Path) -> Result<()> {
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LETTER: (f64, f64, f64, f64) = (0.0, 0.0, 612.0, 792.0);
+
+    #[test]
+    fn test_validate_pdf_rejects_encrypted_files() {
+        let encrypted = include_bytes!("../../tests/fixtures/encrypted.pdf");
+        assert!(matches!(
+            validate_pdf(encrypted),
+            Err(PdfValidationError::PasswordProtected {
+                requires_password: true
+            })
+        ));
+
+        let empty_user_password =
+            include_bytes!("../../tests/fixtures/encrypted-empty-user-password.pdf");
+        assert!(matches!(
+            validate_pdf(empty_user_password),
+            Err(PdfValidationError::PasswordProtected {
+                requires_password: false
+            })
+        ));
+
+        assert!(matches!(
+            validate_pdf(b"%PDF-1.4\ngarbage\n%%EOF\n"),
+            Err(PdfValidationError::Invalid(_))
+        ));
+    }
+
+    #[test]
+    fn test_check_file_signature() {
+        assert!(check_file_signature(b"%PDF-1.7\n...\n%%EOF\n").is_ok());
+        assert!(check_file_signature(b"%PDF-2.0\n...\n%%EOF").is_ok());
+
+        assert!(matches!(
+            check_file_signature(b"MZ\x90\x00 not a pdf"),
+            Err(PdfValidationError::NotPdf)
+        ));
+        assert!(matches!(
+            check_file_signature(b"%PDF-9.1\n%%EOF"),
+            Err(PdfValidationError::Invalid(_))
+        ));
+        assert!(matches!(
+            check_file_signature(b"%PDF-1.4\n1 0 obj"),
+            Err(PdfValidationError::Invalid(_))
+        ));
+    }
+
+    #[test]
+    fn test_check_file_signature_detects_office_documents() {
+        let docx = b"PK\x03\x04\x14\x00[Content_Types].xmlPK\x03\x04word/document.xml";
+        assert!(matches!(
+            check_file_signature(docx),
+            Err(PdfValidationError::OfficeDocument { kind: ".docx" })
+        ));
+
+        let doc = b"\xD0\xCF\x11\xE0\xA1\xB1\x1A\xE1\x00\x00";
+        assert!(matches!(
+            check_file_signature(doc),
+            Err(PdfValidationError::OfficeDocument {
+                kind: "Office document"
+            })
+        ));
+
+        assert!(matches!(
+            check_file_signature(b"PK\x03\x04archive.txt"),
+            Err(PdfValidationError::NotPdf)
+        ));
+    }
+
+    #[test]
+    fn test_page_sizes_apply_rotation() {
+        let rotated = include_bytes!("../../tests/fixtures/rotated.pdf");
+        let sizes: Vec<(u32, f64, f64)> = get_pdf_metadata(rotated)
+            .unwrap()
+            .page_sizes
+            .iter()
+            .map(|p| (p.number, p.width, p.height))
+            .collect();
+
+        // The first page inherits /Rotate 90 from the page tree
+        assert_eq!(sizes, vec![(1, 792.0, 612.0), (2, 612.0, 792.0)]);
+    }
+
+    #[test]
+    fn test_check_limits() {
+        let sample = include_bytes!("../../tests/fixtures/active-content.pdf");
+        let limits = PdfLimits {
+            max_pages: 1,
+            max_page_dimension: 14400.0,
+        };
+        assert!(check_limits(sample, &limits).is_ok());
+
+        let no_pages = PdfLimits {
+            max_pages: 0,
+            ..limits
+        };
+        assert!(matches!(
+            check_limits(sample, &no_pages),
+            Err(PdfValidationError::TooManyPages { pages: 1, max: 0 })
+        ));
+
+        let small = PdfLimits {
+            max_page_dimension: 700.0,
+            ..limits
+        };
+        assert!(matches!(
+            check_limits(sample, &small),
+            Err(PdfValidationError::InvalidPageSize { page: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn test_security_scan_and_strip() {
+        let active = include_bytes!("../../tests/fixtures/active-content.pdf");
+        assert_eq!(
+            security_scan(active).unwrap().findings,
+            vec![ActiveContent::JavaScript, ActiveContent::EmbeddedFiles]
+        );
+
+        let stripped = strip_active_content(active).unwrap();
+        assert!(security_scan(&stripped).unwrap().findings.is_empty());
+        assert!(validate_pdf(&stripped).is_ok());
+        assert_eq!(get_page_count(&stripped).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_read_acroform() {
+        let form = read_acroform(include_bytes!("../../tests/fixtures/acroform.pdf")).unwrap();
+
+        let widget = |name: &str, field_type, page, (x, y, width, height)| FormWidget {
+            name: name.to_string(),
+            field_type,
+            page,
+            x,
+            y,
+            width,
+            height,
+        };
+        assert_eq!(
+            form.widgets,
+            vec![
+                widget("full_name", FieldType::Text, 1, (72.0, 68.0, 200.0, 24.0)),
+                widget("agree", FieldType::Checkbox, 1, (72.0, 128.0, 14.0, 14.0)),
+                widget(
+                    "Signé",
+                    FieldType::Signature,
+                    1,
+                    (300.0, 642.0, 200.0, 50.0)
+                ),
+                widget(
+                    "address.city#1",
+                    FieldType::Text,
+                    1,
+                    (72.0, 172.0, 200.0, 20.0)
+                ),
+                // Page 2 is turned a quarter
+                widget(
+                    "address.city#2",
+                    FieldType::Text,
+                    2,
+                    (100.0, 72.0, 20.0, 200.0)
+                ),
+            ]
+        );
+
+        let skipped: Vec<&str> = form.skipped.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(skipped, vec!["choice", "reset"]);
+
+        let sample = include_bytes!("../../tests/fixtures/rotated.pdf");
+        assert!(read_acroform(sample).unwrap().widgets.is_empty());
+    }
+
+    #[test]
+    fn test_to_page_space_rotations() {
+        let rect = (100.0, 600.0, 300.0, 650.0);
+        assert_eq!(to_page_space(rect, LETTER, 0), (100.0, 142.0, 200.0, 50.0));
+        assert_eq!(to_page_space(rect, LETTER, 90), (600.0, 100.0, 50.0, 200.0));
+        assert_eq!(
+            to_page_space(rect, LETTER, 180),
+            (312.0, 600.0, 200.0, 50.0)
+        );
+        assert_eq!(
+            to_page_space(rect, LETTER, -90),
+            (142.0, 312.0, 50.0, 200.0)
+        );
+
+        let offset = (10.0, 20.0, 622.0, 812.0);
+        assert_eq!(
+            to_page_space((110.0, 620.0, 310.0, 670.0), offset, 0),
+            (100.0, 142.0, 200.0, 50.0)
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_pdftoppm_renderer() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let script = |name: &str, body: &str| {
+            let path = dir.path().join(name);
+            fs::write(&path, format!("#!/bin/sh\n{}\n", body)).unwrap();
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+            PdftoppmRenderer::new(path, Duration::from_secs(10))
+        };
+
+        let args = dir.path().join("args");
+        let renderer = script(
+            "pdftoppm",
+            &format!(
+                "echo \"$@\" > {}; cat > /dev/null; printf PNG",
+                args.display()
+            ),
+        );
+        let png = renderer
+            .render_page(b"%PDF-1.4".to_vec(), 2, 640)
+            .await
+            .unwrap();
+        assert_eq!(png, b"PNG");
+        assert_eq!(
+            fs::read_to_string(&args).unwrap().trim(),
+            "-png -singlefile -f 2 -l 2 -scale-to-x 640 -scale-to-y -1 -"
+        );
+
+        let failing = script("failing", "echo 'Wrong page range' >&2; exit 99");
+        let err = failing.render_page(Vec::new(), 1, 640).await.unwrap_err();
+        assert!(matches!(err, RenderError::Other(_)));
+        assert!(err.to_string().contains("Wrong page range"), "{}", err);
+
+        let missing = PdftoppmRenderer::new(dir.path().join("missing"), Duration::from_secs(10));
+        assert!(matches!(
+            missing.render_page(Vec::new(), 1, 640).await,
+            Err(RenderError::Unavailable(_))
+        ));
+    }
+}
@@ -0,0 +1,300 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use utoipa::ToSchema;
+use uuid::Uuid;
+use validator::ValidateEmail;
+
+use crate::models::document::{Document, DocumentFieldRow, DocumentPage, FieldType};
+use crate::models::signer::{Signer, SignerOpenLink, SignerRole};
+
+/// Slack for rounding in clients that derive positions from pixels.
+const BOUNDS_EPSILON: f64 = 1e-6;
+
+/// Domains reserved for documentation and testing (RFC 2606, RFC 6761),
+/// which never receive mail.
+const RESERVED_DOMAINS: [&str; 3] = ["example.com", "example.net", "example.org"];
+const RESERVED_SUFFIXES: [&str; 4] = ["example", "invalid", "localhost", "test"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PreflightCode {
+    /// The document has no signers and is not self-sign.
+    NoSigners,
+    /// A signer has no signature or initial field assigned to them.
+    MissingSignatureField,
+    /// A field is assigned to a signer who is no longer on the document.
+    FieldSignerRemoved,
+    /// A field is assigned to no one, so any signer may fill it. Removing a
+    /// signer leaves their fields like this.
+    FieldUnassigned,
+    /// A field lies on a page the document does not have, or past its edges.
+    FieldOutOfBounds,
+    /// The document expires before it could be signed.
+    ExpiryInPast,
+    /// A signer's email address is malformed.
+    InvalidEmail,
+    /// A signer's email address is on a domain that never receives mail.
+    UndeliverableEmail,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PreflightSeverity {
+    /// Sending is refused.
+    Error,
+    /// Sending is allowed, but probably not what the owner meant.
+    Warning,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct PreflightIssue {
+    pub code: PreflightCode,
+    pub severity: PreflightSeverity,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signer_id: Option<Uuid>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub field_id: Option<Uuid>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PreflightResponse {
+    /// True when no issue is an error, i.e. the document can be sent.
+    pub ready: bool,
+    pub issues: Vec<PreflightIssue>,
+}
+
+impl PreflightIssue {
+    fn new(code: PreflightCode, severity: PreflightSeverity, message: String) -> Self {
+        Self {
+            code,
+            severity,
+            message,
+            signer_id: None,
+            field_id: None,
+        }
+    }
+
+    fn signer(mut self, signer_id: Uuid) -> Self {
+        self.signer_id = Some(signer_id);
+        self
+    }
+
+    fn field(mut self, field_id: Uuid) -> Self {
+        self.field_id = Some(field_id);
+        self
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == PreflightSeverity::Error
+    }
+}
+
+/// Runs every rule a document must pass to be sent, returning all problems
+/// found. Both the preflight endpoint and `send_document` go through here.
+pub fn check(
+    document: &Document,
+    signers: &[Signer],
+    open_links: &[SignerOpenLink],
+    fields: &[DocumentFieldRow],
+    pages: &[DocumentPage],
+    now: DateTime<Utc>,
+) -> Vec<PreflightIssue> {
+    use PreflightCode::*;
+    use PreflightSeverity::*;
+
+    let mut issues = Vec::new();
+
+    if signers.is_empty() && !document.self_sign_only {
+        issues.push(PreflightIssue::new(
+            NoSigners,
+            Error,
+            "Add at least one signer before sending".to_string(),
+        ));
+    }
+
+    if let Some(expires_at) = document.expires_at {
+        if expires_at <= now {
+            issues.push(PreflightIssue::new(
+                ExpiryInPast,
+                Error,
+                "The expiry date has already passed".to_string(),
+            ));
+        }
+    }
+
+    for signer in signers {
+        if !document.self_sign_only
+            && signer.role == SignerRole::Signer
+            && !fields.iter().any(|field| {
+                field.signer_id == Some(signer.id)
+                    && matches!(field.field_type, FieldType::Signature | FieldType::Initial)
+            })
+        {
+            issues.push(
+                PreflightIssue::new(
+                    MissingSignatureField,
+                    Error,
+                    format!("{} has no signature or initial field", signer.name),
+                )
+                .signer(signer.id),
+            );
+        }
+
+        // Open slots get their email when claimed.
+        let unclaimed = open_links
+            .iter()
+            .any(|link| link.signer_id == signer.id && link.claimed_at.is_none());
+        if unclaimed {
+            continue;
+        }
+
+        if let Some((code, severity, problem)) = email_problem(&signer.email) {
+            issues.push(
+                PreflightIssue::new(
+                    code,
+                    severity,
+                    format!("{} ({}) {}", signer.name, signer.email, problem),
+                )
+                .signer(signer.id),
+            );
+        }
+    }
+
+    for field in fields {
+        match field.signer_id {
+            Some(signer_id) if !signers.iter().any(|s| s.id == signer_id) => {
+                issues.push(
+                    PreflightIssue::new(
+                        FieldSignerRemoved,
+                        Error,
+                        format!(
+                            "A {} field on page {} belongs to a signer who was removed",
+                            field_name(field.field_type),
+                            field.page
+                        ),
+                    )
+                    .signer(signer_id)
+                    .field(field.id),
+                );
+            }
+            None if !signers.is_empty() && !document.self_sign_only => {
+                issues.push(
+                    PreflightIssue::new(
+                        FieldUnassigned,
+                        Warning,
+                        format!(
+                            "A {} field on page {} is not assigned to a signer",
+                            field_name(field.field_type),
+                            field.page
+                        ),
+                    )
+                    .field(field.id),
+                );
+            }
+            _ => {}
+        }
+
+        if let Some(problem) = bounds_problem(field, pages) {
+            issues.push(PreflightIssue::new(FieldOutOfBounds, Error, problem).field(field.id));
+        }
+    }
+
+    issues
+}
+
+fn field_name(field_type: FieldType) -> &'static str {
+    match field_type {
+        FieldType::Signature => "signature",
+        FieldType::Date => "date",
+        FieldType::Text => "text",
+        FieldType::Initial => "initial",
+        FieldType::Checkbox => "checkbox",
+        FieldType::Number => "number",
+        FieldType::Radio => "radio",
+    }
+}
+
+fn bounds_problem(field: &DocumentFieldRow, pages: &[DocumentPage]) -> Option<String> {
+    let Some(page) = pages.iter().find(|p| p.number == field.page) else {
+        return Some(format!(
+            "A {} field is on page {}, which the document does not have",
+            field_name(field.field_type),
+            field.page
+        ));
+    };
+
+    let within = field.x >= -BOUNDS_EPSILON
+        && field.y >= -BOUNDS_EPSILON
+        && field.x + field.width <= page.width + BOUNDS_EPSILON
+        && field.y + field.height <= page.height + BOUNDS_EPSILON;
+
+    (!within).then(|| {
+        format!(
+            "A {} field extends past the edges of page {}",
+            field_name(field.field_type),
+            field.page
+        )
+    })
+}
+
+fn email_problem(email: &str) -> Option<(PreflightCode, PreflightSeverity, &'static str)> {
+    if !email.validate_email() {
+        return Some((
+            PreflightCode::InvalidEmail,
+            PreflightSeverity::Error,
+            "is not a valid email address",
+        ));
+    }
+
+    let domain = email
+        .rsplit('@')
+        .next()?
+        .trim_end_matches('.')
+        .to_lowercase();
+    let tld = domain.rsplit('.').next().unwrap_or(&domain);
+
+    if !domain.contains('.')
+        || RESERVED_DOMAINS.contains(&domain.as_str())
+        || RESERVED_SUFFIXES.contains(&tld)
+    {
+        return Some((
+            PreflightCode::UndeliverableEmail,
+            PreflightSeverity::Warning,
+            "is on a domain that does not receive email",
+        ));
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_email_problem() {
+        assert_eq!(email_problem("jane@acme.io"), None);
+        assert_eq!(
+            email_problem("not-an-email").map(|p| p.0),
+            Some(PreflightCode::InvalidEmail)
+        );
+        for email in [
+            "jane@example.com",
+            "jane@Example.ORG",
+            "jane@mail.test",
+            "jane@localhost",
+            "jane@host.invalid",
+        ] {
+            assert_eq!(
+                email_problem(email).map(|p| (p.0, p.1)),
+                Some((
+                    PreflightCode::UndeliverableEmail,
+                    PreflightSeverity::Warning
+                )),
+                "{}",
+                email
+            );
+        }
+    }
+}
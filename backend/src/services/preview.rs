@@ -0,0 +1,79 @@
+use anyhow::Context;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::warn;
+
+use crate::models::document::Document;
+use crate::services::config::Config;
+use crate::services::pdf::{PdfWorkers, PdftoppmRenderer, RenderError, Renderer};
+use crate::services::storage::{self, Storage};
+
+pub const MIN_PREVIEW_WIDTH: u32 = 100;
+pub const DEFAULT_PREVIEW_WIDTH: u32 = 800;
+
+const RENDER_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// PNG previews of document pages, rendered on first request and cached in
+/// storage next to the document. Previews of encrypted documents are rendered
+/// every time instead, so no plaintext copy of their pages is stored.
+pub struct PagePreviews {
+    storage: Arc<dyn Storage>,
+    workers: Arc<PdfWorkers>,
+    renderer: Arc<dyn Renderer>,
+    max_width: u32,
+}
+
+impl PagePreviews {
+    pub fn new(config: &Config, storage: Arc<dyn Storage>, workers: Arc<PdfWorkers>) -> Self {
+        Self {
+            storage,
+            workers,
+            renderer: Arc::new(PdftoppmRenderer::new(&config.pdftoppm_path, RENDER_TIMEOUT)),
+            max_width: config.preview_max_width,
+        }
+    }
+
+    pub fn max_width(&self) -> u32 {
+        self.max_width
+    }
+
+    /// The PNG of page `page` (1-based) at `width` pixels. Callers check the
+    /// page number and width against the document first.
+    pub async fn get(
+        &self,
+        document: &Document,
+        page: u32,
+        width: u32,
+    ) -> Result<Vec<u8>, RenderError> {
+        let key = storage::page_preview_key(&document.file_path, &document.file_hash, page, width);
+        let encryption = document.original_encryption();
+
+        if encryption.is_none() && self.storage.exists(&key).await? {
+            return Ok(self.storage.get(&key).await?);
+        }
+
+        let data = self
+            .storage
+            .get_file(&document.file_path, encryption.as_ref())
+            .await
+            .context("Failed to read PDF")?;
+        let png = self
+            .workers
+            .render_page(self.renderer.as_ref(), data, page, width)
+            .await?;
+
+        if encryption.is_some() {
+            return Ok(png);
+        }
+
+        // A preview that cannot be cached is still worth serving
+        if let Err(e) = self.storage.put(&key, png.clone()).await {
+            warn!(
+                "Failed to cache preview of page {} of document {}: {}",
+                page, document.id, e
+            );
+        }
+
+        Ok(png)
+    }
+}
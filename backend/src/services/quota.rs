@@ -0,0 +1,140 @@
+use anyhow::Result;
+use sqlx::PgPool;
+use std::sync::Arc;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::db;
+use crate::models::user::StorageUsage;
+use crate::services::config::Config;
+use crate::services::storage::Storage;
+
+const BYTES_PER_MB: i64 = 1024 * 1024;
+const BACKFILL_BATCH_SIZE: i64 = 100;
+
+/// The user's storage usage, or `None` if the user does not exist.
+pub async fn get_usage(
+    pool: &PgPool,
+    config: &Config,
+    user_id: Uuid,
+) -> Result<Option<StorageUsage>> {
+    let Some((quota_mb, used_bytes, document_count)) =
+        db::user::get_storage_usage(pool, user_id).await?
+    else {
+        return Ok(None);
+    };
+
+    Ok(Some(StorageUsage {
+        user_id,
+        used_bytes,
+        document_count,
+        quota_bytes: quota_bytes(quota_mb, config.default_storage_quota_mb),
+        custom_quota: quota_mb.is_some(),
+    }))
+}
+
+/// The quota in bytes from a user's override or else the default, both in
+/// MB with 0 meaning unlimited.
+fn quota_bytes(override_mb: Option<i64>, default_mb: u64) -> Option<i64> {
+    let mb = override_mb.unwrap_or(default_mb as i64);
+    (mb > 0).then(|| mb.saturating_mul(BYTES_PER_MB))
+}
+
+/// A byte count for messages, e.g. `12.5 MB`.
+pub fn format_size(bytes: i64) -> String {
+    let bytes = bytes as f64;
+    let mb = BYTES_PER_MB as f64;
+
+    if bytes >= 1024.0 * mb {
+        format!("{:.1} GB", bytes / (1024.0 * mb))
+    } else if bytes >= mb {
+        format!("{:.1} MB", bytes / mb)
+    } else {
+        format!("{:.1} KB", bytes / 1024.0)
+    }
+}
+
+/// Records the sizes of files stored before sizes were tracked, reading them
+/// from storage. Files that cannot be read count as empty; the storage scan
+/// reports missing files. Returns how many documents were updated.
+pub async fn backfill_file_sizes(pool: &PgPool, storage: &dyn Storage) -> Result<usize> {
+    let mut updated = 0;
+
+    loop {
+        let batch = db::document::get_documents_without_sizes(pool, BACKFILL_BATCH_SIZE).await?;
+        if batch.is_empty() {
+            break;
+        }
+
+        for (id, file_path, sealed_file_path) in batch {
+            let file_size = stored_size(storage, &file_path).await;
+            let sealed_file_size = match &sealed_file_path {
+                Some(key) => Some(stored_size(storage, key).await),
+                None => None,
+            };
+
+            db::document::set_file_sizes(pool, id, file_size, sealed_file_size).await?;
+            updated += 1;
+        }
+    }
+
+    Ok(updated)
+}
+
+async fn stored_size(storage: &dyn Storage, key: &str) -> i64 {
+    match storage.size(key).await {
+        Ok(size) => size as i64,
+        Err(e) => {
+            warn!("Could not read the size of {}: {:?}", key, e);
+            0
+        }
+    }
+}
+
+pub fn spawn_size_backfill(pool: PgPool, storage: Arc<dyn Storage>) {
+    tokio::spawn(async move {
+        match backfill_file_sizes(&pool, storage.as_ref()).await {
+            Ok(0) => {}
+            Ok(updated) => info!("Recorded file sizes of {} document(s)", updated),
+            Err(e) => error!("Failed to record document file sizes: {:?}", e),
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn usage(used_bytes: i64, quota_bytes: Option<i64>) -> StorageUsage {
+        StorageUsage {
+            user_id: Uuid::nil(),
+            used_bytes,
+            document_count: 1,
+            quota_bytes,
+            custom_quota: false,
+        }
+    }
+
+    #[test]
+    fn test_quota_bytes() {
+        assert_eq!(quota_bytes(None, 100), Some(100 * BYTES_PER_MB));
+        assert_eq!(quota_bytes(Some(5), 100), Some(5 * BYTES_PER_MB));
+        assert_eq!(quota_bytes(Some(0), 100), None);
+        assert_eq!(quota_bytes(None, 0), None);
+    }
+
+    #[test]
+    fn test_format_size() {
+        assert_eq!(format_size(0), "0.0 KB");
+        assert_eq!(format_size(1536), "1.5 KB");
+        assert_eq!(format_size(BYTES_PER_MB * 5 / 2), "2.5 MB");
+        assert_eq!(format_size(BYTES_PER_MB * 1024 * 3), "3.0 GB");
+    }
+
+    #[test]
+    fn test_usage_allows() {
+        assert!(usage(60, Some(100)).allows(40));
+        assert!(!usage(60, Some(100)).allows(41));
+        assert!(usage(i64::MAX / 2, None).allows(i64::MAX / 2));
+    }
+}
@@ -0,0 +1,296 @@
+//! Radio groups: radio fields sharing a group key are the options of one
+//! choice. An option is selected when it holds `true`; a signer selects at most
+//! one option of each choice, and exactly one when any of its options is
+//! required. Options hidden by their condition do not count.
+
+use std::collections::{HashMap, HashSet};
+
+use serde::Serialize;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::models::document::{DocumentFieldRow, FieldType};
+use crate::services::field_conditions::CHECKED;
+
+/// The options of one choice, in the document's field order.
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct RadioGroup {
+    pub group_key: String,
+    pub field_ids: Vec<Uuid>,
+    /// Whether an option must be selected.
+    pub required: bool,
+}
+
+/// Groups the radio fields among `fields`.
+pub fn groups<'a>(fields: impl IntoIterator<Item = &'a DocumentFieldRow>) -> Vec<RadioGroup> {
+    let mut groups: Vec<RadioGroup> = Vec::new();
+
+    for field in fields
+        .into_iter()
+        .filter(|f| f.field_type == FieldType::Radio)
+    {
+        let Some(key) = field.group_key.as_deref() else {
+            continue;
+        };
+
+        match groups.iter_mut().find(|g| g.group_key == key) {
+            Some(group) => {
+                group.field_ids.push(field.id);
+                group.required |= field.required;
+            }
+            None => groups.push(RadioGroup {
+                group_key: key.to_string(),
+                field_ids: vec![field.id],
+                required: field.required,
+            }),
+        }
+    }
+
+    groups
+}
+
+/// Checks the group of the field `field_id` (`None` for a field being added)
+/// of type `field_type`, assigned to `signer_id`.
+pub fn validate_group(
+    field_id: Option<Uuid>,
+    field_type: FieldType,
+    signer_id: Option<Uuid>,
+    group_key: Option<&str>,
+    fields: &[DocumentFieldRow],
+) -> Result<(), String> {
+    let group_key = group_key.map(str::trim).filter(|k| !k.is_empty());
+
+    let Some(group_key) = group_key else {
+        return match field_type {
+            FieldType::Radio => Err("Radio fields need a group_key".to_string()),
+            _ => Ok(()),
+        };
+    };
+
+    if field_type != FieldType::Radio {
+        return Err("group_key only applies to radio fields".to_string());
+    }
+
+    // One signer makes the choice
+    let assigned_elsewhere = fields.iter().any(|f| {
+        Some(f.id) != field_id
+            && f.field_type == FieldType::Radio
+            && f.group_key.as_deref() == Some(group_key)
+            && f.signer_id != signer_id
+    });
+    if assigned_elsewhere {
+        return Err(format!(
+            "The options of {} must be assigned to the same signer",
+            group_key
+        ));
+    }
+
+    Ok(())
+}
+
+/// Checks the choices the signer `signer_id` makes among the `visible` fields.
+/// `submitted` holds values being submitted, which take precedence over the
+/// stored ones.
+pub fn check_selection(
+    fields: &[DocumentFieldRow],
+    visible: &HashSet<Uuid>,
+    signer_id: Uuid,
+    submitted: &HashMap<Uuid, String>,
+) -> Result<(), String> {
+    let is_theirs = |f: &DocumentFieldRow| f.signer_id.is_none() || f.signer_id == Some(signer_id);
+    let options: Vec<&DocumentFieldRow> = fields
+        .iter()
+        .filter(|f| f.field_type == FieldType::Radio && visible.contains(&f.id) && is_theirs(f))
+        .collect();
+
+    for option in &options {
+        if let Some(value) = submitted.get(&option.id) {
+            if !matches!(value.trim(), "" | CHECKED | "false") {
+                return Err(format!(
+                    "{}: an option is selected with true, or false to leave it",
+                    option.display_name()
+                ));
+            }
+        }
+    }
+
+    let selected = |f: &DocumentFieldRow| {
+        submitted
+            .get(&f.id)
+            .map(String::as_str)
+            .or(f.value.as_deref())
+            .map(str::trim)
+            == Some(CHECKED)
+    };
+
+    for group in groups(options.iter().copied()) {
+        let count = options
+            .iter()
+            .filter(|f| group.field_ids.contains(&f.id) && selected(f))
+            .count();
+
+        if count > 1 {
+            return Err(format!(
+                "Only one option of {} can be selected",
+                group.group_key
+            ));
+        }
+
+        if count == 0 && group.required {
+            return Err(format!("An option of {} must be selected", group.group_key));
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn option(group_key: &str, required: bool) -> DocumentFieldRow {
+        DocumentFieldRow {
+            id: Uuid::new_v4(),
+            document_id: Uuid::nil(),
+            field_type: FieldType::Radio,
+            page: 1,
+            x: 0.0,
+            y: 0.0,
+            width: 10.0,
+            height: 10.0,
+            signer_id: None,
+            value: None,
+            font_size: None,
+            font_family: None,
+            date_format: None,
+            norm_x: None,
+            norm_y: None,
+            norm_width: None,
+            norm_height: None,
+            required,
+            condition_field_id: None,
+            condition_value: None,
+            label: None,
+            min_value: None,
+            max_value: None,
+            decimal_places: None,
+            read_only: false,
+            group_key: Some(group_key.to_string()),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn all_visible(fields: &[DocumentFieldRow]) -> HashSet<Uuid> {
+        fields.iter().map(|f| f.id).collect()
+    }
+
+    #[test]
+    fn test_groups_collect_options_by_key() {
+        let card = option("payment", false);
+        let invoice = option("payment", true);
+        let yes = option("newsletter", false);
+        let fields = vec![card.clone(), yes.clone(), invoice.clone()];
+
+        assert_eq!(
+            groups(&fields),
+            vec![
+                RadioGroup {
+                    group_key: "payment".to_string(),
+                    field_ids: vec![card.id, invoice.id],
+                    required: true,
+                },
+                RadioGroup {
+                    group_key: "newsletter".to_string(),
+                    field_ids: vec![yes.id],
+                    required: false,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_check_selection() {
+        let signer_id = Uuid::new_v4();
+        let card = option("payment", true);
+        let invoice = option("payment", false);
+        let fields = vec![card.clone(), invoice.clone()];
+        let check = |values: &[(Uuid, &str)]| {
+            let submitted = values
+                .iter()
+                .map(|(id, value)| (*id, value.to_string()))
+                .collect();
+            check_selection(&fields, &all_visible(&fields), signer_id, &submitted)
+        };
+
+        assert!(check(&[(card.id, CHECKED)]).is_ok());
+        assert!(check(&[(card.id, "false"), (invoice.id, CHECKED)]).is_ok());
+        assert_eq!(
+            check(&[]).unwrap_err(),
+            "An option of payment must be selected"
+        );
+        assert_eq!(
+            check(&[(card.id, CHECKED), (invoice.id, CHECKED)]).unwrap_err(),
+            "Only one option of payment can be selected"
+        );
+        assert!(check(&[(card.id, "yes")]).is_err());
+    }
+
+    #[test]
+    fn test_optional_choices_and_hidden_options() {
+        let signer_id = Uuid::new_v4();
+        let yes = option("newsletter", false);
+        let mut hidden = option("newsletter", true);
+        hidden.value = Some(CHECKED.to_string());
+        let fields = vec![yes.clone(), hidden];
+        let visible = HashSet::from([yes.id]);
+
+        assert!(check_selection(&fields, &visible, signer_id, &HashMap::new()).is_ok());
+    }
+
+    #[test]
+    fn test_other_signers_choices_are_not_checked() {
+        let mut card = option("payment", true);
+        card.signer_id = Some(Uuid::new_v4());
+        let fields = vec![card];
+
+        let result = check_selection(
+            &fields,
+            &all_visible(&fields),
+            Uuid::new_v4(),
+            &HashMap::new(),
+        );
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_validate_group() {
+        let mut card = option("payment", false);
+        card.signer_id = Some(Uuid::new_v4());
+        let fields = vec![card.clone()];
+
+        assert!(validate_group(
+            None,
+            FieldType::Radio,
+            card.signer_id,
+            Some("payment"),
+            &fields
+        )
+        .is_ok());
+        assert!(validate_group(None, FieldType::Radio, None, Some("payment"), &fields).is_err());
+        assert!(validate_group(None, FieldType::Radio, None, Some("other"), &fields).is_ok());
+        assert!(validate_group(None, FieldType::Radio, None, Some(" "), &fields).is_err());
+        assert!(validate_group(None, FieldType::Text, None, Some("payment"), &fields).is_err());
+        assert!(validate_group(None, FieldType::Text, None, None, &fields).is_ok());
+        // A field being updated is not compared with itself
+        assert!(validate_group(
+            Some(card.id),
+            FieldType::Radio,
+            None,
+            Some("payment"),
+            &fields
+        )
+        .is_ok());
+    }
+}
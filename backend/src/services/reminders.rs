@@ -0,0 +1,208 @@
+use anyhow::{Context, Result};
+use chrono::{Duration, Utc};
+use sqlx::PgPool;
+use std::collections::{BTreeSet, HashMap};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::db;
+use crate::models::audit::AuditAction;
+use crate::models::document::Document;
+use crate::models::outbox::OutboxEmailKind;
+use crate::models::signer::Signer;
+use crate::models::user::User;
+use crate::services::audit;
+use crate::services::config::Config;
+use crate::services::email::EmailService;
+use crate::services::outbox::EmailOutbox;
+
+/// How often signers are checked for due reminders and upcoming expiry.
+pub const CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60 * 60);
+const CLAIM_BATCH_SIZE: i64 = 100;
+
+/// Queues a reminder for every signer due one under their document's
+/// reminder settings, then delivers them. Returns how many were queued.
+pub async fn send_due_reminders(
+    pool: &PgPool,
+    email_outbox: &EmailOutbox,
+    config: &Config,
+) -> Result<usize> {
+    let mut queued = 0;
+
+    loop {
+        let mut tx = pool.begin().await?;
+
+        let signers = db::signer::claim_due_reminders(&mut *tx, CLAIM_BATCH_SIZE).await?;
+
+        for signer in &signers {
+            db::outbox::enqueue_email(
+                &mut *tx,
+                OutboxEmailKind::SigningReminder,
+                signer.document_id,
+                Some(signer.id),
+            )
+            .await?;
+
+            audit::log_action_in(
+                &mut tx,
+                config.hash_algorithm,
+                signer.document_id,
+                Some(signer.id),
+                None,
+                AuditAction::SignerReminded,
+                None,
+                None,
+                Some(serde_json::json!({
+                    "signer_email": signer.email,
+                    "reminder": signer.reminders_sent,
+                    "automatic": true
+                })),
+            )
+            .await?;
+        }
+
+        tx.commit().await?;
+
+        queued += signers.len();
+        if signers.len() < CLAIM_BATCH_SIZE as usize {
+            break;
+        }
+    }
+
+    if queued > 0 {
+        info!("Queued {} signing reminder(s)", queued);
+        if let Err(e) = email_outbox.deliver(None).await {
+            warn!("Failed to deliver signing reminders: {:?}", e);
+        }
+    }
+
+    Ok(queued)
+}
+
+/// Warns signers who have not finished a pending document that it expires
+/// within `EXPIRY_WARNING_DAYS`, once per signer, then sends each affected
+/// owner a digest of their documents expiring in that window. Returns how
+/// many signers were warned.
+pub async fn send_expiry_warnings(
+    pool: &PgPool,
+    email_service: &EmailService,
+    config: &Config,
+) -> Result<usize> {
+    let mut documents: HashMap<Uuid, (Document, User)> = HashMap::new();
+    let mut owners = BTreeSet::new();
+    let mut warned = 0;
+
+    loop {
+        let signers =
+            db::signer::claim_expiry_warnings(pool, config.expiry_warning_days, CLAIM_BATCH_SIZE)
+                .await?;
+        let claimed = signers.len();
+        let mut failed = false;
+
+        for signer in signers {
+            match warn_signer(pool, email_service, config, &mut documents, &signer).await {
+                Ok(owner_id) => {
+                    owners.insert(owner_id);
+                    warned += 1;
+                }
+                Err(e) => {
+                    warn!(
+                        "Failed to send expiry warning to signer {}: {:?}",
+                        signer.id, e
+                    );
+                    db::signer::release_expiry_warning(pool, signer.id).await?;
+                    failed = true;
+                }
+            }
+        }
+
+        // Released claims would be picked straight back up; leave them for
+        // the next run.
+        if failed || claimed < CLAIM_BATCH_SIZE as usize {
+            break;
+        }
+    }
+
+    let before = Utc::now() + Duration::days(config.expiry_warning_days);
+
+    for owner_id in owners {
+        if let Err(e) = send_digest(pool, email_service, owner_id, before).await {
+            warn!("Failed to send expiry digest to user {}: {:?}", owner_id, e);
+        }
+    }
+
+    if warned > 0 {
+        info!("Sent {} expiry warning(s)", warned);
+    }
+
+    Ok(warned)
+}
+
+async fn warn_signer(
+    pool: &PgPool,
+    email_service: &EmailService,
+    config: &Config,
+    documents: &mut HashMap<Uuid, (Document, User)>,
+    signer: &Signer,
+) -> Result<Uuid> {
+    if !documents.contains_key(&signer.document_id) {
+        let document = db::document::get_document_by_id(pool, signer.document_id)
+            .await?
+            .context("Document not found")?;
+        let owner = db::user::get_user_by_id(pool, document.owner_id)
+            .await?
+            .context("Owner not found")?;
+        documents.insert(document.id, (document, owner));
+    }
+    let (document, owner) = &documents[&signer.document_id];
+
+    email_service
+        .send_expiry_warning(
+            &signer.email,
+            &signer.name,
+            &document.title,
+            &owner.name,
+            document.expires_at.context("Document has no expiry")?,
+            &signer.access_token,
+        )
+        .await?;
+
+    audit::log_action(
+        pool,
+        config.hash_algorithm,
+        document.id,
+        Some(signer.id),
+        None,
+        AuditAction::SignerEmailSent,
+        None,
+        None,
+        Some(serde_json::json!({
+            "signer_email": signer.email,
+            "email": "expiry_warning",
+            "expires_at": document.expires_at
+        })),
+    )
+    .await?;
+
+    Ok(owner.id)
+}
+
+async fn send_digest(
+    pool: &PgPool,
+    email_service: &EmailService,
+    owner_id: Uuid,
+    before: chrono::DateTime<Utc>,
+) -> Result<()> {
+    let owner = db::user::get_user_by_id(pool, owner_id)
+        .await?
+        .context("Owner not found")?;
+    let documents = db::document::get_expiring_documents(pool, owner_id, before).await?;
+
+    if documents.is_empty() {
+        return Ok(());
+    }
+
+    email_service
+        .send_expiry_digest(&owner.email, &owner.name, &documents)
+        .await
+}
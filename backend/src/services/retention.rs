@@ -0,0 +1,145 @@
+use anyhow::Result;
+use chrono::{Duration, Utc};
+use sqlx::PgPool;
+use tracing::{info, warn};
+
+use crate::db;
+use crate::models::document::{Document, DocumentStatus};
+use crate::models::purge_log::{PURGE_REASON_RETENTION, PURGE_REASON_TRASH};
+use crate::services::blob_store;
+use crate::services::config::Config;
+use crate::services::storage::{self, Storage};
+
+/// How often documents are checked against the retention policy.
+pub const PURGE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60 * 60);
+
+/// How long background job runs are kept for the admin job overview.
+const JOB_RUN_RETENTION_DAYS: i64 = 30;
+
+/// How long processed outbox events are kept.
+const OUTBOX_EVENT_RETENTION_DAYS: i64 = 30;
+
+const STATUSES: [DocumentStatus; 5] = [
+    DocumentStatus::Draft,
+    DocumentStatus::Pending,
+    DocumentStatus::Completed,
+    DocumentStatus::Voided,
+    DocumentStatus::Expired,
+];
+
+/// Permanently deletes documents past their retention period: per-status
+/// retention from the config, and the trash retention for soft-deleted
+/// documents. Documents under legal hold are skipped. Returns how many
+/// documents were purged. Expired idempotency keys and sessions, old job runs
+/// and processed outbox events are dropped as well.
+pub async fn purge_expired(pool: &PgPool, storage: &dyn Storage, config: &Config) -> Result<usize> {
+    let now = Utc::now();
+    let mut purged = 0;
+
+    let keys = db::idempotency::delete_expired(pool).await?;
+    if keys > 0 {
+        info!("Deleted {} expired idempotency key(s)", keys);
+    }
+
+    let sessions = db::session::delete_expired(pool).await?;
+    if sessions > 0 {
+        info!("Deleted {} expired session(s)", sessions);
+    }
+
+    let runs =
+        db::job_run::delete_runs_before(pool, now - Duration::days(JOB_RUN_RETENTION_DAYS)).await?;
+    if runs > 0 {
+        info!("Deleted {} old job run(s)", runs);
+    }
+
+    let events = db::outbox::delete_processed_events_before(
+        pool,
+        now - Duration::days(OUTBOX_EVENT_RETENTION_DAYS),
+    )
+    .await?;
+    if events > 0 {
+        info!("Deleted {} processed outbox event(s)", events);
+    }
+
+    if let Some(days) = config.auth_event_retention_days {
+        let events = db::auth_event::delete_events_before(pool, now - Duration::days(days)).await?;
+        if events > 0 {
+            info!("Deleted {} old login event(s)", events);
+        }
+    }
+
+    let trashed = db::document::get_trashed_documents_to_purge(
+        pool,
+        now - Duration::days(config.deleted_document_retention_days),
+    )
+    .await?;
+    purged += purge_documents(pool, storage, &trashed, PURGE_REASON_TRASH).await?;
+
+    for status in STATUSES {
+        let Some(days) = config.retention.days_for(status) else {
+            continue;
+        };
+
+        let expired =
+            db::document::get_documents_past_retention(pool, status, now - Duration::days(days))
+                .await?;
+        purged += purge_documents(pool, storage, &expired, PURGE_REASON_RETENTION).await?;
+    }
+
+    if purged > 0 {
+        info!("Purged {} document(s)", purged);
+    }
+
+    Ok(purged)
+}
+
+async fn purge_documents(
+    pool: &PgPool,
+    storage: &dyn Storage,
+    documents: &[Document],
+    reason: &str,
+) -> Result<usize> {
+    let mut purged = 0;
+
+    for document in documents {
+        let previews = storage::page_preview_prefix(&document.file_path);
+        if let Err(e) = storage.delete_prefix(&previews).await {
+            warn!(
+                "Failed to delete page previews of document {}: {}",
+                document.id, e
+            );
+        }
+
+        let signatures = storage::signature_image_prefix(document.owner_id, document.id);
+        if let Err(e) = storage.delete_prefix(&signatures).await {
+            warn!(
+                "Failed to delete signature images of document {}: {}",
+                document.id, e
+            );
+        }
+
+        if let Some(sealed) = &document.sealed_file_path {
+            if let Err(e) = storage.delete(sealed).await {
+                warn!(
+                    "Failed to delete sealed file for document {}, keeping it for now: {}",
+                    document.id, e
+                );
+                continue;
+            }
+        }
+
+        if let Err(e) = blob_store::release(pool, storage, &document.file_path).await {
+            warn!(
+                "Failed to delete file for document {}, keeping it for now: {}",
+                document.id, e
+            );
+            continue;
+        }
+
+        db::purge_log::create_entry(pool, document, reason).await?;
+        db::document::delete_document(pool, document.id).await?;
+        purged += 1;
+    }
+
+    Ok(purged)
+}
@@ -0,0 +1,259 @@
+use anyhow::Result;
+use futures::future::BoxFuture;
+use futures::FutureExt;
+use sqlx::pool::PoolConnection;
+use sqlx::{PgPool, Postgres};
+use std::any::Any;
+use std::future::Future;
+use std::panic::AssertUnwindSafe;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, info, warn};
+use uuid::Uuid;
+
+use crate::db;
+use crate::models::job::{JobRun, JobRunStatus};
+
+type JobFn<S> = Arc<dyn Fn(S) -> BoxFuture<'static, Result<()>> + Send + Sync>;
+
+/// A background task run every `interval`, given the state `S`.
+pub struct Job<S> {
+    name: &'static str,
+    interval: Duration,
+    run: JobFn<S>,
+}
+
+impl<S> Clone for Job<S> {
+    fn clone(&self) -> Self {
+        Self {
+            name: self.name,
+            interval: self.interval,
+            run: self.run.clone(),
+        }
+    }
+}
+
+impl<S> Job<S> {
+    pub fn new<F, Fut>(name: &'static str, interval: Duration, run: F) -> Self
+    where
+        F: Fn(S) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        Self {
+            name,
+            interval,
+            run: Arc::new(move |state| run(state).boxed()),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+}
+
+/// Runs registered jobs on their intervals, recording each run in
+/// `job_runs`. A Postgres advisory lock per job keeps instances sharing the
+/// database from running the same job at once; the instance that does not
+/// get the lock skips that run. A job that fails or panics is logged and
+/// runs again at its next interval.
+pub struct Scheduler<S> {
+    pool: PgPool,
+    jobs: Vec<Job<S>>,
+    shutdown: CancellationToken,
+}
+
+impl<S: Clone + Send + Sync + 'static> Scheduler<S> {
+    pub fn new(pool: PgPool, jobs: Vec<Job<S>>, shutdown: CancellationToken) -> Self {
+        Self {
+            pool,
+            jobs,
+            shutdown,
+        }
+    }
+
+    pub fn jobs(&self) -> &[Job<S>] {
+        &self.jobs
+    }
+
+    pub fn job(&self, name: &str) -> Option<&Job<S>> {
+        self.jobs.iter().find(|job| job.name == name)
+    }
+
+    /// Runs every job on its interval, starting now, until shutdown.
+    pub fn spawn(self: Arc<Self>, state: S) {
+        for job in self.jobs.clone() {
+            let scheduler = self.clone();
+            let state = state.clone();
+
+            tokio::spawn(async move {
+                let mut ticker = tokio::time::interval(job.interval);
+                ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+                loop {
+                    tokio::select! {
+                        _ = scheduler.shutdown.cancelled() => break,
+                        _ = ticker.tick() => {}
+                    }
+
+                    match scheduler.lock(&job, None).await {
+                        Ok(Some((lock, run))) => {
+                            scheduler.execute(&job, state.clone(), lock, run).await
+                        }
+                        Ok(None) => debug!("Skipping job {}, it is running elsewhere", job.name),
+                        Err(e) => error!("Failed to start job {}: {:?}", job.name, e),
+                    }
+                }
+
+                info!("Job {} stopped", job.name);
+            });
+        }
+    }
+
+    /// Starts a run of the job now, in the background. Returns `None` if the
+    /// job is unknown or already running.
+    pub async fn trigger(
+        self: &Arc<Self>,
+        name: &str,
+        state: S,
+        triggered_by: Uuid,
+    ) -> Result<Option<JobRun>> {
+        let Some(job) = self.job(name).cloned() else {
+            return Ok(None);
+        };
+
+        let Some((lock, run)) = self.lock(&job, Some(triggered_by)).await? else {
+            return Ok(None);
+        };
+
+        let scheduler = self.clone();
+        let started = run.clone();
+        tokio::spawn(async move { scheduler.execute(&job, state, lock, run).await });
+
+        Ok(Some(started))
+    }
+
+    /// Takes the job's lock and records the start of a run, or returns `None`
+    /// if another run holds the lock.
+    async fn lock(
+        &self,
+        job: &Job<S>,
+        triggered_by: Option<Uuid>,
+    ) -> Result<Option<(JobLock, JobRun)>> {
+        let mut conn = self.pool.acquire().await?;
+        let locked: bool = sqlx::query_scalar(
+            "SELECT pg_try_advisory_lock(hashtextextended('signvault-job:' || $1, 0))",
+        )
+        .bind(job.name)
+        .fetch_one(&mut *conn)
+        .await?;
+
+        if !locked {
+            return Ok(None);
+        }
+
+        let lock = JobLock {
+            name: job.name,
+            conn: Some(conn),
+        };
+
+        match db::job_run::start_run(&self.pool, job.name, triggered_by).await {
+            Ok(run) => Ok(Some((lock, run))),
+            Err(e) => {
+                lock.release().await;
+                Err(e)
+            }
+        }
+    }
+
+    async fn execute(&self, job: &Job<S>, state: S, lock: JobLock, run: JobRun) {
+        let outcome = tokio::select! {
+            _ = self.shutdown.cancelled() => Err("Interrupted by shutdown".to_string()),
+            result = AssertUnwindSafe((job.run)(state)).catch_unwind() => match result {
+                Ok(Ok(())) => Ok(()),
+                Ok(Err(e)) => Err(format!("{:#}", e)),
+                Err(panic) => Err(format!("Panicked: {}", panic_message(panic.as_ref()))),
+            },
+        };
+
+        let (status, error) = match &outcome {
+            Ok(()) => (JobRunStatus::Succeeded, None),
+            Err(message) => {
+                error!("Job {} failed: {}", job.name, message);
+                (JobRunStatus::Failed, Some(message.as_str()))
+            }
+        };
+
+        if let Err(e) = db::job_run::finish_run(&self.pool, run.id, status, error).await {
+            error!("Failed to record the end of job {}: {:?}", job.name, e);
+        }
+
+        lock.release().await;
+    }
+}
+
+/// A job's advisory lock, held by the session of `conn`. Dropped without
+/// being released, the connection is closed so the lock does not stay with
+/// a pooled session.
+struct JobLock {
+    name: &'static str,
+    conn: Option<PoolConnection<Postgres>>,
+}
+
+impl JobLock {
+    async fn release(mut self) {
+        let Some(mut conn) = self.conn.take() else {
+            return;
+        };
+
+        let result =
+            sqlx::query("SELECT pg_advisory_unlock(hashtextextended('signvault-job:' || $1, 0))")
+                .bind(self.name)
+                .execute(&mut *conn)
+                .await;
+
+        if let Err(e) = result {
+            warn!("Failed to release the lock of job {}: {}", self.name, e);
+            drop(conn.detach());
+        }
+    }
+}
+
+impl Drop for JobLock {
+    fn drop(&mut self) {
+        if let Some(conn) = self.conn.take() {
+            drop(conn.detach());
+        }
+    }
+}
+
+fn panic_message(panic: &(dyn Any + Send)) -> &str {
+    if let Some(message) = panic.downcast_ref::<&str>() {
+        message
+    } else if let Some(message) = panic.downcast_ref::<String>() {
+        message
+    } else {
+        "unknown panic"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_panic_message() {
+        let panic = std::panic::catch_unwind(|| panic!("static message")).unwrap_err();
+        assert_eq!(panic_message(panic.as_ref()), "static message");
+
+        let panic = std::panic::catch_unwind(|| panic!("formatted {}", 42)).unwrap_err();
+        assert_eq!(panic_message(panic.as_ref()), "formatted 42");
+
+        let panic = std::panic::catch_unwind(|| std::panic::panic_any(42)).unwrap_err();
+        assert_eq!(panic_message(panic.as_ref()), "unknown panic");
+    }
+}
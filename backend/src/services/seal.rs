@@ -0,0 +1,535 @@
+use anyhow::{Context, Result};
+use chrono::Utc;
+use lopdf::{dictionary, Document, IncrementalDocument, Object, StringFormat};
+use sqlx::PgPool;
+use std::sync::Arc;
+use tracing::error;
+#[cfg(feature = "seal")]
+use tracing::info;
+#[cfg(not(feature = "seal"))]
+use tracing::warn;
+
+#[cfg(feature = "seal")]
+use crate::db;
+#[cfg(feature = "seal")]
+use crate::models::audit::AuditAction;
+use crate::models::document::Document as StoredDocument;
+#[cfg(feature = "seal")]
+use crate::services::audit;
+use crate::services::config::Config;
+#[cfg(feature = "seal")]
+use crate::services::crypto::{self, HashAlgorithm};
+use crate::services::pdf::PdfWorkers;
+#[cfg(feature = "seal")]
+use crate::services::storage;
+use crate::services::storage::Storage;
+
+/// Bytes reserved in the PDF for the CMS signature, certificate chain
+/// included.
+const SIGNATURE_SIZE: usize = 16 * 1024;
+/// Written in place of each `/ByteRange` offset before they are known; wide
+/// enough for files up to 10 GB.
+const BYTE_RANGE_PLACEHOLDER: i64 = 9_999_999_999;
+const SEAL_FIELD_NAME: &str = "SignVault seal";
+const SEAL_REASON: &str = "Document completed on SignVault";
+
+/// Seals completed documents with the server's X.509 certificate: a detached
+/// CMS signature over the whole file, embedded in an incremental update so PDF
+/// readers can validate it. The sealed copy is stored next to the original,
+/// which stays untouched. Without a certificate, or when built without the
+/// `seal` feature, documents are left unsealed.
+pub struct DocumentSealer {
+    pool: PgPool,
+    #[cfg(feature = "seal")]
+    hash_algorithm: HashAlgorithm,
+    storage: Arc<dyn Storage>,
+    workers: Arc<PdfWorkers>,
+    #[cfg(feature = "seal")]
+    credentials: Option<Arc<credentials::SealCredentials>>,
+}
+
+impl DocumentSealer {
+    #[cfg(feature = "seal")]
+    pub fn load(
+        pool: PgPool,
+        config: &Config,
+        storage: Arc<dyn Storage>,
+        workers: Arc<PdfWorkers>,
+    ) -> Result<Self> {
+        let credentials = match config.seal_certificate_path.as_deref() {
+            None => {
+                info!("Seal certificate not configured, completed documents will not be sealed");
+                None
+            }
+            Some(path) => {
+                let credentials = credentials::SealCredentials::load(
+                    path,
+                    config.seal_private_key_path.as_deref(),
+                    config.seal_certificate_password.as_deref(),
+                )
+                .with_context(|| format!("Failed to load seal certificate {}", path))?;
+                info!("Sealing completed documents as {}", credentials.subject);
+                Some(Arc::new(credentials))
+            }
+        };
+
+        Ok(Self {
+            pool,
+            hash_algorithm: config.hash_algorithm,
+            storage,
+            workers,
+            credentials,
+        })
+    }
+
+    #[cfg(not(feature = "seal"))]
+    pub fn load(
+        pool: PgPool,
+        config: &Config,
+        storage: Arc<dyn Storage>,
+        workers: Arc<PdfWorkers>,
+    ) -> Result<Self> {
+        if config.seal_certificate_path.is_some() {
+            warn!("Built without the seal feature, ignoring SEAL_CERTIFICATE_PATH");
+        }
+
+        Ok(Self {
+            pool,
+            storage,
+            workers,
+        })
+    }
+
+    pub fn enabled(&self) -> bool {
+        #[cfg(feature = "seal")]
+        return self.credentials.is_some();
+        #[cfg(not(feature = "seal"))]
+        return false;
+    }
+
+    /// Seals a completed document. Failures are logged rather than returned,
+    /// so that they never fail the signature that completed the document; the
+    /// owner can seal it again later.
+    pub async fn seal_completed(&self, document: &StoredDocument) {
+        if let Err(e) = self.seal(document).await {
+            error!("Failed to seal document {}: {:?}", document.id, e);
+        }
+    }
+
+    /// Seals the document's stored file, replacing any earlier sealed copy.
+    /// Returns `None` when sealing is not configured.
+    #[cfg(feature = "seal")]
+    pub async fn seal(&self, document: &StoredDocument) -> Result<Option<StoredDocument>> {
+        let Some(credentials) = self.credentials.clone() else {
+            return Ok(None);
+        };
+
+        let data = self
+            .storage
+            .get_file(&document.file_path, document.original_encryption().as_ref())
+            .await?;
+        if !crypto::verify_data(&document.file_hash, &data)? {
+            anyhow::bail!("Stored file does not match its upload hash");
+        }
+
+        let signer = credentials.subject.clone();
+        let sealed = self
+            .workers
+            .run(move || {
+                seal_pdf(&data, &credentials.subject, |signed| {
+                    credentials.sign(signed)
+                })
+            })
+            .await??;
+
+        let key = storage::sealed_pdf_key(document.owner_id, document.id);
+        let sealed_hash = crypto::hash_data(self.hash_algorithm, &sealed);
+        let sealed_size = sealed.len() as i64;
+        let nonce = self
+            .storage
+            .put_file(&key, sealed, document.data_key().as_ref())
+            .await?;
+
+        let document = db::document::set_seal(
+            &self.pool,
+            document.id,
+            &key,
+            nonce.as_deref(),
+            &sealed_hash,
+            sealed_size,
+            &signer,
+        )
+        .await?;
+
+        audit::log_action(
+            &self.pool,
+            self.hash_algorithm,
+            document.id,
+            None,
+            None,
+            AuditAction::DocumentSealed,
+            None,
+            None,
+            Some(serde_json::json!({
+                "sealed_file_hash": sealed_hash,
+                "seal_signer": signer,
+            })),
+        )
+        .await?;
+
+        info!("Sealed document {}", document.id);
+
+        Ok(Some(document))
+    }
+
+    #[cfg(not(feature = "seal"))]
+    pub async fn seal(&self, document: &StoredDocument) -> Result<Option<StoredDocument>> {
+        Ok(None)
+    }
+}
+
+/// Appends a signature field to `data` as an incremental update and fills it
+/// with the CMS signature `sign` computes over every byte of the result except
+/// the signature itself, as `/SubFilter /adbe.pkcs7.detached` requires.
+pub fn seal_pdf(
+    data: &[u8],
+    signer_name: &str,
+    sign: impl FnOnce(&[u8]) -> Result<Vec<u8>>,
+) -> Result<Vec<u8>> {
+    let previous = Document::load_mem(data).context("Failed to parse PDF")?;
+    let catalog_id = previous.trailer.get(b"Root")?.as_reference()?;
+    let page_id = *previous
+        .get_pages()
+        .values()
+        .next()
+        .context("PDF has no pages")?;
+
+    let mut page = previous.get_dictionary(page_id)?.clone();
+    let mut annotations = inline_array(&previous, page.get(b"Annots").ok())?;
+
+    let mut catalog = previous.get_dictionary(catalog_id)?.clone();
+    let (acroform_id, mut acroform) = match catalog.get(b"AcroForm") {
+        Ok(Object::Reference(id)) => (Some(*id), previous.get_dictionary(*id)?.clone()),
+        Ok(Object::Dictionary(acroform)) => (None, acroform.clone()),
+        _ => (None, lopdf::Dictionary::new()),
+    };
+    let mut fields = inline_array(&previous, acroform.get(b"Fields").ok())?;
+
+    let mut update = IncrementalDocument::create_from(data.to_vec(), previous);
+    let document = &mut update.new_document;
+
+    let byte_range: Vec<Object> = [
+        0,
+        BYTE_RANGE_PLACEHOLDER,
+        BYTE_RANGE_PLACEHOLDER,
+        BYTE_RANGE_PLACEHOLDER,
+    ]
+    .into_iter()
+    .map(Object::Integer)
+    .collect();
+    let signature_id = document.add_object(dictionary! {
+        "Type" => "Sig",
+        "Filter" => "Adobe.PPKLite",
+        "SubFilter" => "adbe.pkcs7.detached",
+        "ByteRange" => byte_range,
+        "Contents" => Object::String(vec![0; SIGNATURE_SIZE], StringFormat::Hexadecimal),
+        "M" => Object::string_literal(format!("D:{}Z", Utc::now().format("%Y%m%d%H%M%S"))),
+        "Name" => Object::string_literal(signer_name),
+        "Reason" => Object::string_literal(SEAL_REASON),
+    });
+
+    // An invisible widget: the seal covers the whole document, not a spot on
+    // a page. Flags are Print and Locked.
+    let field_id = document.add_object(dictionary! {
+        "Type" => "Annot",
+        "Subtype" => "Widget",
+        "FT" => "Sig",
+        "T" => Object::string_literal(SEAL_FIELD_NAME),
+        "F" => 132,
+        "Rect" => vec![0.into(), 0.into(), 0.into(), 0.into()],
+        "V" => signature_id,
+        "P" => page_id,
+    });
+
+    annotations.push(field_id.into());
+    page.set("Annots", annotations);
+    document.set_object(page_id, page);
+
+    fields.push(field_id.into());
+    acroform.set("Fields", fields);
+    acroform.set("SigFlags", 3);
+    match acroform_id {
+        Some(id) => document.set_object(id, acroform),
+        None => {
+            catalog.set("AcroForm", acroform);
+            document.set_object(catalog_id, catalog);
+        }
+    }
+
+    let mut sealed = Vec::new();
+    update.save_to(&mut sealed)?;
+
+    let placeholder = format!("<{}>", "0".repeat(SIGNATURE_SIZE * 2));
+    let contents_start = data.len()
+        + find(&sealed[data.len()..], placeholder.as_bytes())
+            .context("Signature placeholder not found")?;
+    let contents_end = contents_start + placeholder.len();
+
+    let byte_range_key =
+        data.len() + find(&sealed[data.len()..], b"/ByteRange").context("Byte range not found")?;
+    let byte_range_start =
+        byte_range_key + find(&sealed[byte_range_key..], b"[").context("Byte range not found")?;
+    let byte_range_end = byte_range_start
+        + find(&sealed[byte_range_start..], b"]").context("Byte range not found")?;
+
+    let byte_range = format!(
+        "[0 {} {} {}",
+        contents_start,
+        contents_end,
+        sealed.len() - contents_end
+    );
+    let slot = &mut sealed[byte_range_start..byte_range_end];
+    if byte_range.len() > slot.len() {
+        anyhow::bail!("PDF is too large to seal");
+    }
+    slot.fill(b' ');
+    slot[..byte_range.len()].copy_from_slice(byte_range.as_bytes());
+
+    let mut signed = Vec::with_capacity(sealed.len() - placeholder.len());
+    signed.extend_from_slice(&sealed[..contents_start]);
+    signed.extend_from_slice(&sealed[contents_end..]);
+
+    let signature = hex::encode_upper(sign(&signed)?);
+    if signature.len() > SIGNATURE_SIZE * 2 {
+        anyhow::bail!(
+            "Signature is {} bytes, more than the {} reserved",
+            signature.len() / 2,
+            SIGNATURE_SIZE
+        );
+    }
+    sealed[contents_start + 1..contents_start + 1 + signature.len()]
+        .copy_from_slice(signature.as_bytes());
+
+    Ok(sealed)
+}
+
+/// An array that may be stored inline or as an indirect object, as a copy
+/// that can be written inline into an updated object.
+fn inline_array(document: &Document, object: Option<&Object>) -> Result<Vec<Object>> {
+    Ok(match object {
+        None => Vec::new(),
+        Some(Object::Reference(id)) => document.get_object(*id)?.as_array()?.clone(),
+        Some(object) => object.as_array()?.clone(),
+    })
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+#[cfg(feature = "seal")]
+mod credentials {
+    use anyhow::{Context, Result};
+    use openssl::cms::{CMSOptions, CmsContentInfo};
+    use openssl::nid::Nid;
+    use openssl::pkcs12::Pkcs12;
+    use openssl::pkey::{PKey, Private};
+    use openssl::stack::Stack;
+    use openssl::x509::X509;
+    use std::fs;
+
+    /// The certificate, private key and intermediate certificates that seal
+    /// documents, from a PKCS#12 bundle or PEM files.
+    pub struct SealCredentials {
+        certificate: X509,
+        private_key: PKey<Private>,
+        chain: Stack<X509>,
+        pub subject: String,
+    }
+
+    impl SealCredentials {
+        /// Loads a PKCS#12 bundle, or a PEM file whose first certificate is
+        /// the signing one. A PEM private key is read from `private_key_path`,
+        /// or from the certificate file when it is not set.
+        pub fn load(
+            certificate_path: &str,
+            private_key_path: Option<&str>,
+            password: Option<&str>,
+        ) -> Result<Self> {
+            let data = fs::read(certificate_path)?;
+            let password = password.unwrap_or_default();
+
+            let (certificate, private_key, chain) = if data.starts_with(b"-----BEGIN") {
+                let mut certificates = X509::stack_from_pem(&data)?.into_iter();
+                let certificate = certificates.next().context("No certificate found")?;
+
+                let key_data = match private_key_path {
+                    Some(path) => fs::read(path)
+                        .with_context(|| format!("Failed to read private key {}", path))?,
+                    None => data.clone(),
+                };
+                let private_key =
+                    PKey::private_key_from_pem_passphrase(&key_data, password.as_bytes())
+                        .context("Failed to read private key")?;
+
+                let mut chain = Stack::new()?;
+                for certificate in certificates {
+                    chain.push(certificate)?;
+                }
+                (certificate, private_key, chain)
+            } else {
+                let bundle = Pkcs12::from_der(&data)?
+                    .parse2(password)
+                    .context("Failed to open PKCS#12 bundle, check the password")?;
+                (
+                    bundle.cert.context("No certificate found")?,
+                    bundle.pkey.context("No private key found")?,
+                    match bundle.ca {
+                        Some(chain) => chain,
+                        None => Stack::new()?,
+                    },
+                )
+            };
+
+            if !certificate.public_key()?.public_eq(&private_key) {
+                anyhow::bail!("Private key does not match the certificate");
+            }
+
+            let subject = certificate
+                .subject_name()
+                .entries_by_nid(Nid::COMMONNAME)
+                .next()
+                .and_then(|entry| entry.data().as_utf8().ok())
+                .map(|name| name.to_string())
+                .unwrap_or_else(|| "Unknown".to_string());
+
+            Ok(Self {
+                certificate,
+                private_key,
+                chain,
+                subject,
+            })
+        }
+
+        /// A detached CMS signature over `data`, with the certificate chain.
+        pub fn sign(&self, data: &[u8]) -> Result<Vec<u8>> {
+            let signature = CmsContentInfo::sign(
+                Some(&self.certificate),
+                Some(&self.private_key),
+                Some(&self.chain),
+                Some(data),
+                CMSOptions::DETACHED | CMSOptions::BINARY | CMSOptions::NOSMIMECAP,
+            )?;
+            Ok(signature.to_der()?)
+        }
+    }
+}
+
+#[cfg(all(test, feature = "seal"))]
+mod tests {
+    use super::credentials::SealCredentials;
+    use super::*;
+    use openssl::cms::{CMSOptions, CmsContentInfo};
+    use openssl::pkcs12::Pkcs12;
+    use openssl::pkey::PKey;
+    use openssl::x509::X509;
+
+    const SEAL_PEM: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/seal.pem");
+
+    fn byte_range(sealed: &[u8]) -> Vec<usize> {
+        let key = find(sealed, b"/ByteRange").unwrap();
+        let start = key + find(&sealed[key..], b"[").unwrap();
+        let end = start + find(&sealed[start..], b"]").unwrap();
+        std::str::from_utf8(&sealed[start + 1..end])
+            .unwrap()
+            .split_whitespace()
+            .map(|n| n.parse().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_seal_pdf() {
+        let credentials = SealCredentials::load(SEAL_PEM, None, None).unwrap();
+        assert_eq!(credentials.subject, "SignVault Test Seal");
+
+        for original in [
+            &include_bytes!("../../tests/fixtures/rotated.pdf")[..],
+            &include_bytes!("../../tests/fixtures/acroform.pdf")[..],
+        ] {
+            let sealed = seal_pdf(original, &credentials.subject, |signed| {
+                credentials.sign(signed)
+            })
+            .unwrap();
+            assert!(sealed.starts_with(original));
+
+            let range = byte_range(&sealed);
+            assert_eq!(range[0], 0);
+            assert_eq!(range[2] + range[3], sealed.len());
+            let contents = &sealed[range[1]..range[2]];
+            assert!(contents.starts_with(b"<") && contents.ends_with(b">"));
+
+            let mut signed = sealed[..range[1]].to_vec();
+            signed.extend_from_slice(&sealed[range[2]..]);
+            // DER is self-delimiting, so the zero padding can stay
+            let der = hex::decode(&contents[1..contents.len() - 1]).unwrap();
+            let mut cms = CmsContentInfo::from_der(&der).unwrap();
+            cms.verify(
+                None,
+                None,
+                Some(&signed),
+                None,
+                CMSOptions::NOVERIFY | CMSOptions::BINARY,
+            )
+            .unwrap();
+
+            let mut tampered = signed.clone();
+            tampered[10] ^= 1;
+            assert!(cms
+                .verify(
+                    None,
+                    None,
+                    Some(&tampered),
+                    None,
+                    CMSOptions::NOVERIFY | CMSOptions::BINARY
+                )
+                .is_err());
+
+            let document = Document::load_mem(&sealed).unwrap();
+            let catalog = document.catalog().unwrap();
+            let acroform = match catalog.get(b"AcroForm").unwrap() {
+                Object::Reference(id) => document.get_dictionary(*id).unwrap(),
+                object => object.as_dict().unwrap(),
+            };
+            assert_eq!(acroform.get(b"SigFlags").unwrap().as_i64().unwrap(), 3);
+            let fields = acroform.get(b"Fields").unwrap().as_array().unwrap();
+            let seal = document
+                .get_dictionary(fields.last().unwrap().as_reference().unwrap())
+                .unwrap();
+            assert_eq!(seal.get(b"FT").unwrap().as_name_str().unwrap(), "Sig");
+        }
+    }
+
+    #[test]
+    fn test_load_pkcs12_credentials() {
+        let pem = std::fs::read(SEAL_PEM).unwrap();
+        let certificate = X509::from_pem(&pem).unwrap();
+        let key = PKey::private_key_from_pem(&pem).unwrap();
+        let bundle = Pkcs12::builder()
+            .name("seal")
+            .pkey(&key)
+            .cert(&certificate)
+            .build2("secret")
+            .unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("seal.p12");
+        std::fs::write(&path, bundle.to_der().unwrap()).unwrap();
+        let path = path.to_str().unwrap();
+
+        assert!(SealCredentials::load(path, None, Some("wrong")).is_err());
+        let credentials = SealCredentials::load(path, None, Some("secret")).unwrap();
+        assert_eq!(credentials.subject, "SignVault Test Seal");
+    }
+}
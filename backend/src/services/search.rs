@@ -0,0 +1,160 @@
+use anyhow::Result;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::db;
+use crate::models::document::{DocumentSearchResult, HighlightFragment, SearchField, SearchMatch};
+
+/// Searches the owner's documents by title, file name and signer name or
+/// email, returning a page of results, best first, and how many there are
+/// in all.
+pub async fn search_documents(
+    pool: &PgPool,
+    owner_id: Uuid,
+    term: &str,
+    limit: i64,
+    offset: i64,
+) -> Result<(Vec<DocumentSearchResult>, i64)> {
+    let pattern = like_pattern(term);
+
+    let rows =
+        db::document::search_documents_by_owner(pool, owner_id, &pattern, term, limit, offset)
+            .await?;
+    let total = db::document::count_search_results_by_owner(pool, owner_id, &pattern).await?;
+
+    let results = rows
+        .into_iter()
+        .map(|row| {
+            let mut matches = Vec::new();
+            let mut add = |field, value: &str| {
+                if let Some(fragments) = highlight(value, term) {
+                    matches.push(SearchMatch {
+                        field,
+                        value: value.to_string(),
+                        fragments,
+                    });
+                }
+            };
+
+            add(SearchField::Title, &row.document.title);
+            add(SearchField::Filename, &row.document.original_filename);
+            for name in &row.matched_signer_names {
+                add(SearchField::SignerName, name);
+            }
+            for email in &row.matched_signer_emails {
+                add(SearchField::SignerEmail, email);
+            }
+
+            DocumentSearchResult {
+                document: row.document,
+                rank: row.rank,
+                matches,
+            }
+        })
+        .collect();
+
+    Ok((results, total))
+}
+
+/// An ILIKE pattern matching values that contain `term`.
+fn like_pattern(term: &str) -> String {
+    let mut pattern = String::with_capacity(term.len() + 2);
+    pattern.push('%');
+    for c in term.chars() {
+        if matches!(c, '\\' | '%' | '_') {
+            pattern.push('\\');
+        }
+        pattern.push(c);
+    }
+    pattern.push('%');
+    pattern
+}
+
+/// Splits `value` around the case-insensitive occurrences of `term`, or
+/// `None` if there are none.
+fn highlight(value: &str, term: &str) -> Option<Vec<HighlightFragment>> {
+    let term: Vec<char> = term.chars().collect();
+    if term.is_empty() {
+        return None;
+    }
+
+    let chars: Vec<(usize, char)> = value.char_indices().collect();
+    let same = |a: char, b: char| a == b || a.to_lowercase().eq(b.to_lowercase());
+
+    let mut fragments = Vec::new();
+    let mut plain_start = 0;
+    let mut i = 0;
+    while i + term.len() <= chars.len() {
+        let found = chars[i..i + term.len()]
+            .iter()
+            .zip(&term)
+            .all(|(&(_, a), &b)| same(a, b));
+        if !found {
+            i += 1;
+            continue;
+        }
+
+        let start = chars[i].0;
+        let end = chars.get(i + term.len()).map_or(value.len(), |&(at, _)| at);
+        if start > plain_start {
+            fragments.push(HighlightFragment {
+                text: value[plain_start..start].to_string(),
+                matched: false,
+            });
+        }
+        fragments.push(HighlightFragment {
+            text: value[start..end].to_string(),
+            matched: true,
+        });
+        plain_start = end;
+        i += term.len();
+    }
+
+    if fragments.is_empty() {
+        return None;
+    }
+    if plain_start < value.len() {
+        fragments.push(HighlightFragment {
+            text: value[plain_start..].to_string(),
+            matched: false,
+        });
+    }
+
+    Some(fragments)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fragment(text: &str, matched: bool) -> HighlightFragment {
+        HighlightFragment {
+            text: text.to_string(),
+            matched,
+        }
+    }
+
+    #[test]
+    fn test_like_pattern_escapes_wildcards() {
+        assert_eq!(like_pattern("acme"), "%acme%");
+        assert_eq!(like_pattern("50%_off\\"), "%50\\%\\_off\\\\%");
+    }
+
+    #[test]
+    fn test_highlight_marks_every_occurrence() {
+        assert_eq!(
+            highlight("jane@ACME.com for Acme", "acme").unwrap(),
+            [
+                fragment("jane@", false),
+                fragment("ACME", true),
+                fragment(".com for ", false),
+                fragment("Acme", true),
+            ]
+        );
+        assert_eq!(
+            highlight("Café lease", "CAFÉ").unwrap(),
+            [fragment("Café", true), fragment(" lease", false)]
+        );
+        assert!(highlight("Lease agreement", "acme").is_none());
+    }
+}
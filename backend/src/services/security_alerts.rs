@@ -0,0 +1,120 @@
+use anyhow::Result;
+use sqlx::PgPool;
+use std::sync::Arc;
+use tracing::{error, warn};
+
+use crate::db;
+use crate::models::audit::AuditChainStatus;
+use crate::models::document::Document;
+use crate::models::security_event::{
+    SecurityEventSeverity, SecurityEventSource, SecurityEventType,
+};
+use crate::services::audit;
+use crate::services::email::EmailService;
+
+/// Turns failed file and audit chain checks into security events, emailing
+/// the document owner and every admin. Alerts are deduplicated per document
+/// and kind: until an admin acknowledges the event, further failures are
+/// only counted.
+pub struct SecurityAlerts {
+    pool: PgPool,
+    email_service: Option<Arc<EmailService>>,
+}
+
+impl SecurityAlerts {
+    pub fn new(pool: PgPool, email_service: Option<Arc<EmailService>>) -> Self {
+        Self {
+            pool,
+            email_service,
+        }
+    }
+
+    pub async fn raise(
+        &self,
+        document: &Document,
+        event_type: SecurityEventType,
+        source: SecurityEventSource,
+        details: serde_json::Value,
+    ) -> Result<()> {
+        let Some(event) = db::security_event::record_event(
+            &self.pool,
+            document.id,
+            event_type,
+            SecurityEventSeverity::High,
+            source,
+            Some(details),
+        )
+        .await?
+        else {
+            return Ok(());
+        };
+
+        error!(
+            "Security event {} raised for document {}: {:?}",
+            event.id, document.id, event_type
+        );
+
+        let Some(email_service) = &self.email_service else {
+            return Ok(());
+        };
+
+        let mut recipients = db::user::get_admin_users(&self.pool).await?;
+        if !recipients.iter().any(|u| u.id == document.owner_id) {
+            if let Some(owner) = db::user::get_user_by_id(&self.pool, document.owner_id).await? {
+                recipients.insert(0, owner);
+            }
+        }
+
+        for user in recipients {
+            if let Err(e) = email_service
+                .send_tamper_alert(
+                    &user.email,
+                    &user.name,
+                    &document.title,
+                    event_type.description(),
+                )
+                .await
+            {
+                warn!(
+                    "Failed to alert {} about security event {}: {}",
+                    user.email, event.id, e
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Alerts when a chain verified by `audit::verify_integrity` or
+    /// `audit::chain_status` is broken.
+    pub async fn check_chain(
+        &self,
+        document: &Document,
+        status: &AuditChainStatus,
+        source: SecurityEventSource,
+    ) -> Result<()> {
+        if status.intact {
+            return Ok(());
+        }
+
+        self.raise(
+            document,
+            SecurityEventType::AuditChainBroken,
+            source,
+            serde_json::json!({ "first_broken_sequence": status.first_broken_sequence }),
+        )
+        .await
+    }
+
+    /// Verifies the document's audit chain, alerting if it is broken.
+    pub async fn verify_audit_chain(
+        &self,
+        document: &Document,
+        source: SecurityEventSource,
+    ) -> Result<AuditChainStatus> {
+        let status = audit::verify_integrity(&self.pool, document.id).await?;
+        self.check_chain(document, &status, source).await?;
+
+        Ok(status)
+    }
+}
@@ -0,0 +1,253 @@
+use anyhow::{Context, Result};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use ed25519_dalek::pkcs8::spki::der::pem::LineEnding;
+use ed25519_dalek::pkcs8::{DecodePrivateKey, DecodePublicKey, EncodePrivateKey, EncodePublicKey};
+use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey};
+use std::path::Path;
+use tracing::info;
+
+use crate::models::server_key::{
+    ServerPublicKey, ServerPublicKeySet, ServerSignature, ServerSignatureVerification, ED25519,
+};
+use crate::services::config::Config;
+use crate::services::crypto::{self, HashAlgorithm};
+
+/// Response headers carrying the signature of a signed download, made over
+/// the exact response body.
+pub const KEY_ID_HEADER: &str = "x-signvault-key-id";
+pub const SIGNATURE_HEADER: &str = "x-signvault-signature";
+
+/// Hex digits of the public key fingerprint used as its key id.
+const KEY_ID_LEN: usize = 16;
+
+/// The Ed25519 key SignVault signs certificates and audit exports with, so
+/// third parties can check offline that a document came from this server.
+/// Retired keys from `SIGNING_RETIRED_KEY_PATHS` are published alongside the
+/// current one and only used to verify.
+pub struct ServerKey {
+    signing_key: SigningKey,
+    /// The current key first.
+    keys: Vec<(VerifyingKey, ServerPublicKey)>,
+}
+
+impl ServerKey {
+    pub fn from_config(config: &Config) -> Result<Self> {
+        let signing_key = load_or_generate(Path::new(&config.signing_key_path))?;
+
+        let mut keys = vec![describe(signing_key.verifying_key(), true)?];
+        for path in &config.signing_retired_key_paths {
+            let key = load_public_key(Path::new(path))
+                .with_context(|| format!("Failed to load retired signing key {}", path))?;
+            keys.push(describe(key, false)?);
+        }
+
+        info!("Signing certificates with server key {}", keys[0].1.key_id);
+
+        Ok(Self::new(signing_key, keys))
+    }
+
+    fn new(signing_key: SigningKey, keys: Vec<(VerifyingKey, ServerPublicKey)>) -> Self {
+        Self { signing_key, keys }
+    }
+
+    pub fn key_id(&self) -> &str {
+        &self.keys[0].1.key_id
+    }
+
+    pub fn sign(&self, message: &[u8]) -> ServerSignature {
+        let current = &self.keys[0].1;
+
+        ServerSignature {
+            algorithm: ED25519.to_string(),
+            key_id: current.key_id.clone(),
+            key_fingerprint: current.fingerprint.clone(),
+            signature: BASE64.encode(self.signing_key.sign(message).to_bytes()),
+        }
+    }
+
+    pub fn public_keys(&self) -> ServerPublicKeySet {
+        ServerPublicKeySet {
+            current_key_id: self.key_id().to_string(),
+            keys: self.keys.iter().map(|(_, key)| key.clone()).collect(),
+        }
+    }
+
+    /// Checks a signature made with the current or a retired key.
+    pub fn verify(
+        &self,
+        message: &[u8],
+        key_id: &str,
+        signature: &str,
+    ) -> ServerSignatureVerification {
+        let Some((verifying_key, key)) = self.keys.iter().find(|(_, key)| key.key_id == key_id)
+        else {
+            return ServerSignatureVerification {
+                valid: false,
+                key: None,
+            };
+        };
+
+        let valid = BASE64
+            .decode(signature.trim())
+            .ok()
+            .and_then(|bytes| Signature::from_slice(&bytes).ok())
+            .is_some_and(|signature| verifying_key.verify_strict(message, &signature).is_ok());
+
+        ServerSignatureVerification {
+            valid,
+            key: Some(key.clone()),
+        }
+    }
+}
+
+/// Reads the PKCS#8 PEM key at `path`, generating and saving one if the file
+/// does not exist yet.
+fn load_or_generate(path: &Path) -> Result<SigningKey> {
+    if path.exists() {
+        let pem = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read signing key {}", path.display()))?;
+        return SigningKey::from_pkcs8_pem(&pem)
+            .map_err(|e| anyhow::anyhow!("Invalid signing key {}: {}", path.display(), e));
+    }
+
+    let mut seed = [0u8; 32];
+    getrandom::getrandom(&mut seed).context("Failed to generate signing key")?;
+    let signing_key = SigningKey::from_bytes(&seed);
+
+    let pem = signing_key
+        .to_pkcs8_pem(LineEnding::LF)
+        .map_err(|e| anyhow::anyhow!("Failed to encode signing key: {}", e))?;
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    write_private(path, pem.as_bytes())
+        .with_context(|| format!("Failed to save signing key {}", path.display()))?;
+
+    info!("Generated a new server signing key at {}", path.display());
+
+    Ok(signing_key)
+}
+
+#[cfg(unix)]
+fn write_private(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    use std::io::Write;
+    use std::os::unix::fs::OpenOptionsExt;
+
+    std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o600)
+        .open(path)?
+        .write_all(contents)
+}
+
+#[cfg(not(unix))]
+fn write_private(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    std::fs::write(path, contents)
+}
+
+/// Reads a public key PEM, or a private key PEM whose public half is used.
+fn load_public_key(path: &Path) -> Result<VerifyingKey> {
+    let pem = std::fs::read_to_string(path)?;
+
+    VerifyingKey::from_public_key_pem(&pem)
+        .or_else(|_| SigningKey::from_pkcs8_pem(&pem).map(|key| key.verifying_key()))
+        .map_err(|e| anyhow::anyhow!("Not an Ed25519 key: {}", e))
+}
+
+fn describe(key: VerifyingKey, current: bool) -> Result<(VerifyingKey, ServerPublicKey)> {
+    let fingerprint = crypto::hash_data(HashAlgorithm::Sha256, key.as_bytes());
+    let key_id = fingerprint
+        .trim_start_matches("sha256:")
+        .chars()
+        .take(KEY_ID_LEN)
+        .collect();
+
+    let public_key = key
+        .to_public_key_pem(LineEnding::LF)
+        .map_err(|e| anyhow::anyhow!("Failed to encode public key: {}", e))?;
+
+    Ok((
+        key,
+        ServerPublicKey {
+            key_id,
+            algorithm: ED25519,
+            fingerprint,
+            public_key,
+            current,
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn server_key(seed: u8, retired: &[u8]) -> ServerKey {
+        let signing_key = SigningKey::from_bytes(&[seed; 32]);
+        let mut keys = vec![describe(signing_key.verifying_key(), true).unwrap()];
+        for seed in retired {
+            let key = SigningKey::from_bytes(&[*seed; 32]).verifying_key();
+            keys.push(describe(key, false).unwrap());
+        }
+        ServerKey::new(signing_key, keys)
+    }
+
+    #[test]
+    fn test_sign_and_verify() {
+        let key = server_key(1, &[]);
+        let signature = key.sign(b"sha256:abcd");
+
+        assert_eq!(signature.key_id, key.key_id());
+        assert_eq!(signature.key_id.len(), KEY_ID_LEN);
+        assert!(signature.key_fingerprint.starts_with("sha256:"));
+
+        let result = key.verify(b"sha256:abcd", &signature.key_id, &signature.signature);
+        assert!(result.valid);
+        assert!(result.key.unwrap().current);
+
+        assert!(
+            !key.verify(b"sha256:abce", &signature.key_id, &signature.signature)
+                .valid
+        );
+        assert!(
+            !key.verify(b"sha256:abcd", &signature.key_id, "not base64")
+                .valid
+        );
+
+        let unknown = key.verify(b"sha256:abcd", "0000000000000000", &signature.signature);
+        assert!(!unknown.valid);
+        assert!(unknown.key.is_none());
+    }
+
+    #[test]
+    fn test_retired_key_still_verifies() {
+        let old = server_key(1, &[]);
+        let signature = old.sign(b"sha256:abcd");
+
+        let rotated = server_key(2, &[1]);
+        assert_ne!(rotated.key_id(), old.key_id());
+
+        let result = rotated.verify(b"sha256:abcd", &signature.key_id, &signature.signature);
+        assert!(result.valid);
+        assert!(!result.key.unwrap().current);
+
+        let keys = rotated.public_keys();
+        assert_eq!(keys.current_key_id, rotated.key_id());
+        assert_eq!(keys.keys.len(), 2);
+    }
+
+    #[test]
+    fn test_generated_key_is_reloaded() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("keys").join("signing-key.pem");
+
+        let generated = load_or_generate(&path).unwrap();
+        let reloaded = load_or_generate(&path).unwrap();
+        assert_eq!(generated.to_bytes(), reloaded.to_bytes());
+
+        let public = load_public_key(&path).unwrap();
+        assert_eq!(public, generated.verifying_key());
+    }
+}
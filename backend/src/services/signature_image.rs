@@ -0,0 +1,290 @@
+use anyhow::Result;
+use base64::Engine;
+use std::io::Cursor;
+
+pub const MAX_SIGNATURE_WIDTH_PX: u32 = 4096;
+pub const MAX_SIGNATURE_HEIGHT_PX: u32 = 2048;
+
+const PNG_PREFIX: &str = "data:image/png;base64,";
+const JPEG_PREFIXES: &[&str] = &["data:image/jpeg;base64,", "data:image/jpg;base64,"];
+
+/// Validates a submitted signature image data URL and returns a normalized
+/// data URL. PNGs are re-encoded with surrounding transparent pixels trimmed;
+/// JPEGs have no transparency and are kept as submitted.
+pub fn sanitize_signature_data_url(data: &str, max_bytes: usize) -> Result<String> {
+    let data = data.trim();
+
+    let (encoded, is_png) = if let Some(encoded) = data.strip_prefix(PNG_PREFIX) {
+        (encoded, true)
+    } else if let Some(encoded) = JPEG_PREFIXES.iter().find_map(|p| data.strip_prefix(p)) {
+        (encoded, false)
+    } else {
+        return Err(anyhow::anyhow!(
+            "Signature must be a base64 PNG or JPEG data URL"
+        ));
+    };
+
+    if encoded.len() > max_bytes.div_ceil(3) * 4 {
+        return Err(size_error(max_bytes));
+    }
+
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(encoded)
+        .map_err(|_| anyhow::anyhow!("Signature image is not valid base64"))?;
+
+    if bytes.len() > max_bytes {
+        return Err(size_error(max_bytes));
+    }
+
+    if is_png {
+        let (width, height, rgba) = decode_png(&bytes)?;
+        let (width, height, rgba) = trim_transparent(width, height, &rgba)?;
+        let png = encode_rgba_png(width, height, &rgba)?;
+
+        Ok(format!(
+            "{}{}",
+            PNG_PREFIX,
+            base64::engine::general_purpose::STANDARD.encode(png)
+        ))
+    } else {
+        validate_jpeg(&bytes)?;
+
+        Ok(format!(
+            "data:image/jpeg;base64,{}",
+            base64::engine::general_purpose::STANDARD.encode(bytes)
+        ))
+    }
+}
+
+/// Splits a sanitized signature data URL into its content type and image
+/// bytes, for storing the image as a file.
+pub fn split_data_url(data_url: &str) -> Result<(&'static str, Vec<u8>)> {
+    let (content_type, encoded) = if let Some(encoded) = data_url.strip_prefix(PNG_PREFIX) {
+        ("image/png", encoded)
+    } else if let Some(encoded) = JPEG_PREFIXES.iter().find_map(|p| data_url.strip_prefix(p)) {
+        ("image/jpeg", encoded)
+    } else {
+        return Err(anyhow::anyhow!("Not a PNG or JPEG data URL"));
+    };
+
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(encoded)
+        .map_err(|_| anyhow::anyhow!("Signature image is not valid base64"))?;
+
+    Ok((content_type, bytes))
+}
+
+/// The data URL `split_data_url` was given back, which is what signature
+/// hashes are computed over.
+pub fn join_data_url(content_type: &str, bytes: &[u8]) -> String {
+    format!(
+        "data:{};base64,{}",
+        content_type,
+        base64::engine::general_purpose::STANDARD.encode(bytes)
+    )
+}
+
+fn size_error(max_bytes: usize) -> anyhow::Error {
+    anyhow::anyhow!(
+        "Signature image exceeds the maximum size of {} KB",
+        max_bytes / 1024
+    )
+}
+
+fn check_dimensions(width: u32, height: u32) -> Result<()> {
+    if width == 0 || height == 0 {
+        return Err(anyhow::anyhow!("Signature image is empty"));
+    }
+
+    if width > MAX_SIGNATURE_WIDTH_PX || height > MAX_SIGNATURE_HEIGHT_PX {
+        return Err(anyhow::anyhow!(
+            "Signature image dimensions {}x{} exceed the maximum of {}x{}",
+            width,
+            height,
+            MAX_SIGNATURE_WIDTH_PX,
+            MAX_SIGNATURE_HEIGHT_PX
+        ));
+    }
+
+    Ok(())
+}
+
+/// Decodes a PNG into 8-bit RGBA pixels.
+fn decode_png(bytes: &[u8]) -> Result<(u32, u32, Vec<u8>)> {
+    let mut decoder = png::Decoder::new(Cursor::new(bytes));
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+
+    let mut reader = decoder
+        .read_info()
+        .map_err(|_| anyhow::anyhow!("Signature image is not a valid PNG"))?;
+
+    let (width, height) = (reader.info().width, reader.info().height);
+    check_dimensions(width, height)?;
+
+    let mut buf = vec![0; reader.output_buffer_size()];
+    let frame = reader
+        .next_frame(&mut buf)
+        .map_err(|_| anyhow::anyhow!("Signature image is not a valid PNG"))?;
+    let pixels = &buf[..frame.buffer_size()];
+
+    let rgba = match frame.color_type {
+        png::ColorType::Rgba => pixels.to_vec(),
+        png::ColorType::Rgb => pixels
+            .chunks_exact(3)
+            .flat_map(|p| [p[0], p[1], p[2], 255])
+            .collect(),
+        png::ColorType::GrayscaleAlpha => pixels
+            .chunks_exact(2)
+            .flat_map(|p| [p[0], p[0], p[0], p[1]])
+            .collect(),
+        png::ColorType::Grayscale => pixels.iter().flat_map(|g| [*g, *g, *g, 255]).collect(),
+        png::ColorType::Indexed => {
+            return Err(anyhow::anyhow!("Unsupported PNG color type"));
+        }
+    };
+
+    Ok((width, height, rgba))
+}
+
+fn validate_jpeg(bytes: &[u8]) -> Result<()> {
+    let mut decoder = jpeg_decoder::Decoder::new(Cursor::new(bytes));
+
+    decoder
+        .read_info()
+        .map_err(|_| anyhow::anyhow!("Signature image is not a valid JPEG"))?;
+
+    let info = decoder
+        .info()
+        .ok_or_else(|| anyhow::anyhow!("Signature image is not a valid JPEG"))?;
+    check_dimensions(info.width as u32, info.height as u32)?;
+
+    decoder
+        .decode()
+        .map_err(|_| anyhow::anyhow!("Signature image is not a valid JPEG"))?;
+
+    Ok(())
+}
+
+/// Crops RGBA pixels to the bounding box of non-transparent pixels.
+fn trim_transparent(width: u32, height: u32, rgba: &[u8]) -> Result<(u32, u32, Vec<u8>)> {
+    let (width, height) = (width as usize, height as usize);
+    let (mut min_x, mut min_y, mut max_x, mut max_y) = (width, height, 0, 0);
+
+    for y in 0..height {
+        for x in 0..width {
+            if rgba[(y * width + x) * 4 + 3] > 0 {
+                min_x = min_x.min(x);
+                min_y = min_y.min(y);
+                max_x = max_x.max(x);
+                max_y = max_y.max(y);
+            }
+        }
+    }
+
+    if min_x > max_x || min_y > max_y {
+        return Err(anyhow::anyhow!("Signature image is blank"));
+    }
+
+    let cropped_width = max_x - min_x + 1;
+    let cropped_height = max_y - min_y + 1;
+    let mut cropped = Vec::with_capacity(cropped_width * cropped_height * 4);
+
+    for y in min_y..=max_y {
+        let start = (y * width + min_x) * 4;
+        cropped.extend_from_slice(&rgba[start..start + cropped_width * 4]);
+    }
+
+    Ok((cropped_width as u32, cropped_height as u32, cropped))
+}
+
+pub fn encode_rgba_png(width: u32, height: u32, rgba: &[u8]) -> Result<Vec<u8>> {
+    let mut out = Vec::new();
+    {
+        let mut encoder = png::Encoder::new(&mut out, width, height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(rgba)?;
+    }
+
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PIXEL: &str = "data:image/png;base64,iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAYAAAAfFcSJAAAADUlEQVR42mNk+M9QDwADhgGAWjR9awAAAABJRU5ErkJggg==";
+
+    fn png_data_url(width: u32, height: u32, rgba: &[u8]) -> String {
+        let png = encode_rgba_png(width, height, rgba).unwrap();
+        format!(
+            "{}{}",
+            PNG_PREFIX,
+            base64::engine::general_purpose::STANDARD.encode(png)
+        )
+    }
+
+    #[test]
+    fn test_sanitize_accepts_png() {
+        let sanitized = sanitize_signature_data_url(PIXEL, 1024).unwrap();
+        assert!(sanitized.starts_with(PNG_PREFIX));
+    }
+
+    #[test]
+    fn test_sanitize_rejects_other_content() {
+        assert!(sanitize_signature_data_url("data:image/svg+xml;base64,PHN2Zz4=", 1024).is_err());
+        assert!(sanitize_signature_data_url("data:image/png;base64,aGVsbG8=", 1024).is_err());
+        assert!(sanitize_signature_data_url("data:image/png;base64,!!!", 1024).is_err());
+        assert!(sanitize_signature_data_url("data:image/jpeg;base64,aGVsbG8=", 1024).is_err());
+    }
+
+    #[test]
+    fn test_sanitize_enforces_size_limit() {
+        assert!(sanitize_signature_data_url(PIXEL, 16).is_err());
+    }
+
+    #[test]
+    fn test_sanitize_enforces_dimensions() {
+        let wide = png_data_url(
+            MAX_SIGNATURE_WIDTH_PX + 1,
+            1,
+            &vec![255; (MAX_SIGNATURE_WIDTH_PX as usize + 1) * 4],
+        );
+        let err = sanitize_signature_data_url(&wide, 1024 * 1024).unwrap_err();
+        assert!(err.to_string().contains("dimensions"));
+    }
+
+    #[test]
+    fn test_sanitize_trims_transparent_border() {
+        let mut rgba = vec![0u8; 4 * 4 * 4];
+        for (x, y) in [(1, 1), (2, 2)] {
+            let i = (y * 4 + x) * 4;
+            rgba[i..i + 4].copy_from_slice(&[0, 0, 0, 255]);
+        }
+
+        let sanitized = sanitize_signature_data_url(&png_data_url(4, 4, &rgba), 1024).unwrap();
+        let bytes = base64::engine::general_purpose::STANDARD
+            .decode(sanitized.strip_prefix(PNG_PREFIX).unwrap())
+            .unwrap();
+        let (width, height, _) = decode_png(&bytes).unwrap();
+
+        assert_eq!((width, height), (2, 2));
+    }
+
+    #[test]
+    fn test_split_data_url_round_trips() {
+        let sanitized = sanitize_signature_data_url(PIXEL, 1024).unwrap();
+        let (content_type, bytes) = split_data_url(&sanitized).unwrap();
+
+        assert_eq!(content_type, "image/png");
+        assert_eq!(join_data_url(content_type, &bytes), sanitized);
+        assert!(split_data_url("data:image/svg+xml;base64,PHN2Zz4=").is_err());
+    }
+
+    #[test]
+    fn test_sanitize_rejects_blank_image() {
+        let blank = png_data_url(2, 2, &[0; 16]);
+        assert!(sanitize_signature_data_url(&blank, 1024).is_err());
+    }
+}
@@ -0,0 +1,139 @@
+use anyhow::{Context, Result};
+use sqlx::PgPool;
+use std::sync::Arc;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::db;
+use crate::models::document::Document;
+use crate::models::signature::{Signature, StoredSignatureImage};
+use crate::services::signature_image;
+use crate::services::storage::{self, Storage};
+
+const MIGRATION_BATCH_SIZE: i64 = 100;
+
+/// Stores a sanitized signature data URL as the image of signature
+/// `signature_id` on `document`, encrypted with the document's key if it has
+/// one.
+pub async fn store_image(
+    storage: &dyn Storage,
+    document: &Document,
+    signature_id: Uuid,
+    data_url: &str,
+) -> Result<StoredSignatureImage> {
+    let (content_type, bytes) = signature_image::split_data_url(data_url)?;
+    let storage_key =
+        storage::signature_image_key(document.owner_id, document.id, signature_id, content_type);
+    let byte_length = i32::try_from(bytes.len()).context("Signature image is too large")?;
+
+    let nonce = storage
+        .put_file(&storage_key, bytes, document.data_key().as_ref())
+        .await?;
+
+    Ok(StoredSignatureImage {
+        storage_key,
+        content_type: content_type.to_string(),
+        byte_length,
+        nonce,
+    })
+}
+
+/// The content type and bytes of a signature's image. Images still kept in
+/// the table are moved to storage first.
+pub async fn load_image(
+    pool: &PgPool,
+    storage: &dyn Storage,
+    document: &Document,
+    signature: &Signature,
+) -> Result<(String, Vec<u8>)> {
+    if let Some(image) = read_stored_image(storage, document, signature).await? {
+        return Ok(image);
+    }
+
+    match db::signature::get_inline_signature_data(pool, signature.id).await? {
+        Some(data_url) => {
+            let (content_type, bytes) = signature_image::split_data_url(&data_url)?;
+            move_inline_image(pool, storage, document, signature.id, &data_url).await?;
+            Ok((content_type.to_string(), bytes))
+        }
+        None => {
+            // Moved by someone else since the signature was read
+            let moved = db::signature::get_signature_by_id(pool, signature.id)
+                .await?
+                .context("Signature not found")?;
+            read_stored_image(storage, document, &moved)
+                .await?
+                .context("Signature has no image")
+        }
+    }
+}
+
+async fn read_stored_image(
+    storage: &dyn Storage,
+    document: &Document,
+    signature: &Signature,
+) -> Result<Option<(String, Vec<u8>)>> {
+    let (Some(key), Some(content_type)) = (&signature.storage_key, &signature.content_type) else {
+        return Ok(None);
+    };
+
+    let encryption = document.file_encryption(signature.image_nonce.as_deref());
+    let bytes = storage.get_file(key, encryption.as_ref()).await?;
+    Ok(Some((content_type.clone(), bytes)))
+}
+
+async fn move_inline_image(
+    pool: &PgPool,
+    storage: &dyn Storage,
+    document: &Document,
+    signature_id: Uuid,
+    data_url: &str,
+) -> Result<()> {
+    let image = store_image(storage, document, signature_id, data_url).await?;
+    db::signature::set_signature_image(pool, signature_id, &image).await?;
+    Ok(())
+}
+
+/// Moves the images of signatures written before images were kept in storage
+/// out of the table. Images that cannot be moved are left for the next run.
+/// Returns how many were moved.
+pub async fn migrate_inline_images(pool: &PgPool, storage: &dyn Storage) -> Result<usize> {
+    let mut moved = 0;
+    let mut after = Uuid::nil();
+
+    loop {
+        let batch =
+            db::signature::get_signatures_with_inline_data(pool, after, MIGRATION_BATCH_SIZE)
+                .await?;
+        let Some((last, _)) = batch.last() else {
+            break;
+        };
+        after = *last;
+
+        for (id, document_id) in batch {
+            let Some(data_url) = db::signature::get_inline_signature_data(pool, id).await? else {
+                continue;
+            };
+            let Some(document) = db::document::get_document_by_id(pool, document_id).await? else {
+                continue;
+            };
+
+            match move_inline_image(pool, storage, &document, id, &data_url).await {
+                Ok(()) => moved += 1,
+                Err(e) => warn!("Could not move the image of signature {}: {:?}", id, e),
+            }
+        }
+    }
+
+    Ok(moved)
+}
+
+pub fn spawn_image_migration(pool: PgPool, storage: Arc<dyn Storage>) {
+    tokio::spawn(async move {
+        match migrate_inline_images(&pool, storage.as_ref()).await {
+            Ok(0) => {}
+            Ok(moved) => info!("Moved {} signature image(s) to storage", moved),
+            Err(e) => error!("Failed to move signature images to storage: {:?}", e),
+        }
+    });
+}
@@ -1,31 +1,119 @@
 use anyhow::Result;
-use sqlx::PgPool;
+use sqlx::{PgConnection, PgPool};
+use std::collections::{HashMap, HashSet};
 use uuid::Uuid;
 
 use crate::db;
-use crate::models::audit::AuditAction;
-use crate::models::document::DocumentStatus;
-use crate::models::signature::CompleteSigningRequest;
-use crate::models::signer::SignerStatus;
-use crate::services::{audit, crypto};
+use crate::models::audit::{AuditAction, GeoLocation};
+use crate::models::document::{Document, DocumentStatus, FieldType};
+use crate::models::outbox::OutboxEventType;
+use crate::models::signature::{CompleteSigningRequest, SignatureType, SubmitSignatureRequest};
+use crate::models::signer::{Signer, SignerRole, SignerStatus};
+use crate::services::crypto::HashAlgorithm;
+use crate::services::number_field::NumberConstraints;
+use crate::services::storage::Storage;
+use crate::services::typed_signature::TypedSignatureRenderer;
+use crate::services::{
+    audit, crypto, field_conditions, radio_group, signature_image, signature_store,
+};
 
 pub struct SigningContext {
     pub signer_id: Uuid,
     pub document_id: Uuid,
     pub ip_address: String,
     pub user_agent: String,
+    pub actor: SigningActor,
+    pub location: Option<GeoLocation>,
+    /// Algorithm for the signature and audit hashes written.
+    pub hash_algorithm: HashAlgorithm,
+}
+
+/// Who is driving a signing action on behalf of the signer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SigningActor {
+    /// The signer, authenticated by their emailed access token.
+    AccessToken,
+    /// A document owner hosting an in-person session on their own device.
+    InPersonHost(Uuid),
+    /// The authenticated document owner signing their own document.
+    Owner(Uuid),
+}
+
+/// What a signing submission did.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SigningSubmission {
+    Recorded,
+    /// The signer had already submitted the same signatures, e.g. a browser
+    /// retrying after a dropped response. Nothing was changed.
+    AlreadySubmitted,
+}
+
+impl SigningContext {
+    pub fn user_id(&self) -> Option<Uuid> {
+        match self.actor {
+            SigningActor::AccessToken => None,
+            SigningActor::InPersonHost(user_id) | SigningActor::Owner(user_id) => Some(user_id),
+        }
+    }
+
+    pub fn audit_details(&self, mut details: serde_json::Value) -> serde_json::Value {
+        if let (Some(location), Some(map)) = (&self.location, details.as_object_mut()) {
+            map.insert("location".to_string(), serde_json::json!(location));
+        }
+
+        match self.actor {
+            SigningActor::AccessToken => details,
+            SigningActor::InPersonHost(host_user_id) => {
+                in_person_audit_details(Some(host_user_id), details)
+            }
+            SigningActor::Owner(user_id) => {
+                if let Some(map) = details.as_object_mut() {
+                    map.insert("self_signed".to_string(), serde_json::json!(true));
+                    map.insert("user_id".to_string(), serde_json::json!(user_id));
+                }
+                details
+            }
+        }
+    }
+}
+
+/// Tags audit details for actions taken during a host-initiated in-person session.
+pub fn in_person_audit_details(
+    host_user_id: Option<Uuid>,
+    mut details: serde_json::Value,
+) -> serde_json::Value {
+    if let (Some(host_user_id), Some(map)) = (host_user_id, details.as_object_mut()) {
+        map.insert("in_person".to_string(), serde_json::json!(true));
+        map.insert("host_user_id".to_string(), serde_json::json!(host_user_id));
+    }
+    details
 }
 
 pub async fn process_signing(
     pool: &PgPool,
+    storage: &dyn Storage,
+    renderer: &TypedSignatureRenderer,
+    max_signature_bytes: usize,
     ctx: &SigningContext,
     request: &CompleteSigningRequest,
-) -> Result<()> {
-    let signer = db::signer::get_signer_by_id(pool, ctx.signer_id)
+) -> Result<SigningSubmission> {
+    // Everything below commits together or not at all. Locking the document
+    // first serializes submissions, keeping the signer count and the audit
+    // chain consistent when participants finish at the same time.
+    let mut tx = pool.begin().await?;
+
+    let document = db::document::lock_document(&mut tx, ctx.document_id)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Document not found"))?;
+
+    let signer = db::signer::get_signer_by_id(&mut *tx, ctx.signer_id)
         .await?
         .ok_or_else(|| anyhow::anyhow!("Signer not found"))?;
 
     if signer.status == SignerStatus::Signed {
+        if is_repeat_submission(&mut tx, renderer, max_signature_bytes, &signer, request).await? {
+            return Ok(SigningSubmission::AlreadySubmitted);
+        }
         return Err(anyhow::anyhow!("Document already signed by this signer"));
     }
 
@@ -33,9 +121,15 @@ pub async fn process_signing(
         return Err(anyhow::anyhow!("Signer has declined to sign"));
     }
 
-    let document = db::document::get_document_by_id(pool, ctx.document_id)
-        .await?
-        .ok_or_else(|| anyhow::anyhow!("Document not found"))?;
+    if signer.status == SignerStatus::Removed {
+        return Err(anyhow::anyhow!("Signer was removed from this document"));
+    }
+
+    if signer.role == SignerRole::Approver {
+        return Err(anyhow::anyhow!(
+            "Approvers cannot submit signatures, use the approval action instead"
+        ));
+    }
 
     if document.status == DocumentStatus::Completed {
         return Err(anyhow::anyhow!("Document already completed"));
@@ -45,8 +139,65 @@ pub async fn process_signing(
         return Err(anyhow::anyhow!("Document has been voided"));
     }
 
+    // Fields hidden by their condition, given the values being submitted,
+    // accept nothing and are not required
+    let fields = db::document::get_fields_by_document(&mut *tx, ctx.document_id).await?;
+    let submitted_values = submitted_values(request);
+    let visible = field_conditions::visible_fields(&fields, &submitted_values);
+
+    let hidden = request
+        .signatures
+        .iter()
+        .map(|s| s.field_id)
+        .chain(request.field_values.iter().map(|v| v.field_id))
+        .find(|id| fields.iter().any(|f| f.id == *id) && !visible.contains(id));
+    if let Some(field_id) = hidden {
+        return Err(anyhow::anyhow!(
+            "Field {} is hidden by its condition and cannot be filled in",
+            field_id
+        ));
+    }
+
+    let read_only = request
+        .field_values
+        .iter()
+        .find_map(|v| fields.iter().find(|f| f.id == v.field_id && f.read_only));
+    if let Some(field) = read_only {
+        return Err(anyhow::anyhow!(
+            "{} is read-only and cannot be changed",
+            field.display_name()
+        ));
+    }
+
+    radio_group::check_selection(&fields, &visible, signer.id, &submitted_values)
+        .map_err(|e| anyhow::anyhow!(e))?;
+
+    // Number fields are checked before anything is stored, and their values
+    // saved with the precision the field is configured with
+    let mut numbers = HashMap::new();
+    for value_req in &request.field_values {
+        let Some(field) = fields
+            .iter()
+            .find(|f| f.id == value_req.field_id && f.field_type == FieldType::Number)
+        else {
+            continue;
+        };
+
+        if value_req.value.trim().is_empty() {
+            continue;
+        }
+
+        let number = NumberConstraints::of(field)
+            .normalize(&value_req.value)
+            .map_err(|e| anyhow::anyhow!("{}: {}", field.display_name(), e))?;
+        numbers.insert(field.id, number);
+    }
+
+    // Validate every submitted signature before persisting any of them
+    let mut resolved_signatures = Vec::with_capacity(request.signatures.len());
+
     for sig_req in &request.signatures {
-        let field = db::document::get_field_by_id(pool, sig_req.field_id)
+        let field = db::document::get_field_by_id(&mut *tx, sig_req.field_id)
             .await?
             .ok_or_else(|| anyhow::anyhow!("Field not found: {}", sig_req.field_id))?;
 
@@ -60,38 +211,143 @@ pub async fn process_signing(
             }
         }
 
-        let signature_hash = crypto::hash_string(&sig_req.signature_data);
+        let resolved =
+            resolve_signature_data(&mut tx, renderer, max_signature_bytes, &signer, sig_req)
+                .await?;
+        resolved_signatures.push((sig_req, resolved));
+    }
+
+    let bulk_initials = match &request.apply_initials_to_all {
+        Some(initial_data) => {
+            let data =
+                signature_image::sanitize_signature_data_url(initial_data, max_signature_bytes)
+                    .map_err(|e| anyhow::anyhow!("Invalid apply_initials_to_all image: {}", e))?;
+
+            let initial_fields = signer_initial_fields(&mut tx, &signer, request).await?;
+
+            if initial_fields.is_empty() {
+                return Err(anyhow::anyhow!(
+                    "No initial fields are assigned to this signer"
+                ));
+            }
+
+            Some((data, bulk_initial_targets(initial_fields, request)))
+        }
+        None => None,
+    };
+
+    let signed_fields: HashSet<Uuid> = request
+        .signatures
+        .iter()
+        .map(|s| s.field_id)
+        .chain(
+            bulk_initials
+                .iter()
+                .flat_map(|(_, ids)| ids.iter().copied()),
+        )
+        .collect();
+    if let Some(field_id) = field_conditions::missing_required_field(
+        &fields,
+        &visible,
+        signer.id,
+        &submitted_values,
+        &signed_fields,
+    ) {
+        return Err(anyhow::anyhow!(
+            "Required field {} is not filled in",
+            field_id
+        ));
+    }
+
+    for (sig_req, resolved) in resolved_signatures {
+        let signature_hash = crypto::hash_string(ctx.hash_algorithm, &resolved.data);
+        // Stored ahead of the row referring to it; if the submission is
+        // rolled back, the storage scan finds the image orphaned.
+        let signature_id = Uuid::new_v4();
+        let image =
+            signature_store::store_image(storage, &document, signature_id, &resolved.data).await?;
 
         db::signature::create_signature(
-            pool,
+            &mut *tx,
+            signature_id,
             ctx.signer_id,
             ctx.document_id,
             sig_req.field_id,
-            &sig_req.signature_data,
+            &image,
             &signature_hash,
+            sig_req.signature_type,
+            resolved.typed_text.as_deref(),
+            resolved.typed_font.as_deref(),
             &ctx.ip_address,
             &ctx.user_agent,
         )
         .await?;
 
-        audit::log_action(
-            pool,
+        audit::log_action_in(
+            &mut tx,
+            ctx.hash_algorithm,
             ctx.document_id,
             Some(ctx.signer_id),
-            None,
+            ctx.user_id(),
             AuditAction::SignatureApplied,
             Some(&ctx.ip_address),
             Some(&ctx.user_agent),
-            Some(serde_json::json!({
+            Some(ctx.audit_details(serde_json::json!({
                 "field_id": sig_req.field_id,
+                "signature_hash": signature_hash,
+                "signature_type": sig_req.signature_type,
+                "typed_text": resolved.typed_text,
+                "saved_signature_id": sig_req.saved_signature_id
+            }))),
+        )
+        .await?;
+    }
+
+    if let Some((data, field_ids)) = bulk_initials.filter(|(_, ids)| !ids.is_empty()) {
+        let signature_hash = crypto::hash_string(ctx.hash_algorithm, &data);
+
+        for field_id in &field_ids {
+            let signature_id = Uuid::new_v4();
+            let image =
+                signature_store::store_image(storage, &document, signature_id, &data).await?;
+
+            db::signature::create_signature(
+                &mut *tx,
+                signature_id,
+                ctx.signer_id,
+                ctx.document_id,
+                *field_id,
+                &image,
+                &signature_hash,
+                SignatureType::Drawn,
+                None,
+                None,
+                &ctx.ip_address,
+                &ctx.user_agent,
+            )
+            .await?;
+        }
+
+        audit::log_action_in(
+            &mut tx,
+            ctx.hash_algorithm,
+            ctx.document_id,
+            Some(ctx.signer_id),
+            ctx.user_id(),
+            AuditAction::SignatureApplied,
+            Some(&ctx.ip_address),
+            Some(&ctx.user_agent),
+            Some(ctx.audit_details(serde_json::json!({
+                "applied_to_all_initials": true,
+                "field_ids": field_ids,
                 "signature_hash": signature_hash
-            })),
+            }))),
         )
         .await?;
     }
 
     for field_req in &request.field_values {
-        let field = db::document::get_field_by_id(pool, field_req.field_id)
+        let field = db::document::get_field_by_id(&mut *tx, field_req.field_id)
             .await?
             .ok_or_else(|| anyhow::anyhow!("Field not found: {}", field_req.field_id))?;
 
@@ -99,34 +355,316 @@ pub async fn process_signing(
             return Err(anyhow::anyhow!("Field does not belong to this document"));
         }
 
-        db::document::update_field_value(pool, field_req.field_id, &field_req.value).await?;
+        let value = numbers.get(&field_req.field_id).unwrap_or(&field_req.value);
+        db::document::update_field_value(&mut *tx, field_req.field_id, value).await?;
     }
 
-    db::signer::mark_signer_signed(pool, ctx.signer_id, &ctx.ip_address, &ctx.user_agent).await?;
+    db::signer::mark_signer_signed(&mut *tx, ctx.signer_id, &ctx.ip_address, &ctx.user_agent)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Document already signed by this signer"))?;
 
-    audit::log_action(
-        pool,
+    audit::log_action_in(
+        &mut tx,
+        ctx.hash_algorithm,
         ctx.document_id,
         Some(ctx.signer_id),
-        None,
+        ctx.user_id(),
         AuditAction::SignerSigned,
         Some(&ctx.ip_address),
         Some(&ctx.user_agent),
-        Some(serde_json::json!({
+        Some(ctx.audit_details(serde_json::json!({
             "signer_email": signer.email,
             "signer_name": signer.name
-        })),
+        }))),
+    )
+    .await?;
+
+    let progress = db::document::recount_signers(&mut *tx, ctx.document_id).await?;
+
+    db::outbox::record_event(
+        &mut *tx,
+        OutboxEventType::SignerSigned,
+        ctx.document_id,
+        Some(ctx.signer_id),
+        serde_json::json!({
+            "role": signer.role,
+            "completed_signers": progress.completed_signers,
+            "total_signers": progress.total_signers
+        }),
     )
     .await?;
 
-    let updated_doc = db::document::increment_completed_signers(pool, ctx.document_id).await?;
+    record_participant_completion(&mut tx, ctx.hash_algorithm, &progress).await?;
 
-    if updated_doc.completed_signers >= updated_doc.total_signers {
-        db::document::mark_document_completed(pool, ctx.document_id).await?;
+    tx.commit().await?;
 
-        audit::log_action(
-            pool,
-            ctx.document_id,
+    Ok(SigningSubmission::Recorded)
+}
+
+struct ResolvedSignature {
+    data: String,
+    typed_text: Option<String>,
+    typed_font: Option<String>,
+}
+
+/// The initial fields assigned to `signer` that the submitted values leave
+/// visible. Fields hidden by their conditions need no initial.
+async fn signer_initial_fields(
+    conn: &mut PgConnection,
+    signer: &Signer,
+    request: &CompleteSigningRequest,
+) -> Result<Vec<Uuid>> {
+    let fields = db::document::get_fields_by_document(&mut *conn, signer.document_id).await?;
+    let visible = field_conditions::visible_fields(&fields, &submitted_values(request));
+
+    Ok(fields
+        .into_iter()
+        .filter(|f| f.field_type == FieldType::Initial && f.signer_id == Some(signer.id))
+        .filter(|f| visible.contains(&f.id))
+        .map(|f| f.id)
+        .collect())
+}
+
+fn submitted_values(request: &CompleteSigningRequest) -> HashMap<Uuid, String> {
+    request
+        .field_values
+        .iter()
+        .map(|v| (v.field_id, v.value.clone()))
+        .collect()
+}
+
+/// The initial fields `apply_initials_to_all` fills. Explicit per-field
+/// submissions take precedence over the bulk initial.
+fn bulk_initial_targets(initial_fields: Vec<Uuid>, request: &CompleteSigningRequest) -> Vec<Uuid> {
+    initial_fields
+        .into_iter()
+        .filter(|id| !request.signatures.iter().any(|s| s.field_id == *id))
+        .collect()
+}
+
+/// Whether `request` repeats the submission the signer already made: the same
+/// fields, each with a signature matching the stored hash.
+async fn is_repeat_submission(
+    conn: &mut PgConnection,
+    renderer: &TypedSignatureRenderer,
+    max_signature_bytes: usize,
+    signer: &Signer,
+    request: &CompleteSigningRequest,
+) -> Result<bool> {
+    let mut submitted = Vec::new();
+
+    for sig_req in &request.signatures {
+        let Ok(resolved) =
+            resolve_signature_data(conn, renderer, max_signature_bytes, signer, sig_req).await
+        else {
+            return Ok(false);
+        };
+        submitted.push((sig_req.field_id, resolved.data));
+    }
+
+    if let Some(initial_data) = &request.apply_initials_to_all {
+        let Ok(data) =
+            signature_image::sanitize_signature_data_url(initial_data, max_signature_bytes)
+        else {
+            return Ok(false);
+        };
+
+        let initial_fields = signer_initial_fields(conn, signer, request).await?;
+        for field_id in bulk_initial_targets(initial_fields, request) {
+            submitted.push((field_id, data.clone()));
+        }
+    }
+
+    let stored = db::signature::get_signatures_by_signer(&mut *conn, signer.id).await?;
+    if stored.len() != submitted.len() {
+        return Ok(false);
+    }
+
+    Ok(submitted.iter().all(|(field_id, data)| {
+        stored.iter().any(|sig| {
+            sig.field_id == *field_id
+                && crypto::verify_string(&sig.signature_hash, data).unwrap_or(false)
+        })
+    }))
+}
+
+/// Returns the image data for a submitted signature, loading it from the
+/// signer's saved signatures or rendering typed text as required.
+async fn resolve_signature_data(
+    conn: &mut PgConnection,
+    renderer: &TypedSignatureRenderer,
+    max_signature_bytes: usize,
+    signer: &Signer,
+    sig_req: &SubmitSignatureRequest,
+) -> Result<ResolvedSignature> {
+    if sig_req.signature_type == SignatureType::Typed {
+        if sig_req.signature_data.is_some() || sig_req.saved_signature_id.is_some() {
+            return Err(anyhow::anyhow!(
+                "Typed signature for field {} must not include image data",
+                sig_req.field_id
+            ));
+        }
+
+        let (Some(text), Some(font)) = (&sig_req.text, &sig_req.font) else {
+            return Err(anyhow::anyhow!(
+                "Typed signature for field {} requires text and font",
+                sig_req.field_id
+            ));
+        };
+
+        let data = renderer.render(text, font)?;
+
+        return Ok(ResolvedSignature {
+            data,
+            typed_text: Some(text.trim().to_string()),
+            typed_font: Some(font.clone()),
+        });
+    }
+
+    let data = match (&sig_req.signature_data, sig_req.saved_signature_id) {
+        (Some(data), None) => {
+            signature_image::sanitize_signature_data_url(data, max_signature_bytes).map_err(
+                |e| anyhow::anyhow!("Invalid signature for field {}: {}", sig_req.field_id, e),
+            )?
+        }
+        (None, Some(saved_id)) => {
+            let saved = db::signature::get_user_signature_by_id(&mut *conn, saved_id)
+                .await?
+                .ok_or_else(|| anyhow::anyhow!("Saved signature not found: {}", saved_id))?;
+
+            let owner = db::user::get_user_by_id(&mut *conn, saved.user_id).await?;
+            let owned_by_signer = owner
+                .map(|u| u.email.eq_ignore_ascii_case(&signer.email))
+                .unwrap_or(false);
+
+            if !owned_by_signer {
+                return Err(anyhow::anyhow!("Saved signature not found: {}", saved_id));
+            }
+
+            saved.signature_data
+        }
+        (Some(_), Some(_)) => {
+            return Err(anyhow::anyhow!(
+                "Provide either signature_data or saved_signature_id for field {}, not both",
+                sig_req.field_id
+            ))
+        }
+        (None, None) => {
+            return Err(anyhow::anyhow!(
+                "Signature data is required for field {}",
+                sig_req.field_id
+            ))
+        }
+    };
+
+    Ok(ResolvedSignature {
+        data,
+        typed_text: None,
+        typed_font: None,
+    })
+}
+
+pub async fn process_approval(pool: &PgPool, ctx: &SigningContext) -> Result<()> {
+    let mut tx = pool.begin().await?;
+
+    let document = db::document::lock_document(&mut tx, ctx.document_id)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Document not found"))?;
+
+    let signer = db::signer::get_signer_by_id(&mut *tx, ctx.signer_id)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Signer not found"))?;
+
+    if signer.role != SignerRole::Approver {
+        return Err(anyhow::anyhow!("Only approvers can approve this document"));
+    }
+
+    if signer.status == SignerStatus::Approved {
+        return Err(anyhow::anyhow!(
+            "Document already approved by this approver"
+        ));
+    }
+
+    if signer.status == SignerStatus::Declined {
+        return Err(anyhow::anyhow!("Approver has rejected this document"));
+    }
+
+    if signer.status == SignerStatus::Removed {
+        return Err(anyhow::anyhow!("Signer was removed from this document"));
+    }
+
+    if document.status == DocumentStatus::Completed {
+        return Err(anyhow::anyhow!("Document already completed"));
+    }
+
+    if document.status == DocumentStatus::Voided {
+        return Err(anyhow::anyhow!("Document has been voided"));
+    }
+
+    db::signer::mark_signer_approved(&mut *tx, ctx.signer_id, &ctx.ip_address, &ctx.user_agent)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Document already approved by this approver"))?;
+
+    audit::log_action_in(
+        &mut tx,
+        ctx.hash_algorithm,
+        ctx.document_id,
+        Some(ctx.signer_id),
+        ctx.user_id(),
+        AuditAction::SignerApproved,
+        Some(&ctx.ip_address),
+        Some(&ctx.user_agent),
+        Some(ctx.audit_details(serde_json::json!({
+            "signer_email": signer.email,
+            "signer_name": signer.name
+        }))),
+    )
+    .await?;
+
+    let progress = db::document::recount_signers(&mut *tx, ctx.document_id).await?;
+
+    db::outbox::record_event(
+        &mut *tx,
+        OutboxEventType::SignerSigned,
+        ctx.document_id,
+        Some(ctx.signer_id),
+        serde_json::json!({
+            "role": signer.role,
+            "completed_signers": progress.completed_signers,
+            "total_signers": progress.total_signers
+        }),
+    )
+    .await?;
+
+    record_participant_completion(&mut tx, ctx.hash_algorithm, &progress).await?;
+
+    tx.commit().await?;
+
+    Ok(())
+}
+
+/// Completes the document once every participant is done, given its freshly
+/// recounted signatures and approvals. Completion is idempotent, so only the
+/// transaction that completes the document logs it and records the event.
+pub async fn record_participant_completion(
+    conn: &mut PgConnection,
+    hash_algorithm: HashAlgorithm,
+    updated_doc: &Document,
+) -> Result<()> {
+    let document_id = updated_doc.id;
+
+    if updated_doc.completed_signers < updated_doc.total_signers {
+        return Ok(());
+    }
+
+    if db::document::mark_document_completed(&mut *conn, document_id)
+        .await?
+        .is_some()
+    {
+        audit::log_action_in(
+            conn,
+            hash_algorithm,
+            document_id,
             None,
             None,
             AuditAction::DocumentCompleted,
@@ -138,6 +676,15 @@ pub async fn process_signing(
             })),
         )
         .await?;
+
+        db::outbox::record_event(
+            conn,
+            OutboxEventType::DocumentCompleted,
+            document_id,
+            None,
+            serde_json::json!({}),
+        )
+        .await?;
     }
 
     Ok(())
@@ -145,13 +692,16 @@ pub async fn process_signing(
 
 pub async fn decline_signing(
     pool: &PgPool,
-    signer_id: Uuid,
-    document_id: Uuid,
+    ctx: &SigningContext,
     reason: Option<&str>,
-    ip_address: &str,
-    user_agent: &str,
 ) -> Result<()> {
-    let signer = db::signer::get_signer_by_id(pool, signer_id)
+    let mut tx = pool.begin().await?;
+
+    db::document::lock_document(&mut tx, ctx.document_id)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Document not found"))?;
+
+    let signer = db::signer::get_signer_by_id(&mut *tx, ctx.signer_id)
         .await?
         .ok_or_else(|| anyhow::anyhow!("Signer not found"))?;
 
@@ -163,22 +713,43 @@ pub async fn decline_signing(
         return Err(anyhow::anyhow!("Already declined"));
     }
 
-    db::signer::mark_signer_declined(pool, signer_id, reason).await?;
+    if signer.status == SignerStatus::Removed {
+        return Err(anyhow::anyhow!("Signer was removed from this document"));
+    }
+
+    if signer.status == SignerStatus::Approved {
+        return Err(anyhow::anyhow!("Document already approved"));
+    }
+
+    db::signer::mark_signer_declined(&mut *tx, ctx.signer_id, reason).await?;
 
-    audit::log_action(
-        pool,
-        document_id,
-        Some(signer_id),
-        None,
+    audit::log_action_in(
+        &mut tx,
+        ctx.hash_algorithm,
+        ctx.document_id,
+        Some(ctx.signer_id),
+        ctx.user_id(),
         AuditAction::SignerDeclined,
-        Some(ip_address),
-        Some(user_agent),
-        Some(serde_json::json!({
+        Some(&ctx.ip_address),
+        Some(&ctx.user_agent),
+        Some(ctx.audit_details(serde_json::json!({
             "signer_email": signer.email,
+            "role": signer.role,
             "reason": reason
-        })),
+        }))),
     )
     .await?;
 
+    db::outbox::record_event(
+        &mut *tx,
+        OutboxEventType::SignerDeclined,
+        ctx.document_id,
+        Some(ctx.signer_id),
+        serde_json::json!({ "role": signer.role, "reason": reason }),
+    )
+    .await?;
+
+    tx.commit().await?;
+
     Ok(())
 }
@@ -0,0 +1,584 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use bytes::Bytes;
+use chrono::{DateTime, Utc};
+use futures::stream::{BoxStream, StreamExt, TryStreamExt};
+use object_store::{
+    aws::{AmazonS3, AmazonS3Builder},
+    path::Path as ObjectPath,
+    GetOptions, GetRange, ObjectStore, PutPayload,
+};
+use std::io::SeekFrom;
+use std::ops::Range;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+use tokio::fs;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+use tokio_util::io::ReaderStream;
+
+use crate::models::encryption::{FileEncryption, WrappedDataKey};
+use crate::services::config::Config;
+use crate::services::encryption::{self, EncryptedStorage};
+
+pub const BACKEND_LOCAL: &str = "local";
+pub const BACKEND_S3: &str = "s3";
+
+/// Object written and removed again to check that storage is writable.
+pub const HEALTH_CHECK_KEY: &str = ".health_check";
+
+pub type ByteStream = BoxStream<'static, std::io::Result<Bytes>>;
+
+#[derive(Debug, Clone)]
+pub struct StoredObject {
+    pub key: String,
+    pub size: u64,
+    pub last_modified: DateTime<Utc>,
+}
+
+/// Where document files live. Keys are backend-relative, `/`-separated paths
+/// such as `{owner_id}/{document_id}/original.pdf`, and are what gets stored
+/// in `documents.file_path`.
+#[async_trait]
+pub trait Storage: Send + Sync {
+    /// Human readable location, e.g. a directory or bucket, for health checks
+    /// and logs.
+    fn location(&self) -> String;
+
+    async fn put(&self, key: &str, data: Vec<u8>) -> Result<()>;
+
+    async fn get(&self, key: &str) -> Result<Vec<u8>>;
+
+    async fn size(&self, key: &str) -> Result<u64>;
+
+    /// Streams the object, or only the bytes in `range` (end exclusive).
+    async fn stream(&self, key: &str, range: Option<Range<u64>>) -> Result<ByteStream>;
+
+    /// Deletes the object. Deleting a missing object is not an error.
+    async fn delete(&self, key: &str) -> Result<()>;
+
+    /// Deletes every object below `prefix`, a key ending with `/`.
+    async fn delete_prefix(&self, prefix: &str) -> Result<()>;
+
+    async fn exists(&self, key: &str) -> Result<bool>;
+
+    /// Every stored object, in no particular order.
+    async fn list(&self) -> Result<Vec<StoredObject>>;
+
+    /// A new document's data key, or `None` when files are stored in
+    /// plaintext.
+    async fn new_data_key(&self) -> Result<Option<WrappedDataKey>> {
+        Ok(None)
+    }
+
+    /// Writes a document file, encrypted with `data_key` if given, and returns
+    /// the nonce to record with it.
+    async fn put_file(
+        &self,
+        key: &str,
+        data: Vec<u8>,
+        data_key: Option<&WrappedDataKey>,
+    ) -> Result<Option<Vec<u8>>> {
+        match data_key {
+            Some(_) => Err(encryption_not_configured(key)),
+            None => {
+                self.put(key, data).await?;
+                Ok(None)
+            }
+        }
+    }
+
+    /// Reads a document file, decrypting it if it was encrypted.
+    async fn get_file(&self, key: &str, encryption: Option<&FileEncryption>) -> Result<Vec<u8>> {
+        match encryption {
+            Some(_) => Err(encryption_not_configured(key)),
+            None => self.get(key).await,
+        }
+    }
+
+    /// Streams a document file like `stream`, with `range` in plaintext
+    /// bytes.
+    async fn stream_file(
+        &self,
+        key: &str,
+        range: Option<Range<u64>>,
+        encryption: Option<&FileEncryption>,
+    ) -> Result<ByteStream> {
+        match encryption {
+            Some(_) => Err(encryption_not_configured(key)),
+            None => self.stream(key, range).await,
+        }
+    }
+
+    /// Size of a document file's plaintext.
+    async fn file_size(&self, key: &str, encryption: Option<&FileEncryption>) -> Result<u64> {
+        match encryption {
+            Some(_) => Err(encryption_not_configured(key)),
+            None => self.size(key).await,
+        }
+    }
+}
+
+fn encryption_not_configured(key: &str) -> anyhow::Error {
+    anyhow::anyhow!(
+        "{} is encrypted, but STORAGE_ENCRYPTION_KEY is not set",
+        key
+    )
+}
+
+/// The configured storage backend, encrypting document files when a master
+/// key is set.
+pub fn from_config(config: &Config) -> Result<Arc<dyn Storage>> {
+    let backend = backend_from_config(config)?;
+
+    match encryption::key_provider(config)? {
+        Some(keys) => Ok(Arc::new(EncryptedStorage::new(backend, keys))),
+        None => Ok(backend),
+    }
+}
+
+/// The configured storage backend, without encryption.
+pub fn backend_from_config(config: &Config) -> Result<Arc<dyn Storage>> {
+    match config.storage_backend.as_str() {
+        BACKEND_LOCAL => Ok(Arc::new(LocalStorage::new(&config.storage_path)?)),
+        BACKEND_S3 => Ok(Arc::new(S3Storage::new(config)?)),
+        other => Err(anyhow::anyhow!(
+            "Unsupported STORAGE_BACKEND: {} (expected {} or {})",
+            other,
+            BACKEND_LOCAL,
+            BACKEND_S3
+        )),
+    }
+}
+
+/// Directory holding a document's own files, such as its sealed copy. Its
+/// original may be stored elsewhere when the upload was deduplicated.
+pub fn document_dir(owner_id: uuid::Uuid, document_id: uuid::Uuid) -> String {
+    format!("{}/{}", owner_id, document_id)
+}
+
+/// Storage key for a document's uploaded PDF.
+pub fn original_pdf_key(owner_id: uuid::Uuid, document_id: uuid::Uuid) -> String {
+    format!("{}/original.pdf", document_dir(owner_id, document_id))
+}
+
+/// Storage key of a document's sealed copy.
+pub fn sealed_pdf_key(owner_id: uuid::Uuid, document_id: uuid::Uuid) -> String {
+    format!("{}/sealed.pdf", document_dir(owner_id, document_id))
+}
+
+/// Prefix of the rendered page previews cached next to the document stored at
+/// `file_path`.
+pub fn page_preview_prefix(file_path: &str) -> String {
+    match file_path.rsplit_once('/') {
+        Some((dir, _)) => format!("{}/previews/", dir),
+        None => format!("{}.previews/", file_path),
+    }
+}
+
+/// Storage key of a cached page preview. The file hash is part of the key so
+/// a replaced file never serves previews of its previous contents.
+pub fn page_preview_key(file_path: &str, file_hash: &str, page: u32, width: u32) -> String {
+    let digest = file_hash
+        .split_once(':')
+        .map_or(file_hash, |(_, digest)| digest);
+    let hash = digest.get(..16).unwrap_or(digest);
+    format!(
+        "{}{}-page{}-{}w.png",
+        page_preview_prefix(file_path),
+        hash,
+        page,
+        width
+    )
+}
+
+/// Prefix of a document's signature images.
+pub fn signature_image_prefix(owner_id: uuid::Uuid, document_id: uuid::Uuid) -> String {
+    format!("{}/signatures/", document_dir(owner_id, document_id))
+}
+
+/// Storage key of a signature image, e.g. `signatures/{signature_id}.png` in
+/// the document's directory.
+pub fn signature_image_key(
+    owner_id: uuid::Uuid,
+    document_id: uuid::Uuid,
+    signature_id: uuid::Uuid,
+    content_type: &str,
+) -> String {
+    let extension = match content_type {
+        "image/jpeg" => "jpg",
+        _ => "png",
+    };
+    format!(
+        "{}{}.{}",
+        signature_image_prefix(owner_id, document_id),
+        signature_id,
+        extension
+    )
+}
+
+/// Storage key of an uploaded branding logo. Each upload gets a new key, so a
+/// replaced logo is never served from the old one's place.
+pub fn branding_logo_key(user_id: uuid::Uuid, logo_id: uuid::Uuid, content_type: &str) -> String {
+    let extension = match content_type {
+        "image/jpeg" => "jpg",
+        _ => "png",
+    };
+    format!("{}/branding/{}.{}", user_id, logo_id, extension)
+}
+
+/// Files on the local filesystem under `STORAGE_PATH`.
+pub struct LocalStorage {
+    root: PathBuf,
+}
+
+impl LocalStorage {
+    pub fn new(root: impl Into<PathBuf>) -> Result<Self> {
+        let root = root.into();
+        std::fs::create_dir_all(&root)
+            .with_context(|| format!("Failed to create storage directory {}", root.display()))?;
+        Ok(Self { root })
+    }
+
+    /// Resolves a key below the storage root, refusing keys that could escape
+    /// it.
+    fn path(&self, key: &str) -> Result<PathBuf> {
+        let relative = Path::new(key);
+        if key.is_empty()
+            || !relative
+                .components()
+                .all(|c| matches!(c, Component::Normal(_)))
+        {
+            return Err(anyhow::anyhow!("Invalid storage key: {}", key));
+        }
+
+        Ok(self.root.join(relative))
+    }
+}
+
+#[async_trait]
+impl Storage for LocalStorage {
+    fn location(&self) -> String {
+        self.root.display().to_string()
+    }
+
+    async fn put(&self, key: &str, data: Vec<u8>) -> Result<()> {
+        let path = self.path(key)?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+        fs::write(&path, data).await?;
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> Result<Vec<u8>> {
+        Ok(fs::read(self.path(key)?).await?)
+    }
+
+    async fn size(&self, key: &str) -> Result<u64> {
+        Ok(fs::metadata(self.path(key)?).await?.len())
+    }
+
+    async fn stream(&self, key: &str, range: Option<Range<u64>>) -> Result<ByteStream> {
+        let mut file = fs::File::open(self.path(key)?).await?;
+
+        match range {
+            None => Ok(ReaderStream::new(file).boxed()),
+            Some(range) => {
+                file.seek(SeekFrom::Start(range.start)).await?;
+                Ok(ReaderStream::new(file.take(range.end - range.start)).boxed())
+            }
+        }
+    }
+
+    async fn delete(&self, key: &str) -> Result<()> {
+        let path = self.path(key)?;
+        match fs::remove_file(&path).await {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e.into()),
+        }
+
+        // Drop the per-document directory once it is empty.
+        if let Some(parent) = path.parent().filter(|p| *p != self.root) {
+            let _ = fs::remove_dir(parent).await;
+        }
+
+        Ok(())
+    }
+
+    async fn delete_prefix(&self, prefix: &str) -> Result<()> {
+        let path = self.path(prefix.trim_end_matches('/'))?;
+        match fs::remove_dir_all(&path).await {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e.into()),
+        }
+
+        if let Some(parent) = path.parent().filter(|p| *p != self.root) {
+            let _ = fs::remove_dir(parent).await;
+        }
+
+        Ok(())
+    }
+
+    async fn exists(&self, key: &str) -> Result<bool> {
+        Ok(fs::try_exists(self.path(key)?).await?)
+    }
+
+    async fn list(&self) -> Result<Vec<StoredObject>> {
+        let mut objects = Vec::new();
+        let mut dirs = vec![self.root.clone()];
+
+        while let Some(dir) = dirs.pop() {
+            let mut entries = fs::read_dir(&dir).await?;
+            while let Some(entry) = entries.next_entry().await? {
+                let metadata = entry.metadata().await?;
+                if metadata.is_dir() {
+                    dirs.push(entry.path());
+                    continue;
+                }
+
+                let path = entry.path();
+                let Some(key) = path
+                    .strip_prefix(&self.root)
+                    .ok()
+                    .and_then(|relative| relative.to_str())
+                else {
+                    continue;
+                };
+
+                objects.push(StoredObject {
+                    key: key.replace(std::path::MAIN_SEPARATOR, "/"),
+                    size: metadata.len(),
+                    last_modified: metadata.modified()?.into(),
+                });
+            }
+        }
+
+        Ok(objects)
+    }
+}
+
+/// Objects in an S3 or S3-compatible (MinIO, R2, ...) bucket. Credentials not
+/// set in the config are taken from the usual `AWS_*` environment variables
+/// or the instance role.
+pub struct S3Storage {
+    store: AmazonS3,
+    bucket: String,
+    prefix: Option<String>,
+}
+
+impl S3Storage {
+    pub fn new(config: &Config) -> Result<Self> {
+        let bucket = config
+            .s3_bucket
+            .clone()
+            .context("S3_BUCKET must be set when STORAGE_BACKEND is s3")?;
+
+        let mut builder = AmazonS3Builder::from_env()
+            .with_bucket_name(&bucket)
+            .with_region(&config.s3_region)
+            .with_allow_http(config.s3_allow_http);
+
+        if let Some(endpoint) = &config.s3_endpoint {
+            builder = builder.with_endpoint(endpoint);
+        }
+        if let Some(access_key_id) = &config.s3_access_key_id {
+            builder = builder.with_access_key_id(access_key_id);
+        }
+        if let Some(secret_access_key) = &config.s3_secret_access_key {
+            builder = builder.with_secret_access_key(secret_access_key);
+        }
+
+        let store = builder
+            .build()
+            .context("Invalid S3 storage configuration")?;
+
+        Ok(Self {
+            store,
+            bucket,
+            prefix: config
+                .s3_prefix
+                .as_ref()
+                .map(|p| p.trim_matches('/').to_string())
+                .filter(|p| !p.is_empty()),
+        })
+    }
+
+    fn path(&self, key: &str) -> Result<ObjectPath> {
+        let key = match &self.prefix {
+            Some(prefix) => format!("{}/{}", prefix, key),
+            None => key.to_string(),
+        };
+        ObjectPath::parse(&key).with_context(|| format!("Invalid storage key: {}", key))
+    }
+}
+
+#[async_trait]
+impl Storage for S3Storage {
+    fn location(&self) -> String {
+        match &self.prefix {
+            Some(prefix) => format!("s3://{}/{}", self.bucket, prefix),
+            None => format!("s3://{}", self.bucket),
+        }
+    }
+
+    async fn put(&self, key: &str, data: Vec<u8>) -> Result<()> {
+        self.store
+            .put(&self.path(key)?, PutPayload::from(data))
+            .await?;
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> Result<Vec<u8>> {
+        let bytes = self.store.get(&self.path(key)?).await?.bytes().await?;
+        Ok(bytes.to_vec())
+    }
+
+    async fn size(&self, key: &str) -> Result<u64> {
+        Ok(self.store.head(&self.path(key)?).await?.size as u64)
+    }
+
+    async fn stream(&self, key: &str, range: Option<Range<u64>>) -> Result<ByteStream> {
+        let options = GetOptions {
+            range: range.map(|r| GetRange::Bounded(r.start as usize..r.end as usize)),
+            ..Default::default()
+        };
+
+        let result = self.store.get_opts(&self.path(key)?, options).await?;
+
+        Ok(result.into_stream().map_err(std::io::Error::other).boxed())
+    }
+
+    async fn delete(&self, key: &str) -> Result<()> {
+        match self.store.delete(&self.path(key)?).await {
+            Ok(()) | Err(object_store::Error::NotFound { .. }) => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn delete_prefix(&self, prefix: &str) -> Result<()> {
+        let prefix = self.path(prefix.trim_end_matches('/'))?;
+        let objects: Vec<_> = self.store.list(Some(&prefix)).try_collect().await?;
+
+        for meta in objects {
+            match self.store.delete(&meta.location).await {
+                Ok(()) | Err(object_store::Error::NotFound { .. }) => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn exists(&self, key: &str) -> Result<bool> {
+        match self.store.head(&self.path(key)?).await {
+            Ok(_) => Ok(true),
+            Err(object_store::Error::NotFound { .. }) => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn list(&self) -> Result<Vec<StoredObject>> {
+        let prefix = self.prefix.as_deref().map(ObjectPath::from);
+        let objects: Vec<_> = self.store.list(prefix.as_ref()).try_collect().await?;
+
+        Ok(objects
+            .into_iter()
+            .map(|meta| {
+                let key = meta.location.to_string();
+                let key = match &self.prefix {
+                    Some(prefix) => key
+                        .strip_prefix(prefix.as_str())
+                        .map(|k| k.trim_start_matches('/').to_string())
+                        .unwrap_or(key),
+                    None => key,
+                };
+                StoredObject {
+                    key,
+                    size: meta.size as u64,
+                    last_modified: meta.last_modified,
+                }
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_local_storage_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = LocalStorage::new(dir.path()).unwrap();
+        let key = "owner/doc/original.pdf";
+
+        storage.put(key, b"0123456789".to_vec()).await.unwrap();
+        assert!(storage.exists(key).await.unwrap());
+        assert_eq!(storage.size(key).await.unwrap(), 10);
+        assert_eq!(storage.get(key).await.unwrap(), b"0123456789");
+
+        let chunks: Vec<Bytes> = storage
+            .stream(key, Some(2..5))
+            .await
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(chunks.concat(), b"234");
+
+        let listed = storage.list().await.unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].key, key);
+        assert_eq!(listed[0].size, 10);
+
+        storage.delete(key).await.unwrap();
+        assert!(!storage.exists(key).await.unwrap());
+        assert!(!dir.path().join("owner/doc").exists());
+        storage.delete(key).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_local_storage_delete_page_previews() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = LocalStorage::new(dir.path()).unwrap();
+        let key = "owner/doc/original.pdf";
+        let preview = page_preview_key(key, "0123456789abcdef0123", 2, 800);
+
+        assert_eq!(
+            preview,
+            "owner/doc/previews/0123456789abcdef-page2-800w.png"
+        );
+        assert_eq!(
+            page_preview_key(key, "sha512:0123456789abcdef0123", 2, 800),
+            preview
+        );
+
+        storage.put(key, b"pdf".to_vec()).await.unwrap();
+        storage.put(&preview, b"png".to_vec()).await.unwrap();
+
+        storage
+            .delete_prefix(&page_preview_prefix(key))
+            .await
+            .unwrap();
+        assert!(!storage.exists(&preview).await.unwrap());
+        assert!(storage.exists(key).await.unwrap());
+
+        storage
+            .delete_prefix(&page_preview_prefix(key))
+            .await
+            .unwrap();
+    }
+
+    #[test]
+    fn test_local_storage_rejects_escaping_keys() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = LocalStorage::new(dir.path()).unwrap();
+
+        for key in ["", "../secret", "/etc/passwd", "a/../../b"] {
+            assert!(storage.path(key).is_err(), "{}", key);
+        }
+        assert!(storage.path("a/b/original.pdf").is_ok());
+    }
+}
@@ -0,0 +1,161 @@
+use anyhow::Result;
+use chrono::{Duration, Utc};
+use sqlx::PgPool;
+use std::collections::HashSet;
+use std::sync::Arc;
+use tracing::info;
+
+use crate::db;
+use crate::models::storage_scan::{HashMismatch, MissingFile, OrphanedObject, StorageScanReport};
+use crate::services::config::Config;
+use crate::services::integrity::{FileIntegrity, IntegrityActor};
+use crate::services::storage::{self, Storage};
+
+/// Walks storage, cross-references it with `documents` and re-hashes every
+/// document file. Hash mismatches are only reported; orphaned objects older
+/// than the grace period are deleted when `STORAGE_SCAN_DELETE_ORPHANS` is set.
+pub struct StorageScanner {
+    pool: PgPool,
+    storage: Arc<dyn Storage>,
+    file_integrity: Arc<FileIntegrity>,
+    orphan_grace: Duration,
+    delete_orphans: bool,
+    running: tokio::sync::Mutex<()>,
+}
+
+impl StorageScanner {
+    pub fn new(
+        pool: PgPool,
+        config: &Config,
+        storage: Arc<dyn Storage>,
+        file_integrity: Arc<FileIntegrity>,
+    ) -> Self {
+        Self {
+            pool,
+            storage,
+            file_integrity,
+            orphan_grace: Duration::hours(config.storage_orphan_grace_hours),
+            delete_orphans: config.storage_scan_delete_orphans,
+            running: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    /// Runs a scan and stores its report. Returns `None` if a scan is already
+    /// in progress.
+    pub async fn scan(&self) -> Result<Option<StorageScanReport>> {
+        let Ok(_guard) = self.running.try_lock() else {
+            return Ok(None);
+        };
+
+        let started_at = Utc::now();
+        let objects = self.storage.list().await?;
+        let documents = db::document::get_all_documents(&self.pool).await?;
+
+        let signature_images = db::signature::get_signature_storage_keys(&self.pool).await?;
+        let logos = db::branding::get_logo_storage_keys(&self.pool).await?;
+        let referenced: HashSet<&str> = documents
+            .iter()
+            .flat_map(|d| [Some(d.file_path.as_str()), d.sealed_file_path.as_deref()])
+            .flatten()
+            .chain(signature_images.iter().map(String::as_str))
+            .chain(logos.iter().map(String::as_str))
+            .collect();
+        let stored: HashSet<&str> = objects.iter().map(|o| o.key.as_str()).collect();
+        let preview_prefixes: HashSet<String> = documents
+            .iter()
+            .map(|d| storage::page_preview_prefix(&d.file_path))
+            .collect();
+
+        let mut errors = Vec::new();
+        let mut orphans = Vec::new();
+        let mut orphans_deleted = 0;
+
+        // Younger objects may belong to an upload that is still in progress
+        let orphan_cutoff = started_at - self.orphan_grace;
+
+        for object in &objects {
+            let is_preview = object
+                .key
+                .rfind('/')
+                .is_some_and(|i| preview_prefixes.contains(&object.key[..=i]));
+
+            if referenced.contains(object.key.as_str())
+                || is_preview
+                || object.key == storage::HEALTH_CHECK_KEY
+                || object.last_modified > orphan_cutoff
+            {
+                continue;
+            }
+
+            let mut deleted = false;
+            if self.delete_orphans {
+                match self.storage.delete(&object.key).await {
+                    Ok(()) => {
+                        deleted = true;
+                        orphans_deleted += 1;
+                    }
+                    Err(e) => errors.push(format!("Failed to delete {}: {}", object.key, e)),
+                }
+            }
+
+            orphans.push(OrphanedObject {
+                key: object.key.clone(),
+                size: object.size,
+                last_modified: object.last_modified,
+                deleted,
+            });
+        }
+
+        let mut missing_files = Vec::new();
+        let mut hash_mismatches = Vec::new();
+
+        for document in &documents {
+            if !stored.contains(document.file_path.as_str()) {
+                missing_files.push(MissingFile {
+                    document_id: document.id,
+                    file_path: document.file_path.clone(),
+                });
+                continue;
+            }
+
+            match self
+                .file_integrity
+                .verify(document, &IntegrityActor::storage_scan())
+                .await
+            {
+                Ok(report) if !report.intact => hash_mismatches.push(HashMismatch {
+                    document_id: document.id,
+                    expected_hash: report.expected_hash,
+                    actual_hash: report.actual_hash,
+                }),
+                Ok(_) => {}
+                Err(e) => errors.push(format!("Failed to verify document {}: {}", document.id, e)),
+            }
+        }
+
+        let report = db::storage_scan::create_report(
+            &self.pool,
+            started_at,
+            objects.len() as i32,
+            documents.len() as i32,
+            &orphans,
+            orphans_deleted,
+            &missing_files,
+            &hash_mismatches,
+            &errors,
+        )
+        .await?;
+
+        info!(
+            "Storage scan finished: {} objects, {} documents, {} orphans ({} deleted), {} missing, {} hash mismatches",
+            report.objects_scanned,
+            report.documents_checked,
+            orphans.len(),
+            orphans_deleted,
+            missing_files.len(),
+            hash_mismatches.len()
+        );
+
+        Ok(Some(report))
+    }
+}
@@ -0,0 +1,711 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, NaiveDateTime, Utc};
+use sqlx::PgPool;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::db;
+use crate::models::audit::AuditAction;
+use crate::models::document::Document;
+use crate::models::timestamp::{DocumentTimestamp, TimestampSubject, TimestampVerification};
+use crate::services::audit;
+use crate::services::config::Config;
+use crate::services::crypto::{self, HashAlgorithm};
+
+/// Attempts per timestamp before giving up on it.
+const MAX_ATTEMPTS: i32 = 10;
+const RETRY_BASE_DELAY_SECS: i64 = 60;
+const RETRY_MAX_DELAY_SECS: i64 = 6 * 3600;
+const RETRY_BATCH_SIZE: i64 = 50;
+
+const TAG_BOOLEAN: u8 = 0x01;
+const TAG_INTEGER: u8 = 0x02;
+const TAG_OCTET_STRING: u8 = 0x04;
+const TAG_NULL: u8 = 0x05;
+const TAG_OID: u8 = 0x06;
+const TAG_GENERALIZED_TIME: u8 = 0x18;
+const TAG_SEQUENCE: u8 = 0x30;
+const TAG_SET: u8 = 0x31;
+const TAG_CONTEXT_0: u8 = 0xa0;
+
+/// 2.16.840.1.101.3.4.2.1
+const OID_SHA256: &[u8] = &[0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x01];
+/// 2.16.840.1.101.3.4.2.3
+const OID_SHA512: &[u8] = &[0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x03];
+/// 1.2.840.113549.1.7.2
+const OID_SIGNED_DATA: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x07, 0x02];
+/// 1.2.840.113549.1.9.16.1.4
+const OID_TST_INFO: &[u8] = &[
+    0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x09, 0x10, 0x01, 0x04,
+];
+
+/// Obtains RFC 3161 timestamps for document and certificate hashes from the
+/// authority at `TSA_URL`. Requests that fail are kept and retried in the
+/// background with exponential backoff, so an unavailable authority never
+/// holds up signing.
+pub struct Timestamps {
+    pool: PgPool,
+    hash_algorithm: HashAlgorithm,
+    client: reqwest::Client,
+    tsa_url: Option<String>,
+    #[cfg(feature = "seal")]
+    trust_anchors: Option<Vec<openssl::x509::X509>>,
+}
+
+impl Timestamps {
+    pub fn from_config(pool: PgPool, config: &Config) -> Result<Self> {
+        let client = reqwest::Client::builder()
+            .timeout(std::time::Duration::from_secs(config.tsa_timeout_secs))
+            .build()?;
+
+        match config.tsa_url.as_deref() {
+            Some(url) => info!("Timestamping completed documents with {}", url),
+            None => info!("Timestamp authority not configured, documents will not be timestamped"),
+        }
+
+        #[cfg(feature = "seal")]
+        let trust_anchors = config
+            .tsa_ca_path
+            .as_deref()
+            .map(|path| {
+                signature::load_trust_anchors(path)
+                    .with_context(|| format!("Failed to load timestamp authority CA {}", path))
+            })
+            .transpose()?;
+        #[cfg(not(feature = "seal"))]
+        if config.tsa_ca_path.is_some() {
+            warn!("Built without the seal feature, ignoring TSA_CA_PATH");
+        }
+
+        Ok(Self {
+            pool,
+            hash_algorithm: config.hash_algorithm,
+            client,
+            tsa_url: config.tsa_url.clone(),
+            #[cfg(feature = "seal")]
+            trust_anchors,
+        })
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.tsa_url.is_some()
+    }
+
+    /// Timestamps a completed document's file hash. Failures are logged
+    /// rather than returned, like sealing; the request is retried later.
+    pub async fn stamp_completed(&self, document: &Document) {
+        if let Err(e) = self
+            .stamp(document.id, TimestampSubject::Document, &document.file_hash)
+            .await
+        {
+            error!("Failed to timestamp document {}: {:?}", document.id, e);
+        }
+    }
+
+    /// Requests a timestamp of `hashed_value`, a stored hash. The returned row
+    /// is pending if the authority could not be reached. Returns `None` when
+    /// no authority is configured, or for BLAKE3 hashes, which authorities do
+    /// not accept.
+    pub async fn stamp(
+        &self,
+        document_id: Uuid,
+        subject: TimestampSubject,
+        hashed_value: &str,
+    ) -> Result<Option<DocumentTimestamp>> {
+        let Some(tsa_url) = self.tsa_url.as_deref() else {
+            return Ok(None);
+        };
+
+        let (algorithm, _) = crypto::parse_hash(hashed_value)?;
+        if digest_oid(algorithm).is_none() {
+            warn!(
+                "Not timestamping {:?} hash of document {}: {} is not supported by timestamp authorities",
+                subject, document_id, algorithm
+            );
+            return Ok(None);
+        }
+
+        let timestamp = db::timestamp::create_timestamp(
+            &self.pool,
+            document_id,
+            subject,
+            hashed_value,
+            tsa_url,
+        )
+        .await?;
+
+        Ok(Some(self.attempt(timestamp).await?))
+    }
+
+    /// Retries every pending timestamp that is due. Returns how many were
+    /// granted.
+    pub async fn retry_pending(&self) -> Result<usize> {
+        let mut granted = 0;
+
+        for timestamp in db::timestamp::get_due_timestamps(&self.pool, RETRY_BATCH_SIZE).await? {
+            if self.attempt(timestamp).await?.token.is_some() {
+                granted += 1;
+            }
+        }
+
+        Ok(granted)
+    }
+
+    async fn attempt(&self, timestamp: DocumentTimestamp) -> Result<DocumentTimestamp> {
+        match self
+            .request(&timestamp.tsa_url, &timestamp.hashed_value)
+            .await
+        {
+            Ok((token, info)) => {
+                let timestamp = db::timestamp::set_token(
+                    &self.pool,
+                    timestamp.id,
+                    &token,
+                    info.gen_time,
+                    &info.serial_number,
+                )
+                .await?;
+
+                audit::log_action(
+                    &self.pool,
+                    self.hash_algorithm,
+                    timestamp.document_id,
+                    None,
+                    None,
+                    AuditAction::DocumentTimestamped,
+                    None,
+                    None,
+                    Some(serde_json::json!({
+                        "timestamp_id": timestamp.id,
+                        "subject": timestamp.subject,
+                        "hashed_value": timestamp.hashed_value,
+                        "gen_time": info.gen_time,
+                        "serial_number": info.serial_number,
+                    })),
+                )
+                .await?;
+
+                info!(
+                    "Timestamped {:?} hash of document {}",
+                    timestamp.subject, timestamp.document_id
+                );
+
+                Ok(timestamp)
+            }
+            Err(e) => {
+                let attempts = timestamp.attempts + 1;
+                let next_attempt_at =
+                    (attempts < MAX_ATTEMPTS).then(|| Utc::now() + retry_delay(attempts));
+
+                warn!(
+                    "Timestamp request {} for document {} failed (attempt {}): {:?}",
+                    timestamp.id, timestamp.document_id, attempts, e
+                );
+
+                db::timestamp::record_failure(
+                    &self.pool,
+                    timestamp.id,
+                    &format!("{:#}", e),
+                    next_attempt_at,
+                )
+                .await
+            }
+        }
+    }
+
+    /// Sends a request to the authority and returns the token it granted.
+    async fn request(&self, tsa_url: &str, hashed_value: &str) -> Result<(Vec<u8>, TstInfo)> {
+        let (algorithm, digest) = crypto::parse_hash(hashed_value)?;
+        let oid = digest_oid(algorithm)
+            .with_context(|| format!("{} is not supported by timestamp authorities", algorithm))?;
+        let hash = hex::decode(digest).context("Hashed value is not hex")?;
+        let nonce = generate_nonce();
+
+        let response = self
+            .client
+            .post(tsa_url)
+            .header(reqwest::header::CONTENT_TYPE, "application/timestamp-query")
+            .body(build_request(oid, &hash, &nonce))
+            .send()
+            .await?
+            .error_for_status()?
+            .bytes()
+            .await?;
+
+        let token = parse_response(&response)?;
+        let info = parse_token(&token)?;
+
+        if !info.attests(hashed_value) {
+            anyhow::bail!("Timestamp token does not attest the requested hash");
+        }
+        if info.nonce.as_deref().map(strip_leading_zeros) != Some(strip_leading_zeros(&nonce)) {
+            anyhow::bail!("Timestamp token nonce does not match the request");
+        }
+
+        Ok((token, info))
+    }
+
+    /// Checks a stored token: that it attests the requested hash and, when
+    /// built with OpenSSL, that the authority's signature over it is valid
+    /// and chains to `TSA_CA_PATH`.
+    pub fn verify(&self, timestamp: &DocumentTimestamp) -> TimestampVerification {
+        let mut verification = TimestampVerification {
+            timestamp_id: timestamp.id,
+            valid: false,
+            imprint_matches: false,
+            signature_valid: None,
+            chain_trusted: None,
+            gen_time: None,
+            errors: Vec::new(),
+        };
+
+        let Some(token) = timestamp.token.as_deref() else {
+            verification
+                .errors
+                .push("No timestamp token has been obtained".to_string());
+            return verification;
+        };
+
+        match parse_token(token) {
+            Ok(info) => {
+                verification.imprint_matches = info.attests(&timestamp.hashed_value);
+                if !verification.imprint_matches {
+                    verification
+                        .errors
+                        .push("Token does not attest the stored hash".to_string());
+                }
+                if Some(info.gen_time) != timestamp.gen_time {
+                    verification
+                        .errors
+                        .push("Token time does not match the stored time".to_string());
+                }
+                verification.gen_time = Some(info.gen_time);
+            }
+            Err(e) => verification.errors.push(format!("{:#}", e)),
+        }
+
+        #[cfg(feature = "seal")]
+        {
+            let (signature_valid, chain_trusted) = signature::verify(
+                token,
+                self.trust_anchors.as_deref(),
+                &mut verification.errors,
+            );
+            verification.signature_valid = Some(signature_valid);
+            verification.chain_trusted = chain_trusted;
+        }
+
+        verification.valid = verification.errors.is_empty();
+        verification
+    }
+}
+
+fn retry_delay(attempts: i32) -> Duration {
+    let delay = RETRY_BASE_DELAY_SECS.saturating_mul(1 << (attempts - 1).clamp(0, 20));
+    Duration::seconds(delay.min(RETRY_MAX_DELAY_SECS))
+}
+
+/// Eight random bytes, encoded as a minimal positive INTEGER.
+fn generate_nonce() -> Vec<u8> {
+    let mut nonce = Uuid::new_v4().as_bytes()[..8].to_vec();
+    nonce[0] = (nonce[0] & 0x7f) | 0x40;
+    nonce
+}
+
+fn strip_leading_zeros(value: &[u8]) -> &[u8] {
+    let start = value.iter().position(|&b| b != 0).unwrap_or(value.len());
+    &value[start..]
+}
+
+/// Fields of a token's `TSTInfo` that SignVault keeps.
+#[derive(Debug)]
+pub struct TstInfo {
+    pub algorithm: HashAlgorithm,
+    pub hashed_message: Vec<u8>,
+    pub serial_number: String,
+    pub gen_time: DateTime<Utc>,
+    pub nonce: Option<Vec<u8>>,
+}
+
+impl TstInfo {
+    /// Whether the token attests `hash`, a stored hash.
+    pub fn attests(&self, hash: &str) -> bool {
+        let hashed_message = hex::encode(&self.hashed_message);
+        crypto::parse_hash(hash).is_ok_and(|(algorithm, digest)| {
+            algorithm == self.algorithm && digest == hashed_message
+        })
+    }
+}
+
+/// Object identifier of a digest algorithm RFC 3161 authorities accept.
+fn digest_oid(algorithm: HashAlgorithm) -> Option<&'static [u8]> {
+    match algorithm {
+        HashAlgorithm::Sha256 => Some(OID_SHA256),
+        HashAlgorithm::Sha512 => Some(OID_SHA512),
+        HashAlgorithm::Blake3 => None,
+    }
+}
+
+/// A DER `TimeStampReq` for `hash`, digested with the algorithm `oid`
+/// identifies, asking for the authority's certificate to be included in the
+/// token.
+pub fn build_request(oid: &[u8], hash: &[u8], nonce: &[u8]) -> Vec<u8> {
+    let algorithm = der(
+        TAG_SEQUENCE,
+        &[der(TAG_OID, oid), der(TAG_NULL, &[])].concat(),
+    );
+    let imprint = der(
+        TAG_SEQUENCE,
+        &[algorithm, der(TAG_OCTET_STRING, hash)].concat(),
+    );
+
+    der(
+        TAG_SEQUENCE,
+        &[
+            der(TAG_INTEGER, &[1]),
+            imprint,
+            der(TAG_INTEGER, nonce),
+            der(TAG_BOOLEAN, &[0xff]),
+        ]
+        .concat(),
+    )
+}
+
+/// The DER `TimeStampToken` of a `TimeStampResp`, if the request was granted.
+pub fn parse_response(data: &[u8]) -> Result<Vec<u8>> {
+    let (response, _) = read(data, TAG_SEQUENCE).context("Malformed timestamp response")?;
+    let (status_info, rest) = read(response, TAG_SEQUENCE)?;
+    let (status, _) = read(status_info, TAG_INTEGER)?;
+
+    // 0 is granted, 1 granted with modifications
+    if !matches!(strip_leading_zeros(status), [] | [1]) {
+        anyhow::bail!(
+            "Timestamp authority rejected the request with status {}",
+            hex::encode(status)
+        );
+    }
+
+    let token = read_expected(rest, TAG_SEQUENCE).context("Timestamp response has no token")?;
+    Ok(token.encoded.to_vec())
+}
+
+pub fn parse_token(token: &[u8]) -> Result<TstInfo> {
+    parse_tst_info(token).context("Malformed timestamp token")
+}
+
+fn parse_tst_info(token: &[u8]) -> Result<TstInfo> {
+    let (content_info, _) = read(token, TAG_SEQUENCE)?;
+    let (content_type, rest) = read(content_info, TAG_OID)?;
+    if content_type != OID_SIGNED_DATA {
+        anyhow::bail!("Token is not CMS signed data");
+    }
+    let (signed_data, _) = read(rest, TAG_CONTEXT_0)?;
+    let (signed_data, _) = read(signed_data, TAG_SEQUENCE)?;
+    let (_, rest) = read(signed_data, TAG_INTEGER)?;
+    let (_, rest) = read(rest, TAG_SET)?;
+    let (encap_content_info, _) = read(rest, TAG_SEQUENCE)?;
+    let (content_type, rest) = read(encap_content_info, TAG_OID)?;
+    if content_type != OID_TST_INFO {
+        anyhow::bail!("Token does not contain a TSTInfo");
+    }
+    let (content, _) = read(rest, TAG_CONTEXT_0)?;
+    let (tst_info, _) = read(content, TAG_OCTET_STRING)?;
+
+    let (tst_info, _) = read(tst_info, TAG_SEQUENCE)?;
+    let (_, rest) = read(tst_info, TAG_INTEGER)?;
+    let (_, rest) = read(rest, TAG_OID)?;
+    let (imprint, rest) = read(rest, TAG_SEQUENCE)?;
+    let (algorithm, imprint_rest) = read(imprint, TAG_SEQUENCE)?;
+    let (algorithm, _) = read(algorithm, TAG_OID)?;
+    let algorithm = match algorithm {
+        OID_SHA256 => HashAlgorithm::Sha256,
+        OID_SHA512 => HashAlgorithm::Sha512,
+        _ => anyhow::bail!("Token uses an unsupported digest algorithm"),
+    };
+    let (hashed_message, _) = read(imprint_rest, TAG_OCTET_STRING)?;
+    let (serial_number, rest) = read(rest, TAG_INTEGER)?;
+    let (gen_time, mut rest) = read(rest, TAG_GENERALIZED_TIME)?;
+
+    // accuracy and ordering are optional and may come before the nonce
+    let mut nonce = None;
+    while let Ok(element) = read_element(rest) {
+        if element.tag == TAG_INTEGER {
+            nonce = Some(element.content.to_vec());
+            break;
+        }
+        rest = element.rest;
+    }
+
+    Ok(TstInfo {
+        algorithm,
+        hashed_message: hashed_message.to_vec(),
+        serial_number: hex::encode(strip_leading_zeros(serial_number)),
+        gen_time: parse_generalized_time(gen_time)?,
+        nonce,
+    })
+}
+
+/// `YYYYMMDDHHMMSS[.fff]Z`, the only form RFC 3161 allows.
+fn parse_generalized_time(value: &[u8]) -> Result<DateTime<Utc>> {
+    let value = std::str::from_utf8(value)?
+        .strip_suffix('Z')
+        .context("Timestamp time is not in UTC")?;
+    let (seconds, fraction) = value.split_once('.').unwrap_or((value, ""));
+
+    let mut time = NaiveDateTime::parse_from_str(seconds, "%Y%m%d%H%M%S")
+        .context("Malformed timestamp time")?
+        .and_utc();
+    if !fraction.is_empty() {
+        let nanos: u32 = format!("{:0<9}", fraction)
+            .get(..9)
+            .and_then(|n| n.parse().ok())
+            .context("Malformed timestamp time")?;
+        time += Duration::nanoseconds(nanos.into());
+    }
+
+    Ok(time)
+}
+
+fn der(tag: u8, content: &[u8]) -> Vec<u8> {
+    let mut encoded = vec![tag];
+    let length = content.len();
+    if length < 0x80 {
+        encoded.push(length as u8);
+    } else {
+        let bytes = length.to_be_bytes();
+        let bytes = strip_leading_zeros(&bytes);
+        encoded.push(0x80 | bytes.len() as u8);
+        encoded.extend_from_slice(bytes);
+    }
+    encoded.extend_from_slice(content);
+    encoded
+}
+
+/// One DER element and what follows it.
+struct Element<'a> {
+    tag: u8,
+    content: &'a [u8],
+    /// The whole element, header included.
+    encoded: &'a [u8],
+    rest: &'a [u8],
+}
+
+fn read_element(data: &[u8]) -> Result<Element<'_>> {
+    let (&tag, rest) = data.split_first().context("Unexpected end of data")?;
+    let (&first, rest) = rest.split_first().context("Unexpected end of data")?;
+
+    let (length, rest) = if first < 0x80 {
+        (first as usize, rest)
+    } else {
+        let count = (first & 0x7f) as usize;
+        if count == 0 || count > 4 || rest.len() < count {
+            anyhow::bail!("Unsupported length encoding");
+        }
+        let length = rest[..count]
+            .iter()
+            .fold(0usize, |length, &b| (length << 8) | b as usize);
+        (length, &rest[count..])
+    };
+
+    if rest.len() < length {
+        anyhow::bail!("Unexpected end of data");
+    }
+    let header = data.len() - rest.len();
+    Ok(Element {
+        tag,
+        content: &rest[..length],
+        encoded: &data[..header + length],
+        rest: &rest[length..],
+    })
+}
+
+fn read_expected(data: &[u8], expected: u8) -> Result<Element<'_>> {
+    let element = read_element(data)?;
+    if element.tag != expected {
+        anyhow::bail!("Expected tag {:#04x}, found {:#04x}", expected, element.tag);
+    }
+    Ok(element)
+}
+
+/// Reads an element with tag `expected`, returning its contents and what
+/// follows it.
+fn read(data: &[u8], expected: u8) -> Result<(&[u8], &[u8])> {
+    let element = read_expected(data, expected)?;
+    Ok((element.content, element.rest))
+}
+
+#[cfg(feature = "seal")]
+mod signature {
+    use openssl::cms::{CMSOptions, CmsContentInfo};
+    use openssl::error::ErrorStack;
+    use openssl::x509::store::X509StoreBuilder;
+    use openssl::x509::{X509PurposeId, X509};
+
+    pub fn load_trust_anchors(path: &str) -> anyhow::Result<Vec<X509>> {
+        Ok(X509::stack_from_pem(&std::fs::read(path)?)?)
+    }
+
+    /// Whether the authority's signature over `token` is valid and, when
+    /// trust anchors are given, whether its certificate chains to them.
+    pub fn verify(
+        token: &[u8],
+        trust_anchors: Option<&[X509]>,
+        errors: &mut Vec<String>,
+    ) -> (bool, Option<bool>) {
+        let mut cms = match CmsContentInfo::from_der(token) {
+            Ok(cms) => cms,
+            Err(e) => {
+                errors.push(format!("Failed to read token signature: {}", e));
+                return (false, trust_anchors.map(|_| false));
+            }
+        };
+
+        let signature_valid = cms
+            .verify(None, None, None, None, CMSOptions::NOVERIFY)
+            .map_err(|e| errors.push(format!("Token signature is invalid: {}", e)))
+            .is_ok();
+
+        let chain_trusted = trust_anchors.map(|anchors| {
+            verify_chain(&mut cms, anchors)
+                .map_err(|e| errors.push(format!("Token certificate is not trusted: {}", e)))
+                .is_ok()
+        });
+
+        (signature_valid, chain_trusted)
+    }
+
+    fn verify_chain(cms: &mut CmsContentInfo, anchors: &[X509]) -> Result<(), ErrorStack> {
+        let mut store = X509StoreBuilder::new()?;
+        for anchor in anchors {
+            store.add_cert(anchor.clone())?;
+        }
+        store.set_purpose(X509PurposeId::TIMESTAMP_SIGN)?;
+        let store = store.build();
+        cms.verify(None, Some(&store), None, None, CMSOptions::empty())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RESPONSE: &[u8] = include_bytes!("../../tests/fixtures/timestamp.tsr");
+    /// SHA-256 of "signvault", the hash the fixture response attests.
+    const HASH: &str = "290f37e0d13a0675258898ad4892b8b59386a7057e21375b6c78eccd4c324edf";
+
+    fn stored(token: Vec<u8>, info: &TstInfo, hashed_value: &str) -> DocumentTimestamp {
+        DocumentTimestamp {
+            id: Uuid::new_v4(),
+            document_id: Uuid::new_v4(),
+            subject: TimestampSubject::Document,
+            hashed_value: hashed_value.to_string(),
+            tsa_url: "http://tsa.example.com".to_string(),
+            token: Some(token),
+            gen_time: Some(info.gen_time),
+            serial_number: Some(info.serial_number.clone()),
+            attempts: 1,
+            last_error: None,
+            next_attempt_at: None,
+            created_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_build_request() {
+        let hash = hex::decode(HASH).unwrap();
+        let request = build_request(OID_SHA256, &hash, &[0x40, 1, 2, 3, 4, 5, 6, 7]);
+
+        let (body, rest) = read(&request, TAG_SEQUENCE).unwrap();
+        assert!(rest.is_empty());
+        let (version, rest) = read(body, TAG_INTEGER).unwrap();
+        assert_eq!(version, [1]);
+        let (imprint, rest) = read(rest, TAG_SEQUENCE).unwrap();
+        let (_, imprint) = read(imprint, TAG_SEQUENCE).unwrap();
+        assert_eq!(read(imprint, TAG_OCTET_STRING).unwrap().0, &hash[..]);
+        let (nonce, rest) = read(rest, TAG_INTEGER).unwrap();
+        assert_eq!(nonce, [0x40, 1, 2, 3, 4, 5, 6, 7]);
+        assert_eq!(read(rest, TAG_BOOLEAN).unwrap().0, [0xff]);
+    }
+
+    #[test]
+    fn test_generate_nonce_is_positive_and_minimal() {
+        for _ in 0..100 {
+            let nonce = generate_nonce();
+            assert_eq!(nonce.len(), 8);
+            assert!(nonce[0] >= 0x40 && nonce[0] < 0x80);
+        }
+    }
+
+    #[test]
+    fn test_parse_response() {
+        let token = parse_response(RESPONSE).unwrap();
+        let info = parse_token(&token).unwrap();
+
+        assert_eq!(hex::encode(&info.hashed_message), HASH);
+        assert!(info.attests(HASH));
+        assert!(info.attests(&format!("sha256:{}", HASH)));
+        assert!(!info.attests(&format!("sha512:{}", HASH)));
+        assert_eq!(info.serial_number, "02");
+        assert_eq!(
+            info.nonce.as_deref(),
+            Some(&[0x1c, 0xd8, 0x7b, 0x13, 0x59, 0xdc, 0x5e, 0x1e][..])
+        );
+
+        assert!(parse_response(&RESPONSE[..RESPONSE.len() - 10]).is_err());
+        // status 2: rejection
+        assert!(parse_response(&[0x30, 0x05, 0x30, 0x03, 0x02, 0x01, 0x02]).is_err());
+    }
+
+    #[test]
+    fn test_parse_generalized_time() {
+        assert_eq!(
+            parse_generalized_time(b"20240102030405Z").unwrap(),
+            "2024-01-02T03:04:05Z".parse::<DateTime<Utc>>().unwrap()
+        );
+        assert_eq!(
+            parse_generalized_time(b"20240102030405.25Z").unwrap(),
+            "2024-01-02T03:04:05.25Z".parse::<DateTime<Utc>>().unwrap()
+        );
+        assert!(parse_generalized_time(b"20240102030405").is_err());
+    }
+
+    #[cfg(feature = "seal")]
+    #[tokio::test]
+    async fn test_verify_token() {
+        let timestamps = Timestamps {
+            pool: sqlx::postgres::PgPoolOptions::new()
+                .connect_lazy("postgres://localhost/unused")
+                .unwrap(),
+            hash_algorithm: HashAlgorithm::Sha256,
+            client: reqwest::Client::new(),
+            tsa_url: None,
+            trust_anchors: Some(
+                signature::load_trust_anchors(concat!(
+                    env!("CARGO_MANIFEST_DIR"),
+                    "/tests/fixtures/tsa-ca.pem"
+                ))
+                .unwrap(),
+            ),
+        };
+
+        let token = parse_response(RESPONSE).unwrap();
+        let info = parse_token(&token).unwrap();
+
+        let verification = timestamps.verify(&stored(token.clone(), &info, HASH));
+        assert!(verification.valid, "{:?}", verification.errors);
+        assert_eq!(verification.signature_valid, Some(true));
+        assert_eq!(verification.chain_trusted, Some(true));
+
+        let verification = timestamps.verify(&stored(token.clone(), &info, &"0".repeat(64)));
+        assert!(!verification.valid);
+        assert!(!verification.imprint_matches);
+        assert_eq!(verification.signature_valid, Some(true));
+
+        let mut tampered = token;
+        let at = tampered.len() - 40;
+        tampered[at] ^= 1;
+        let verification = timestamps.verify(&stored(tampered, &info, HASH));
+        assert!(!verification.valid);
+        assert_eq!(verification.signature_valid, Some(false));
+    }
+}
@@ -0,0 +1,291 @@
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Nonce};
+use anyhow::{anyhow, Context, Result};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use hmac::{Hmac, Mac};
+use sha1::Sha1;
+
+use crate::services::crypto;
+
+/// Seconds each code is valid for.
+pub const STEP_SECONDS: i64 = 30;
+/// Steps either side of the current one whose codes are still accepted, for
+/// authenticator clocks that drift.
+pub const ALLOWED_DRIFT_STEPS: i64 = 1;
+pub const RECOVERY_CODE_COUNT: usize = 10;
+
+const DIGITS: u32 = 6;
+const SECRET_BYTES: usize = 20;
+const NONCE_BYTES: usize = 12;
+const BASE32_ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+pub fn generate_secret() -> Result<Vec<u8>> {
+    let mut secret = vec![0u8; SECRET_BYTES];
+    getrandom::getrandom(&mut secret).context("Failed to generate TOTP secret")?;
+    Ok(secret)
+}
+
+/// RFC 4648 base32 without padding, the form authenticator apps expect.
+pub fn base32_encode(data: &[u8]) -> String {
+    let mut out = String::with_capacity((data.len() * 8).div_ceil(5));
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+
+    for &byte in data {
+        buffer = (buffer << 8) | byte as u32;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            out.push(BASE32_ALPHABET[((buffer >> bits) & 0x1f) as usize] as char);
+        }
+    }
+    if bits > 0 {
+        out.push(BASE32_ALPHABET[((buffer << (5 - bits)) & 0x1f) as usize] as char);
+    }
+
+    out
+}
+
+/// The `otpauth://` URI authenticator apps import, usually from a QR code.
+pub fn provisioning_uri(secret: &[u8], issuer: &str, account: &str) -> String {
+    format!(
+        "otpauth://totp/{}:{}?secret={}&issuer={}&algorithm=SHA1&digits={}&period={}",
+        percent_encode(issuer),
+        percent_encode(account),
+        base32_encode(secret),
+        percent_encode(issuer),
+        DIGITS,
+        STEP_SECONDS
+    )
+}
+
+fn percent_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'@' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/// The time step a Unix timestamp falls in.
+pub fn step_at(unix_seconds: i64) -> i64 {
+    unix_seconds.div_euclid(STEP_SECONDS)
+}
+
+/// The HOTP code (RFC 4226) for a time step.
+pub fn code_at(secret: &[u8], step: i64) -> String {
+    let mut mac = <Hmac<Sha1> as Mac>::new_from_slice(secret).expect("HMAC accepts any key length");
+    mac.update(&(step as u64).to_be_bytes());
+    let digest = mac.finalize().into_bytes();
+
+    let offset = (digest[digest.len() - 1] & 0x0f) as usize;
+    let value = u32::from_be_bytes([
+        digest[offset] & 0x7f,
+        digest[offset + 1],
+        digest[offset + 2],
+        digest[offset + 3],
+    ]);
+
+    format!(
+        "{:0width$}",
+        value % 10u32.pow(DIGITS),
+        width = DIGITS as usize
+    )
+}
+
+/// Checks `code` against the steps around `now`. Returns the step it matched,
+/// which must be later than `last_used_step` so a code is only accepted once.
+pub fn verify_code(
+    secret: &[u8],
+    code: &str,
+    unix_seconds: i64,
+    last_used_step: Option<i64>,
+) -> Option<i64> {
+    let code: String = code.chars().filter(|c| !c.is_whitespace()).collect();
+    if code.len() != DIGITS as usize || !code.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+
+    let current = step_at(unix_seconds);
+    (current - ALLOWED_DRIFT_STEPS..=current + ALLOWED_DRIFT_STEPS)
+        .filter(|step| last_used_step < Some(*step))
+        .find(|step| constant_time_eq(code_at(secret, *step).as_bytes(), code.as_bytes()))
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// Single-use codes for when the authenticator is lost, as `xxxxx-xxxxx`.
+pub fn generate_recovery_codes() -> Result<Vec<String>> {
+    (0..RECOVERY_CODE_COUNT)
+        .map(|_| {
+            let mut bytes = [0u8; 7];
+            getrandom::getrandom(&mut bytes).context("Failed to generate recovery code")?;
+            let code = base32_encode(&bytes).to_lowercase();
+            Ok(format!("{}-{}", &code[..5], &code[5..10]))
+        })
+        .collect()
+}
+
+/// Lookup hash of a recovery code, ignoring case, spaces and dashes.
+pub fn hash_recovery_code(code: &str) -> String {
+    let normalized: String = code
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_lowercase())
+        .collect();
+    crypto::hash_token(&normalized)
+}
+
+/// Encrypts TOTP secrets at rest with AES-256-GCM.
+#[derive(Clone)]
+pub struct SecretCipher {
+    cipher: Aes256Gcm,
+}
+
+impl SecretCipher {
+    pub fn new(key: &[u8; 32]) -> Self {
+        Self {
+            cipher: Aes256Gcm::new(key.into()),
+        }
+    }
+
+    /// Base64 of a random nonce followed by the ciphertext.
+    pub fn encrypt(&self, secret: &[u8]) -> Result<String> {
+        let mut nonce = [0u8; NONCE_BYTES];
+        getrandom::getrandom(&mut nonce).context("Failed to generate nonce")?;
+
+        let ciphertext = self
+            .cipher
+            .encrypt(Nonce::from_slice(&nonce), secret)
+            .map_err(|_| anyhow!("Failed to encrypt TOTP secret"))?;
+
+        let mut out = nonce.to_vec();
+        out.extend_from_slice(&ciphertext);
+        Ok(BASE64.encode(out))
+    }
+
+    pub fn decrypt(&self, encrypted: &str) -> Result<Vec<u8>> {
+        let data = BASE64
+            .decode(encrypted)
+            .context("Encrypted TOTP secret is not base64")?;
+        if data.len() <= NONCE_BYTES {
+            return Err(anyhow!("Encrypted TOTP secret is too short"));
+        }
+
+        let (nonce, ciphertext) = data.split_at(NONCE_BYTES);
+        self.cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| anyhow!("Failed to decrypt TOTP secret; was TOTP_ENCRYPTION_KEY changed?"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The SHA-1 secret of the RFC 6238 test vectors
+    const RFC_SECRET: &[u8] = b"12345678901234567890";
+
+    #[test]
+    fn test_rfc6238_vectors() {
+        for (time, code) in [
+            (59, "287082"),
+            (1_111_111_109, "081804"),
+            (1_111_111_111, "050471"),
+            (1_234_567_890, "005924"),
+            (2_000_000_000, "279037"),
+        ] {
+            assert_eq!(code_at(RFC_SECRET, step_at(time)), code, "at {}", time);
+        }
+    }
+
+    #[test]
+    fn test_drift_of_one_step_is_tolerated() {
+        let now = 1_234_567_890;
+        let step = step_at(now);
+
+        for offset in [-1, 0, 1] {
+            let code = code_at(RFC_SECRET, step + offset);
+            assert_eq!(
+                verify_code(RFC_SECRET, &code, now, None),
+                Some(step + offset)
+            );
+        }
+        for offset in [-2, 2] {
+            let code = code_at(RFC_SECRET, step + offset);
+            assert_eq!(verify_code(RFC_SECRET, &code, now, None), None);
+        }
+    }
+
+    #[test]
+    fn test_codes_are_single_use() {
+        let now = 1_234_567_890;
+        let step = step_at(now);
+        let code = code_at(RFC_SECRET, step);
+
+        assert_eq!(
+            verify_code(RFC_SECRET, &code, now, Some(step - 1)),
+            Some(step)
+        );
+        assert_eq!(verify_code(RFC_SECRET, &code, now, Some(step)), None);
+        // An older code is refused once a newer one was used
+        let previous = code_at(RFC_SECRET, step - 1);
+        assert_eq!(verify_code(RFC_SECRET, &previous, now, Some(step)), None);
+    }
+
+    #[test]
+    fn test_malformed_codes_are_rejected() {
+        assert_eq!(verify_code(RFC_SECRET, "12345", 59, None), None);
+        assert_eq!(verify_code(RFC_SECRET, "28708a", 59, None), None);
+        assert_eq!(verify_code(RFC_SECRET, "287 082", 59, None), Some(1));
+    }
+
+    #[test]
+    fn test_base32_encode() {
+        assert_eq!(base32_encode(b""), "");
+        assert_eq!(base32_encode(b"f"), "MY");
+        assert_eq!(base32_encode(b"foobar"), "MZXW6YTBOI");
+        assert_eq!(
+            base32_encode(RFC_SECRET),
+            "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ"
+        );
+    }
+
+    #[test]
+    fn test_provisioning_uri() {
+        assert_eq!(
+            provisioning_uri(RFC_SECRET, "Sign Vault", "jane@example.com"),
+            "otpauth://totp/Sign%20Vault:jane@example.com?secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ&issuer=Sign%20Vault&algorithm=SHA1&digits=6&period=30"
+        );
+    }
+
+    #[test]
+    fn test_recovery_codes() {
+        let codes = generate_recovery_codes().unwrap();
+        assert_eq!(codes.len(), RECOVERY_CODE_COUNT);
+        assert!(codes
+            .iter()
+            .all(|code| code.len() == 11 && code.as_bytes()[5] == b'-'));
+
+        assert_eq!(
+            hash_recovery_code(&codes[0]),
+            hash_recovery_code(&format!(" {} ", codes[0].to_uppercase().replace('-', "")))
+        );
+    }
+
+    #[test]
+    fn test_secret_cipher_round_trip() {
+        let cipher = SecretCipher::new(&[7u8; 32]);
+        let encrypted = cipher.encrypt(RFC_SECRET).unwrap();
+        assert_ne!(cipher.encrypt(RFC_SECRET).unwrap(), encrypted);
+        assert_eq!(cipher.decrypt(&encrypted).unwrap(), RFC_SECRET);
+
+        assert!(SecretCipher::new(&[8u8; 32]).decrypt(&encrypted).is_err());
+    }
+}
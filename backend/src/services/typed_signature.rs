@@ -0,0 +1,208 @@
+use ab_glyph::{point, Font, FontArc, Glyph, PxScale, ScaleFont};
+use anyhow::Result;
+use base64::Engine;
+use std::collections::HashMap;
+use std::path::Path;
+use tracing::{info, warn};
+
+use crate::services::signature_image;
+
+/// Fonts a signer may choose for a typed signature, keyed by the identifier
+/// clients send, mapped to the font file expected in the fonts directory.
+pub const SIGNATURE_FONTS: &[(&str, &str)] = &[
+    ("dancing-script", "DancingScript-Regular.ttf"),
+    ("great-vibes", "GreatVibes-Regular.ttf"),
+    ("homemade-apple", "HomemadeApple-Regular.ttf"),
+    ("sacramento", "Sacramento-Regular.ttf"),
+];
+
+pub const MAX_TYPED_TEXT_CHARS: usize = 100;
+
+const RENDER_SIZE_PX: f32 = 72.0;
+const PADDING_PX: f32 = 8.0;
+const INK_RGB: [u8; 3] = [0x11, 0x18, 0x27];
+
+pub struct TypedSignatureRenderer {
+    fonts: HashMap<&'static str, FontArc>,
+}
+
+impl TypedSignatureRenderer {
+    pub fn load(fonts_dir: &Path) -> Self {
+        let mut fonts = HashMap::new();
+
+        for (key, file_name) in SIGNATURE_FONTS {
+            let path = fonts_dir.join(file_name);
+            let loaded = std::fs::read(&path)
+                .map_err(anyhow::Error::from)
+                .and_then(|data| FontArc::try_from_vec(data).map_err(anyhow::Error::from));
+
+            match loaded {
+                Ok(font) => {
+                    fonts.insert(*key, font);
+                }
+                Err(e) => warn!(
+                    "Signature font {} unavailable ({}): {}",
+                    key,
+                    path.display(),
+                    e
+                ),
+            }
+        }
+
+        info!("Loaded {} signature font(s)", fonts.len());
+
+        Self { fonts }
+    }
+
+    pub fn available_fonts(&self) -> Vec<&'static str> {
+        SIGNATURE_FONTS
+            .iter()
+            .map(|(key, _)| *key)
+            .filter(|key| self.fonts.contains_key(key))
+            .collect()
+    }
+
+    /// Renders `text` in the chosen font and returns it as a PNG data URL.
+    pub fn render(&self, text: &str, font_key: &str) -> Result<String> {
+        if !SIGNATURE_FONTS.iter().any(|(key, _)| *key == font_key) {
+            return Err(anyhow::anyhow!("Unknown signature font: {}", font_key));
+        }
+
+        let font = self
+            .fonts
+            .get(font_key)
+            .ok_or_else(|| anyhow::anyhow!("Signature font is not available: {}", font_key))?;
+
+        let text = validate_typed_text(text)?;
+        let png = render_png(font, text)?;
+
+        Ok(format!(
+            "data:image/png;base64,{}",
+            base64::engine::general_purpose::STANDARD.encode(png)
+        ))
+    }
+}
+
+pub fn validate_typed_text(text: &str) -> Result<&str> {
+    let text = text.trim();
+
+    if text.is_empty() {
+        return Err(anyhow::anyhow!("Typed signature text is required"));
+    }
+
+    if text.chars().count() > MAX_TYPED_TEXT_CHARS {
+        return Err(anyhow::anyhow!(
+            "Typed signature text must be at most {} characters",
+            MAX_TYPED_TEXT_CHARS
+        ));
+    }
+
+    if text.chars().any(char::is_control) {
+        return Err(anyhow::anyhow!(
+            "Typed signature text contains invalid characters"
+        ));
+    }
+
+    Ok(text)
+}
+
+fn render_png(font: &FontArc, text: &str) -> Result<Vec<u8>> {
+    let scale = PxScale::from(RENDER_SIZE_PX);
+    let scaled = font.as_scaled(scale);
+
+    let mut glyphs: Vec<Glyph> = Vec::new();
+    let mut caret = 0.0;
+    let mut previous = None;
+
+    for c in text.chars() {
+        let id = scaled.glyph_id(c);
+        if let Some(prev) = previous {
+            caret += scaled.kern(prev, id);
+        }
+        glyphs.push(id.with_scale_and_position(scale, point(caret, scaled.ascent())));
+        caret += scaled.h_advance(id);
+        previous = Some(id);
+    }
+
+    let outlines: Vec<_> = glyphs
+        .into_iter()
+        .filter_map(|g| font.outline_glyph(g))
+        .collect();
+
+    let (min_x, min_y, max_x, max_y) = outlines.iter().map(|o| o.px_bounds()).fold(
+        (f32::MAX, f32::MAX, f32::MIN, f32::MIN),
+        |(x0, y0, x1, y1), b| {
+            (
+                x0.min(b.min.x),
+                y0.min(b.min.y),
+                x1.max(b.max.x),
+                y1.max(b.max.y),
+            )
+        },
+    );
+
+    if outlines.is_empty() {
+        return Err(anyhow::anyhow!("Typed signature has no visible characters"));
+    }
+
+    let width = (max_x - min_x + 2.0 * PADDING_PX).ceil() as u32;
+    let height = (max_y - min_y + 2.0 * PADDING_PX).ceil() as u32;
+    let mut coverage = vec![0u8; (width * height) as usize];
+
+    for outline in &outlines {
+        let bounds = outline.px_bounds();
+        let offset_x = bounds.min.x - min_x + PADDING_PX;
+        let offset_y = bounds.min.y - min_y + PADDING_PX;
+
+        outline.draw(|x, y, c| {
+            let px = x as f32 + offset_x;
+            let py = y as f32 + offset_y;
+            if px < 0.0 || py < 0.0 || px >= width as f32 || py >= height as f32 {
+                return;
+            }
+            let idx = py as usize * width as usize + px as usize;
+            let value = coverage[idx] as f32 + c * 255.0;
+            coverage[idx] = value.min(255.0) as u8;
+        });
+    }
+
+    encode_png(width, height, &coverage)
+}
+
+/// Encodes an alpha coverage mask as an RGBA PNG inked in a fixed pen colour.
+fn encode_png(width: u32, height: u32, coverage: &[u8]) -> Result<Vec<u8>> {
+    let rgba: Vec<u8> = coverage
+        .iter()
+        .flat_map(|alpha| [INK_RGB[0], INK_RGB[1], INK_RGB[2], *alpha])
+        .collect();
+
+    signature_image::encode_rgba_png(width, height, &rgba)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_typed_text() {
+        assert_eq!(validate_typed_text("  Jane Doe ").unwrap(), "Jane Doe");
+        assert!(validate_typed_text("   ").is_err());
+        assert!(validate_typed_text("Jane\u{0}Doe").is_err());
+        assert!(validate_typed_text(&"a".repeat(MAX_TYPED_TEXT_CHARS + 1)).is_err());
+    }
+
+    #[test]
+    fn test_unknown_font_is_rejected() {
+        let renderer = TypedSignatureRenderer {
+            fonts: HashMap::new(),
+        };
+        let err = renderer.render("Jane Doe", "comic-sans").unwrap_err();
+        assert!(err.to_string().contains("Unknown signature font"));
+    }
+
+    #[test]
+    fn test_encode_png_produces_png() {
+        let png = encode_png(2, 1, &[0, 255]).unwrap();
+        assert!(png.starts_with(b"\x89PNG\r\n\x1a\n"));
+    }
+}
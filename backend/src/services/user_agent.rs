@@ -0,0 +1,115 @@
+/// Maximum length of a raw user agent used as a fallback description.
+pub const MAX_RAW_USER_AGENT_CHARS: usize = 120;
+
+/// Browsers in match order; more specific tokens must come first since most
+/// browsers also claim to be Chrome and/or Safari.
+const BROWSERS: &[(&str, &str)] = &[
+    ("Edg/", "Edge"),
+    ("EdgA/", "Edge"),
+    ("EdgiOS/", "Edge"),
+    ("OPR/", "Opera"),
+    ("SamsungBrowser/", "Samsung Internet"),
+    ("FxiOS/", "Firefox"),
+    ("Firefox/", "Firefox"),
+    ("CriOS/", "Chrome"),
+    ("Chrome/", "Chrome"),
+    ("Version/", "Safari"),
+];
+
+/// Describes a user agent string as e.g. "Safari on iOS (iPhone)". Agents
+/// that cannot be recognised fall back to the raw string, truncated.
+pub fn describe(user_agent: &str) -> String {
+    let user_agent = user_agent.trim();
+    let browser = browser(user_agent);
+    let (os, device) = platform(user_agent);
+
+    let description = match (browser, os) {
+        (Some(browser), Some(os)) => format!("{} on {}", browser, os),
+        (Some(browser), None) => browser.to_string(),
+        (None, Some(os)) => format!("Unknown browser on {}", os),
+        (None, None) => return truncate(user_agent),
+    };
+
+    match device {
+        Some(device) => format!("{} ({})", description, device),
+        None => description,
+    }
+}
+
+fn browser(user_agent: &str) -> Option<&'static str> {
+    BROWSERS
+        .iter()
+        .find(|(token, name)| {
+            user_agent.contains(token) && (*name != "Safari" || user_agent.contains("Safari/"))
+        })
+        .map(|(_, name)| *name)
+}
+
+fn platform(user_agent: &str) -> (Option<&'static str>, Option<&'static str>) {
+    if user_agent.contains("iPhone") {
+        (Some("iOS"), Some("iPhone"))
+    } else if user_agent.contains("iPad") {
+        (Some("iPadOS"), Some("iPad"))
+    } else if user_agent.contains("Android") {
+        let device = if user_agent.contains("Mobile") {
+            "phone"
+        } else {
+            "tablet"
+        };
+        (Some("Android"), Some(device))
+    } else if user_agent.contains("Windows") {
+        (Some("Windows"), None)
+    } else if user_agent.contains("CrOS") {
+        (Some("ChromeOS"), None)
+    } else if user_agent.contains("Macintosh") || user_agent.contains("Mac OS X") {
+        (Some("macOS"), None)
+    } else if user_agent.contains("Linux") {
+        (Some("Linux"), None)
+    } else {
+        (None, None)
+    }
+}
+
+fn truncate(user_agent: &str) -> String {
+    if user_agent.chars().count() <= MAX_RAW_USER_AGENT_CHARS {
+        return user_agent.to_string();
+    }
+
+    let truncated: String = user_agent.chars().take(MAX_RAW_USER_AGENT_CHARS).collect();
+    format!("{}...", truncated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_describe_common_browsers() {
+        assert_eq!(
+            describe("Mozilla/5.0 (iPhone; CPU iPhone OS 17_4 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.4 Mobile/15E148 Safari/604.1"),
+            "Safari on iOS (iPhone)"
+        );
+        assert_eq!(
+            describe("Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/124.0.0.0 Safari/537.36 Edg/124.0.0.0"),
+            "Edge on Windows"
+        );
+        assert_eq!(
+            describe("Mozilla/5.0 (Macintosh; Intel Mac OS X 14.4; rv:125.0) Gecko/20100101 Firefox/125.0"),
+            "Firefox on macOS"
+        );
+        assert_eq!(
+            describe("Mozilla/5.0 (Linux; Android 14; Pixel 8) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/124.0.0.0 Mobile Safari/537.36"),
+            "Chrome on Android (phone)"
+        );
+    }
+
+    #[test]
+    fn test_describe_unknown_agent_falls_back_to_raw() {
+        assert_eq!(describe("curl/8.5.0"), "curl/8.5.0");
+
+        let long = "x".repeat(MAX_RAW_USER_AGENT_CHARS + 10);
+        let described = describe(&long);
+        assert!(described.ends_with("..."));
+        assert_eq!(described.chars().count(), MAX_RAW_USER_AGENT_CHARS + 3);
+    }
+}
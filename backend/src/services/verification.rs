@@ -0,0 +1,272 @@
+use async_trait::async_trait;
+use chrono::{Duration, Utc};
+use serde::Serialize;
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::sync::Arc;
+use thiserror::Error;
+use tracing::warn;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::db;
+use crate::models::document::Document;
+use crate::models::signer::Signer;
+use crate::services::config::Config;
+use crate::services::crypto;
+use crate::services::email::EmailService;
+use crate::services::i18n;
+
+pub const EMAIL_OTP: &str = "email_otp";
+
+pub const OUTCOME_VERIFIED: &str = "verified";
+pub const OUTCOME_FAILED: &str = "failed";
+
+const CODE_RESEND_INTERVAL_SECS: i64 = 60;
+const MAX_CODES_PER_HOUR: i64 = 5;
+
+#[derive(Debug, Error)]
+pub enum VerificationError {
+    #[error("Too many failed verification attempts, please try again later")]
+    LockedOut,
+
+    #[error("Please wait before requesting another verification code")]
+    TooManyCodes,
+
+    #[error("No active verification code, please request a new one")]
+    NoActiveCode,
+
+    #[error("Invalid verification proof: {0}")]
+    InvalidProof(String),
+
+    #[error(transparent)]
+    Other(#[from] anyhow::Error),
+}
+
+/// What the signer needs to do next to complete verification, returned to the
+/// frontend as-is.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct VerificationChallenge {
+    pub method: &'static str,
+    pub details: serde_json::Value,
+}
+
+#[derive(Debug)]
+pub enum VerificationResult {
+    Verified,
+    Failed { reason: String },
+}
+
+/// A provider able to establish a signer's identity before they sign, e.g. by
+/// email one-time code, SMS or an external eID service.
+#[async_trait]
+pub trait IdentityVerifier: Send + Sync {
+    /// Identifier stored on documents and signers, e.g. `email_otp`.
+    fn method(&self) -> &'static str;
+
+    async fn start_verification(
+        &self,
+        signer: &Signer,
+    ) -> Result<VerificationChallenge, VerificationError>;
+
+    /// Checks the signer-submitted proof, whose shape is provider specific.
+    async fn check(
+        &self,
+        signer: &Signer,
+        proof: &serde_json::Value,
+    ) -> Result<VerificationResult, VerificationError>;
+}
+
+/// The identity verification providers enabled for this deployment.
+pub struct IdentityVerifiers {
+    verifiers: HashMap<&'static str, Arc<dyn IdentityVerifier>>,
+}
+
+impl IdentityVerifiers {
+    pub fn from_config(
+        pool: PgPool,
+        config: &Config,
+        email_service: Option<Arc<EmailService>>,
+    ) -> Self {
+        let mut verifiers: HashMap<&'static str, Arc<dyn IdentityVerifier>> = HashMap::new();
+
+        for provider in &config.verification_providers {
+            match provider.as_str() {
+                EMAIL_OTP => {
+                    verifiers.insert(
+                        EMAIL_OTP,
+                        Arc::new(EmailOtpVerifier {
+                            pool: pool.clone(),
+                            config: config.clone(),
+                            email_service: email_service.clone(),
+                        }),
+                    );
+                }
+                other => warn!("Unknown identity verification provider: {}", other),
+            }
+        }
+
+        Self { verifiers }
+    }
+
+    pub fn get(&self, method: &str) -> Option<Arc<dyn IdentityVerifier>> {
+        self.verifiers.get(method).cloned()
+    }
+}
+
+/// Whether the signer completed verification with the document's method
+/// recently enough to sign.
+pub fn is_verified(document: &Document, signer: &Signer, config: &Config) -> bool {
+    signer.verification_method == document.verification_method
+        && signer.verified_at.is_some_and(|verified_at| {
+            Utc::now() - verified_at < Duration::minutes(config.verified_session_minutes)
+        })
+}
+
+pub fn verification_required(document: &Document, signer: &Signer, config: &Config) -> bool {
+    document.verification_method.is_some() && !is_verified(document, signer, config)
+}
+
+/// Verifies signers with a one-time code sent to their email address.
+pub struct EmailOtpVerifier {
+    pool: PgPool,
+    config: Config,
+    email_service: Option<Arc<EmailService>>,
+}
+
+#[async_trait]
+impl IdentityVerifier for EmailOtpVerifier {
+    fn method(&self) -> &'static str {
+        EMAIL_OTP
+    }
+
+    async fn start_verification(
+        &self,
+        signer: &Signer,
+    ) -> Result<VerificationChallenge, VerificationError> {
+        let document = db::document::get_document_by_id(&self.pool, signer.document_id)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Document not found"))?;
+
+        let code = issue_code(&self.pool, &self.config, signer).await?;
+
+        if let Some(email_service) = &self.email_service {
+            email_service
+                .send_verification_code(
+                    &signer.email,
+                    &signer.name,
+                    i18n::signer_locale(signer, &document, self.config.default_locale),
+                    &document.title,
+                    &code,
+                    self.config.verification_code_minutes,
+                )
+                .await?;
+        } else {
+            warn!(
+                "Email service not configured, verification code for {} was not delivered",
+                signer.email
+            );
+        }
+
+        Ok(VerificationChallenge {
+            method: EMAIL_OTP,
+            details: serde_json::json!({
+                "expires_in_minutes": self.config.verification_code_minutes
+            }),
+        })
+    }
+
+    async fn check(
+        &self,
+        signer: &Signer,
+        proof: &serde_json::Value,
+    ) -> Result<VerificationResult, VerificationError> {
+        let code = proof["code"].as_str().ok_or_else(|| {
+            VerificationError::InvalidProof("a verification code is required".to_string())
+        })?;
+
+        verify_code(&self.pool, &self.config, signer, code).await
+    }
+}
+
+/// What is hashed for a code, binding it to the signer.
+fn code_preimage(signer_id: Uuid, code: &str) -> String {
+    format!("{}:{}", signer_id, code.trim())
+}
+
+async fn ensure_not_locked_out(
+    pool: &PgPool,
+    config: &Config,
+    signer_id: Uuid,
+) -> Result<(), VerificationError> {
+    let since = Utc::now() - Duration::minutes(config.verification_lockout_minutes);
+    let failures = db::signer::count_failed_verifications_since(pool, signer_id, since).await?;
+
+    if failures >= config.verification_max_attempts {
+        return Err(VerificationError::LockedOut);
+    }
+
+    Ok(())
+}
+
+/// Issues a new one-time code for the signer, returning the plaintext code to
+/// be delivered. Only a hash of the code is stored.
+async fn issue_code(
+    pool: &PgPool,
+    config: &Config,
+    signer: &Signer,
+) -> Result<String, VerificationError> {
+    ensure_not_locked_out(pool, config, signer.id).await?;
+
+    let now = Utc::now();
+
+    if let Some(latest) = db::signer::get_latest_verification_code(pool, signer.id).await? {
+        if now - latest.created_at < Duration::seconds(CODE_RESEND_INTERVAL_SECS) {
+            return Err(VerificationError::TooManyCodes);
+        }
+    }
+
+    let issued_last_hour =
+        db::signer::count_verification_codes_since(pool, signer.id, now - Duration::hours(1))
+            .await?;
+
+    if issued_last_hour >= MAX_CODES_PER_HOUR {
+        return Err(VerificationError::TooManyCodes);
+    }
+
+    let code = crypto::generate_verification_code();
+    let expires_at = now + Duration::minutes(config.verification_code_minutes);
+
+    let code_hash = crypto::hash_string(config.hash_algorithm, &code_preimage(signer.id, &code));
+    db::signer::create_verification_code(pool, signer.id, &code_hash, expires_at).await?;
+
+    Ok(code)
+}
+
+/// Checks a submitted code against the signer's latest active code. Failed
+/// attempts count towards a lockout.
+async fn verify_code(
+    pool: &PgPool,
+    config: &Config,
+    signer: &Signer,
+    code: &str,
+) -> Result<VerificationResult, VerificationError> {
+    ensure_not_locked_out(pool, config, signer.id).await?;
+
+    let active = db::signer::get_latest_verification_code(pool, signer.id)
+        .await?
+        .filter(|c| c.consumed_at.is_none() && c.expires_at > Utc::now())
+        .ok_or(VerificationError::NoActiveCode)?;
+
+    // Codes hashed before an algorithm switch still verify
+    if !crypto::verify_string(&active.code_hash, &code_preimage(signer.id, code)).unwrap_or(false) {
+        db::signer::record_failed_verification(pool, active.id).await?;
+        return Ok(VerificationResult::Failed {
+            reason: "Invalid verification code".to_string(),
+        });
+    }
+
+    db::signer::consume_verification_code(pool, active.id).await?;
+
+    Ok(VerificationResult::Verified)
+}
@@ -1 +1 @@
-// Utility module placeholder
+pub mod request_id;
@@ -0,0 +1,63 @@
+use std::future::Future;
+
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+tokio::task_local! {
+    static REQUEST_ID: String;
+}
+
+/// Runs `f` with `request_id` available to `current()` for its whole duration.
+pub async fn scope<F: Future>(request_id: String, f: F) -> F::Output {
+    REQUEST_ID.scope(request_id, f).await
+}
+
+/// The id of the request being handled, if called while handling one.
+pub fn current() -> Option<String> {
+    REQUEST_ID.try_with(|id| id.clone()).ok()
+}
+
+/// Accepts a client-supplied request id if it is short and made of safe
+/// characters, otherwise generates a new one.
+pub fn from_incoming(incoming: Option<&str>) -> String {
+    incoming
+        .map(str::trim)
+        .filter(|id| {
+            !id.is_empty()
+                && id.len() <= MAX_REQUEST_ID_LEN
+                && id
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | ':'))
+        })
+        .map(str::to_string)
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_incoming_honours_valid_ids() {
+        assert_eq!(from_incoming(Some("req-123_abc")), "req-123_abc");
+    }
+
+    #[test]
+    fn test_from_incoming_replaces_invalid_ids() {
+        for incoming in [None, Some(""), Some("bad id"), Some("<script>")] {
+            let id = from_incoming(incoming);
+            assert!(uuid::Uuid::parse_str(&id).is_ok());
+        }
+
+        let too_long = "a".repeat(MAX_REQUEST_ID_LEN + 1);
+        assert_ne!(from_incoming(Some(&too_long)), too_long);
+    }
+
+    #[tokio::test]
+    async fn test_current_is_scoped() {
+        assert_eq!(current(), None);
+        let inside = scope("abc".to_string(), async { current() }).await;
+        assert_eq!(inside.as_deref(), Some("abc"));
+    }
+}
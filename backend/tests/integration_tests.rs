@@ -87,6 +87,76 @@ async fn test_login_invalid_credentials() {
     assert_eq!(res.status(), 401);
 }
 
+#[tokio::test]
+async fn test_auth_events() {
+    wait_for_server().await;
+
+    let client = Client::new();
+    // Other tests log in too; their events are told apart by user agent.
+    let agent = format!("auth-events-test/{}", uuid::Uuid::new_v4());
+    let ghost = format!("ghost-{}@example.com", uuid::Uuid::new_v4().simple());
+
+    let attempt = |email: String, password: &'static str| {
+        client
+            .post(format!("{}/auth/login", BASE_URL))
+            .header("User-Agent", agent.as_str())
+            .json(&json!({ "email": email, "password": password }))
+            .send()
+    };
+
+    let res = attempt("admin@example.com".to_string(), "wrong-password")
+        .await
+        .expect("Request failed");
+    assert_eq!(res.status(), 401);
+    let res = attempt(ghost.clone(), "wrong-password")
+        .await
+        .expect("Request failed");
+    assert_eq!(res.status(), 401);
+    let res = attempt(
+        "admin@example.com".to_string(),
+        "change-this-secure-password",
+    )
+    .await
+    .expect("Request failed");
+    assert!(res.status().is_success());
+    let body: serde_json::Value = res.json().await.expect("Failed to parse login");
+    let token = body["token"].as_str().unwrap().to_string();
+
+    let res = client
+        .get(format!("{}/auth/events?limit=200", BASE_URL))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("List events failed");
+    assert!(res.status().is_success());
+    let events: Vec<serde_json::Value> = res.json().await.expect("Failed to parse events");
+    let mine: Vec<_> = events
+        .iter()
+        .filter(|event| event["user_agent"] == agent.as_str())
+        .collect();
+    assert_eq!(mine.len(), 2);
+    assert_eq!(mine[0]["event_type"], "login_succeeded");
+    assert!(mine[0]["token_expires_at"].is_string());
+    assert_eq!(mine[1]["event_type"], "login_failed");
+    assert!(mine[1].get("failure_reason").is_none());
+
+    let res = client
+        .get(format!("{}/admin/auth-events?limit=200", BASE_URL))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("List events failed");
+    assert!(res.status().is_success());
+    let events: Vec<serde_json::Value> = res.json().await.expect("Failed to parse events");
+    let unknown = events
+        .iter()
+        .find(|event| event["email"] == ghost.as_str())
+        .expect("Failed login not recorded");
+    assert_eq!(unknown["user_id"], serde_json::Value::Null);
+    assert_eq!(unknown["failure_reason"], "unknown_email");
+    assert_eq!(unknown["user_agent"], agent.as_str());
+}
+
 #[tokio::test]
 async fn test_get_current_user() {
     wait_for_server().await;
@@ -105,7 +175,7 @@ async fn test_get_current_user() {
 
     let body: serde_json::Value = res.json().await.expect("Failed to parse response");
     assert_eq!(body["email"], "admin@example.com");
-    assert_eq!(body["is_admin"], true);
+    assert_eq!(body["role"], "admin");
 }
 
 #[tokio::test]
@@ -478,7 +548,7 @@ async fn test_void_document() {
     let doc_id = doc["id"].as_str().expect("No document ID");
 
     // Add signer and send
-    client
+    let res = client
         .post(format!("{}/documents/{}/signers", BASE_URL, doc_id))
         .header("Authorization", format!("Bearer {}", token))
         .json(&json!({
@@ -488,6 +558,8 @@ async fn test_void_document() {
         .send()
         .await
         .expect("Add signer failed");
+    let signer: serde_json::Value = res.json().await.expect("Failed to parse signer");
+    let access_token = signer["access_token"].as_str().expect("No access token");
 
     client
         .post(format!("{}/documents/{}/send", BASE_URL, doc_id))
@@ -496,10 +568,21 @@ async fn test_void_document() {
         .await
         .expect("Send failed");
 
+    // A reason is required
+    let res = client
+        .post(format!("{}/documents/{}/void", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({ "reason": "  " }))
+        .send()
+        .await
+        .expect("Void failed");
+    assert_eq!(res.status(), 400);
+
     // Void the document
     let res = client
         .post(format!("{}/documents/{}/void", BASE_URL, doc_id))
         .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({ "reason": "Wrong contract amount" }))
         .send()
         .await
         .expect("Void failed");
@@ -507,49 +590,76 @@ async fn test_void_document() {
     assert!(res.status().is_success(), "Void failed: {:?}", res.status());
     let voided_doc: serde_json::Value = res.json().await.expect("Failed to parse response");
     assert_eq!(voided_doc["status"], "voided");
+    assert_eq!(voided_doc["void_reason"], "Wrong contract amount");
+
+    let res = client
+        .post(format!("{}/documents/{}/void", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({ "reason": "Again" }))
+        .send()
+        .await
+        .expect("Void failed");
+    assert_eq!(res.status(), 409);
+
+    // The signer is told why their link stopped working
+    let res = client
+        .get(format!("{}/sign/{}", BASE_URL, access_token))
+        .send()
+        .await
+        .expect("Get signing session failed");
+    assert_eq!(res.status(), 400);
+    let body: serde_json::Value = res.json().await.expect("Failed to parse error");
+    assert_eq!(
+        body["message"],
+        "This document has been voided: Wrong contract amount"
+    );
+
+    let res = client
+        .get(format!("{}/documents/{}/audit", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Get audit failed");
+    let logs: Vec<serde_json::Value> = res.json().await.expect("Failed to parse audit");
+    let voided = logs
+        .iter()
+        .find(|log| log["action"] == "document_voided")
+        .expect("No void audit entry");
+    assert_eq!(voided["details"]["reason"], "Wrong contract amount");
 }
 
 #[tokio::test]
-async fn test_field_operations() {
+async fn test_status_change_leaves_other_documents_untouched() {
     wait_for_server().await;
 
     let client = Client::new();
     let token = login(&client, "admin@example.com", "change-this-secure-password").await;
 
-    // Upload document
-    let pdf_content = include_bytes!("../tests/fixtures/sample.pdf");
-    let form = reqwest::multipart::Form::new()
-        .text("title", "Field Operations Test")
-        .text("self_sign_only", "true")
-        .part(
-            "file",
-            reqwest::multipart::Part::bytes(pdf_content.to_vec())
-                .file_name("test.pdf")
-                .mime_str("application/pdf")
-                .unwrap(),
-        );
+    let Some(doc_id) = upload_sample_document(&client, &token, "Status Change Test").await else {
+        return;
+    };
+    let Some(other_id) = upload_sample_document(&client, &token, "Bystander Test").await else {
+        return;
+    };
 
     let res = client
-        .post(format!("{}/documents", BASE_URL))
+        .post(format!("{}/documents/{}/signers", BASE_URL, doc_id))
         .header("Authorization", format!("Bearer {}", token))
-        .multipart(form)
+        .json(&json!({
+            "email": "statussigner@example.com",
+            "name": "Status Signer"
+        }))
         .send()
         .await
-        .expect("Upload failed");
-
-    if !res.status().is_success() {
-        return;
-    }
-
-    let doc: serde_json::Value = res.json().await.expect("Failed to parse response");
-    let doc_id = doc["id"].as_str().expect("No document ID");
+        .expect("Add signer failed");
+    let signer: serde_json::Value = res.json().await.expect("Failed to parse signer");
 
-    // Add signature field
-    let res = client
+    client
         .post(format!("{}/documents/{}/fields", BASE_URL, doc_id))
         .header("Authorization", format!("Bearer {}", token))
         .json(&json!({
             "field_type": "signature",
+            "signer_id": signer["id"],
             "page": 1,
             "x": 100.0,
             "y": 500.0,
@@ -560,85 +670,9290 @@ async fn test_field_operations() {
         .await
         .expect("Add field failed");
 
-    assert!(res.status().is_success());
-    let field: serde_json::Value = res.json().await.expect("Failed to parse field");
-    let field_id = field["id"].as_str().expect("No field ID");
+    let res = client
+        .post(format!("{}/documents/{}/send", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Send failed");
+    assert!(res.status().is_success(), "Send failed: {:?}", res.status());
 
-    // Add date field
     let res = client
-        .post(format!("{}/documents/{}/fields", BASE_URL, doc_id))
+        .post(format!("{}/documents/{}/void", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({ "reason": "Only this one" }))
+        .send()
+        .await
+        .expect("Void failed");
+    assert!(res.status().is_success(), "Void failed: {:?}", res.status());
+
+    // Sending and voiding one document must not change the status of another
+    let res = client
+        .get(format!("{}/documents/{}", BASE_URL, other_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Get document failed");
+    let other: serde_json::Value = res.json().await.expect("Failed to parse document");
+    assert_eq!(other["document"]["status"], "draft");
+}
+
+#[tokio::test]
+async fn test_concurrent_sends_only_send_once() {
+    wait_for_server().await;
+
+    let client = Client::new();
+    let token = login(&client, "admin@example.com", "change-this-secure-password").await;
+
+    let Some(doc_id) = upload_sample_document(&client, &token, "Double Send Test").await else {
+        return;
+    };
+
+    let signer: serde_json::Value = client
+        .post(format!("{}/documents/{}/signers", BASE_URL, doc_id))
         .header("Authorization", format!("Bearer {}", token))
         .json(&json!({
-            "field_type": "date",
-            "page": 1,
-            "x": 100.0,
-            "y": 600.0,
-            "width": 150.0,
-            "height": 30.0,
-            "date_format": "MM/DD/YYYY"
+            "email": "double-send@example.com",
+            "name": "Double Send Signer"
         }))
         .send()
         .await
-        .expect("Add date field failed");
+        .expect("Add signer failed")
+        .json()
+        .await
+        .expect("Failed to parse signer");
+    add_signature_field(&client, &token, &doc_id, signer["id"].as_str().unwrap()).await;
 
-    assert!(res.status().is_success());
+    let send = || {
+        client
+            .post(format!("{}/documents/{}/send", BASE_URL, doc_id))
+            .header("Authorization", format!("Bearer {}", token))
+            .send()
+    };
 
-    // Add text field
+    let (first, second) = tokio::join!(send(), send());
+    let mut statuses = [
+        first.expect("Send failed").status().as_u16(),
+        second.expect("Send failed").status().as_u16(),
+    ];
+    statuses.sort();
+    assert_eq!(statuses, [200, 409]);
+
+    let res = send().await.expect("Send failed");
+    assert_eq!(res.status(), 409);
+
+    let res = client
+        .get(format!("{}/documents/{}/audit", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Get audit failed");
+    let logs: Vec<serde_json::Value> = res.json().await.expect("Failed to parse audit");
+    let sent = logs
+        .iter()
+        .filter(|log| log["action"] == "document_sent")
+        .count();
+    assert_eq!(sent, 1);
+}
+
+#[tokio::test]
+async fn test_send_requires_signature_fields() {
+    wait_for_server().await;
+
+    let client = Client::new();
+    let token = login(&client, "admin@example.com", "change-this-secure-password").await;
+
+    let Some(doc_id) = upload_sample_document(&client, &token, "Field Check Test").await else {
+        return;
+    };
+
+    let mut signers = Vec::new();
+    for (email, name, role) in [
+        ("field-check@example.com", "Fieldless Signer", "signer"),
+        (
+            "field-check-approver@example.com",
+            "Field Check Approver",
+            "approver",
+        ),
+    ] {
+        let signer: serde_json::Value = client
+            .post(format!("{}/documents/{}/signers", BASE_URL, doc_id))
+            .header("Authorization", format!("Bearer {}", token))
+            .json(&json!({ "email": email, "name": name, "role": role }))
+            .send()
+            .await
+            .expect("Add signer failed")
+            .json()
+            .await
+            .expect("Failed to parse signer");
+        signers.push(signer);
+    }
+
+    let add_field = |field_type: &str, signer_id: serde_json::Value| {
+        client
+            .post(format!("{}/documents/{}/fields", BASE_URL, doc_id))
+            .header("Authorization", format!("Bearer {}", token))
+            .json(&json!({
+                "field_type": field_type,
+                "page": 1,
+                "x": 100.0,
+                "y": 500.0,
+                "width": 200.0,
+                "height": 50.0,
+                "signer_id": signer_id
+            }))
+            .send()
+    };
+    let send = || {
+        client
+            .post(format!("{}/documents/{}/send", BASE_URL, doc_id))
+            .header("Authorization", format!("Bearer {}", token))
+            .send()
+    };
+
+    // Neither an unassigned signature field nor a text field of their own counts
+    assert!(add_field("signature", serde_json::Value::Null)
+        .await
+        .unwrap()
+        .status()
+        .is_success());
+    assert!(add_field("text", signers[0]["id"].clone())
+        .await
+        .unwrap()
+        .status()
+        .is_success());
+
+    let res = send().await.expect("Send failed");
+    assert_eq!(res.status(), 400);
+    let body: serde_json::Value = res.json().await.expect("Failed to parse error");
+    let message = body["message"].as_str().unwrap();
+    assert!(message.contains("Fieldless Signer"), "{}", message);
+    assert!(!message.contains("Field Check Approver"), "{}", message);
+
+    assert!(add_field("initial", signers[0]["id"].clone())
+        .await
+        .unwrap()
+        .status()
+        .is_success());
+    assert_eq!(send().await.expect("Send failed").status(), 200);
+}
+
+#[tokio::test]
+async fn test_preflight() {
+    wait_for_server().await;
+
+    let client = Client::new();
+    let token = login(&client, "admin@example.com", "change-this-secure-password").await;
+
+    let Some(doc_id) = upload_sample_document(&client, &token, "Preflight Test").await else {
+        return;
+    };
+
+    let preflight = || {
+        client
+            .get(format!("{}/documents/{}/preflight", BASE_URL, doc_id))
+            .header("Authorization", format!("Bearer {}", token))
+            .send()
+    };
+    let codes = |report: &serde_json::Value| -> Vec<(String, String)> {
+        let mut codes: Vec<(String, String)> = report["issues"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|issue| {
+                (
+                    issue["code"].as_str().unwrap().to_string(),
+                    issue["severity"].as_str().unwrap().to_string(),
+                )
+            })
+            .collect();
+        codes.sort();
+        codes
+    };
+
+    let report: serde_json::Value = preflight().await.unwrap().json().await.unwrap();
+    assert_eq!(report["ready"], false);
+    assert_eq!(
+        codes(&report),
+        vec![("no_signers".to_string(), "error".to_string())]
+    );
+
+    let signer: serde_json::Value = client
+        .post(format!("{}/documents/{}/signers", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({ "email": "preflight@example.com", "name": "Preflight Signer" }))
+        .send()
+        .await
+        .expect("Add signer failed")
+        .json()
+        .await
+        .expect("Failed to parse signer");
+
+    // Off the page, and assigned to no one
     let res = client
         .post(format!("{}/documents/{}/fields", BASE_URL, doc_id))
         .header("Authorization", format!("Bearer {}", token))
         .json(&json!({
             "field_type": "text",
             "page": 1,
-            "x": 100.0,
-            "y": 650.0,
+            "x": 5000.0,
+            "y": 500.0,
             "width": 200.0,
-            "height": 30.0,
-            "value": "Sample Text"
+            "height": 50.0
         }))
         .send()
         .await
-        .expect("Add text field failed");
-
+        .expect("Add field failed");
     assert!(res.status().is_success());
+    let stray: serde_json::Value = res.json().await.unwrap();
 
-    // Update field
+    let report: serde_json::Value = preflight().await.unwrap().json().await.unwrap();
+    assert_eq!(report["ready"], false);
+    assert_eq!(
+        codes(&report),
+        vec![
+            ("field_out_of_bounds".to_string(), "error".to_string()),
+            ("field_unassigned".to_string(), "warning".to_string()),
+            ("missing_signature_field".to_string(), "error".to_string()),
+            ("undeliverable_email".to_string(), "warning".to_string()),
+        ]
+    );
+    let missing = report["issues"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|issue| issue["code"] == "missing_signature_field")
+        .unwrap();
+    assert_eq!(missing["signer_id"], signer["id"]);
+    assert!(missing.get("field_id").is_none());
+
+    // Sending refuses the same errors, and forcing only waives missing fields
     let res = client
-        .put(format!(
-            "{}/documents/{}/fields/{}",
-            BASE_URL, doc_id, field_id
-        ))
+        .post(format!("{}/documents/{}/send?force=true", BASE_URL, doc_id))
         .header("Authorization", format!("Bearer {}", token))
-        .json(&json!({
-            "x": 150.0,
-            "y": 550.0,
-            "width": 250.0,
-            "height": 60.0
-        }))
         .send()
         .await
-        .expect("Update field failed");
-
-    assert!(res.status().is_success());
+        .expect("Send failed");
+    assert_eq!(res.status(), 400);
+    let body: serde_json::Value = res.json().await.unwrap();
+    assert!(body["message"].as_str().unwrap().contains("page 1"));
 
-    // Delete field
-    let res = client
+    client
         .delete(format!(
             "{}/documents/{}/fields/{}",
-            BASE_URL, doc_id, field_id
+            BASE_URL,
+            doc_id,
+            stray["id"].as_str().unwrap()
         ))
         .header("Authorization", format!("Bearer {}", token))
         .send()
         .await
         .expect("Delete field failed");
+    add_signature_field(&client, &token, &doc_id, signer["id"].as_str().unwrap()).await;
 
-    assert!(res.status().is_success());
+    // Warnings don't stop the send
+    let report: serde_json::Value = preflight().await.unwrap().json().await.unwrap();
+    assert_eq!(report["ready"], true);
+    assert_eq!(
+        codes(&report),
+        vec![("undeliverable_email".to_string(), "warning".to_string())]
+    );
 
-    // Cleanup
-    client
-        .delete(format!("{}/documents/{}", BASE_URL, doc_id))
+    let res = client
+        .post(format!("{}/documents/{}/send", BASE_URL, doc_id))
         .header("Authorization", format!("Bearer {}", token))
         .send()
         .await
-        .ok();
+        .expect("Send failed");
+    assert_eq!(res.status(), 200);
+
+    assert_eq!(preflight().await.unwrap().status(), 409);
+}
+
+#[tokio::test]
+async fn test_owner_preview() {
+    wait_for_server().await;
+
+    let client = Client::new();
+    let token = login(&client, "admin@example.com", "change-this-secure-password").await;
+
+    let Some(doc_id) = upload_sample_document(&client, &token, "Preview Test").await else {
+        return;
+    };
+
+    let mut signers = Vec::new();
+    for (email, name) in [
+        ("preview-a@example.com", "Preview Signer A"),
+        ("preview-b@example.com", "Preview Signer B"),
+    ] {
+        let signer: serde_json::Value = client
+            .post(format!("{}/documents/{}/signers", BASE_URL, doc_id))
+            .header("Authorization", format!("Bearer {}", token))
+            .json(&json!({ "email": email, "name": name }))
+            .send()
+            .await
+            .expect("Add signer failed")
+            .json()
+            .await
+            .expect("Failed to parse signer");
+        add_signature_field(&client, &token, &doc_id, signer["id"].as_str().unwrap()).await;
+        signers.push(signer);
+    }
+
+    let preview = |signer_id: &serde_json::Value| {
+        client
+            .get(format!(
+                "{}/documents/{}/preview?signer_id={}",
+                BASE_URL,
+                doc_id,
+                signer_id.as_str().unwrap()
+            ))
+            .header("Authorization", format!("Bearer {}", token))
+            .send()
+    };
+
+    for signer in &signers {
+        let res = preview(&signer["id"]).await.expect("Preview failed");
+        assert_eq!(res.status(), 200);
+        let session: serde_json::Value = res.json().await.unwrap();
+        assert_eq!(session["signer"]["id"], signer["id"]);
+        let fields = session["fields"].as_array().unwrap();
+        assert_eq!(fields.len(), 1);
+        assert_eq!(fields[0]["signer_id"], signer["id"]);
+        assert!(session["page_count"].as_u64().unwrap() >= 1);
+    }
+
+    let res = client
+        .get(format!("{}/documents/{}/preview/pdf", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Preview PDF failed");
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.headers()["content-type"], "application/pdf");
+
+    // A signer of another document is not found
+    let res = preview(&json!(uuid::Uuid::new_v4().to_string()))
+        .await
+        .expect("Preview failed");
+    assert_eq!(res.status(), 404);
+
+    let res = client
+        .post(format!("{}/documents/{}/send", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Send failed");
+    assert_eq!(res.status(), 200);
+    assert_eq!(preview(&signers[1]["id"]).await.unwrap().status(), 200);
+
+    // Previewing left no trace on the signers
+    let document: serde_json::Value = client
+        .get(format!("{}/documents/{}", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    for signer in document["signers"].as_array().unwrap() {
+        assert!(signer["viewed_at"].is_null());
+    }
+    let logs: Vec<serde_json::Value> = client
+        .get(format!("{}/documents/{}/audit", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert!(!logs
+        .iter()
+        .any(|log| log["action"] == "signer_viewed" || log["action"] == "document_viewed"));
+}
+
+#[tokio::test]
+async fn test_open_link_claim() {
+    wait_for_server().await;
+
+    let client = Client::new();
+    let token = login(&client, "admin@example.com", "change-this-secure-password").await;
+
+    let Some(doc_id) = upload_sample_document(&client, &token, "Open Link Test").await else {
+        return;
+    };
+
+    let res = client
+        .post(format!("{}/documents/{}/open-link", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({ "label": "Walk-in customer" }))
+        .send()
+        .await
+        .expect("Create open link failed");
+    assert_eq!(res.status(), 200);
+    let open: serde_json::Value = res.json().await.unwrap();
+    let open_token = open["token"].as_str().unwrap().to_string();
+    let signer_id = open["signer"]["id"].as_str().unwrap().to_string();
+    assert_eq!(open["signer"]["name"], "Walk-in customer");
+    assert_ne!(open_token, open["signer"]["access_token"].as_str().unwrap());
+
+    // One open slot per document
+    let res = client
+        .post(format!("{}/documents/{}/open-link", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 409);
+
+    add_signature_field(&client, &token, &doc_id, &signer_id).await;
+
+    // The empty email does not stop the document from being sent
+    let preflight: serde_json::Value = client
+        .get(format!("{}/documents/{}/preflight", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(preflight["ready"], true, "{}", preflight);
+
+    // Claiming before the document is sent is refused
+    let claim = |name: &str, email: &str| {
+        client
+            .post(format!("{}/sign/{}/claim", BASE_URL, open_token))
+            .json(&json!({ "name": name, "email": email }))
+            .send()
+    };
+    assert_eq!(
+        claim("Early Bird", "early@example.com")
+            .await
+            .unwrap()
+            .status(),
+        400
+    );
+
+    let res = client
+        .post(format!("{}/documents/{}/send", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Send failed");
+    assert_eq!(res.status(), 200);
+
+    // The open link itself asks to be claimed
+    let res = client
+        .get(format!("{}/sign/{}", BASE_URL, open_token))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 400);
+
+    // Of concurrent claims exactly one wins
+    let results = futures::future::join_all((0..5).map(|i| {
+        let client = client.clone();
+        let url = format!("{}/sign/{}/claim", BASE_URL, open_token);
+        async move {
+            client
+                .post(url)
+                .json(&json!({
+                    "name": format!("Visitor {}", i),
+                    "email": format!("visitor-{}@example.com", i)
+                }))
+                .send()
+                .await
+                .expect("Claim failed")
+        }
+    }))
+    .await;
+    let statuses: Vec<u16> = results.iter().map(|r| r.status().as_u16()).collect();
+    assert_eq!(
+        statuses.iter().filter(|s| **s == 200).count(),
+        1,
+        "{:?}",
+        statuses
+    );
+    assert_eq!(
+        statuses.iter().filter(|s| **s == 409).count(),
+        4,
+        "{:?}",
+        statuses
+    );
+
+    let mut claimed = None;
+    for res in results {
+        if res.status() == 200 {
+            claimed = Some(res.json::<serde_json::Value>().await.unwrap());
+        }
+    }
+    let claimed = claimed.unwrap();
+    assert_eq!(claimed["signer_id"], signer_id.as_str());
+
+    // Later visitors are told the link is taken
+    let res = client
+        .get(format!("{}/sign/{}", BASE_URL, open_token))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 409);
+    let body: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(body["message"], "This link has been claimed");
+
+    // The claimant carries on with their own token
+    let session: serde_json::Value = client
+        .get(format!(
+            "{}/sign/{}",
+            BASE_URL,
+            claimed["token"].as_str().unwrap()
+        ))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(session["signer"]["id"], signer_id.as_str());
+    assert!(session["signer"]["name"]
+        .as_str()
+        .unwrap()
+        .starts_with("Visitor "));
+    assert!(session["signer"]["email"]
+        .as_str()
+        .unwrap()
+        .starts_with("visitor-"));
+
+    let logs: Vec<serde_json::Value> = client
+        .get(format!("{}/documents/{}/audit", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let claims: Vec<_> = logs
+        .iter()
+        .filter(|log| log["action"] == "signer_claimed")
+        .collect();
+    assert_eq!(claims.len(), 1);
+    assert_eq!(
+        claims[0]["details"]["signer_email"],
+        session["signer"]["email"]
+    );
+    assert!(claims[0]["ip_address"].is_string());
+    assert!(claims[0]["details"]["claimed_at"].is_string());
+    assert!(logs.iter().any(|log| log["action"] == "open_link_created"));
+}
+
+#[tokio::test]
+async fn test_signing_session_token() {
+    wait_for_server().await;
+
+    let client = Client::new();
+    let token = login(&client, "admin@example.com", "change-this-secure-password").await;
+
+    let Some(doc_id) = upload_sample_document(&client, &token, "Session Token Test").await else {
+        return;
+    };
+
+    let signer: serde_json::Value = client
+        .post(format!("{}/documents/{}/signers", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({ "email": "session-signer@example.com", "name": "Session Signer" }))
+        .send()
+        .await
+        .expect("Add signer failed")
+        .json()
+        .await
+        .unwrap();
+    add_signature_field(&client, &token, &doc_id, signer["id"].as_str().unwrap()).await;
+
+    let res = client
+        .post(format!("{}/documents/{}/send", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Send failed");
+    assert_eq!(res.status(), 200);
+
+    let access_token = signer["access_token"].as_str().unwrap();
+    let exchange = || {
+        client
+            .post(format!("{}/sign/{}/session", BASE_URL, access_token))
+            .send()
+    };
+
+    let res = exchange().await.expect("Exchange failed");
+    assert_eq!(res.status(), 200);
+    let session: serde_json::Value = res.json().await.unwrap();
+    let session_token = session["session_token"].as_str().unwrap().to_string();
+    assert!(session["expires_at"].is_string());
+
+    let get = |path: &str, bearer: &str| {
+        client
+            .get(format!("{}/sign/session{}", BASE_URL, path))
+            .header("Authorization", format!("Bearer {}", bearer))
+            .send()
+    };
+
+    let res = get("", &session_token).await.unwrap();
+    assert_eq!(res.status(), 200);
+    let signing: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(signing["signer"]["id"], signer["id"]);
+
+    let res = get("/pdf", &session_token).await.unwrap();
+    assert_eq!(res.status(), 200);
+
+    // A login token is not a signing session, nor is garbage
+    assert_eq!(get("", &token).await.unwrap().status(), 401);
+    assert_eq!(get("", "not-a-token").await.unwrap().status(), 401);
+
+    // Path tokens keep working by default, also next to a login token
+    let res = client
+        .get(format!("{}/sign/{}", BASE_URL, access_token))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+
+    // Exchanging the link again gives a fresh session
+    let res = exchange().await.unwrap();
+    assert_eq!(res.status(), 200);
+    let renewed: serde_json::Value = res.json().await.unwrap();
+    let renewed_token = renewed["session_token"].as_str().unwrap().to_string();
+    assert_eq!(get("", &renewed_token).await.unwrap().status(), 200);
+
+    // Replacing the signer's link ends its sessions
+    let res = client
+        .post(format!("{}/documents/{}/recall", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    assert_eq!(get("", &session_token).await.unwrap().status(), 401);
+    assert_eq!(exchange().await.unwrap().status(), 404);
+}
+
+#[tokio::test]
+async fn test_rotate_signer_token() {
+    wait_for_server().await;
+
+    let client = Client::new();
+    let token = login(&client, "admin@example.com", "change-this-secure-password").await;
+
+    let Some(doc_id) = upload_sample_document(&client, &token, "Rotate Token Test").await else {
+        return;
+    };
+
+    let mut signers = Vec::new();
+    let mut fields = Vec::new();
+    for (email, name) in [
+        ("rotate-a@example.com", "Rotate Signer A"),
+        ("rotate-b@example.com", "Rotate Signer B"),
+    ] {
+        let signer: serde_json::Value = client
+            .post(format!("{}/documents/{}/signers", BASE_URL, doc_id))
+            .header("Authorization", format!("Bearer {}", token))
+            .json(&json!({ "email": email, "name": name }))
+            .send()
+            .await
+            .expect("Add signer failed")
+            .json()
+            .await
+            .unwrap();
+        fields.push(
+            add_signature_field(&client, &token, &doc_id, signer["id"].as_str().unwrap()).await,
+        );
+        signers.push(signer);
+    }
+
+    let res = client
+        .post(format!("{}/documents/{}/send", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Send failed");
+    assert_eq!(res.status(), 200);
+
+    let rotate = |signer: &serde_json::Value, body: serde_json::Value| {
+        client
+            .post(format!(
+                "{}/documents/{}/signers/{}/rotate-token",
+                BASE_URL,
+                doc_id,
+                signer["id"].as_str().unwrap()
+            ))
+            .header("Authorization", format!("Bearer {}", token))
+            .json(&body)
+            .send()
+    };
+
+    let old_token = signers[0]["access_token"].as_str().unwrap();
+    let res = rotate(
+        &signers[0],
+        json!({ "reason": "Forwarded to the wrong person" }),
+    )
+    .await
+    .expect("Rotate failed");
+    assert_eq!(res.status(), 200);
+    let rotated: serde_json::Value = res.json().await.unwrap();
+    let new_token = rotated["access_token"].as_str().unwrap();
+    assert_ne!(new_token, old_token);
+
+    // The old link says it was revoked, on the signing and status pages
+    for path in ["sign", "status"] {
+        let res = client
+            .get(format!("{}/{}/{}", BASE_URL, path, old_token))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), 410);
+        let body: serde_json::Value = res.json().await.unwrap();
+        assert_eq!(body["error"], "link_expired");
+        assert!(body["message"].as_str().unwrap().contains("revoked"));
+    }
+    let res = client
+        .post(format!("{}/sign/{}/session", BASE_URL, old_token))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 410);
+
+    let res = client
+        .post(format!("{}/sign/{}/submit", BASE_URL, new_token))
+        .json(&json!({
+            "signatures": [{
+                "field_id": fields[0],
+                "signature_data": "data:image/png;base64,iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAYAAAAfFcSJAAAADUlEQVR42mNk+M9QDwADhgGAWjR9awAAAABJRU5ErkJggg=="
+            }],
+            "field_values": []
+        }))
+        .send()
+        .await
+        .expect("Submit failed");
+    assert!(res.status().is_success());
+
+    // Signers who signed keep their link
+    let res = rotate(&signers[0], json!({})).await.unwrap();
+    assert_eq!(res.status(), 409);
+
+    let logs: Vec<serde_json::Value> = client
+        .get(format!("{}/documents/{}/audit", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let rotations: Vec<_> = logs
+        .iter()
+        .filter(|log| log["action"] == "signer_token_rotated")
+        .collect();
+    assert_eq!(rotations.len(), 1);
+    assert_eq!(
+        rotations[0]["details"]["reason"],
+        "Forwarded to the wrong person"
+    );
+    assert_eq!(rotations[0]["signer_id"], signers[0]["id"]);
+}
+
+#[tokio::test]
+async fn test_recall_document() {
+    wait_for_server().await;
+
+    let client = Client::new();
+    let token = login(&client, "admin@example.com", "change-this-secure-password").await;
+
+    let Some(doc_id) = upload_sample_document(&client, &token, "Recall Test").await else {
+        return;
+    };
+
+    let mut signers = Vec::new();
+    for (email, name) in [
+        ("recall-a@example.com", "Recall Signer A"),
+        ("recall-b@example.com", "Recall Signer B"),
+    ] {
+        let res = client
+            .post(format!("{}/documents/{}/signers", BASE_URL, doc_id))
+            .header("Authorization", format!("Bearer {}", token))
+            .json(&json!({ "email": email, "name": name }))
+            .send()
+            .await
+            .expect("Add signer failed");
+        let signer: serde_json::Value = res.json().await.expect("Failed to parse signer");
+        signers.push(signer);
+    }
+
+    let res = client
+        .post(format!("{}/documents/{}/fields", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({
+            "field_type": "signature",
+            "page": 1,
+            "x": 100.0,
+            "y": 500.0,
+            "width": 200.0,
+            "height": 50.0,
+            "signer_id": signers[0]["id"]
+        }))
+        .send()
+        .await
+        .expect("Add field failed");
+    let field: serde_json::Value = res.json().await.expect("Failed to parse field");
+    add_signature_field(&client, &token, &doc_id, signers[1]["id"].as_str().unwrap()).await;
+
+    let send = || {
+        client
+            .post(format!("{}/documents/{}/send", BASE_URL, doc_id))
+            .header("Authorization", format!("Bearer {}", token))
+            .send()
+    };
+    let recall = || {
+        client
+            .post(format!("{}/documents/{}/recall", BASE_URL, doc_id))
+            .header("Authorization", format!("Bearer {}", token))
+            .json(&json!({ "notify_signers": true }))
+            .send()
+    };
+
+    let res = recall().await.expect("Recall failed");
+    assert_eq!(res.status(), 409);
+
+    assert_eq!(send().await.expect("Send failed").status(), 200);
+
+    let old_token = signers[0]["access_token"]
+        .as_str()
+        .expect("No access token");
+    let res = client
+        .get(format!("{}/sign/{}", BASE_URL, old_token))
+        .send()
+        .await
+        .expect("Get signing session failed");
+    assert_eq!(res.status(), 200);
+
+    let res = recall().await.expect("Recall failed");
+    assert_eq!(res.status(), 200);
+    let document: serde_json::Value = res.json().await.expect("Failed to parse document");
+    assert_eq!(document["status"], "draft");
+
+    // Links that went out before the recall no longer work.
+    let res = client
+        .get(format!("{}/sign/{}", BASE_URL, old_token))
+        .send()
+        .await
+        .expect("Get signing session failed");
+    assert_eq!(res.status(), 404);
+
+    let res = client
+        .get(format!("{}/documents/{}", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Get document failed");
+    let document: serde_json::Value = res.json().await.expect("Failed to parse document");
+    let current = document["signers"].as_array().expect("No signers").clone();
+    assert_eq!(current.len(), 2);
+    for signer in &current {
+        assert_eq!(signer["status"], "pending");
+        assert!(signer["viewed_at"].is_null());
+        assert_ne!(signer["access_token"], signers[0]["access_token"]);
+        assert_ne!(signer["access_token"], signers[1]["access_token"]);
+    }
+
+    // Once someone has signed, the document can only be corrected by voiding it.
+    assert_eq!(send().await.expect("Send failed").status(), 200);
+
+    let new_token = current
+        .iter()
+        .find(|s| s["id"] == signers[0]["id"])
+        .and_then(|s| s["access_token"].as_str())
+        .expect("No access token");
+    let res = client
+        .post(format!("{}/sign/{}/submit", BASE_URL, new_token))
+        .json(&json!({
+            "signatures": [{
+                "field_id": field["id"],
+                "signature_data": "data:image/png;base64,iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAYAAAAfFcSJAAAADUlEQVR42mNk+M9QDwADhgGAWjR9awAAAABJRU5ErkJggg=="
+            }],
+            "field_values": []
+        }))
+        .send()
+        .await
+        .expect("Submit failed");
+    assert!(res.status().is_success());
+
+    let res = recall().await.expect("Recall failed");
+    assert_eq!(res.status(), 409);
+    let body: serde_json::Value = res.json().await.expect("Failed to parse error");
+    assert!(body["message"]
+        .as_str()
+        .is_some_and(|m| m.contains("Void it")));
+
+    let res = client
+        .get(format!("{}/documents/{}/audit", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Get audit failed");
+    let logs: Vec<serde_json::Value> = res.json().await.expect("Failed to parse audit");
+    let count = |action: &str| logs.iter().filter(|log| log["action"] == action).count();
+    assert_eq!(count("document_recalled"), 1);
+    assert_eq!(count("document_sent"), 2);
+}
+
+#[tokio::test]
+async fn test_reset_declined_signer() {
+    wait_for_server().await;
+
+    let client = Client::new();
+    let token = login(&client, "admin@example.com", "change-this-secure-password").await;
+
+    let Some(doc_id) = upload_sample_document(&client, &token, "Reset Signer Test").await else {
+        return;
+    };
+
+    let mut signers = Vec::new();
+    for (email, name, y) in [
+        ("reset-a@example.com", "Reset Signer A", 500.0),
+        ("reset-b@example.com", "Reset Signer B", 400.0),
+    ] {
+        let res = client
+            .post(format!("{}/documents/{}/signers", BASE_URL, doc_id))
+            .header("Authorization", format!("Bearer {}", token))
+            .json(&json!({ "email": email, "name": name }))
+            .send()
+            .await
+            .expect("Add signer failed");
+        let signer: serde_json::Value = res.json().await.expect("Failed to parse signer");
+
+        let res = client
+            .post(format!("{}/documents/{}/fields", BASE_URL, doc_id))
+            .header("Authorization", format!("Bearer {}", token))
+            .json(&json!({
+                "field_type": "signature",
+                "page": 1,
+                "x": 100.0,
+                "y": y,
+                "width": 200.0,
+                "height": 50.0,
+                "signer_id": signer["id"]
+            }))
+            .send()
+            .await
+            .expect("Add field failed");
+        let field: serde_json::Value = res.json().await.expect("Failed to parse field");
+
+        signers.push((signer, field));
+    }
+
+    client
+        .post(format!("{}/documents/{}/send", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Send failed");
+
+    let reset = |signer_id: &serde_json::Value| {
+        client
+            .post(format!(
+                "{}/documents/{}/signers/{}/reset",
+                BASE_URL,
+                doc_id,
+                signer_id.as_str().unwrap()
+            ))
+            .header("Authorization", format!("Bearer {}", token))
+            .send()
+    };
+
+    let (declining, _) = &signers[0];
+    let old_token = declining["access_token"].as_str().expect("No access token");
+
+    // Only declined signers can be reset
+    let res = reset(&declining["id"]).await.expect("Reset failed");
+    assert_eq!(res.status(), 409);
+
+    let res = client
+        .post(format!("{}/sign/{}/decline", BASE_URL, old_token))
+        .json(&json!({ "reason": "Clicked the wrong button" }))
+        .send()
+        .await
+        .expect("Decline failed");
+    assert!(res.status().is_success());
+
+    let res = reset(&declining["id"]).await.expect("Reset failed");
+    assert_eq!(res.status(), 200);
+    let signer: serde_json::Value = res.json().await.expect("Failed to parse signer");
+    assert_eq!(signer["status"], "sent");
+    assert!(signer["declined_at"].is_null());
+    assert!(signer["decline_reason"].is_null());
+    assert_ne!(signer["access_token"], declining["access_token"]);
+
+    let res = client
+        .get(format!("{}/sign/{}", BASE_URL, old_token))
+        .send()
+        .await
+        .expect("Get signing session failed");
+    assert_eq!(res.status(), 404);
+
+    let res = client
+        .get(format!(
+            "{}/sign/{}",
+            BASE_URL,
+            signer["access_token"].as_str().unwrap()
+        ))
+        .send()
+        .await
+        .expect("Get signing session failed");
+    assert_eq!(res.status(), 200);
+
+    // A signer who signed cannot be reset
+    let (signing, field) = &signers[1];
+    let res = client
+        .post(format!(
+            "{}/sign/{}/submit",
+            BASE_URL,
+            signing["access_token"].as_str().unwrap()
+        ))
+        .json(&json!({
+            "signatures": [{
+                "field_id": field["id"],
+                "signature_data": "data:image/png;base64,iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAYAAAAfFcSJAAAADUlEQVR42mNk+M9QDwADhgGAWjR9awAAAABJRU5ErkJggg=="
+            }],
+            "field_values": []
+        }))
+        .send()
+        .await
+        .expect("Submit failed");
+    assert!(res.status().is_success());
+
+    let res = reset(&signing["id"]).await.expect("Reset failed");
+    assert_eq!(res.status(), 409);
+
+    let res = client
+        .get(format!("{}/documents/{}/audit", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Get audit failed");
+    let logs: Vec<serde_json::Value> = res.json().await.expect("Failed to parse audit");
+    let resets: Vec<_> = logs
+        .iter()
+        .filter(|log| log["action"] == "signer_reset")
+        .collect();
+    assert_eq!(resets.len(), 1);
+    assert_eq!(
+        resets[0]["details"]["decline_reason"],
+        "Clicked the wrong button"
+    );
+}
+
+#[tokio::test]
+async fn test_reminder_settings() {
+    wait_for_server().await;
+
+    let client = Client::new();
+    let token = login(&client, "admin@example.com", "change-this-secure-password").await;
+
+    let Some(doc_id) = upload_sample_document_with(
+        &client,
+        &token,
+        "Reminder Test",
+        &[("reminder_interval_days", "3"), ("max_reminders", "3")],
+    )
+    .await
+    else {
+        return;
+    };
+
+    // An interval without a cap is refused
+    let rejected = upload_sample_document_with(
+        &client,
+        &token,
+        "Reminder Test Without Cap",
+        &[("reminder_interval_days", "3")],
+    )
+    .await;
+    assert!(rejected.is_none());
+
+    let get_document = || async {
+        let res = client
+            .get(format!("{}/documents/{}", BASE_URL, doc_id))
+            .header("Authorization", format!("Bearer {}", token))
+            .send()
+            .await
+            .expect("Get document failed");
+        res.json::<serde_json::Value>()
+            .await
+            .expect("Failed to parse document")
+    };
+
+    let document = get_document().await;
+    assert_eq!(document["reminder_interval_days"], 3);
+    assert_eq!(document["max_reminders"], 3);
+
+    let patch = |body: serde_json::Value| {
+        client
+            .patch(format!("{}/documents/{}", BASE_URL, doc_id))
+            .header("Authorization", format!("Bearer {}", token))
+            .json(&body)
+            .send()
+    };
+
+    let res = patch(json!({ "max_reminders": 0 }))
+        .await
+        .expect("Patch failed");
+    assert!(res.status().is_client_error());
+
+    let res = patch(json!({ "reminder_interval_days": 2, "max_reminders": 50 }))
+        .await
+        .expect("Patch failed");
+    assert!(res.status().is_client_error());
+
+    let res = patch(json!({ "reminder_interval_days": 2, "max_reminders": 5 }))
+        .await
+        .expect("Patch failed");
+    assert_eq!(res.status(), 200);
+    let document: serde_json::Value = res.json().await.expect("Failed to parse document");
+    assert_eq!(document["reminder_interval_days"], 2);
+    assert_eq!(document["max_reminders"], 5);
+
+    let res = patch(json!({ "reminder_interval_days": 0, "max_reminders": 0 }))
+        .await
+        .expect("Patch failed");
+    assert_eq!(res.status(), 200);
+    let document: serde_json::Value = res.json().await.expect("Failed to parse document");
+    assert!(document["reminder_interval_days"].is_null());
+    assert!(document["max_reminders"].is_null());
+
+    // Signers of a draft have nothing to be reminded of
+    let res = client
+        .post(format!("{}/documents/{}/signers", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({
+            "email": "reminded@example.com",
+            "name": "Reminded Signer"
+        }))
+        .send()
+        .await
+        .expect("Add signer failed");
+    let signer: serde_json::Value = res.json().await.expect("Failed to parse signer");
+    assert_eq!(signer["reminders_sent"], 0);
+
+    let res = client
+        .post(format!(
+            "{}/documents/{}/signers/{}/remind",
+            BASE_URL,
+            doc_id,
+            signer["id"].as_str().unwrap()
+        ))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Remind failed");
+    assert_eq!(res.status(), 400);
+}
+
+#[tokio::test]
+async fn test_document_list_shows_waiting_on() {
+    wait_for_server().await;
+
+    let client = Client::new();
+    let token = login(&client, "admin@example.com", "change-this-secure-password").await;
+
+    let Some(doc_id) = upload_sample_document(&client, &token, "Waiting On Test").await else {
+        return;
+    };
+
+    let mut signers = Vec::new();
+    for (email, name, order_index, y) in [
+        ("waiting-second@example.com", "Second Signer", 1, 400.0),
+        ("waiting-first@example.com", "First Signer", 0, 500.0),
+    ] {
+        let res = client
+            .post(format!("{}/documents/{}/signers", BASE_URL, doc_id))
+            .header("Authorization", format!("Bearer {}", token))
+            .json(&json!({ "email": email, "name": name, "order_index": order_index }))
+            .send()
+            .await
+            .expect("Add signer failed");
+        let signer: serde_json::Value = res.json().await.expect("Failed to parse signer");
+
+        let res = client
+            .post(format!("{}/documents/{}/fields", BASE_URL, doc_id))
+            .header("Authorization", format!("Bearer {}", token))
+            .json(&json!({
+                "field_type": "signature",
+                "page": 1,
+                "x": 100.0,
+                "y": y,
+                "width": 200.0,
+                "height": 50.0,
+                "signer_id": signer["id"]
+            }))
+            .send()
+            .await
+            .expect("Add field failed");
+        let field: serde_json::Value = res.json().await.expect("Failed to parse field");
+
+        signers.push((signer, field));
+    }
+
+    let list_item = || async {
+        let res = client
+            .get(format!("{}/documents?limit=100", BASE_URL))
+            .header("Authorization", format!("Bearer {}", token))
+            .send()
+            .await
+            .expect("List documents failed");
+        let list: serde_json::Value = res.json().await.expect("Failed to parse list");
+        list["documents"]
+            .as_array()
+            .expect("No documents")
+            .iter()
+            .find(|d| d["id"] == doc_id.as_str())
+            .cloned()
+            .expect("Document not listed")
+    };
+
+    // Drafts are not waiting on anyone yet
+    let item = list_item().await;
+    assert!(item["waiting_on_email"].is_null());
+    assert_eq!(item["unviewed_signers"], 2);
+    assert!(item["last_activity_at"].is_string());
+
+    client
+        .post(format!("{}/documents/{}/send", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Send failed");
+
+    let item = list_item().await;
+    assert_eq!(item["waiting_on_email"], "waiting-first@example.com");
+    assert_eq!(item["waiting_on_name"], "First Signer");
+
+    let (first, field) = &signers[1];
+    let res = client
+        .post(format!(
+            "{}/sign/{}/submit",
+            BASE_URL,
+            first["access_token"].as_str().unwrap()
+        ))
+        .json(&json!({
+            "signatures": [{
+                "field_id": field["id"],
+                "signature_data": "data:image/png;base64,iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAYAAAAfFcSJAAAADUlEQVR42mNk+M9QDwADhgGAWjR9awAAAABJRU5ErkJggg=="
+            }],
+            "field_values": []
+        }))
+        .send()
+        .await
+        .expect("Submit failed");
+    assert!(res.status().is_success());
+
+    let item = list_item().await;
+    assert_eq!(item["waiting_on_email"], "waiting-second@example.com");
+    assert_eq!(item["completed_signers"], 1);
+}
+
+#[tokio::test]
+async fn test_document_shares() {
+    wait_for_server().await;
+
+    let client = Client::new();
+    let token = login(&client, "admin@example.com", "change-this-secure-password").await;
+
+    let Some(doc_id) = upload_sample_document(&client, &token, "Shares Test").await else {
+        return;
+    };
+
+    let share = |email: &str, role: &str| {
+        client
+            .post(format!("{}/documents/{}/shares", BASE_URL, doc_id))
+            .header("Authorization", format!("Bearer {}", token))
+            .json(&json!({ "email": email, "role": role }))
+            .send()
+    };
+
+    let res = share("nobody@example.com", "viewer")
+        .await
+        .expect("Share failed");
+    assert_eq!(res.status(), 404);
+
+    let res = share("admin@example.com", "editor")
+        .await
+        .expect("Share failed");
+    assert_eq!(res.status(), 400);
+
+    let res = share("admin@example.com", "owner")
+        .await
+        .expect("Share failed");
+    assert_eq!(res.status(), 422);
+
+    let res = client
+        .get(format!("{}/documents/{}/shares", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("List shares failed");
+    assert!(res.status().is_success());
+    let shares: serde_json::Value = res.json().await.expect("Failed to parse shares");
+    assert_eq!(shares, json!([]));
+
+    let res = client
+        .delete(format!(
+            "{}/documents/{}/shares/00000000-0000-0000-0000-000000000000",
+            BASE_URL, doc_id
+        ))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Unshare failed");
+    assert_eq!(res.status(), 404);
+
+    let res = client
+        .get(format!("{}/documents/shared", BASE_URL))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("List shared documents failed");
+    assert!(res.status().is_success());
+    let body: serde_json::Value = res.json().await.expect("Failed to parse documents");
+    assert!(body["documents"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .all(|d| d["id"] != doc_id.as_str()));
+}
+
+#[tokio::test]
+async fn test_storage_usage() {
+    wait_for_server().await;
+
+    let client = Client::new();
+    let token = login(&client, "admin@example.com", "change-this-secure-password").await;
+
+    let Some(_doc_id) = upload_sample_document(&client, &token, "Storage Usage Test").await else {
+        return;
+    };
+
+    let res = client
+        .get(format!("{}/auth/usage", BASE_URL))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Usage request failed");
+    assert!(res.status().is_success());
+    let usage: serde_json::Value = res.json().await.expect("Failed to parse usage");
+    let sample_size = std::fs::metadata("tests/fixtures/sample.pdf")
+        .unwrap()
+        .len() as i64;
+    assert!(usage["used_bytes"].as_i64().unwrap() >= sample_size);
+    assert!(usage["document_count"].as_i64().unwrap() >= 1);
+    assert_eq!(usage["custom_quota"], false);
+    let user_id = usage["user_id"].as_str().unwrap().to_string();
+
+    let set_quota = |quota_mb: serde_json::Value| {
+        client
+            .put(format!("{}/admin/users/{}/quota", BASE_URL, user_id))
+            .header("Authorization", format!("Bearer {}", token))
+            .json(&json!({ "quota_mb": quota_mb }))
+            .send()
+    };
+
+    let res = set_quota(json!(-1)).await.expect("Set quota failed");
+    assert_eq!(res.status(), 422);
+
+    // Unlimited, so concurrent tests uploading as the same user are unaffected
+    let res = set_quota(json!(0)).await.expect("Set quota failed");
+    assert!(res.status().is_success());
+    let usage: serde_json::Value = res.json().await.expect("Failed to parse usage");
+    assert_eq!(usage["custom_quota"], true);
+    assert!(usage["quota_bytes"].is_null());
+
+    let res = client
+        .get(format!("{}/admin/users/{}/usage", BASE_URL, user_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Usage request failed");
+    assert!(res.status().is_success());
+    let usage: serde_json::Value = res.json().await.expect("Failed to parse usage");
+    assert_eq!(usage["custom_quota"], true);
+
+    let res = set_quota(json!(null)).await.expect("Set quota failed");
+    let usage: serde_json::Value = res.json().await.expect("Failed to parse usage");
+    assert_eq!(usage["custom_quota"], false);
+
+    let res = client
+        .get(format!(
+            "{}/admin/users/00000000-0000-0000-0000-000000000000/usage",
+            BASE_URL
+        ))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Usage request failed");
+    assert_eq!(res.status(), 404);
+}
+
+#[tokio::test]
+async fn test_idempotency_keys() {
+    wait_for_server().await;
+
+    let client = Client::new();
+    let token = login(&client, "admin@example.com", "change-this-secure-password").await;
+    let key = format!("idempotency-test-{}", uuid::Uuid::new_v4());
+
+    // Each form gets its own random multipart boundary, as real retries do
+    let upload = |key: String, title: &'static str| {
+        let form = reqwest::multipart::Form::new().text("title", title).part(
+            "file",
+            reqwest::multipart::Part::bytes(
+                include_bytes!("../tests/fixtures/sample.pdf").to_vec(),
+            )
+            .file_name("test.pdf")
+            .mime_str("application/pdf")
+            .unwrap(),
+        );
+        client
+            .post(format!("{}/documents", BASE_URL))
+            .header("Authorization", format!("Bearer {}", token))
+            .header("Idempotency-Key", key)
+            .multipart(form)
+            .send()
+    };
+
+    let (first, second) = tokio::join!(
+        upload(key.clone(), "Idempotent Upload"),
+        upload(key.clone(), "Idempotent Upload")
+    );
+    // One runs; the other is told it is still in progress, or replays it if
+    // it already finished
+    let (mut ran, mut others) = (Vec::new(), Vec::new());
+    for res in [first, second] {
+        let res = res.expect("Upload failed");
+        if res.status().is_success() && res.headers().get("idempotent-replayed").is_none() {
+            ran.push(res);
+        } else {
+            others.push(res);
+        }
+    }
+    assert_eq!(ran.len(), 1);
+    let other = others.pop().unwrap();
+    let first: serde_json::Value = ran
+        .pop()
+        .unwrap()
+        .json()
+        .await
+        .expect("Failed to parse document");
+    let doc_id = first["id"].as_str().unwrap().to_string();
+    if other.status().is_success() {
+        let second: serde_json::Value = other.json().await.expect("Failed to parse document");
+        assert_eq!(second["id"], doc_id);
+    } else {
+        assert_eq!(other.status(), 409);
+    }
+
+    let res = upload(key.clone(), "Idempotent Upload")
+        .await
+        .expect("Upload failed");
+    assert!(res.status().is_success());
+    let retried: serde_json::Value = res.json().await.expect("Failed to parse document");
+    assert_eq!(retried["id"], doc_id);
+
+    let res = upload(key.clone(), "Different Upload")
+        .await
+        .expect("Upload failed");
+    assert_eq!(res.status(), 409);
+
+    let res = upload("x".repeat(256), "Idempotent Upload")
+        .await
+        .expect("Upload failed");
+    assert_eq!(res.status(), 400);
+
+    let signer_key = format!("{}-signer", key);
+    let add_signer = || {
+        client
+            .post(format!("{}/documents/{}/signers", BASE_URL, doc_id))
+            .header("Authorization", format!("Bearer {}", token))
+            .header("Idempotency-Key", &signer_key)
+            .json(&json!({
+                "email": "idempotent-signer@example.com",
+                "name": "Idempotent Signer"
+            }))
+            .send()
+    };
+    let first: serde_json::Value = add_signer()
+        .await
+        .expect("Add signer failed")
+        .json()
+        .await
+        .expect("Failed to parse signer");
+    let second: serde_json::Value = add_signer()
+        .await
+        .expect("Add signer failed")
+        .json()
+        .await
+        .expect("Failed to parse signer");
+    assert_eq!(first["id"], second["id"]);
+    add_signature_field(&client, &token, &doc_id, first["id"].as_str().unwrap()).await;
+
+    // The key is scoped to the endpoint as well as the payload
+    let res = client
+        .post(format!("{}/documents/{}/send", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .header("Idempotency-Key", &signer_key)
+        .send()
+        .await
+        .expect("Send failed");
+    assert_eq!(res.status(), 409);
+
+    let send_key = format!("{}-send", key);
+    let send = || {
+        client
+            .post(format!("{}/documents/{}/send", BASE_URL, doc_id))
+            .header("Authorization", format!("Bearer {}", token))
+            .header("Idempotency-Key", &send_key)
+            .send()
+    };
+    let res = send().await.expect("Send failed");
+    assert!(res.status().is_success());
+    let res = send().await.expect("Send failed");
+    assert!(res.status().is_success());
+    assert_eq!(res.headers()["idempotent-replayed"], "true");
+
+    let res = client
+        .get(format!("{}/documents/{}", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Get document failed");
+    let doc: serde_json::Value = res.json().await.expect("Failed to parse document");
+    assert_eq!(doc["status"], "pending");
+    assert_eq!(doc["signers"].as_array().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn test_idempotency_key_taken_over_after_lapsed_claim() {
+    use signvault::db::idempotency;
+    use signvault::models::idempotency::KeyClaim;
+
+    // Drives the key claims directly, as a request outliving its claim would
+    // take minutes
+    let Ok(database_url) = std::env::var("DATABASE_URL") else {
+        return;
+    };
+
+    let pool = sqlx::PgPool::connect(&database_url)
+        .await
+        .expect("Failed to connect to the database");
+    let user = signvault::db::user::create_user(
+        &pool,
+        &format!("idempotency-lease-{}@example.com", uuid::Uuid::new_v4()),
+        "",
+        "Idempotency Lease",
+        signvault::models::user::Role::Sender,
+    )
+    .await
+    .expect("Failed to create user");
+    let claim = || idempotency::claim_key(&pool, user.id, "lease-test", "hash", 24, 600);
+
+    let KeyClaim::Claimed { claimed_at: first } = claim().await.unwrap() else {
+        panic!("Fresh key was not claimed");
+    };
+    assert!(matches!(claim().await.unwrap(), KeyClaim::Taken(_)));
+
+    // The first request runs past its claim, and a retry takes the key over
+    sqlx::query(
+        "UPDATE idempotency_keys SET created_at = created_at - INTERVAL '1 hour' WHERE user_id = $1",
+    )
+    .bind(user.id)
+    .execute(&pool)
+    .await
+    .unwrap();
+    let KeyClaim::Claimed { claimed_at: retry } = claim().await.unwrap() else {
+        panic!("Lapsed claim was not taken over");
+    };
+
+    // When the first request finishes it touches neither the retry's claim
+    // nor its response
+    let stored =
+        idempotency::store_response(&pool, user.id, "lease-test", first, 201, None, b"first")
+            .await
+            .unwrap();
+    assert!(!stored);
+    idempotency::release_key(&pool, user.id, "lease-test", first)
+        .await
+        .unwrap();
+    let KeyClaim::Taken(held) = claim().await.unwrap() else {
+        panic!("Retry's claim was released");
+    };
+    assert_eq!(held.response_status, None);
+
+    let stored =
+        idempotency::store_response(&pool, user.id, "lease-test", retry, 201, None, b"retry")
+            .await
+            .unwrap();
+    assert!(stored);
+    let KeyClaim::Taken(done) = claim().await.unwrap() else {
+        panic!("Stored response was lost");
+    };
+    assert_eq!(done.response_status, Some(201));
+    assert_eq!(done.response_body.as_deref(), Some(&b"retry"[..]));
+
+    sqlx::query("DELETE FROM users WHERE id = $1")
+        .bind(user.id)
+        .execute(&pool)
+        .await
+        .expect("Failed to delete user");
+}
+
+#[tokio::test]
+async fn test_bulk_document_actions() {
+    wait_for_server().await;
+
+    let client = Client::new();
+    let token = login(&client, "admin@example.com", "change-this-secure-password").await;
+
+    let Some(first) = upload_sample_document(&client, &token, "Bulk Test 1").await else {
+        return;
+    };
+    let Some(second) = upload_sample_document(&client, &token, "Bulk Test 2").await else {
+        return;
+    };
+    let missing = "00000000-0000-0000-0000-000000000000";
+
+    let bulk = |body: serde_json::Value| {
+        client
+            .post(format!("{}/documents/bulk", BASE_URL))
+            .header("Authorization", format!("Bearer {}", token))
+            .json(&body)
+            .send()
+    };
+
+    let res = bulk(json!({ "action": "delete", "ids": [] }))
+        .await
+        .expect("Bulk request failed");
+    assert_eq!(res.status(), 422);
+
+    let too_many: Vec<String> = (0..101).map(|_| uuid::Uuid::new_v4().to_string()).collect();
+    let res = bulk(json!({ "action": "delete", "ids": too_many }))
+        .await
+        .expect("Bulk request failed");
+    assert_eq!(res.status(), 422);
+
+    let res = bulk(json!({
+        "action": "void",
+        "ids": [first, missing, second],
+        "reason": "Stale test envelopes"
+    }))
+    .await
+    .expect("Bulk request failed");
+    assert!(res.status().is_success());
+    let body: serde_json::Value = res.json().await.expect("Failed to parse results");
+    assert_eq!(body["succeeded"], 2);
+    assert_eq!(body["failed"], 1);
+    let results = body["results"].as_array().unwrap();
+    assert_eq!(results[0]["id"], first.as_str());
+    assert_eq!(results[0]["success"], true);
+    assert_eq!(results[1]["success"], false);
+    assert_eq!(results[1]["error"], "not_found");
+    assert_eq!(results[2]["id"], second.as_str());
+
+    let res = client
+        .get(format!("{}/documents/{}/audit", BASE_URL, first))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Get audit failed");
+    let logs: Vec<serde_json::Value> = res.json().await.expect("Failed to parse audit");
+    let voided: Vec<_> = logs
+        .iter()
+        .filter(|log| log["action"] == "document_voided")
+        .collect();
+    assert_eq!(voided.len(), 1);
+    assert_eq!(voided[0]["details"]["reason"], "Stale test envelopes");
+
+    let res = bulk(json!({ "action": "void", "ids": [first], "reason": "Again" }))
+        .await
+        .expect("Bulk request failed");
+    let body: serde_json::Value = res.json().await.expect("Failed to parse results");
+    assert_eq!(body["results"][0]["error"], "conflict");
+
+    // Repeated ids are only acted on once
+    let res = bulk(json!({ "action": "delete", "ids": [first, second, first] }))
+        .await
+        .expect("Bulk request failed");
+    let body: serde_json::Value = res.json().await.expect("Failed to parse results");
+    assert_eq!(body["succeeded"], 2);
+    assert_eq!(body["results"].as_array().unwrap().len(), 2);
+
+    let res = client
+        .get(format!("{}/documents/{}", BASE_URL, first))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Get document failed");
+    assert_eq!(res.status(), 404);
+}
+
+#[tokio::test]
+async fn test_document_tags() {
+    wait_for_server().await;
+
+    let client = Client::new();
+    let token = login(&client, "admin@example.com", "change-this-secure-password").await;
+
+    let Some(first) = upload_sample_document(&client, &token, "Tag Test 1").await else {
+        return;
+    };
+    let Some(second) = upload_sample_document(&client, &token, "Tag Test 2").await else {
+        return;
+    };
+
+    // Other tests share the account, so the tags are made unique to this run.
+    let run = uuid::Uuid::new_v4().simple().to_string();
+    let finance = format!("Finance-{}", &run[..8]);
+    let vendor = format!("vendor-{}", &run[..8]);
+
+    let add_tags = |id: &str, tags: serde_json::Value| {
+        client
+            .post(format!("{}/documents/{}/tags", BASE_URL, id))
+            .header("Authorization", format!("Bearer {}", token))
+            .json(&json!({ "tags": tags }))
+            .send()
+    };
+
+    let res = add_tags(&first, json!([format!("  {}  ", finance), vendor]))
+        .await
+        .expect("Add tags failed");
+    assert!(res.status().is_success());
+    let tags: Vec<String> = res.json().await.expect("Failed to parse tags");
+    assert_eq!(tags, [finance.clone(), vendor.clone()]);
+
+    // Adding a tag in another case keeps the spelling already there.
+    let res = add_tags(&second, json!([finance.to_uppercase()]))
+        .await
+        .expect("Add tags failed");
+    assert!(res.status().is_success());
+    let res = add_tags(&first, json!([finance.to_lowercase()]))
+        .await
+        .expect("Add tags failed");
+    let tags: Vec<String> = res.json().await.expect("Failed to parse tags");
+    assert_eq!(tags, [finance.clone(), vendor.clone()]);
+
+    let res = add_tags(&first, json!(["a,b"]))
+        .await
+        .expect("Add tags failed");
+    assert_eq!(res.status(), 422);
+
+    let many: Vec<String> = (0..19)
+        .map(|i| format!("extra-{}-{}", i, &run[..8]))
+        .collect();
+    let res = add_tags(&first, json!(many))
+        .await
+        .expect("Add tags failed");
+    assert_eq!(res.status(), 400);
+
+    let list = |query: String| {
+        client
+            .get(format!("{}/documents?limit=100&{}", BASE_URL, query))
+            .header("Authorization", format!("Bearer {}", token))
+            .send()
+    };
+    let ids = |body: &serde_json::Value| -> Vec<String> {
+        body["documents"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|doc| doc["id"].as_str().unwrap().to_string())
+            .collect()
+    };
+
+    let res = list(format!("tags={}", finance.to_lowercase()))
+        .await
+        .expect("List documents failed");
+    let body: serde_json::Value = res.json().await.expect("Failed to parse list");
+    assert_eq!(body["total"], 2);
+    assert_eq!(ids(&body), [second.clone(), first.clone()]);
+    assert_eq!(body["documents"][1]["tags"], json!([finance, vendor]));
+
+    let res = list(format!("tags={},{}", finance, vendor))
+        .await
+        .expect("List documents failed");
+    let body: serde_json::Value = res.json().await.expect("Failed to parse list");
+    assert_eq!(body["total"], 1);
+    assert_eq!(ids(&body), [first.as_str()]);
+
+    let res = list(format!("tags={},{}&tag_mode=any", finance, vendor))
+        .await
+        .expect("List documents failed");
+    let body: serde_json::Value = res.json().await.expect("Failed to parse list");
+    assert_eq!(body["total"], 2);
+
+    let res = client
+        .get(format!("{}/documents/tags", BASE_URL))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("List tags failed");
+    assert!(res.status().is_success());
+    let usage: Vec<serde_json::Value> = res.json().await.expect("Failed to parse tags");
+    let finance_usage = usage
+        .iter()
+        .find(|tag| tag["name"] == finance.as_str())
+        .expect("Tag not listed");
+    assert_eq!(finance_usage["document_count"], 2);
+    assert!(!usage
+        .iter()
+        .any(|tag| tag["name"] == finance.to_uppercase().as_str()));
+
+    let res = client
+        .delete(format!(
+            "{}/documents/{}/tags/{}",
+            BASE_URL,
+            first,
+            vendor.to_uppercase()
+        ))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Remove tag failed");
+    assert!(res.status().is_success());
+    let tags: Vec<String> = res.json().await.expect("Failed to parse tags");
+    assert_eq!(tags, [finance.as_str()]);
+
+    let res = client
+        .delete(format!("{}/documents/{}/tags/{}", BASE_URL, first, vendor))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Remove tag failed");
+    assert_eq!(res.status(), 404);
+}
+
+#[tokio::test]
+async fn test_document_search() {
+    wait_for_server().await;
+
+    let client = Client::new();
+    let token = login(&client, "admin@example.com", "change-this-secure-password").await;
+
+    // Other tests share the account, so the search term is unique to this run.
+    let run = format!("srch{}", &uuid::Uuid::new_v4().simple().to_string()[..8]);
+
+    let Some(titled) =
+        upload_sample_document(&client, &token, &format!("Lease {} 2024", run)).await
+    else {
+        return;
+    };
+    let Some(signed) = upload_sample_document(&client, &token, "Search Test Contract").await else {
+        return;
+    };
+
+    let res = client
+        .post(format!("{}/documents/{}/signers", BASE_URL, signed))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({
+            "email": format!("jane@{}.example.com", run),
+            "name": "Jane Searcher"
+        }))
+        .send()
+        .await
+        .expect("Add signer failed");
+    let signer: serde_json::Value = res.json().await.expect("Failed to parse signer");
+
+    let search = |query: String| {
+        client
+            .get(format!("{}/documents/search?{}", BASE_URL, query))
+            .header("Authorization", format!("Bearer {}", token))
+            .send()
+    };
+
+    let res = search(format!("q={}", run.to_uppercase()))
+        .await
+        .expect("Search failed");
+    assert!(res.status().is_success());
+    let body: serde_json::Value = res.json().await.expect("Failed to parse results");
+    assert_eq!(body["total"], 2);
+    let documents = body["documents"].as_array().unwrap();
+    assert_eq!(documents[0]["id"], titled.as_str());
+    assert_eq!(documents[0]["matches"][0]["field"], "title");
+    assert_eq!(documents[1]["id"], signed.as_str());
+    let matched = &documents[1]["matches"][0];
+    assert_eq!(matched["field"], "signer_email");
+    assert_eq!(
+        matched["fragments"],
+        json!([
+            { "text": "jane@", "matched": false },
+            { "text": run, "matched": true },
+            { "text": ".example.com", "matched": false },
+        ])
+    );
+
+    let res = search(format!("q={}&limit=1&offset=1", run))
+        .await
+        .expect("Search failed");
+    let body: serde_json::Value = res.json().await.expect("Failed to parse results");
+    assert_eq!(body["total"], 2);
+    assert_eq!(body["documents"].as_array().unwrap().len(), 1);
+    assert_eq!(body["documents"][0]["id"], signed.as_str());
+
+    // Wildcards in the term are matched literally.
+    let res = search(format!("q={}%25", run))
+        .await
+        .expect("Search failed");
+    let body: serde_json::Value = res.json().await.expect("Failed to parse results");
+    assert_eq!(body["total"], 0);
+
+    let res = client
+        .put(format!(
+            "{}/documents/{}/signers/{}",
+            BASE_URL,
+            signed,
+            signer["id"].as_str().unwrap()
+        ))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({ "email": "jane@elsewhere.example.com" }))
+        .send()
+        .await
+        .expect("Update signer failed");
+    assert!(res.status().is_success());
+
+    let res = search(format!("q={}", run)).await.expect("Search failed");
+    let body: serde_json::Value = res.json().await.expect("Failed to parse results");
+    assert_eq!(body["total"], 1);
+
+    let res = search("q=%20".to_string()).await.expect("Search failed");
+    assert_eq!(res.status(), 422);
+}
+
+#[tokio::test]
+async fn test_conditional_edits() {
+    wait_for_server().await;
+
+    let client = Client::new();
+    let token = login(&client, "admin@example.com", "change-this-secure-password").await;
+
+    let Some(doc_id) = upload_sample_document(&client, &token, "Conditional Edit Test").await
+    else {
+        return;
+    };
+
+    let res = client
+        .post(format!("{}/documents/{}/fields", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({
+            "field_type": "text",
+            "page": 1,
+            "x": 100.0,
+            "y": 500.0,
+            "width": 200.0,
+            "height": 50.0
+        }))
+        .send()
+        .await
+        .expect("Add field failed");
+    let field: serde_json::Value = res.json().await.expect("Failed to parse field");
+    let field_url = format!(
+        "{}/documents/{}/fields/{}",
+        BASE_URL,
+        doc_id,
+        field["id"].as_str().unwrap()
+    );
+    let read_at = field["updated_at"]
+        .as_str()
+        .expect("No updated_at")
+        .to_string();
+
+    // Fresh precondition
+    let res = client
+        .put(&field_url)
+        .header("Authorization", format!("Bearer {}", token))
+        .header("If-Match", format!("\"{}\"", read_at))
+        .json(&json!({ "x": 120.0 }))
+        .send()
+        .await
+        .expect("Update field failed");
+    assert!(res.status().is_success());
+    let moved: serde_json::Value = res.json().await.expect("Failed to parse field");
+
+    // A second tab still holding the first read loses, and gets the field as it is now
+    let res = client
+        .put(&field_url)
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({ "x": 300.0, "expected_updated_at": read_at }))
+        .send()
+        .await
+        .expect("Update field failed");
+    assert_eq!(res.status(), 409);
+    let body: serde_json::Value = res.json().await.expect("Failed to parse error");
+    assert_eq!(body["error"], "edit_conflict");
+    assert_eq!(body["current"]["x"], 120.0);
+    assert_eq!(body["current"]["updated_at"], moved["updated_at"]);
+
+    // Without a precondition the last write wins
+    let res = client
+        .put(&field_url)
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({ "x": 300.0 }))
+        .send()
+        .await
+        .expect("Update field failed");
+    assert!(res.status().is_success());
+
+    let res = client
+        .post(format!("{}/documents/{}/signers", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({
+            "email": "conditional@example.com",
+            "name": "Conditional Signer"
+        }))
+        .send()
+        .await
+        .expect("Add signer failed");
+    let signer: serde_json::Value = res.json().await.expect("Failed to parse signer");
+    let signer_url = format!(
+        "{}/documents/{}/signers/{}",
+        BASE_URL,
+        doc_id,
+        signer["id"].as_str().unwrap()
+    );
+
+    let res = client
+        .put(&signer_url)
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({ "name": "Renamed Signer", "expected_updated_at": signer["updated_at"] }))
+        .send()
+        .await
+        .expect("Update signer failed");
+    assert!(res.status().is_success());
+
+    let res = client
+        .put(&signer_url)
+        .header("Authorization", format!("Bearer {}", token))
+        .header(
+            "If-Match",
+            format!("\"{}\"", signer["updated_at"].as_str().unwrap()),
+        )
+        .json(&json!({ "name": "Stale Name" }))
+        .send()
+        .await
+        .expect("Update signer failed");
+    assert_eq!(res.status(), 409);
+    let body: serde_json::Value = res.json().await.expect("Failed to parse error");
+    assert_eq!(body["current"]["name"], "Renamed Signer");
+
+    let res = client
+        .get(format!("{}/documents/{}", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Get document failed");
+    let document: serde_json::Value = res.json().await.expect("Failed to parse document");
+    let document_read_at = document["updated_at"].as_str().unwrap().to_string();
+
+    let res = client
+        .patch(format!("{}/documents/{}", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .header("If-Match", format!("\"{}\"", document_read_at))
+        .json(&json!({ "title": "Renamed Document" }))
+        .send()
+        .await
+        .expect("Update document failed");
+    assert!(res.status().is_success());
+    let renamed: serde_json::Value = res.json().await.expect("Failed to parse document");
+    assert_eq!(renamed["title"], "Renamed Document");
+
+    let res = client
+        .patch(format!("{}/documents/{}", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .header("If-Match", format!("\"{}\"", document_read_at))
+        .json(&json!({ "title": "Stale Title" }))
+        .send()
+        .await
+        .expect("Update document failed");
+    assert_eq!(res.status(), 409);
+    let body: serde_json::Value = res.json().await.expect("Failed to parse error");
+    assert_eq!(body["current"]["title"], "Renamed Document");
+
+    let res = client
+        .patch(format!("{}/documents/{}", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .header("If-Match", "\"not a timestamp\"")
+        .json(&json!({ "title": "Bad Precondition" }))
+        .send()
+        .await
+        .expect("Update document failed");
+    assert_eq!(res.status(), 400);
+}
+
+#[tokio::test]
+async fn test_field_operations() {
+    wait_for_server().await;
+
+    let client = Client::new();
+    let token = login(&client, "admin@example.com", "change-this-secure-password").await;
+
+    // Upload document
+    let pdf_content = include_bytes!("../tests/fixtures/sample.pdf");
+    let form = reqwest::multipart::Form::new()
+        .text("title", "Field Operations Test")
+        .text("self_sign_only", "true")
+        .part(
+            "file",
+            reqwest::multipart::Part::bytes(pdf_content.to_vec())
+                .file_name("test.pdf")
+                .mime_str("application/pdf")
+                .unwrap(),
+        );
+
+    let res = client
+        .post(format!("{}/documents", BASE_URL))
+        .header("Authorization", format!("Bearer {}", token))
+        .multipart(form)
+        .send()
+        .await
+        .expect("Upload failed");
+
+    if !res.status().is_success() {
+        return;
+    }
+
+    let doc: serde_json::Value = res.json().await.expect("Failed to parse response");
+    let doc_id = doc["id"].as_str().expect("No document ID");
+
+    // Add signature field
+    let res = client
+        .post(format!("{}/documents/{}/fields", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({
+            "field_type": "signature",
+            "page": 1,
+            "x": 100.0,
+            "y": 500.0,
+            "width": 200.0,
+            "height": 50.0
+        }))
+        .send()
+        .await
+        .expect("Add field failed");
+
+    assert!(res.status().is_success());
+    let field: serde_json::Value = res.json().await.expect("Failed to parse field");
+    let field_id = field["id"].as_str().expect("No field ID");
+
+    // Add date field
+    let res = client
+        .post(format!("{}/documents/{}/fields", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({
+            "field_type": "date",
+            "page": 1,
+            "x": 100.0,
+            "y": 600.0,
+            "width": 150.0,
+            "height": 30.0,
+            "date_format": "MM/DD/YYYY"
+        }))
+        .send()
+        .await
+        .expect("Add date field failed");
+
+    assert!(res.status().is_success());
+
+    // Add text field
+    let res = client
+        .post(format!("{}/documents/{}/fields", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({
+            "field_type": "text",
+            "page": 1,
+            "x": 100.0,
+            "y": 650.0,
+            "width": 200.0,
+            "height": 30.0,
+            "value": "Sample Text"
+        }))
+        .send()
+        .await
+        .expect("Add text field failed");
+
+    assert!(res.status().is_success());
+
+    // Update field
+    let res = client
+        .put(format!(
+            "{}/documents/{}/fields/{}",
+            BASE_URL, doc_id, field_id
+        ))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({
+            "x": 150.0,
+            "y": 550.0,
+            "width": 250.0,
+            "height": 60.0
+        }))
+        .send()
+        .await
+        .expect("Update field failed");
+
+    assert!(res.status().is_success());
+
+    // Delete field
+    let res = client
+        .delete(format!(
+            "{}/documents/{}/fields/{}",
+            BASE_URL, doc_id, field_id
+        ))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Delete field failed");
+
+    assert!(res.status().is_success());
+
+    // The audit trail records what the update changed and what was deleted.
+    let res = client
+        .get(format!("{}/documents/{}/audit", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Get audit failed");
+    let logs: Vec<serde_json::Value> = res.json().await.expect("Failed to parse audit");
+    let updated = logs
+        .iter()
+        .find(|log| log["action"] == "field_updated")
+        .expect("No field_updated entry");
+    let changed = &updated["details"]["changed"];
+    assert_eq!(changed["x"]["to"], 150.0);
+    assert_eq!(changed["height"]["to"], 60.0);
+    assert!(changed["x"]["from"].is_number());
+    assert!(changed.get("updated_at").is_none());
+    assert!(changed.get("page").is_none());
+    let deleted = logs
+        .iter()
+        .find(|log| log["action"] == "field_deleted")
+        .expect("No field_deleted entry");
+    assert_eq!(deleted["details"]["field"]["id"], field_id);
+    assert_eq!(deleted["details"]["field"]["x"], 150.0);
+
+    // Cleanup
+    client
+        .delete(format!("{}/documents/{}", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .ok();
+}
+
+async fn upload_sample_document(client: &Client, token: &str, title: &str) -> Option<String> {
+    upload_sample_document_with(client, token, title, &[]).await
+}
+
+async fn upload_sample_document_with(
+    client: &Client,
+    token: &str,
+    title: &str,
+    options: &[(&str, &str)],
+) -> Option<String> {
+    let pdf_content = include_bytes!("../tests/fixtures/sample.pdf");
+    let mut form = reqwest::multipart::Form::new()
+        .text("title", title.to_string())
+        .text("self_sign_only", "false");
+    for (name, value) in options {
+        form = form.text(name.to_string(), value.to_string());
+    }
+    let form = form.part(
+        "file",
+        reqwest::multipart::Part::bytes(pdf_content.to_vec())
+            .file_name("test.pdf")
+            .mime_str("application/pdf")
+            .unwrap(),
+    );
+
+    let res = client
+        .post(format!("{}/documents", BASE_URL))
+        .header("Authorization", format!("Bearer {}", token))
+        .multipart(form)
+        .send()
+        .await
+        .expect("Upload failed");
+
+    if !res.status().is_success() {
+        return None;
+    }
+
+    let doc: serde_json::Value = res.json().await.expect("Failed to parse response");
+    doc["id"].as_str().map(|s| s.to_string())
+}
+
+/// Places a signature field for the signer, which every signer needs before
+/// the document can be sent.
+async fn add_signature_field(
+    client: &Client,
+    token: &str,
+    doc_id: &str,
+    signer_id: &str,
+) -> String {
+    let res = client
+        .post(format!("{}/documents/{}/fields", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({
+            "field_type": "signature",
+            "page": 1,
+            "x": 100.0,
+            "y": 600.0,
+            "width": 200.0,
+            "height": 50.0,
+            "signer_id": signer_id
+        }))
+        .send()
+        .await
+        .expect("Add field failed");
+    assert!(res.status().is_success());
+    let field: serde_json::Value = res.json().await.expect("Failed to parse field");
+    field["id"].as_str().expect("No field id").to_string()
+}
+
+#[tokio::test]
+async fn test_approver_workflow() {
+    wait_for_server().await;
+
+    let client = Client::new();
+    let token = login(&client, "admin@example.com", "change-this-secure-password").await;
+
+    let Some(doc_id) = upload_sample_document(&client, &token, "Approval Test").await else {
+        return;
+    };
+
+    let res = client
+        .post(format!("{}/documents/{}/signers", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({
+            "email": "manager@example.com",
+            "name": "Approving Manager",
+            "role": "approver"
+        }))
+        .send()
+        .await
+        .expect("Add approver failed");
+
+    assert!(res.status().is_success());
+    let approver: serde_json::Value = res.json().await.expect("Failed to parse signer");
+    assert_eq!(approver["role"], "approver");
+    let approver_id = approver["id"].as_str().expect("No signer ID");
+    let access_token = approver["access_token"].as_str().expect("No access token");
+
+    // Approvers never place signatures
+    let res = client
+        .post(format!("{}/documents/{}/fields", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({
+            "field_type": "signature",
+            "page": 1,
+            "x": 100.0,
+            "y": 500.0,
+            "width": 200.0,
+            "height": 50.0,
+            "signer_id": approver_id
+        }))
+        .send()
+        .await
+        .expect("Add field failed");
+
+    assert_eq!(res.status(), 400);
+
+    let res = client
+        .post(format!("{}/documents/{}/send", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Send failed");
+
+    assert!(res.status().is_success());
+
+    let res = client
+        .get(format!("{}/sign/{}", BASE_URL, access_token))
+        .send()
+        .await
+        .expect("Get signing session failed");
+
+    assert!(res.status().is_success());
+    let session: serde_json::Value = res.json().await.expect("Failed to parse session");
+    assert_eq!(session["signer"]["role"], "approver");
+    assert_eq!(
+        session["pages"].as_array().unwrap().len() as u64,
+        session["page_count"].as_u64().unwrap()
+    );
+
+    // Rejecting requires a reason
+    let res = client
+        .post(format!("{}/sign/{}/approve", BASE_URL, access_token))
+        .json(&json!({ "decision": "reject" }))
+        .send()
+        .await
+        .expect("Approve request failed");
+
+    assert_eq!(res.status(), 400);
+
+    let res = client
+        .post(format!("{}/sign/{}/approve", BASE_URL, access_token))
+        .json(&json!({ "decision": "approve" }))
+        .send()
+        .await
+        .expect("Approve request failed");
+
+    assert!(res.status().is_success());
+    let result: serde_json::Value = res.json().await.expect("Failed to parse result");
+    assert_eq!(result["approved"], true);
+    assert_eq!(result["document_completed"], true);
+
+    let res = client
+        .get(format!("{}/documents/{}/certificate", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Get certificate failed");
+
+    assert!(res.status().is_success());
+    let certificate: serde_json::Value = res.json().await.expect("Failed to parse certificate");
+    assert_eq!(certificate["approvals"][0]["email"], "manager@example.com");
+    assert!(certificate["signers"].as_array().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_in_person_signing_session() {
+    wait_for_server().await;
+
+    let client = Client::new();
+    let token = login(&client, "admin@example.com", "change-this-secure-password").await;
+
+    let Some(doc_id) = upload_sample_document(&client, &token, "In-Person Test").await else {
+        return;
+    };
+
+    let res = client
+        .post(format!("{}/documents/{}/signers", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({
+            "email": "buyer@example.com",
+            "name": "Home Buyer"
+        }))
+        .send()
+        .await
+        .expect("Add signer failed");
+
+    let signer: serde_json::Value = res.json().await.expect("Failed to parse signer");
+    let signer_id = signer["id"].as_str().expect("No signer ID");
+    let emailed_token = signer["access_token"].as_str().expect("No access token");
+
+    // Not available before the envelope is sent
+    let res = client
+        .post(format!(
+            "{}/documents/{}/signers/{}/in-person-session",
+            BASE_URL, doc_id, signer_id
+        ))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Request failed");
+
+    assert_eq!(res.status(), 400);
+
+    // The buyer only acknowledges in person, without a signature field
+    let res = client
+        .post(format!("{}/documents/{}/send?force=true", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Send failed");
+    assert!(res.status().is_success());
+
+    let res = client
+        .post(format!(
+            "{}/documents/{}/signers/{}/in-person-session",
+            BASE_URL, doc_id, signer_id
+        ))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Request failed");
+
+    assert!(res.status().is_success());
+    let session: serde_json::Value = res.json().await.expect("Failed to parse session");
+    let session_token = session["token"].as_str().expect("No session token");
+    assert_ne!(session_token, emailed_token);
+    assert!(session["signing_url"]
+        .as_str()
+        .unwrap()
+        .ends_with(session_token));
+
+    let res = client
+        .get(format!("{}/sign/{}", BASE_URL, session_token))
+        .send()
+        .await
+        .expect("Get signing session failed");
+
+    assert!(res.status().is_success());
+    let signing_session: serde_json::Value = res.json().await.expect("Failed to parse session");
+    assert_eq!(signing_session["signer"]["email"], "buyer@example.com");
+
+    let res = client
+        .post(format!("{}/sign/{}/submit", BASE_URL, session_token))
+        .json(&json!({ "signatures": [], "field_values": [] }))
+        .send()
+        .await
+        .expect("Submit signing failed");
+
+    assert!(res.status().is_success());
+
+    let res = client
+        .get(format!("{}/documents/{}/audit", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Get audit failed");
+
+    let audit_logs: serde_json::Value = res.json().await.expect("Failed to parse audit");
+    let signed = audit_logs
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|l| l["action"] == "signer_signed")
+        .expect("No signer_signed entry");
+    assert_eq!(signed["details"]["in_person"], true);
+    assert_eq!(signed["signer_id"], signer_id);
+    assert!(signed["user_id"].is_string());
+}
+
+#[tokio::test]
+async fn test_self_sign_workflow() {
+    wait_for_server().await;
+
+    let client = Client::new();
+    let token = login(&client, "admin@example.com", "change-this-secure-password").await;
+
+    let pdf_content = include_bytes!("../tests/fixtures/sample.pdf");
+    let form = reqwest::multipart::Form::new()
+        .text("title", "Self Sign Test")
+        .text("self_sign_only", "true")
+        .part(
+            "file",
+            reqwest::multipart::Part::bytes(pdf_content.to_vec())
+                .file_name("test.pdf")
+                .mime_str("application/pdf")
+                .unwrap(),
+        );
+
+    let res = client
+        .post(format!("{}/documents", BASE_URL))
+        .header("Authorization", format!("Bearer {}", token))
+        .multipart(form)
+        .send()
+        .await
+        .expect("Upload failed");
+
+    if !res.status().is_success() {
+        return;
+    }
+
+    let doc: serde_json::Value = res.json().await.expect("Failed to parse response");
+    let doc_id = doc["id"].as_str().expect("No document ID");
+
+    let res = client
+        .post(format!("{}/documents/{}/fields", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({
+            "field_type": "signature",
+            "page": 1,
+            "x": 100.0,
+            "y": 500.0,
+            "width": 200.0,
+            "height": 50.0
+        }))
+        .send()
+        .await
+        .expect("Add field failed");
+
+    let field: serde_json::Value = res.json().await.expect("Failed to parse field");
+    let field_id = field["id"].as_str().expect("No field ID");
+
+    let signing_request = json!({
+        "signatures": [{
+            "field_id": field_id,
+            "signature_data": "data:image/png;base64,iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAYAAAAfFcSJAAAADUlEQVR42mNk+M9QDwADhgGAWjR9awAAAABJRU5ErkJggg=="
+        }],
+        "field_values": []
+    });
+
+    // Must be sent first
+    let res = client
+        .post(format!("{}/documents/{}/self-sign", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&signing_request)
+        .send()
+        .await
+        .expect("Self-sign failed");
+
+    assert_eq!(res.status(), 400);
+
+    let res = client
+        .post(format!("{}/documents/{}/send", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Send failed");
+
+    assert!(res.status().is_success());
+    let sent: serde_json::Value = res.json().await.expect("Failed to parse response");
+    assert_eq!(sent["status"], "pending");
+
+    let res = client
+        .post(format!("{}/documents/{}/self-sign", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&signing_request)
+        .send()
+        .await
+        .expect("Self-sign failed");
+
+    assert!(
+        res.status().is_success(),
+        "Self-sign failed: {:?}",
+        res.status()
+    );
+    let result: serde_json::Value = res.json().await.expect("Failed to parse result");
+    assert_eq!(result["document_completed"], true);
+
+    let res = client
+        .get(format!("{}/documents/{}/audit", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Get audit failed");
+
+    let audit_logs: serde_json::Value = res.json().await.expect("Failed to parse audit");
+    let applied = audit_logs
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|l| l["action"] == "signature_applied")
+        .expect("No signature_applied entry");
+    assert!(applied["user_id"].is_string());
+    assert_eq!(applied["details"]["self_signed"], true);
+}
+
+#[tokio::test]
+async fn test_document_package() {
+    use async_zip::base::read::mem::ZipFileReader;
+    use futures::AsyncReadExt;
+    use sha2::{Digest, Sha256};
+
+    wait_for_server().await;
+
+    let client = Client::new();
+    let token = login(&client, "admin@example.com", "change-this-secure-password").await;
+
+    let pdf_content = include_bytes!("../tests/fixtures/sample.pdf");
+    let form = reqwest::multipart::Form::new()
+        .text("title", "Package Test")
+        .text("self_sign_only", "true")
+        .part(
+            "file",
+            reqwest::multipart::Part::bytes(pdf_content.to_vec())
+                .file_name("lease.pdf")
+                .mime_str("application/pdf")
+                .unwrap(),
+        );
+    let res = client
+        .post(format!("{}/documents", BASE_URL))
+        .header("Authorization", format!("Bearer {}", token))
+        .multipart(form)
+        .send()
+        .await
+        .expect("Upload failed");
+    if !res.status().is_success() {
+        return;
+    }
+    let doc: serde_json::Value = res.json().await.expect("Failed to parse response");
+    let doc_id = doc["id"].as_str().expect("No document ID").to_string();
+
+    let download = || {
+        client
+            .get(format!("{}/documents/{}/package.zip", BASE_URL, doc_id))
+            .header("Authorization", format!("Bearer {}", token))
+            .send()
+    };
+
+    let res = download().await.expect("Package download failed");
+    assert_eq!(res.status(), 400);
+
+    let res = client
+        .post(format!("{}/documents/{}/fields", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({
+            "field_type": "signature",
+            "page": 1,
+            "x": 100.0,
+            "y": 500.0,
+            "width": 200.0,
+            "height": 50.0
+        }))
+        .send()
+        .await
+        .expect("Add field failed");
+    let field: serde_json::Value = res.json().await.expect("Failed to parse field");
+
+    client
+        .post(format!("{}/documents/{}/send", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Send failed");
+    let res = client
+        .post(format!("{}/documents/{}/self-sign", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({
+            "signatures": [{
+                "field_id": field["id"],
+                "signature_data": "data:image/png;base64,iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAYAAAAfFcSJAAAADUlEQVR42mNk+M9QDwADhgGAWjR9awAAAABJRU5ErkJggg=="
+            }],
+            "field_values": []
+        }))
+        .send()
+        .await
+        .expect("Self-sign failed");
+    assert!(res.status().is_success());
+
+    let res = download().await.expect("Package download failed");
+    assert!(res.status().is_success());
+    assert_eq!(res.headers()["content-type"], "application/zip");
+    assert!(res.headers()["content-disposition"]
+        .to_str()
+        .unwrap()
+        .contains("lease-package.zip"));
+    let data = res.bytes().await.expect("Failed to read package").to_vec();
+
+    let zip = ZipFileReader::new(data).await.expect("Not a ZIP file");
+    let mut files = std::collections::HashMap::new();
+    for (index, entry) in zip.file().entries().iter().enumerate() {
+        let mut content = Vec::new();
+        zip.reader_with_entry(index)
+            .await
+            .unwrap()
+            .read_to_end(&mut content)
+            .await
+            .unwrap();
+        files.insert(entry.filename().as_str().unwrap().to_string(), content);
+    }
+
+    assert_eq!(files["original.pdf"], pdf_content.to_vec());
+    for name in ["certificate.json", "audit-trail.json", "manifest.json"] {
+        assert!(files.contains_key(name), "{} missing from package", name);
+    }
+
+    let manifest: serde_json::Value = serde_json::from_slice(&files["manifest.json"]).unwrap();
+    assert_eq!(manifest["document_id"], doc_id.as_str());
+    let entries = manifest["entries"].as_array().unwrap();
+    assert_eq!(entries.len(), files.len() - 1);
+    for entry in entries {
+        let content = &files[entry["name"].as_str().unwrap()];
+        assert_eq!(entry["sha256"], hex::encode(Sha256::digest(content)));
+        assert_eq!(entry["size"], content.len());
+    }
+
+    let certificate: serde_json::Value =
+        serde_json::from_slice(&files["certificate.json"]).unwrap();
+    assert_eq!(certificate["document_id"], doc_id.as_str());
+
+    let audit_trail = String::from_utf8(files["audit-trail.json"].clone()).unwrap();
+    assert!(audit_trail.contains("document_package_downloaded"));
+    let signature = &entries
+        .iter()
+        .find(|entry| entry["name"] == "audit-trail.json")
+        .unwrap()["server_signature"];
+    let res = client
+        .post(format!("{}/server-key/verify", BASE_URL))
+        .json(&json!({
+            "message": audit_trail,
+            "key_id": signature["key_id"],
+            "signature": signature["signature"]
+        }))
+        .send()
+        .await
+        .expect("Verify failed");
+    let verification: serde_json::Value = res.json().await.expect("Failed to parse result");
+    assert_eq!(verification["valid"], true);
+}
+
+#[tokio::test]
+async fn test_saved_signatures() {
+    wait_for_server().await;
+
+    let client = Client::new();
+    let token = login(&client, "admin@example.com", "change-this-secure-password").await;
+
+    let res = client
+        .post(format!("{}/auth/signatures", BASE_URL))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({
+            "name": "Not an image",
+            "signature_data": "data:image/svg+xml;base64,PHN2Zz48L3N2Zz4="
+        }))
+        .send()
+        .await
+        .expect("Request failed");
+
+    assert_eq!(res.status(), 400);
+
+    let res = client
+        .post(format!("{}/auth/signatures", BASE_URL))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({
+            "name": "Full signature",
+            "signature_data": "data:image/png;base64,iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAYAAAAfFcSJAAAADUlEQVR42mNk+M9QDwADhgGAWjR9awAAAABJRU5ErkJggg=="
+        }))
+        .send()
+        .await
+        .expect("Request failed");
+
+    assert!(res.status().is_success());
+    let saved: serde_json::Value = res.json().await.expect("Failed to parse response");
+    let saved_id = saved["id"].as_str().expect("No signature ID");
+
+    let res = client
+        .get(format!("{}/auth/signatures", BASE_URL))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Request failed");
+
+    let list: serde_json::Value = res.json().await.expect("Failed to parse response");
+    assert!(list.as_array().unwrap().iter().any(|s| s["id"] == saved_id));
+
+    let res = client
+        .delete(format!("{}/auth/signatures/{}", BASE_URL, saved_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Request failed");
+
+    assert!(res.status().is_success());
+
+    let res = client
+        .delete(format!("{}/auth/signatures/{}", BASE_URL, saved_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Request failed");
+
+    assert_eq!(res.status(), 404);
+}
+
+#[tokio::test]
+async fn test_typed_signature() {
+    wait_for_server().await;
+
+    let client = Client::new();
+    let token = login(&client, "admin@example.com", "change-this-secure-password").await;
+
+    let Some(doc_id) = upload_sample_document(&client, &token, "Typed Signature Test").await else {
+        return;
+    };
+
+    let res = client
+        .post(format!("{}/documents/{}/signers", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({
+            "email": "typed@example.com",
+            "name": "Typed Signer"
+        }))
+        .send()
+        .await
+        .expect("Add signer failed");
+
+    let signer: serde_json::Value = res.json().await.expect("Failed to parse signer");
+    let signer_id = signer["id"].as_str().expect("No signer ID");
+    let access_token = signer["access_token"].as_str().expect("No access token");
+
+    let res = client
+        .post(format!("{}/documents/{}/fields", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({
+            "field_type": "signature",
+            "page": 1,
+            "x": 100.0,
+            "y": 500.0,
+            "width": 200.0,
+            "height": 50.0,
+            "signer_id": signer_id
+        }))
+        .send()
+        .await
+        .expect("Add field failed");
+
+    let field: serde_json::Value = res.json().await.expect("Failed to parse field");
+    let field_id = field["id"].as_str().expect("No field ID");
+
+    client
+        .post(format!("{}/documents/{}/send", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Send failed");
+
+    let res = client
+        .get(format!("{}/sign/{}", BASE_URL, access_token))
+        .send()
+        .await
+        .expect("Get session failed");
+
+    let session: serde_json::Value = res.json().await.expect("Failed to parse session");
+    let fonts = session["signature_fonts"]
+        .as_array()
+        .expect("No signature fonts")
+        .clone();
+
+    let res = client
+        .post(format!("{}/sign/{}/submit", BASE_URL, access_token))
+        .json(&json!({
+            "signatures": [{
+                "field_id": field_id,
+                "signature_data": "data:image/svg+xml;base64,PHN2Zz48L3N2Zz4="
+            }],
+            "field_values": []
+        }))
+        .send()
+        .await
+        .expect("Submit failed");
+
+    assert_eq!(res.status(), 400);
+    let error: serde_json::Value = res.json().await.expect("Failed to parse error");
+    assert!(error["message"].as_str().unwrap().contains(field_id));
+
+    let res = client
+        .post(format!("{}/sign/{}/submit", BASE_URL, access_token))
+        .json(&json!({
+            "signatures": [{
+                "field_id": field_id,
+                "signature_type": "typed",
+                "text": "Typed Signer",
+                "font": "not-a-whitelisted-font"
+            }],
+            "field_values": []
+        }))
+        .send()
+        .await
+        .expect("Submit failed");
+
+    assert_eq!(res.status(), 400);
+
+    let Some(font) = fonts.first() else {
+        return;
+    };
+
+    let res = client
+        .post(format!("{}/sign/{}/submit", BASE_URL, access_token))
+        .json(&json!({
+            "signatures": [{
+                "field_id": field_id,
+                "signature_type": "typed",
+                "text": "Typed Signer",
+                "font": font
+            }],
+            "field_values": []
+        }))
+        .send()
+        .await
+        .expect("Submit failed");
+
+    assert!(res.status().is_success());
+}
+
+/// A submission failing after its signatures were written must leave no
+/// trace: the field value is checked only once the signature is inserted.
+#[tokio::test]
+async fn test_failed_signing_submission_persists_nothing() {
+    wait_for_server().await;
+
+    let client = Client::new();
+    let token = login(&client, "admin@example.com", "change-this-secure-password").await;
+
+    let Some(doc_id) = upload_sample_document(&client, &token, "Atomic Signing Test").await else {
+        return;
+    };
+
+    let res = client
+        .post(format!("{}/documents/{}/signers", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({
+            "email": "atomic@example.com",
+            "name": "Atomic Signer"
+        }))
+        .send()
+        .await
+        .expect("Add signer failed");
+    let signer: serde_json::Value = res.json().await.expect("Failed to parse signer");
+    let signer_id = signer["id"].as_str().expect("No signer ID");
+    let access_token = signer["access_token"].as_str().expect("No access token");
+
+    let res = client
+        .post(format!("{}/documents/{}/fields", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({
+            "field_type": "signature",
+            "page": 1,
+            "x": 100.0,
+            "y": 500.0,
+            "width": 200.0,
+            "height": 50.0,
+            "signer_id": signer_id
+        }))
+        .send()
+        .await
+        .expect("Add field failed");
+    let field: serde_json::Value = res.json().await.expect("Failed to parse field");
+
+    client
+        .post(format!("{}/documents/{}/send", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Send failed");
+
+    let submit = |field_values: serde_json::Value| {
+        client
+            .post(format!("{}/sign/{}/submit", BASE_URL, access_token))
+            .json(&json!({
+                "signatures": [{
+                    "field_id": field["id"],
+                    "signature_data": "data:image/png;base64,iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAYAAAAfFcSJAAAADUlEQVR42mNk+M9QDwADhgGAWjR9awAAAABJRU5ErkJggg=="
+                }],
+                "field_values": field_values
+            }))
+            .send()
+    };
+
+    let res = submit(json!([{
+        "field_id": "00000000-0000-0000-0000-000000000000",
+        "value": "never stored"
+    }]))
+    .await
+    .expect("Submit failed");
+    assert_eq!(res.status(), 400);
+
+    let res = client
+        .get(format!("{}/documents/{}", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Get document failed");
+    let document: serde_json::Value = res.json().await.expect("Failed to parse document");
+    assert_eq!(document["completed_signers"], 0);
+    assert_eq!(document["signers"][0]["status"], "pending");
+
+    let res = client
+        .get(format!("{}/documents/{}/audit", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Get audit failed");
+    let logs: Vec<serde_json::Value> = res.json().await.expect("Failed to parse audit");
+    assert!(!logs
+        .iter()
+        .any(|log| log["action"] == "signature_applied" || log["action"] == "signer_signed"));
+
+    let res = submit(json!([])).await.expect("Submit failed");
+    assert!(res.status().is_success());
+
+    let res = client
+        .get(format!("{}/documents/{}", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Get document failed");
+    let document: serde_json::Value = res.json().await.expect("Failed to parse document");
+    assert_eq!(document["status"], "completed");
+    assert_eq!(document["completed_signers"], 1);
+
+    let res = client
+        .get(format!("{}/documents/{}/audit", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Get audit failed");
+    let logs: Vec<serde_json::Value> = res.json().await.expect("Failed to parse audit");
+    let applied = logs
+        .iter()
+        .filter(|log| log["action"] == "signature_applied")
+        .count();
+    assert_eq!(applied, 1);
+}
+
+#[tokio::test]
+async fn test_concurrent_submissions_complete_document_once() {
+    wait_for_server().await;
+
+    let client = Client::new();
+    let token = login(&client, "admin@example.com", "change-this-secure-password").await;
+
+    let Some(doc_id) = upload_sample_document(&client, &token, "Concurrent Signing Test").await
+    else {
+        return;
+    };
+
+    let mut participants = Vec::new();
+    for (i, email) in ["race-a@example.com", "race-b@example.com"]
+        .iter()
+        .enumerate()
+    {
+        let res = client
+            .post(format!("{}/documents/{}/signers", BASE_URL, doc_id))
+            .header("Authorization", format!("Bearer {}", token))
+            .json(&json!({
+                "email": email,
+                "name": format!("Racing Signer {}", i + 1)
+            }))
+            .send()
+            .await
+            .expect("Add signer failed");
+        let signer: serde_json::Value = res.json().await.expect("Failed to parse signer");
+
+        let res = client
+            .post(format!("{}/documents/{}/fields", BASE_URL, doc_id))
+            .header("Authorization", format!("Bearer {}", token))
+            .json(&json!({
+                "field_type": "signature",
+                "page": 1,
+                "x": 100.0,
+                "y": 400.0 + 100.0 * i as f64,
+                "width": 200.0,
+                "height": 50.0,
+                "signer_id": signer["id"]
+            }))
+            .send()
+            .await
+            .expect("Add field failed");
+        let field: serde_json::Value = res.json().await.expect("Failed to parse field");
+
+        participants.push((
+            signer["access_token"]
+                .as_str()
+                .expect("No access token")
+                .to_string(),
+            field["id"].clone(),
+        ));
+    }
+
+    client
+        .post(format!("{}/documents/{}/send", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Send failed");
+
+    let submit = |(access_token, field_id): &(String, serde_json::Value)| {
+        client
+            .post(format!("{}/sign/{}/submit", BASE_URL, access_token))
+            .json(&json!({
+                "signatures": [{
+                    "field_id": field_id,
+                    "signature_data": "data:image/png;base64,iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAYAAAAfFcSJAAAADUlEQVR42mNk+M9QDwADhgGAWjR9awAAAABJRU5ErkJggg=="
+                }],
+                "field_values": []
+            }))
+            .send()
+    };
+
+    // Both signers at once, plus a double-submit from the first one.
+    let (first, duplicate, second) = tokio::join!(
+        submit(&participants[0]),
+        submit(&participants[0]),
+        submit(&participants[1])
+    );
+    let mut already_submitted = 0;
+    for res in [first, duplicate, second] {
+        let res = res.expect("Submit failed");
+        assert!(res.status().is_success());
+        let body: serde_json::Value = res.json().await.expect("Failed to parse response");
+        if body["already_submitted"] == true {
+            already_submitted += 1;
+        }
+    }
+    assert_eq!(already_submitted, 1);
+
+    let res = client
+        .get(format!("{}/documents/{}", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Get document failed");
+    let document: serde_json::Value = res.json().await.expect("Failed to parse document");
+    assert_eq!(document["status"], "completed");
+    assert_eq!(document["completed_signers"], 2);
+    assert_eq!(document["total_signers"], 2);
+
+    let res = client
+        .get(format!("{}/documents/{}/audit", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Get audit failed");
+    let logs: Vec<serde_json::Value> = res.json().await.expect("Failed to parse audit");
+    let count = |action: &str| logs.iter().filter(|log| log["action"] == action).count();
+    assert_eq!(count("signer_signed"), 2);
+    assert_eq!(count("signature_applied"), 2);
+    assert_eq!(count("document_completed"), 1);
+}
+
+#[tokio::test]
+async fn test_retried_submission_is_idempotent() {
+    wait_for_server().await;
+
+    let client = Client::new();
+    let token = login(&client, "admin@example.com", "change-this-secure-password").await;
+
+    let Some(doc_id) = upload_sample_document(&client, &token, "Retried Signing Test").await else {
+        return;
+    };
+
+    let res = client
+        .post(format!("{}/documents/{}/signers", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({
+            "email": "retry@example.com",
+            "name": "Retrying Signer"
+        }))
+        .send()
+        .await
+        .expect("Add signer failed");
+    let signer: serde_json::Value = res.json().await.expect("Failed to parse signer");
+    let access_token = signer["access_token"].as_str().expect("No access token");
+
+    let res = client
+        .post(format!("{}/documents/{}/fields", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({
+            "field_type": "signature",
+            "page": 1,
+            "x": 100.0,
+            "y": 500.0,
+            "width": 200.0,
+            "height": 50.0,
+            "signer_id": signer["id"]
+        }))
+        .send()
+        .await
+        .expect("Add field failed");
+    let field: serde_json::Value = res.json().await.expect("Failed to parse field");
+
+    client
+        .post(format!("{}/documents/{}/send", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Send failed");
+
+    let submit = |signature_data: &'static str| {
+        client
+            .post(format!("{}/sign/{}/submit", BASE_URL, access_token))
+            .json(&json!({
+                "signatures": [{
+                    "field_id": field["id"],
+                    "signature_data": signature_data
+                }],
+                "field_values": []
+            }))
+            .send()
+    };
+
+    for expected in [false, true, true] {
+        let res = submit("data:image/png;base64,iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAYAAAAfFcSJAAAADUlEQVR42mNk+M9QDwADhgGAWjR9awAAAABJRU5ErkJggg==")
+            .await
+            .expect("Submit failed");
+        assert!(res.status().is_success());
+        let body: serde_json::Value = res.json().await.expect("Failed to parse response");
+        assert_eq!(body["already_submitted"], expected);
+        assert_eq!(body["document_completed"], true);
+    }
+
+    // A different signature is not a retry.
+    let res = submit("data:image/png;base64,iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAYAAAAfFcSJAAAADUlEQVR42mP8z8BQDwAEhQGAhKmMIQAAAABJRU5ErkJggg==")
+        .await
+        .expect("Submit failed");
+    assert_eq!(res.status(), 400);
+
+    let res = client
+        .get(format!("{}/documents/{}/audit", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Get audit failed");
+    let logs: Vec<serde_json::Value> = res.json().await.expect("Failed to parse audit");
+    let count = |action: &str| logs.iter().filter(|log| log["action"] == action).count();
+    assert_eq!(count("signature_applied"), 1);
+    assert_eq!(count("signer_signed"), 1);
+    assert_eq!(count("document_completed"), 1);
+}
+
+#[tokio::test]
+async fn test_apply_initials_to_all() {
+    wait_for_server().await;
+
+    let client = Client::new();
+    let token = login(&client, "admin@example.com", "change-this-secure-password").await;
+
+    let Some(doc_id) = upload_sample_document(&client, &token, "Initials Test").await else {
+        return;
+    };
+
+    let res = client
+        .post(format!("{}/documents/{}/signers", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({
+            "email": "tenant@example.com",
+            "name": "Tenant"
+        }))
+        .send()
+        .await
+        .expect("Add signer failed");
+
+    let signer: serde_json::Value = res.json().await.expect("Failed to parse signer");
+    let signer_id = signer["id"].as_str().expect("No signer ID");
+    let access_token = signer["access_token"].as_str().expect("No access token");
+
+    let mut initial_ids = Vec::new();
+    for y in [100.0, 200.0, 300.0] {
+        let res = client
+            .post(format!("{}/documents/{}/fields", BASE_URL, doc_id))
+            .header("Authorization", format!("Bearer {}", token))
+            .json(&json!({
+                "field_type": "initial",
+                "page": 1,
+                "x": 500.0,
+                "y": y,
+                "width": 40.0,
+                "height": 20.0,
+                "signer_id": signer_id
+            }))
+            .send()
+            .await
+            .expect("Add field failed");
+
+        let field: serde_json::Value = res.json().await.expect("Failed to parse field");
+        initial_ids.push(field["id"].as_str().expect("No field ID").to_string());
+    }
+
+    client
+        .post(format!("{}/documents/{}/send", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Send failed");
+
+    let pixel = "data:image/png;base64,iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAYAAAAfFcSJAAAADUlEQVR42mNk+M9QDwADhgGAWjR9awAAAABJRU5ErkJggg==";
+
+    let res = client
+        .post(format!("{}/sign/{}/submit", BASE_URL, access_token))
+        .json(&json!({
+            "signatures": [{
+                "field_id": initial_ids[0],
+                "signature_data": pixel
+            }],
+            "field_values": [],
+            "apply_initials_to_all": pixel
+        }))
+        .send()
+        .await
+        .expect("Submit failed");
+
+    assert!(res.status().is_success());
+
+    let res = client
+        .get(format!("{}/documents/{}/audit", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Get audit failed");
+
+    let audit_logs: serde_json::Value = res.json().await.expect("Failed to parse audit");
+    let bulk = audit_logs
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|l| l["details"]["applied_to_all_initials"] == true)
+        .expect("No bulk initials entry");
+
+    let bulk_fields = bulk["details"]["field_ids"].as_array().unwrap();
+    assert_eq!(bulk_fields.len(), 2);
+    assert!(!bulk_fields.iter().any(|id| id == initial_ids[0].as_str()));
+}
+
+#[tokio::test]
+async fn test_email_verification_required() {
+    wait_for_server().await;
+
+    let client = Client::new();
+    let token = login(&client, "admin@example.com", "change-this-secure-password").await;
+
+    let Some(doc_id) = upload_sample_document_with(
+        &client,
+        &token,
+        "Verification Test",
+        &[("verification_method", "email_otp")],
+    )
+    .await
+    else {
+        return;
+    };
+
+    let res = client
+        .post(format!("{}/documents/{}/signers", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({
+            "email": "verify@example.com",
+            "name": "Verified Signer"
+        }))
+        .send()
+        .await
+        .expect("Add signer failed");
+
+    let signer: serde_json::Value = res.json().await.expect("Failed to parse signer");
+    let signer_id = signer["id"].as_str().expect("No signer ID");
+    let access_token = signer["access_token"].as_str().expect("No access token");
+
+    let res = client
+        .post(format!("{}/documents/{}/fields", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({
+            "field_type": "signature",
+            "page": 1,
+            "x": 100.0,
+            "y": 500.0,
+            "width": 200.0,
+            "height": 50.0,
+            "signer_id": signer_id
+        }))
+        .send()
+        .await
+        .expect("Add field failed");
+
+    let field: serde_json::Value = res.json().await.expect("Failed to parse field");
+    let field_id = field["id"].as_str().expect("No field ID");
+
+    client
+        .post(format!("{}/documents/{}/send", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Send failed");
+
+    let res = client
+        .get(format!("{}/sign/{}", BASE_URL, access_token))
+        .send()
+        .await
+        .expect("Get session failed");
+
+    let session: serde_json::Value = res.json().await.expect("Failed to parse session");
+    assert_eq!(session["verification_required"], true);
+    assert_eq!(session["verification_method"], "email_otp");
+    assert!(session["fields"].as_array().unwrap().is_empty());
+
+    let res = client
+        .post(format!("{}/sign/{}/submit", BASE_URL, access_token))
+        .json(&json!({
+            "signatures": [{
+                "field_id": field_id,
+                "signature_data": "data:image/png;base64,iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAYAAAAfFcSJAAAADUlEQVR42mNk+M9QDwADhgGAWjR9awAAAABJRU5ErkJggg=="
+            }],
+            "field_values": []
+        }))
+        .send()
+        .await
+        .expect("Submit failed");
+
+    assert_eq!(res.status(), 400);
+
+    let res = client
+        .post(format!(
+            "{}/sign/{}/verification/start",
+            BASE_URL, access_token
+        ))
+        .send()
+        .await
+        .expect("Send code failed");
+
+    assert!(res.status().is_success());
+
+    // Codes are rate-limited
+    let res = client
+        .post(format!(
+            "{}/sign/{}/verification/start",
+            BASE_URL, access_token
+        ))
+        .send()
+        .await
+        .expect("Send code failed");
+
+    assert_eq!(res.status(), 429);
+
+    // Repeated wrong codes lock the signer out
+    for _ in 0..5 {
+        let res = client
+            .post(format!(
+                "{}/sign/{}/verification/complete",
+                BASE_URL, access_token
+            ))
+            .json(&json!({ "code": "wrong" }))
+            .send()
+            .await
+            .expect("Verify code failed");
+
+        assert_eq!(res.status(), 400);
+    }
+
+    let res = client
+        .post(format!(
+            "{}/sign/{}/verification/complete",
+            BASE_URL, access_token
+        ))
+        .json(&json!({ "code": "wrong" }))
+        .send()
+        .await
+        .expect("Verify code failed");
+
+    assert_eq!(res.status(), 429);
+
+    let res = client
+        .get(format!("{}/documents/{}/audit", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Get audit failed");
+
+    let audit_logs: serde_json::Value = res.json().await.expect("Failed to parse audit");
+    let failures = audit_logs
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|l| l["action"] == "verification_failed")
+        .count();
+    assert_eq!(failures, 5);
+}
+
+#[tokio::test]
+async fn test_signer_access_code() {
+    wait_for_server().await;
+
+    let client = Client::new();
+    let token = login(&client, "admin@example.com", "change-this-secure-password").await;
+
+    let Some(doc_id) = upload_sample_document(&client, &token, "Access Code Test").await else {
+        return;
+    };
+
+    let res = client
+        .post(format!("{}/documents/{}/signers", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({
+            "email": "locked@example.com",
+            "name": "Locked Signer",
+            "access_code": "1234"
+        }))
+        .send()
+        .await
+        .expect("Add signer failed");
+
+    let signer: serde_json::Value = res.json().await.expect("Failed to parse signer");
+    let signer_id = signer["id"].as_str().expect("No signer ID");
+    let access_token = signer["access_token"].as_str().expect("No access token");
+
+    // The owner can change the code after adding the signer
+    let res = client
+        .put(format!(
+            "{}/documents/{}/signers/{}",
+            BASE_URL, doc_id, signer_id
+        ))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({ "access_code": "open-sesame" }))
+        .send()
+        .await
+        .expect("Update signer failed");
+
+    assert!(res.status().is_success());
+
+    // A code that is too short leaves the rest of the update unsaved too
+    let res = client
+        .put(format!(
+            "{}/documents/{}/signers/{}",
+            BASE_URL, doc_id, signer_id
+        ))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({ "name": "Renamed Signer", "access_code": "123" }))
+        .send()
+        .await
+        .expect("Update signer failed");
+
+    assert_eq!(res.status(), 422);
+
+    let res = client
+        .get(format!("{}/documents/{}", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Get document failed");
+    let document: serde_json::Value = res.json().await.expect("Failed to parse document");
+    assert_eq!(document["signers"][0]["name"], "Locked Signer");
+
+    let res = client
+        .get(format!("{}/sign/{}", BASE_URL, access_token))
+        .send()
+        .await
+        .expect("Get session failed");
+
+    assert_eq!(res.status(), 400);
+
+    let res = client
+        .get(format!("{}/sign/{}/pdf", BASE_URL, access_token))
+        .send()
+        .await
+        .expect("Get PDF failed");
+
+    assert_eq!(res.status(), 400);
+
+    let res = client
+        .post(format!("{}/sign/{}/unlock", BASE_URL, access_token))
+        .json(&json!({ "access_code": "1234" }))
+        .send()
+        .await
+        .expect("Unlock failed");
+
+    assert_eq!(res.status(), 400);
+
+    let res = client
+        .post(format!("{}/sign/{}/unlock", BASE_URL, access_token))
+        .json(&json!({ "access_code": "open-sesame" }))
+        .send()
+        .await
+        .expect("Unlock failed");
+
+    assert!(res.status().is_success());
+
+    let res = client
+        .get(format!("{}/sign/{}", BASE_URL, access_token))
+        .send()
+        .await
+        .expect("Get session failed");
+
+    assert!(res.status().is_success());
+
+    let res = client
+        .get(format!("{}/documents/{}/audit", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Get audit failed");
+
+    let audit_logs: serde_json::Value = res.json().await.expect("Failed to parse audit");
+    let logs = audit_logs.as_array().unwrap();
+    assert!(logs.iter().any(|l| l["action"] == "access_code_failed"));
+    assert!(logs.iter().any(|l| l["action"] == "signer_unlocked"));
+    assert!(!audit_logs.to_string().contains("open-sesame"));
+}
+
+#[tokio::test]
+async fn test_signing_progress() {
+    wait_for_server().await;
+
+    let client = Client::new();
+    let token = login(&client, "admin@example.com", "change-this-secure-password").await;
+
+    let Some(doc_id) = upload_sample_document(&client, &token, "Signing Progress Test").await
+    else {
+        return;
+    };
+
+    let res = client
+        .post(format!("{}/documents/{}/signers", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({ "email": "progress@example.com", "name": "Progress Signer" }))
+        .send()
+        .await
+        .expect("Add signer failed");
+    let signer: serde_json::Value = res.json().await.expect("Failed to parse signer");
+    let signer_id = signer["id"].as_str().expect("No signer ID");
+    let access_token = signer["access_token"].as_str().expect("No access token");
+    let field_id = add_signature_field(&client, &token, &doc_id, signer_id).await;
+
+    let res = client
+        .post(format!("{}/documents/{}/send", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Send failed");
+    assert!(res.status().is_success());
+
+    let save = |progress: serde_json::Value| {
+        client
+            .put(format!("{}/sign/{}/progress", BASE_URL, access_token))
+            .json(&progress)
+            .send()
+    };
+
+    // Only the signer's own fields can be saved
+    let res = save(json!({
+        "signatures": [],
+        "field_values": [{ "field_id": uuid::Uuid::new_v4(), "value": "x" }]
+    }))
+    .await
+    .expect("Save progress failed");
+    assert_eq!(res.status(), 400);
+
+    let submission = json!({
+        "signatures": [{
+            "field_id": field_id,
+            "signature_data": "data:image/png;base64,iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAYAAAAfFcSJAAAADUlEQVR42mNk+M9QDwADhgGAWjR9awAAAABJRU5ErkJggg=="
+        }],
+        "field_values": []
+    });
+    let res = save(submission.clone())
+        .await
+        .expect("Save progress failed");
+    assert_eq!(res.status(), 200);
+    let saved: serde_json::Value = res.json().await.expect("Failed to parse result");
+    assert!(saved["saved_at"].is_string());
+
+    let session: serde_json::Value = client
+        .get(format!("{}/sign/{}", BASE_URL, access_token))
+        .send()
+        .await
+        .expect("Get signing session failed")
+        .json()
+        .await
+        .expect("Failed to parse session");
+    assert_ne!(session["signer"]["status"], "signed");
+    let progress = &session["saved_progress"];
+    assert_eq!(progress["saved_at"], saved["saved_at"]);
+    assert_eq!(progress["values"]["signatures"][0]["field_id"], field_id);
+
+    // Saving is not signing
+    let audit_logs: Vec<serde_json::Value> = client
+        .get(format!("{}/documents/{}/audit", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Get audit failed")
+        .json()
+        .await
+        .expect("Failed to parse audit");
+    assert!(!audit_logs
+        .iter()
+        .any(|log| log["action"] == "signature_applied" || log["action"] == "signer_signed"));
+
+    if session["consent_required"] == true {
+        let res = client
+            .post(format!("{}/sign/{}/consent", BASE_URL, access_token))
+            .json(&json!({ "version": session["consent_version"] }))
+            .send()
+            .await
+            .expect("Consent failed");
+        assert!(res.status().is_success());
+    }
+
+    let res = client
+        .post(format!("{}/sign/{}/submit", BASE_URL, access_token))
+        .json(&submission)
+        .send()
+        .await
+        .expect("Submit signing failed");
+    assert!(res.status().is_success());
+
+    // Submitting clears it, and nothing more can be saved
+    let res = save(submission).await.expect("Save progress failed");
+    assert_eq!(res.status(), 400);
+
+    if let Ok(database_url) = std::env::var("DATABASE_URL") {
+        let pool = sqlx::PgPool::connect(&database_url)
+            .await
+            .expect("Failed to connect to the database");
+        let cleared: bool = sqlx::query_scalar(
+            "SELECT signing_progress IS NULL AND signing_progress_saved_at IS NULL FROM signers WHERE id = $1",
+        )
+        .bind(uuid::Uuid::parse_str(signer_id).unwrap())
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert!(cleared);
+    }
+}
+
+#[tokio::test]
+async fn test_signer_consent() {
+    wait_for_server().await;
+
+    let client = Client::new();
+    let token = login(&client, "admin@example.com", "change-this-secure-password").await;
+
+    let Some(doc_id) = upload_sample_document(&client, &token, "Consent Test").await else {
+        return;
+    };
+
+    let res = client
+        .post(format!("{}/documents/{}/signers", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({ "email": "consent@example.com", "name": "Consenting Signer" }))
+        .send()
+        .await
+        .expect("Add signer failed");
+    let signer: serde_json::Value = res.json().await.expect("Failed to parse signer");
+    let signer_id = signer["id"].as_str().expect("No signer ID");
+    let access_token = signer["access_token"].as_str().expect("No access token");
+    let field_id = add_signature_field(&client, &token, &doc_id, signer_id).await;
+
+    let res = client
+        .post(format!("{}/documents/{}/send", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Send failed");
+    assert!(res.status().is_success());
+
+    let session: serde_json::Value = client
+        .get(format!("{}/sign/{}", BASE_URL, access_token))
+        .send()
+        .await
+        .expect("Get signing session failed")
+        .json()
+        .await
+        .expect("Failed to parse session");
+    assert_eq!(session["consent_given"], false);
+    assert!(!session["consent_text"].as_str().unwrap().is_empty());
+    let version = session["consent_version"]
+        .as_str()
+        .expect("No consent version");
+
+    let submission = json!({
+        "signatures": [{
+            "field_id": field_id,
+            "signature_data": "data:image/png;base64,iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAYAAAAfFcSJAAAADUlEQVR42mNk+M9QDwADhgGAWjR9awAAAABJRU5ErkJggg=="
+        }],
+        "field_values": []
+    });
+
+    if session["consent_required"] == true {
+        let res = client
+            .post(format!("{}/sign/{}/submit", BASE_URL, access_token))
+            .json(&submission)
+            .send()
+            .await
+            .expect("Submit signing failed");
+        assert_eq!(res.status(), 400);
+    }
+
+    // Consent is only recorded for the text the signer was shown
+    let res = client
+        .post(format!("{}/sign/{}/consent", BASE_URL, access_token))
+        .json(&json!({ "version": "outdated" }))
+        .send()
+        .await
+        .expect("Consent failed");
+    assert_eq!(res.status(), 409);
+
+    let res = client
+        .post(format!("{}/sign/{}/consent", BASE_URL, access_token))
+        .json(&json!({ "version": version }))
+        .send()
+        .await
+        .expect("Consent failed");
+    assert!(res.status().is_success());
+
+    let session: serde_json::Value = client
+        .get(format!("{}/sign/{}", BASE_URL, access_token))
+        .send()
+        .await
+        .expect("Get signing session failed")
+        .json()
+        .await
+        .expect("Failed to parse session");
+    assert_eq!(session["consent_given"], true);
+
+    let res = client
+        .post(format!("{}/sign/{}/submit", BASE_URL, access_token))
+        .json(&submission)
+        .send()
+        .await
+        .expect("Submit signing failed");
+    assert!(res.status().is_success());
+
+    let audit_logs: Vec<serde_json::Value> = client
+        .get(format!("{}/documents/{}/audit", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Get audit failed")
+        .json()
+        .await
+        .expect("Failed to parse audit");
+    let consent = audit_logs
+        .iter()
+        .find(|log| log["action"] == "consent_given")
+        .expect("No consent_given entry");
+    assert_eq!(consent["details"]["consent_version"], version);
+    assert_eq!(consent["details"]["consent_text"], session["consent_text"]);
+
+    let certificate: serde_json::Value = client
+        .get(format!("{}/documents/{}/certificate", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Get certificate failed")
+        .json()
+        .await
+        .expect("Failed to parse certificate");
+    assert_eq!(certificate["signers"][0]["consent_version"], version);
+    assert!(certificate["signers"][0]["consented_at"].is_string());
+}
+
+#[tokio::test]
+async fn test_signer_locale() {
+    wait_for_server().await;
+
+    let client = Client::new();
+    let token = login(&client, "admin@example.com", "change-this-secure-password").await;
+
+    let Some(doc_id) = upload_sample_document(&client, &token, "Locale Test").await else {
+        return;
+    };
+
+    let mut access_tokens = Vec::new();
+    for (email, locale) in [
+        ("french@example.com", json!("fr-CA")),
+        ("german@example.com", json!("de")),
+        ("unset@example.com", json!(null)),
+    ] {
+        let res = client
+            .post(format!("{}/documents/{}/signers", BASE_URL, doc_id))
+            .header("Authorization", format!("Bearer {}", token))
+            .json(&json!({ "email": email, "name": "Locale Signer", "locale": locale }))
+            .send()
+            .await
+            .expect("Add signer failed");
+        assert!(res.status().is_success());
+        let signer: serde_json::Value = res.json().await.expect("Failed to parse signer");
+        assert_eq!(signer["locale"], locale);
+        add_signature_field(&client, &token, &doc_id, signer["id"].as_str().unwrap()).await;
+        access_tokens.push(signer["access_token"].as_str().unwrap().to_string());
+    }
+
+    let res = client
+        .post(format!("{}/documents/{}/send", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Send failed");
+    assert!(res.status().is_success());
+
+    let session_locales = || async {
+        let mut locales = Vec::new();
+        for access_token in &access_tokens {
+            let session: serde_json::Value = client
+                .get(format!("{}/sign/{}", BASE_URL, access_token))
+                .send()
+                .await
+                .expect("Get signing session failed")
+                .json()
+                .await
+                .expect("Failed to parse session");
+            locales.push(session["locale"].as_str().unwrap().to_string());
+        }
+        locales
+    };
+
+    // Unsupported languages fall back to the server default
+    assert_eq!(session_locales().await, ["fr", "en", "en"]);
+
+    let res = client
+        .patch(format!("{}/documents/{}", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({ "locale": "fr" }))
+        .send()
+        .await
+        .expect("Update document failed");
+    assert!(res.status().is_success());
+    let document: serde_json::Value = res.json().await.expect("Failed to parse document");
+    assert_eq!(document["locale"], "fr");
+
+    assert_eq!(session_locales().await, ["fr", "fr", "fr"]);
+
+    let res = client
+        .patch(format!("{}/documents/{}", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({ "locale": "" }))
+        .send()
+        .await
+        .expect("Update document failed");
+    let document: serde_json::Value = res.json().await.expect("Failed to parse document");
+    assert!(document["locale"].is_null());
+
+    assert_eq!(session_locales().await, ["fr", "en", "en"]);
+}
+
+#[tokio::test]
+async fn test_branding() {
+    const LOGO: &str = "data:image/png;base64,iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAYAAAAfFcSJAAAADUlEQVR42mNk+M9QDwADhgGAWjR9awAAAABJRU5ErkJggg==";
+
+    wait_for_server().await;
+
+    let client = Client::new();
+    let token = login(&client, "admin@example.com", "change-this-secure-password").await;
+
+    let update_branding = |body: serde_json::Value| {
+        client
+            .put(format!("{}/auth/branding", BASE_URL))
+            .header("Authorization", format!("Bearer {}", token))
+            .json(&body)
+            .send()
+    };
+
+    let res = update_branding(json!({ "primary_color": "blue" }))
+        .await
+        .expect("Update branding failed");
+    assert_eq!(res.status(), 422);
+
+    let res = update_branding(json!({ "logo": "data:image/gif;base64,R0lGODlhAQABAAAAACw=" }))
+        .await
+        .expect("Update branding failed");
+    assert_eq!(res.status(), 400);
+
+    let res = update_branding(json!({
+        "company_name": "Acme Corp",
+        "primary_color": "#0F766E",
+        "logo": LOGO
+    }))
+    .await
+    .expect("Update branding failed");
+    assert_eq!(res.status(), 200);
+    let branding: serde_json::Value = res.json().await.expect("Failed to parse branding");
+    assert_eq!(branding["company_name"], "Acme Corp");
+    assert_eq!(branding["primary_color"], "#0f766e");
+    let logo_url = branding["logo_url"].as_str().expect("No logo URL");
+
+    // Served without credentials, for email clients
+    let logo_path = &logo_url[logo_url.find("/api/").unwrap() + 4..];
+    let res = client
+        .get(format!("{}{}", BASE_URL, logo_path))
+        .send()
+        .await
+        .expect("Get logo failed");
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.headers()["content-type"], "image/png");
+
+    // Leaving the logo out keeps it
+    let res = update_branding(json!({ "company_name": "Acme Corp" }))
+        .await
+        .expect("Update branding failed");
+    let branding: serde_json::Value = res.json().await.expect("Failed to parse branding");
+    assert!(branding["primary_color"].is_null());
+    assert!(branding["logo_url"].is_string());
+
+    let Some(doc_id) = upload_sample_document(&client, &token, "Branding Test").await else {
+        return;
+    };
+    let res = client
+        .post(format!("{}/documents/{}/signers", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({ "email": "branded@example.com", "name": "Branded Signer" }))
+        .send()
+        .await
+        .expect("Add signer failed");
+    let signer: serde_json::Value = res.json().await.expect("Failed to parse signer");
+    add_signature_field(&client, &token, &doc_id, signer["id"].as_str().unwrap()).await;
+
+    let res = client
+        .post(format!("{}/documents/{}/send", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Send failed");
+    assert!(res.status().is_success());
+
+    let session: serde_json::Value = client
+        .get(format!(
+            "{}/sign/{}",
+            BASE_URL,
+            signer["access_token"].as_str().unwrap()
+        ))
+        .send()
+        .await
+        .expect("Get signing session failed")
+        .json()
+        .await
+        .expect("Failed to parse session");
+    assert_eq!(session["branding"]["company_name"], "Acme Corp");
+    assert!(session["branding"]["logo_url"].is_string());
+
+    let res = update_branding(json!({ "logo": "" }))
+        .await
+        .expect("Update branding failed");
+    let branding: serde_json::Value = res.json().await.expect("Failed to parse branding");
+    assert!(branding["company_name"].is_null());
+    assert!(branding["logo_url"].is_null());
+
+    let res = client
+        .get(format!("{}{}", BASE_URL, logo_path))
+        .send()
+        .await
+        .expect("Get logo failed");
+    assert_eq!(res.status(), 404);
+}
+
+#[tokio::test]
+async fn test_reply_to_preference() {
+    wait_for_server().await;
+
+    let client = Client::new();
+    let token = login(&client, "admin@example.com", "change-this-secure-password").await;
+
+    let user: serde_json::Value = client
+        .get(format!("{}/auth/me", BASE_URL))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Get user failed")
+        .json()
+        .await
+        .expect("Failed to parse user");
+    assert_eq!(user["reply_to_enabled"], true);
+
+    for enabled in [false, true] {
+        let res = client
+            .patch(format!("{}/auth/me", BASE_URL))
+            .header("Authorization", format!("Bearer {}", token))
+            .json(&json!({ "reply_to_enabled": enabled }))
+            .send()
+            .await
+            .expect("Update failed");
+        assert_eq!(res.status(), 200);
+        let user: serde_json::Value = res.json().await.expect("Failed to parse user");
+        assert_eq!(user["reply_to_enabled"], enabled);
+    }
+}
+
+#[tokio::test]
+async fn test_audit_log_device_description() {
+    wait_for_server().await;
+
+    let client = Client::builder()
+        .user_agent("Mozilla/5.0 (iPhone; CPU iPhone OS 17_4 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.4 Mobile/15E148 Safari/604.1")
+        .build()
+        .unwrap();
+    let token = login(&client, "admin@example.com", "change-this-secure-password").await;
+
+    let Some(doc_id) = upload_sample_document(&client, &token, "Device Test").await else {
+        return;
+    };
+
+    let res = client
+        .get(format!("{}/documents/{}/audit", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Get audit failed");
+
+    let audit_logs: serde_json::Value = res.json().await.expect("Failed to parse audit");
+    let created = &audit_logs.as_array().unwrap()[0];
+
+    assert_eq!(created["device"], "Safari on iOS (iPhone)");
+    assert_eq!(created["details"]["device"], "Safari on iOS (iPhone)");
+    assert!(created["user_agent"].as_str().unwrap().contains("iPhone"));
+}
+
+#[tokio::test]
+async fn test_forwarded_ip_from_untrusted_peer_is_ignored() {
+    wait_for_server().await;
+
+    let mut headers = reqwest::header::HeaderMap::new();
+    headers.insert("x-forwarded-for", "198.51.100.7".parse().unwrap());
+    headers.insert("x-signvault-client-ip", "198.51.100.8".parse().unwrap());
+
+    let client = Client::builder().default_headers(headers).build().unwrap();
+    let token = login(&client, "admin@example.com", "change-this-secure-password").await;
+
+    let Some(doc_id) = upload_sample_document(&client, &token, "Client IP Test").await else {
+        return;
+    };
+
+    let res = client
+        .get(format!("{}/documents/{}/audit", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Get audit failed");
+
+    let audit_logs: serde_json::Value = res.json().await.expect("Failed to parse audit");
+    let created = &audit_logs.as_array().unwrap()[0];
+
+    assert_eq!(created["ip_address"], "127.0.0.1");
+}
+
+#[tokio::test]
+async fn test_request_id_propagation() {
+    wait_for_server().await;
+
+    let client = Client::new();
+
+    let res = client
+        .get(format!("{}/health", BASE_URL))
+        .send()
+        .await
+        .expect("Health check failed");
+
+    assert!(res.headers().contains_key("x-request-id"));
+
+    let res = client
+        .get(format!("{}/sign/not-a-real-token", BASE_URL))
+        .header("X-Request-Id", "support-ticket-42")
+        .send()
+        .await
+        .expect("Get session failed");
+
+    assert_eq!(res.headers()["x-request-id"], "support-ticket-42");
+    let body: serde_json::Value = res.json().await.expect("Failed to parse error");
+    assert_eq!(body["request_id"], "support-ticket-42");
+
+    let token = login(&client, "admin@example.com", "change-this-secure-password").await;
+    let Some(doc_id) = upload_sample_document(&client, &token, "Request Id Test").await else {
+        return;
+    };
+
+    let res = client
+        .get(format!("{}/documents/{}/audit", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Get audit failed");
+
+    let audit_logs: serde_json::Value = res.json().await.expect("Failed to parse audit");
+    assert!(audit_logs[0]["details"]["request_id"].is_string());
+}
+
+#[tokio::test]
+async fn test_hashes_record_their_algorithm() {
+    wait_for_server().await;
+
+    let client = Client::new();
+    let token = login(&client, "admin@example.com", "change-this-secure-password").await;
+
+    let Some(doc_id) = upload_sample_document(&client, &token, "Hash Algorithm Test").await else {
+        return;
+    };
+
+    let is_prefixed = |hash: &serde_json::Value| {
+        let (algorithm, digest) = hash.as_str().unwrap().split_once(':').unwrap();
+        ["sha256", "sha512", "blake3"].contains(&algorithm)
+            && digest.chars().all(|c| c.is_ascii_hexdigit())
+    };
+
+    let res = client
+        .get(format!("{}/documents/{}", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Get document failed");
+    let doc: serde_json::Value = res.json().await.expect("Failed to parse document");
+    assert!(is_prefixed(&doc["file_hash"]));
+
+    let res = client
+        .get(format!("{}/documents/{}/audit", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Get audit failed");
+    let logs: Vec<serde_json::Value> = res.json().await.expect("Failed to parse audit");
+    assert!(logs.iter().all(|log| {
+        let hash = log["entry_hash"].as_str().unwrap();
+        hash.strip_prefix("v2:")
+            .is_some_and(|hash| is_prefixed(&serde_json::json!(hash)))
+    }));
+
+    let res = client
+        .post(format!(
+            "{}/admin/documents/{}/verify-integrity",
+            BASE_URL, doc_id
+        ))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Verify failed");
+    let report: serde_json::Value = res.json().await.expect("Failed to parse report");
+    assert_eq!(report["intact"], true);
+    assert_eq!(report["actual_hash"], doc["file_hash"]);
+}
+
+#[tokio::test]
+async fn test_unknown_hash_algorithm_rejected_at_startup() {
+    if std::env::var("DATABASE_URL").is_err() {
+        return;
+    }
+
+    let mut server = std::process::Command::new(env!("CARGO_BIN_EXE_signvault"))
+        .env("BACKEND_PORT", "18090")
+        .env("HASH_ALGORITHM", "MD5")
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .spawn()
+        .expect("Failed to start server");
+
+    let mut status = None;
+    for _ in 0..100 {
+        status = server.try_wait().unwrap();
+        if status.is_some() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    if status.is_none() {
+        server.kill().ok();
+        panic!("Server started with an unknown hash algorithm");
+    }
+    assert!(!status.unwrap().success());
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_graceful_shutdown_drains_in_flight_request() {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    // Spawns a dedicated server process, so it needs the same environment as
+    // the shared test server
+    if std::env::var("DATABASE_URL").is_err() {
+        return;
+    }
+
+    const PORT: u16 = 18089;
+
+    let mut server = std::process::Command::new(env!("CARGO_BIN_EXE_signvault"))
+        .env("BACKEND_HOST", "127.0.0.1")
+        .env("BACKEND_PORT", PORT.to_string())
+        .env("SHUTDOWN_DRAIN_TIMEOUT_SECS", "10")
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .spawn()
+        .expect("Failed to start server");
+
+    let client = Client::new();
+    let mut ready = false;
+    for _ in 0..50 {
+        if client
+            .get(format!("http://127.0.0.1:{}/api/health", PORT))
+            .send()
+            .await
+            .is_ok()
+        {
+            ready = true;
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(200)).await;
+    }
+    assert!(ready, "Server did not start in time");
+
+    let body = json!({
+        "email": "admin@example.com",
+        "password": "change-this-secure-password"
+    })
+    .to_string();
+    let (first, rest) = body.split_at(body.len() / 2);
+
+    // Start a request whose body is still being uploaded
+    let mut stream = tokio::net::TcpStream::connect(("127.0.0.1", PORT))
+        .await
+        .expect("Connect failed");
+    stream
+        .write_all(
+            format!(
+                "POST /api/auth/login HTTP/1.1\r\nHost: localhost\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+                body.len(),
+                first
+            )
+            .as_bytes(),
+        )
+        .await
+        .unwrap();
+    tokio::time::sleep(Duration::from_millis(300)).await;
+
+    std::process::Command::new("kill")
+        .args(["-TERM", &server.id().to_string()])
+        .status()
+        .expect("Failed to send SIGTERM");
+
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    assert!(
+        server.try_wait().unwrap().is_none(),
+        "Server exited with a request in flight"
+    );
+
+    stream.write_all(rest.as_bytes()).await.unwrap();
+
+    let mut response = Vec::new();
+    tokio::time::timeout(Duration::from_secs(10), stream.read_to_end(&mut response))
+        .await
+        .expect("Response timed out")
+        .unwrap();
+    assert!(String::from_utf8_lossy(&response).starts_with("HTTP/1.1 200"));
+
+    let mut status = None;
+    for _ in 0..50 {
+        status = server.try_wait().unwrap();
+        if status.is_some() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(200)).await;
+    }
+
+    let status = status.unwrap_or_else(|| {
+        server.kill().ok();
+        panic!("Server did not exit after draining");
+    });
+    assert!(status.success());
+}
+
+#[tokio::test]
+async fn test_upload_size_limit() {
+    wait_for_server().await;
+
+    let client = Client::new();
+    let token = login(&client, "admin@example.com", "change-this-secure-password").await;
+
+    // Assumes the default MAX_FILE_SIZE_MB of 50
+    const MAX_FILE_SIZE: usize = 50 * 1024 * 1024;
+
+    // Just over the file limit is caught by the per-field check, well over it by
+    // the request body limit before the upload is fully buffered
+    for size in [MAX_FILE_SIZE + 1, MAX_FILE_SIZE + 3 * 1024 * 1024] {
+        let form = reqwest::multipart::Form::new()
+            .text("title", "Too Large")
+            .part(
+                "file",
+                reqwest::multipart::Part::bytes(vec![b'%'; size])
+                    .file_name("large.pdf")
+                    .mime_str("application/pdf")
+                    .unwrap(),
+            );
+
+        let res = client
+            .post(format!("{}/documents", BASE_URL))
+            .header("Authorization", format!("Bearer {}", token))
+            .multipart(form)
+            .send()
+            .await;
+
+        // The server may stop reading and close the connection once the limit
+        // is hit, before the client has finished sending
+        let Ok(res) = res else {
+            continue;
+        };
+
+        assert_eq!(res.status(), 400);
+        let body: serde_json::Value = res.json().await.expect("Failed to parse error");
+        assert!(body["message"].as_str().unwrap().contains("50 MB"));
+    }
+}
+
+#[tokio::test]
+async fn test_download_range_requests() {
+    wait_for_server().await;
+
+    let client = Client::new();
+    let token = login(&client, "admin@example.com", "change-this-secure-password").await;
+
+    let Some(doc_id) = upload_sample_document(&client, &token, "Range Test").await else {
+        return;
+    };
+
+    let url = format!("{}/documents/{}/download", BASE_URL, doc_id);
+
+    let res = client
+        .get(&url)
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Download failed");
+
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.headers()["accept-ranges"], "bytes");
+    let full = res.bytes().await.unwrap();
+    assert!(full.starts_with(b"%PDF"));
+
+    let res = client
+        .get(&url)
+        .header("Authorization", format!("Bearer {}", token))
+        .header("Range", "bytes=0-9")
+        .send()
+        .await
+        .expect("Range download failed");
+
+    assert_eq!(res.status(), 206);
+    assert_eq!(
+        res.headers()["content-range"],
+        format!("bytes 0-9/{}", full.len()).as_str()
+    );
+    assert_eq!(res.bytes().await.unwrap(), full.slice(0..10));
+
+    let res = client
+        .get(&url)
+        .header("Authorization", format!("Bearer {}", token))
+        .header("Range", format!("bytes={}-", full.len()))
+        .send()
+        .await
+        .expect("Range download failed");
+
+    assert_eq!(res.status(), 416);
+    assert_eq!(
+        res.headers()["content-range"],
+        format!("bytes */{}", full.len()).as_str()
+    );
+
+    let res = client
+        .get(&url)
+        .header("Authorization", format!("Bearer {}", token))
+        .header("Range", "bytes=oops")
+        .send()
+        .await
+        .expect("Range download failed");
+
+    assert_eq!(res.status(), 416);
+}
+
+#[tokio::test]
+async fn test_pdf_conditional_get() {
+    wait_for_server().await;
+
+    let client = Client::new();
+    let token = login(&client, "admin@example.com", "change-this-secure-password").await;
+
+    let Some(doc_id) = upload_sample_document(&client, &token, "ETag Test").await else {
+        return;
+    };
+
+    let res = client
+        .post(format!("{}/documents/{}/signers", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({
+            "email": "etag@example.com",
+            "name": "ETag Signer"
+        }))
+        .send()
+        .await
+        .expect("Add signer failed");
+
+    let signer: serde_json::Value = res.json().await.expect("Failed to parse signer");
+    let access_token = signer["access_token"].as_str().expect("No access token");
+
+    let urls = [
+        (
+            format!("{}/documents/{}/download", BASE_URL, doc_id),
+            Some(&token),
+        ),
+        (format!("{}/sign/{}/pdf", BASE_URL, access_token), None),
+    ];
+
+    for (url, auth) in urls {
+        let with_auth = |req: reqwest::RequestBuilder| match auth {
+            Some(token) => req.header("Authorization", format!("Bearer {}", token)),
+            None => req,
+        };
+
+        let res = with_auth(client.get(&url))
+            .send()
+            .await
+            .expect("PDF request failed");
+
+        assert_eq!(res.status(), 200);
+        let etag = res.headers()["etag"].to_str().unwrap().to_string();
+        assert!(etag.starts_with('"'));
+
+        let res = with_auth(client.get(&url))
+            .header("If-None-Match", &etag)
+            .send()
+            .await
+            .expect("Conditional request failed");
+
+        assert_eq!(res.status(), 304);
+        assert_eq!(res.headers()["etag"], etag.as_str());
+        assert!(res.bytes().await.unwrap().is_empty());
+    }
+
+    let res = client
+        .get(format!("{}/sign/{}/pdf", BASE_URL, access_token))
+        .send()
+        .await
+        .expect("PDF request failed");
+
+    assert_eq!(
+        res.headers()["cache-control"],
+        "private, max-age=0, must-revalidate"
+    );
+}
+
+#[tokio::test]
+async fn test_documents_use_storage_keys() {
+    wait_for_server().await;
+
+    let client = Client::new();
+    let token = login(&client, "admin@example.com", "change-this-secure-password").await;
+
+    let res = client
+        .get(format!("{}/health/detailed", BASE_URL))
+        .send()
+        .await
+        .expect("Health check failed");
+
+    let health: serde_json::Value = res.json().await.expect("Failed to parse health");
+    assert_eq!(health["storage"]["backend"], "local");
+    assert_eq!(health["storage"]["writable"], true);
+
+    let Some(doc_id) = upload_sample_document(&client, &token, "Storage Key Test").await else {
+        return;
+    };
+
+    let res = client
+        .get(format!("{}/documents/{}", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Get document failed");
+
+    let doc: serde_json::Value = res.json().await.expect("Failed to parse document");
+    let pages = doc["pages"].as_array().expect("No pages");
+    assert!(!pages.is_empty());
+    assert_eq!(pages[0]["number"], 1);
+    assert_eq!(doc["page_count"].as_u64().unwrap(), pages.len() as u64);
+    assert!(doc.get("page_sizes").is_none());
+    assert!(pages[0]["width"].as_f64().unwrap() > 0.0);
+    // A deduplicated upload keeps the key of the document first stored with
+    // that file
+    let owner_id = doc["owner_id"].as_str().unwrap();
+    let file_path = doc["file_path"].as_str().unwrap();
+    let document_dir = file_path
+        .strip_prefix(&format!("{}/", owner_id))
+        .and_then(|rest| rest.strip_suffix("/original.pdf"))
+        .expect("Unexpected storage key");
+    assert!(document_dir.parse::<uuid::Uuid>().is_ok());
+
+    let res = client
+        .delete(format!("{}/documents/{}", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Delete failed");
+    assert!(res.status().is_success());
+}
+
+#[tokio::test]
+async fn test_download_integrity_violation() {
+    wait_for_server().await;
+
+    // Tampers with the stored file directly, so it needs the server's storage
+    let Ok(storage_path) = std::env::var("STORAGE_PATH") else {
+        return;
+    };
+
+    let client = Client::new();
+    let token = login(&client, "admin@example.com", "change-this-secure-password").await;
+
+    let Some(doc_id) = upload_sample_document(&client, &token, "Integrity Test").await else {
+        return;
+    };
+
+    let res = client
+        .get(format!("{}/documents/{}", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Get document failed");
+    let doc: serde_json::Value = res.json().await.expect("Failed to parse document");
+    let file = std::path::Path::new(&storage_path).join(doc["file_path"].as_str().unwrap());
+    let original = std::fs::read(&file).expect("Stored file missing");
+
+    let download_url = format!("{}/documents/{}/download", BASE_URL, doc_id);
+    let verify_url = format!("{}/admin/documents/{}/verify-integrity", BASE_URL, doc_id);
+
+    let mut tampered = original.clone();
+    tampered.extend_from_slice(b"\n% tampered");
+    std::fs::write(&file, &tampered).unwrap();
+
+    for range in [None, Some("bytes=0-99")] {
+        let mut req = client
+            .get(&download_url)
+            .header("Authorization", format!("Bearer {}", token));
+        if let Some(range) = range {
+            req = req.header("Range", range);
+        }
+
+        let res = req.send().await.expect("Download failed");
+        assert_eq!(res.status(), 500);
+        let body: serde_json::Value = res.json().await.expect("Failed to parse error");
+        assert_eq!(body["error"], "file_integrity_violation");
+    }
+
+    let res = client
+        .post(&verify_url)
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Verify failed");
+    let report: serde_json::Value = res.json().await.expect("Failed to parse report");
+    assert_eq!(report["intact"], false);
+    assert_eq!(report["expected_hash"], doc["file_hash"]);
+
+    let res = client
+        .get(format!("{}/documents/{}/audit", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Get audit failed");
+    let logs: Vec<serde_json::Value> = res.json().await.expect("Failed to parse audit");
+    let violations = logs
+        .iter()
+        .filter(|l| l["action"] == "file_integrity_violation")
+        .count();
+    assert_eq!(violations, 1);
+
+    // The repeated failures raise a single alert
+    let res = client
+        .get(format!("{}/admin/security-events?limit=200", BASE_URL))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("List security events failed");
+    assert!(res.status().is_success());
+    let events: Vec<serde_json::Value> = res.json().await.expect("Failed to parse events");
+    let events: Vec<_> = events
+        .iter()
+        .filter(|event| event["document_id"] == doc_id.as_str())
+        .collect();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0]["event_type"], "file_integrity_violation");
+    assert_eq!(events[0]["severity"], "high");
+    assert_eq!(events[0]["source"], "request");
+    assert_eq!(events[0]["details"]["expected_hash"], doc["file_hash"]);
+
+    let acknowledge_url = format!(
+        "{}/admin/security-events/{}/acknowledge",
+        BASE_URL,
+        events[0]["id"].as_str().unwrap()
+    );
+    let res = client
+        .post(&acknowledge_url)
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Acknowledge failed");
+    assert!(res.status().is_success());
+    let event: serde_json::Value = res.json().await.expect("Failed to parse event");
+    assert!(event["acknowledged_at"].is_string());
+
+    let res = client
+        .post(&acknowledge_url)
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Acknowledge failed");
+    assert_eq!(res.status(), 409);
+
+    let res = client
+        .get(format!("{}/admin/security-events?limit=200", BASE_URL))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("List security events failed");
+    let events: Vec<serde_json::Value> = res.json().await.expect("Failed to parse events");
+    assert!(!events
+        .iter()
+        .any(|event| event["document_id"] == doc_id.as_str()));
+
+    std::fs::write(&file, &original).unwrap();
+
+    let res = client
+        .post(&verify_url)
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Verify failed");
+    let report: serde_json::Value = res.json().await.expect("Failed to parse report");
+    assert_eq!(report["intact"], true);
+
+    let res = client
+        .get(&download_url)
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Download failed");
+    assert_eq!(res.status(), 200);
+    assert_eq!(
+        res.bytes().await.unwrap().as_ref(),
+        include_bytes!("../tests/fixtures/sample.pdf")
+    );
+}
+
+#[tokio::test]
+async fn test_storage_scan_reports_orphans() {
+    wait_for_server().await;
+
+    // Plants a file in the server's storage directly
+    let Ok(storage_path) = std::env::var("STORAGE_PATH") else {
+        return;
+    };
+
+    let client = Client::new();
+    let token = login(&client, "admin@example.com", "change-this-secure-password").await;
+
+    let orphan_dir = format!("orphan-test/{}", uuid::Uuid::new_v4());
+    let orphan_key = format!("{}/original.pdf", orphan_dir);
+    let orphan = std::path::Path::new(&storage_path).join(&orphan_key);
+    std::fs::create_dir_all(orphan.parent().unwrap()).unwrap();
+    std::fs::write(&orphan, b"%PDF-orphan").unwrap();
+
+    // Older than the default grace period
+    let stale = std::time::SystemTime::now() - std::time::Duration::from_secs(3 * 24 * 3600);
+    std::fs::File::options()
+        .write(true)
+        .open(&orphan)
+        .unwrap()
+        .set_modified(stale)
+        .unwrap();
+
+    let res = client
+        .post(format!("{}/admin/storage/scans", BASE_URL))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Scan failed");
+
+    // Another scan may be running concurrently, e.g. the one at startup
+    if res.status() == 409 {
+        std::fs::remove_dir_all(orphan.parent().unwrap()).ok();
+        return;
+    }
+
+    assert_eq!(res.status(), 200);
+    let report: serde_json::Value = res.json().await.expect("Failed to parse report");
+    let orphan_entry = report["orphans"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|o| o["key"] == orphan_key.as_str())
+        .expect("Orphan not reported");
+    // STORAGE_SCAN_DELETE_ORPHANS is off by default
+    assert_eq!(orphan_entry["deleted"], false);
+    assert!(orphan.exists());
+
+    let res = client
+        .get(format!("{}/admin/storage/scans?limit=1", BASE_URL))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("List scans failed");
+    let reports: Vec<serde_json::Value> = res.json().await.expect("Failed to parse reports");
+    assert_eq!(reports.len(), 1);
+
+    std::fs::remove_dir_all(orphan.parent().unwrap()).ok();
+}
+
+#[tokio::test]
+async fn test_soft_delete_and_restore() {
+    wait_for_server().await;
+
+    let client = Client::new();
+    let token = login(&client, "admin@example.com", "change-this-secure-password").await;
+
+    let Some(doc_id) = upload_sample_document(&client, &token, "Trash Test").await else {
+        return;
+    };
+    let doc_url = format!("{}/documents/{}", BASE_URL, doc_id);
+
+    let res = client
+        .delete(&doc_url)
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Delete failed");
+    assert!(res.status().is_success());
+    let body: serde_json::Value = res.json().await.expect("Failed to parse response");
+    assert!(body["deleted_at"].is_string());
+
+    let res = client
+        .get(&doc_url)
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Get document failed");
+    assert_eq!(res.status(), 404);
+
+    let res = client
+        .get(format!("{}/documents/trash?limit=100", BASE_URL))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("List trash failed");
+    let trash: serde_json::Value = res.json().await.expect("Failed to parse trash");
+    assert!(trash["retention_days"].is_i64());
+    assert!(trash["documents"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .any(|d| d["id"] == doc_id.as_str()));
+
+    let res = client
+        .post(format!("{}/restore", doc_url))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Restore failed");
+    assert!(res.status().is_success());
+    let restored: serde_json::Value = res.json().await.expect("Failed to parse document");
+    assert!(restored["deleted_at"].is_null());
+
+    // Only documents in the trash can be restored
+    let res = client
+        .post(format!("{}/restore", doc_url))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Restore failed");
+    assert_eq!(res.status(), 404);
+
+    let res = client
+        .get(format!("{}/audit", doc_url))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Get audit failed");
+    let logs: Vec<serde_json::Value> = res.json().await.expect("Failed to parse audit");
+    assert!(logs.iter().any(|l| l["action"] == "document_deleted"));
+    assert!(logs.iter().any(|l| l["action"] == "document_restored"));
+}
+
+#[tokio::test]
+async fn test_legal_hold_and_purge_log() {
+    wait_for_server().await;
+
+    let client = Client::new();
+    let token = login(&client, "admin@example.com", "change-this-secure-password").await;
+
+    let Some(doc_id) = upload_sample_document(&client, &token, "Legal Hold Test").await else {
+        return;
+    };
+    let doc_url = format!("{}/documents/{}", BASE_URL, doc_id);
+
+    let res = client
+        .put(format!("{}/legal-hold", doc_url))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({ "legal_hold": true }))
+        .send()
+        .await
+        .expect("Set legal hold failed");
+    assert!(res.status().is_success());
+    let document: serde_json::Value = res.json().await.expect("Failed to parse document");
+    assert_eq!(document["legal_hold"], true);
+
+    let res = client
+        .get(format!("{}/audit", doc_url))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Get audit failed");
+    let logs: Vec<serde_json::Value> = res.json().await.expect("Failed to parse audit");
+    assert!(logs.iter().any(|l| l["action"] == "legal_hold_changed"));
+
+    let res = client
+        .get(format!("{}/admin/purge-log?limit=10", BASE_URL))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("List purge log failed");
+    assert!(res.status().is_success());
+    let entries: serde_json::Value = res.json().await.expect("Failed to parse purge log");
+    assert!(entries.is_array());
+}
+
+#[tokio::test]
+async fn test_anonymize_signer() {
+    wait_for_server().await;
+
+    let client = Client::new();
+    let token = login(&client, "admin@example.com", "change-this-secure-password").await;
+
+    let Some(doc_id) = upload_sample_document(&client, &token, "Anonymization Test").await else {
+        return;
+    };
+    let doc_url = format!("{}/documents/{}", BASE_URL, doc_id);
+    let email = format!("erase-{}@example.com", uuid::Uuid::new_v4().simple());
+
+    let res = client
+        .post(format!("{}/signers", doc_url))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({ "email": email, "name": "Erase Me" }))
+        .send()
+        .await
+        .expect("Add signer failed");
+    assert!(res.status().is_success());
+
+    let res = client
+        .post(format!("{}/admin/signers/anonymize", BASE_URL))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({ "email": email.to_uppercase() }))
+        .send()
+        .await
+        .expect("Anonymize failed");
+    assert!(res.status().is_success());
+    let report: serde_json::Value = res.json().await.expect("Failed to parse report");
+    assert_eq!(report["signers_anonymized"], 1);
+    assert!(report["audit_entries_anonymized"].as_u64().unwrap() >= 1);
+    assert_eq!(report["documents"][0]["document_id"], doc_id.as_str());
+
+    let res = client
+        .get(&doc_url)
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Get document failed");
+    let body = res.text().await.expect("Failed to read document");
+    assert!(!body.contains(&email));
+    assert!(!body.contains("Erase Me"));
+
+    let res = client
+        .get(format!("{}/audit", doc_url))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Get audit failed");
+    let body = res.text().await.expect("Failed to read audit");
+    assert!(!body.contains(&email));
+    assert!(body.contains("signer_anonymized"));
+
+    // Nothing is left to erase the second time
+    let res = client
+        .post(format!("{}/admin/signers/anonymize", BASE_URL))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({ "email": email }))
+        .send()
+        .await
+        .expect("Anonymize failed");
+    let report: serde_json::Value = res.json().await.expect("Failed to parse report");
+    assert_eq!(report["signers_anonymized"], 0);
+}
+
+#[tokio::test]
+async fn test_upload_rejects_password_protected_pdfs() {
+    wait_for_server().await;
+
+    let client = Client::new();
+    let token = login(&client, "admin@example.com", "change-this-secure-password").await;
+
+    let fixtures: [&[u8]; 2] = [
+        include_bytes!("../tests/fixtures/encrypted.pdf"),
+        include_bytes!("../tests/fixtures/encrypted-empty-user-password.pdf"),
+    ];
+
+    for pdf_content in fixtures {
+        let form = reqwest::multipart::Form::new()
+            .text("title", "Encrypted")
+            .part(
+                "file",
+                reqwest::multipart::Part::bytes(pdf_content.to_vec())
+                    .file_name("encrypted.pdf")
+                    .mime_str("application/pdf")
+                    .unwrap(),
+            );
+
+        let res = client
+            .post(format!("{}/documents", BASE_URL))
+            .header("Authorization", format!("Bearer {}", token))
+            .multipart(form)
+            .send()
+            .await
+            .expect("Upload failed");
+
+        assert_eq!(res.status(), 400);
+        let body: serde_json::Value = res.json().await.expect("Failed to parse error");
+        assert!(body["message"]
+            .as_str()
+            .unwrap()
+            .starts_with("Password-protected PDFs are not supported"));
+    }
+}
+
+#[tokio::test]
+async fn test_upload_rejects_active_content() {
+    wait_for_server().await;
+
+    let client = Client::new();
+    let token = login(&client, "admin@example.com", "change-this-secure-password").await;
+
+    let pdf_content = include_bytes!("../tests/fixtures/active-content.pdf");
+    let form = reqwest::multipart::Form::new()
+        .text("title", "Active Content")
+        .part(
+            "file",
+            reqwest::multipart::Part::bytes(pdf_content.to_vec())
+                .file_name("active.pdf")
+                .mime_str("application/pdf")
+                .unwrap(),
+        );
+
+    let res = client
+        .post(format!("{}/documents", BASE_URL))
+        .header("Authorization", format!("Bearer {}", token))
+        .multipart(form)
+        .send()
+        .await
+        .expect("Upload failed");
+
+    assert_eq!(res.status(), 400);
+    let body: serde_json::Value = res.json().await.expect("Failed to parse error");
+    let message = body["message"].as_str().unwrap();
+    assert!(message.contains("JavaScript"), "{}", message);
+    assert!(message.contains("embedded files"), "{}", message);
+}
+
+#[tokio::test]
+async fn test_upload_rejects_files_that_are_not_pdfs() {
+    wait_for_server().await;
+
+    let client = Client::new();
+    let token = login(&client, "admin@example.com", "change-this-secure-password").await;
+
+    let cases: [(&[u8], &str); 2] = [
+        (
+            b"MZ\x90\x00\x03\x00\x00\x00 renamed executable",
+            "File is not a PDF",
+        ),
+        (
+            b"PK\x03\x04\x14\x00[Content_Types].xmlPK\x03\x04word/document.xml",
+            "convert your .docx to PDF",
+        ),
+    ];
+
+    for (content, expected) in cases {
+        let form = reqwest::multipart::Form::new()
+            .text("title", "Not a PDF")
+            .part(
+                "file",
+                reqwest::multipart::Part::bytes(content.to_vec())
+                    .file_name("contract.pdf")
+                    .mime_str("application/pdf")
+                    .unwrap(),
+            );
+
+        let res = client
+            .post(format!("{}/documents", BASE_URL))
+            .header("Authorization", format!("Bearer {}", token))
+            .multipart(form)
+            .send()
+            .await
+            .expect("Upload failed");
+
+        assert_eq!(res.status(), 400);
+        let body: serde_json::Value = res.json().await.expect("Failed to parse error");
+        let message = body["message"].as_str().unwrap();
+        assert!(message.contains(expected), "{}", message);
+    }
+}
+
+#[tokio::test]
+async fn test_health_reports_upload_limits() {
+    wait_for_server().await;
+
+    let client = Client::new();
+    let res = client
+        .get(format!("{}/health/detailed", BASE_URL))
+        .send()
+        .await
+        .expect("Health check failed");
+
+    let health: serde_json::Value = res.json().await.expect("Failed to parse health");
+    let limits = &health["upload_limits"];
+    assert!(limits["max_file_size_mb"].as_u64().unwrap() > 0);
+    assert!(limits["max_pdf_pages"].as_u64().unwrap() > 0);
+    assert!(limits["max_pdf_page_dimension_pt"].as_f64().unwrap() > 0.0);
+}
+
+#[tokio::test]
+async fn test_health_reports_pool_and_queues() {
+    wait_for_server().await;
+
+    let client = Client::new();
+    let res = client
+        .get(format!("{}/health/detailed", BASE_URL))
+        .send()
+        .await
+        .expect("Health check failed");
+
+    let health: serde_json::Value = res.json().await.expect("Failed to parse health");
+    assert!(["healthy", "degraded"].contains(&health["status"].as_str().unwrap()));
+
+    let pool = &health["pool"];
+    let size = pool["size"].as_u64().unwrap();
+    assert!(size <= pool["max_connections"].as_u64().unwrap());
+    assert_eq!(
+        pool["idle"].as_u64().unwrap() + pool["in_use"].as_u64().unwrap(),
+        size
+    );
+    assert!(pool["acquire_timeout_secs"].as_u64().unwrap() > 0);
+    assert!(pool["saturated"].is_boolean());
+
+    let queues = &health["queues"];
+    assert!(queues["email_outbox"]["pending"].as_i64().unwrap() >= 0);
+    assert!(queues["outbox_events"]["pending"].as_i64().unwrap() >= 0);
+    assert!(queues["overdue_pending_documents"].as_i64().unwrap() >= 0);
+}
+
+#[tokio::test]
+async fn test_page_previews() {
+    wait_for_server().await;
+
+    let client = Client::new();
+    let token = login(&client, "admin@example.com", "change-this-secure-password").await;
+
+    let Some(doc_id) = upload_sample_document(&client, &token, "Preview Test").await else {
+        return;
+    };
+
+    let res = client
+        .post(format!("{}/documents/{}/signers", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({ "email": "previewer@example.com", "name": "Previewer" }))
+        .send()
+        .await
+        .expect("Add signer failed");
+    let signer: serde_json::Value = res.json().await.expect("Failed to parse signer");
+    let access_token = signer["access_token"].as_str().expect("No access token");
+
+    client
+        .post(format!("{}/documents/{}/send", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Send failed");
+
+    let owner_preview = |path: &str| {
+        client
+            .get(format!("{}/documents/{}/pages/{}", BASE_URL, doc_id, path))
+            .header("Authorization", format!("Bearer {}", token))
+    };
+
+    for (path, status) in [
+        ("0/preview.png", 404),
+        ("99/preview.png", 404),
+        ("1/preview.png?width=10", 400),
+        ("1/preview.png?width=100000", 400),
+    ] {
+        let res = owner_preview(path).send().await.expect("Preview failed");
+        assert_eq!(res.status(), status, "{}", path);
+    }
+
+    let res = client
+        .get(format!(
+            "{}/documents/{}/pages/1/preview.png",
+            BASE_URL, doc_id
+        ))
+        .send()
+        .await
+        .expect("Preview failed");
+    assert_eq!(res.status(), 401);
+
+    // Rendering needs pdftoppm, which is not installed everywhere
+    let res = owner_preview("1/preview.png?width=400")
+        .send()
+        .await
+        .expect("Preview failed");
+    let rendered = res.status() == 200;
+    assert!(rendered || res.status() == 503, "{:?}", res.status());
+
+    if rendered {
+        assert_eq!(res.headers()["content-type"], "image/png");
+        let etag = res.headers()["etag"].to_str().unwrap().to_string();
+        let png = res.bytes().await.unwrap();
+        assert!(png.starts_with(b"\x89PNG"));
+
+        let res = owner_preview("1/preview.png?width=400")
+            .header("If-None-Match", &etag)
+            .send()
+            .await
+            .expect("Preview failed");
+        assert_eq!(res.status(), 304);
+    }
+
+    let res = client
+        .get(format!(
+            "{}/sign/{}/pages/1/preview.png?width=400",
+            BASE_URL, access_token
+        ))
+        .send()
+        .await
+        .expect("Signer preview failed");
+    assert_eq!(res.status(), if rendered { 200 } else { 503 });
+
+    client
+        .post(format!("{}/documents/{}/void", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({ "reason": "No longer needed" }))
+        .send()
+        .await
+        .expect("Void failed");
+
+    let res = client
+        .get(format!(
+            "{}/sign/{}/pages/1/preview.png",
+            BASE_URL, access_token
+        ))
+        .send()
+        .await
+        .expect("Signer preview failed");
+    assert_eq!(res.status(), 400);
+}
+
+#[tokio::test]
+async fn test_import_acroform_fields() {
+    wait_for_server().await;
+
+    let client = Client::new();
+    let token = login(&client, "admin@example.com", "change-this-secure-password").await;
+
+    let pdf_content = include_bytes!("../tests/fixtures/acroform.pdf");
+    let form = reqwest::multipart::Form::new()
+        .text("title", "AcroForm Import")
+        .part(
+            "file",
+            reqwest::multipart::Part::bytes(pdf_content.to_vec())
+                .file_name("form.pdf")
+                .mime_str("application/pdf")
+                .unwrap(),
+        );
+
+    let res = client
+        .post(format!("{}/documents", BASE_URL))
+        .header("Authorization", format!("Bearer {}", token))
+        .multipart(form)
+        .send()
+        .await
+        .expect("Upload failed");
+    assert!(
+        res.status().is_success(),
+        "Upload failed: {:?}",
+        res.status()
+    );
+    let doc: serde_json::Value = res.json().await.expect("Failed to parse document");
+    let doc_id = doc["id"].as_str().expect("No document ID");
+
+    let import = || {
+        client
+            .post(format!(
+                "{}/documents/{}/fields/import-acroform",
+                BASE_URL, doc_id
+            ))
+            .header("Authorization", format!("Bearer {}", token))
+            .send()
+    };
+
+    let res = import().await.expect("Import failed");
+    assert_eq!(res.status(), 200);
+    let result: serde_json::Value = res.json().await.expect("Failed to parse import");
+
+    let imported = result["imported"].as_array().unwrap();
+    let types: Vec<&str> = imported
+        .iter()
+        .map(|f| f["field_type"].as_str().unwrap())
+        .collect();
+    assert_eq!(types, ["text", "checkbox", "signature", "text", "text"]);
+    assert!(imported.iter().all(|f| f["signer_id"].is_null()));
+    assert_eq!(imported[0]["page"], 1);
+    assert_eq!(imported[0]["x"], 72.0);
+    assert_eq!(imported[0]["y"], 68.0);
+    assert_eq!(imported[0]["width"], 200.0);
+    assert_eq!(imported[0]["height"], 24.0);
+    assert_eq!(result["skipped"].as_array().unwrap().len(), 2);
+    assert_eq!(result["skipped"][0]["name"], "choice");
+
+    let res = import().await.expect("Import failed");
+    let result: serde_json::Value = res.json().await.expect("Failed to parse import");
+    assert!(result["imported"].as_array().unwrap().is_empty());
+    assert_eq!(result["already_imported"].as_array().unwrap().len(), 5);
+
+    let res = client
+        .get(format!("{}/documents/{}", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Get document failed");
+    let doc: serde_json::Value = res.json().await.expect("Failed to parse document");
+    assert_eq!(doc["fields"].as_array().unwrap().len(), 5);
+}
+
+#[tokio::test]
+async fn test_normalized_field_coordinates() {
+    wait_for_server().await;
+
+    let client = Client::new();
+    let token = login(&client, "admin@example.com", "change-this-secure-password").await;
+
+    let form = reqwest::multipart::Form::new()
+        .text("title", "Normalized Fields")
+        .part(
+            "file",
+            reqwest::multipart::Part::bytes(generate_pdf(1))
+                .file_name("letter.pdf")
+                .mime_str("application/pdf")
+                .unwrap(),
+        );
+
+    let res = client
+        .post(format!("{}/documents", BASE_URL))
+        .header("Authorization", format!("Bearer {}", token))
+        .multipart(form)
+        .send()
+        .await
+        .expect("Upload failed");
+    assert!(res.status().is_success());
+    let doc: serde_json::Value = res.json().await.expect("Failed to parse document");
+    let doc_id = doc["id"].as_str().expect("No document ID");
+
+    let add_field = |body: serde_json::Value| {
+        client
+            .post(format!("{}/documents/{}/fields", BASE_URL, doc_id))
+            .header("Authorization", format!("Bearer {}", token))
+            .json(&body)
+            .send()
+    };
+
+    let res = add_field(json!({
+        "field_type": "signature",
+        "page": 1,
+        "coordinate_space": "normalized",
+        "x": 0.25,
+        "y": 0.5,
+        "width": 0.5,
+        "height": 0.125
+    }))
+    .await
+    .expect("Add field failed");
+    assert_eq!(res.status(), 200);
+    let field: serde_json::Value = res.json().await.expect("Failed to parse field");
+    assert_eq!(field["x"], 153.0);
+    assert_eq!(field["y"], 396.0);
+    assert_eq!(field["width"], 306.0);
+    assert_eq!(field["height"], 99.0);
+    assert_eq!(field["norm_x"], 0.25);
+    let field_id = field["id"].as_str().expect("No field ID");
+
+    // Points are still accepted while clients migrate
+    let res = add_field(json!({
+        "field_type": "date",
+        "page": 1,
+        "x": 306.0,
+        "y": 198.0,
+        "width": 153.0,
+        "height": 99.0
+    }))
+    .await
+    .expect("Add field failed");
+    assert_eq!(res.status(), 200);
+    let field: serde_json::Value = res.json().await.expect("Failed to parse field");
+    assert_eq!(field["norm_x"], 0.5);
+    assert_eq!(field["norm_y"], 0.25);
+    assert_eq!(field["norm_width"], 0.25);
+    assert_eq!(field["norm_height"], 0.125);
+
+    for body in [
+        json!({ "field_type": "text", "page": 1, "coordinate_space": "normalized",
+                "x": 0.75, "y": 0.5, "width": 0.5, "height": 0.1 }),
+        json!({ "field_type": "text", "page": 2, "coordinate_space": "normalized",
+                "x": 0.1, "y": 0.1, "width": 0.1, "height": 0.1 }),
+    ] {
+        let res = add_field(body).await.expect("Add field failed");
+        assert_eq!(res.status(), 400);
+    }
+
+    let res = client
+        .put(format!(
+            "{}/documents/{}/fields/{}",
+            BASE_URL, doc_id, field_id
+        ))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({ "coordinate_space": "normalized", "x": 0.5 }))
+        .send()
+        .await
+        .expect("Update field failed");
+    assert_eq!(res.status(), 200);
+    let field: serde_json::Value = res.json().await.expect("Failed to parse field");
+    assert_eq!(field["x"], 306.0);
+    assert_eq!(field["y"], 396.0);
+    assert_eq!(field["norm_x"], 0.5);
+    assert_eq!(field["norm_width"], 0.5);
+}
+
+/// Sealing depends on the server having a certificate configured
+/// (`SEAL_CERTIFICATE_PATH`); without one, documents stay unsealed.
+#[tokio::test]
+async fn test_seal_completed_document() {
+    wait_for_server().await;
+
+    let client = Client::new();
+    let token = login(&client, "admin@example.com", "change-this-secure-password").await;
+
+    let pdf = generate_pdf(1);
+    let form = reqwest::multipart::Form::new()
+        .text("title", "Seal Test")
+        .text("self_sign_only", "true")
+        .part(
+            "file",
+            reqwest::multipart::Part::bytes(pdf.clone())
+                .file_name("contract.pdf")
+                .mime_str("application/pdf")
+                .unwrap(),
+        );
+
+    let res = client
+        .post(format!("{}/documents", BASE_URL))
+        .header("Authorization", format!("Bearer {}", token))
+        .multipart(form)
+        .send()
+        .await
+        .expect("Upload failed");
+    assert!(res.status().is_success());
+    let doc: serde_json::Value = res.json().await.expect("Failed to parse document");
+    let doc_id = doc["id"].as_str().expect("No document ID");
+
+    let seal = || {
+        client
+            .post(format!("{}/documents/{}/seal", BASE_URL, doc_id))
+            .header("Authorization", format!("Bearer {}", token))
+            .send()
+    };
+    let download_sealed = || {
+        client
+            .get(format!("{}/documents/{}/download/sealed", BASE_URL, doc_id))
+            .header("Authorization", format!("Bearer {}", token))
+            .send()
+    };
+
+    let res = seal().await.expect("Seal failed");
+    assert_eq!(res.status(), 400);
+
+    let res = client
+        .post(format!("{}/documents/{}/fields", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({
+            "field_type": "signature",
+            "page": 1,
+            "x": 100.0,
+            "y": 500.0,
+            "width": 200.0,
+            "height": 50.0
+        }))
+        .send()
+        .await
+        .expect("Add field failed");
+    let field: serde_json::Value = res.json().await.expect("Failed to parse field");
+
+    let res = client
+        .post(format!("{}/documents/{}/send", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Send failed");
+    assert!(res.status().is_success());
+
+    let res = client
+        .post(format!("{}/documents/{}/self-sign", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({
+            "signatures": [{
+                "field_id": field["id"],
+                "signature_data": "data:image/png;base64,iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAYAAAAfFcSJAAAADUlEQVR42mNk+M9QDwADhgGAWjR9awAAAABJRU5ErkJggg=="
+            }],
+            "field_values": []
+        }))
+        .send()
+        .await
+        .expect("Self-sign failed");
+    assert!(res.status().is_success());
+
+    let res = client
+        .get(format!("{}/documents/{}", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Get document failed");
+    let document: serde_json::Value = res.json().await.expect("Failed to parse document");
+    assert_eq!(document["status"], "completed");
+    assert!(document.get("sealed_file_path").is_none());
+
+    let res = client
+        .get(format!("{}/documents/{}/certificate", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Get certificate failed");
+    let certificate: serde_json::Value = res.json().await.expect("Failed to parse certificate");
+
+    if document["sealed_at"].is_null() {
+        assert!(certificate["seal"].is_null());
+        let res = seal().await.expect("Seal failed");
+        assert_eq!(res.status(), 400);
+        let res = download_sealed().await.expect("Download failed");
+        assert_eq!(res.status(), 404);
+        return;
+    }
+
+    assert!(document["seal_signer"].is_string());
+    assert_eq!(
+        certificate["seal"]["sealed_file_hash"],
+        document["sealed_file_hash"]
+    );
+
+    let res = download_sealed().await.expect("Download failed");
+    assert_eq!(res.status(), 200);
+    assert!(res.headers()["content-disposition"]
+        .to_str()
+        .unwrap()
+        .contains("contract-sealed.pdf"));
+    let sealed = res.bytes().await.expect("Failed to read sealed file");
+    assert!(sealed.starts_with(&pdf));
+    assert!(sealed.len() > pdf.len());
+    let tail = String::from_utf8_lossy(&sealed[pdf.len()..]);
+    assert!(tail.contains("/SubFilter/adbe.pkcs7.detached"));
+
+    // Sealing again replaces the sealed copy
+    let res = seal().await.expect("Seal failed");
+    assert_eq!(res.status(), 200);
+    let resealed: serde_json::Value = res.json().await.expect("Failed to parse document");
+    assert!(resealed["sealed_at"].is_string());
+}
+
+#[tokio::test]
+async fn test_timestamp_completed_document() {
+    wait_for_server().await;
+
+    let client = Client::new();
+    let token = login(&client, "admin@example.com", "change-this-secure-password").await;
+
+    let form = reqwest::multipart::Form::new()
+        .text("title", "Timestamp Test")
+        .text("self_sign_only", "true")
+        .part(
+            "file",
+            reqwest::multipart::Part::bytes(generate_pdf(1))
+                .file_name("contract.pdf")
+                .mime_str("application/pdf")
+                .unwrap(),
+        );
+
+    let res = client
+        .post(format!("{}/documents", BASE_URL))
+        .header("Authorization", format!("Bearer {}", token))
+        .multipart(form)
+        .send()
+        .await
+        .expect("Upload failed");
+    assert!(res.status().is_success());
+    let doc: serde_json::Value = res.json().await.expect("Failed to parse document");
+    let doc_id = doc["id"].as_str().expect("No document ID");
+
+    let list_timestamps = || async {
+        let res = client
+            .get(format!("{}/documents/{}/timestamps", BASE_URL, doc_id))
+            .header("Authorization", format!("Bearer {}", token))
+            .send()
+            .await
+            .expect("List timestamps failed");
+        assert_eq!(res.status(), 200);
+        res.json::<Vec<serde_json::Value>>()
+            .await
+            .expect("Failed to parse timestamps")
+    };
+
+    assert!(list_timestamps().await.is_empty());
+
+    let res = client
+        .post(format!("{}/documents/{}/fields", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({
+            "field_type": "signature",
+            "page": 1,
+            "x": 100.0,
+            "y": 500.0,
+            "width": 200.0,
+            "height": 50.0
+        }))
+        .send()
+        .await
+        .expect("Add field failed");
+    let field: serde_json::Value = res.json().await.expect("Failed to parse field");
+
+    let res = client
+        .post(format!("{}/documents/{}/send", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Send failed");
+    assert!(res.status().is_success());
+
+    let res = client
+        .post(format!("{}/documents/{}/self-sign", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({
+            "signatures": [{
+                "field_id": field["id"],
+                "signature_data": "data:image/png;base64,iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAYAAAAfFcSJAAAADUlEQVR42mNk+M9QDwADhgGAWjR9awAAAABJRU5ErkJggg=="
+            }],
+            "field_values": []
+        }))
+        .send()
+        .await
+        .expect("Self-sign failed");
+    assert!(res.status().is_success());
+
+    let res = client
+        .get(format!("{}/documents/{}/certificate", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Get certificate failed");
+    assert_eq!(res.status(), 200);
+    let certificate: serde_json::Value = res.json().await.expect("Failed to parse certificate");
+
+    let timestamps = list_timestamps().await;
+    if timestamps.is_empty() {
+        // No timestamp authority configured
+        assert!(certificate["document_timestamp"].is_null());
+        assert!(certificate["certificate_timestamp"].is_null());
+        return;
+    }
+
+    assert_eq!(timestamps.len(), 2);
+    let document_timestamp = &timestamps[0];
+    assert_eq!(document_timestamp["subject"], "document");
+    assert_eq!(document_timestamp["hashed_value"], doc["file_hash"]);
+    let certificate_timestamp = &timestamps[1];
+    assert_eq!(certificate_timestamp["subject"], "certificate");
+    assert_eq!(
+        certificate_timestamp["hashed_value"],
+        certificate["certificate_hash"]
+    );
+
+    if document_timestamp["status"] != "granted" {
+        // The authority is unreachable: the request is kept for a retry
+        assert_eq!(document_timestamp["status"], "pending");
+        assert!(document_timestamp["last_error"].is_string());
+        assert!(certificate["document_timestamp"].is_null());
+        return;
+    }
+
+    assert!(document_timestamp["token"].is_string());
+    assert_eq!(
+        certificate["document_timestamp"]["gen_time"],
+        document_timestamp["gen_time"]
+    );
+    assert_eq!(
+        certificate["certificate_timestamp"]["timestamp_id"],
+        certificate_timestamp["id"]
+    );
+
+    let res = client
+        .post(format!(
+            "{}/admin/timestamps/{}/verify",
+            BASE_URL,
+            document_timestamp["id"].as_str().unwrap()
+        ))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Verify timestamp failed");
+    assert_eq!(res.status(), 200);
+    let verification: serde_json::Value = res.json().await.expect("Failed to parse verification");
+    assert_eq!(verification["imprint_matches"], true);
+    assert_eq!(verification["valid"], true);
+}
+
+#[tokio::test]
+async fn test_server_signed_certificate_and_audit_export() {
+    use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+    use ed25519_dalek::{pkcs8::DecodePublicKey, Signature, VerifyingKey};
+
+    wait_for_server().await;
+
+    let client = Client::new();
+    let token = login(&client, "admin@example.com", "change-this-secure-password").await;
+
+    let form = reqwest::multipart::Form::new()
+        .text("title", "Server Key Test")
+        .text("self_sign_only", "true")
+        .part(
+            "file",
+            reqwest::multipart::Part::bytes(generate_pdf(1))
+                .file_name("contract.pdf")
+                .mime_str("application/pdf")
+                .unwrap(),
+        );
+
+    let res = client
+        .post(format!("{}/documents", BASE_URL))
+        .header("Authorization", format!("Bearer {}", token))
+        .multipart(form)
+        .send()
+        .await
+        .expect("Upload failed");
+    assert!(res.status().is_success());
+    let doc: serde_json::Value = res.json().await.expect("Failed to parse document");
+    let doc_id = doc["id"].as_str().expect("No document ID");
+
+    let res = client
+        .post(format!("{}/documents/{}/fields", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({
+            "field_type": "signature",
+            "page": 1,
+            "x": 100.0,
+            "y": 500.0,
+            "width": 200.0,
+            "height": 50.0
+        }))
+        .send()
+        .await
+        .expect("Add field failed");
+    let field: serde_json::Value = res.json().await.expect("Failed to parse field");
+
+    let res = client
+        .post(format!("{}/documents/{}/send", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Send failed");
+    assert!(res.status().is_success());
+
+    let res = client
+        .post(format!("{}/documents/{}/self-sign", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({
+            "signatures": [{
+                "field_id": field["id"],
+                "signature_data": "data:image/png;base64,iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAYAAAAfFcSJAAAADUlEQVR42mNk+M9QDwADhgGAWjR9awAAAABJRU5ErkJggg=="
+            }],
+            "field_values": []
+        }))
+        .send()
+        .await
+        .expect("Self-sign failed");
+    assert!(res.status().is_success());
+
+    // Published outside /api
+    let res = client
+        .get(format!(
+            "{}/.well-known/signvault-public-key",
+            BASE_URL.trim_end_matches("/api")
+        ))
+        .send()
+        .await
+        .expect("Get public key failed");
+    assert_eq!(res.status(), 200);
+    let key_set: serde_json::Value = res.json().await.expect("Failed to parse public keys");
+    let current_key_id = key_set["current_key_id"].as_str().unwrap();
+    let current = key_set["keys"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|k| k["key_id"] == current_key_id)
+        .expect("Current key not published");
+    assert_eq!(current["algorithm"], "Ed25519");
+    assert_eq!(current["current"], true);
+    let public_key =
+        VerifyingKey::from_public_key_pem(current["public_key"].as_str().unwrap()).unwrap();
+
+    let verify_offline = |message: &[u8], signature: &str| {
+        let signature = Signature::from_slice(&BASE64.decode(signature).unwrap()).unwrap();
+        public_key.verify_strict(message, &signature).is_ok()
+    };
+
+    let res = client
+        .get(format!("{}/documents/{}/certificate", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Get certificate failed");
+    assert_eq!(res.status(), 200);
+    let certificate: serde_json::Value = res.json().await.expect("Failed to parse certificate");
+    let certificate_hash = certificate["certificate_hash"].as_str().unwrap();
+    let server_signature = &certificate["server_signature"];
+    assert_eq!(server_signature["key_id"], current_key_id);
+    assert_eq!(server_signature["key_fingerprint"], current["fingerprint"]);
+    assert!(verify_offline(
+        certificate_hash.as_bytes(),
+        server_signature["signature"].as_str().unwrap()
+    ));
+
+    let verify = |message: &str| {
+        client
+            .post(format!("{}/server-key/verify", BASE_URL))
+            .json(&json!({
+                "message": message,
+                "key_id": server_signature["key_id"],
+                "signature": server_signature["signature"]
+            }))
+            .send()
+    };
+
+    let res = verify(certificate_hash).await.expect("Verify failed");
+    assert_eq!(res.status(), 200);
+    let verification: serde_json::Value = res.json().await.expect("Failed to parse verification");
+    assert_eq!(verification["valid"], true);
+    assert_eq!(verification["key"]["key_id"], current_key_id);
+
+    let res = verify("sha256:tampered").await.expect("Verify failed");
+    let verification: serde_json::Value = res.json().await.expect("Failed to parse verification");
+    assert_eq!(verification["valid"], false);
+
+    let res = client
+        .get(format!("{}/documents/{}/audit/export", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Export audit failed");
+    assert_eq!(res.status(), 200);
+    assert_eq!(
+        res.headers()["x-signvault-key-id"].to_str().unwrap(),
+        current_key_id
+    );
+    let signature = res.headers()["x-signvault-signature"]
+        .to_str()
+        .unwrap()
+        .to_string();
+    let body = res.bytes().await.expect("Failed to read export");
+    assert!(verify_offline(&body, &signature));
+
+    let export: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(export["document_id"], doc_id);
+    assert_eq!(export["chain"]["intact"], true);
+    assert!(export["entries"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .any(|e| e["action"] == "document_completed"));
+}
+
+#[tokio::test]
+async fn test_display_timezone() {
+    wait_for_server().await;
+
+    let client = Client::new();
+    let token = login(&client, "admin@example.com", "change-this-secure-password").await;
+
+    let set_timezone = |timezone: &str| {
+        client
+            .patch(format!("{}/auth/me", BASE_URL))
+            .header("Authorization", format!("Bearer {}", token))
+            .json(&json!({ "display_timezone": timezone }))
+            .send()
+    };
+
+    let res = set_timezone("Mars/Olympus_Mons")
+        .await
+        .expect("Update failed");
+    assert_eq!(res.status(), 422);
+
+    let res = set_timezone("America/New_York")
+        .await
+        .expect("Update failed");
+    assert_eq!(res.status(), 200);
+    let user: serde_json::Value = res.json().await.expect("Failed to parse user");
+    assert_eq!(user["display_timezone"], "America/New_York");
+
+    let form = reqwest::multipart::Form::new()
+        .text("title", "Display Timezone Test")
+        .text("self_sign_only", "true")
+        .part(
+            "file",
+            reqwest::multipart::Part::bytes(generate_pdf(1))
+                .file_name("contract.pdf")
+                .mime_str("application/pdf")
+                .unwrap(),
+        );
+    let res = client
+        .post(format!("{}/documents", BASE_URL))
+        .header("Authorization", format!("Bearer {}", token))
+        .multipart(form)
+        .send()
+        .await
+        .expect("Upload failed");
+    let doc: serde_json::Value = res.json().await.expect("Failed to parse document");
+    let doc_id = doc["id"].as_str().expect("No document ID");
+
+    let res = client
+        .post(format!("{}/documents/{}/fields", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({
+            "field_type": "signature",
+            "page": 1,
+            "x": 100.0,
+            "y": 500.0,
+            "width": 200.0,
+            "height": 50.0
+        }))
+        .send()
+        .await
+        .expect("Add field failed");
+    let field: serde_json::Value = res.json().await.expect("Failed to parse field");
+
+    let res = client
+        .post(format!("{}/documents/{}/send", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Send failed");
+    assert!(res.status().is_success());
+
+    let res = client
+        .post(format!("{}/documents/{}/self-sign", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({
+            "signatures": [{
+                "field_id": field["id"],
+                "signature_data": "data:image/png;base64,iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAYAAAAfFcSJAAAADUlEQVR42mNk+M9QDwADhgGAWjR9awAAAABJRU5ErkJggg=="
+            }],
+            "field_values": []
+        }))
+        .send()
+        .await
+        .expect("Self-sign failed");
+    assert!(res.status().is_success());
+
+    let res = client
+        .get(format!("{}/documents/{}/certificate", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Get certificate failed");
+    assert_eq!(res.status(), 200);
+    let certificate: serde_json::Value = res.json().await.expect("Failed to parse certificate");
+    let local_times = &certificate["local_times"];
+    assert_eq!(local_times["timezone"], "America/New_York");
+    let completed_at = local_times["completed_at"].as_str().unwrap();
+    assert!(completed_at.ends_with("(UTC-04:00)") || completed_at.ends_with("(UTC-05:00)"));
+    assert_eq!(
+        local_times["audit_trail"].as_array().unwrap().len(),
+        certificate["audit_trail"].as_array().unwrap().len()
+    );
+
+    let res = client
+        .get(format!(
+            "{}/documents/{}/audit/export?format=csv",
+            BASE_URL, doc_id
+        ))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Export audit failed");
+    assert_eq!(res.status(), 200);
+    assert!(res.headers()["content-type"]
+        .to_str()
+        .unwrap()
+        .starts_with("text/csv"));
+    assert!(res.headers().contains_key("x-signvault-signature"));
+    let csv = res.text().await.expect("Failed to read export");
+    let mut lines = csv.lines();
+    assert!(lines
+        .next()
+        .unwrap()
+        .starts_with("sequence,timestamp,local_time,action"));
+    let completed = lines
+        .find(|line| line.contains(",document_completed,"))
+        .expect("No completion entry");
+    assert!(completed.contains(" (UTC-0"));
+
+    let res = set_timezone("").await.expect("Update failed");
+    let user: serde_json::Value = res.json().await.expect("Failed to parse user");
+    assert!(user["display_timezone"].is_null());
+}
+
+#[tokio::test]
+async fn test_verify_uploaded_file() {
+    wait_for_server().await;
+
+    let client = Client::new();
+    let token = login(&client, "admin@example.com", "change-this-secure-password").await;
+    let pdf = generate_pdf(2);
+
+    let form = reqwest::multipart::Form::new()
+        .text("title", "Verify File Test")
+        .part(
+            "file",
+            reqwest::multipart::Part::bytes(pdf.clone())
+                .file_name("contract.pdf")
+                .mime_str("application/pdf")
+                .unwrap(),
+        );
+
+    let res = client
+        .post(format!("{}/documents", BASE_URL))
+        .header("Authorization", format!("Bearer {}", token))
+        .multipart(form)
+        .send()
+        .await
+        .expect("Upload failed");
+    assert!(res.status().is_success());
+    let doc: serde_json::Value = res.json().await.expect("Failed to parse document");
+    let doc_id = doc["id"].as_str().expect("No document ID");
+
+    let verify = |data: Vec<u8>| {
+        let form = reqwest::multipart::Form::new().part(
+            "file",
+            reqwest::multipart::Part::bytes(data)
+                .file_name("received.pdf")
+                .mime_str("application/pdf")
+                .unwrap(),
+        );
+        client
+            .post(format!("{}/documents/{}/verify-file", BASE_URL, doc_id))
+            .header("Authorization", format!("Bearer {}", token))
+            .multipart(form)
+            .send()
+    };
+
+    let res = verify(pdf.clone()).await.expect("Verify file failed");
+    assert_eq!(res.status(), 200);
+    let result: serde_json::Value = res.json().await.expect("Failed to parse verification");
+    assert_eq!(result["matched"], "original");
+    assert_eq!(result["matched_hash"], doc["file_hash"]);
+    assert_eq!(result["recorded_at"], doc["created_at"]);
+    assert_eq!(result["uploaded_hash"], doc["file_hash"]);
+    assert_eq!(result["uploaded_size"], pdf.len());
+
+    let mut altered = pdf.clone();
+    altered.extend_from_slice(b"\n% altered\n");
+    let res = verify(altered).await.expect("Verify file failed");
+    assert_eq!(res.status(), 200);
+    let result: serde_json::Value = res.json().await.expect("Failed to parse verification");
+    assert!(result["matched"].is_null());
+    assert!(result["matched_hash"].is_null());
+    assert_ne!(result["uploaded_hash"], doc["file_hash"]);
+
+    let res = client
+        .post(format!("{}/documents/{}/verify-file", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .multipart(reqwest::multipart::Form::new().text("title", "no file"))
+        .send()
+        .await
+        .expect("Verify file failed");
+    assert_eq!(res.status(), 400);
+
+    let res = client
+        .get(format!("{}/documents/{}/audit", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Get audit failed");
+    let logs: Vec<serde_json::Value> = res.json().await.expect("Failed to parse audit");
+    let verified: Vec<_> = logs
+        .iter()
+        .filter(|log| log["action"] == "file_verified")
+        .collect();
+    assert_eq!(verified.len(), 2);
+    assert_eq!(verified[0]["details"]["matched"], "original");
+    assert!(verified[1]["details"]["matched"].is_null());
+}
+
+/// A valid PDF with `pages` pages of text, large enough to make parsing
+/// noticeable.
+fn generate_pdf(pages: usize) -> Vec<u8> {
+    let mut objects = vec![
+        "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+        String::new(),
+        "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica >>".to_string(),
+    ];
+
+    let mut kids = Vec::new();
+    for page in 0..pages {
+        let page_id = objects.len() + 1;
+        let content: String = (0..40)
+            .map(|line| {
+                format!(
+                    "BT /F1 10 Tf 72 {} Td (Page {} line {} of generated load test content) Tj ET\n",
+                    760 - line * 16,
+                    page + 1,
+                    line + 1
+                )
+            })
+            .collect();
+        objects.push(format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 612 792] /Contents {} 0 R /Resources << /Font << /F1 3 0 R >> >> >>",
+            page_id + 1
+        ));
+        objects.push(format!(
+            "<< /Length {} >>\nstream\n{}endstream",
+            content.len(),
+            content
+        ));
+        kids.push(format!("{} 0 R", page_id));
+    }
+    objects[1] = format!(
+        "<< /Type /Pages /Kids [{}] /Count {} >>",
+        kids.join(" "),
+        pages
+    );
+
+    let mut pdf = b"%PDF-1.4\n".to_vec();
+    let mut offsets = Vec::new();
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(pdf.len());
+        pdf.extend_from_slice(format!("{} 0 obj\n{}\nendobj\n", i + 1, object).as_bytes());
+    }
+    let xref = pdf.len();
+    pdf.extend_from_slice(
+        format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).as_bytes(),
+    );
+    for offset in offsets {
+        pdf.extend_from_slice(format!("{:010} 00000 n \n", offset).as_bytes());
+    }
+    pdf.extend_from_slice(
+        format!(
+            "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+            objects.len() + 1,
+            xref
+        )
+        .as_bytes(),
+    );
+    pdf
+}
+
+/// Load test: measures how long a trivial request takes while a burst of large
+/// uploads is being parsed. Run with `cargo test -- --ignored --nocapture
+/// load_test_concurrent_uploads` against a running server.
+#[tokio::test]
+#[ignore]
+async fn load_test_concurrent_uploads() {
+    const UPLOADS: usize = 24;
+    const PAGES: usize = 450;
+
+    wait_for_server().await;
+
+    let client = Client::new();
+    let token = login(&client, "admin@example.com", "change-this-secure-password").await;
+    let pdf = generate_pdf(PAGES);
+
+    let done = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+    let probe = {
+        let client = client.clone();
+        let done = done.clone();
+        tokio::spawn(async move {
+            let mut latencies = Vec::new();
+            while !done.load(std::sync::atomic::Ordering::Relaxed) {
+                let start = std::time::Instant::now();
+                client
+                    .get(format!("{}/health", BASE_URL))
+                    .send()
+                    .await
+                    .expect("Health check failed");
+                latencies.push(start.elapsed());
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+            latencies
+        })
+    };
+
+    let start = std::time::Instant::now();
+    let uploads: Vec<_> = (0..UPLOADS)
+        .map(|i| {
+            let client = client.clone();
+            let token = token.clone();
+            let pdf = pdf.clone();
+            tokio::spawn(async move {
+                let form = reqwest::multipart::Form::new()
+                    .text("title", format!("Load Test {}", i))
+                    .part(
+                        "file",
+                        reqwest::multipart::Part::bytes(pdf)
+                            .file_name("load.pdf")
+                            .mime_str("application/pdf")
+                            .unwrap(),
+                    );
+                let res = client
+                    .post(format!("{}/documents", BASE_URL))
+                    .header("Authorization", format!("Bearer {}", token))
+                    .multipart(form)
+                    .send()
+                    .await
+                    .expect("Upload failed");
+                assert!(res.status().is_success(), "Upload failed: {}", res.status());
+            })
+        })
+        .collect();
+    for upload in uploads {
+        upload.await.unwrap();
+    }
+    let elapsed = start.elapsed();
+
+    done.store(true, std::sync::atomic::Ordering::Relaxed);
+    let mut latencies = probe.await.unwrap();
+    latencies.sort();
+    let percentile = |p: usize| latencies[(latencies.len() - 1) * p / 100];
+
+    println!(
+        "{} uploads of {} pages in {:?}; /health latency p50 {:?}, p99 {:?}, max {:?} over {} probes",
+        UPLOADS,
+        PAGES,
+        elapsed,
+        percentile(50),
+        percentile(99),
+        latencies.last().unwrap(),
+        latencies.len()
+    );
+}
+
+#[tokio::test]
+async fn test_share_links() {
+    wait_for_server().await;
+
+    let client = Client::new();
+    let token = login(&client, "admin@example.com", "change-this-secure-password").await;
+
+    let pdf_content = include_bytes!("../tests/fixtures/sample.pdf");
+    let form = reqwest::multipart::Form::new()
+        .text("title", "Share Link Test")
+        .text("self_sign_only", "true")
+        .part(
+            "file",
+            reqwest::multipart::Part::bytes(pdf_content.to_vec())
+                .file_name("test.pdf")
+                .mime_str("application/pdf")
+                .unwrap(),
+        );
+
+    let res = client
+        .post(format!("{}/documents", BASE_URL))
+        .header("Authorization", format!("Bearer {}", token))
+        .multipart(form)
+        .send()
+        .await
+        .expect("Upload failed");
+    assert!(res.status().is_success());
+    let doc: serde_json::Value = res.json().await.expect("Failed to parse response");
+    let doc_id = doc["id"].as_str().expect("No document ID");
+
+    // Only completed documents can be shared by link
+    let res = client
+        .post(format!("{}/documents/{}/share-links", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({}))
+        .send()
+        .await
+        .expect("Create share link failed");
+    assert_eq!(res.status(), 400);
+
+    let res = client
+        .post(format!("{}/documents/{}/fields", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({
+            "field_type": "signature",
+            "page": 1,
+            "x": 100.0,
+            "y": 500.0,
+            "width": 200.0,
+            "height": 50.0
+        }))
+        .send()
+        .await
+        .expect("Add field failed");
+    let field: serde_json::Value = res.json().await.expect("Failed to parse field");
+    let field_id = field["id"].as_str().expect("No field ID");
+
+    let res = client
+        .post(format!("{}/documents/{}/send", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Send failed");
+    assert!(res.status().is_success());
+
+    let res = client
+        .post(format!("{}/documents/{}/self-sign", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({
+            "signatures": [{
+                "field_id": field_id,
+                "signature_data": "data:image/png;base64,iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAYAAAAfFcSJAAAADUlEQVR42mNk+M9QDwADhgGAWjR9awAAAABJRU5ErkJggg=="
+            }],
+            "field_values": []
+        }))
+        .send()
+        .await
+        .expect("Self-sign failed");
+    assert!(res.status().is_success());
+
+    let res = client
+        .post(format!("{}/documents/{}/share-links", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({ "expires_in_hours": 2, "passcode": "1234" }))
+        .send()
+        .await
+        .expect("Create share link failed");
+    assert!(res.status().is_success());
+    let link: serde_json::Value = res.json().await.expect("Failed to parse link");
+    let link_id = link["id"].as_str().expect("No link ID");
+    let link_token = link["token"].as_str().expect("No link token");
+    assert_eq!(link["has_passcode"], true);
+    assert!(link.get("passcode_hash").is_none());
+    assert!(link["url"].as_str().unwrap().ends_with(link_token));
+
+    let res = client
+        .get(format!("{}/shared/{}", BASE_URL, link_token))
+        .send()
+        .await
+        .expect("Get shared document failed");
+    assert_eq!(res.status(), 401);
+    let body: serde_json::Value = res.json().await.expect("Failed to parse error");
+    assert_eq!(body["error"], "passcode_required");
+
+    let res = client
+        .get(format!("{}/shared/{}", BASE_URL, link_token))
+        .header("X-Share-Passcode", "0000")
+        .send()
+        .await
+        .expect("Get shared document failed");
+    assert_eq!(res.status(), 401);
+
+    let res = client
+        .get(format!("{}/shared/{}", BASE_URL, link_token))
+        .header("X-Share-Passcode", "1234")
+        .send()
+        .await
+        .expect("Get shared document failed");
+    assert_eq!(res.status(), 200);
+    let shared: serde_json::Value = res.json().await.expect("Failed to parse document");
+    assert_eq!(shared["title"], "Share Link Test");
+    assert_eq!(shared["status"], "completed");
+
+    let res = client
+        .get(format!("{}/shared/{}/pdf", BASE_URL, link_token))
+        .header("X-Share-Passcode", "1234")
+        .send()
+        .await
+        .expect("Get shared PDF failed");
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.headers()["content-type"], "application/pdf");
+
+    let res = client
+        .get(format!("{}/shared/{}/certificate", BASE_URL, link_token))
+        .header("X-Share-Passcode", "1234")
+        .send()
+        .await
+        .expect("Get shared certificate failed");
+    assert_eq!(res.status(), 200);
+
+    let res = client
+        .get(format!("{}/documents/{}/share-links", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("List share links failed");
+    let links: serde_json::Value = res.json().await.expect("Failed to parse links");
+    let listed = links
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|l| l["id"] == link_id)
+        .expect("Link not listed");
+    assert_eq!(listed["access_count"], 3);
+    assert!(listed.get("token").is_none());
+
+    let res = client
+        .delete(format!(
+            "{}/documents/{}/share-links/{}",
+            BASE_URL, doc_id, link_id
+        ))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Revoke share link failed");
+    assert!(res.status().is_success());
+
+    let res = client
+        .get(format!("{}/shared/{}", BASE_URL, link_token))
+        .header("X-Share-Passcode", "1234")
+        .send()
+        .await
+        .expect("Get shared document failed");
+    assert_eq!(res.status(), 410);
+    let body: serde_json::Value = res.json().await.expect("Failed to parse error");
+    assert_eq!(body["error"], "link_expired");
+
+    let res = client
+        .get(format!("{}/shared/not-a-real-token", BASE_URL))
+        .send()
+        .await
+        .expect("Get shared document failed");
+    assert_eq!(res.status(), 404);
+
+    let res = client
+        .get(format!("{}/documents/{}/audit", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Get audit failed");
+    let audit_logs: serde_json::Value = res.json().await.expect("Failed to parse audit");
+    let actions: Vec<&str> = audit_logs
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter_map(|l| l["action"].as_str())
+        .collect();
+    for action in [
+        "share_link_created",
+        "share_link_passcode_failed",
+        "share_link_accessed",
+        "share_link_revoked",
+    ] {
+        assert!(actions.contains(&action), "missing {}", action);
+    }
+}
+
+#[tokio::test]
+async fn test_envelope_status_page() {
+    wait_for_server().await;
+
+    let client = Client::new();
+    let token = login(&client, "admin@example.com", "change-this-secure-password").await;
+
+    let pdf_content = include_bytes!("../tests/fixtures/sample.pdf");
+    let form = reqwest::multipart::Form::new()
+        .text("title", "Status Page Test")
+        .text("self_sign_only", "false")
+        .text("signer_download_enabled", "true")
+        .part(
+            "file",
+            reqwest::multipart::Part::bytes(pdf_content.to_vec())
+                .file_name("test.pdf")
+                .mime_str("application/pdf")
+                .unwrap(),
+        );
+
+    let res = client
+        .post(format!("{}/documents", BASE_URL))
+        .header("Authorization", format!("Bearer {}", token))
+        .multipart(form)
+        .send()
+        .await
+        .expect("Upload failed");
+    assert!(res.status().is_success());
+    let doc: serde_json::Value = res.json().await.expect("Failed to parse response");
+    let doc_id = doc["id"].as_str().expect("No document ID");
+    assert_eq!(doc["signer_download_enabled"], true);
+
+    let mut signers = Vec::new();
+    for (email, name) in [
+        ("status-first@example.com", "First Signer"),
+        ("status-second@example.com", "Second Signer"),
+    ] {
+        let res = client
+            .post(format!("{}/documents/{}/signers", BASE_URL, doc_id))
+            .header("Authorization", format!("Bearer {}", token))
+            .json(&json!({ "email": email, "name": name }))
+            .send()
+            .await
+            .expect("Add signer failed");
+        assert!(res.status().is_success());
+        let signer: serde_json::Value = res.json().await.expect("Failed to parse signer");
+
+        let res = client
+            .post(format!("{}/documents/{}/fields", BASE_URL, doc_id))
+            .header("Authorization", format!("Bearer {}", token))
+            .json(&json!({
+                "field_type": "signature",
+                "page": 1,
+                "x": 100.0,
+                "y": 500.0,
+                "width": 200.0,
+                "height": 50.0,
+                "signer_id": signer["id"]
+            }))
+            .send()
+            .await
+            .expect("Add field failed");
+        let field: serde_json::Value = res.json().await.expect("Failed to parse field");
+
+        signers.push((
+            signer["access_token"].as_str().unwrap().to_string(),
+            field["id"].as_str().unwrap().to_string(),
+        ));
+    }
+
+    let res = client
+        .post(format!("{}/documents/{}/send", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Send failed");
+    assert!(res.status().is_success());
+
+    let sign = |access_token: String, field_id: String| {
+        let client = client.clone();
+        async move {
+            let res = client
+                .post(format!("{}/sign/{}/submit", BASE_URL, access_token))
+                .json(&json!({
+                    "signatures": [{
+                        "field_id": field_id,
+                        "signature_data": "data:image/png;base64,iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAYAAAAfFcSJAAAADUlEQVR42mNk+M9QDwADhgGAWjR9awAAAABJRU5ErkJggg=="
+                    }],
+                    "field_values": []
+                }))
+                .send()
+                .await
+                .expect("Submit signing failed");
+            assert!(res.status().is_success());
+        }
+    };
+
+    let (first_token, first_field) = signers[0].clone();
+    sign(first_token.clone(), first_field).await;
+
+    let res = client
+        .get(format!("{}/status/{}", BASE_URL, first_token))
+        .send()
+        .await
+        .expect("Get status failed");
+    assert_eq!(res.status(), 200);
+    let body = res.text().await.expect("Failed to read status");
+    assert!(!body.contains("@example.com"));
+    let status: serde_json::Value = serde_json::from_str(&body).expect("Failed to parse status");
+    assert_eq!(status["document_title"], "Status Page Test");
+    assert_eq!(status["status"], "pending");
+    assert_eq!(status["completed_signers"], 1);
+    assert_eq!(status["total_signers"], 2);
+    assert_eq!(status["signer"]["status"], "signed");
+    assert!(status["signer"]["signed_at"].is_string());
+    assert_eq!(status["download_available"], false);
+
+    let res = client
+        .get(format!("{}/status/{}/pdf", BASE_URL, first_token))
+        .send()
+        .await
+        .expect("Get status PDF failed");
+    assert_eq!(res.status(), 403);
+
+    let (second_token, second_field) = signers[1].clone();
+    sign(second_token, second_field).await;
+
+    let res = client
+        .get(format!("{}/status/{}", BASE_URL, first_token))
+        .send()
+        .await
+        .expect("Get status failed");
+    let status: serde_json::Value = res.json().await.expect("Failed to parse status");
+    assert_eq!(status["status"], "completed");
+    assert_eq!(status["completed_signers"], 2);
+    assert_eq!(status["download_available"], true);
+
+    let res = client
+        .get(format!("{}/status/{}/pdf", BASE_URL, first_token))
+        .send()
+        .await
+        .expect("Get status PDF failed");
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.headers()["content-type"], "application/pdf");
+
+    let res = client
+        .get(format!("{}/status/not-a-real-token", BASE_URL))
+        .send()
+        .await
+        .expect("Get status failed");
+    assert_eq!(res.status(), 404);
+}
+
+#[tokio::test]
+async fn test_email_change() {
+    // Creates its own user and reads the pending change from the database, so
+    // that the shared admin account keeps its email
+    let Ok(database_url) = std::env::var("DATABASE_URL") else {
+        return;
+    };
+    wait_for_server().await;
+
+    let pool = sqlx::PgPool::connect(&database_url)
+        .await
+        .expect("Failed to connect to the database");
+    let run = uuid::Uuid::new_v4();
+    let old_email = format!("email-change-{}@example.com", run);
+    let new_email = format!("email-changed-{}@example.com", run);
+    let password = "email-change-password";
+
+    let user = signvault::db::user::create_user(
+        &pool,
+        &old_email,
+        &bcrypt::hash(password, 4).unwrap(),
+        "Email Change",
+        signvault::models::user::Role::Sender,
+    )
+    .await
+    .expect("Failed to create user");
+
+    let client = Client::new();
+    let token = login(&client, &old_email, password).await;
+
+    let request_change = |new_email: String, password: &'static str| {
+        client
+            .post(format!("{}/auth/email", BASE_URL))
+            .header("Authorization", format!("Bearer {}", token))
+            .json(&json!({ "new_email": new_email, "password": password }))
+            .send()
+    };
+
+    let res = request_change(new_email.clone(), "wrong-password")
+        .await
+        .expect("Request email change failed");
+    assert_eq!(res.status(), 400);
+
+    let res = request_change("admin@example.com".to_string(), password)
+        .await
+        .expect("Request email change failed");
+    assert_eq!(res.status(), 409);
+
+    let res = request_change(new_email.clone(), password)
+        .await
+        .expect("Request email change failed");
+    assert!(res.status().is_success());
+    let change: serde_json::Value = res.json().await.expect("Failed to parse change");
+    assert_eq!(change["new_email"], new_email);
+    assert!(change.get("token_hash").is_none());
+
+    // The emailed token is only stored hashed, so swap in one we know
+    let confirm_token = format!("confirm-{}", run);
+    sqlx::query("UPDATE email_changes SET token_hash = $1 WHERE id = $2")
+        .bind(signvault::services::crypto::hash_token(&confirm_token))
+        .bind(uuid::Uuid::parse_str(change["id"].as_str().unwrap()).unwrap())
+        .execute(&pool)
+        .await
+        .expect("Failed to set token");
+
+    // Nothing changes until the link is followed
+    login(&client, &old_email, password).await;
+
+    let confirm = |token: String| {
+        client
+            .post(format!("{}/auth/email/confirm", BASE_URL))
+            .json(&json!({ "token": token }))
+            .send()
+    };
+
+    let res = confirm("not-a-real-token".to_string())
+        .await
+        .expect("Confirm failed");
+    assert_eq!(res.status(), 404);
+
+    let res = confirm(confirm_token.clone())
+        .await
+        .expect("Confirm failed");
+    assert!(res.status().is_success());
+    let confirmed: serde_json::Value = res.json().await.expect("Failed to parse user");
+    assert_eq!(confirmed["email"], new_email);
+
+    let res = confirm(confirm_token).await.expect("Confirm failed");
+    assert_eq!(res.status(), 410);
+
+    // Logging in works with the new email only
+    let res = client
+        .post(format!("{}/auth/login", BASE_URL))
+        .json(&json!({ "email": old_email, "password": password }))
+        .send()
+        .await
+        .expect("Login request failed");
+    assert_eq!(res.status(), 401);
+    login(&client, &new_email, password).await;
+
+    // Tokens issued before the change still identify the user
+    let res = client
+        .get(format!("{}/auth/me", BASE_URL))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Get current user failed");
+    assert!(res.status().is_success());
+    let me: serde_json::Value = res.json().await.expect("Failed to parse user");
+    assert_eq!(me["email"], new_email);
+
+    let res = client
+        .get(format!("{}/auth/events", BASE_URL))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("List auth events failed");
+    let events: serde_json::Value = res.json().await.expect("Failed to parse events");
+    let types: Vec<&str> = events
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter_map(|e| e["event_type"].as_str())
+        .collect();
+    assert!(types.contains(&"email_change_requested"));
+    assert!(types.contains(&"email_changed"));
+
+    sqlx::query("DELETE FROM users WHERE id = $1")
+        .bind(user.id)
+        .execute(&pool)
+        .await
+        .expect("Failed to delete user");
+}
+
+/// Decodes the unpadded base32 of a TOTP secret.
+fn base32_decode(encoded: &str) -> Vec<u8> {
+    let mut out = Vec::new();
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+    for c in encoded.bytes() {
+        let value = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'2'..=b'7' => c - b'2' + 26,
+            _ => panic!("Invalid base32 character {}", c as char),
+        };
+        buffer = (buffer << 5) | value as u32;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((buffer >> bits) as u8);
+        }
+    }
+    out
+}
+
+#[tokio::test]
+async fn test_two_factor_authentication() {
+    use signvault::services::totp::{code_at, step_at};
+
+    // Creates its own user, as turning on two-factor authentication changes
+    // how it logs in
+    let Ok(database_url) = std::env::var("DATABASE_URL") else {
+        return;
+    };
+    wait_for_server().await;
+
+    let pool = sqlx::PgPool::connect(&database_url)
+        .await
+        .expect("Failed to connect to the database");
+    let email = format!("two-factor-{}@example.com", uuid::Uuid::new_v4());
+    let password = "two-factor-password";
+
+    let user = signvault::db::user::create_user(
+        &pool,
+        &email,
+        &bcrypt::hash(password, 4).unwrap(),
+        "Two Factor",
+        signvault::models::user::Role::Sender,
+    )
+    .await
+    .expect("Failed to create user");
+
+    let client = Client::new();
+    let token = login(&client, &email, password).await;
+    let post = |path: &str, body: serde_json::Value| {
+        client
+            .post(format!("{}{}", BASE_URL, path))
+            .header("Authorization", format!("Bearer {}", token))
+            .json(&body)
+            .send()
+    };
+
+    let res = post("/auth/2fa/enable", json!({ "code": "123456" }))
+        .await
+        .expect("Enable failed");
+    assert_eq!(res.status(), 400);
+
+    let res = post("/auth/2fa/setup", json!({}))
+        .await
+        .expect("Setup failed");
+    assert!(res.status().is_success());
+    let setup: serde_json::Value = res.json().await.expect("Failed to parse setup");
+    let secret = base32_decode(setup["secret"].as_str().unwrap());
+    assert!(setup["provisioning_uri"]
+        .as_str()
+        .unwrap()
+        .starts_with("otpauth://totp/"));
+
+    let step = step_at(chrono::Utc::now().timestamp());
+
+    let wrong_code = if code_at(&secret, step) == "000000" {
+        "111111"
+    } else {
+        "000000"
+    };
+    let res = post("/auth/2fa/enable", json!({ "code": wrong_code }))
+        .await
+        .expect("Enable failed");
+    assert_eq!(res.status(), 400);
+
+    let res = post(
+        "/auth/2fa/enable",
+        json!({ "code": code_at(&secret, step) }),
+    )
+    .await
+    .expect("Enable failed");
+    assert!(res.status().is_success());
+    let enabled: serde_json::Value = res.json().await.expect("Failed to parse recovery codes");
+    let recovery_codes: Vec<String> = enabled["recovery_codes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|code| code.as_str().unwrap().to_string())
+        .collect();
+    assert_eq!(recovery_codes.len(), 10);
+
+    let res = post("/auth/2fa/setup", json!({}))
+        .await
+        .expect("Setup failed");
+    assert_eq!(res.status(), 409);
+
+    let challenge = || async {
+        let res = client
+            .post(format!("{}/auth/login", BASE_URL))
+            .json(&json!({ "email": email, "password": password }))
+            .send()
+            .await
+            .expect("Login failed");
+        assert!(res.status().is_success());
+        let body: serde_json::Value = res.json().await.expect("Failed to parse login");
+        assert_eq!(body["two_factor_required"], true);
+        assert!(body.get("token").is_none());
+        body["challenge_token"].as_str().unwrap().to_string()
+    };
+    let verify = |challenge_token: String, code: String| {
+        client
+            .post(format!("{}/auth/2fa/verify", BASE_URL))
+            .json(&json!({ "challenge_token": challenge_token, "code": code }))
+            .send()
+    };
+
+    let challenge_token = challenge().await;
+
+    // The challenge is not a bearer token
+    let res = client
+        .get(format!("{}/auth/me", BASE_URL))
+        .header("Authorization", format!("Bearer {}", challenge_token))
+        .send()
+        .await
+        .expect("Get user failed");
+    assert_eq!(res.status(), 401);
+
+    // Nor is a bearer token a challenge
+    let res = verify(token.clone(), code_at(&secret, step + 1))
+        .await
+        .expect("Verify failed");
+    assert_eq!(res.status(), 401);
+
+    // The code that enabled two-factor authentication was used up
+    let res = verify(challenge_token.clone(), code_at(&secret, step))
+        .await
+        .expect("Verify failed");
+    assert_eq!(res.status(), 401);
+
+    // A code one step ahead is accepted, for drifting clocks
+    let res = verify(challenge_token.clone(), code_at(&secret, step + 1))
+        .await
+        .expect("Verify failed");
+    assert!(res.status().is_success());
+    let body: serde_json::Value = res.json().await.expect("Failed to parse login");
+    assert_eq!(body["user"]["email"], email);
+    let two_factor_token = body["token"].as_str().unwrap().to_string();
+
+    let res = verify(challenge().await, recovery_codes[0].to_uppercase())
+        .await
+        .expect("Verify failed");
+    assert!(res.status().is_success());
+
+    let res = verify(challenge().await, recovery_codes[0].clone())
+        .await
+        .expect("Verify failed");
+    assert_eq!(res.status(), 401);
+
+    let res = client
+        .get(format!("{}/auth/2fa", BASE_URL))
+        .header("Authorization", format!("Bearer {}", two_factor_token))
+        .send()
+        .await
+        .expect("Get two-factor status failed");
+    let status: serde_json::Value = res.json().await.expect("Failed to parse status");
+    assert_eq!(status["enabled"], true);
+    assert_eq!(status["recovery_codes_remaining"], 9);
+
+    let res = post(
+        "/auth/2fa/disable",
+        json!({ "password": "wrong-password", "code": recovery_codes[1] }),
+    )
+    .await
+    .expect("Disable failed");
+    assert_eq!(res.status(), 400);
+
+    let res = post(
+        "/auth/2fa/disable",
+        json!({ "password": password, "code": recovery_codes[1] }),
+    )
+    .await
+    .expect("Disable failed");
+    assert!(res.status().is_success());
+
+    login(&client, &email, password).await;
+
+    let res = client
+        .get(format!("{}/auth/events?limit=200", BASE_URL))
+        .header("Authorization", format!("Bearer {}", two_factor_token))
+        .send()
+        .await
+        .expect("List auth events failed");
+    let events: Vec<serde_json::Value> = res.json().await.expect("Failed to parse events");
+    let count = |event_type: &str| {
+        events
+            .iter()
+            .filter(|event| event["event_type"] == event_type)
+            .count()
+    };
+    assert_eq!(count("two_factor_enabled"), 1);
+    assert_eq!(count("two_factor_disabled"), 1);
+    assert_eq!(count("two_factor_required"), 3);
+    assert_eq!(count("recovery_code_used"), 2);
+    assert_eq!(count("login_failed"), 2);
+
+    sqlx::query("DELETE FROM users WHERE id = $1")
+        .bind(user.id)
+        .execute(&pool)
+        .await
+        .expect("Failed to delete user");
+}
+
+#[tokio::test]
+async fn test_sessions() {
+    // Creates its own user, so revoking sessions does not sign out other tests
+    let Ok(database_url) = std::env::var("DATABASE_URL") else {
+        return;
+    };
+    wait_for_server().await;
+
+    let pool = sqlx::PgPool::connect(&database_url)
+        .await
+        .expect("Failed to connect to the database");
+    let email = format!("sessions-{}@example.com", uuid::Uuid::new_v4());
+    let password = "sessions-password";
+
+    let user = signvault::db::user::create_user(
+        &pool,
+        &email,
+        &bcrypt::hash(password, 4).unwrap(),
+        "Sessions",
+        signvault::models::user::Role::Sender,
+    )
+    .await
+    .expect("Failed to create user");
+
+    let client = Client::new();
+    let login_from = |user_agent: &'static str| {
+        let client = &client;
+        let email = &email;
+        async move {
+            let res = client
+                .post(format!("{}/auth/login", BASE_URL))
+                .header("User-Agent", user_agent)
+                .json(&json!({ "email": email, "password": password }))
+                .send()
+                .await
+                .expect("Login failed");
+            assert!(res.status().is_success());
+            let body: serde_json::Value = res.json().await.expect("Failed to parse login");
+            body["token"].as_str().unwrap().to_string()
+        }
+    };
+    let get_me = |token: String| {
+        client
+            .get(format!("{}/auth/me", BASE_URL))
+            .header("Authorization", format!("Bearer {}", token))
+            .send()
+    };
+    let delete = |path: String, token: String| {
+        client
+            .delete(format!("{}{}", BASE_URL, path))
+            .header("Authorization", format!("Bearer {}", token))
+            .send()
+    };
+
+    let laptop = login_from("laptop-agent").await;
+    let phone = login_from("phone-agent").await;
+
+    let res = client
+        .get(format!("{}/auth/sessions", BASE_URL))
+        .header("Authorization", format!("Bearer {}", laptop))
+        .send()
+        .await
+        .expect("List sessions failed");
+    assert!(res.status().is_success());
+    let sessions: Vec<serde_json::Value> = res.json().await.expect("Failed to parse sessions");
+    assert_eq!(sessions.len(), 2);
+    let current: Vec<_> = sessions
+        .iter()
+        .filter(|session| session["current"] == true)
+        .collect();
+    assert_eq!(current.len(), 1);
+    assert_eq!(current[0]["user_agent"], "laptop-agent");
+    let laptop_id = current[0]["id"].as_str().unwrap().to_string();
+    let phone_id = sessions
+        .iter()
+        .find(|session| session["user_agent"] == "phone-agent")
+        .expect("Phone session not listed")["id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+
+    let res = delete(format!("/auth/sessions/{}", phone_id), laptop.clone())
+        .await
+        .expect("Revoke session failed");
+    assert!(res.status().is_success());
+    assert_eq!(get_me(phone.clone()).await.unwrap().status(), 401);
+    assert!(get_me(laptop.clone()).await.unwrap().status().is_success());
+
+    let res = delete(format!("/auth/sessions/{}", phone_id), laptop.clone())
+        .await
+        .expect("Revoke session failed");
+    assert_eq!(res.status(), 404);
+    let res = delete(
+        format!("/auth/sessions/{}", uuid::Uuid::new_v4()),
+        laptop.clone(),
+    )
+    .await
+    .expect("Revoke session failed");
+    assert_eq!(res.status(), 404);
+
+    let tablet = login_from("tablet-agent").await;
+    let other = login_from("other-agent").await;
+    let res = delete("/auth/sessions".to_string(), laptop.clone())
+        .await
+        .expect("Revoke other sessions failed");
+    assert!(res.status().is_success());
+    let body: serde_json::Value = res.json().await.expect("Failed to parse response");
+    assert_eq!(body["revoked"], 2);
+    assert_eq!(get_me(tablet).await.unwrap().status(), 401);
+    assert_eq!(get_me(other).await.unwrap().status(), 401);
+    assert!(get_me(laptop.clone()).await.unwrap().status().is_success());
+
+    // Revoking the current session signs out
+    let res = delete(format!("/auth/sessions/{}", laptop_id), laptop.clone())
+        .await
+        .expect("Revoke session failed");
+    assert!(res.status().is_success());
+    assert_eq!(get_me(laptop).await.unwrap().status(), 401);
+
+    sqlx::query("DELETE FROM users WHERE id = $1")
+        .bind(user.id)
+        .execute(&pool)
+        .await
+        .expect("Failed to delete user");
+}
+
+#[tokio::test]
+async fn test_invitations() {
+    // Reads and rewrites invitation tokens in the database, as they are only
+    // delivered by email
+    let Ok(database_url) = std::env::var("DATABASE_URL") else {
+        return;
+    };
+    wait_for_server().await;
+
+    let pool = sqlx::PgPool::connect(&database_url)
+        .await
+        .expect("Failed to connect to the database");
+    let run = uuid::Uuid::new_v4();
+    let email = format!("invitee-{}@example.com", run);
+    let password = "invitee-password";
+
+    let client = Client::new();
+    let admin = login(&client, "admin@example.com", "change-this-secure-password").await;
+    let invite = |email: String| {
+        client
+            .post(format!("{}/admin/invitations", BASE_URL))
+            .header("Authorization", format!("Bearer {}", admin))
+            .json(&json!({ "email": email, "name": "Invitee" }))
+            .send()
+    };
+    let set_token = |id: &str, token: &str| {
+        sqlx::query("UPDATE invitations SET token_hash = $1 WHERE id = $2")
+            .bind(signvault::services::crypto::hash_token(token))
+            .bind(uuid::Uuid::parse_str(id).unwrap())
+            .execute(&pool)
+    };
+    let accept = |token: String, name: &'static str, password: &'static str| {
+        client
+            .post(format!("{}/auth/invitations/{}/accept", BASE_URL, token))
+            .json(&json!({ "name": name, "password": password }))
+            .send()
+    };
+
+    let res = invite(email.clone()).await.expect("Invite failed");
+    assert!(res.status().is_success());
+    let invitation: serde_json::Value = res.json().await.expect("Failed to parse invitation");
+    assert_eq!(invitation["email"], email);
+    assert!(invitation.get("token_hash").is_none());
+    let id = invitation["id"].as_str().unwrap().to_string();
+
+    assert_eq!(invite(email.clone()).await.unwrap().status(), 409);
+    assert_eq!(
+        invite("admin@example.com".to_string())
+            .await
+            .unwrap()
+            .status(),
+        409
+    );
+
+    // The account exists but no password opens it
+    for attempt in ["", password] {
+        let res = client
+            .post(format!("{}/auth/login", BASE_URL))
+            .json(&json!({ "email": email, "password": attempt }))
+            .send()
+            .await
+            .expect("Login failed");
+        assert!(!res.status().is_success());
+    }
+
+    let res = client
+        .get(format!("{}/admin/invitations", BASE_URL))
+        .header("Authorization", format!("Bearer {}", admin))
+        .send()
+        .await
+        .expect("List invitations failed");
+    let pending: Vec<serde_json::Value> = res.json().await.expect("Failed to parse invitations");
+    assert!(pending.iter().any(|invitation| invitation["id"] == id));
+
+    let res = client
+        .post(format!("{}/admin/invitations/{}/resend", BASE_URL, id))
+        .header("Authorization", format!("Bearer {}", admin))
+        .send()
+        .await
+        .expect("Resend failed");
+    assert!(res.status().is_success());
+
+    let token = format!("invitation-{}", run);
+    set_token(&id, &token).await.expect("Failed to set token");
+
+    assert_eq!(
+        accept("not-a-real-token".to_string(), "Invitee", password)
+            .await
+            .unwrap()
+            .status(),
+        404
+    );
+    assert!(accept(token.clone(), "Invitee", "short")
+        .await
+        .unwrap()
+        .status()
+        .is_client_error());
+
+    sqlx::query("UPDATE invitations SET expires_at = NOW() - INTERVAL '1 hour' WHERE id = $1")
+        .bind(uuid::Uuid::parse_str(&id).unwrap())
+        .execute(&pool)
+        .await
+        .expect("Failed to expire invitation");
+    let res = accept(token.clone(), "Invitee", password).await.unwrap();
+    assert_eq!(res.status(), 410);
+    let body: serde_json::Value = res.json().await.expect("Failed to parse error");
+    assert_eq!(body["error"], "link_expired");
+
+    sqlx::query("UPDATE invitations SET expires_at = NOW() + INTERVAL '1 hour' WHERE id = $1")
+        .bind(uuid::Uuid::parse_str(&id).unwrap())
+        .execute(&pool)
+        .await
+        .expect("Failed to extend invitation");
+
+    // Only one of two simultaneous accepts creates the account
+    let (first, second) = tokio::join!(
+        accept(token.clone(), "Invitee Name", password),
+        accept(token.clone(), "Someone Else", "other-password"),
+    );
+    let mut statuses = [first.unwrap().status(), second.unwrap().status()];
+    statuses.sort();
+    assert!(statuses[0].is_success(), "{:?}", statuses);
+    assert_eq!(statuses[1], 409);
+
+    assert_eq!(
+        accept(token.clone(), "Invitee", password)
+            .await
+            .unwrap()
+            .status(),
+        409
+    );
+
+    let user =
+        sqlx::query_as::<_, (uuid::Uuid, String)>("SELECT id, name FROM users WHERE email = $1")
+            .bind(&email)
+            .fetch_one(&pool)
+            .await
+            .expect("Failed to load user");
+    let password = if user.1 == "Invitee Name" {
+        password
+    } else {
+        "other-password"
+    };
+    let invitee = login(&client, &email, password).await;
+
+    let res = client
+        .get(format!("{}/admin/invitations", BASE_URL))
+        .header("Authorization", format!("Bearer {}", invitee))
+        .send()
+        .await
+        .expect("List invitations failed");
+    assert_eq!(res.status(), 403);
+
+    // Accepted invitations can no longer be revoked
+    let res = client
+        .delete(format!("{}/admin/invitations/{}", BASE_URL, id))
+        .header("Authorization", format!("Bearer {}", admin))
+        .send()
+        .await
+        .expect("Revoke failed");
+    assert_eq!(res.status(), 404);
+
+    let revoked_email = format!("revoked-{}@example.com", run);
+    let res = invite(revoked_email.clone()).await.expect("Invite failed");
+    let revoked: serde_json::Value = res.json().await.expect("Failed to parse invitation");
+    let revoked_token = format!("revoked-{}", run);
+    set_token(revoked["id"].as_str().unwrap(), &revoked_token)
+        .await
+        .expect("Failed to set token");
+
+    let res = client
+        .delete(format!(
+            "{}/admin/invitations/{}",
+            BASE_URL,
+            revoked["id"].as_str().unwrap()
+        ))
+        .header("Authorization", format!("Bearer {}", admin))
+        .send()
+        .await
+        .expect("Revoke failed");
+    assert!(res.status().is_success());
+    assert_eq!(
+        accept(revoked_token, "Revoked", "revoked-password")
+            .await
+            .unwrap()
+            .status(),
+        404
+    );
+    // The address is free to invite again
+    assert!(invite(revoked_email.clone())
+        .await
+        .unwrap()
+        .status()
+        .is_success());
+
+    sqlx::query("DELETE FROM users WHERE email = $1 OR email = $2")
+        .bind(&email)
+        .bind(&revoked_email)
+        .execute(&pool)
+        .await
+        .expect("Failed to delete users");
+}
+
+#[tokio::test]
+async fn test_roles() {
+    // Creates its users directly in the database
+    let Ok(database_url) = std::env::var("DATABASE_URL") else {
+        return;
+    };
+    wait_for_server().await;
+
+    let pool = sqlx::PgPool::connect(&database_url)
+        .await
+        .expect("Failed to connect to the database");
+    let run = uuid::Uuid::new_v4();
+    let sender_email = format!("role-sender-{}@example.com", run);
+    let viewer_email = format!("role-viewer-{}@example.com", run);
+    let password = "role-password";
+    let password_hash = bcrypt::hash(password, 4).unwrap();
+
+    signvault::db::user::create_user(
+        &pool,
+        &sender_email,
+        &password_hash,
+        "Role Sender",
+        signvault::models::user::Role::Sender,
+    )
+    .await
+    .expect("Failed to create sender");
+    let viewer = signvault::db::user::create_user(
+        &pool,
+        &viewer_email,
+        &password_hash,
+        "Role Viewer",
+        signvault::models::user::Role::Viewer,
+    )
+    .await
+    .expect("Failed to create viewer");
+
+    let client = Client::new();
+    let admin = login(&client, "admin@example.com", "change-this-secure-password").await;
+    let sender = login(&client, &sender_email, password).await;
+    let viewer_token = login(&client, &viewer_email, password).await;
+
+    let res = client
+        .get(format!("{}/auth/me", BASE_URL))
+        .header("Authorization", format!("Bearer {}", viewer_token))
+        .send()
+        .await
+        .expect("Get me failed");
+    let me: serde_json::Value = res.json().await.expect("Failed to parse user");
+    assert_eq!(me["role"], "viewer");
+
+    let Some(doc_id) = upload_sample_document(&client, &sender, "Roles Test").await else {
+        return;
+    };
+    let res = client
+        .post(format!("{}/documents/{}/shares", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", sender))
+        .json(&json!({ "email": viewer_email, "role": "editor" }))
+        .send()
+        .await
+        .expect("Share failed");
+    assert!(res.status().is_success());
+
+    // A viewer sees what is shared with them but changes nothing, even as an
+    // editor of the document
+    assert!(
+        upload_sample_document(&client, &viewer_token, "Viewer Upload")
+            .await
+            .is_none()
+    );
+    let res = client
+        .get(format!("{}/documents/{}", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", viewer_token))
+        .send()
+        .await
+        .expect("Get document failed");
+    assert!(res.status().is_success());
+    let res = client
+        .patch(format!("{}/documents/{}", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", viewer_token))
+        .json(&json!({ "title": "Renamed" }))
+        .send()
+        .await
+        .expect("Update document failed");
+    assert_eq!(res.status(), 403);
+    let res = client
+        .post(format!("{}/documents/{}/share-links", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", viewer_token))
+        .json(&json!({}))
+        .send()
+        .await
+        .expect("Create share link failed");
+    assert_eq!(res.status(), 403);
+
+    // Only admins reach the admin endpoints
+    for token in [&sender, &viewer_token] {
+        let res = client
+            .get(format!("{}/admin/invitations", BASE_URL))
+            .header("Authorization", format!("Bearer {}", token))
+            .send()
+            .await
+            .expect("List invitations failed");
+        assert_eq!(res.status(), 403);
+    }
+
+    let set_role = |id: String, body: serde_json::Value| {
+        client
+            .put(format!("{}/admin/users/{}/role", BASE_URL, id))
+            .header("Authorization", format!("Bearer {}", admin))
+            .json(&body)
+            .send()
+    };
+
+    let res = set_role(
+        uuid::Uuid::new_v4().to_string(),
+        json!({ "role": "sender" }),
+    )
+    .await
+    .expect("Set role failed");
+    assert_eq!(res.status(), 404);
+
+    // A promotion applies to tokens issued afterwards
+    let res = set_role(viewer.id.to_string(), json!({ "role": "sender" }))
+        .await
+        .expect("Set role failed");
+    assert!(res.status().is_success());
+    let body: serde_json::Value = res.json().await.expect("Failed to parse user");
+    assert_eq!(body["role"], "sender");
+    assert!(
+        upload_sample_document(&client, &viewer_token, "Viewer Upload")
+            .await
+            .is_none()
+    );
+    let promoted = login(&client, &viewer_email, password).await;
+    assert!(
+        upload_sample_document(&client, &promoted, "Promoted Upload")
+            .await
+            .is_some()
+    );
+
+    // Revoking the sessions applies a downgrade at once
+    let res = set_role(
+        viewer.id.to_string(),
+        json!({ "role": "viewer", "revoke_sessions": true }),
+    )
+    .await
+    .expect("Set role failed");
+    assert!(res.status().is_success());
+    let res = client
+        .get(format!("{}/auth/me", BASE_URL))
+        .header("Authorization", format!("Bearer {}", promoted))
+        .send()
+        .await
+        .expect("Get me failed");
+    assert_eq!(res.status(), 401);
+
+    let res = client
+        .get(format!("{}/auth/me", BASE_URL))
+        .header("Authorization", format!("Bearer {}", admin))
+        .send()
+        .await
+        .expect("Get me failed");
+    let me: serde_json::Value = res.json().await.expect("Failed to parse user");
+    assert_eq!(me["role"], "admin");
+    let admin_id = me["id"].as_str().unwrap().to_string();
+    let admin_count = signvault::db::user::count_admin_users(&pool).await.unwrap();
+    if admin_count == 1 {
+        let res = set_role(admin_id, json!({ "role": "sender" }))
+            .await
+            .expect("Set role failed");
+        assert_eq!(res.status(), 409);
+    }
+
+    sqlx::query("DELETE FROM users WHERE email = $1 OR email = $2")
+        .bind(&sender_email)
+        .bind(&viewer_email)
+        .execute(&pool)
+        .await
+        .expect("Failed to delete users");
+}
+
+#[tokio::test]
+async fn test_background_jobs() {
+    wait_for_server().await;
+
+    let client = Client::new();
+    let admin = login(&client, "admin@example.com", "change-this-secure-password").await;
+
+    let list_jobs = || {
+        client
+            .get(format!("{}/admin/jobs", BASE_URL))
+            .header("Authorization", format!("Bearer {}", admin))
+            .send()
+    };
+
+    let jobs: Vec<serde_json::Value> = list_jobs()
+        .await
+        .expect("List jobs failed")
+        .json()
+        .await
+        .expect("Failed to parse jobs");
+    let retention = jobs
+        .iter()
+        .find(|job| job["name"] == "retention")
+        .expect("No retention job");
+    assert_eq!(retention["interval_secs"], 3600);
+
+    let res = client
+        .post(format!("{}/admin/jobs/no-such-job/run", BASE_URL))
+        .header("Authorization", format!("Bearer {}", admin))
+        .send()
+        .await
+        .expect("Run job failed");
+    assert_eq!(res.status(), 404);
+
+    // The scheduled run at startup may still hold the lock
+    let mut run = None;
+    for _ in 0..20 {
+        let res = client
+            .post(format!("{}/admin/jobs/retention/run", BASE_URL))
+            .header("Authorization", format!("Bearer {}", admin))
+            .send()
+            .await
+            .expect("Run job failed");
+        if res.status() == 409 {
+            tokio::time::sleep(Duration::from_millis(250)).await;
+            continue;
+        }
+        assert!(res.status().is_success());
+        run = Some(res.json::<serde_json::Value>().await.unwrap());
+        break;
+    }
+    let run = run.expect("The retention job stayed locked");
+    assert_eq!(run["job_name"], "retention");
+    assert_eq!(run["status"], "running");
+    assert!(run["triggered_by"].is_string());
+
+    let mut last_run = serde_json::Value::Null;
+    for _ in 0..20 {
+        let jobs: Vec<serde_json::Value> = list_jobs().await.unwrap().json().await.unwrap();
+        last_run = jobs
+            .into_iter()
+            .find(|job| job["name"] == "retention")
+            .map(|job| job["last_run"].clone())
+            .unwrap_or_default();
+        if last_run["id"] == run["id"] && last_run["status"] != "running" {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(250)).await;
+    }
+    assert_eq!(last_run["id"], run["id"]);
+    assert_eq!(last_run["status"], "succeeded");
+    assert!(last_run["finished_at"].is_string());
+}
+
+#[tokio::test]
+async fn test_outbox_events() {
+    // Reads the events and queued emails from the database
+    let Ok(database_url) = std::env::var("DATABASE_URL") else {
+        return;
+    };
+    wait_for_server().await;
+
+    let pool = sqlx::PgPool::connect(&database_url)
+        .await
+        .expect("Failed to connect to the database");
+    let client = Client::new();
+    let token = login(&client, "admin@example.com", "change-this-secure-password").await;
+
+    let Some(doc_id) = upload_sample_document(&client, &token, "Outbox Events Test").await else {
+        return;
+    };
+    let doc_uuid: uuid::Uuid = doc_id.parse().unwrap();
+
+    let res = client
+        .post(format!("{}/documents/{}/signers", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({
+            "email": "outbox-approver@example.com",
+            "name": "Outbox Approver",
+            "role": "approver"
+        }))
+        .send()
+        .await
+        .expect("Add approver failed");
+    assert!(res.status().is_success());
+    let approver: serde_json::Value = res.json().await.expect("Failed to parse signer");
+    let approver_id: uuid::Uuid = approver["id"].as_str().unwrap().parse().unwrap();
+    let access_token = approver["access_token"].as_str().expect("No access token");
+
+    let res = client
+        .post(format!("{}/documents/{}/send", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Send failed");
+    assert!(res.status().is_success());
+
+    let res = client
+        .post(format!("{}/sign/{}/approve", BASE_URL, access_token))
+        .json(&json!({ "decision": "approve" }))
+        .send()
+        .await
+        .expect("Approve request failed");
+    assert!(res.status().is_success());
+    let result: serde_json::Value = res.json().await.expect("Failed to parse result");
+    assert_eq!(result["document_completed"], true);
+
+    // Each change recorded one event, dispatched once it committed
+    let events: Vec<(String, Option<uuid::Uuid>, bool)> = sqlx::query_as(
+        r#"
+        SELECT event_type::TEXT, signer_id, processed_at IS NOT NULL
+        FROM outbox_events
+        WHERE document_id = $1
+        ORDER BY created_at, id
+        "#,
+    )
+    .bind(doc_uuid)
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    assert_eq!(
+        events,
+        vec![
+            ("document_sent".to_string(), None, true),
+            ("signer_signed".to_string(), Some(approver_id), true),
+            ("document_completed".to_string(), None, true),
+        ]
+    );
+
+    // With email configured, their emails were queued exactly once: the
+    // request to the approver, and completion notices to the owner and the
+    // approver. Without it nothing is queued.
+    let emails: Vec<(String, Option<uuid::Uuid>)> = sqlx::query_as(
+        r#"
+        SELECT kind::TEXT, signer_id
+        FROM email_outbox
+        WHERE document_id = $1
+        ORDER BY created_at, signer_id NULLS FIRST
+        "#,
+    )
+    .bind(doc_uuid)
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    assert!(
+        emails.is_empty()
+            || emails
+                == vec![
+                    ("signing_request".to_string(), Some(approver_id)),
+                    ("completion_notice".to_string(), None),
+                    ("completion_notice".to_string(), Some(approver_id)),
+                ],
+        "{:?}",
+        emails
+    );
+}
+
+#[tokio::test]
+async fn test_deadline_calendar_updates() {
+    // Reads the queued emails from the database
+    let Ok(database_url) = std::env::var("DATABASE_URL") else {
+        return;
+    };
+    wait_for_server().await;
+
+    let pool = sqlx::PgPool::connect(&database_url)
+        .await
+        .expect("Failed to connect to the database");
+    let client = Client::new();
+    let token = login(&client, "admin@example.com", "change-this-secure-password").await;
+
+    let Some(doc_id) = upload_sample_document(&client, &token, "Deadline Calendar Test").await
+    else {
+        return;
+    };
+    let doc_uuid: uuid::Uuid = doc_id.parse().unwrap();
+
+    let res = client
+        .post(format!("{}/documents/{}/signers", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({
+            "email": "deadline-signer@example.com",
+            "name": "Deadline Signer"
+        }))
+        .send()
+        .await
+        .expect("Add signer failed");
+    assert!(res.status().is_success());
+    let signer: serde_json::Value = res.json().await.expect("Failed to parse signer");
+    let signer_id = signer["id"].as_str().unwrap();
+    add_signature_field(&client, &token, &doc_id, signer_id).await;
+
+    let set_deadline = |expires_at: String| {
+        client
+            .patch(format!("{}/documents/{}", BASE_URL, doc_id))
+            .header("Authorization", format!("Bearer {}", token))
+            .json(&json!({ "expires_at": expires_at }))
+            .send()
+    };
+
+    let first = (chrono::Utc::now() + chrono::Duration::days(7)).to_rfc3339();
+    let res = set_deadline(first.clone()).await.expect("Update failed");
+    assert_eq!(res.status(), 200);
+
+    let res = client
+        .post(format!("{}/documents/{}/send", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Send failed");
+    assert!(res.status().is_success());
+
+    // Setting the same deadline changes nothing; moving it updates the event
+    let res = set_deadline(first).await.expect("Update failed");
+    assert_eq!(res.status(), 200);
+    let second = (chrono::Utc::now() + chrono::Duration::days(14)).to_rfc3339();
+    let res = set_deadline(second).await.expect("Update failed");
+    assert_eq!(res.status(), 200);
+
+    let res = client
+        .post(format!("{}/documents/{}/void", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({ "reason": "Deadline test" }))
+        .send()
+        .await
+        .expect("Void failed");
+    assert!(res.status().is_success());
+
+    // Without email configured nothing is queued. The request is only marked
+    // sent once delivered, so whether the signer counts as waiting for the
+    // deadline change depends on the mail server.
+    let emails: Vec<String> = sqlx::query_scalar(
+        "SELECT kind::TEXT FROM email_outbox WHERE document_id = $1 ORDER BY created_at",
+    )
+    .bind(doc_uuid)
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    assert!(
+        emails.is_empty()
+            || emails == ["signing_request", "deadline_changed", "request_cancelled"]
+            || emails == ["signing_request"],
+        "{:?}",
+        emails
+    );
+}
+
+#[tokio::test]
+async fn test_expiry_warnings() {
+    wait_for_server().await;
+
+    let client = Client::new();
+    let token = login(&client, "admin@example.com", "change-this-secure-password").await;
+
+    // The job only exists with email configured and warnings enabled
+    let jobs: Vec<serde_json::Value> = client
+        .get(format!("{}/admin/jobs", BASE_URL))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("List jobs failed")
+        .json()
+        .await
+        .expect("Failed to parse jobs");
+    if !jobs.iter().any(|job| job["name"] == "expiry_warnings") {
+        return;
+    }
+
+    let Some(doc_id) = upload_sample_document(&client, &token, "Expiry Warning Test").await else {
+        return;
+    };
+    let res = client
+        .post(format!("{}/documents/{}/signers", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({
+            "email": "expiry-signer@example.com",
+            "name": "Expiry Signer"
+        }))
+        .send()
+        .await
+        .expect("Add signer failed");
+    let signer: serde_json::Value = res.json().await.expect("Failed to parse signer");
+    add_signature_field(&client, &token, &doc_id, signer["id"].as_str().unwrap()).await;
+
+    // Expires within the warning window of every non-zero setting
+    let res = client
+        .patch(format!("{}/documents/{}", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({ "expires_at": (chrono::Utc::now() + chrono::Duration::hours(12)).to_rfc3339() }))
+        .send()
+        .await
+        .expect("Update failed");
+    assert_eq!(res.status(), 200);
+    let res = client
+        .post(format!("{}/documents/{}/send", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Send failed");
+    assert!(res.status().is_success());
+
+    let warnings = || async {
+        let logs: Vec<serde_json::Value> = client
+            .get(format!("{}/documents/{}/audit", BASE_URL, doc_id))
+            .header("Authorization", format!("Bearer {}", token))
+            .send()
+            .await
+            .expect("Get audit failed")
+            .json()
+            .await
+            .expect("Failed to parse audit");
+        logs.iter()
+            .filter(|log| {
+                log["action"] == "signer_email_sent" && log["details"]["email"] == "expiry_warning"
+            })
+            .count()
+    };
+
+    run_job_to_completion(&client, &token, "expiry_warnings").await;
+    assert_eq!(warnings().await, 1);
+
+    // The signer is warned once, however often the job runs
+    run_job_to_completion(&client, &token, "expiry_warnings").await;
+    assert_eq!(warnings().await, 1);
+}
+
+/// Starts a run of the job `name` and waits for it to succeed.
+async fn run_job_to_completion(client: &Client, token: &str, name: &str) {
+    // A scheduled run may hold the lock
+    let mut run = None;
+    for _ in 0..20 {
+        let res = client
+            .post(format!("{}/admin/jobs/{}/run", BASE_URL, name))
+            .header("Authorization", format!("Bearer {}", token))
+            .send()
+            .await
+            .expect("Run job failed");
+        if res.status() == 409 {
+            tokio::time::sleep(Duration::from_millis(250)).await;
+            continue;
+        }
+        assert!(res.status().is_success());
+        run = Some(res.json::<serde_json::Value>().await.unwrap());
+        break;
+    }
+    let run = run.expect("The job stayed locked");
+
+    for _ in 0..40 {
+        let jobs: Vec<serde_json::Value> = client
+            .get(format!("{}/admin/jobs", BASE_URL))
+            .header("Authorization", format!("Bearer {}", token))
+            .send()
+            .await
+            .expect("List jobs failed")
+            .json()
+            .await
+            .expect("Failed to parse jobs");
+        let last_run = jobs
+            .into_iter()
+            .find(|job| job["name"] == name)
+            .map(|job| job["last_run"].clone())
+            .unwrap_or_default();
+        if last_run["id"] == run["id"] && last_run["status"] != "running" {
+            assert_eq!(last_run["status"], "succeeded");
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(250)).await;
+    }
+    panic!("The {} job did not finish", name);
+}
+
+#[tokio::test]
+async fn test_progress_notifications() {
+    // Reads the queued emails from the database
+    let Ok(database_url) = std::env::var("DATABASE_URL") else {
+        return;
+    };
+    wait_for_server().await;
+
+    let pool = sqlx::PgPool::connect(&database_url)
+        .await
+        .expect("Failed to connect to the database");
+    let client = Client::new();
+    let token = login(&client, "admin@example.com", "change-this-secure-password").await;
+
+    let Some(doc_id) = upload_sample_document(&client, &token, "Progress Test").await else {
+        return;
+    };
+    let doc_uuid: uuid::Uuid = doc_id.parse().unwrap();
+
+    let patch = |body: serde_json::Value| {
+        client
+            .patch(format!("{}/documents/{}", BASE_URL, doc_id))
+            .header("Authorization", format!("Bearer {}", token))
+            .json(&body)
+            .send()
+    };
+
+    let res = patch(json!({ "progress_notifications": ["every_hour"] }))
+        .await
+        .expect("Patch failed");
+    assert!(res.status().is_client_error());
+
+    let res = patch(json!({ "progress_notifications": ["one_remaining"] }))
+        .await
+        .expect("Patch failed");
+    assert_eq!(res.status(), 200);
+    let document: serde_json::Value = res.json().await.expect("Failed to parse document");
+    assert_eq!(document["progress_notifications"], json!(["one_remaining"]));
+
+    let mut access_tokens = Vec::new();
+    for i in 0..3 {
+        let res = client
+            .post(format!("{}/documents/{}/signers", BASE_URL, doc_id))
+            .header("Authorization", format!("Bearer {}", token))
+            .json(&json!({
+                "email": format!("progress-approver-{}@example.com", i),
+                "name": format!("Progress Approver {}", i),
+                "role": "approver"
+            }))
+            .send()
+            .await
+            .expect("Add approver failed");
+        assert!(res.status().is_success());
+        let approver: serde_json::Value = res.json().await.expect("Failed to parse signer");
+        access_tokens.push(approver["access_token"].as_str().unwrap().to_string());
+    }
+
+    let res = client
+        .post(format!("{}/documents/{}/send", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Send failed");
+    assert!(res.status().is_success());
+
+    let owner_emails = || async {
+        sqlx::query_scalar::<_, String>(
+            r#"
+            SELECT kind::TEXT
+            FROM email_outbox
+            WHERE document_id = $1 AND signer_id IS NULL
+            ORDER BY created_at
+            "#,
+        )
+        .bind(doc_uuid)
+        .fetch_all(&pool)
+        .await
+        .unwrap()
+    };
+
+    let mut queued_after = Vec::new();
+    for access_token in &access_tokens {
+        let res = client
+            .post(format!("{}/sign/{}/approve", BASE_URL, access_token))
+            .json(&json!({ "decision": "approve" }))
+            .send()
+            .await
+            .expect("Approve request failed");
+        assert!(res.status().is_success());
+        queued_after.push(owner_emails().await);
+    }
+
+    // With email configured, the owner hears when one approver is left and
+    // when everyone is done, once each. Without it nothing is queued.
+    if !queued_after[2].is_empty() {
+        assert!(queued_after[0].is_empty());
+        assert_eq!(queued_after[1], vec!["progress_update"]);
+        assert_eq!(
+            queued_after[2],
+            vec!["progress_update", "completion_notice"]
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_signature_images() {
+    wait_for_server().await;
+
+    let client = Client::new();
+    let token = login(&client, "admin@example.com", "change-this-secure-password").await;
+
+    let Some(doc_id) = upload_sample_document(&client, &token, "Signature Image Test").await else {
+        return;
+    };
+
+    let res = client
+        .post(format!("{}/documents/{}/signers", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({
+            "email": "image-signer@example.com",
+            "name": "Image Signer"
+        }))
+        .send()
+        .await
+        .expect("Add signer failed");
+    let signer: serde_json::Value = res.json().await.expect("Failed to parse signer");
+    let signer_id = signer["id"].as_str().unwrap();
+    let access_token = signer["access_token"].as_str().unwrap();
+    let field_id = add_signature_field(&client, &token, &doc_id, signer_id).await;
+
+    let res = client
+        .post(format!("{}/documents/{}/send", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Send failed");
+    assert!(res.status().is_success());
+
+    let pixel = "data:image/png;base64,iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAYAAAAfFcSJAAAADUlEQVR42mNk+M9QDwADhgGAWjR9awAAAABJRU5ErkJggg==";
+    let res = client
+        .post(format!("{}/sign/{}/submit", BASE_URL, access_token))
+        .json(&json!({
+            "signatures": [{ "field_id": field_id, "signature_data": pixel }],
+            "field_values": []
+        }))
+        .send()
+        .await
+        .expect("Submit signing failed");
+    assert!(res.status().is_success());
+
+    let res = client
+        .get(format!("{}/documents/{}/signatures", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("List signatures failed");
+    assert_eq!(res.status(), 200);
+    let signatures: serde_json::Value = res.json().await.expect("Failed to parse signatures");
+    let signature = &signatures[0];
+    assert_eq!(signature["field_id"], field_id.as_str());
+    assert_eq!(signature["content_type"], "image/png");
+    assert!(signature.get("signature_data").is_none());
+    assert!(signature.get("storage_key").is_none());
+    let signature_id = signature["id"].as_str().unwrap();
+
+    let get_image = || async {
+        client
+            .get(format!(
+                "{}/documents/{}/signatures/{}/image",
+                BASE_URL, doc_id, signature_id
+            ))
+            .header("Authorization", format!("Bearer {}", token))
+            .send()
+            .await
+            .expect("Get signature image failed")
+    };
+
+    let res = get_image().await;
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.headers()["content-type"], "image/png");
+    let image = res.bytes().await.unwrap();
+    assert_eq!(
+        image.len() as i64,
+        signature["byte_length"].as_i64().unwrap()
+    );
+    assert!(image.starts_with(b"\x89PNG"));
+
+    let res = client
+        .get(format!(
+            "{}/documents/{}/signatures/{}/image",
+            BASE_URL,
+            doc_id,
+            uuid::Uuid::new_v4()
+        ))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Get signature image failed");
+    assert_eq!(res.status(), 404);
+
+    // Signatures written before images were stored keep them in the table
+    // until first requested
+    let Ok(database_url) = std::env::var("DATABASE_URL") else {
+        return;
+    };
+    let pool = sqlx::PgPool::connect(&database_url)
+        .await
+        .expect("Failed to connect to the database");
+    let signature_uuid: uuid::Uuid = signature_id.parse().unwrap();
+
+    let inline: (Option<String>, Option<String>) =
+        sqlx::query_as("SELECT signature_data, storage_key FROM signatures WHERE id = $1")
+            .bind(signature_uuid)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert!(inline.0.is_none());
+    assert!(inline.1.is_some());
+
+    use base64::Engine;
+    let legacy = format!(
+        "data:image/png;base64,{}",
+        base64::engine::general_purpose::STANDARD.encode(&image)
+    );
+    sqlx::query(
+        "UPDATE signatures SET signature_data = $2, storage_key = NULL, content_type = NULL, byte_length = NULL WHERE id = $1",
+    )
+    .bind(signature_uuid)
+    .bind(&legacy)
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let res = get_image().await;
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.bytes().await.unwrap(), image);
+
+    let moved: (Option<String>, Option<String>) =
+        sqlx::query_as("SELECT signature_data, storage_key FROM signatures WHERE id = $1")
+            .bind(signature_uuid)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert!(moved.0.is_none());
+    assert!(moved
+        .1
+        .is_some_and(|key| key.ends_with(&format!("/signatures/{}.png", signature_id))));
+}
+
+#[tokio::test]
+async fn test_upload_deduplication() {
+    wait_for_server().await;
+
+    let client = Client::new();
+    let token = login(&client, "admin@example.com", "change-this-secure-password").await;
+
+    let Some(first_id) = upload_sample_document(&client, &token, "Dedup Test A").await else {
+        return;
+    };
+    let pdf_content = include_bytes!("../tests/fixtures/sample.pdf");
+    let form = reqwest::multipart::Form::new()
+        .text("title", "Dedup Test B")
+        .part(
+            "file",
+            reqwest::multipart::Part::bytes(pdf_content.to_vec())
+                .file_name("test.pdf")
+                .mime_str("application/pdf")
+                .unwrap(),
+        );
+    let res = client
+        .post(format!("{}/documents", BASE_URL))
+        .header("Authorization", format!("Bearer {}", token))
+        .multipart(form)
+        .send()
+        .await
+        .expect("Upload failed");
+    assert!(res.status().is_success());
+    let second: serde_json::Value = res.json().await.expect("Failed to parse document");
+    let second_id = second["id"].as_str().unwrap().to_string();
+
+    // The same owner already has this file, so the upload points at a copy.
+    // Other tests upload the same sample, so it need not be the first one.
+    let duplicate_of = second["duplicate_of"]
+        .as_str()
+        .expect("Upload was not flagged as a duplicate");
+    assert_ne!(duplicate_of, second_id);
+
+    let Ok(database_url) = std::env::var("DATABASE_URL") else {
+        return;
+    };
+    let pool = sqlx::PgPool::connect(&database_url)
+        .await
+        .expect("Failed to connect to the database");
+
+    // With deduplication on, both documents share one stored file
+    let paths: Vec<String> =
+        sqlx::query_scalar("SELECT file_path FROM documents WHERE id = ANY($1)")
+            .bind(vec![
+                first_id.parse::<uuid::Uuid>().unwrap(),
+                second_id.parse::<uuid::Uuid>().unwrap(),
+            ])
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+    assert_eq!(paths.len(), 2);
+    if paths[0] == paths[1] {
+        let ref_count: i32 =
+            sqlx::query_scalar("SELECT ref_count FROM blobs WHERE storage_key = $1")
+                .bind(&paths[0])
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert!(ref_count >= 2);
+    }
+
+    // Deleting one copy leaves the other readable
+    let res = client
+        .delete(format!("{}/documents/{}", BASE_URL, first_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Delete failed");
+    assert!(res.status().is_success());
+    let res = client
+        .get(format!("{}/documents/{}/download", BASE_URL, second_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Download failed");
+    assert!(res.status().is_success());
+    assert_eq!(res.bytes().await.unwrap().as_ref(), pdf_content.as_slice());
+}
+
+#[tokio::test]
+async fn test_conditional_fields() {
+    wait_for_server().await;
+
+    let client = Client::new();
+    let token = login(&client, "admin@example.com", "change-this-secure-password").await;
+
+    let Some(doc_id) = upload_sample_document(&client, &token, "Conditional Fields Test").await
+    else {
+        return;
+    };
+
+    let res = client
+        .post(format!("{}/documents/{}/signers", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({ "email": "conditional@example.com", "name": "Conditional Signer" }))
+        .send()
+        .await
+        .expect("Add signer failed");
+    let signer: serde_json::Value = res.json().await.expect("Failed to parse signer");
+    let signer_id = signer["id"].as_str().expect("No signer ID");
+    let access_token = signer["access_token"].as_str().expect("No access token");
+    let signature_field_id = add_signature_field(&client, &token, &doc_id, signer_id).await;
+
+    let add_field = |field: serde_json::Value| {
+        client
+            .post(format!("{}/documents/{}/fields", BASE_URL, doc_id))
+            .header("Authorization", format!("Bearer {}", token))
+            .json(&field)
+            .send()
+    };
+
+    let res = add_field(json!({
+        "field_type": "checkbox",
+        "page": 1, "x": 100.0, "y": 100.0, "width": 20.0, "height": 20.0,
+        "signer_id": signer_id
+    }))
+    .await
+    .expect("Add field failed");
+    let checkbox: serde_json::Value = res.json().await.expect("Failed to parse field");
+    let checkbox_id = checkbox["id"].as_str().expect("No field id");
+
+    // The field a condition depends on must hold a value
+    let res = add_field(json!({
+        "field_type": "text",
+        "page": 1, "x": 100.0, "y": 150.0, "width": 200.0, "height": 20.0,
+        "signer_id": signer_id,
+        "condition_field_id": signature_field_id,
+        "condition_value": "true"
+    }))
+    .await
+    .expect("Add field failed");
+    assert_eq!(res.status(), 400);
+
+    let res = add_field(json!({
+        "field_type": "text",
+        "page": 1, "x": 100.0, "y": 150.0, "width": 200.0, "height": 20.0,
+        "signer_id": signer_id,
+        "required": true,
+        "condition_field_id": checkbox_id,
+        "condition_value": "true"
+    }))
+    .await
+    .expect("Add field failed");
+    assert!(res.status().is_success());
+    let company: serde_json::Value = res.json().await.expect("Failed to parse field");
+    let company_id = company["id"].as_str().expect("No field id");
+    assert_eq!(company["required"], true);
+    assert_eq!(company["condition_field_id"], checkbox_id);
+
+    // Fields cannot end up depending on each other
+    let res = client
+        .put(format!(
+            "{}/documents/{}/fields/{}",
+            BASE_URL, doc_id, checkbox_id
+        ))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({ "condition_field_id": company_id, "condition_value": "Acme" }))
+        .send()
+        .await
+        .expect("Update field failed");
+    assert_eq!(res.status(), 400);
+
+    let res = client
+        .post(format!("{}/documents/{}/send", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Send failed");
+    assert!(res.status().is_success());
+
+    let session: serde_json::Value = client
+        .get(format!("{}/sign/{}", BASE_URL, access_token))
+        .send()
+        .await
+        .expect("Get signing session failed")
+        .json()
+        .await
+        .expect("Failed to parse session");
+    let session_company = session["fields"]
+        .as_array()
+        .expect("No fields")
+        .iter()
+        .find(|f| f["id"] == company_id)
+        .expect("Conditional field missing from the session");
+    assert_eq!(session_company["condition_field_id"], checkbox_id);
+    assert_eq!(session_company["condition_value"], "true");
+
+    let submit = |field_values: serde_json::Value| {
+        client
+            .post(format!("{}/sign/{}/submit", BASE_URL, access_token))
+            .json(&json!({
+                "signatures": [{
+                    "field_id": signature_field_id,
+                    "signature_data": "data:image/png;base64,iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAYAAAAfFcSJAAAADUlEQVR42mNk+M9QDwADhgGAWjR9awAAAABJRU5ErkJggg=="
+                }],
+                "field_values": field_values
+            }))
+            .send()
+    };
+
+    // A hidden field accepts no value
+    let res = submit(json!([
+        { "field_id": checkbox_id, "value": "false" },
+        { "field_id": company_id, "value": "Acme" }
+    ]))
+    .await
+    .expect("Submit failed");
+    assert_eq!(res.status(), 400);
+
+    // Once shown, it is required
+    let res = submit(json!([{ "field_id": checkbox_id, "value": "true" }]))
+        .await
+        .expect("Submit failed");
+    assert_eq!(res.status(), 400);
+
+    let res = submit(json!([
+        { "field_id": checkbox_id, "value": "true" },
+        { "field_id": company_id, "value": "Acme" }
+    ]))
+    .await
+    .expect("Submit failed");
+    assert!(res.status().is_success());
+}
+
+#[tokio::test]
+async fn test_number_fields() {
+    wait_for_server().await;
+
+    let client = Client::new();
+    let token = login(&client, "admin@example.com", "change-this-secure-password").await;
+
+    let Some(doc_id) = upload_sample_document(&client, &token, "Number Fields Test").await else {
+        return;
+    };
+
+    let res = client
+        .post(format!("{}/documents/{}/signers", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({ "email": "amount@example.com", "name": "Amount Signer" }))
+        .send()
+        .await
+        .expect("Add signer failed");
+    let signer: serde_json::Value = res.json().await.expect("Failed to parse signer");
+    let signer_id = signer["id"].as_str().expect("No signer ID");
+    let access_token = signer["access_token"].as_str().expect("No access token");
+    let signature_field_id = add_signature_field(&client, &token, &doc_id, signer_id).await;
+
+    let add_field = |field: serde_json::Value| {
+        client
+            .post(format!("{}/documents/{}/fields", BASE_URL, doc_id))
+            .header("Authorization", format!("Bearer {}", token))
+            .json(&field)
+            .send()
+    };
+
+    // Bounds and precision only apply to number fields, and must make sense
+    let res = add_field(json!({
+        "field_type": "text",
+        "page": 1, "x": 100.0, "y": 100.0, "width": 120.0, "height": 20.0,
+        "min_value": "0"
+    }))
+    .await
+    .expect("Add field failed");
+    assert_eq!(res.status(), 400);
+
+    let res = add_field(json!({
+        "field_type": "number",
+        "page": 1, "x": 100.0, "y": 100.0, "width": 120.0, "height": 20.0,
+        "min_value": "100", "max_value": "10"
+    }))
+    .await
+    .expect("Add field failed");
+    assert_eq!(res.status(), 400);
+
+    let res = add_field(json!({
+        "field_type": "number",
+        "page": 1, "x": 100.0, "y": 100.0, "width": 120.0, "height": 20.0,
+        "signer_id": signer_id,
+        "label": "Amount",
+        "min_value": "0",
+        "max_value": "10000",
+        "decimal_places": 2
+    }))
+    .await
+    .expect("Add field failed");
+    assert!(res.status().is_success());
+    let amount: serde_json::Value = res.json().await.expect("Failed to parse field");
+    let amount_id = amount["id"].as_str().expect("No field id");
+    assert_eq!(amount["field_type"], "number");
+    assert_eq!(amount["decimal_places"], 2);
+
+    let res = client
+        .post(format!("{}/documents/{}/send", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Send failed");
+    assert!(res.status().is_success());
+
+    let submit = |value: &str| {
+        client
+            .post(format!("{}/sign/{}/submit", BASE_URL, access_token))
+            .json(&json!({
+                "signatures": [{
+                    "field_id": signature_field_id,
+                    "signature_data": "data:image/png;base64,iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAYAAAAfFcSJAAAADUlEQVR42mNk+M9QDwADhgGAWjR9awAAAABJRU5ErkJggg=="
+                }],
+                "field_values": [{ "field_id": amount_id, "value": value }]
+            }))
+            .send()
+    };
+
+    for (value, expected) in [
+        ("12000", "Amount: Must be at most 10000"),
+        ("12.345", "Amount: Must have at most 2 decimal places"),
+        ("12,5", "Amount: Use a dot as the decimal separator"),
+    ] {
+        let res = submit(value).await.expect("Submit failed");
+        assert_eq!(res.status(), 400, "{value} was accepted");
+        let body: serde_json::Value = res.json().await.expect("Failed to parse error");
+        assert!(
+            body["message"]
+                .as_str()
+                .unwrap_or_default()
+                .contains(expected),
+            "unexpected error for {value}: {body}"
+        );
+    }
+
+    let res = submit("12.5").await.expect("Submit failed");
+    assert!(res.status().is_success());
+
+    let res = client
+        .get(format!("{}/documents/{}", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Get document failed");
+    let document: serde_json::Value = res.json().await.expect("Failed to parse document");
+    let stored = document["fields"]
+        .as_array()
+        .expect("No fields")
+        .iter()
+        .find(|f| f["id"] == amount_id)
+        .expect("Number field missing");
+    assert_eq!(stored["value"], "12.50");
+}
+
+#[tokio::test]
+async fn test_read_only_fields() {
+    wait_for_server().await;
+
+    let client = Client::new();
+    let token = login(&client, "admin@example.com", "change-this-secure-password").await;
+
+    let Some(doc_id) = upload_sample_document(&client, &token, "Read-only Fields Test").await
+    else {
+        return;
+    };
+
+    let res = client
+        .post(format!("{}/documents/{}/signers", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({ "email": "readonly@example.com", "name": "Read-only Signer" }))
+        .send()
+        .await
+        .expect("Add signer failed");
+    let signer: serde_json::Value = res.json().await.expect("Failed to parse signer");
+    let signer_id = signer["id"].as_str().expect("No signer ID");
+    let access_token = signer["access_token"].as_str().expect("No access token");
+    let signature_field_id = add_signature_field(&client, &token, &doc_id, signer_id).await;
+
+    let add_field = |field: serde_json::Value| {
+        client
+            .post(format!("{}/documents/{}/fields", BASE_URL, doc_id))
+            .header("Authorization", format!("Bearer {}", token))
+            .json(&field)
+            .send()
+    };
+
+    // A read-only field needs a value
+    let res = add_field(json!({
+        "field_type": "text",
+        "page": 1, "x": 100.0, "y": 100.0, "width": 200.0, "height": 20.0,
+        "read_only": true
+    }))
+    .await
+    .expect("Add field failed");
+    assert_eq!(res.status(), 400);
+
+    let res = add_field(json!({
+        "field_type": "text",
+        "page": 1, "x": 100.0, "y": 100.0, "width": 200.0, "height": 20.0,
+        "label": "Contract number",
+        "value": "C-2024-001",
+        "read_only": true
+    }))
+    .await
+    .expect("Add field failed");
+    assert!(res.status().is_success());
+    let contract: serde_json::Value = res.json().await.expect("Failed to parse field");
+    let contract_id = contract["id"].as_str().expect("No field id");
+    assert_eq!(contract["read_only"], true);
+
+    // Nor can a field without a value be flipped to read-only
+    let res = add_field(json!({
+        "field_type": "text",
+        "page": 1, "x": 100.0, "y": 150.0, "width": 200.0, "height": 20.0
+    }))
+    .await
+    .expect("Add field failed");
+    let notes: serde_json::Value = res.json().await.expect("Failed to parse field");
+    let notes_id = notes["id"].as_str().expect("No field id");
+
+    let update_field = |field_id: &str, update: serde_json::Value| {
+        client
+            .put(format!(
+                "{}/documents/{}/fields/{}",
+                BASE_URL, doc_id, field_id
+            ))
+            .header("Authorization", format!("Bearer {}", token))
+            .json(&update)
+            .send()
+    };
+
+    let res = update_field(notes_id, json!({ "read_only": true }))
+        .await
+        .expect("Update field failed");
+    assert_eq!(res.status(), 400);
+
+    let res = update_field(contract_id, json!({ "value": " " }))
+        .await
+        .expect("Update field failed");
+    assert_eq!(res.status(), 400);
+
+    let res = client
+        .post(format!("{}/documents/{}/send", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Send failed");
+    assert!(res.status().is_success());
+
+    let session: serde_json::Value = client
+        .get(format!("{}/sign/{}", BASE_URL, access_token))
+        .send()
+        .await
+        .expect("Get signing session failed")
+        .json()
+        .await
+        .expect("Failed to parse session");
+    let session_contract = session["fields"]
+        .as_array()
+        .expect("No fields")
+        .iter()
+        .find(|f| f["id"] == contract_id)
+        .expect("Read-only field missing from the session");
+    assert_eq!(session_contract["read_only"], true);
+    assert_eq!(session_contract["value"], "C-2024-001");
+
+    let submit = |field_values: serde_json::Value| {
+        client
+            .post(format!("{}/sign/{}/submit", BASE_URL, access_token))
+            .json(&json!({
+                "signatures": [{
+                    "field_id": signature_field_id,
+                    "signature_data": "data:image/png;base64,iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAYAAAAfFcSJAAAADUlEQVR42mNk+M9QDwADhgGAWjR9awAAAABJRU5ErkJggg=="
+                }],
+                "field_values": field_values
+            }))
+            .send()
+    };
+
+    let res = submit(json!([{ "field_id": contract_id, "value": "C-9999" }]))
+        .await
+        .expect("Submit failed");
+    assert_eq!(res.status(), 400);
+
+    let res = submit(json!([{ "field_id": notes_id, "value": "Signed on site" }]))
+        .await
+        .expect("Submit failed");
+    assert!(res.status().is_success());
+
+    let document: serde_json::Value = client
+        .get(format!("{}/documents/{}", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Get document failed")
+        .json()
+        .await
+        .expect("Failed to parse document");
+    let stored = document["fields"]
+        .as_array()
+        .expect("No fields")
+        .iter()
+        .find(|f| f["id"] == contract_id)
+        .expect("Read-only field missing");
+    assert_eq!(stored["value"], "C-2024-001");
+}
+
+#[tokio::test]
+async fn test_radio_groups() {
+    wait_for_server().await;
+
+    let client = Client::new();
+    let token = login(&client, "admin@example.com", "change-this-secure-password").await;
+
+    let Some(doc_id) = upload_sample_document(&client, &token, "Radio Groups Test").await else {
+        return;
+    };
+
+    let res = client
+        .post(format!("{}/documents/{}/signers", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({ "email": "radio@example.com", "name": "Radio Signer" }))
+        .send()
+        .await
+        .expect("Add signer failed");
+    let signer: serde_json::Value = res.json().await.expect("Failed to parse signer");
+    let signer_id = signer["id"].as_str().expect("No signer ID");
+    let access_token = signer["access_token"].as_str().expect("No access token");
+    let signature_field_id = add_signature_field(&client, &token, &doc_id, signer_id).await;
+
+    let add_field = |field: serde_json::Value| {
+        client
+            .post(format!("{}/documents/{}/fields", BASE_URL, doc_id))
+            .header("Authorization", format!("Bearer {}", token))
+            .json(&field)
+            .send()
+    };
+
+    // A radio field is always an option of a choice
+    let res = add_field(json!({
+        "field_type": "radio",
+        "page": 1, "x": 100.0, "y": 100.0, "width": 14.0, "height": 14.0,
+        "signer_id": signer_id
+    }))
+    .await
+    .expect("Add field failed");
+    assert_eq!(res.status(), 400);
+
+    let mut options = Vec::new();
+    for (i, label) in ["Card", "Invoice", "Wire"].iter().enumerate() {
+        let res = add_field(json!({
+            "field_type": "radio",
+            "page": 1, "x": 100.0 + 60.0 * i as f64, "y": 100.0, "width": 14.0, "height": 14.0,
+            "signer_id": signer_id,
+            "label": label,
+            "group_key": "payment_method",
+            "required": true
+        }))
+        .await
+        .expect("Add field failed");
+        assert!(res.status().is_success());
+        let field: serde_json::Value = res.json().await.expect("Failed to parse field");
+        options.push(field["id"].as_str().expect("No field id").to_string());
+    }
+
+    // The options of a choice belong to one signer
+    let res = add_field(json!({
+        "field_type": "radio",
+        "page": 1, "x": 300.0, "y": 100.0, "width": 14.0, "height": 14.0,
+        "group_key": "payment_method"
+    }))
+    .await
+    .expect("Add field failed");
+    assert_eq!(res.status(), 400);
+
+    let res = client
+        .post(format!("{}/documents/{}/send", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Send failed");
+    assert!(res.status().is_success());
+
+    let session: serde_json::Value = client
+        .get(format!("{}/sign/{}", BASE_URL, access_token))
+        .send()
+        .await
+        .expect("Get signing session failed")
+        .json()
+        .await
+        .expect("Failed to parse session");
+    assert_eq!(
+        session["radio_groups"],
+        json!([{ "group_key": "payment_method", "field_ids": options, "required": true }])
+    );
+
+    let submit = |field_values: serde_json::Value| {
+        client
+            .post(format!("{}/sign/{}/submit", BASE_URL, access_token))
+            .json(&json!({
+                "signatures": [{
+                    "field_id": signature_field_id,
+                    "signature_data": "data:image/png;base64,iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAYAAAAfFcSJAAAADUlEQVR42mNk+M9QDwADhgGAWjR9awAAAABJRU5ErkJggg=="
+                }],
+                "field_values": field_values
+            }))
+            .send()
+    };
+
+    // The choice is required
+    let res = submit(json!([])).await.expect("Submit failed");
+    assert_eq!(res.status(), 400);
+
+    let res = submit(json!([
+        { "field_id": options[0], "value": "true" },
+        { "field_id": options[2], "value": "true" }
+    ]))
+    .await
+    .expect("Submit failed");
+    assert_eq!(res.status(), 400);
+
+    let res = submit(json!([
+        { "field_id": options[0], "value": "false" },
+        { "field_id": options[1], "value": "true" },
+        { "field_id": options[2], "value": "false" }
+    ]))
+    .await
+    .expect("Submit failed");
+    assert!(res.status().is_success());
+}
+
+#[tokio::test]
+async fn test_duplicate_signer_emails_are_rejected() {
+    wait_for_server().await;
+
+    let client = Client::new();
+    let token = login(&client, "admin@example.com", "change-this-secure-password").await;
+
+    let Some(doc_id) = upload_sample_document(&client, &token, "Duplicate Signers Test").await
+    else {
+        return;
+    };
+
+    let add_signer = |email: &str, name: &str| {
+        client
+            .post(format!("{}/documents/{}/signers", BASE_URL, doc_id))
+            .header("Authorization", format!("Bearer {}", token))
+            .json(&json!({ "email": email, "name": name }))
+            .send()
+    };
+
+    let res = add_signer("dana@example.com", "Dana")
+        .await
+        .expect("Add signer failed");
+    assert!(res.status().is_success());
+    let dana: serde_json::Value = res.json().await.expect("Failed to parse signer");
+    let dana_id = dana["id"].as_str().expect("No signer ID");
+
+    // Emails are compared ignoring case
+    let res = add_signer("Dana@Example.COM", "Dana Again")
+        .await
+        .expect("Add signer failed");
+    assert_eq!(res.status(), 409);
+    let body: serde_json::Value = res.json().await.expect("Failed to parse error");
+    let message = body["message"].as_str().unwrap_or_default();
+    assert!(message.contains("Dana"), "unexpected message: {message}");
+    assert!(message.contains(dana_id), "unexpected message: {message}");
+
+    let res = add_signer("erin@example.com", "Erin")
+        .await
+        .expect("Add signer failed");
+    assert!(res.status().is_success());
+    let erin: serde_json::Value = res.json().await.expect("Failed to parse signer");
+    let erin_id = erin["id"].as_str().expect("No signer ID");
+
+    // Nor can another signer be renamed to it
+    let res = client
+        .put(format!(
+            "{}/documents/{}/signers/{}",
+            BASE_URL, doc_id, erin_id
+        ))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({ "email": "DANA@example.com" }))
+        .send()
+        .await
+        .expect("Update signer failed");
+    assert_eq!(res.status(), 409);
+
+    // A signer keeps their own email
+    let res = client
+        .put(format!(
+            "{}/documents/{}/signers/{}",
+            BASE_URL, doc_id, dana_id
+        ))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({ "email": "DANA@example.com" }))
+        .send()
+        .await
+        .expect("Update signer failed");
+    assert!(res.status().is_success());
+
+    // Concurrent adds of the same email leave one signer
+    let results =
+        futures::future::join_all((0..4).map(|_| add_signer("frank@example.com", "Frank"))).await;
+    let added = results
+        .into_iter()
+        .map(|res| res.expect("Add signer failed").status())
+        .filter(|status| status.is_success())
+        .count();
+    assert_eq!(added, 1);
+}
+
+#[tokio::test]
+async fn test_amend_pending_document_with_signer() {
+    wait_for_server().await;
+
+    let client = Client::new();
+    let token = login(&client, "admin@example.com", "change-this-secure-password").await;
+
+    let Some(doc_id) = upload_sample_document(&client, &token, "Amend Signers Test").await else {
+        return;
+    };
+
+    let mut signers = Vec::new();
+    for (email, name, y) in [
+        ("amend-a@example.com", "Amend Signer A", 500.0),
+        ("amend-b@example.com", "Amend Signer B", 400.0),
+    ] {
+        let res = client
+            .post(format!("{}/documents/{}/signers", BASE_URL, doc_id))
+            .header("Authorization", format!("Bearer {}", token))
+            .json(&json!({ "email": email, "name": name }))
+            .send()
+            .await
+            .expect("Add signer failed");
+        let signer: serde_json::Value = res.json().await.expect("Failed to parse signer");
+
+        let res = client
+            .post(format!("{}/documents/{}/fields", BASE_URL, doc_id))
+            .header("Authorization", format!("Bearer {}", token))
+            .json(&json!({
+                "field_type": "signature",
+                "page": 1,
+                "x": 100.0,
+                "y": y,
+                "width": 200.0,
+                "height": 50.0,
+                "signer_id": signer["id"]
+            }))
+            .send()
+            .await
+            .expect("Add field failed");
+        let field: serde_json::Value = res.json().await.expect("Failed to parse field");
+
+        signers.push((signer, field));
+    }
+
+    let amend = |body: serde_json::Value| {
+        client
+            .post(format!("{}/documents/{}/amend/signers", BASE_URL, doc_id))
+            .header("Authorization", format!("Bearer {}", token))
+            .json(&body)
+            .send()
+    };
+    let sign = |access_token: &str, signatures: serde_json::Value| {
+        client
+            .post(format!("{}/sign/{}/submit", BASE_URL, access_token))
+            .json(&json!({ "signatures": signatures, "field_values": [] }))
+            .send()
+    };
+    let signature = |field: &serde_json::Value| {
+        json!([{
+            "field_id": field["id"],
+            "signature_data": "data:image/png;base64,iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAYAAAAfFcSJAAAADUlEQVR42mNk+M9QDwADhgGAWjR9awAAAABJRU5ErkJggg=="
+        }])
+    };
+    let get_document = || async {
+        let res = client
+            .get(format!("{}/documents/{}", BASE_URL, doc_id))
+            .header("Authorization", format!("Bearer {}", token))
+            .send()
+            .await
+            .expect("Get document failed");
+        let body: serde_json::Value = res.json().await.expect("Failed to parse document");
+        body["document"].clone()
+    };
+
+    // Drafts take signers the usual way
+    let res = amend(json!({ "email": "amend-c@example.com", "name": "Co-signer" }))
+        .await
+        .expect("Amend failed");
+    assert_eq!(res.status(), 400);
+
+    client
+        .post(format!("{}/documents/{}/send", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Send failed");
+
+    let (first, first_field) = &signers[0];
+    let res = sign(
+        first["access_token"].as_str().unwrap(),
+        signature(first_field),
+    )
+    .await
+    .expect("Submit failed");
+    assert!(res.status().is_success());
+
+    // Not before someone who already signed
+    let res = amend(json!({
+        "email": "amend-c@example.com",
+        "name": "Co-signer",
+        "order_index": first["order_index"]
+    }))
+    .await
+    .expect("Amend failed");
+    assert_eq!(res.status(), 400);
+
+    let res = amend(json!({ "email": "AMEND-A@example.com", "name": "Again" }))
+        .await
+        .expect("Amend failed");
+    assert_eq!(res.status(), 409);
+
+    let res = amend(json!({ "email": "amend-c@example.com", "name": "Co-signer" }))
+        .await
+        .expect("Amend failed");
+    assert_eq!(res.status(), 200);
+    let added: serde_json::Value = res.json().await.expect("Failed to parse signer");
+    assert!(added["order_index"].as_i64() > first["order_index"].as_i64());
+
+    let document = get_document().await;
+    assert_eq!(document["total_signers"], 3);
+    assert_eq!(document["completed_signers"], 1);
+
+    // Everyone who was there at sending has signed, the co-signer has not
+    let (second, second_field) = &signers[1];
+    let res = sign(
+        second["access_token"].as_str().unwrap(),
+        signature(second_field),
+    )
+    .await
+    .expect("Submit failed");
+    assert!(res.status().is_success());
+    assert_eq!(get_document().await["status"], "pending");
+
+    let res = sign(added["access_token"].as_str().unwrap(), json!([]))
+        .await
+        .expect("Submit failed");
+    assert!(res.status().is_success());
+    assert_eq!(get_document().await["status"], "completed");
+
+    let res = amend(json!({ "email": "amend-d@example.com", "name": "Too Late" }))
+        .await
+        .expect("Amend failed");
+    assert_eq!(res.status(), 409);
+
+    let res = client
+        .get(format!("{}/documents/{}/audit", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Get audit failed");
+    let logs: Vec<serde_json::Value> = res.json().await.expect("Failed to parse audit");
+    let amendments: Vec<_> = logs
+        .iter()
+        .filter(|log| log["action"] == "document_amended")
+        .collect();
+    assert_eq!(amendments.len(), 1);
+    assert_eq!(amendments[0]["details"]["change"], "signer_added");
+}
+
+#[tokio::test]
+async fn test_amend_pending_document_by_removing_and_replacing_signers() {
+    wait_for_server().await;
+
+    let client = Client::new();
+    let token = login(&client, "admin@example.com", "change-this-secure-password").await;
+
+    let Some(doc_id) = upload_sample_document(&client, &token, "Amend Removal Test").await else {
+        return;
+    };
+
+    let mut signers = Vec::new();
+    for (email, name, y) in [
+        ("removal-a@example.com", "Removal Signer A", 500.0),
+        ("removal-b@example.com", "Removal Signer B", 400.0),
+        ("removal-c@example.com", "Removal Signer C", 300.0),
+    ] {
+        let res = client
+            .post(format!("{}/documents/{}/signers", BASE_URL, doc_id))
+            .header("Authorization", format!("Bearer {}", token))
+            .json(&json!({ "email": email, "name": name }))
+            .send()
+            .await
+            .expect("Add signer failed");
+        let signer: serde_json::Value = res.json().await.expect("Failed to parse signer");
+
+        let res = client
+            .post(format!("{}/documents/{}/fields", BASE_URL, doc_id))
+            .header("Authorization", format!("Bearer {}", token))
+            .json(&json!({
+                "field_type": "signature",
+                "page": 1,
+                "x": 100.0,
+                "y": y,
+                "width": 200.0,
+                "height": 50.0,
+                "signer_id": signer["id"]
+            }))
+            .send()
+            .await
+            .expect("Add field failed");
+        let field: serde_json::Value = res.json().await.expect("Failed to parse field");
+
+        signers.push((signer, field));
+    }
+
+    client
+        .post(format!("{}/documents/{}/send", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Send failed");
+
+    let amend = |signer_id: &serde_json::Value, action: &str, body: serde_json::Value| {
+        client
+            .post(format!(
+                "{}/documents/{}/amend/signers/{}/{}",
+                BASE_URL,
+                doc_id,
+                signer_id.as_str().unwrap(),
+                action
+            ))
+            .header("Authorization", format!("Bearer {}", token))
+            .json(&body)
+            .send()
+    };
+    let sign = |access_token: &str, field: &serde_json::Value| {
+        client
+            .post(format!("{}/sign/{}/submit", BASE_URL, access_token))
+            .json(&json!({
+                "signatures": [{
+                    "field_id": field["id"],
+                    "signature_data": "data:image/png;base64,iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAYAAAAfFcSJAAAADUlEQVR42mNk+M9QDwADhgGAWjR9awAAAABJRU5ErkJggg=="
+                }],
+                "field_values": []
+            }))
+            .send()
+    };
+    let get_document = || async {
+        let res = client
+            .get(format!("{}/documents/{}", BASE_URL, doc_id))
+            .header("Authorization", format!("Bearer {}", token))
+            .send()
+            .await
+            .expect("Get document failed");
+        res.json::<serde_json::Value>()
+            .await
+            .expect("Failed to parse document")
+    };
+
+    let (first, first_field) = &signers[0];
+    let res = sign(first["access_token"].as_str().unwrap(), first_field)
+        .await
+        .expect("Submit failed");
+    assert!(res.status().is_success());
+
+    // Signers who signed stay
+    let res = amend(&first["id"], "remove", json!({}))
+        .await
+        .expect("Remove failed");
+    assert_eq!(res.status(), 409);
+
+    // The replacement takes over the second signer's place and field
+    let (second, second_field) = &signers[1];
+    let res = amend(
+        &second["id"],
+        "replace",
+        json!({ "email": "removal-a@example.com", "name": "Taken" }),
+    )
+    .await
+    .expect("Replace failed");
+    assert_eq!(res.status(), 409);
+
+    let res = amend(
+        &second["id"],
+        "replace",
+        json!({
+            "email": "removal-d@example.com",
+            "name": "Removal Signer D",
+            "reason": "Left the company"
+        }),
+    )
+    .await
+    .expect("Replace failed");
+    assert_eq!(res.status(), 200);
+    let replacement: serde_json::Value = res.json().await.expect("Failed to parse signer");
+    assert_eq!(replacement["order_index"], second["order_index"]);
+
+    let res = client
+        .get(format!(
+            "{}/sign/{}",
+            BASE_URL,
+            second["access_token"].as_str().unwrap()
+        ))
+        .send()
+        .await
+        .expect("Get signing session failed");
+    assert_eq!(res.status(), 410);
+
+    let document = get_document().await;
+    let ids: Vec<_> = document["signers"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|s| s["id"].clone())
+        .collect();
+    assert_eq!(ids.len(), 3);
+    assert!(!ids.contains(&second["id"]));
+    let field = document["fields"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|f| f["id"] == second_field["id"])
+        .expect("Field missing");
+    assert_eq!(field["signer_id"], replacement["id"]);
+
+    let res = sign(replacement["access_token"].as_str().unwrap(), second_field)
+        .await
+        .expect("Submit failed");
+    assert!(res.status().is_success());
+
+    // Removing the last signer outstanding completes the document
+    let (third, _) = &signers[2];
+    let res = amend(&third["id"], "remove", json!({}))
+        .await
+        .expect("Remove failed");
+    assert_eq!(res.status(), 200);
+    let removed: serde_json::Value = res.json().await.expect("Failed to parse signer");
+    assert_eq!(removed["status"], "removed");
+
+    let document = get_document().await;
+    assert_eq!(document["document"]["status"], "completed");
+    assert_eq!(document["document"]["total_signers"], 2);
+    assert_eq!(document["document"]["completed_signers"], 2);
+
+    let res = amend(&replacement["id"], "remove", json!({}))
+        .await
+        .expect("Remove failed");
+    assert_eq!(res.status(), 409);
+
+    let res = client
+        .get(format!("{}/documents/{}/audit", BASE_URL, doc_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+        .expect("Get audit failed");
+    let logs: Vec<serde_json::Value> = res.json().await.expect("Failed to parse audit");
+    let amendments: Vec<_> = logs
+        .iter()
+        .filter(|log| log["action"] == "document_amended")
+        .collect();
+    assert_eq!(amendments.len(), 2);
+    let replaced = &amendments[0]["details"];
+    assert_eq!(replaced["change"], "signer_replaced");
+    assert_eq!(replaced["reason"], "Left the company");
+    assert_eq!(replaced["participants_before"].as_array().unwrap().len(), 3);
+    assert_eq!(replaced["participants_after"].as_array().unwrap().len(), 3);
+    let removal = &amendments[1]["details"];
+    assert_eq!(removal["change"], "signer_removed");
+    assert_eq!(removal["participants_after"].as_array().unwrap().len(), 2);
+    assert!(logs.iter().any(|log| log["action"] == "document_completed"));
 }
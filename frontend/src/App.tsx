@@ -5,6 +5,10 @@ import LoginPage from '@/pages/LoginPage';
 import DashboardPage from '@/pages/DashboardPage';
 import DocumentEditorPage from '@/pages/DocumentEditorPage';
 import SigningPage from '@/pages/SigningPage';
+import SharedDocumentPage from '@/pages/SharedDocumentPage';
+import StatusPage from '@/pages/StatusPage';
+import ConfirmEmailPage from '@/pages/ConfirmEmailPage';
+import AcceptInvitationPage from '@/pages/AcceptInvitationPage';
 import Layout from '@/components/Layout';
 
 function ProtectedRoute({ children }: { children: React.ReactNode }) {
@@ -33,7 +37,11 @@ function App() {
   return (
     <Routes>
       <Route path="/login" element={<LoginPage />} />
+      <Route path="/confirm-email/:token" element={<ConfirmEmailPage />} />
+      <Route path="/invitations/:token" element={<AcceptInvitationPage />} />
       <Route path="/sign/:token" element={<SigningPage />} />
+      <Route path="/status/:token" element={<StatusPage />} />
+      <Route path="/shared/:token" element={<SharedDocumentPage />} />
       <Route
         path="/"
         element={
@@ -1,25 +1,66 @@
 import type {
+  LoginOutcome,
   LoginResponse,
   User,
+  UpdateProfileRequest,
+  Branding,
+  UpdateBrandingRequest,
+  StorageUsage,
+  AuthEvent,
+  EmailChange,
+  UserSession,
+  TwoFactorStatus,
+  TwoFactorSetup,
+  RecoveryCodes,
   DocumentListResponse,
+  DocumentSearchResponse,
+  TagMode,
+  TagUsage,
+  SharedDocumentListResponse,
+  DocumentShare,
+  ShareRole,
+  ShareLink,
+  CreatedShareLink,
+  CreateShareLinkRequest,
+  ShareLinkDocument,
+  BulkDocumentAction,
+  BulkDocumentResponse,
   DocumentWithFields,
   Document,
+  UploadedDocument,
+  PreflightResponse,
   DocumentField,
+  Signature,
   Signer,
+  ReplaceSignerRequest,
+  CreateOpenLinkRequest,
+  OpenLinkResponse,
+  ClaimSigningLinkResponse,
+  SigningSessionToken,
   AuditLog,
   Certificate,
+  DocumentTimestamp,
+  FileVerification,
   SigningSession,
+  EnvelopeStatus,
   AddFieldRequest,
   UpdateFieldRequest,
   AddSignerRequest,
   CompleteSigningRequest,
+  SigningSubmissionResponse,
+  SaveSigningProgressResponse,
   ApiError,
 } from '@/types';
 
 const API_BASE = '/api';
 
+function sharePasscodeHeaders(passcode?: string): Record<string, string> {
+  return passcode ? { 'X-Share-Passcode': passcode } : {};
+}
+
 class ApiClient {
   private token: string | null = null;
+  private signingSessions = new Map<string, SigningSessionToken>();
 
   setToken(token: string | null): void {
     this.token = token;
@@ -46,7 +87,7 @@ class ApiClient {
     };
 
     const token = this.getToken();
-    if (token) {
+    if (token && !('Authorization' in headers)) {
       (headers as Record<string, string>)['Authorization'] = `Bearer ${token}`;
     }
 
@@ -69,7 +110,12 @@ class ApiClient {
           message: `Request failed with status ${response.status}`,
         };
       }
-      throw new ApiClientError(errorData.message, errorData.error, response.status);
+      throw new ApiClientError(
+        errorData.message,
+        errorData.error,
+        response.status,
+        errorData.current
+      );
     }
 
     if (response.status === 204) {
@@ -85,27 +131,183 @@ class ApiClient {
   }
 
   // Auth
-  async login(email: string, password: string): Promise<LoginResponse> {
-    const response = await this.request<LoginResponse>('/auth/login', {
+  async login(email: string, password: string): Promise<LoginOutcome> {
+    const response = await this.request<LoginOutcome>('/auth/login', {
       method: 'POST',
       body: JSON.stringify({ email, password }),
     });
+    if ('token' in response) {
+      this.setToken(response.token);
+    }
+    return response;
+  }
+
+  async verifyTwoFactor(challengeToken: string, code: string): Promise<LoginResponse> {
+    const response = await this.request<LoginResponse>('/auth/2fa/verify', {
+      method: 'POST',
+      body: JSON.stringify({ challenge_token: challengeToken, code }),
+    });
+    this.setToken(response.token);
+    return response;
+  }
+
+  async acceptInvitation(token: string, name: string, password: string): Promise<LoginResponse> {
+    const response = await this.request<LoginResponse>(
+      `/auth/invitations/${encodeURIComponent(token)}/accept`,
+      {
+        method: 'POST',
+        body: JSON.stringify({ name, password }),
+      }
+    );
     this.setToken(response.token);
     return response;
   }
 
+  async getTwoFactorStatus(): Promise<TwoFactorStatus> {
+    return this.request<TwoFactorStatus>('/auth/2fa');
+  }
+
+  async setupTwoFactor(): Promise<TwoFactorSetup> {
+    return this.request<TwoFactorSetup>('/auth/2fa/setup', { method: 'POST' });
+  }
+
+  async enableTwoFactor(code: string): Promise<RecoveryCodes> {
+    return this.request<RecoveryCodes>('/auth/2fa/enable', {
+      method: 'POST',
+      body: JSON.stringify({ code }),
+    });
+  }
+
+  async disableTwoFactor(password: string, code: string): Promise<void> {
+    await this.request<{ success: boolean }>('/auth/2fa/disable', {
+      method: 'POST',
+      body: JSON.stringify({ password, code }),
+    });
+  }
+
   async getCurrentUser(): Promise<User> {
     return this.request<User>('/auth/me');
   }
 
+  async updateProfile(data: UpdateProfileRequest): Promise<User> {
+    return this.request<User>('/auth/me', {
+      method: 'PATCH',
+      body: JSON.stringify(data),
+    });
+  }
+
+  async getBranding(): Promise<Branding> {
+    return this.request<Branding>('/auth/branding');
+  }
+
+  async updateBranding(data: UpdateBrandingRequest): Promise<Branding> {
+    return this.request<Branding>('/auth/branding', {
+      method: 'PUT',
+      body: JSON.stringify(data),
+    });
+  }
+
+  async getStorageUsage(): Promise<StorageUsage> {
+    return this.request<StorageUsage>('/auth/usage');
+  }
+
+  async listAuthEvents(limit = 50, offset = 0): Promise<AuthEvent[]> {
+    return this.request<AuthEvent[]>(
+      `/auth/events?limit=${limit}&offset=${offset}`
+    );
+  }
+
+  async listSessions(): Promise<UserSession[]> {
+    return this.request<UserSession[]>('/auth/sessions');
+  }
+
+  async revokeSession(id: string): Promise<void> {
+    await this.request<{ success: boolean }>(`/auth/sessions/${id}`, {
+      method: 'DELETE',
+    });
+  }
+
+  // Signs out every other device; returns how many sessions were revoked
+  async revokeOtherSessions(): Promise<number> {
+    const response = await this.request<{ revoked: number }>('/auth/sessions', {
+      method: 'DELETE',
+    });
+    return response.revoked;
+  }
+
+  async requestEmailChange(newEmail: string, password: string): Promise<EmailChange> {
+    return this.request<EmailChange>('/auth/email', {
+      method: 'POST',
+      body: JSON.stringify({ new_email: newEmail, password }),
+    });
+  }
+
+  async confirmEmailChange(token: string): Promise<User> {
+    return this.request<User>('/auth/email/confirm', {
+      method: 'POST',
+      body: JSON.stringify({ token }),
+    });
+  }
+
   logout(): void {
     this.setToken(null);
   }
 
   // Documents
-  async listDocuments(limit = 20, offset = 0): Promise<DocumentListResponse> {
-    return this.request<DocumentListResponse>(
-      `/documents?limit=${limit}&offset=${offset}`
+  async listDocuments(
+    limit = 20,
+    offset = 0,
+    tags: string[] = [],
+    tagMode: TagMode = 'all'
+  ): Promise<DocumentListResponse> {
+    const params = new URLSearchParams({
+      limit: String(limit),
+      offset: String(offset),
+    });
+    if (tags.length > 0) {
+      params.set('tags', tags.join(','));
+      params.set('tag_mode', tagMode);
+    }
+    return this.request<DocumentListResponse>(`/documents?${params}`);
+  }
+
+  async searchDocuments(
+    q: string,
+    limit = 20,
+    offset = 0
+  ): Promise<DocumentSearchResponse> {
+    const params = new URLSearchParams({
+      q,
+      limit: String(limit),
+      offset: String(offset),
+    });
+    return this.request<DocumentSearchResponse>(`/documents/search?${params}`);
+  }
+
+  async listTags(): Promise<TagUsage[]> {
+    return this.request<TagUsage[]>('/documents/tags');
+  }
+
+  async addTags(id: string, tags: string[]): Promise<string[]> {
+    return this.request<string[]>(`/documents/${id}/tags`, {
+      method: 'POST',
+      body: JSON.stringify({ tags }),
+    });
+  }
+
+  async removeTag(id: string, name: string): Promise<string[]> {
+    return this.request<string[]>(
+      `/documents/${id}/tags/${encodeURIComponent(name)}`,
+      { method: 'DELETE' }
+    );
+  }
+
+  async listSharedDocuments(
+    limit = 20,
+    offset = 0
+  ): Promise<SharedDocumentListResponse> {
+    return this.request<SharedDocumentListResponse>(
+      `/documents/shared?limit=${limit}&offset=${offset}`
     );
   }
 
@@ -117,13 +319,13 @@ class ApiClient {
     title: string,
     file: File,
     selfSignOnly: boolean
-  ): Promise<Document> {
+  ): Promise<UploadedDocument> {
     const formData = new FormData();
     formData.append('title', title);
     formData.append('file', file);
     formData.append('self_sign_only', selfSignOnly.toString());
 
-    return this.request<Document>('/documents', {
+    return this.request<UploadedDocument>('/documents', {
       method: 'POST',
       body: formData,
     });
@@ -135,15 +337,90 @@ class ApiClient {
     });
   }
 
-  async sendDocument(id: string): Promise<Document> {
-    return this.request<Document>(`/documents/${id}/send`, {
+  async getSigningPreview(id: string, signerId: string): Promise<SigningSession> {
+    const params = new URLSearchParams({ signer_id: signerId });
+    return this.request<SigningSession>(`/documents/${id}/preview?${params}`);
+  }
+
+  async getSigningPreviewPdf(id: string): Promise<Blob> {
+    return this.request<Blob>(`/documents/${id}/preview/pdf`);
+  }
+
+  async getPreflight(id: string): Promise<PreflightResponse> {
+    return this.request<PreflightResponse>(`/documents/${id}/preflight`);
+  }
+
+  async sendDocument(id: string, force = false): Promise<Document> {
+    const query = force ? '?force=true' : '';
+    return this.request<Document>(`/documents/${id}/send${query}`, {
+      method: 'POST',
+    });
+  }
+
+  async recallDocument(id: string, notifySigners = false): Promise<Document> {
+    return this.request<Document>(`/documents/${id}/recall`, {
       method: 'POST',
+      body: JSON.stringify({ notify_signers: notifySigners }),
     });
   }
 
-  async voidDocument(id: string): Promise<Document> {
+  async voidDocument(id: string, reason: string): Promise<Document> {
     return this.request<Document>(`/documents/${id}/void`, {
       method: 'POST',
+      body: JSON.stringify({ reason }),
+    });
+  }
+
+  async bulkUpdateDocuments(
+    action: BulkDocumentAction,
+    ids: string[],
+    reason?: string
+  ): Promise<BulkDocumentResponse> {
+    return this.request<BulkDocumentResponse>('/documents/bulk', {
+      method: 'POST',
+      body: JSON.stringify({ action, ids, reason }),
+    });
+  }
+
+  async listShares(id: string): Promise<DocumentShare[]> {
+    return this.request<DocumentShare[]>(`/documents/${id}/shares`);
+  }
+
+  async shareDocument(
+    id: string,
+    email: string,
+    role: ShareRole
+  ): Promise<DocumentShare> {
+    return this.request<DocumentShare>(`/documents/${id}/shares`, {
+      method: 'POST',
+      body: JSON.stringify({ email, role }),
+    });
+  }
+
+  async unshareDocument(id: string, userId: string): Promise<void> {
+    await this.request<{ success: boolean }>(
+      `/documents/${id}/shares/${userId}`,
+      { method: 'DELETE' }
+    );
+  }
+
+  async listShareLinks(id: string): Promise<ShareLink[]> {
+    return this.request<ShareLink[]>(`/documents/${id}/share-links`);
+  }
+
+  async createShareLink(
+    id: string,
+    request: CreateShareLinkRequest = {}
+  ): Promise<CreatedShareLink> {
+    return this.request<CreatedShareLink>(`/documents/${id}/share-links`, {
+      method: 'POST',
+      body: JSON.stringify(request),
+    });
+  }
+
+  async revokeShareLink(id: string, linkId: string): Promise<ShareLink> {
+    return this.request<ShareLink>(`/documents/${id}/share-links/${linkId}`, {
+      method: 'DELETE',
     });
   }
 
@@ -151,6 +428,59 @@ class ApiClient {
     return this.request<Blob>(`/documents/${id}/download`);
   }
 
+  async downloadSealedDocument(id: string): Promise<Blob> {
+    return this.request<Blob>(`/documents/${id}/download/sealed`);
+  }
+
+  async listSignatures(id: string): Promise<Signature[]> {
+    return this.request<Signature[]>(`/documents/${id}/signatures`);
+  }
+
+  async getSignatureImage(id: string, signatureId: string): Promise<Blob> {
+    return this.request<Blob>(`/documents/${id}/signatures/${signatureId}/image`);
+  }
+
+  async downloadPackage(id: string): Promise<Blob> {
+    return this.request<Blob>(`/documents/${id}/package.zip`);
+  }
+
+  async sealDocument(id: string): Promise<Document> {
+    return this.request<Document>(`/documents/${id}/seal`, {
+      method: 'POST',
+    });
+  }
+
+  // The signature covers the exact bytes, so the body is kept as a Blob
+  async exportAuditLog(
+    id: string,
+    format: 'json' | 'csv' = 'json'
+  ): Promise<{ file: Blob; keyId: string | null; signature: string | null }> {
+    const response = await fetch(`${API_BASE}/documents/${id}/audit/export?format=${format}`, {
+      headers: { Authorization: `Bearer ${this.getToken()}` },
+    });
+    if (!response.ok) {
+      throw new ApiClientError('Failed to export audit log', 'export_error', response.status);
+    }
+    return {
+      file: await response.blob(),
+      keyId: response.headers.get('X-SignVault-Key-Id'),
+      signature: response.headers.get('X-SignVault-Signature'),
+    };
+  }
+
+  async verifyFile(id: string, file: File): Promise<FileVerification> {
+    const formData = new FormData();
+    formData.append('file', file);
+    return this.request<FileVerification>(`/documents/${id}/verify-file`, {
+      method: 'POST',
+      body: formData,
+    });
+  }
+
+  async listTimestamps(id: string): Promise<DocumentTimestamp[]> {
+    return this.request<DocumentTimestamp[]>(`/documents/${id}/timestamps`);
+  }
+
   // Fields
   async addField(documentId: string, field: AddFieldRequest): Promise<DocumentField> {
     return this.request<DocumentField>(`/documents/${documentId}/fields`, {
@@ -190,6 +520,30 @@ class ApiClient {
     });
   }
 
+  async createOpenLink(
+    documentId: string,
+    request: CreateOpenLinkRequest = {}
+  ): Promise<OpenLinkResponse> {
+    return this.request<OpenLinkResponse>(`/documents/${documentId}/open-link`, {
+      method: 'POST',
+      body: JSON.stringify(request),
+    });
+  }
+
+  async rotateSignerToken(
+    documentId: string,
+    signerId: string,
+    options: { reason?: string; resend?: boolean } = {}
+  ): Promise<Signer> {
+    return this.request<Signer>(
+      `/documents/${documentId}/signers/${signerId}/rotate-token`,
+      {
+        method: 'POST',
+        body: JSON.stringify(options),
+      }
+    );
+  }
+
   async removeSigner(documentId: string, signerId: string): Promise<void> {
     await this.request<{ success: boolean }>(
       `/documents/${documentId}/signers/${signerId}`,
@@ -199,6 +553,54 @@ class ApiClient {
     );
   }
 
+  async remindSigner(documentId: string, signerId: string): Promise<Signer> {
+    return this.request<Signer>(`/documents/${documentId}/signers/${signerId}/remind`, {
+      method: 'POST',
+    });
+  }
+
+  async resetSigner(documentId: string, signerId: string): Promise<Signer> {
+    return this.request<Signer>(`/documents/${documentId}/signers/${signerId}/reset`, {
+      method: 'POST',
+    });
+  }
+
+  // Amendments to pending documents
+  async amendAddSigner(documentId: string, signer: AddSignerRequest): Promise<Signer> {
+    return this.request<Signer>(`/documents/${documentId}/amend/signers`, {
+      method: 'POST',
+      body: JSON.stringify(signer),
+    });
+  }
+
+  async amendRemoveSigner(
+    documentId: string,
+    signerId: string,
+    options: { reason?: string } = {}
+  ): Promise<Signer> {
+    return this.request<Signer>(
+      `/documents/${documentId}/amend/signers/${signerId}/remove`,
+      {
+        method: 'POST',
+        body: JSON.stringify(options),
+      }
+    );
+  }
+
+  async amendReplaceSigner(
+    documentId: string,
+    signerId: string,
+    replacement: ReplaceSignerRequest
+  ): Promise<Signer> {
+    return this.request<Signer>(
+      `/documents/${documentId}/amend/signers/${signerId}/replace`,
+      {
+        method: 'POST',
+        body: JSON.stringify(replacement),
+      }
+    );
+  }
+
   // Audit
   async getAuditLogs(documentId: string): Promise<AuditLog[]> {
     return this.request<AuditLog[]>(`/documents/${documentId}/audit`);
@@ -208,45 +610,151 @@ class ApiClient {
     return this.request<Certificate>(`/documents/${documentId}/certificate`);
   }
 
-  // Signing (public routes)
+  // Signing (public routes). The link token is exchanged for a short-lived
+  // session token, sent in place of it; an expired session is renewed by
+  // exchanging the link token again.
+  private async signingSessionToken(linkToken: string, renew: boolean): Promise<string> {
+    const cached = this.signingSessions.get(linkToken);
+    if (cached && !renew && new Date(cached.expires_at).getTime() - Date.now() > 30_000) {
+      return cached.session_token;
+    }
+    const session = await this.request<SigningSessionToken>(`/sign/${linkToken}/session`, {
+      method: 'POST',
+    });
+    this.signingSessions.set(linkToken, session);
+    return session.session_token;
+  }
+
+  private async signingRequest<T>(
+    linkToken: string,
+    path: string,
+    options: RequestInit = {}
+  ): Promise<T> {
+    const send = async (renew: boolean) => {
+      const sessionToken = await this.signingSessionToken(linkToken, renew);
+      return this.request<T>(`/sign/session${path}`, {
+        ...options,
+        headers: { ...options.headers, Authorization: `Bearer ${sessionToken}` },
+      });
+    };
+    try {
+      return await send(false);
+    } catch (err) {
+      if (err instanceof ApiClientError && err.statusCode === 401) {
+        return send(true);
+      }
+      throw err;
+    }
+  }
+
   async getSigningSession(token: string): Promise<SigningSession> {
-    return this.request<SigningSession>(`/sign/${token}`);
+    return this.signingRequest<SigningSession>(token, '');
+  }
+
+  async claimSigningLink(
+    token: string,
+    name: string,
+    email: string
+  ): Promise<ClaimSigningLinkResponse> {
+    return this.request<ClaimSigningLinkResponse>(`/sign/${token}/claim`, {
+      method: 'POST',
+      body: JSON.stringify({ name, email }),
+    });
   }
 
   async getSigningPdf(token: string): Promise<Blob> {
-    const response = await fetch(`${API_BASE}/sign/${token}/pdf`);
-    if (!response.ok) {
-      throw new ApiClientError('Failed to load PDF', 'pdf_error', response.status);
+    try {
+      return await this.signingRequest<Blob>(token, '/pdf');
+    } catch (err) {
+      if (err instanceof ApiClientError) {
+        throw new ApiClientError('Failed to load PDF', 'pdf_error', err.statusCode);
+      }
+      throw err;
     }
-    return response.blob();
   }
 
   async submitSigning(
     token: string,
     request: CompleteSigningRequest
-  ): Promise<{ success: boolean; document_completed: boolean }> {
-    return this.request<{ success: boolean; document_completed: boolean }>(
-      `/sign/${token}/submit`,
-      {
-        method: 'POST',
-        body: JSON.stringify(request),
-      }
-    );
+  ): Promise<SigningSubmissionResponse> {
+    return this.signingRequest<SigningSubmissionResponse>(token, '/submit', {
+      method: 'POST',
+      body: JSON.stringify(request),
+    });
+  }
+
+  async saveSigningProgress(
+    token: string,
+    progress: CompleteSigningRequest
+  ): Promise<SaveSigningProgressResponse> {
+    return this.signingRequest<SaveSigningProgressResponse>(token, '/progress', {
+      method: 'PUT',
+      body: JSON.stringify(progress),
+    });
+  }
+
+  async giveSigningConsent(token: string, version: string): Promise<void> {
+    await this.signingRequest<{ success: boolean }>(token, '/consent', {
+      method: 'POST',
+      body: JSON.stringify({ version }),
+    });
   }
 
   async declineSigning(token: string, reason?: string): Promise<void> {
-    await this.request<{ success: boolean }>(`/sign/${token}/decline`, {
+    await this.signingRequest<{ success: boolean }>(token, '/decline', {
       method: 'POST',
       body: JSON.stringify({ reason }),
     });
   }
+
+  async getEnvelopeStatus(token: string): Promise<EnvelopeStatus> {
+    return this.request<EnvelopeStatus>(`/status/${token}`);
+  }
+
+  async getStatusPdf(token: string): Promise<Blob> {
+    const response = await fetch(`${API_BASE}/status/${token}/pdf`);
+    if (!response.ok) {
+      throw new ApiClientError('Failed to download PDF', 'pdf_error', response.status);
+    }
+    return response.blob();
+  }
+
+  // Share links (public routes)
+  async getSharedDocument(
+    token: string,
+    passcode?: string
+  ): Promise<ShareLinkDocument> {
+    return this.request<ShareLinkDocument>(`/shared/${token}`, {
+      headers: sharePasscodeHeaders(passcode),
+    });
+  }
+
+  async getSharedPdf(token: string, passcode?: string): Promise<Blob> {
+    const response = await fetch(`${API_BASE}/shared/${token}/pdf`, {
+      headers: sharePasscodeHeaders(passcode),
+    });
+    if (!response.ok) {
+      throw new ApiClientError('Failed to load PDF', 'pdf_error', response.status);
+    }
+    return response.blob();
+  }
+
+  async getSharedCertificate(
+    token: string,
+    passcode?: string
+  ): Promise<Certificate> {
+    return this.request<Certificate>(`/shared/${token}/certificate`, {
+      headers: sharePasscodeHeaders(passcode),
+    });
+  }
 }
 
 export class ApiClientError extends Error {
   constructor(
     message: string,
     public errorType: string,
-    public statusCode: number
+    public statusCode: number,
+    public current?: unknown
   ) {
     super(message);
     this.name = 'ApiClientError';
@@ -16,6 +16,9 @@ const fieldLabels: Record<FieldType, string> = {
   date: 'Date',
   text: 'Text',
   initial: 'Initial',
+  checkbox: 'Checkbox',
+  number: 'Number',
+  radio: 'Radio',
 };
 
 const fieldIcons: Record<FieldType, string> = {
@@ -23,6 +26,9 @@ const fieldIcons: Record<FieldType, string> = {
   date: 'M8 7V3m8 4V3m-9 8h10M5 21h14a2 2 0 002-2V7a2 2 0 00-2-2H5a2 2 0 00-2 2v12a2 2 0 002 2z',
   text: 'M4 6h16M4 12h16M4 18h7',
   initial: 'M13 10V3L4 14h7v7l9-11h-7z',
+  checkbox: 'M5 13l4 4L19 7',
+  number: 'M7 20l4-16m2 16l4-16M6 9h14M4 15h14',
+  radio: 'M12 21a9 9 0 100-18 9 9 0 000 18zm0-6a3 3 0 100-6 3 3 0 000 6z',
 };
 
 export default function DraggableField({
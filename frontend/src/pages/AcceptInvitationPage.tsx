@@ -0,0 +1,82 @@
+import { useState, type FormEvent } from 'react';
+import { useNavigate, useParams } from 'react-router-dom';
+import { useAuthStore } from '@/stores/auth';
+
+export default function AcceptInvitationPage() {
+  const { token } = useParams<{ token: string }>();
+  const [name, setName] = useState('');
+  const [password, setPassword] = useState('');
+  const { acceptInvitation, isLoading, error, clearError } = useAuthStore();
+  const navigate = useNavigate();
+
+  const handleSubmit = async (e: FormEvent) => {
+    e.preventDefault();
+    if (!token) return;
+
+    clearError();
+    try {
+      await acceptInvitation(token, name, password);
+      navigate('/');
+    } catch {
+      // Error is handled in store
+    }
+  };
+
+  return (
+    <div className="min-h-screen flex items-center justify-center bg-gray-50 p-4">
+      <form
+        className="max-w-md w-full bg-white rounded-lg shadow-lg p-8 space-y-4"
+        onSubmit={handleSubmit}
+      >
+        <div>
+          <h2 className="text-xl font-bold text-gray-900">Welcome to SignVault</h2>
+          <p className="mt-2 text-sm text-gray-600">
+            Choose your name and a password to finish setting up your account.
+          </p>
+        </div>
+        {error && (
+          <div className="rounded-md bg-red-50 p-4">
+            <p className="text-sm text-red-700">{error}</p>
+          </div>
+        )}
+        <div>
+          <label htmlFor="name" className="block text-sm font-medium text-gray-700">
+            Name
+          </label>
+          <input
+            id="name"
+            type="text"
+            autoComplete="name"
+            required
+            value={name}
+            onChange={(e) => setName(e.target.value)}
+            className="mt-1 block w-full px-3 py-2 border border-gray-300 rounded-md shadow-sm focus:outline-none focus:ring-primary-500 focus:border-primary-500 sm:text-sm"
+          />
+        </div>
+        <div>
+          <label htmlFor="password" className="block text-sm font-medium text-gray-700">
+            Password
+          </label>
+          <input
+            id="password"
+            type="password"
+            autoComplete="new-password"
+            required
+            minLength={8}
+            value={password}
+            onChange={(e) => setPassword(e.target.value)}
+            className="mt-1 block w-full px-3 py-2 border border-gray-300 rounded-md shadow-sm focus:outline-none focus:ring-primary-500 focus:border-primary-500 sm:text-sm"
+          />
+          <p className="mt-1 text-xs text-gray-500">At least 8 characters.</p>
+        </div>
+        <button
+          type="submit"
+          disabled={isLoading}
+          className="w-full flex justify-center py-2 px-4 border border-transparent text-sm font-medium rounded-md text-white bg-primary-600 hover:bg-primary-700 focus:outline-none focus:ring-2 focus:ring-offset-2 focus:ring-primary-500 disabled:opacity-50 disabled:cursor-not-allowed"
+        >
+          {isLoading ? 'Setting up...' : 'Create account'}
+        </button>
+      </form>
+    </div>
+  );
+}
@@ -0,0 +1,53 @@
+import { useEffect, useState } from 'react';
+import { Link, useParams } from 'react-router-dom';
+import { api } from '@/api/client';
+
+export default function ConfirmEmailPage() {
+  const { token } = useParams<{ token: string }>();
+  const [email, setEmail] = useState<string | null>(null);
+  const [error, setError] = useState<string | null>(null);
+
+  useEffect(() => {
+    if (!token) return;
+
+    const confirm = async () => {
+      try {
+        const user = await api.confirmEmailChange(token);
+        setEmail(user.email);
+      } catch (err) {
+        setError(err instanceof Error ? err.message : 'Failed to confirm email address');
+      }
+    };
+
+    void confirm();
+  }, [token]);
+
+  if (!email && !error) {
+    return (
+      <div className="min-h-screen flex items-center justify-center bg-gray-50">
+        <div className="animate-spin rounded-full h-12 w-12 border-b-2 border-primary-600" />
+      </div>
+    );
+  }
+
+  return (
+    <div className="min-h-screen flex items-center justify-center bg-gray-50 p-4">
+      <div className="max-w-md w-full bg-white rounded-lg shadow-lg p-8 text-center">
+        <h2 className="text-xl font-bold text-gray-900">
+          {error ?? 'Email address confirmed'}
+        </h2>
+        {email && (
+          <p className="mt-2 text-gray-600">
+            Sign in with <strong>{email}</strong> from now on.
+          </p>
+        )}
+        <Link
+          to="/login"
+          className="mt-6 inline-block text-sm font-medium text-primary-600 hover:text-primary-500"
+        >
+          Go to sign in
+        </Link>
+      </div>
+    </div>
+  );
+}
@@ -1,5 +1,6 @@
 import { useEffect, useState, useRef, type ChangeEvent } from 'react';
 import { Link } from 'react-router-dom';
+import { useAuthStore } from '@/stores/auth';
 import { useDocumentStore } from '@/stores/document';
 import { format } from 'date-fns';
 import type { DocumentStatus } from '@/types';
@@ -23,6 +24,8 @@ const statusLabels: Record<DocumentStatus, string> = {
 export default function DashboardPage() {
   const { documents, total, isLoading, error, fetchDocuments, createDocument } =
     useDocumentStore();
+  const { user } = useAuthStore();
+  const canUpload = user?.role !== 'viewer';
   const [showUploadModal, setShowUploadModal] = useState(false);
   const [uploadTitle, setUploadTitle] = useState('');
   const [uploadFile, setUploadFile] = useState<File | null>(null);
@@ -70,25 +73,27 @@ export default function DashboardPage() {
             {total} document{total !== 1 ? 's' : ''} total
           </p>
         </div>
-        <button
-          onClick={() => setShowUploadModal(true)}
-          className="inline-flex items-center px-4 py-2 border border-transparent text-sm font-medium rounded-md shadow-sm text-white bg-primary-600 hover:bg-primary-700 focus:outline-none focus:ring-2 focus:ring-offset-2 focus:ring-primary-500"
-        >
-          <svg
-            className="w-5 h-5 mr-2"
-            fill="none"
-            viewBox="0 0 24 24"
-            stroke="currentColor"
+        {canUpload && (
+          <button
+            onClick={() => setShowUploadModal(true)}
+            className="inline-flex items-center px-4 py-2 border border-transparent text-sm font-medium rounded-md shadow-sm text-white bg-primary-600 hover:bg-primary-700 focus:outline-none focus:ring-2 focus:ring-offset-2 focus:ring-primary-500"
           >
-            <path
-              strokeLinecap="round"
-              strokeLinejoin="round"
-              strokeWidth={2}
-              d="M12 4v16m8-8H4"
-            />
-          </svg>
-          New Document
-        </button>
+            <svg
+              className="w-5 h-5 mr-2"
+              fill="none"
+              viewBox="0 0 24 24"
+              stroke="currentColor"
+            >
+              <path
+                strokeLinecap="round"
+                strokeLinejoin="round"
+                strokeWidth={2}
+                d="M12 4v16m8-8H4"
+              />
+            </svg>
+            New Document
+          </button>
+        )}
       </div>
 
       {error && (
@@ -4,7 +4,7 @@ import { useDocumentStore } from '@/stores/document';
 import { api } from '@/api/client';
 import PDFViewer from '@/components/PDFViewer';
 import DraggableField from '@/components/DraggableField';
-import type { FieldType, AuditLog, Certificate } from '@/types';
+import type { FieldType, AuditLog, Certificate, PreflightResponse } from '@/types';
 import { format } from 'date-fns';
 
 const fieldTypes: { type: FieldType; label: string; icon: string }[] = [
@@ -57,6 +57,7 @@ export default function DocumentEditorPage() {
   const [auditLogs, setAuditLogs] = useState<AuditLog[]>([]);
   const [certificate, setCertificate] = useState<Certificate | null>(null);
   const [isSending, setIsSending] = useState(false);
+  const [preflight, setPreflight] = useState<PreflightResponse | null>(null);
 
   useEffect(() => {
     if (id) {
@@ -86,6 +87,24 @@ export default function DocumentEditorPage() {
     };
   }, [currentDocument, id]);
 
+  // Re-run the send checks whenever the document, its fields or signers change
+  useEffect(() => {
+    if (!id || currentDocument?.status !== 'draft') {
+      setPreflight(null);
+      return;
+    }
+    let cancelled = false;
+    api
+      .getPreflight(id)
+      .then((report) => {
+        if (!cancelled) setPreflight(report);
+      })
+      .catch(console.error);
+    return () => {
+      cancelled = true;
+    };
+  }, [currentDocument, id]);
+
   const loadAuditLogs = useCallback(async () => {
     if (!id) return;
     try {
@@ -167,6 +186,14 @@ export default function DocumentEditorPage() {
     setIsSending(true);
     try {
       await sendDocument(id);
+    } catch (err) {
+      // Signers without a signature field can still be sent to on purpose,
+      // e.g. participants who only need to view the document
+      const message = err instanceof Error ? err.message : '';
+      if (!message.includes('force=true') || !window.confirm(`${message.split('. ')[0]}.\n\nSend anyway?`)) {
+        throw err;
+      }
+      await sendDocument(id, true);
     } finally {
       setIsSending(false);
     }
@@ -174,8 +201,9 @@ export default function DocumentEditorPage() {
 
   const handleVoid = useCallback(async () => {
     if (!id) return;
-    if (window.confirm('Are you sure you want to void this document?')) {
-      await voidDocument(id);
+    const reason = window.prompt('Why are you voiding this document? Signers will see this reason.');
+    if (reason?.trim()) {
+      await voidDocument(id, reason.trim());
     }
   }, [id, voidDocument]);
 
@@ -236,6 +264,11 @@ export default function DocumentEditorPage() {
   }
 
   const isDraft = currentDocument.status === 'draft';
+  // Signers without a signature field can still be sent to with force
+  const sendBlocked =
+    preflight?.issues.some(
+      (issue) => issue.severity === 'error' && issue.code !== 'missing_signature_field'
+    ) ?? false;
 
   return (
     <div className="flex gap-6">
@@ -258,7 +291,7 @@ export default function DocumentEditorPage() {
                 <>
                   <button
                     onClick={handleSend}
-                    disabled={isSending || (!currentDocument.self_sign_only && currentDocument.signers.length === 0)}
+                    disabled={isSending || sendBlocked}
                     className="px-4 py-2 bg-green-600 text-white rounded-md hover:bg-green-700 disabled:opacity-50 disabled:cursor-not-allowed"
                   >
                     {isSending ? 'Sending...' : 'Send for Signing'}
@@ -281,6 +314,18 @@ export default function DocumentEditorPage() {
               )}
             </div>
           </div>
+          {isDraft && preflight && preflight.issues.length > 0 && (
+            <ul className="mt-4 space-y-1 text-sm">
+              {preflight.issues.map((issue, i) => (
+                <li
+                  key={`${issue.code}-${issue.signer_id ?? issue.field_id ?? i}`}
+                  className={issue.severity === 'error' ? 'text-red-600' : 'text-yellow-700'}
+                >
+                  {issue.severity === 'error' ? '✗' : '!'} {issue.message}
+                </li>
+              ))}
+            </ul>
+          )}
         </div>
 
         <div
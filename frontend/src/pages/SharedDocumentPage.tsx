@@ -0,0 +1,148 @@
+import { useEffect, useState, useCallback } from 'react';
+import { useParams } from 'react-router-dom';
+import { api, ApiClientError } from '@/api/client';
+import PDFViewer from '@/components/PDFViewer';
+import type { ShareLinkDocument } from '@/types';
+import { format } from 'date-fns';
+
+export default function SharedDocumentPage() {
+  const { token } = useParams<{ token: string }>();
+  const [document, setDocument] = useState<ShareLinkDocument | null>(null);
+  const [pdfUrl, setPdfUrl] = useState<string | null>(null);
+  const [isLoading, setIsLoading] = useState(true);
+  const [error, setError] = useState<string | null>(null);
+  const [needsPasscode, setNeedsPasscode] = useState(false);
+  const [passcode, setPasscode] = useState('');
+  const [passcodeError, setPasscodeError] = useState<string | null>(null);
+
+  const load = useCallback(
+    async (passcode?: string) => {
+      if (!token) return;
+
+      setIsLoading(true);
+      try {
+        // The document first, so a missing passcode isn't reported as a failed PDF
+        const documentData = await api.getSharedDocument(token, passcode);
+        const pdfBlob = await api.getSharedPdf(token, passcode);
+
+        setDocument(documentData);
+        setPdfUrl(URL.createObjectURL(pdfBlob));
+        setNeedsPasscode(false);
+      } catch (err) {
+        if (err instanceof ApiClientError && err.errorType === 'passcode_required') {
+          setNeedsPasscode(true);
+          setPasscodeError(passcode ? err.message : null);
+        } else {
+          setError(err instanceof Error ? err.message : 'Failed to load document');
+        }
+      } finally {
+        setIsLoading(false);
+      }
+    },
+    [token]
+  );
+
+  useEffect(() => {
+    void load();
+  }, [load]);
+
+  const handleDownloadCertificate = useCallback(async () => {
+    if (!token || !document) return;
+
+    try {
+      const certificate = await api.getSharedCertificate(token, passcode || undefined);
+      const blob = new Blob([JSON.stringify(certificate, null, 2)], {
+        type: 'application/json',
+      });
+      const url = URL.createObjectURL(blob);
+      const link = window.document.createElement('a');
+      link.href = url;
+      link.download = `${document.title} - certificate.json`;
+      link.click();
+      URL.revokeObjectURL(url);
+    } catch (err) {
+      setError(err instanceof Error ? err.message : 'Failed to load certificate');
+    }
+  }, [token, document, passcode]);
+
+  if (isLoading) {
+    return (
+      <div className="min-h-screen flex items-center justify-center bg-gray-50">
+        <div className="animate-spin rounded-full h-12 w-12 border-b-2 border-primary-600" />
+      </div>
+    );
+  }
+
+  if (error) {
+    return (
+      <div className="min-h-screen flex items-center justify-center bg-gray-50 p-4">
+        <div className="max-w-md w-full">
+          <div className="bg-white rounded-lg shadow-lg p-8 text-center">
+            <h2 className="text-xl font-bold text-gray-900">{error}</h2>
+          </div>
+        </div>
+      </div>
+    );
+  }
+
+  if (needsPasscode) {
+    return (
+      <div className="min-h-screen flex items-center justify-center bg-gray-50 p-4">
+        <form
+          className="max-w-md w-full bg-white rounded-lg shadow-lg p-8"
+          onSubmit={(e) => {
+            e.preventDefault();
+            void load(passcode);
+          }}
+        >
+          <h2 className="text-xl font-bold text-gray-900">This document is protected</h2>
+          <p className="mt-2 text-sm text-gray-600">
+            Enter the passcode you were given with the link.
+          </p>
+          <input
+            type="password"
+            value={passcode}
+            onChange={(e) => setPasscode(e.target.value)}
+            className="mt-4 block w-full px-3 py-2 border border-gray-300 rounded-md shadow-sm focus:outline-none focus:ring-primary-500 focus:border-primary-500 sm:text-sm"
+            autoFocus
+          />
+          {passcodeError && <p className="mt-2 text-sm text-red-600">{passcodeError}</p>}
+          <button
+            type="submit"
+            disabled={!passcode}
+            className="mt-4 w-full flex justify-center py-2 px-4 border border-transparent text-sm font-medium rounded-md text-white bg-primary-600 hover:bg-primary-700 focus:outline-none focus:ring-2 focus:ring-offset-2 focus:ring-primary-500 disabled:opacity-50 disabled:cursor-not-allowed"
+          >
+            View document
+          </button>
+        </form>
+      </div>
+    );
+  }
+
+  return (
+    <div className="min-h-screen bg-gray-50">
+      <header className="bg-white shadow-sm">
+        <div className="max-w-5xl mx-auto px-4 py-4 flex items-center justify-between">
+          <div>
+            <h1 className="text-lg font-semibold text-gray-900">{document?.title}</h1>
+            {document?.completed_at && (
+              <p className="text-sm text-gray-500">
+                Completed {format(new Date(document.completed_at), 'MMM d, yyyy')}
+              </p>
+            )}
+          </div>
+          <button
+            onClick={() => void handleDownloadCertificate()}
+            className="inline-flex items-center px-4 py-2 border border-gray-300 text-sm font-medium rounded-md text-gray-700 bg-white hover:bg-gray-50 focus:outline-none focus:ring-2 focus:ring-offset-2 focus:ring-primary-500"
+          >
+            Certificate
+          </button>
+        </div>
+      </header>
+
+      <main className="max-w-5xl mx-auto px-4 py-6">
+        {pdfUrl && <PDFViewer url={pdfUrl} />}
+      </main>
+    </div>
+  );
+}
@@ -1,5 +1,5 @@
 import { useEffect, useState, useCallback, useMemo } from 'react';
-import { useParams } from 'react-router-dom';
+import { Link, useParams } from 'react-router-dom';
 import { api } from '@/api/client';
 import PDFViewer from '@/components/PDFViewer';
 import SignaturePad from '@/components/SignaturePad';
@@ -38,6 +38,9 @@ export default function SigningPage() {
   const [completed, setCompleted] = useState(false);
   const [declineReason, setDeclineReason] = useState('');
   const [showDeclineModal, setShowDeclineModal] = useState(false);
+  const [isConsenting, setIsConsenting] = useState(false);
+  const [isSavingProgress, setIsSavingProgress] = useState(false);
+  const [progressSavedAt, setProgressSavedAt] = useState<string | null>(null);
 
   useEffect(() => {
     if (!token) return;
@@ -52,10 +55,11 @@ export default function SigningPage() {
 
         setSession(sessionData);
         setPdfUrl(URL.createObjectURL(pdfBlob));
+        document.documentElement.lang = sessionData.locale;
 
-        // Initialize field values for date and text fields
+        // Initialize field values for date, text and number fields
         const initialValues: FieldValue[] = sessionData.fields
-          .filter((f) => f.field_type === 'date' || f.field_type === 'text')
+          .filter((f) => ['date', 'text', 'number', 'radio'].includes(f.field_type) && !f.read_only)
           .map((f) => ({
             fieldId: f.id,
             value:
@@ -63,6 +67,20 @@ export default function SigningPage() {
                 ? format(new Date(), dateFormats[f.date_format ?? 'YYYY-MM-DD'] ?? 'yyyy-MM-dd')
                 : f.value ?? '',
           }));
+        // Pick up where the signer left off
+        const saved = sessionData.saved_progress;
+        if (saved) {
+          setSignatures(
+            saved.values.signatures
+              .filter((s) => s.signature_data)
+              .map((s) => ({ fieldId: s.field_id, signatureData: s.signature_data }))
+          );
+          setProgressSavedAt(saved.saved_at);
+          for (const v of saved.values.field_values) {
+            const initial = initialValues.find((f) => f.fieldId === v.field_id);
+            if (initial) initial.value = v.value;
+          }
+        }
         setFieldValues(initialValues);
       } catch (err) {
         setError(err instanceof Error ? err.message : 'Failed to load document');
@@ -108,6 +126,17 @@ export default function SigningPage() {
     });
   }, []);
 
+  // Selecting a radio option clears the other options of its choice
+  const handleRadioSelect = useCallback(
+    (fieldId: string) => {
+      const group = session?.radio_groups.find((g) => g.field_ids.includes(fieldId));
+      for (const optionId of group?.field_ids ?? [fieldId]) {
+        handleFieldValueChange(optionId, optionId === fieldId ? 'true' : 'false');
+      }
+    },
+    [session?.radio_groups, handleFieldValueChange]
+  );
+
   const requiredSignatureFields = useMemo(() => {
     return session?.fields.filter((f) => f.field_type === 'signature' || f.field_type === 'initial') ?? [];
   }, [session?.fields]);
@@ -142,6 +171,43 @@ export default function SigningPage() {
     }
   }, [token, signatures, fieldValues, allSignaturesComplete]);
 
+  const handleSaveProgress = useCallback(async () => {
+    if (!token) return;
+
+    setIsSavingProgress(true);
+    try {
+      const result = await api.saveSigningProgress(token, {
+        signatures: signatures.map((s) => ({
+          field_id: s.fieldId,
+          signature_data: s.signatureData,
+        })),
+        field_values: fieldValues.map((f) => ({
+          field_id: f.fieldId,
+          value: f.value,
+        })),
+      });
+      setProgressSavedAt(result.saved_at);
+    } catch (err) {
+      setError(err instanceof Error ? err.message : 'Failed to save your progress');
+    } finally {
+      setIsSavingProgress(false);
+    }
+  }, [token, signatures, fieldValues]);
+
+  const handleConsent = useCallback(async () => {
+    if (!token || !session) return;
+
+    setIsConsenting(true);
+    try {
+      await api.giveSigningConsent(token, session.consent_version);
+      setSession({ ...session, consent_given: true });
+    } catch (err) {
+      setError(err instanceof Error ? err.message : 'Failed to record your consent');
+    } finally {
+      setIsConsenting(false);
+    }
+  }, [token, session]);
+
   const handleDecline = useCallback(async () => {
     if (!token) return;
 
@@ -183,13 +249,15 @@ export default function SigningPage() {
               signatureData={getSignatureForField(field.id)}
               value={getValueForField(field.id)}
               onSignatureClick={() => setActiveSignatureField(field.id)}
-              onValueChange={(value) => handleFieldValueChange(field.id, value)}
+              onValueChange={(value) =>
+                field.field_type === 'radio' ? handleRadioSelect(field.id) : handleFieldValueChange(field.id, value)
+              }
             />
           ))}
         </>
       );
     },
-    [session, getSignatureForField, getValueForField, handleFieldValueChange]
+    [session, getSignatureForField, getValueForField, handleFieldValueChange, handleRadioSelect]
   );
 
   if (completed) {
@@ -219,6 +287,12 @@ export default function SigningPage() {
             <p className="mt-4 text-sm text-gray-500">
               You can close this window now.
             </p>
+            <Link
+              to={`/status/${token}`}
+              className="mt-4 inline-block text-sm font-medium text-primary-600 hover:text-primary-500"
+            >
+              Follow the other signatures
+            </Link>
           </div>
         </div>
       </div>
@@ -252,6 +326,12 @@ export default function SigningPage() {
               />
             </svg>
             <h2 className="mt-4 text-xl font-bold text-gray-900">{error}</h2>
+            <Link
+              to={`/status/${token}`}
+              className="mt-4 inline-block text-sm font-medium text-primary-600 hover:text-primary-500"
+            >
+              See where this document stands
+            </Link>
           </div>
         </div>
       </div>
@@ -267,6 +347,17 @@ export default function SigningPage() {
         <div className="max-w-7xl mx-auto px-4 py-4">
           <div className="flex items-center justify-between">
             <div>
+              {session.branding?.logo_url ? (
+                <img
+                  src={session.branding.logo_url}
+                  alt={session.branding.company_name ?? ''}
+                  className="h-8 mb-2"
+                />
+              ) : (
+                session.branding?.company_name && (
+                  <p className="text-sm font-semibold text-gray-700">{session.branding.company_name}</p>
+                )
+              )}
               <h1 className="text-xl font-bold text-gray-900">{session.document_title}</h1>
               <p className="text-sm text-gray-500">
                 Signing as {session.signer.name} ({session.signer.email})
@@ -279,9 +370,18 @@ export default function SigningPage() {
               >
                 Decline to Sign
               </button>
+              <button
+                onClick={() => void handleSaveProgress()}
+                disabled={isSavingProgress || isSubmitting}
+                title={progressSavedAt ? `Saved ${format(new Date(progressSavedAt), 'PPp')}` : undefined}
+                className="px-4 py-2 border border-gray-300 rounded-md text-sm font-medium text-gray-700 hover:bg-gray-50 disabled:opacity-50"
+              >
+                {isSavingProgress ? 'Saving...' : 'Save and Finish Later'}
+              </button>
               <button
                 onClick={() => void handleSubmit()}
-                disabled={!allSignaturesComplete || isSubmitting}
+                disabled={!allSignaturesComplete || !session.consent_given || isSubmitting}
+                style={session.branding?.primary_color ? { backgroundColor: session.branding.primary_color } : undefined}
                 className="px-4 py-2 bg-primary-600 text-white rounded-md text-sm font-medium hover:bg-primary-700 disabled:opacity-50 disabled:cursor-not-allowed"
               >
                 {isSubmitting ? 'Submitting...' : 'Complete Signing'}
@@ -315,6 +415,35 @@ export default function SigningPage() {
         />
       )}
 
+      {/* Consent Modal */}
+      {!session.consent_given && !showDeclineModal && (
+        <div className="fixed inset-0 bg-gray-500 bg-opacity-75 flex items-center justify-center p-4 z-50">
+          <div className="bg-white rounded-lg max-w-md w-full p-6">
+            <h3 className="text-lg font-medium text-gray-900 mb-4">
+              Consent to Sign Electronically
+            </h3>
+            <p className="text-sm text-gray-700 mb-6 whitespace-pre-line">
+              {session.consent_text}
+            </p>
+            <div className="flex justify-end gap-3">
+              <button
+                onClick={() => setShowDeclineModal(true)}
+                className="px-4 py-2 border border-gray-300 rounded-md text-sm font-medium text-gray-700 hover:bg-gray-50"
+              >
+                Decline to Sign
+              </button>
+              <button
+                onClick={() => void handleConsent()}
+                disabled={isConsenting}
+                className="px-4 py-2 bg-primary-600 text-white rounded-md text-sm font-medium hover:bg-primary-700 disabled:opacity-50"
+              >
+                {isConsenting ? 'Saving...' : 'I Agree'}
+              </button>
+            </div>
+          </div>
+        </div>
+      )}
+
       {/* Decline Modal */}
       {showDeclineModal && (
         <div className="fixed inset-0 bg-gray-500 bg-opacity-75 flex items-center justify-center p-4 z-50">
@@ -354,9 +483,9 @@ export default function SigningPage() {
       <footer className="bg-white border-t mt-8">
         <div className="max-w-7xl mx-auto px-4 py-4">
           <p className="text-xs text-gray-500 text-center">
-            By clicking "Complete Signing", you agree that your electronic signature is legally binding
-            under the ESIGN Act (USA) and eIDAS Regulation (EU). Your signature, IP address, and timestamp
-            will be recorded for legal compliance.
+            Your electronic signature is legally binding under the ESIGN Act (USA) and eIDAS Regulation
+            (EU), as you agreed before signing. Your consent, signature, IP address, and timestamps will be
+            recorded for legal compliance.
           </p>
         </div>
       </footer>
@@ -398,7 +527,14 @@ function SigningField({
       }}
       onClick={isSignatureField && !signatureData ? onSignatureClick : undefined}
     >
-      {isSignatureField ? (
+      {field.read_only ? (
+        <div
+          className="flex items-center w-full h-full px-2 text-gray-800 text-sm"
+          style={{ fontSize: field.font_size ?? 12 }}
+        >
+          {field.value}
+        </div>
+      ) : isSignatureField ? (
         signatureData ? (
           <img
             src={signatureData}
@@ -418,6 +554,25 @@ function SigningField({
           className="w-full h-full px-2 bg-transparent text-gray-800 text-sm border-none outline-none"
           style={{ fontSize: field.font_size ?? 12 }}
         />
+      ) : field.field_type === 'radio' ? (
+        <input
+          type="radio"
+          name={field.group_key ?? field.id}
+          checked={value === 'true'}
+          onChange={() => onValueChange('true')}
+          aria-label={field.label ?? undefined}
+          className="w-full h-full cursor-pointer"
+        />
+      ) : field.field_type === 'number' ? (
+        <input
+          type="text"
+          inputMode="decimal"
+          value={value}
+          onChange={(e) => onValueChange(e.target.value)}
+          placeholder={field.label ?? '0.00'}
+          className="w-full h-full px-2 bg-transparent text-gray-800 text-sm text-right border-none outline-none"
+          style={{ fontSize: field.font_size ?? 12 }}
+        />
       ) : (
         <input
           type="text"
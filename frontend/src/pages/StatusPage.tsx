@@ -0,0 +1,129 @@
+import { useEffect, useState, useCallback } from 'react';
+import { useParams } from 'react-router-dom';
+import { api } from '@/api/client';
+import type { EnvelopeStatus } from '@/types';
+import { format } from 'date-fns';
+
+const statusLabels: Record<EnvelopeStatus['status'], string> = {
+  draft: 'Not sent yet',
+  pending: 'Waiting for signatures',
+  completed: 'Completed',
+  voided: 'Voided',
+  expired: 'Expired',
+};
+
+function formatTime(value: string | null): string | null {
+  return value ? format(new Date(value), 'MMM d, yyyy h:mm a') : null;
+}
+
+export default function StatusPage() {
+  const { token } = useParams<{ token: string }>();
+  const [status, setStatus] = useState<EnvelopeStatus | null>(null);
+  const [isLoading, setIsLoading] = useState(true);
+  const [error, setError] = useState<string | null>(null);
+
+  useEffect(() => {
+    if (!token) return;
+
+    const loadStatus = async () => {
+      setIsLoading(true);
+      try {
+        setStatus(await api.getEnvelopeStatus(token));
+      } catch (err) {
+        setError(err instanceof Error ? err.message : 'Failed to load status');
+      } finally {
+        setIsLoading(false);
+      }
+    };
+
+    void loadStatus();
+  }, [token]);
+
+  const handleDownload = useCallback(async () => {
+    if (!token || !status) return;
+
+    try {
+      const blob = await api.getStatusPdf(token);
+      const url = URL.createObjectURL(blob);
+      const link = document.createElement('a');
+      link.href = url;
+      link.download = `${status.document_title}.pdf`;
+      link.click();
+      URL.revokeObjectURL(url);
+    } catch (err) {
+      setError(err instanceof Error ? err.message : 'Failed to download document');
+    }
+  }, [token, status]);
+
+  if (isLoading) {
+    return (
+      <div className="min-h-screen flex items-center justify-center bg-gray-50">
+        <div className="animate-spin rounded-full h-12 w-12 border-b-2 border-primary-600" />
+      </div>
+    );
+  }
+
+  if (error || !status) {
+    return (
+      <div className="min-h-screen flex items-center justify-center bg-gray-50 p-4">
+        <div className="max-w-md w-full bg-white rounded-lg shadow-lg p-8 text-center">
+          <h2 className="text-xl font-bold text-gray-900">{error ?? 'Status not found'}</h2>
+        </div>
+      </div>
+    );
+  }
+
+  const timeline = [
+    { label: 'Sent to you', time: formatTime(status.signer.email_sent_at) },
+    { label: 'Viewed', time: formatTime(status.signer.viewed_at) },
+    { label: 'Signed', time: formatTime(status.signer.signed_at) },
+    { label: 'Approved', time: formatTime(status.signer.approved_at) },
+    { label: 'Declined', time: formatTime(status.signer.declined_at) },
+  ].filter((entry) => entry.time);
+
+  return (
+    <div className="min-h-screen flex items-center justify-center bg-gray-50 p-4">
+      <div className="max-w-md w-full bg-white rounded-lg shadow-lg p-8">
+        <h2 className="text-xl font-bold text-gray-900">{status.document_title}</h2>
+        <p className="mt-1 text-sm text-gray-600">{statusLabels[status.status]}</p>
+
+        <div className="mt-6">
+          <div className="flex justify-between text-sm text-gray-700">
+            <span>Signatures</span>
+            <span>
+              {status.completed_signers} of {status.total_signers}
+            </span>
+          </div>
+          <div className="mt-2 h-2 rounded-full bg-gray-200">
+            <div
+              className="h-2 rounded-full bg-primary-600"
+              style={{
+                width: `${status.total_signers ? (100 * status.completed_signers) / status.total_signers : 0}%`,
+              }}
+            />
+          </div>
+        </div>
+
+        {timeline.length > 0 && (
+          <dl className="mt-6 space-y-2 text-sm">
+            {timeline.map((entry) => (
+              <div key={entry.label} className="flex justify-between">
+                <dt className="text-gray-500">{entry.label}</dt>
+                <dd className="text-gray-900">{entry.time}</dd>
+              </div>
+            ))}
+          </dl>
+        )}
+
+        {status.download_available && (
+          <button
+            onClick={() => void handleDownload()}
+            className="mt-6 w-full flex justify-center py-2 px-4 border border-transparent text-sm font-medium rounded-md text-white bg-primary-600 hover:bg-primary-700 focus:outline-none focus:ring-2 focus:ring-offset-2 focus:ring-primary-500"
+          >
+            Download signed document
+          </button>
+        )}
+      </div>
+    </div>
+  );
+}
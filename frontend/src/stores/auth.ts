@@ -8,7 +8,11 @@ interface AuthState {
   isAuthenticated: boolean;
   isLoading: boolean;
   error: string | null;
+  // Set while a login waits for a two-factor code
+  twoFactorChallenge: string | null;
   login: (email: string, password: string) => Promise<void>;
+  verifyTwoFactor: (code: string) => Promise<void>;
+  acceptInvitation: (token: string, name: string, password: string) => Promise<void>;
   logout: () => void;
   checkAuth: () => Promise<void>;
   clearError: () => void;
@@ -16,16 +20,21 @@ interface AuthState {
 
 export const useAuthStore = create<AuthState>()(
   persist(
-    (set) => ({
+    (set, get) => ({
       user: null,
       isAuthenticated: false,
       isLoading: false,
       error: null,
+      twoFactorChallenge: null,
 
       login: async (email: string, password: string) => {
-        set({ isLoading: true, error: null });
+        set({ isLoading: true, error: null, twoFactorChallenge: null });
         try {
           const response = await api.login(email, password);
+          if ('challenge_token' in response) {
+            set({ twoFactorChallenge: response.challenge_token, isLoading: false });
+            return;
+          }
           set({
             user: response.user,
             isAuthenticated: true,
@@ -40,12 +49,53 @@ export const useAuthStore = create<AuthState>()(
         }
       },
 
+      verifyTwoFactor: async (code: string) => {
+        const challenge = get().twoFactorChallenge;
+        if (!challenge) return;
+
+        set({ isLoading: true, error: null });
+        try {
+          const response = await api.verifyTwoFactor(challenge, code);
+          set({
+            user: response.user,
+            isAuthenticated: true,
+            isLoading: false,
+            twoFactorChallenge: null,
+          });
+        } catch (err) {
+          set({
+            error: err instanceof Error ? err.message : 'Verification failed',
+            isLoading: false,
+          });
+          throw err;
+        }
+      },
+
+      acceptInvitation: async (token: string, name: string, password: string) => {
+        set({ isLoading: true, error: null });
+        try {
+          const response = await api.acceptInvitation(token, name, password);
+          set({
+            user: response.user,
+            isAuthenticated: true,
+            isLoading: false,
+          });
+        } catch (err) {
+          set({
+            error: err instanceof Error ? err.message : 'Failed to accept invitation',
+            isLoading: false,
+          });
+          throw err;
+        }
+      },
+
       logout: () => {
         api.logout();
         set({
           user: null,
           isAuthenticated: false,
           error: null,
+          twoFactorChallenge: null,
         });
       },
 
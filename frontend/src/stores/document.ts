@@ -21,8 +21,8 @@ interface DocumentState {
   fetchDocument: (id: string) => Promise<void>;
   createDocument: (title: string, file: File, selfSignOnly: boolean) => Promise<Document>;
   deleteDocument: (id: string) => Promise<void>;
-  sendDocument: (id: string) => Promise<void>;
-  voidDocument: (id: string) => Promise<void>;
+  sendDocument: (id: string, force?: boolean) => Promise<void>;
+  voidDocument: (id: string, reason: string) => Promise<void>;
 
   addField: (documentId: string, field: AddFieldRequest) => Promise<DocumentField>;
   updateField: (documentId: string, fieldId: string, updates: UpdateFieldRequest) => Promise<void>;
@@ -108,10 +108,11 @@ export const useDocumentStore = create<DocumentState>((set) => ({
     }
   },
 
-  sendDocument: async (id: string) => {
+  sendDocument: async (id: string, force = false) => {
     try {
-      const updated = await api.sendDocument(id);
+      const updated = await api.sendDocument(id, force);
       set((state) => ({
+        error: null,
         documents: state.documents.map((d) => (d.id === id ? updated : d)),
         currentDocument:
           state.currentDocument?.id === id
@@ -126,9 +127,9 @@ export const useDocumentStore = create<DocumentState>((set) => ({
     }
   },
 
-  voidDocument: async (id: string) => {
+  voidDocument: async (id: string, reason: string) => {
     try {
-      const updated = await api.voidDocument(id);
+      const updated = await api.voidDocument(id, reason);
       set((state) => ({
         documents: state.documents.map((d) => (d.id === id ? updated : d)),
         currentDocument:
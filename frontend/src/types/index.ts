@@ -1,8 +1,76 @@
+export type Role = 'admin' | 'sender' | 'viewer';
+
 export interface User {
   id: string;
   email: string;
   name: string;
-  is_admin: boolean;
+  role: Role;
+  // IANA timezone certificates and audit exports are shown in
+  display_timezone: string | null;
+  // Whether signers' replies to signing requests and reminders reach this user
+  reply_to_enabled: boolean;
+}
+
+// Applied to the emails an owner's signers get and to their signing page;
+// unset parts use the default styling.
+export interface Branding {
+  company_name: string | null;
+  // #rrggbb
+  primary_color: string | null;
+  logo_url: string | null;
+}
+
+export interface UpdateBrandingRequest {
+  company_name?: string;
+  primary_color?: string;
+  // Base64 PNG or JPEG data URL, or '' to remove the logo; omitted keeps it
+  logo?: string;
+}
+
+export interface UpdateProfileRequest {
+  // Empty string clears it, falling back to the server's default
+  display_timezone?: string;
+  reply_to_enabled?: boolean;
+}
+
+export interface StorageUsage {
+  user_id: string;
+  used_bytes: number;
+  document_count: number;
+  quota_bytes: number | null;
+  custom_quota: boolean;
+}
+
+export type AuthEventType =
+  | 'login_succeeded'
+  | 'login_failed'
+  | 'email_change_requested'
+  | 'email_changed'
+  | 'two_factor_required'
+  | 'two_factor_enabled'
+  | 'two_factor_disabled'
+  | 'recovery_code_used';
+
+// A login email change waiting for the new address to be confirmed.
+export interface EmailChange {
+  id: string;
+  user_id: string;
+  new_email: string;
+  expires_at: string;
+  created_at: string;
+  confirmed_at: string | null;
+}
+
+export interface AuthEvent {
+  id: string;
+  user_id: string | null;
+  event_type: AuthEventType;
+  email: string;
+  failure_reason?: 'unknown_email' | 'wrong_password' | 'wrong_two_factor_code';
+  token_expires_at: string | null;
+  ip_address: string | null;
+  user_agent: string | null;
+  created_at: string;
 }
 
 export interface LoginResponse {
@@ -10,9 +78,48 @@ export interface LoginResponse {
   user: User;
 }
 
+// Returned by login instead of a token when the account has two-factor
+// authentication on; exchanged with a code for a token.
+export interface TwoFactorChallenge {
+  two_factor_required: true;
+  challenge_token: string;
+  expires_at: string;
+}
+
+export type LoginOutcome = LoginResponse | TwoFactorChallenge;
+
+// A login; the one making the request is marked current.
+export interface UserSession {
+  id: string;
+  user_id: string;
+  ip_address: string | null;
+  user_agent: string | null;
+  created_at: string;
+  last_used_at: string;
+  expires_at: string;
+  current: boolean;
+}
+
+export interface TwoFactorStatus {
+  enabled: boolean;
+  enabled_at: string | null;
+  recovery_codes_remaining: number;
+}
+
+export interface TwoFactorSetup {
+  secret: string;
+  provisioning_uri: string;
+}
+
+// Single-use codes for signing in without the authenticator; only returned
+// when two-factor authentication is enabled.
+export interface RecoveryCodes {
+  recovery_codes: string[];
+}
+
 export type DocumentStatus = 'draft' | 'pending' | 'completed' | 'voided' | 'expired';
-export type FieldType = 'signature' | 'date' | 'text' | 'initial';
-export type SignerStatus = 'pending' | 'sent' | 'viewed' | 'signed' | 'declined';
+export type FieldType = 'signature' | 'date' | 'text' | 'initial' | 'checkbox' | 'number' | 'radio';
+export type SignerStatus = 'pending' | 'sent' | 'viewed' | 'signed' | 'declined' | 'removed';
 
 export interface Document {
   id: string;
@@ -27,10 +134,26 @@ export interface Document {
   completed_signers: number;
   expires_at: string | null;
   completed_at: string | null;
+  sealed_at: string | null;
+  sealed_file_hash: string | null;
+  seal_signer: string | null;
+  void_reason: string | null;
+  reminder_interval_days: number | null;
+  max_reminders: number | null;
+  signer_download_enabled: boolean;
+  progress_notifications: ProgressNotification[];
+  // Language of emails to signers without one of their own, e.g. 'fr'.
+  locale: string | null;
   created_at: string;
   updated_at: string;
 }
 
+export interface UploadedDocument extends Document {
+  duplicate_of?: string;
+}
+
+export type ProgressNotification = 'every_signature' | 'one_remaining';
+
 export interface DocumentField {
   id: string;
   document_id: string;
@@ -40,11 +163,28 @@ export interface DocumentField {
   y: number;
   width: number;
   height: number;
+  norm_x: number | null;
+  norm_y: number | null;
+  norm_width: number | null;
+  norm_height: number | null;
   signer_id: string | null;
   value: string | null;
   font_size: number | null;
   font_family: string | null;
   date_format: string | null;
+  required: boolean;
+  // Shown only while this field holds condition_value.
+  condition_field_id: string | null;
+  condition_value: string | null;
+  label: string | null;
+  // Bounds and precision of a number field, as decimal strings.
+  min_value: string | null;
+  max_value: string | null;
+  decimal_places: number | null;
+  // Shows the owner's value, which the signer cannot change.
+  read_only: boolean;
+  // The choice a radio field is an option of.
+  group_key: string | null;
   created_at: string;
   updated_at: string;
 }
@@ -63,7 +203,12 @@ export interface Signer {
   signed_at: string | null;
   declined_at: string | null;
   decline_reason: string | null;
+  consented_at: string | null;
+  consent_version: string | null;
+  locale: string | null;
   email_sent_at: string | null;
+  reminders_sent: number;
+  last_reminded_at: string | null;
   created_at: string;
   updated_at: string;
 }
@@ -73,8 +218,138 @@ export interface DocumentWithFields extends Document {
   signers: Signer[];
 }
 
+export type PreflightCode =
+  | 'no_signers'
+  | 'missing_signature_field'
+  | 'field_signer_removed'
+  | 'field_unassigned'
+  | 'field_out_of_bounds'
+  | 'expiry_in_past'
+  | 'invalid_email'
+  | 'undeliverable_email';
+
+export interface PreflightIssue {
+  code: PreflightCode;
+  severity: 'error' | 'warning';
+  message: string;
+  signer_id?: string;
+  field_id?: string;
+}
+
+export interface PreflightResponse {
+  ready: boolean;
+  issues: PreflightIssue[];
+}
+
+export interface DocumentListItem extends Document {
+  waiting_on_name: string | null;
+  waiting_on_email: string | null;
+  last_activity_at: string | null;
+  viewed_signers: number;
+  unviewed_signers: number;
+  tags: string[];
+}
+
+export type SearchField = 'title' | 'filename' | 'signer_name' | 'signer_email';
+
+export interface HighlightFragment {
+  text: string;
+  matched: boolean;
+}
+
+export interface SearchMatch {
+  field: SearchField;
+  value: string;
+  fragments: HighlightFragment[];
+}
+
+export interface DocumentSearchResult extends Document {
+  rank: number;
+  matches: SearchMatch[];
+}
+
+export interface DocumentSearchResponse {
+  documents: DocumentSearchResult[];
+  total: number;
+}
+
+export type TagMode = 'all' | 'any';
+
+export interface TagUsage {
+  name: string;
+  document_count: number;
+}
+
 export interface DocumentListResponse {
-  documents: Document[];
+  documents: DocumentListItem[];
+  total: number;
+}
+
+export type ShareRole = 'viewer' | 'editor';
+
+export interface DocumentShare {
+  document_id: string;
+  user_id: string;
+  email: string;
+  name: string;
+  role: ShareRole;
+  created_at: string;
+}
+
+export interface ShareLink {
+  id: string;
+  document_id: string;
+  has_passcode: boolean;
+  expires_at: string;
+  created_by: string | null;
+  created_at: string;
+  revoked_at: string | null;
+  access_count: number;
+  last_accessed_at: string | null;
+}
+
+// The token is only returned when the link is created.
+export interface CreatedShareLink extends ShareLink {
+  token: string;
+  url: string;
+}
+
+export interface CreateShareLinkRequest {
+  expires_in_hours?: number;
+  passcode?: string;
+}
+
+export interface ShareLinkDocument {
+  title: string;
+  status: DocumentStatus;
+  completed_at: string | null;
+  sealed: boolean;
+  expires_at: string;
+  pdf_url: string;
+  certificate_url: string;
+}
+
+export type BulkDocumentAction = 'void' | 'delete';
+
+export interface BulkDocumentResult {
+  id: string;
+  success: boolean;
+  error?: string;
+  message?: string;
+}
+
+export interface BulkDocumentResponse {
+  succeeded: number;
+  failed: number;
+  results: BulkDocumentResult[];
+}
+
+export interface SharedDocument extends Document {
+  share_role: ShareRole;
+}
+
+export interface SharedDocumentListResponse {
+  documents: SharedDocument[];
   total: number;
 }
 
@@ -90,6 +365,12 @@ export interface AddFieldRequest {
   font_size?: number;
   font_family?: string;
   date_format?: string;
+  label?: string;
+  min_value?: string;
+  max_value?: string;
+  decimal_places?: number;
+  read_only?: boolean;
+  group_key?: string;
 }
 
 export interface UpdateFieldRequest {
@@ -101,12 +382,50 @@ export interface UpdateFieldRequest {
   font_size?: number;
   font_family?: string;
   date_format?: string;
+  label?: string;
+  min_value?: string;
+  max_value?: string;
+  decimal_places?: number;
+  read_only?: boolean;
+  group_key?: string;
+  // The field's updated_at as last read; the update fails with a 409 if it changed.
+  expected_updated_at?: string;
 }
 
 export interface AddSignerRequest {
   email: string;
   name: string;
   order_index?: number;
+  // Language of the signer's emails; the document's when omitted.
+  locale?: string;
+}
+
+// Takes over a signer's place, role and fields on a pending document.
+export interface ReplaceSignerRequest {
+  email: string;
+  name: string;
+  access_code?: string;
+  locale?: string;
+  reason?: string;
+}
+
+export interface CreateOpenLinkRequest {
+  label?: string;
+  order_index?: number;
+  single_use?: boolean;
+}
+
+export interface OpenLinkResponse {
+  signer: Signer;
+  signing_url: string;
+  token: string;
+  single_use: boolean;
+}
+
+export interface ClaimSigningLinkResponse {
+  signer_id: string;
+  token: string;
+  signing_url: string;
 }
 
 export type AuditAction =
@@ -116,22 +435,38 @@ export type AuditAction =
   | 'document_sent'
   | 'document_completed'
   | 'document_voided'
+  | 'document_recalled'
+  | 'document_amended'
+  | 'document_shared'
+  | 'document_unshared'
+  | 'share_link_created'
+  | 'share_link_revoked'
+  | 'share_link_accessed'
+  | 'share_link_passcode_failed'
   | 'document_downloaded'
+  | 'document_package_downloaded'
   | 'field_added'
   | 'field_updated'
   | 'field_deleted'
   | 'signer_added'
+  | 'open_link_created'
+  | 'signer_claimed'
+  | 'signer_token_rotated'
   | 'signer_removed'
   | 'signer_email_sent'
   | 'signer_viewed'
+  | 'consent_given'
   | 'signer_signed'
   | 'signer_declined'
+  | 'signer_reset'
+  | 'signer_reminded'
   | 'signature_applied'
   | 'certificate_generated';
 
 export interface AuditLog {
   id: string;
   document_id: string;
+  sequence: number;
   signer_id: string | null;
   user_id: string | null;
   action: AuditAction;
@@ -149,6 +484,8 @@ export interface CertificateSigner {
   signed_at: string;
   ip_address: string;
   signature_hash: string;
+  consented_at: string | null;
+  consent_version: string | null;
 }
 
 export interface CertificateAuditEntry {
@@ -157,6 +494,56 @@ export interface CertificateAuditEntry {
   timestamp: string;
   ip_address: string | null;
   details: string | null;
+  changes: string | null;
+}
+
+export interface CertificateSeal {
+  sealed_at: string;
+  sealed_file_hash: string;
+  signer: string;
+}
+
+export interface CertificateTimestamp {
+  timestamp_id: string;
+  tsa_url: string;
+  gen_time: string;
+  serial_number: string | null;
+}
+
+export type TimestampStatus = 'granted' | 'pending' | 'failed';
+
+export interface DocumentTimestamp {
+  id: string;
+  document_id: string;
+  subject: 'document' | 'certificate';
+  hashed_value: string;
+  tsa_url: string;
+  status: TimestampStatus;
+  token: string | null;
+  gen_time: string | null;
+  serial_number: string | null;
+  attempts: number;
+  last_error: string | null;
+  next_attempt_at: string | null;
+  created_at: string;
+}
+
+export interface FileVerification {
+  document_id: string;
+  matched: 'original' | 'sealed' | null;
+  matched_hash: string | null;
+  recorded_at: string | null;
+  timestamp: CertificateTimestamp | null;
+  uploaded_hash: string;
+  uploaded_size: number;
+  checked_at: string;
+}
+
+export interface ServerSignature {
+  algorithm: string;
+  key_id: string;
+  key_fingerprint: string;
+  signature: string;
 }
 
 export interface Certificate {
@@ -167,8 +554,58 @@ export interface Certificate {
   completed_at: string;
   signers: CertificateSigner[];
   audit_trail: CertificateAuditEntry[];
+  seal: CertificateSeal | null;
+  document_timestamp: CertificateTimestamp | null;
   certificate_hash: string;
+  server_signature: ServerSignature;
+  generated_at: string;
+  certificate_timestamp: CertificateTimestamp | null;
+  local_times: CertificateLocalTimes;
+}
+
+// The certificate's times in the reader's display timezone, e.g.
+// "2024-11-03 01:30:00 EST (UTC-05:00)". Arrays follow the order of the
+// certificate's signers, approvals and audit_trail.
+export interface CertificateLocalTimes {
+  timezone: string;
+  created_at: string;
+  completed_at: string;
   generated_at: string;
+  sealed_at: string | null;
+  signers: string[];
+  approvals: string[];
+  audit_trail: string[];
+}
+
+// What a signer's status page shows: only counts of the other signers.
+export interface EnvelopeStatus {
+  document_title: string;
+  status: DocumentStatus;
+  completed_signers: number;
+  total_signers: number;
+  completed_at: string | null;
+  signer: {
+    role: 'signer' | 'approver';
+    status: SignerStatus;
+    email_sent_at: string | null;
+    viewed_at: string | null;
+    signed_at: string | null;
+    approved_at: string | null;
+    declined_at: string | null;
+  };
+  download_available: boolean;
+}
+
+export interface SigningSessionToken {
+  session_token: string;
+  expires_at: string;
+}
+
+// Radio fields of which exactly one is selected with 'true'.
+export interface RadioGroup {
+  group_key: string;
+  field_ids: string[];
+  required: boolean;
 }
 
 export interface SigningSession {
@@ -181,7 +618,36 @@ export interface SigningSession {
     status: SignerStatus;
   };
   fields: DocumentField[];
+  radio_groups: RadioGroup[];
   page_count: number;
+  consent_text: string;
+  consent_version: string;
+  // Whether signing is refused until the signer agrees to consent_text.
+  consent_required: boolean;
+  consent_given: boolean;
+  // Language the signer is written to in, e.g. 'fr'.
+  locale: string;
+  // The document owner's branding, null when they have set none.
+  branding: Branding | null;
+  // What the signer saved before leaving, to fill the fields back in.
+  saved_progress: SigningProgress | null;
+}
+
+export interface Signature {
+  id: string;
+  signer_id: string;
+  document_id: string;
+  field_id: string;
+  // Null for signatures made before images were kept in storage.
+  content_type: string | null;
+  byte_length: number | null;
+  signature_hash: string;
+  signature_type: 'drawn' | 'typed';
+  typed_text: string | null;
+  typed_font: string | null;
+  ip_address: string;
+  user_agent: string;
+  created_at: string;
 }
 
 export interface SubmitSignatureRequest {
@@ -199,7 +665,26 @@ export interface CompleteSigningRequest {
   field_values: SubmitFieldValueRequest[];
 }
 
+export interface SigningProgress {
+  values: CompleteSigningRequest;
+  saved_at: string;
+}
+
+export interface SaveSigningProgressResponse {
+  success: boolean;
+  saved_at: string;
+}
+
+export interface SigningSubmissionResponse {
+  success: boolean;
+  document_completed: boolean;
+  // True when this repeated an earlier identical submission.
+  already_submitted: boolean;
+}
+
 export interface ApiError {
   error: string;
   message: string;
+  // The resource as it is now, on an edit_conflict.
+  current?: unknown;
 }